@@ -0,0 +1,55 @@
+use super::{chunk_header::*, chunk_type::*, *};
+
+/// chunkCookieAck represents an SCTP Chunk of type chunkCookieAck
+///
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |   Type = 11   |Chunk  Flags   |     Length = 4                |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+#[derive(Debug, Clone)]
+pub(crate) struct ChunkCookieAck;
+
+/// makes ChunkCookieAck printable
+impl fmt::Display for ChunkCookieAck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.header())
+    }
+}
+
+impl Chunk for ChunkCookieAck {
+    fn header(&self) -> ChunkHeader {
+        ChunkHeader {
+            typ: CT_COOKIE_ACK,
+            flags: 0,
+            value_length: self.value_length() as u16,
+        }
+    }
+
+    fn unmarshal(raw: &Bytes) -> Result<Self> {
+        let header = ChunkHeader::unmarshal(raw)?;
+
+        if header.typ != CT_COOKIE_ACK {
+            return Err(Error::ErrChunkTypeNotCookieAck);
+        }
+
+        Ok(ChunkCookieAck {})
+    }
+
+    fn marshal_to(&self, buf: &mut BytesMut) -> Result<usize> {
+        self.header().marshal_to(buf)?;
+        Ok(buf.len())
+    }
+
+    fn check(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn value_length(&self) -> usize {
+        0
+    }
+
+    fn as_any(&self) -> &(dyn Any + Send + Sync) {
+        self
+    }
+}
@@ -0,0 +1,186 @@
+use super::{chunk_forward_tsn::NEW_CUMULATIVE_TSN_LENGTH, chunk_header::*, chunk_type::*, *};
+
+///I-FORWARD-TSN is the FORWARD-TSN for associations using I-DATA (RFC 8260).
+///It identifies the skipped messages by their Message Identifier instead
+///of the Stream Sequence Number, and also covers unordered messages.
+/// 0                   1                   2                   3
+/// 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///|   Type = 194  |  Flags = 0x00 |        Length = Variable      |
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///|                      New Cumulative TSN                       |
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///|       Stream Identifier       |          Reserved           |U|
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///|                       Message Identifier                      |
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///|                                                               |
+///|                                                               |
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///|       Stream Identifier       |          Reserved           |U|
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///|                       Message Identifier                      |
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+#[derive(Default, Debug, Clone)]
+pub(crate) struct ChunkIForwardTsn {
+    /// This indicates the new cumulative TSN to the data receiver.
+    pub(crate) new_cumulative_tsn: u32,
+    pub(crate) streams: Vec<ChunkIForwardTsnStream>,
+}
+
+pub(crate) const I_FORWARD_TSN_STREAM_LENGTH: usize = 8;
+
+const I_FORWARD_TSN_UNORDERED_BITMASK: u16 = 1;
+
+/// makes ChunkIForwardTsn printable
+impl fmt::Display for ChunkIForwardTsn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut res = vec![self.header().to_string()];
+        res.push(format!("New Cumulative TSN: {}", self.new_cumulative_tsn));
+        for s in &self.streams {
+            res.push(format!(
+                " - si={}, u={}, mid={}",
+                s.identifier, s.unordered, s.message_identifier
+            ));
+        }
+
+        write!(f, "{}", res.join("\n"))
+    }
+}
+
+impl Chunk for ChunkIForwardTsn {
+    fn header(&self) -> ChunkHeader {
+        ChunkHeader {
+            typ: CT_I_FORWARD_TSN,
+            flags: 0,
+            value_length: self.value_length() as u16,
+        }
+    }
+
+    fn unmarshal(buf: &Bytes) -> Result<Self> {
+        let header = ChunkHeader::unmarshal(buf)?;
+
+        if header.typ != CT_I_FORWARD_TSN {
+            return Err(Error::ErrChunkTypeNotIForwardTsn);
+        }
+
+        let end = CHUNK_HEADER_SIZE + header.value_length();
+        let mut offset = CHUNK_HEADER_SIZE + NEW_CUMULATIVE_TSN_LENGTH;
+        if end < offset {
+            return Err(Error::ErrChunkTooShort);
+        }
+
+        let reader = &mut buf.slice(CHUNK_HEADER_SIZE..end);
+        let new_cumulative_tsn = reader.get_u32();
+
+        let mut streams = vec![];
+        while offset < end {
+            let s = ChunkIForwardTsnStream::unmarshal(&buf.slice(offset..end))?;
+            offset += s.value_length();
+            streams.push(s);
+        }
+
+        Ok(ChunkIForwardTsn {
+            new_cumulative_tsn,
+            streams,
+        })
+    }
+
+    fn marshal_to(&self, writer: &mut BytesMut) -> Result<usize> {
+        self.header().marshal_to(writer)?;
+
+        writer.put_u32(self.new_cumulative_tsn);
+
+        for s in &self.streams {
+            writer.extend(s.marshal()?);
+        }
+
+        Ok(writer.len())
+    }
+
+    fn check(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn value_length(&self) -> usize {
+        NEW_CUMULATIVE_TSN_LENGTH + I_FORWARD_TSN_STREAM_LENGTH * self.streams.len()
+    }
+
+    fn as_any(&self) -> &(dyn Any + Send + Sync) {
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ChunkIForwardTsnStream {
+    /// The stream of the skipped message.
+    pub(crate) identifier: u16,
+
+    /// Whether the skipped message was unordered.
+    pub(crate) unordered: bool,
+
+    /// For ordered messages, the largest skipped Message Identifier on the
+    /// stream. For unordered messages, the skipped message itself.
+    pub(crate) message_identifier: u32,
+}
+
+/// makes ChunkIForwardTsnStream printable
+impl fmt::Display for ChunkIForwardTsnStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}, {}, {}",
+            self.identifier, self.unordered, self.message_identifier
+        )
+    }
+}
+
+impl Chunk for ChunkIForwardTsnStream {
+    fn header(&self) -> ChunkHeader {
+        ChunkHeader {
+            typ: ChunkType(0),
+            flags: 0,
+            value_length: self.value_length() as u16,
+        }
+    }
+
+    fn unmarshal(buf: &Bytes) -> Result<Self> {
+        if buf.len() < I_FORWARD_TSN_STREAM_LENGTH {
+            return Err(Error::ErrChunkTooShort);
+        }
+
+        let reader = &mut buf.clone();
+        let identifier = reader.get_u16();
+        let unordered = reader.get_u16() & I_FORWARD_TSN_UNORDERED_BITMASK != 0;
+        let message_identifier = reader.get_u32();
+
+        Ok(ChunkIForwardTsnStream {
+            identifier,
+            unordered,
+            message_identifier,
+        })
+    }
+
+    fn marshal_to(&self, writer: &mut BytesMut) -> Result<usize> {
+        writer.put_u16(self.identifier);
+        writer.put_u16(if self.unordered {
+            I_FORWARD_TSN_UNORDERED_BITMASK
+        } else {
+            0
+        });
+        writer.put_u32(self.message_identifier);
+        Ok(writer.len())
+    }
+
+    fn check(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn value_length(&self) -> usize {
+        I_FORWARD_TSN_STREAM_LENGTH
+    }
+
+    fn as_any(&self) -> &(dyn Any + Send + Sync) {
+        self
+    }
+}
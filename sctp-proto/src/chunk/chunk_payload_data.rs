@@ -0,0 +1,341 @@
+use super::{chunk_header::*, chunk_type::*, *};
+
+use std::time::Instant;
+
+pub(crate) const PAYLOAD_DATA_ENDING_FRAGMENT_BITMASK: u8 = 1;
+pub(crate) const PAYLOAD_DATA_BEGINING_FRAGMENT_BITMASK: u8 = 2;
+pub(crate) const PAYLOAD_DATA_UNORDERED_BITMASK: u8 = 4;
+pub(crate) const PAYLOAD_DATA_IMMEDIATE_SACK: u8 = 8;
+pub(crate) const PAYLOAD_DATA_HEADER_SIZE: usize = 12;
+pub(crate) const I_DATA_HEADER_SIZE: usize = 16;
+
+/// PayloadProtocolIdentifier is an enum for DataChannel payload types
+// PayloadProtocolIdentifier enums
+// <https://www.iana.org/assignments/sctp-parameters/sctp-parameters.xhtml#sctp-parameters-25>
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[repr(C)]
+#[derive(Default)]
+pub enum PayloadProtocolIdentifier {
+    Dcep = 50,
+    String = 51,
+    Binary = 53,
+    StringEmpty = 56,
+    BinaryEmpty = 57,
+    #[default]
+    Unknown,
+}
+
+impl fmt::Display for PayloadProtocolIdentifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match *self {
+            PayloadProtocolIdentifier::Dcep => "WebRTC DCEP",
+            PayloadProtocolIdentifier::String => "WebRTC String",
+            PayloadProtocolIdentifier::Binary => "WebRTC Binary",
+            PayloadProtocolIdentifier::StringEmpty => "WebRTC String (Empty)",
+            PayloadProtocolIdentifier::BinaryEmpty => "WebRTC Binary (Empty)",
+            _ => "Unknown Payload Protocol Identifier",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl From<u32> for PayloadProtocolIdentifier {
+    fn from(v: u32) -> PayloadProtocolIdentifier {
+        match v {
+            50 => PayloadProtocolIdentifier::Dcep,
+            51 => PayloadProtocolIdentifier::String,
+            53 => PayloadProtocolIdentifier::Binary,
+            56 => PayloadProtocolIdentifier::StringEmpty,
+            57 => PayloadProtocolIdentifier::BinaryEmpty,
+            _ => PayloadProtocolIdentifier::Unknown,
+        }
+    }
+}
+
+/// ChunkPayloadData represents an SCTP Chunk of type DATA
+//
+// 0                   1                   2                   3
+// 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+//+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+//|   Type = 0    | Reserved|U|B|E|    Length                     |
+//+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+//|                              TSN                              |
+//+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+//|      Stream Identifier S      |   Stream Sequence Number n    |
+//+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+//|                  Payload Protocol Identifier                  |
+//+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+//|                                                               |
+//|                 User Data (seq n of Stream S)                 |
+//|                                                               |
+//+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+//
+//
+//An unfragmented user message shall have both the B and E bits set to
+//'1'.  Setting both B and E bits to '0' indicates a middle fragment of
+//a multi-fragment user message, as summarized in the following table:
+//   B E                  Description
+//============================================================
+//|  1 0 | First piece of a fragmented user message          |
+//+----------------------------------------------------------+
+//|  0 0 | Middle piece of a fragmented user message         |
+//+----------------------------------------------------------+
+//|  0 1 | Last piece of a fragmented user message           |
+//+----------------------------------------------------------+
+//|  1 1 | Unfragmented message                              |
+//============================================================
+//|             Table 1: Fragment Description Flags          |
+//============================================================
+//
+// With I-DATA (RFC 8260), the same struct is used with a Message Identifier
+// (MID) instead of the Stream Sequence Number, and the fragments of a message
+// are ordered by the Fragment Sequence Number (FSN) instead of the TSN. The
+// FSN takes the place of the PPID in all but the first fragment.
+//
+// 0                   1                   2                   3
+// 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+//+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+//|   Type = 64   |  Res  |I|U|B|E|       Length = Variable       |
+//+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+//|                              TSN                              |
+//+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+//|        Stream Identifier      |           Reserved            |
+//+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+//|                      Message Identifier                       |
+//+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+//|    Payload Protocol Identifier / Fragment Sequence Number     |
+//+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+//\                                                               \
+///                           User Data                           /
+//\                                                               \
+//+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+#[derive(Debug, Clone)]
+pub struct ChunkPayloadData {
+    pub(crate) unordered: bool,
+    pub(crate) beginning_fragment: bool,
+    pub(crate) ending_fragment: bool,
+    pub(crate) immediate_sack: bool,
+
+    pub(crate) tsn: u32,
+    pub(crate) stream_identifier: u16,
+    pub(crate) stream_sequence_number: u16,
+    pub(crate) payload_type: PayloadProtocolIdentifier,
+    pub(crate) user_data: Bytes,
+
+    /// I-DATA chunk (RFC 8260)
+    pub(crate) interleaved: bool,
+    /// I-DATA message identifier
+    pub(crate) message_identifier: u32,
+    /// I-DATA fragment sequence number
+    pub(crate) fragment_sequence_number: u32,
+
+    /// Whether this data chunk was acknowledged (received by peer)
+    pub(crate) acked: bool,
+    pub(crate) miss_indicator: u32,
+
+    /// Partial-reliability parameters used only by sender
+    pub(crate) since: Option<Instant>,
+    /// number of transmission made for this chunk
+    pub(crate) nsent: u32,
+
+    /// valid only with the first fragment
+    pub(crate) abandoned: bool,
+    /// valid only with the first fragment
+    pub(crate) all_inflight: bool,
+
+    /// Retransmission flag set when T1-RTX timeout occurred and this
+    /// chunk is still in the inflight queue
+    pub(crate) retransmit: bool,
+}
+
+impl Default for ChunkPayloadData {
+    fn default() -> Self {
+        ChunkPayloadData {
+            unordered: false,
+            beginning_fragment: false,
+            ending_fragment: false,
+            immediate_sack: false,
+            tsn: 0,
+            stream_identifier: 0,
+            stream_sequence_number: 0,
+            payload_type: PayloadProtocolIdentifier::default(),
+            user_data: Bytes::new(),
+            interleaved: false,
+            message_identifier: 0,
+            fragment_sequence_number: 0,
+            acked: false,
+            miss_indicator: 0,
+            since: None,
+            nsent: 0,
+            abandoned: false,
+            all_inflight: false,
+            retransmit: false,
+        }
+    }
+}
+
+/// makes chunkPayloadData printable
+impl fmt::Display for ChunkPayloadData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}\n{}", self.header(), self.tsn)
+    }
+}
+
+impl Chunk for ChunkPayloadData {
+    fn header(&self) -> ChunkHeader {
+        let mut flags: u8 = 0;
+        if self.ending_fragment {
+            flags = 1;
+        }
+        if self.beginning_fragment {
+            flags |= 1 << 1;
+        }
+        if self.unordered {
+            flags |= 1 << 2;
+        }
+        if self.immediate_sack {
+            flags |= 1 << 3;
+        }
+
+        ChunkHeader {
+            typ: if self.interleaved {
+                CT_I_DATA
+            } else {
+                CT_PAYLOAD_DATA
+            },
+            flags,
+            value_length: self.value_length() as u16,
+        }
+    }
+
+    fn unmarshal(raw: &Bytes) -> Result<Self> {
+        let header = ChunkHeader::unmarshal(raw)?;
+
+        let interleaved = match header.typ {
+            CT_PAYLOAD_DATA => false,
+            CT_I_DATA => true,
+            _ => return Err(Error::ErrChunkTypeNotPayloadData),
+        };
+        let header_size = if interleaved {
+            I_DATA_HEADER_SIZE
+        } else {
+            PAYLOAD_DATA_HEADER_SIZE
+        };
+
+        let immediate_sack = (header.flags & PAYLOAD_DATA_IMMEDIATE_SACK) != 0;
+        let unordered = (header.flags & PAYLOAD_DATA_UNORDERED_BITMASK) != 0;
+        let beginning_fragment = (header.flags & PAYLOAD_DATA_BEGINING_FRAGMENT_BITMASK) != 0;
+        let ending_fragment = (header.flags & PAYLOAD_DATA_ENDING_FRAGMENT_BITMASK) != 0;
+
+        if raw.len() < header_size || header.value_length() < header_size {
+            return Err(Error::ErrChunkPayloadSmall);
+        }
+
+        let reader = &mut raw.slice(CHUNK_HEADER_SIZE..CHUNK_HEADER_SIZE + header.value_length());
+
+        let tsn = reader.get_u32();
+        let stream_identifier = reader.get_u16();
+        let mut stream_sequence_number = 0;
+        let mut message_identifier = 0;
+        let mut fragment_sequence_number = 0;
+        let mut payload_type = PayloadProtocolIdentifier::Unknown;
+        if interleaved {
+            // Reserved
+            reader.get_u16();
+            message_identifier = reader.get_u32();
+            // The first fragment has the PPID, and an implicit FSN of 0.
+            if beginning_fragment {
+                payload_type = reader.get_u32().into();
+            } else {
+                fragment_sequence_number = reader.get_u32();
+            }
+        } else {
+            stream_sequence_number = reader.get_u16();
+            payload_type = reader.get_u32().into();
+        }
+        let user_data =
+            raw.slice(CHUNK_HEADER_SIZE + header_size..CHUNK_HEADER_SIZE + header.value_length());
+
+        Ok(ChunkPayloadData {
+            unordered,
+            beginning_fragment,
+            ending_fragment,
+            immediate_sack,
+            tsn,
+            stream_identifier,
+            stream_sequence_number,
+            payload_type,
+            user_data,
+            interleaved,
+            message_identifier,
+            fragment_sequence_number,
+
+            acked: false,
+            miss_indicator: 0,
+            since: None,
+            nsent: 0,
+            abandoned: false,
+            all_inflight: false,
+            retransmit: false,
+        })
+    }
+
+    fn marshal_to(&self, writer: &mut BytesMut) -> Result<usize> {
+        self.header().marshal_to(writer)?;
+
+        writer.put_u32(self.tsn);
+        writer.put_u16(self.stream_identifier);
+        if self.interleaved {
+            writer.put_u16(0);
+            writer.put_u32(self.message_identifier);
+            if self.beginning_fragment {
+                writer.put_u32(self.payload_type as u32);
+            } else {
+                writer.put_u32(self.fragment_sequence_number);
+            }
+        } else {
+            writer.put_u16(self.stream_sequence_number);
+            writer.put_u32(self.payload_type as u32);
+        }
+        writer.extend_from_slice(&self.user_data);
+
+        Ok(writer.len())
+    }
+
+    fn check(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn value_length(&self) -> usize {
+        self.header_size() + self.user_data.len()
+    }
+
+    fn as_any(&self) -> &(dyn Any + Send + Sync) {
+        self
+    }
+}
+
+impl ChunkPayloadData {
+    /// Size of the header after the chunk header, which is bigger for I-DATA.
+    pub(crate) fn header_size(&self) -> usize {
+        if self.interleaved {
+            I_DATA_HEADER_SIZE
+        } else {
+            PAYLOAD_DATA_HEADER_SIZE
+        }
+    }
+
+    pub(crate) fn abandoned(&self) -> bool {
+        self.abandoned && self.all_inflight
+    }
+
+    pub(crate) fn set_abandoned(&mut self, abandoned: bool) {
+        self.abandoned = abandoned;
+    }
+
+    pub(crate) fn set_all_inflight(&mut self) {
+        if self.ending_fragment {
+            self.all_inflight = true;
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use crate::association::stream::StreamId;
+use crate::chunk::chunk_payload_data::ChunkPayloadData;
+
+use std::collections::{BTreeMap, VecDeque};
+
+/// pendingBaseQueue
+pub(crate) type PendingBaseQueue = VecDeque<ChunkPayloadData>;
+
+/// pendingQueue
+#[derive(Debug, Default)]
+pub(crate) struct PendingQueue {
+    unordered_queue: PendingBaseQueue,
+    ordered_queue: PendingBaseQueue,
+    queue_len: usize,
+    n_bytes: usize,
+    selected: bool,
+    unordered_is_selected: bool,
+
+    // With I-DATA (RFC 8260), fragments of messages on different streams can
+    // be interleaved. The chunks are then queued per stream, and the streams
+    // are served round robin, one chunk at a time.
+    interleaving: bool,
+    stream_queues: BTreeMap<StreamId, PendingBaseQueue>,
+    next_stream: StreamId,
+
+    // Without I-DATA, the fragments of a message must have consecutive TSNs.
+    // A message written in pieces is open until its ending fragment is pushed,
+    // and the chunks of other messages are held until then.
+    open: Option<(StreamId, bool)>,
+    held: PendingBaseQueue,
+}
+
+impl PendingQueue {
+    pub(crate) fn new() -> Self {
+        PendingQueue::default()
+    }
+
+    /// Switch to interleaving the messages of different streams.
+    ///
+    /// Must be called before any chunk is pushed.
+    pub(crate) fn set_interleaving(&mut self, interleaving: bool) {
+        debug_assert!(self.is_empty());
+        self.interleaving = interleaving;
+    }
+
+    pub(crate) fn push(&mut self, c: ChunkPayloadData) {
+        self.n_bytes += c.user_data.len();
+        self.queue_len += 1;
+        self.append(c);
+    }
+
+    fn append(&mut self, mut c: ChunkPayloadData) {
+        if self.interleaving {
+            self.stream_queues
+                .entry(c.stream_identifier)
+                .or_default()
+                .push_back(c);
+            return;
+        }
+
+        if let Some((id, unordered)) = self.open {
+            // The reset of the stream cuts the open message short.
+            let reset = c.user_data.is_empty() && c.stream_identifier == id;
+            let continues =
+                !c.beginning_fragment && c.stream_identifier == id && c.unordered == unordered;
+            if reset {
+                c.unordered = unordered;
+            } else if !continues {
+                self.held.push_back(c);
+                return;
+            }
+        }
+
+        self.open = (!c.ending_fragment).then_some((c.stream_identifier, c.unordered));
+
+        if c.unordered {
+            self.unordered_queue.push_back(c);
+        } else {
+            self.ordered_queue.push_back(c);
+        }
+
+        if self.open.is_none() && !self.held.is_empty() {
+            // Held chunks might open another message, and be held again.
+            for c in std::mem::take(&mut self.held) {
+                self.append(c);
+            }
+        }
+    }
+
+    pub(crate) fn peek(&self) -> Option<&ChunkPayloadData> {
+        if self.interleaving {
+            return self
+                .next_stream_queue()
+                .and_then(|id| self.stream_queues.get(&id))
+                .and_then(|q| q.front());
+        }
+
+        if self.selected {
+            if self.unordered_is_selected {
+                return self.unordered_queue.front();
+            } else {
+                return self.ordered_queue.front();
+            }
+        }
+
+        let c = self.unordered_queue.front();
+
+        if c.is_some() {
+            return c;
+        }
+
+        self.ordered_queue.front()
+    }
+
+    pub(crate) fn pop(
+        &mut self,
+        beginning_fragment: bool,
+        unordered: bool,
+    ) -> Option<ChunkPayloadData> {
+        let popped = if self.interleaving {
+            self.pop_interleaved()
+        } else if self.selected {
+            let popped = if self.unordered_is_selected {
+                self.unordered_queue.pop_front()
+            } else {
+                self.ordered_queue.pop_front()
+            };
+            if let Some(p) = &popped {
+                if p.ending_fragment {
+                    self.selected = false;
+                }
+            }
+            popped
+        } else {
+            if !beginning_fragment {
+                return None;
+            }
+            if unordered {
+                let popped = { self.unordered_queue.pop_front() };
+                if let Some(p) = &popped {
+                    if !p.ending_fragment {
+                        self.selected = true;
+                        self.unordered_is_selected = true;
+                    }
+                }
+                popped
+            } else {
+                let popped = { self.ordered_queue.pop_front() };
+                if let Some(p) = &popped {
+                    if !p.ending_fragment {
+                        self.selected = true;
+                        self.unordered_is_selected = false;
+                    }
+                }
+                popped
+            }
+        };
+
+        if let Some(p) = &popped {
+            self.n_bytes -= p.user_data.len();
+            self.queue_len -= 1;
+        }
+
+        popped
+    }
+
+    /// The stream to send from next, the first one with queued chunks at or
+    /// after the round robin cursor.
+    fn next_stream_queue(&self) -> Option<StreamId> {
+        self.stream_queues
+            .range(self.next_stream..)
+            .chain(self.stream_queues.range(..self.next_stream))
+            .map(|(id, _)| *id)
+            .next()
+    }
+
+    fn pop_interleaved(&mut self) -> Option<ChunkPayloadData> {
+        let id = self.next_stream_queue()?;
+        let queue = self.stream_queues.get_mut(&id)?;
+        let popped = queue.pop_front();
+        if queue.is_empty() {
+            self.stream_queues.remove(&id);
+        }
+        self.next_stream = id.checked_add(1).unwrap_or(0);
+        popped
+    }
+
+    pub(crate) fn get_num_bytes(&self) -> usize {
+        self.n_bytes
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.queue_len
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
@@ -0,0 +1,799 @@
+use crate::chunk::chunk_payload_data::{ChunkPayloadData, PayloadProtocolIdentifier};
+use crate::error::{Error, Result};
+use crate::util::*;
+use crate::StreamId;
+
+use bytes::{Bytes, BytesMut};
+use std::cmp::Ordering;
+
+fn sort_chunks_by_tsn(c: &mut [ChunkPayloadData]) {
+    c.sort_by(|a, b| {
+        if sna32lt(a.tsn, b.tsn) {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        }
+    });
+}
+
+fn sort_chunks_by_fsn(c: &mut [ChunkPayloadData]) {
+    c.sort_by(|a, b| {
+        if sna32lt(a.fragment_sequence_number, b.fragment_sequence_number) {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        }
+    });
+}
+
+fn sort_chunks_by_mid(c: &mut [Chunks]) {
+    c.sort_by(|a, b| {
+        if sna32lt(a.mid, b.mid) {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        }
+    });
+}
+
+fn sort_chunks_by_ssn(c: &mut [Chunks]) {
+    c.sort_by(|a, b| {
+        if sna16lt(a.ssn, b.ssn) {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        }
+    });
+}
+
+/// Drain the fragments from `start` on that follow on the ones already read
+/// of the partially read message, up to its ending fragment.
+fn take_following(
+    chunks: &mut Vec<ChunkPayloadData>,
+    start: usize,
+    partial: &mut PartialState,
+) -> Vec<ChunkPayloadData> {
+    let mut end = start;
+
+    while let Some(c) = chunks.get(end) {
+        let follows = if partial.interleaved {
+            c.fragment_sequence_number == partial.next_fsn
+        } else {
+            c.tsn == partial.next_tsn
+        };
+        if !follows {
+            break;
+        }
+
+        partial.next_tsn = c.tsn.wrapping_add(1);
+        partial.next_fsn = c.fragment_sequence_number.wrapping_add(1);
+        end += 1;
+
+        if c.ending_fragment {
+            break;
+        }
+    }
+
+    chunks.drain(start..end).collect()
+}
+
+fn concat(chunks: &[ChunkPayloadData]) -> Bytes {
+    if let [c] = chunks {
+        return c.user_data.clone();
+    }
+
+    let mut buf = BytesMut::with_capacity(chunks.iter().map(|c| c.user_data.len()).sum());
+    for c in chunks {
+        buf.extend_from_slice(&c.user_data);
+    }
+    buf.freeze()
+}
+
+/// A chunk of data from the stream
+#[derive(Debug, PartialEq)]
+pub struct Chunk {
+    /// The contents of the chunk
+    pub bytes: Bytes,
+}
+
+/// A piece of a message read before all of it has arrived, see
+/// [`Stream::read_partial`](crate::Stream::read_partial).
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartialRead {
+    /// The payload protocol of the message.
+    pub ppi: PayloadProtocolIdentifier,
+    /// The contents of the piece.
+    pub bytes: Bytes,
+    /// This piece is the start of the message.
+    pub beginning: bool,
+    /// This piece is the end of the message.
+    pub ending: bool,
+    /// The sender abandoned the rest of the message (PR-SCTP), and it will not
+    /// arrive. Always together with `ending`, and the piece is empty.
+    pub abandoned: bool,
+}
+
+/// The message being read with [`ReassemblyQueue::read_partial`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PartialState {
+    unordered: bool,
+    interleaved: bool,
+    ppi: PayloadProtocolIdentifier,
+    ssn: u16,
+    mid: u32,
+    /// TSN of the next DATA fragment to read.
+    next_tsn: u32,
+    /// FSN of the next I-DATA fragment to read.
+    next_fsn: u32,
+    abandoned: bool,
+}
+
+/// Chunks is a set of chunks that share the same SSN, or MID for I-DATA
+#[derive(Default, Debug, Clone)]
+pub struct Chunks {
+    /// used only with the ordered chunks
+    pub(crate) ssn: u16,
+    /// used only with I-DATA chunks
+    pub(crate) mid: u32,
+    pub ppi: PayloadProtocolIdentifier,
+    pub chunks: Vec<ChunkPayloadData>,
+    offset: usize,
+    index: usize,
+}
+
+impl Chunks {
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn len(&self) -> usize {
+        let mut l = 0;
+        for c in &self.chunks {
+            l += c.user_data.len();
+        }
+        l
+    }
+
+    // Concat all fragments into the buffer
+    pub fn read(&self, buf: &mut [u8]) -> Result<usize> {
+        let mut n_written = 0;
+        for c in &self.chunks {
+            let to_copy = c.user_data.len();
+            let n = std::cmp::min(to_copy, buf.len() - n_written);
+            buf[n_written..n_written + n].copy_from_slice(&c.user_data[..n]);
+            n_written += n;
+            if n < to_copy {
+                return Err(Error::ErrShortBuffer);
+            }
+        }
+        Ok(n_written)
+    }
+
+    pub fn next(&mut self, max_length: usize) -> Option<Chunk> {
+        if self.index >= self.chunks.len() {
+            return None;
+        }
+
+        let mut buf = BytesMut::with_capacity(max_length);
+
+        let mut n_written = 0;
+        while self.index < self.chunks.len() {
+            let to_copy = self.chunks[self.index].user_data[self.offset..].len();
+            let n = std::cmp::min(to_copy, max_length - n_written);
+            buf.extend_from_slice(&self.chunks[self.index].user_data[self.offset..self.offset + n]);
+            n_written += n;
+            if n < to_copy {
+                self.offset += n;
+                return Some(Chunk {
+                    bytes: buf.freeze(),
+                });
+            }
+            self.index += 1;
+            self.offset = 0;
+        }
+
+        Some(Chunk {
+            bytes: buf.freeze(),
+        })
+    }
+
+    pub(crate) fn new(
+        ssn: u16,
+        ppi: PayloadProtocolIdentifier,
+        chunks: Vec<ChunkPayloadData>,
+    ) -> Self {
+        Chunks {
+            ssn,
+            mid: 0,
+            ppi,
+            chunks,
+            offset: 0,
+            index: 0,
+        }
+    }
+
+    fn is_interleaved(&self) -> bool {
+        self.chunks.first().is_some_and(|c| c.interleaved)
+    }
+
+    pub(crate) fn push(&mut self, chunk: ChunkPayloadData) -> bool {
+        // check if dup
+        for c in &self.chunks {
+            if c.tsn == chunk.tsn {
+                return false;
+            }
+        }
+
+        // append and sort
+        if chunk.interleaved {
+            // Only the first I-DATA fragment carries the PPID.
+            if chunk.beginning_fragment {
+                self.ppi = chunk.payload_type;
+            }
+            self.chunks.push(chunk);
+            sort_chunks_by_fsn(&mut self.chunks);
+        } else {
+            self.chunks.push(chunk);
+            sort_chunks_by_tsn(&mut self.chunks);
+        }
+
+        // Check if we now have a complete set
+        self.is_complete()
+    }
+
+    pub(crate) fn is_complete(&self) -> bool {
+        // Condition for complete set
+        //   0. Has at least one chunk.
+        //   1. Begins with beginningFragment set to true
+        //   2. Ends with endingFragment set to true
+        //   3. TSN monotinically increase by 1 from beginning to end
+
+        // 0.
+        let n_chunks = self.chunks.len();
+        if n_chunks == 0 {
+            return false;
+        }
+
+        // 1.
+        if !self.chunks[0].beginning_fragment {
+            return false;
+        }
+
+        // 2.
+        if !self.chunks[n_chunks - 1].ending_fragment {
+            return false;
+        }
+
+        // 3. For I-DATA, the FSN (implicitly 0 in the first fragment) is used
+        //    instead of the TSN. RFC 8260 2.1:
+        //      The FSN MUST start at 0 and MUST be incremented by 1 for each
+        //      subsequent fragment.
+        if self.is_interleaved() {
+            return self
+                .chunks
+                .iter()
+                .enumerate()
+                .all(|(i, c)| c.fragment_sequence_number == i as u32);
+        }
+
+        let mut last_tsn = 0u32;
+        for (i, c) in self.chunks.iter().enumerate() {
+            if i > 0 {
+                // Fragments must have contiguous TSN
+                // From RFC 4960 Section 3.3.1:
+                //   When a user message is fragmented into multiple chunks, the TSNs are
+                //   used by the receiver to reassemble the message.  This means that the
+                //   TSNs for each fragment of a fragmented user message MUST be strictly
+                //   sequential.
+                if c.tsn != last_tsn + 1 {
+                    // mid or end fragment is missing
+                    return false;
+                }
+            }
+
+            last_tsn = c.tsn;
+        }
+
+        true
+    }
+}
+
+#[derive(Default, Debug)]
+pub(crate) struct ReassemblyQueue {
+    pub(crate) si: StreamId,
+    pub(crate) next_ssn: u16,
+    /// expected SSN for next ordered chunk
+    pub(crate) ordered: Vec<Chunks>,
+    pub(crate) unordered: Vec<Chunks>,
+    pub(crate) unordered_chunks: Vec<ChunkPayloadData>,
+    pub(crate) n_bytes: usize,
+    /// expected MID for next ordered I-DATA chunk
+    pub(crate) next_mid: u32,
+    /// incomplete unordered I-DATA messages, one set per MID
+    pub(crate) unordered_incomplete: Vec<Chunks>,
+    /// message partially read with read_partial
+    pub(crate) partial: Option<PartialState>,
+}
+
+impl ReassemblyQueue {
+    /// From RFC 4960 Sec 6.5:
+    ///   The Stream Sequence Number in all the streams MUST start from 0 when
+    ///   the association is Established.  Also, when the Stream Sequence
+    ///   Number reaches the value 65535 the next Stream Sequence Number MUST
+    ///   be set to 0.
+    pub(crate) fn new(si: StreamId) -> Self {
+        ReassemblyQueue {
+            si,
+            next_ssn: 0, // From RFC 4960 Sec 6.5:
+            ordered: vec![],
+            unordered: vec![],
+            unordered_chunks: vec![],
+            n_bytes: 0,
+            next_mid: 0,
+            unordered_incomplete: vec![],
+            partial: None,
+        }
+    }
+
+    pub(crate) fn push(&mut self, chunk: ChunkPayloadData) -> bool {
+        if chunk.stream_identifier != self.si {
+            return false;
+        }
+
+        if chunk.interleaved {
+            return self.push_interleaved(chunk);
+        }
+
+        if chunk.unordered {
+            // First, insert into unordered_chunks array
+            //atomic.AddUint64(&r.n_bytes, uint64(len(chunk.userData)))
+            self.n_bytes += chunk.user_data.len();
+            self.unordered_chunks.push(chunk);
+            sort_chunks_by_tsn(&mut self.unordered_chunks);
+
+            // Scan unordered_chunks that are contiguous (in TSN)
+            // If found, append the complete set to the unordered array
+            if let Some(cset) = self.find_complete_unordered_chunk_set() {
+                self.unordered.push(cset);
+                return true;
+            }
+
+            false
+        } else {
+            // This is an ordered chunk
+            if sna16lt(chunk.stream_sequence_number, self.next_ssn) {
+                return false;
+            }
+
+            self.n_bytes += chunk.user_data.len();
+
+            // Check if a chunkSet with the SSN already exists
+            for s in &mut self.ordered {
+                if s.ssn == chunk.stream_sequence_number {
+                    return s.push(chunk);
+                }
+            }
+
+            // If not found, create a new chunkSet
+            let mut cset = Chunks::new(chunk.stream_sequence_number, chunk.payload_type, vec![]);
+            let unordered = chunk.unordered;
+            let ok = cset.push(chunk);
+            self.ordered.push(cset);
+            if !unordered {
+                sort_chunks_by_ssn(&mut self.ordered);
+            }
+
+            ok
+        }
+    }
+
+    /// I-DATA fragments are grouped by MID, both for ordered and unordered
+    /// messages, since fragments of different messages can be interleaved.
+    fn push_interleaved(&mut self, chunk: ChunkPayloadData) -> bool {
+        let mid = chunk.message_identifier;
+
+        if chunk.unordered {
+            self.n_bytes += chunk.user_data.len();
+
+            let idx = match self.unordered_incomplete.iter().position(|s| s.mid == mid) {
+                Some(idx) => idx,
+                None => {
+                    let mut cset = Chunks::new(0, chunk.payload_type, vec![]);
+                    cset.mid = mid;
+                    self.unordered_incomplete.push(cset);
+                    self.unordered_incomplete.len() - 1
+                }
+            };
+
+            if self.unordered_incomplete[idx].push(chunk) {
+                let cset = self.unordered_incomplete.remove(idx);
+                self.unordered.push(cset);
+                return true;
+            }
+
+            false
+        } else {
+            if sna32lt(mid, self.next_mid) {
+                return false;
+            }
+
+            self.n_bytes += chunk.user_data.len();
+
+            // Check if a chunkSet with the MID already exists
+            for s in &mut self.ordered {
+                if s.mid == mid {
+                    return s.push(chunk);
+                }
+            }
+
+            // If not found, create a new chunkSet
+            let mut cset = Chunks::new(0, chunk.payload_type, vec![]);
+            cset.mid = mid;
+            let ok = cset.push(chunk);
+            self.ordered.push(cset);
+            sort_chunks_by_mid(&mut self.ordered);
+
+            ok
+        }
+    }
+
+    /// Whether the set is the next one to deliver on the ordered stream.
+    fn is_next_ordered(&self, cset: &Chunks) -> bool {
+        if cset.is_interleaved() {
+            sna32lte(cset.mid, self.next_mid)
+        } else {
+            sna16lte(cset.ssn, self.next_ssn)
+        }
+    }
+
+    pub(crate) fn find_complete_unordered_chunk_set(&mut self) -> Option<Chunks> {
+        let mut start_idx = -1isize;
+        let mut n_chunks = 0usize;
+        let mut last_tsn = 0u32;
+        let mut found = false;
+
+        for (i, c) in self.unordered_chunks.iter().enumerate() {
+            // seek beginning
+            if c.beginning_fragment {
+                start_idx = i as isize;
+                n_chunks = 1;
+                last_tsn = c.tsn;
+
+                if c.ending_fragment {
+                    found = true;
+                    break;
+                }
+                continue;
+            }
+
+            if start_idx < 0 {
+                continue;
+            }
+
+            // Check if contiguous in TSN
+            if c.tsn != last_tsn + 1 {
+                start_idx = -1;
+                continue;
+            }
+
+            last_tsn = c.tsn;
+            n_chunks += 1;
+
+            if c.ending_fragment {
+                found = true;
+                break;
+            }
+        }
+
+        if !found {
+            return None;
+        }
+
+        // Extract the range of chunks
+        let chunks: Vec<ChunkPayloadData> = self
+            .unordered_chunks
+            .drain(start_idx as usize..(start_idx as usize) + n_chunks)
+            .collect();
+        Some(Chunks::new(0, chunks[0].payload_type, chunks))
+    }
+
+    pub(crate) fn is_readable(&self) -> bool {
+        // Check unordered first
+        if !self.unordered.is_empty() {
+            // The chunk sets in r.unordered should all be complete.
+            return true;
+        }
+
+        // Check ordered sets
+        if !self.ordered.is_empty() {
+            let cset = &self.ordered[0];
+            if cset.is_complete() && self.is_next_ordered(cset) {
+                return true;
+            }
+        }
+        false
+    }
+
+    pub(crate) fn read(&mut self) -> Option<Chunks> {
+        // Check unordered first
+        let chunks = if !self.unordered.is_empty() {
+            self.unordered.remove(0)
+        } else if !self.ordered.is_empty() {
+            // Now, check ordered
+            let chunks = &self.ordered[0];
+            if !chunks.is_complete() {
+                return None;
+            }
+            if !self.is_next_ordered(chunks) {
+                return None;
+            }
+            if chunks.is_interleaved() {
+                if chunks.mid == self.next_mid {
+                    self.next_mid = self.next_mid.wrapping_add(1);
+                }
+            } else if chunks.ssn == self.next_ssn {
+                self.next_ssn = self.next_ssn.wrapping_add(1);
+            }
+            self.ordered.remove(0)
+        } else {
+            return None;
+        };
+
+        self.subtract_num_bytes(chunks.len());
+
+        Some(chunks)
+    }
+
+    /// Read the next piece of a message, see [`PartialRead`].
+    pub(crate) fn read_partial(&mut self) -> Option<PartialRead> {
+        let Some(mut partial) = self.partial else {
+            return self.begin_partial();
+        };
+
+        if partial.abandoned {
+            self.partial = None;
+            return Some(PartialRead {
+                ppi: partial.ppi,
+                bytes: Bytes::new(),
+                beginning: false,
+                ending: true,
+                abandoned: true,
+            });
+        }
+
+        let chunks = self.take_partial(&mut partial);
+        if chunks.is_empty() {
+            return None;
+        }
+
+        Some(self.partial_read(partial, chunks, false))
+    }
+
+    fn begin_partial(&mut self) -> Option<PartialRead> {
+        if self.is_readable() {
+            let chunks = self.read()?;
+            return Some(PartialRead {
+                ppi: chunks.ppi,
+                bytes: concat(&chunks.chunks),
+                beginning: true,
+                ending: true,
+                abandoned: false,
+            });
+        }
+
+        let mut partial = PartialState {
+            unordered: false,
+            interleaved: false,
+            ppi: PayloadProtocolIdentifier::Unknown,
+            ssn: 0,
+            mid: 0,
+            next_tsn: 0,
+            next_fsn: 0,
+            abandoned: false,
+        };
+
+        let begins = |s: &Chunks| s.chunks.first().is_some_and(|c| c.beginning_fragment);
+
+        if let Some(s) = self
+            .ordered
+            .first()
+            .filter(|s| self.is_next_ordered(s) && begins(s))
+        {
+            partial.interleaved = s.is_interleaved();
+            partial.ppi = s.ppi;
+            partial.ssn = s.ssn;
+            partial.mid = s.mid;
+            partial.next_tsn = s.chunks[0].tsn;
+        } else if let Some(s) = self.unordered_incomplete.iter().find(|s| begins(s)) {
+            partial.unordered = true;
+            partial.interleaved = true;
+            partial.ppi = s.ppi;
+            partial.mid = s.mid;
+        } else if let Some(c) = self.unordered_chunks.iter().find(|c| c.beginning_fragment) {
+            partial.unordered = true;
+            partial.ppi = c.payload_type;
+            partial.next_tsn = c.tsn;
+        } else {
+            return None;
+        }
+
+        let chunks = self.take_partial(&mut partial);
+        Some(self.partial_read(partial, chunks, true))
+    }
+
+    /// Take the fragments of the partially read message that follow on the
+    /// ones already read.
+    fn take_partial(&mut self, partial: &mut PartialState) -> Vec<ChunkPayloadData> {
+        let mut taken = vec![];
+
+        if partial.unordered && !partial.interleaved {
+            if let Some(start) = self
+                .unordered_chunks
+                .iter()
+                .position(|c| c.tsn == partial.next_tsn)
+            {
+                taken = take_following(&mut self.unordered_chunks, start, partial);
+            }
+        } else {
+            let sets = if partial.unordered {
+                &mut self.unordered_incomplete
+            } else {
+                &mut self.ordered
+            };
+
+            let idx = sets.iter().position(|s| {
+                if partial.interleaved {
+                    s.mid == partial.mid
+                } else {
+                    s.ssn == partial.ssn
+                }
+            });
+
+            if let Some(idx) = idx {
+                taken = take_following(&mut sets[idx].chunks, 0, partial);
+                if sets[idx].chunks.is_empty() {
+                    sets.remove(idx);
+                }
+            }
+        }
+
+        let n_bytes = taken.iter().map(|c| c.user_data.len()).sum();
+        self.subtract_num_bytes(n_bytes);
+
+        taken
+    }
+
+    fn partial_read(
+        &mut self,
+        partial: PartialState,
+        chunks: Vec<ChunkPayloadData>,
+        beginning: bool,
+    ) -> PartialRead {
+        let ending = chunks.last().is_some_and(|c| c.ending_fragment);
+
+        if !ending {
+            self.partial = Some(partial);
+        } else {
+            self.partial = None;
+
+            // The ordered message is delivered, the same as in read().
+            if !partial.unordered {
+                if partial.interleaved {
+                    if partial.mid == self.next_mid {
+                        self.next_mid = self.next_mid.wrapping_add(1);
+                    }
+                } else if partial.ssn == self.next_ssn {
+                    self.next_ssn = self.next_ssn.wrapping_add(1);
+                }
+            }
+        }
+
+        PartialRead {
+            ppi: partial.ppi,
+            bytes: concat(&chunks),
+            beginning,
+            ending,
+            abandoned: false,
+        }
+    }
+
+    /// Mark the partially read message abandoned if it is skipped.
+    fn abandon_partial(&mut self, skipped: impl Fn(&PartialState) -> bool) {
+        if let Some(p) = &mut self.partial {
+            if skipped(p) {
+                p.abandoned = true;
+            }
+        }
+    }
+
+    /// Use last_ssn to locate a chunkSet then remove it if the set has
+    /// not been complete
+    pub(crate) fn forward_tsn_for_ordered(&mut self, last_ssn: u16) {
+        let num_bytes = self
+            .ordered
+            .iter()
+            .filter(|s| sna16lte(s.ssn, last_ssn) && !s.is_complete())
+            .fold(0, |n, s| {
+                n + s.chunks.iter().fold(0, |acc, c| acc + c.user_data.len())
+            });
+        self.subtract_num_bytes(num_bytes);
+
+        self.ordered
+            .retain(|s| !sna16lte(s.ssn, last_ssn) || s.is_complete());
+
+        self.abandon_partial(|p| !p.unordered && !p.interleaved && sna16lte(p.ssn, last_ssn));
+
+        // Finally, forward next_ssn
+        if sna16lte(self.next_ssn, last_ssn) {
+            self.next_ssn = last_ssn.wrapping_add(1);
+        }
+    }
+
+    /// Remove all fragments in the unordered sets that contains chunks
+    /// equal to or older than `new_cumulative_tsn`.
+    /// We know all sets in the r.unordered are complete ones.
+    /// Just remove chunks that are equal to or older than new_cumulative_tsn
+    /// from the unordered_chunks
+    pub(crate) fn forward_tsn_for_unordered(&mut self, new_cumulative_tsn: u32) {
+        let mut last_idx: isize = -1;
+        for (i, c) in self.unordered_chunks.iter().enumerate() {
+            if sna32gt(c.tsn, new_cumulative_tsn) {
+                break;
+            }
+            last_idx = i as isize;
+        }
+        if last_idx >= 0 {
+            for i in 0..(last_idx + 1) as usize {
+                self.subtract_num_bytes(self.unordered_chunks[i].user_data.len());
+            }
+            self.unordered_chunks.drain(..(last_idx + 1) as usize);
+        }
+
+        self.abandon_partial(|p| {
+            p.unordered && !p.interleaved && sna32lte(p.next_tsn, new_cumulative_tsn)
+        });
+    }
+
+    /// Like [`Self::forward_tsn_for_ordered`], but for an I-FORWARD-TSN that
+    /// skips the ordered I-DATA messages up to and including `last_mid`.
+    pub(crate) fn forward_mid_for_ordered(&mut self, last_mid: u32) {
+        let num_bytes = self
+            .ordered
+            .iter()
+            .filter(|s| sna32lte(s.mid, last_mid) && !s.is_complete())
+            .fold(0, |n, s| n + s.len());
+        self.subtract_num_bytes(num_bytes);
+
+        self.ordered
+            .retain(|s| !sna32lte(s.mid, last_mid) || s.is_complete());
+
+        self.abandon_partial(|p| !p.unordered && p.interleaved && sna32lte(p.mid, last_mid));
+
+        // Finally, forward next_mid
+        if sna32lte(self.next_mid, last_mid) {
+            self.next_mid = last_mid.wrapping_add(1);
+        }
+    }
+
+    /// Remove the fragments of the skipped unordered I-DATA message `mid`.
+    pub(crate) fn forward_mid_for_unordered(&mut self, mid: u32) {
+        if let Some(idx) = self.unordered_incomplete.iter().position(|s| s.mid == mid) {
+            let cset = self.unordered_incomplete.remove(idx);
+            self.subtract_num_bytes(cset.len());
+        }
+
+        self.abandon_partial(|p| p.unordered && p.interleaved && p.mid == mid);
+    }
+
+    pub(crate) fn subtract_num_bytes(&mut self, n_bytes: usize) {
+        if self.n_bytes >= n_bytes {
+            self.n_bytes -= n_bytes;
+        } else {
+            self.n_bytes = 0;
+        }
+    }
+
+    pub(crate) fn get_num_bytes(&self) -> usize {
+        self.n_bytes
+    }
+}
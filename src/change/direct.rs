@@ -1,9 +1,11 @@
+use crate::change::DataToken;
 use crate::channel::ChannelId;
-use crate::crypto::Fingerprint;
+use crate::crypto::{Fingerprint, SrtpProfile};
 use crate::media::{Media, MediaKind};
 use crate::rtp_::MidRid;
 use crate::rtp_::{Mid, Rid, Ssrc};
 use crate::sctp::ChannelConfig;
+use crate::sdes::SdesKeys;
 use crate::streams::{StreamRx, StreamTx, DEFAULT_RTX_CACHE_DURATION, DEFAULT_RTX_RATIO_CAP};
 use crate::IceCreds;
 use crate::Rtc;
@@ -88,7 +90,7 @@ impl<'a> DirectApi<'a> {
 
     /// Sets the remote DTLS fingerprint.
     pub fn set_remote_fingerprint(&mut self, dtls_fingerprint: Fingerprint) {
-        self.rtc.remote_fingerprint = Some(dtls_fingerprint);
+        self.rtc.remote_fingerprints = vec![dtls_fingerprint];
     }
 
     /// Start the DTLS subsystem.
@@ -96,11 +98,96 @@ impl<'a> DirectApi<'a> {
         self.rtc.init_dtls(active)
     }
 
+    /// Start a new DTLS handshake keeping the role, i.e. to rotate certificates.
+    ///
+    /// Uses the certificate from [`Rtc::set_dtls_cert()`][crate::Rtc::set_dtls_cert] if
+    /// there is one. The remote peer must restart as well. If its certificate changes, set
+    /// the new fingerprint using [`DirectApi::set_remote_fingerprint()`] first.
+    ///
+    /// Does nothing if DTLS has not been started.
+    pub fn restart_dtls(&mut self) -> Result<(), RtcError> {
+        self.rtc.dtls.advertise_pending_cert();
+        self.rtc.restart_dtls()
+    }
+
+    /// Start SRTP using SDES (RFC 4568) keys exchanged out of band, skipping DTLS.
+    ///
+    /// The keys are the master key followed by the master salt, as in an `a=crypto`
+    /// line. `remote_mki` is the MKI the remote puts in its packets, if any. We never
+    /// send an MKI. Calling this again with different keys re-keys the session.
+    ///
+    /// Data channels need DTLS and are not available with SDES.
+    pub fn start_sdes_srtp(
+        &mut self,
+        profile: SrtpProfile,
+        local_key: &[u8],
+        remote_key: &[u8],
+        remote_mki: Option<&[u8]>,
+    ) -> Result<(), RtcError> {
+        let (key_len, salt_len) = profile.master_key_salt_len();
+        let len = key_len + salt_len;
+
+        if local_key.len() != len || remote_key.len() != len {
+            return Err(RtcError::SdesKeyLength(profile, len));
+        }
+
+        self.rtc.init_sdes(SdesKeys {
+            profile,
+            local: local_key.to_vec(),
+            remote: remote_key.to_vec(),
+            remote_mki: remote_mki.map(|m| m.to_vec()),
+            remote_lifetime: None,
+            remote_ssrcs: vec![],
+        })
+    }
+
     /// Start the SCTP over DTLS.
     pub fn start_sctp(&mut self, client: bool) {
         self.rtc.init_sctp(client)
     }
 
+    /// Set the largest data channel message the remote peer accepts.
+    ///
+    /// This is the `a=max-message-size` of the remote peer. Defaults to 64kB.
+    pub fn set_remote_max_message_size(&mut self, size: usize) {
+        self.rtc.sctp.set_remote_max_message_size(size);
+    }
+
+    /// The [`DataToken`] for this peer, with the local candidates added so far.
+    ///
+    /// `active` is the DTLS role, and the remote peer must use the opposite role.
+    pub fn local_data_token(&self, active: bool) -> DataToken {
+        DataToken {
+            ice_creds: self.rtc.ice.local_credentials().clone(),
+            fingerprint: self.rtc.dtls.local_fingerprint().clone(),
+            active,
+            candidates: self.rtc.ice.local_candidates().to_vec(),
+        }
+    }
+
+    /// Start a data channel only session from the [`DataToken`] of the remote peer.
+    ///
+    /// This sets the remote ICE credentials, fingerprint and candidates, and starts ICE,
+    /// DTLS and SCTP in the role opposite to the remote. Data channels are then created
+    /// with [`DirectApi::create_data_channel()`], using out-of-band negotiated stream ids
+    /// or in-band DCEP.
+    pub fn start_data_session(&mut self, remote: &DataToken) -> Result<(), RtcError> {
+        let active = !remote.active;
+
+        self.set_remote_ice_credentials(remote.ice_creds.clone());
+        self.set_remote_fingerprint(remote.fingerprint.clone());
+
+        for c in &remote.candidates {
+            self.rtc.add_remote_candidate(c.clone());
+        }
+
+        self.set_ice_controlling(active);
+        self.start_dtls(active)?;
+        self.start_sctp(active);
+
+        Ok(())
+    }
+
     /// Create a new data channel.
     pub fn create_data_channel(&mut self, config: ChannelConfig) -> ChannelId {
         let id = self.rtc.chan.new_channel(&config);
@@ -109,8 +196,12 @@ impl<'a> DirectApi<'a> {
     }
 
     /// Close a data channel.
+    ///
+    /// Messages already written are sent before the channel closes, see [`Channel::close()`][crate::channel::Channel::close()].
     pub fn close_data_channel(&mut self, channel_id: ChannelId) {
-        self.rtc.chan.close_channel(channel_id, &mut self.rtc.sctp);
+        self.rtc
+            .chan
+            .close_channel(channel_id, &mut self.rtc.sctp, false);
     }
 
     /// Set whether to enable ice-lite.
@@ -118,6 +209,13 @@ impl<'a> DirectApi<'a> {
         self.rtc.ice.set_ice_lite(ice_lite);
     }
 
+    /// Set whether RTP header extensions and CSRCs are encrypted using Cryptex (RFC 9335).
+    ///
+    /// Both peers must agree on this out of band.
+    pub fn set_cryptex(&mut self, enabled: bool) {
+        self.rtc.session.set_cryptex(enabled);
+    }
+
     /// Enable twcc feedback.
     pub fn enable_twcc_feedback(&mut self) {
         self.rtc.session.enable_twcc_feedback()
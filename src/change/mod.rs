@@ -12,9 +12,49 @@
 //! The direct API is a lower level API which typically can't be mixed with the SDP API. If you make
 //! changes directly to the session, the remote side would not be aware of them unless you construct
 //! some "other way" keeping the two peers in sync.
+//!
+//! ## Data only sessions
+//!
+//! Peers that only use data channels can skip SDP altogether. Each peer sends a [`DataToken`]
+//! to the other, which is a compact string with the ICE credentials, DTLS fingerprint, DTLS
+//! role and ICE candidates.
+//!
+//! ```no_run
+//! # use str0m::{Rtc, Candidate};
+//! # use str0m::change::DataToken;
+//! # use str0m::channel::ChannelConfig;
+//! # fn send_to_peer(token: String) {}
+//! # fn receive_from_peer() -> String { todo!() }
+//! let mut rtc = Rtc::new();
+//!
+//! let addr = "192.168.0.2:5000".parse().unwrap();
+//! rtc.add_local_candidate(Candidate::host(addr, "udp").unwrap());
+//!
+//! // One peer is active, the other passive.
+//! let token = rtc.direct_api().local_data_token(true);
+//! send_to_peer(token.to_string());
+//!
+//! let remote: DataToken = receive_from_peer().parse().unwrap();
+//! rtc.direct_api().start_data_session(&remote).unwrap();
+//!
+//! // Both peers create the channel with the same negotiated stream id.
+//! let cid = rtc.direct_api().create_data_channel(ChannelConfig {
+//!     label: "telemetry".into(),
+//!     negotiated: Some(0),
+//!     ..Default::default()
+//! });
+//! ```
 mod sdp;
 pub(crate) use sdp::AddMedia;
 pub use sdp::{SdpAnswer, SdpApi, SdpOffer, SdpPendingOffer};
 
+pub use crate::sdp::{MediaSection, SdpLine, SessionDescription, SessionSection};
+
 mod direct;
 pub use direct::DirectApi;
+
+mod token;
+pub use token::{DataToken, DataTokenError};
+
+mod perfect;
+pub use perfect::PerfectNegotiation;
@@ -0,0 +1,129 @@
+use crate::RtcError;
+
+use super::{SdpAnswer, SdpApi, SdpOffer, SdpPendingOffer};
+
+/// Helper for the W3C "perfect negotiation" pattern.
+///
+/// Both peers can make offers at any time. When the offers collide (glare), the polite
+/// peer rolls back its own offer and answers the remote one, while the impolite peer
+/// ignores the remote offer and waits for its answer. The rolled back changes are offered
+/// again once the polite peer's answer is sent. The peers must pick opposite roles.
+///
+/// ```no_run
+/// # use str0m::Rtc;
+/// # use str0m::change::{PerfectNegotiation, SdpOffer, SdpAnswer};
+/// # use str0m::media::{MediaKind, Direction};
+/// # fn send_offer(offer: SdpOffer) {}
+/// # fn send_answer(answer: SdpAnswer) {}
+/// let mut rtc = Rtc::new();
+/// let mut negotiation = PerfectNegotiation::new(true);
+///
+/// let mut changes = rtc.sdp_api();
+/// changes.add_media(MediaKind::Audio, Direction::SendRecv, None, None, None);
+/// if let Some(offer) = negotiation.offer(changes) {
+///     send_offer(offer);
+/// }
+///
+/// // An offer from the remote peer.
+/// let offer: SdpOffer = todo!();
+/// if let Some(answer) = negotiation.accept_offer(rtc.sdp_api(), offer).unwrap() {
+///     send_answer(answer);
+/// }
+///
+/// // Our offer was rolled back, make it again.
+/// if negotiation.needs_offer() {
+///     if let Some(offer) = negotiation.offer(rtc.sdp_api()) {
+///         send_offer(offer);
+///     }
+/// }
+///
+/// // An answer from the remote peer.
+/// let answer: SdpAnswer = todo!();
+/// negotiation.accept_answer(rtc.sdp_api(), answer).unwrap();
+/// ```
+pub struct PerfectNegotiation {
+    polite: bool,
+    pending: Option<SdpPendingOffer>,
+    rolled_back: Option<SdpPendingOffer>,
+}
+
+impl PerfectNegotiation {
+    /// Creates a new helper, for the polite or impolite peer.
+    pub fn new(polite: bool) -> Self {
+        PerfectNegotiation {
+            polite,
+            pending: None,
+            rolled_back: None,
+        }
+    }
+
+    /// Whether this is the polite peer.
+    pub fn is_polite(&self) -> bool {
+        self.polite
+    }
+
+    /// Whether there is an offer waiting for an answer.
+    pub fn has_pending_offer(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Whether an offer was rolled back, and [`PerfectNegotiation::offer()`] should be
+    /// called to offer it again.
+    pub fn needs_offer(&self) -> bool {
+        self.rolled_back.is_some()
+    }
+
+    /// Makes an offer of the `changes`, together with any changes rolled back or still
+    /// waiting for an answer.
+    ///
+    /// An offer waiting for an answer is replaced by the new one. Returns `None` if there
+    /// is nothing to negotiate.
+    pub fn offer(&mut self, mut changes: SdpApi) -> Option<SdpOffer> {
+        if let Some(pending) = self.pending.take() {
+            changes.merge(pending);
+        }
+        if let Some(pending) = self.rolled_back.take() {
+            changes.merge(pending);
+        }
+
+        let (offer, pending) = changes.apply()?;
+        self.pending = Some(pending);
+
+        Some(offer)
+    }
+
+    /// Accepts an offer from the remote peer.
+    ///
+    /// Returns the answer to send back, or `None` if the offer collided with ours and
+    /// we are impolite.
+    pub fn accept_offer(
+        &mut self,
+        mut changes: SdpApi,
+        offer: SdpOffer,
+    ) -> Result<Option<SdpAnswer>, RtcError> {
+        if let Some(pending) = self.pending.take() {
+            if !self.polite {
+                debug!("Ignore colliding offer, we are impolite");
+                self.pending = Some(pending);
+                return Ok(None);
+            }
+
+            debug!("Roll back our offer for colliding offer, we are polite");
+            changes.do_rollback(&pending)?;
+            self.rolled_back = Some(pending);
+        }
+
+        changes.accept_offer(offer).map(Some)
+    }
+
+    /// Accepts an answer to our offer.
+    ///
+    /// Returns [`RtcError::ChangesOutOfOrder`] if there is no offer waiting for an answer.
+    pub fn accept_answer(&mut self, changes: SdpApi, answer: SdpAnswer) -> Result<(), RtcError> {
+        let Some(pending) = self.pending.take() else {
+            return Err(RtcError::ChangesOutOfOrder);
+        };
+
+        changes.accept_answer(pending, answer)
+    }
+}
@@ -16,7 +16,7 @@ use crate::rtp_::{Direction, Extension, ExtensionMap, Mid, Pt, Ssrc};
 use crate::sctp::ChannelConfig;
 use crate::sdp::SimulcastGroups;
 use crate::sdp::{self, MediaAttribute, MediaLine, MediaType, Msid, Sdp};
-use crate::sdp::{Proto, SessionAttribute, Setup};
+use crate::sdp::{Proto, SdesCrypto, SessionAttribute, Setup};
 use crate::session::Session;
 use crate::Rtc;
 use crate::RtcError;
@@ -44,7 +44,8 @@ impl<'a> SdpApi<'a> {
     /// sent to the remote peer.
     ///
     /// <b>Note. Pending changes from a previous non-completed [`SdpApi`][super::SdpApi] will be
-    /// considered rolled back when calling this function.</b>
+    /// considered rolled back when calling this function. Use [`SdpApi::rollback()`] before
+    /// this to also restore channels and directions changed by the pending offer.</b>
     ///
     /// The incoming SDP is validated in various ways which can cause this call to fail.
     /// Example of such problems would be an SDP without any m-lines, missing `a=fingerprint`
@@ -81,36 +82,48 @@ impl<'a> SdpApi<'a> {
 
         add_ice_details(self.rtc, &offer, None)?;
 
-        if self.rtc.remote_fingerprint.is_none() {
-            if let Some(f) = offer.fingerprint() {
-                self.rtc.remote_fingerprint = Some(f);
-            } else {
-                self.rtc.disconnect();
-                return Err(RtcError::RemoteSdp("missing a=fingerprint".into()));
-            }
-        }
+        let sdes = use_sdes(self.rtc, &offer);
 
-        if !self.rtc.dtls.is_inited() {
+        let remote_changed = !sdes && set_remote_fingerprints(self.rtc, &offer)?;
+        let dtls_was_inited = self.rtc.dtls.is_inited();
+
+        if !self.rtc.dtls.is_inited() && !self.rtc.sdes.is_keyed() {
             // The side that makes the first offer is the controlling side, unless they
             // are ICE Lite, in which case the roles are reversed (see RFC 5245).
             self.rtc.ice.set_controlling(offer.session.ice_lite());
         }
 
-        // Ensure setup=active/passive is corresponding remote and init dtls.
-        init_dtls(self.rtc, &offer)?;
+        if sdes {
+            // Pick one of the offered a=crypto lines and start SRTP.
+            accept_sdes_offer(self.rtc, &offer)?;
+        } else {
+            // Ensure setup=active/passive is corresponding remote and init dtls.
+            init_dtls(self.rtc, &offer)?;
+        }
+
+        if let Some(size) = offer.max_message_size() {
+            self.rtc.sctp.set_remote_max_message_size(size);
+        }
 
         // Modify session with offer
         apply_offer(&mut self.rtc.session, offer)?;
 
         // Handle potentially new m=application line.
-        let client = self.rtc.dtls.is_active().expect("DTLS active to be set");
         if self.rtc.session.app().is_some() {
-            self.rtc.init_sctp(client);
+            init_sctp(self.rtc);
         }
 
         let params = AsSdpParams::new(self.rtc, None);
+        let uses_dtls = params.sdes.is_none();
         let sdp = as_sdp(&self.rtc.session, params);
 
+        if uses_dtls {
+            let new_cert = self.rtc.dtls.advertise_pending_cert();
+            if dtls_was_inited && (remote_changed || new_cert) {
+                self.rtc.restart_dtls()?;
+            }
+        }
+
         debug!("Create answer");
         Ok(sdp.into())
     }
@@ -158,28 +171,36 @@ impl<'a> SdpApi<'a> {
 
         add_ice_details(self.rtc, &answer, Some(&pending))?;
 
-        // Ensure setup=active/passive is corresponding remote and init dtls.
-        init_dtls(self.rtc, &answer)?;
+        if use_sdes(self.rtc, &answer) {
+            // Use the keys of the a=crypto line the remote picked.
+            accept_sdes_answer(self.rtc, &answer)?;
+        } else {
+            let dtls_was_inited = self.rtc.dtls.is_inited();
+
+            // Ensure setup=active/passive is corresponding remote and init dtls.
+            init_dtls(self.rtc, &answer)?;
 
-        if self.rtc.remote_fingerprint.is_none() {
-            if let Some(f) = answer.fingerprint() {
-                self.rtc.remote_fingerprint = Some(f);
-            } else {
-                self.rtc.disconnect();
-                return Err(RtcError::RemoteSdp("missing a=fingerprint".into()));
+            let remote_changed = set_remote_fingerprints(self.rtc, &answer)?;
+
+            // A new certificate on either side means a new DTLS handshake.
+            if dtls_was_inited && (remote_changed || self.rtc.dtls.has_advertised_cert()) {
+                self.rtc.restart_dtls()?;
             }
         }
 
         // Split out new channels, since that is not handled by the Session.
         let new_channels = pending.changes.take_new_channels();
 
+        if let Some(size) = answer.max_message_size() {
+            self.rtc.sctp.set_remote_max_message_size(size);
+        }
+
         // Modify session with answer
         apply_answer(&mut self.rtc.session, pending.changes, answer)?;
 
         // Handle potentially new m=application line.
-        let client = self.rtc.dtls.is_active().expect("DTLS to be inited");
         if self.rtc.session.app().is_some() {
-            self.rtc.init_sctp(client);
+            init_sctp(self.rtc);
         }
 
         for (id, config) in new_channels {
@@ -292,6 +313,8 @@ impl<'a> SdpApi<'a> {
             ssrcs,
             simulcast,
 
+            prefs: vec![],
+
             // Added later
             pts: vec![],
             exts: ExtensionMap::empty(),
@@ -314,10 +337,92 @@ impl<'a> SdpApi<'a> {
     /// If the direction is set for media that doesn't exist, or if the direction is
     /// the same that's already set [`SdpApi::apply()`] not require a negotiation.
     pub fn set_direction(&mut self, mid: Mid, dir: Direction) {
+        let prev = self.rtc.media(mid).map(|m| m.direction());
         let changed = self.rtc.session.set_direction(mid, dir);
 
-        if changed {
-            self.changes.0.push(Change::Direction(mid, dir));
+        if let (true, Some(prev)) = (changed, prev) {
+            self.changes.0.push(Change::Direction(mid, dir, prev));
+        }
+    }
+
+    /// Set the codec preferences of a media, like `setCodecPreferences()` for a transceiver.
+    ///
+    /// The `pts` are payload types from the [`CodecConfig`] of the `Rtc`, most preferred
+    /// first. Only those codecs are offered for the media, in the given order. The media
+    /// can be added by [`SdpApi::add_media()`] in the same changes, or already exist.
+    ///
+    /// For existing media, the preferences can narrow and reorder the codecs already
+    /// negotiated, but not bring back codecs the remote peer didn't accept. An empty
+    /// `pts` goes back to the order of the [`CodecConfig`].
+    ///
+    /// PTs not configured for the kind of media are ignored. If there are no other PTs,
+    /// or the media doesn't exist, the call is ignored.
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl")] {
+    /// # use str0m::{Rtc, media::MediaKind, media::Direction};
+    /// # use str0m::format::Codec;
+    /// let mut rtc = Rtc::new();
+    ///
+    /// let h264: Vec<_> = rtc
+    ///     .codec_config()
+    ///     .params()
+    ///     .iter()
+    ///     .filter(|p| p.spec().codec == Codec::H264)
+    ///     .map(|p| p.pt())
+    ///     .collect();
+    ///
+    /// let mut changes = rtc.sdp_api();
+    ///
+    /// let mid = changes.add_media(MediaKind::Video, Direction::SendRecv, None, None, None);
+    /// changes.set_codec_preferences(mid, &h264);
+    /// # }
+    /// ```
+    pub fn set_codec_preferences(&mut self, mid: Mid, pts: &[Pt]) {
+        let pending = self.changes.0.iter_mut().find_map(|c| match c {
+            Change::AddMedia(v) if v.mid == mid => Some(v),
+            _ => None,
+        });
+
+        let kind = match (&pending, self.rtc.media(mid)) {
+            (Some(v), _) => v.kind,
+            (None, Some(m)) => m.kind(),
+            (None, None) => {
+                warn!("Ignore codec preferences for missing mid: {}", mid);
+                return;
+            }
+        };
+
+        let config = &self.rtc.session.codec_config;
+        let mut prefs: Vec<Pt> = vec![];
+        for pt in pts {
+            if config.all_for_kind(kind).any(|p| p.pt() == *pt) && !prefs.contains(pt) {
+                prefs.push(*pt);
+            }
+        }
+
+        if !pts.is_empty() && prefs.is_empty() {
+            warn!(
+                "Ignore codec preferences without configured PTs for mid: {}",
+                mid
+            );
+            return;
+        }
+
+        if let Some(v) = pending {
+            v.prefs = prefs;
+            return;
+        }
+
+        // Unwrap is OK, since we checked the media exists above.
+        let media = self.rtc.session.media_by_mid_mut(mid).unwrap();
+        let prev = media.codec_preferences().to_vec();
+
+        if prev != prefs {
+            media.set_codec_preferences(prefs.clone());
+            self.changes
+                .0
+                .push(Change::CodecPreferences(mid, prefs, prev));
         }
     }
 
@@ -394,7 +499,7 @@ impl<'a> SdpApi<'a> {
     ///
     /// The local ICE candidates can be kept as is, or be cleared out, in which case new ice
     /// candidates must be added via [`Rtc::add_local_candidate`] before connectivity can be
-    /// re-established.
+    /// re-established. Kept candidates also keep a signalled end-of-candidates.
     ///
     /// Returns the new ICE credentials that will be used going forward.
     pub fn ice_restart(&mut self, keep_local_candidates: bool) -> IceCreds {
@@ -481,15 +586,105 @@ impl<'a> SdpApi<'a> {
     /// ```
     pub fn merge(&mut self, mut pending_offer: SdpPendingOffer) {
         pending_offer.retain_relevant(self.rtc);
+
+        // Redo what a rollback undid.
+        for c in pending_offer.changes.iter_mut() {
+            match c {
+                Change::AddChannel((id, config)) => self.rtc.chan.reissue(*id, config),
+                Change::Direction(mid, dir, prev) => {
+                    if let Some(m) = self.rtc.media(*mid) {
+                        *prev = m.direction();
+                    }
+                    self.rtc.session.set_direction(*mid, *dir);
+                }
+                Change::CodecPreferences(mid, pts, prev) => {
+                    if let Some(m) = self.rtc.session.media_by_mid_mut(*mid) {
+                        *prev = m.codec_preferences().to_vec();
+                        m.set_codec_preferences(pts.clone());
+                    }
+                }
+                _ => {}
+            }
+        }
+
         self.changes.extend(pending_offer.changes.drain(..));
     }
+
+    /// Roll back a pending [`SdpOffer`], restoring the session to how it was before
+    /// [`SdpApi::apply()`].
+    ///
+    /// This is for when the remote peer sends an offer while ours is pending, known as
+    /// glare. The channels added are dropped and changed directions are restored. Added
+    /// media and ICE restarts only take effect with the answer, so they are simply abandoned.
+    ///
+    /// The rolled back offer can no longer be used with [`SdpApi::accept_answer()`], but it
+    /// can be given to [`SdpApi::merge()`] to make the same changes in a later offer.
+    ///
+    /// This function returns an [`RtcError::ChangesOutOfOrder`] if the pending offer is not
+    /// the latest, such as when an offer from the remote was accepted after it.
+    ///
+    /// See [`PerfectNegotiation`][super::PerfectNegotiation] for a helper handling glare.
+    ///
+    /// ```no_run
+    /// # use str0m::Rtc;
+    /// # use str0m::media::{MediaKind, Direction};
+    /// # use str0m::change::SdpOffer;
+    /// let mut rtc = Rtc::new();
+    ///
+    /// let mut changes = rtc.sdp_api();
+    /// let mid = changes.add_media(MediaKind::Audio, Direction::SendOnly, None, None, None);
+    /// let (offer, pending) = changes.apply().unwrap();
+    ///
+    /// // send offer to remote peer, but receive an offer from it instead.
+    /// let remote_offer: SdpOffer = todo!();
+    ///
+    /// rtc.sdp_api().rollback(&pending).unwrap();
+    /// let answer = rtc.sdp_api().accept_offer(remote_offer).unwrap();
+    ///
+    /// // Later, offer the rolled back changes again.
+    /// let mut changes = rtc.sdp_api();
+    /// changes.merge(pending);
+    /// let (offer, pending) = changes.apply().unwrap();
+    /// ```
+    pub fn rollback(mut self, pending: &SdpPendingOffer) -> Result<(), RtcError> {
+        self.do_rollback(pending)
+    }
+
+    pub(crate) fn do_rollback(&mut self, pending: &SdpPendingOffer) -> Result<(), RtcError> {
+        debug!("Rollback offer");
+
+        if !self.rtc.is_correct_change_id(pending.change_id) {
+            return Err(RtcError::ChangesOutOfOrder);
+        }
+
+        // Invalidate the pending offer.
+        self.rtc.next_change_id();
+
+        // In reverse, so the first direction change restores the original.
+        for c in pending.changes.iter().rev() {
+            match c {
+                Change::AddChannel((id, _)) => self.rtc.chan.forget(*id),
+                Change::Direction(mid, _, prev) => {
+                    self.rtc.session.set_direction(*mid, *prev);
+                }
+                Change::CodecPreferences(mid, _, prev) => {
+                    if let Some(m) = self.rtc.session.media_by_mid_mut(*mid) {
+                        m.set_codec_preferences(prev.clone());
+                    }
+                }
+                Change::AddMedia(_) | Change::AddApp(_) | Change::IceRestart(_, _) => {}
+            }
+        }
+
+        Ok(())
+    }
 }
 
 /// Pending offer from a previous [`Rtc::sdp_api()`] call.
 ///
-/// This allows us to accept a remote answer. No changes have been made to the session
-/// before we call [`SdpApi::accept_answer()`], which means that rolling back a
-/// change is as simple as dropping this instance.
+/// This allows us to accept a remote answer. Most changes are not made to the session
+/// before we call [`SdpApi::accept_answer()`]. Direction changes and channel ids are made
+/// straight away, and [`SdpApi::rollback()`] undoes them if the offer is abandoned.
 ///
 /// ```no_run
 /// # use str0m::Rtc;
@@ -522,10 +717,14 @@ impl SdpPendingOffer {
                 Change::AddMedia(v) => rtc.media(v.mid).is_none(),
                 Change::AddApp(_) => rtc.session.app().is_none(),
                 Change::AddChannel(v) => rtc.chan.stream_id_by_channel_id(v.0).is_none(),
-                Change::Direction(m, d) => {
+                Change::Direction(m, d, _) => {
                     // If mid is missing, this is not relevant.
                     rtc.media(*m).map(|m| m.direction() != *d).unwrap_or(false)
                 }
+                Change::CodecPreferences(m, pts, _) => rtc
+                    .media(*m)
+                    .map(|m| m.codec_preferences() != pts)
+                    .unwrap_or(false),
                 Change::IceRestart(v, _) => rtc.ice.local_credentials() != v,
             }
         }
@@ -558,7 +757,10 @@ pub(crate) enum Change {
     AddMedia(AddMedia),
     AddApp(Mid),
     AddChannel((ChannelId, ChannelConfig)),
-    Direction(Mid, Direction),
+    /// The new direction and the one before it.
+    Direction(Mid, Direction, Direction),
+    /// The new codec preferences and the ones before them.
+    CodecPreferences(Mid, Vec<Pt>, Vec<Pt>),
     IceRestart(IceCreds, bool),
 }
 
@@ -571,10 +773,11 @@ pub(crate) struct AddMedia {
     pub dir: Direction,
     pub ssrcs: Vec<(Ssrc, Option<Ssrc>)>,
     pub simulcast: Option<Simulcast>,
+    /// Codec preferences, if set via SdpApi::set_codec_preferences().
+    pub prefs: Vec<Pt>,
 
     // pts and index are filled in when creating the SDP OFFER.
-    // The default PT order is set by the Session (BUNDLE).
-    // TODO: We can make this configurable here too.
+    // The default PT order is set by the Session (BUNDLE), unless there are prefs.
     pub pts: Vec<Pt>,
     pub exts: ExtensionMap,
     pub index: usize,
@@ -600,7 +803,8 @@ fn requires_negotiation(c: &Change) -> bool {
         Change::AddMedia(_) => true,
         Change::AddApp(_) => true,
         Change::AddChannel(_) => false,
-        Change::Direction(_, _) => true,
+        Change::Direction(_, _, _) => true,
+        Change::CodecPreferences(_, _, _) => true,
     }
 }
 
@@ -614,15 +818,21 @@ fn apply_direct_changes(rtc: &mut Rtc, mut changes: Changes) {
 }
 
 fn create_offer(rtc: &mut Rtc, changes: &Changes) -> SdpOffer {
-    if !rtc.dtls.is_inited() {
+    if !rtc.dtls.is_inited() && !rtc.sdes.is_keyed() {
         // The side that makes the first offer is the controlling side, unless they
         // are ICE Lite, in which case the roles are reversed (see RFC 5245).
         rtc.ice.set_controlling(!rtc.ice.ice_lite());
     }
 
     let params = AsSdpParams::new(rtc, Some(changes));
+    let uses_dtls = params.sdes.is_none();
     let sdp = as_sdp(&rtc.session, params);
 
+    if uses_dtls {
+        // Used once the answer comes back.
+        rtc.dtls.advertise_pending_cert();
+    }
+
     sdp.into()
 }
 
@@ -670,6 +880,16 @@ fn add_ice_details(
         rtc.ice.add_remote_candidate(r.clone());
     }
 
+    // A remote that doesn't do trickle ice has given us all its candidates in the SDP.
+    let remote_trickle = sdp.ice_options().contains(&"trickle");
+
+    if sdp.end_of_candidates() || !remote_trickle {
+        rtc.ice.end_of_remote_candidates();
+    }
+
+    let remote_renomination = sdp.ice_options().contains(&"renomination");
+    rtc.ice.set_remote_renomination(remote_renomination);
+
     Ok(())
 }
 
@@ -693,6 +913,74 @@ fn init_dtls(rtc: &mut Rtc, remote_sdp: &Sdp) -> Result<(), RtcError> {
     Ok(())
 }
 
+/// Take the remote a=fingerprint lines. Returns true if they replace those of an
+/// earlier negotiation, which means the remote has a new certificate.
+fn set_remote_fingerprints(rtc: &mut Rtc, remote_sdp: &Sdp) -> Result<bool, RtcError> {
+    let fingerprints = remote_sdp.fingerprints();
+
+    if fingerprints.is_empty() {
+        if rtc.remote_fingerprints.is_empty() {
+            rtc.disconnect();
+            return Err(RtcError::RemoteSdp("missing a=fingerprint".into()));
+        }
+        // Keep what we have from before.
+        return Ok(false);
+    }
+
+    if fingerprints == rtc.remote_fingerprints {
+        return Ok(false);
+    }
+
+    // The same certificate might be sent with other hash functions, in which case
+    // at least one line is unchanged.
+    let changed = !rtc.remote_fingerprints.is_empty()
+        && !fingerprints
+            .iter()
+            .any(|f| rtc.remote_fingerprints.contains(f));
+
+    rtc.remote_fingerprints = fingerprints;
+
+    Ok(changed)
+}
+
+/// Whether the remote keys SRTP with a=crypto (SDES) instead of DTLS.
+fn use_sdes(rtc: &Rtc, remote_sdp: &Sdp) -> bool {
+    rtc.sdes.is_enabled()
+        && remote_sdp.fingerprints().is_empty()
+        && !remote_sdp.sdes_crypto().is_empty()
+}
+
+fn accept_sdes_offer(rtc: &mut Rtc, offer: &Sdp) -> Result<(), RtcError> {
+    let proto = offer.rtp_proto().unwrap_or(Proto::SdesSavpf);
+
+    let Some(keys) = rtc
+        .sdes
+        .accept_offer(proto, &offer.sdes_crypto(), &offer.ssrcs())
+    else {
+        return Err(RtcError::RemoteSdp("no supported a=crypto".into()));
+    };
+
+    rtc.init_sdes(keys)
+}
+
+fn accept_sdes_answer(rtc: &mut Rtc, answer: &Sdp) -> Result<(), RtcError> {
+    let keys = rtc
+        .sdes
+        .accept_answer(&answer.sdes_crypto(), &answer.ssrcs())
+        .map_err(RtcError::RemoteSdp)?;
+
+    rtc.init_sdes(keys)
+}
+
+fn init_sctp(rtc: &mut Rtc) {
+    // With SDES-SRTP there is no DTLS to carry SCTP.
+    if let Some(client) = rtc.dtls.is_active() {
+        rtc.init_sctp(client);
+    } else {
+        warn!("Data channels need DTLS, not available with SDES-SRTP");
+    }
+}
+
 fn as_sdp(session: &Session, params: AsSdpParams) -> Sdp {
     let (media_lines, mids, stream_ids) = {
         let mut v = as_media_lines(session);
@@ -743,6 +1031,17 @@ fn as_sdp(session: &Session, params: AsSdpParams) -> Sdp {
             pending.apply_to(&mut lines);
         }
 
+        for l in lines.iter_mut().filter(|l| l.typ.is_channel()) {
+            l.attrs
+                .push(MediaAttribute::MaxMessageSize(params.max_message_size));
+        }
+
+        if let Some((proto, _)) = &params.sdes {
+            for l in lines.iter_mut().filter(|l| l.proto.is_rtp()) {
+                l.proto = *proto;
+            }
+        }
+
         // Mids go into the session part of the SDP.
         let mids = v.iter().map(|m| m.mid()).collect();
 
@@ -903,6 +1202,7 @@ fn add_pending_changes(session: &mut Session, pending: Changes) {
         // it the same once the m-line is created.
         media.set_cname(add_media.cname);
         media.set_msid(add_media.msid);
+        media.set_codec_preferences(add_media.prefs);
 
         // If there are RIDs, the SSRC order matches that of the rid order.
         let rids = add_media.simulcast.map(|x| x.send).unwrap_or(vec![]);
@@ -1040,6 +1340,10 @@ fn update_session(session: &mut Session, sdp: &Sdp) {
     if has_transport_cc && has_twcc_header {
         session.enable_twcc_feedback();
     }
+
+    // Cryptex is in the TRANSPORT category (RFC 9335), so it applies to the whole BUNDLE.
+    let cryptex = session.enable_cryptex && sdp.cryptex();
+    session.set_cryptex(cryptex);
 }
 
 /// Returns all media/channels as `AsMediaLine` trait.
@@ -1212,7 +1516,6 @@ impl AsSdpMediaLine for (Mid, usize) {
     ) -> MediaLine {
         attrs.push(MediaAttribute::Mid(self.0));
         attrs.push(MediaAttribute::SctpPort(5000));
-        attrs.push(MediaAttribute::MaxMessageSize(262144));
 
         MediaLine {
             typ: sdp::MediaType::Application,
@@ -1264,10 +1567,10 @@ impl AsSdpMediaLine for Media {
         attrs.push(MediaAttribute::Msid(self.msid().clone()));
         attrs.push(MediaAttribute::RtcpMux);
 
-        // The effective params start from the Session::codec_config to retain the
-        // user's configured preferred order, however they are narrowed only include
-        // those the remote peer wants.
-        let effective_params = params.iter().filter(|p| self.remote_pts().contains(&p.pt));
+        // The effective params start from the codec preferences, or the Session::codec_config,
+        // to retain the user's configured preferred order, however they are narrowed only
+        // include those the remote peer wants.
+        let effective_params = self.effective_params(params);
 
         let mut pts = vec![];
 
@@ -1354,48 +1657,77 @@ impl From<MediaKind> for MediaType {
     }
 }
 
-struct AsSdpParams<'a, 'b> {
+struct AsSdpParams<'b> {
     pub candidates: Vec<Candidate>,
+    pub end_of_candidates: bool,
+    pub renomination: bool,
     pub creds: IceCreds,
-    pub fingerprint: &'a Fingerprint,
+    pub fingerprints: Vec<Fingerprint>,
     pub setup: Setup,
+    pub cryptex: bool,
+    /// Proto and a=crypto lines, when keying SRTP with SDES.
+    pub sdes: Option<(Proto, Vec<SdesCrypto>)>,
+    /// Our a=max-message-size for the m=application line.
+    pub max_message_size: usize,
     pub pending: Option<&'b Changes>,
 }
 
-impl<'a, 'b> AsSdpParams<'a, 'b> {
-    pub fn new(rtc: &'a Rtc, pending: Option<&'b Changes>) -> Self {
-        let (creds, candidates) = if let Some((new_creds, keep_local_candidates)) =
-            pending.and_then(|p| p.ice_restart())
-        {
-            if keep_local_candidates {
-                // If we are performing an ICE restart and we are keeping the same
-                // candidates we need to use ufrag from the new ICE credentials
-                // in our offer.
-                let mut new_candidates = rtc.ice.local_candidates().to_vec();
-                for c in &mut new_candidates {
-                    c.set_ufrag(&new_creds.ufrag);
-                }
+impl<'b> AsSdpParams<'b> {
+    pub fn new(rtc: &Rtc, pending: Option<&'b Changes>) -> Self {
+        // An ICE restart starts a new round of gathering.
+        let (creds, candidates, end_of_candidates) =
+            if let Some((new_creds, keep_local_candidates)) = pending.and_then(|p| p.ice_restart())
+            {
+                if keep_local_candidates {
+                    // If we are performing an ICE restart and we are keeping the same
+                    // candidates we need to use ufrag from the new ICE credentials
+                    // in our offer.
+                    let mut new_candidates = rtc.ice.local_candidates().to_vec();
+                    for c in &mut new_candidates {
+                        c.set_ufrag(&new_creds.ufrag);
+                    }
 
-                (new_creds, new_candidates)
+                    (
+                        new_creds,
+                        new_candidates,
+                        rtc.ice.is_end_of_local_candidates(),
+                    )
+                } else {
+                    (new_creds, vec![], false)
+                }
             } else {
-                (new_creds, vec![])
-            }
-        } else {
-            (
-                rtc.ice.local_credentials().clone(),
-                rtc.ice.local_candidates().to_vec(),
-            )
-        };
+                (
+                    rtc.ice.local_credentials().clone(),
+                    rtc.ice.local_candidates().to_vec(),
+                    rtc.ice.is_end_of_local_candidates(),
+                )
+            };
 
         AsSdpParams {
             candidates,
+            end_of_candidates,
+            renomination: rtc.ice.renomination(),
             creds,
-            fingerprint: rtc.dtls.local_fingerprint(),
+            fingerprints: rtc.dtls.sdp_fingerprints(),
             setup: match rtc.dtls.is_active() {
                 Some(true) => Setup::Active,
                 Some(false) => Setup::Passive,
                 None => Setup::ActPass,
             },
+            // Offers signal what we support, answers what was negotiated.
+            cryptex: if pending.is_some() {
+                rtc.session.enable_cryptex
+            } else {
+                rtc.session.cryptex()
+            },
+            // Offer SDES unless DTLS is already in use.
+            sdes: if pending.is_some() {
+                (rtc.sdes.is_enabled() && !rtc.dtls.is_inited())
+                    .then(|| (Proto::SdesSavpf, rtc.sdes.offer_lines()))
+            } else {
+                rtc.sdes.answer_line().map(|(p, c)| (*p, vec![c.clone()]))
+            },
+            max_message_size: rtc.sctp.local_max_message_size(),
             pending,
         }
     }
@@ -1412,11 +1744,27 @@ impl<'a, 'b> AsSdpParams<'a, 'b> {
             vec![]
         };
 
+        if include_candidates && self.end_of_candidates {
+            v.push(EndOfCandidates);
+        }
+
         v.push(IceUfrag(self.creds.ufrag.clone()));
         v.push(IcePwd(self.creds.pass.clone()));
-        v.push(IceOptions("trickle".into()));
-        v.push(Fingerprint(self.fingerprint.clone()));
-        v.push(Setup(self.setup));
+        if self.renomination {
+            v.push(IceOptions("trickle renomination".into()));
+        } else {
+            v.push(IceOptions("trickle".into()));
+        }
+        if let Some((_, crypto)) = &self.sdes {
+            v.extend(crypto.iter().cloned().map(Crypto));
+        } else {
+            v.extend(self.fingerprints.iter().cloned().map(Fingerprint));
+            v.push(Setup(self.setup));
+        }
+
+        if self.cryptex {
+            v.push(Cryptex);
+        }
 
         v
     }
@@ -1521,7 +1869,7 @@ impl Changes {
 
     pub(crate) fn apply_to(&self, lines: &mut [MediaLine]) {
         for change in &self.0 {
-            if let Change::Direction(mid, dir) = change {
+            if let Change::Direction(mid, dir, _) = change {
                 if let Some(line) = lines.iter_mut().find(|l| l.mid() == *mid) {
                     if let Some(dir_pos) = line.attrs.iter().position(|a| a.is_direction()) {
                         line.attrs[dir_pos] = (*dir).into();
@@ -1564,7 +1912,11 @@ impl Change {
             AddMedia(v) => {
                 // TODO can we avoid all this cloning?
                 let mut add = v.clone();
-                add.pts = config.all_for_kind(v.kind).map(|p| p.pt()).collect();
+                add.pts = if v.prefs.is_empty() {
+                    config.all_for_kind(v.kind).map(|p| p.pt()).collect()
+                } else {
+                    v.prefs.clone()
+                };
                 add.exts = exts.cloned_with_type(v.kind.is_audio());
                 add.index = index;
 
@@ -1621,6 +1973,69 @@ mod test {
         assert!(matches!(r, Err(RtcError::ChangesOutOfOrder)));
     }
 
+    #[test]
+    fn end_of_candidates_in_offer() {
+        crate::init_crypto_default();
+
+        let mut rtc1 = Rtc::new();
+        let mut rtc2 = Rtc::new();
+
+        rtc1.add_local_candidate(Candidate::host("1.1.1.1:1000".parse().unwrap(), "udp").unwrap());
+        rtc1.end_of_local_candidates();
+
+        let mut change = rtc1.sdp_api();
+        change.add_channel("ch1".into());
+        let (offer, pending) = change.apply().unwrap();
+
+        assert!(offer.end_of_candidates());
+        assert_eq!(offer.ice_options(), vec!["trickle"]);
+
+        let answer = rtc2.sdp_api().accept_offer(offer).unwrap();
+        rtc1.sdp_api().accept_answer(pending, answer).unwrap();
+
+        assert!(rtc2.ice.is_end_of_remote_candidates());
+        assert!(!rtc2.ice.is_end_of_local_candidates());
+
+        // An ICE restart keeping the candidates keeps them complete.
+        let mut change = rtc1.sdp_api();
+        change.ice_restart(true);
+        let (offer, _) = change.apply().unwrap();
+        assert!(offer.end_of_candidates());
+
+        let mut change = rtc1.sdp_api();
+        change.ice_restart(false);
+        let (offer, _) = change.apply().unwrap();
+        assert!(!offer.end_of_candidates());
+    }
+
+    #[test]
+    fn renomination_negotiated() {
+        crate::init_crypto_default();
+
+        let mut rtc1 = Rtc::builder().set_ice_renomination(true).build();
+        let mut rtc2 = Rtc::builder().set_ice_renomination(true).build();
+
+        let mut change = rtc1.sdp_api();
+        change.add_channel("ch1".into());
+        let (offer, pending) = change.apply().unwrap();
+
+        assert_eq!(offer.ice_options(), vec!["trickle", "renomination"]);
+
+        let answer = rtc2.sdp_api().accept_offer(offer).unwrap();
+        rtc1.sdp_api().accept_answer(pending, answer).unwrap();
+
+        assert!(rtc1.ice.uses_renomination());
+        assert!(rtc2.ice.uses_renomination());
+
+        let mut rtc3 = Rtc::new();
+        let mut change = rtc3.sdp_api();
+        change.add_channel("ch1".into());
+        let (offer, _) = change.apply().unwrap();
+        rtc1.sdp_api().accept_offer(offer).unwrap();
+
+        assert!(!rtc1.ice.uses_renomination());
+    }
+
     #[test]
     fn sdp_api_merge_works() {
         crate::init_crypto_default();
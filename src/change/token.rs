@@ -0,0 +1,224 @@
+use std::fmt;
+use std::net::SocketAddr;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::crypto::Fingerprint;
+use crate::ice_::{Candidate, CandidateKind, IceCreds};
+use crate::io::Protocol;
+use crate::util::base64;
+
+/// Version of the token format.
+const VERSION: &str = "1";
+
+/// Everything a peer needs to set up a data channel only session, without SDP.
+///
+/// Each peer sends its token to the other by whatever signalling there is, and starts
+/// the session with [`DirectApi::start_data_session()`][crate::change::DirectApi::start_data_session()].
+///
+/// The token is serialized as a compact string with [`to_string()`][ToString::to_string] and parsed
+/// with [`str::parse()`]. It is comma separated and contains nothing but ASCII:
+///
+/// ```text
+/// 1,<ice ufrag>,<ice pwd>,<hash func>,<fingerprint base64>,<a|p>[,<candidate>]*
+/// ```
+///
+/// where each candidate is a kind (`h`ost, `s`erver reflexive or `r`elayed) and a
+/// protocol (`u`dp, `t`cp, `s`sltcp or t`l`s), followed by the socket address, like
+/// `hu192.168.0.2:5000`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataToken {
+    /// ICE credentials of the peer.
+    pub ice_creds: IceCreds,
+
+    /// DTLS fingerprint of the peer.
+    pub fingerprint: Fingerprint,
+
+    /// Whether the peer is the DTLS client.
+    ///
+    /// The active peer is also ICE controlling and SCTP client. The peers must
+    /// pick opposite roles.
+    pub active: bool,
+
+    /// ICE candidates of the peer.
+    pub candidates: Vec<Candidate>,
+}
+
+/// Errors when parsing a [`DataToken`].
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum DataTokenError {
+    /// The token is not of a version we know.
+    #[error("Unsupported data token version: {0}")]
+    Version(String),
+
+    /// A field is missing.
+    #[error("Data token is missing {0}")]
+    Missing(&'static str),
+
+    /// A field could not be parsed.
+    #[error("Bad data token {0}: {1}")]
+    Bad(&'static str, String),
+}
+
+impl fmt::Display for DataToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{},{},{},{},{},{}",
+            VERSION,
+            self.ice_creds.ufrag,
+            self.ice_creds.pass,
+            self.fingerprint.hash_func,
+            base64::encode(&self.fingerprint.bytes),
+            if self.active { "a" } else { "p" }
+        )?;
+
+        for c in &self.candidates {
+            let kind = match c.kind() {
+                CandidateKind::Host => 'h',
+                CandidateKind::ServerReflexive => 's',
+                CandidateKind::Relayed => 'r',
+                // Only known once the peers talk to each other.
+                CandidateKind::PeerReflexive => continue,
+            };
+            let proto = match c.proto() {
+                Protocol::Udp => 'u',
+                Protocol::Tcp => 't',
+                Protocol::SslTcp => 's',
+                Protocol::Tls => 'l',
+            };
+            write!(f, ",{}{}{}", kind, proto, c.addr())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for DataToken {
+    type Err = DataTokenError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().split(',');
+
+        let version = parts.next().ok_or(DataTokenError::Missing("version"))?;
+        if version != VERSION {
+            return Err(DataTokenError::Version(version.to_string()));
+        }
+
+        let mut next = |name: &'static str| {
+            parts
+                .next()
+                .filter(|p| !p.is_empty())
+                .ok_or(DataTokenError::Missing(name))
+        };
+
+        let ufrag = next("ice ufrag")?.to_string();
+        let pass = next("ice pwd")?.to_string();
+        let hash_func = next("hash func")?.to_string();
+
+        let fingerprint = next("fingerprint")?;
+        let bytes = base64::decode(fingerprint)
+            .ok_or_else(|| DataTokenError::Bad("fingerprint", fingerprint.to_string()))?;
+
+        let active = match next("role")? {
+            "a" => true,
+            "p" => false,
+            v => return Err(DataTokenError::Bad("role", v.to_string())),
+        };
+
+        let candidates = parts.map(parse_candidate).collect::<Result<Vec<_>, _>>()?;
+
+        Ok(DataToken {
+            ice_creds: IceCreds { ufrag, pass },
+            fingerprint: Fingerprint { hash_func, bytes },
+            active,
+            candidates,
+        })
+    }
+}
+
+fn parse_candidate(s: &str) -> Result<Candidate, DataTokenError> {
+    let bad = || DataTokenError::Bad("candidate", s.to_string());
+
+    let mut chars = s.chars();
+    let kind = chars.next().ok_or_else(bad)?;
+    let proto = match chars.next().ok_or_else(bad)? {
+        'u' => Protocol::Udp,
+        't' => Protocol::Tcp,
+        's' => Protocol::SslTcp,
+        'l' => Protocol::Tls,
+        _ => return Err(bad()),
+    };
+    let addr: SocketAddr = chars.as_str().parse().map_err(|_| bad())?;
+
+    let candidate = match kind {
+        'h' => Candidate::host(addr, proto),
+        // The base is not used for remote candidates.
+        's' => Candidate::server_reflexive(addr, addr, proto),
+        'r' => Candidate::relayed(addr, proto),
+        _ => return Err(bad()),
+    };
+
+    candidate.map_err(|_| bad())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let token = DataToken {
+            ice_creds: IceCreds {
+                ufrag: "abcd".into(),
+                pass: "0123456789abcdef012345".into(),
+            },
+            fingerprint: Fingerprint {
+                hash_func: "sha-256".into(),
+                bytes: (0..32).collect(),
+            },
+            active: true,
+            candidates: vec![
+                Candidate::host("192.168.0.2:5000".parse().unwrap(), "udp").unwrap(),
+                Candidate::server_reflexive(
+                    "[2001:db8::1]:6000".parse().unwrap(),
+                    "[2001:db8::1]:6000".parse().unwrap(),
+                    "tcp",
+                )
+                .unwrap(),
+            ],
+        };
+
+        let s = token.to_string();
+        assert_eq!(
+            s,
+            "1,abcd,0123456789abcdef012345,sha-256,\
+            AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=,a,\
+            hu192.168.0.2:5000,st[2001:db8::1]:6000"
+        );
+
+        let parsed: DataToken = s.parse().unwrap();
+        assert_eq!(parsed, token);
+    }
+
+    #[test]
+    fn bad_tokens() {
+        assert_eq!(
+            "2,a".parse::<DataToken>(),
+            Err(DataTokenError::Version("2".into()))
+        );
+        assert_eq!(
+            "1,abcd".parse::<DataToken>(),
+            Err(DataTokenError::Missing("ice pwd"))
+        );
+        assert_eq!(
+            "1,abcd,pwd,sha-256,AAEC,x".parse::<DataToken>(),
+            Err(DataTokenError::Bad("role", "x".into()))
+        );
+        assert_eq!(
+            "1,abcd,pwd,sha-256,AAEC,p,hx1.2.3.4:5".parse::<DataToken>(),
+            Err(DataTokenError::Bad("candidate", "hx1.2.3.4:5".into()))
+        );
+    }
+}
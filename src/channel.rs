@@ -3,11 +3,13 @@
 use std::{fmt, str, time::Instant};
 
 use crate::sctp::RtcSctp;
+use crate::stats::{ChannelStats, StatsSnapshot};
 use crate::util::already_happened;
 use crate::{Rtc, RtcError};
 
 pub use crate::sctp::ChannelConfig;
 pub use crate::sctp::Reliability;
+pub use crate::sctp::WriteOptions;
 
 /// Identifier of a data channel.
 ///
@@ -16,6 +18,19 @@ pub use crate::sctp::Reliability;
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct ChannelId(usize);
 
+/// State of a data channel, like `RTCDataChannelState`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelState {
+    /// Waiting for the SCTP stream to open, or for the DCEP ack.
+    Connecting,
+    /// Ready to send and receive data.
+    Open,
+    /// The channel is being closed.
+    Closing,
+    /// The channel is closed.
+    Closed,
+}
+
 /// Data channel data from remote peer.
 ///
 /// This is obtained via [`Event::ChannelData`][crate::Event::ChannelData].
@@ -34,6 +49,38 @@ pub struct ChannelData {
     pub data: Vec<u8>,
 }
 
+/// A piece of a data channel message from the remote peer.
+///
+/// This is obtained via [`Event::ChannelChunk`][crate::Event::ChannelChunk] for channels
+/// that [receive in chunks][Channel::set_chunked_receive()]. The chunks of a message
+/// come in order as the message arrives, without chunks of other messages on the same
+/// channel in between.
+#[derive(PartialEq, Eq)]
+pub struct ChannelChunk {
+    /// Identifier of the channel this data was sent on.
+    pub id: ChannelId,
+
+    /// Tells whether the sender sent this data as binary or text.
+    pub binary: bool,
+
+    /// This is the first chunk of the message.
+    pub start: bool,
+
+    /// This is the last chunk of the message.
+    ///
+    /// An empty message is a single chunk that is both start and end.
+    pub end: bool,
+
+    /// The rest of the message will not arrive.
+    ///
+    /// The sender gave up on it (partial reliability), the channel closed, or the message
+    /// grew over the max message size. Only set on the end chunk, which is empty.
+    pub abandoned: bool,
+
+    /// The data of this chunk, up to 16kB.
+    pub data: Vec<u8>,
+}
+
 /// Channel for sending data to the remote peer.
 ///
 /// Get this handle from [`Rtc::channel()`][crate::Rtc::channel()].
@@ -51,9 +98,237 @@ impl<'a> Channel<'a> {
     }
 
     /// Write data to the remote peer and indicate whether it's text or binary.
+    ///
+    /// Fails with [`SctpError::WouldBlock`][crate::error::SctpError::WouldBlock] if a
+    /// [high threshold][Channel::set_buffered_amount_high_threshold()] is set and the
+    /// write would take the buffered amount over it.
     pub fn write(&mut self, binary: bool, buf: &[u8]) -> Result<usize, RtcError> {
         Ok(self.rtc.sctp.write(self.sctp_stream_id, binary, buf)?)
     }
+
+    /// Write data to the remote peer with options for this message only.
+    ///
+    /// The options can override the ordering and reliability of the channel, and put
+    /// the message ahead of other messages waiting to be sent on the channel.
+    ///
+    /// ```no_run
+    /// # use str0m::{Rtc, channel::{ChannelId, Reliability, WriteOptions}};
+    /// # let mut rtc = Rtc::new();
+    /// # let cid: ChannelId = todo!();
+    /// let mut channel = rtc.channel(cid).unwrap();
+    ///
+    /// // A position update that is useless if it arrives late.
+    /// let options = WriteOptions {
+    ///     binary: true,
+    ///     ordered: Some(false),
+    ///     reliability: Some(Reliability::MaxRetransmits { retransmits: 0 }),
+    ///     priority: 1,
+    /// };
+    ///
+    /// channel.write_with(&[1, 2, 3], options).unwrap();
+    /// ```
+    pub fn write_with(&mut self, buf: &[u8], options: WriteOptions) -> Result<usize, RtcError> {
+        Ok(self
+            .rtc
+            .sctp
+            .write_with(self.sctp_stream_id, buf, options)?)
+    }
+
+    /// Begin a message that is written in pieces with [`Channel::append()`].
+    ///
+    /// Each append is handed to SCTP as it comes, so the message is never held whole in
+    /// memory, and the remote peer can receive the start before the end is written. The
+    /// message ends with [`Channel::finish_message()`]. Other writes to the channel in
+    /// between are sent after it. Only one message can be in progress per channel.
+    ///
+    /// The message must be reliable, and is limited to [`Channel::max_message_size()`]
+    /// like any other write. Unless the remote peer supports SCTP message interleaving
+    /// (I-DATA), other channels wait for the message to be finished.
+    ///
+    /// Fails with [`SctpError::MessageNotReliable`][crate::error::SctpError::MessageNotReliable]
+    /// if the options, or the channel, are not reliable.
+    ///
+    /// ```no_run
+    /// # use str0m::{Rtc, channel::{ChannelId, WriteOptions}};
+    /// # let mut rtc = Rtc::new();
+    /// # let cid: ChannelId = todo!();
+    /// # let file_chunks: Vec<Vec<u8>> = vec![];
+    /// let mut channel = rtc.channel(cid).unwrap();
+    ///
+    /// channel.begin_message(WriteOptions { binary: true, ..Default::default() }).unwrap();
+    ///
+    /// for chunk in file_chunks {
+    ///     channel.append(&chunk).unwrap();
+    /// }
+    ///
+    /// channel.finish_message().unwrap();
+    /// ```
+    pub fn begin_message(&mut self, options: WriteOptions) -> Result<(), RtcError> {
+        Ok(self.rtc.sctp.begin_message(self.sctp_stream_id, options)?)
+    }
+
+    /// Append data to the message begun with [`Channel::begin_message()`].
+    ///
+    /// Fails like [`Channel::write()`], so a high threshold holds back appends until the
+    /// data is sent. If the message would grow over [`Channel::max_message_size()`], this
+    /// fails with [`SctpError::MessageTooLarge`][crate::error::SctpError::MessageTooLarge]
+    /// and the message is kept as it was.
+    pub fn append(&mut self, buf: &[u8]) -> Result<usize, RtcError> {
+        Ok(self.rtc.sctp.append_message(self.sctp_stream_id, buf)?)
+    }
+
+    /// Finish the message begun with [`Channel::begin_message()`].
+    ///
+    /// Returns the size of the message.
+    pub fn finish_message(&mut self) -> Result<usize, RtcError> {
+        Ok(self.rtc.sctp.finish_message(self.sctp_stream_id)?)
+    }
+
+    /// Discard the message begun with [`Channel::begin_message()`], if any.
+    ///
+    /// SCTP can't take back what is sent. If some of the message is, the channel is
+    /// aborted like [`Channel::abort()`], which is the only way to tell the remote peer.
+    pub fn abort_message(&mut self) {
+        self.rtc.sctp.abort_message(self.sctp_stream_id);
+    }
+
+    /// Whether incoming messages are delivered in chunks.
+    ///
+    /// Defaults to `false`.
+    pub fn chunked_receive(&self) -> bool {
+        self.rtc.sctp.chunked_receive(self.sctp_stream_id)
+    }
+
+    /// Deliver incoming messages as [`Event::ChannelChunk`][crate::Event::ChannelChunk]
+    /// instead of [`Event::ChannelData`][crate::Event::ChannelData].
+    ///
+    /// Each message is handed out in chunks of at most 16kB as it arrives, with markers
+    /// for the start and end of the message, so it is never held whole in memory. The
+    /// size is still limited by
+    /// [`RtcConfig::set_sctp_max_message_size()`][crate::RtcConfig::set_sctp_max_message_size],
+    /// checked as the message grows.
+    pub fn set_chunked_receive(&mut self, chunked: bool) {
+        self.rtc
+            .sctp
+            .set_chunked_receive(self.sctp_stream_id, chunked);
+    }
+
+    /// Current state of the channel.
+    ///
+    /// The channel handle is only available when the channel is open or closing.
+    /// [`ChannelStats`] covers all states.
+    pub fn state(&self) -> ChannelState {
+        self.rtc
+            .sctp
+            .channel_state(self.sctp_stream_id)
+            .unwrap_or(ChannelState::Closed)
+    }
+
+    /// Close the channel, after sending the messages already written.
+    ///
+    /// The channel goes to [`ChannelState::Closing`], where writes are refused. Once the
+    /// queued messages are sent, the outgoing SCTP stream is reset (RFC 8831 6.7). The
+    /// channel is closed with [`Event::ChannelClose`][crate::Event::ChannelClose] when the
+    /// remote peer has reset its side too. A message begun with
+    /// [`Channel::begin_message()`] and not finished is discarded, and if some of it is
+    /// sent, the channel is aborted like [`Channel::abort()`].
+    pub fn close(&mut self) {
+        self.rtc.sctp.close_stream(self.sctp_stream_id, false);
+    }
+
+    /// Close the channel without sending the messages waiting in the queue.
+    ///
+    /// Like [`Channel::close()`], but discards messages that are not yet handed to SCTP.
+    pub fn abort(&mut self) {
+        self.rtc.sctp.close_stream(self.sctp_stream_id, true);
+    }
+
+    /// Largest message the remote peer accepts.
+    ///
+    /// This is the `a=max-message-size` of the remote SDP. A larger [`Channel::write()`]
+    /// fails with [`SctpError::MessageTooLarge`][crate::error::SctpError::MessageTooLarge].
+    pub fn max_message_size(&self) -> usize {
+        self.rtc.sctp.remote_max_message_size()
+    }
+
+    /// Number of bytes written, but not yet acknowledged by the remote peer.
+    ///
+    /// This includes messages waiting for their turn to be sent.
+    pub fn buffered_amount(&mut self) -> usize {
+        self.rtc
+            .sctp
+            .buffered_amount(self.sctp_stream_id)
+            .unwrap_or_default()
+    }
+
+    /// Threshold for [`Event::ChannelBufferedAmountLow`][crate::Event::ChannelBufferedAmountLow].
+    ///
+    /// Defaults to 0.
+    pub fn buffered_amount_low_threshold(&self) -> usize {
+        self.rtc
+            .sctp
+            .buffered_amount_low_threshold(self.sctp_stream_id)
+    }
+
+    /// Set the threshold for [`Event::ChannelBufferedAmountLow`][crate::Event::ChannelBufferedAmountLow].
+    ///
+    /// The event is emitted when the buffered amount goes from above the threshold to
+    /// the threshold or below, like `bufferedamountlow` of `RTCDataChannel`.
+    pub fn set_buffered_amount_low_threshold(&mut self, threshold: usize) {
+        self.rtc
+            .sctp
+            .set_buffered_amount_low_threshold(self.sctp_stream_id, threshold);
+    }
+
+    /// Threshold above which [`Channel::write()`] refuses more data.
+    ///
+    /// Defaults to `None`, which means no limit.
+    pub fn buffered_amount_high_threshold(&self) -> Option<usize> {
+        self.rtc
+            .sctp
+            .buffered_amount_high_threshold(self.sctp_stream_id)
+    }
+
+    /// Set a threshold above which [`Channel::write()`] refuses more data.
+    ///
+    /// A write that would take the buffered amount over the threshold fails with
+    /// [`SctpError::WouldBlock`][crate::error::SctpError::WouldBlock]. A write to an
+    /// empty buffer is always accepted, no matter the size.
+    ///
+    /// ```no_run
+    /// # use str0m::{Rtc, Event, RtcError, channel::ChannelId};
+    /// # use str0m::error::SctpError;
+    /// # let mut rtc = Rtc::new();
+    /// # let cid: ChannelId = todo!();
+    /// let mut channel = rtc.channel(cid).unwrap();
+    /// channel.set_buffered_amount_high_threshold(Some(1024 * 1024));
+    /// channel.set_buffered_amount_low_threshold(256 * 1024);
+    ///
+    /// match channel.write(true, &[0; 16_384]) {
+    ///     Ok(_) => {}
+    ///     // Wait for Event::ChannelBufferedAmountLow before writing more.
+    ///     Err(RtcError::Sctp(SctpError::WouldBlock)) => {}
+    ///     Err(e) => panic!("{}", e),
+    /// }
+    /// ```
+    pub fn set_buffered_amount_high_threshold(&mut self, threshold: Option<usize>) {
+        self.rtc
+            .sctp
+            .set_buffered_amount_high_threshold(self.sctp_stream_id, threshold);
+    }
+}
+
+impl fmt::Debug for ChannelChunk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChannelChunk")
+            .field("id", &self.id)
+            .field("binary", &self.binary)
+            .field("start", &self.start)
+            .field("end", &self.end)
+            .field("abandoned", &self.abandoned)
+            .field("data", &self.data.len())
+            .finish()
+    }
 }
 
 impl fmt::Debug for ChannelData {
@@ -154,6 +429,27 @@ impl ChannelHandler {
         a.config = Some(config);
     }
 
+    /// Drop a channel id that was never confirmed, such as when an offer is rolled back.
+    pub fn forget(&mut self, id: ChannelId) {
+        debug!("Forget channel id: {:?}", id);
+        self.allocations
+            .retain(|a| a.id != id || a.config.is_some());
+    }
+
+    /// Issue a forgotten channel id again, to confirm it later.
+    pub fn reissue(&mut self, id: ChannelId, config: &ChannelConfig) {
+        if self.allocations.iter().any(|a| a.id == id) {
+            return;
+        }
+
+        debug!("Reissue channel id: {:?}", id);
+        self.allocations.push(ChannelAllocation {
+            id,
+            sctp_stream_id: config.negotiated,
+            config: None,
+        });
+    }
+
     /// For translating sctp stream id to ChannelId. Any event out of sctp goes via this.
     pub fn channel_id_by_stream_id(&self, sctp_stream_id: u16) -> Option<ChannelId> {
         self.allocations
@@ -274,20 +570,47 @@ impl ChannelHandler {
         }
     }
 
-    pub fn close_channel(&mut self, id: ChannelId, sctp: &mut RtcSctp) {
+    pub fn close_channel(&mut self, id: ChannelId, sctp: &mut RtcSctp, abort: bool) {
         if let Some(sctp_stream_id) = self
             .allocations
             .iter()
             .find(|a| a.id == id)
             .and_then(|s| s.sctp_stream_id)
         {
-            sctp.close_stream(sctp_stream_id);
+            sctp.close_stream(sctp_stream_id, abort);
         }
     }
 
     pub fn remove_channel(&mut self, id: ChannelId) {
         self.allocations.retain(|a| a.id != id)
     }
+
+    pub(crate) fn visit_stats(&self, sctp: &mut RtcSctp, snapshot: &mut StatsSnapshot) {
+        for a in &self.allocations {
+            let Some(sctp_stream_id) = a.sctp_stream_id else {
+                continue;
+            };
+
+            let buffered_amount = sctp.buffered_amount(sctp_stream_id).unwrap_or_default();
+
+            let Some((label, protocol, state, counters)) = sctp.stream_stats(sctp_stream_id) else {
+                continue;
+            };
+
+            snapshot.channels.push(ChannelStats {
+                id: a.id,
+                label: label.to_string(),
+                protocol: protocol.to_string(),
+                state,
+                messages_sent: counters.messages_sent,
+                bytes_sent: counters.bytes_sent,
+                messages_received: counters.messages_received,
+                bytes_received: counters.bytes_received,
+                buffered_amount,
+                timestamp: snapshot.timestamp(),
+            });
+        }
+    }
 }
 
 #[cfg(test)]
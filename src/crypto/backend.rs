@@ -0,0 +1,352 @@
+//! Public traits for supplying a custom crypto implementation.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::time::Instant;
+
+use crate::net::DatagramSend;
+
+use super::dtls::dtls13_unsupported;
+use super::srtp::{aead_aes_128_gcm, aes_128_cm_sha1_80};
+use super::srtp::{aead_aes_256_gcm, aes_256_cm_sha1_80};
+use super::{CryptoError, Dtls13Mode, DtlsCertOptions, DtlsEvent, Fingerprint, SrtpProfile};
+
+/// A user supplied crypto implementation.
+///
+/// Install using [`CryptoProvider::Custom`][super::CryptoProvider::Custom]. This makes it
+/// possible to use a crypto library that str0m doesn't support out of the box, such
+/// as a FIPS validated module, without forking str0m.
+pub trait CryptoBackend: fmt::Debug + Send + Sync + RefUnwindSafe {
+    /// Create a new (self signed) DTLS certificate.
+    fn create_dtls_cert(&self, options: DtlsCertOptions) -> Box<dyn DtlsCertBackend>;
+
+    /// Create the factory for SRTP cipher contexts.
+    fn srtp_crypto(&self) -> Box<dyn SrtpCryptoBackend>;
+}
+
+/// A DTLS certificate from a [`CryptoBackend`].
+pub trait DtlsCertBackend: fmt::Debug + Send + Sync + RefUnwindSafe {
+    /// The fingerprint of the certificate.
+    ///
+    /// This is sent via SDP to the other peer to lock down the DTLS
+    /// to this specific certificate.
+    fn fingerprint(&self) -> Fingerprint;
+
+    /// The DER encoded certificate.
+    ///
+    /// This is used for fingerprints with other hash functions than `sha-256`. The
+    /// default returns `None`, which limits the certificate to `sha-256`.
+    fn certificate_der(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Create a new DTLS instance using this certificate.
+    fn create_dtls(&self) -> Result<Box<dyn DtlsBackend>, CryptoError>;
+}
+
+/// The DTLS handshake and record layer of a [`CryptoBackend`].
+///
+/// The instance is Sans-IO, incoming datagrams are given to
+/// [`DtlsBackend::handle_receive()`] and outgoing are polled from
+/// [`DtlsBackend::poll_datagram()`].
+///
+/// Once the handshake completes, the implementation must emit [`DtlsEvent::Connected`],
+/// [`DtlsEvent::RemoteFingerprint`] and [`DtlsEvent::SrtpKeyingMaterial`] in that order.
+/// The optional [`DtlsEvent::RemoteCertificate`] goes right before the fingerprint.
+/// The keying material is exported with the label `EXTRACTOR-dtls_srtp` (RFC 5764) and
+/// must be [`SrtpProfile::keying_material_len()`][super::SrtpProfile::keying_material_len] long for the
+/// negotiated profile. Any [`DtlsEvent::KeyLog`] follow after those.
+///
+/// A `close_notify` or fatal alert from the connected remote peer is emitted as
+/// [`DtlsEvent::Alert`].
+pub trait DtlsBackend: Send + Sync + UnwindSafe {
+    /// Set whether this instance is active or passive.
+    ///
+    /// i.e. initiating the client hello or not. This is called
+    /// exactly once before starting to handshake (I/O).
+    fn set_active(&mut self, active: bool);
+
+    /// Set which DTLS versions to negotiate. This is called before [`DtlsBackend::set_active()`].
+    ///
+    /// The default only accepts DTLS 1.2, i.e. fails for [`Dtls13Mode::Require`].
+    fn set_dtls13(&mut self, mode: Dtls13Mode) -> Result<(), CryptoError> {
+        dtls13_unsupported(mode)
+    }
+
+    /// Set the SRTP profiles to offer in `use_srtp`, most preferred first. This is called
+    /// before [`DtlsBackend::set_active()`].
+    ///
+    /// The default ignores the preference and offers whatever the backend supports.
+    fn set_srtp_profiles(&mut self, profiles: &[SrtpProfile]) -> Result<(), CryptoError> {
+        let _ = profiles;
+        Ok(())
+    }
+
+    /// Enable emitting [`DtlsEvent::KeyLog`] with the handshake secrets. This is called
+    /// before [`DtlsBackend::set_active()`], and only when enabled.
+    ///
+    /// The default ignores this, i.e. the backend doesn't support key logging.
+    fn set_key_log(&mut self, enabled: bool) {
+        let _ = enabled;
+    }
+
+    /// If set_active, returns what was set.
+    fn is_active(&self) -> Option<bool>;
+
+    /// Drive the handshake. Returns `true` as long as the handshake is ongoing.
+    fn handle_handshake(&mut self, o: &mut VecDeque<DtlsEvent>) -> Result<bool, CryptoError>;
+
+    /// Handles an incoming DTLS datagram.
+    ///
+    /// Decrypted application data is emitted as [`DtlsEvent::Data`].
+    fn handle_receive(&mut self, m: &[u8], o: &mut VecDeque<DtlsEvent>) -> Result<(), CryptoError>;
+
+    /// Poll for the next datagram to send.
+    fn poll_datagram(&mut self) -> Option<DatagramSend>;
+
+    /// Poll for next timeout. This is only used during DTLS handshake.
+    fn poll_timeout(&mut self, now: Instant) -> Option<Instant>;
+
+    /// Handle a timeout, such as retransmitting a handshake flight.
+    fn handle_timeout(&mut self, now: Instant) {
+        let _ = now;
+    }
+
+    /// Handle outgoing application data to be sent as DTLS records.
+    ///
+    /// Should fail with [`std::io::ErrorKind::WouldBlock`] while handshaking.
+    fn handle_input(&mut self, data: &[u8]) -> Result<(), CryptoError>;
+
+    /// Whether the DTLS connection is established.
+    fn is_connected(&self) -> bool;
+
+    /// Send a `close_notify` alert to end the connection.
+    ///
+    /// The default does nothing, i.e. the remote peer isn't told about the close.
+    fn close(&mut self) {}
+}
+
+/// Factory for SRTP cipher contexts of a [`CryptoBackend`].
+pub trait SrtpCryptoBackend: Send + Sync + RefUnwindSafe {
+    /// AES-128 counter mode context for `SRTP_AES128_CM_SHA1_80`.
+    fn new_aes_128_cm_sha1_80(
+        &self,
+        key: aes_128_cm_sha1_80::AesKey,
+        encrypt: bool,
+    ) -> Box<dyn aes_128_cm_sha1_80::CipherCtx>;
+
+    /// AES-128-GCM context for `SRTP_AEAD_AES_128_GCM`.
+    fn new_aead_aes_128_gcm(
+        &self,
+        key: aead_aes_128_gcm::AeadKey,
+        encrypt: bool,
+    ) -> Box<dyn aead_aes_128_gcm::CipherCtx>;
+
+    /// AES-256 counter mode context for `SRTP_AES256_CM_SHA1_80`.
+    fn new_aes_256_cm_sha1_80(
+        &self,
+        key: aes_256_cm_sha1_80::AesKey,
+        encrypt: bool,
+    ) -> Box<dyn aes_256_cm_sha1_80::CipherCtx>;
+
+    /// AES-256-GCM context for `SRTP_AEAD_AES_256_GCM`.
+    fn new_aead_aes_256_gcm(
+        &self,
+        key: aead_aes_256_gcm::AeadKey,
+        encrypt: bool,
+    ) -> Box<dyn aead_aes_256_gcm::CipherCtx>;
+
+    /// Single AES-128 ECB block encryption used for SRTP key derivation.
+    ///
+    /// The `input` is one 16 byte block. The `output` buffer is 32 bytes, of
+    /// which only the first 16 are used.
+    fn srtp_aes_128_ecb_round(&self, key: &[u8], input: &[u8], output: &mut [u8]);
+
+    /// Single AES-256 ECB block encryption used for SRTP key derivation of the 256 bit profiles.
+    ///
+    /// Same as [`SrtpCryptoBackend::srtp_aes_128_ecb_round()`], but with a 32 byte key.
+    fn srtp_aes_256_ecb_round(&self, key: &[u8], input: &[u8], output: &mut [u8]);
+}
+
+#[cfg(all(test, feature = "openssl"))]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::crypto::dtls::DtlsInner;
+    use crate::crypto::ossl::{OsslDtlsCert, OsslDtlsImpl, OsslSrtpCryptoImpl};
+    use crate::crypto::srtp::SrtpCryptoImpl;
+    use crate::crypto::{CryptoProvider, DtlsCert};
+    use crate::dtls::Dtls;
+
+    /// A custom backend that delegates to OpenSSL.
+    #[derive(Debug, Default)]
+    struct TestBackend {
+        certs: AtomicUsize,
+    }
+
+    #[derive(Debug)]
+    struct TestCert(OsslDtlsCert);
+
+    struct TestDtls(OsslDtlsImpl);
+
+    struct TestSrtp;
+
+    impl CryptoBackend for TestBackend {
+        fn create_dtls_cert(&self, options: DtlsCertOptions) -> Box<dyn DtlsCertBackend> {
+            self.certs.fetch_add(1, Ordering::SeqCst);
+            Box::new(TestCert(OsslDtlsCert::new(options)))
+        }
+
+        fn srtp_crypto(&self) -> Box<dyn SrtpCryptoBackend> {
+            Box::new(TestSrtp)
+        }
+    }
+
+    impl DtlsCertBackend for TestCert {
+        fn fingerprint(&self) -> Fingerprint {
+            self.0.fingerprint()
+        }
+
+        fn create_dtls(&self) -> Result<Box<dyn DtlsBackend>, CryptoError> {
+            Ok(Box::new(TestDtls(self.0.new_dtls_impl()?)))
+        }
+    }
+
+    impl DtlsBackend for TestDtls {
+        fn set_active(&mut self, active: bool) {
+            self.0.set_active(active)
+        }
+
+        fn is_active(&self) -> Option<bool> {
+            self.0.is_active()
+        }
+
+        fn handle_handshake(&mut self, o: &mut VecDeque<DtlsEvent>) -> Result<bool, CryptoError> {
+            self.0.handle_handshake(o)
+        }
+
+        fn handle_receive(
+            &mut self,
+            m: &[u8],
+            o: &mut VecDeque<DtlsEvent>,
+        ) -> Result<(), CryptoError> {
+            self.0.handle_receive(m, o)
+        }
+
+        fn poll_datagram(&mut self) -> Option<DatagramSend> {
+            self.0.poll_datagram()
+        }
+
+        fn poll_timeout(&mut self, now: Instant) -> Option<Instant> {
+            self.0.poll_timeout(now)
+        }
+
+        fn handle_input(&mut self, data: &[u8]) -> Result<(), CryptoError> {
+            self.0.handle_input(data)
+        }
+
+        fn is_connected(&self) -> bool {
+            self.0.is_connected()
+        }
+    }
+
+    impl SrtpCryptoBackend for TestSrtp {
+        fn new_aes_128_cm_sha1_80(
+            &self,
+            key: aes_128_cm_sha1_80::AesKey,
+            encrypt: bool,
+        ) -> Box<dyn aes_128_cm_sha1_80::CipherCtx> {
+            Box::new(OsslSrtpCryptoImpl.new_aes_128_cm_sha1_80(key, encrypt))
+        }
+
+        fn new_aead_aes_128_gcm(
+            &self,
+            key: aead_aes_128_gcm::AeadKey,
+            encrypt: bool,
+        ) -> Box<dyn aead_aes_128_gcm::CipherCtx> {
+            Box::new(OsslSrtpCryptoImpl.new_aead_aes_128_gcm(key, encrypt))
+        }
+
+        fn new_aes_256_cm_sha1_80(
+            &self,
+            key: aes_256_cm_sha1_80::AesKey,
+            encrypt: bool,
+        ) -> Box<dyn aes_256_cm_sha1_80::CipherCtx> {
+            Box::new(OsslSrtpCryptoImpl.new_aes_256_cm_sha1_80(key, encrypt))
+        }
+
+        fn new_aead_aes_256_gcm(
+            &self,
+            key: aead_aes_256_gcm::AeadKey,
+            encrypt: bool,
+        ) -> Box<dyn aead_aes_256_gcm::CipherCtx> {
+            Box::new(OsslSrtpCryptoImpl.new_aead_aes_256_gcm(key, encrypt))
+        }
+
+        fn srtp_aes_128_ecb_round(&self, key: &[u8], input: &[u8], output: &mut [u8]) {
+            OsslSrtpCryptoImpl.srtp_aes_128_ecb_round(key, input, output)
+        }
+
+        fn srtp_aes_256_ecb_round(&self, key: &[u8], input: &[u8], output: &mut [u8]) {
+            OsslSrtpCryptoImpl.srtp_aes_256_ecb_round(key, input, output)
+        }
+    }
+
+    #[test]
+    fn custom_backend_handshake() {
+        let backend = Arc::new(TestBackend::default());
+        let provider = CryptoProvider::Custom(backend.clone());
+
+        let cert1 = DtlsCert::new(provider.clone(), DtlsCertOptions::default());
+        let cert2 = DtlsCert::new(provider.clone(), DtlsCertOptions::default());
+        assert_eq!(backend.certs.load(Ordering::SeqCst), 2);
+        assert_eq!(cert1.crypto_provider(), provider);
+        assert_ne!(cert1.crypto_provider(), CryptoProvider::OpenSsl);
+
+        let mut d1 = Dtls::new(cert1).unwrap();
+        let mut d2 = Dtls::new(cert2).unwrap();
+        d1.set_active(true);
+        d2.set_active(false);
+        d1.handle_handshake().unwrap();
+
+        for _ in 0..10 {
+            while let Some(d) = d1.poll_datagram() {
+                d2.handle_receive(&d).unwrap();
+            }
+            while let Some(d) = d2.poll_datagram() {
+                d1.handle_receive(&d).unwrap();
+            }
+        }
+
+        assert!(d1.is_connected());
+        assert!(d2.is_connected());
+
+        let has_keying_material = |d: &mut Dtls| {
+            std::iter::from_fn(|| d.poll_event())
+                .any(|e| matches!(e, DtlsEvent::SrtpKeyingMaterial(_, _)))
+        };
+        assert!(has_keying_material(&mut d1));
+        assert!(has_keying_material(&mut d2));
+    }
+
+    #[test]
+    fn custom_backend_srtp() {
+        let provider = CryptoProvider::custom(TestBackend::default());
+        let srtp = provider.srtp_crypto();
+
+        let mut enc = srtp.new_aead_aes_128_gcm([1; 16], true);
+        let mut dec = srtp.new_aead_aes_128_gcm([1; 16], false);
+
+        let iv = [2; aead_aes_128_gcm::IV_LEN];
+        let aad = [3; 12];
+        let mut ct = [0; 5 + aead_aes_128_gcm::TAG_LEN];
+        enc.encrypt(&iv, &aad, b"hello", &mut ct).unwrap();
+
+        let mut pt = [0; 5 + aead_aes_128_gcm::TAG_LEN];
+        let n = dec.decrypt(&iv, &[&aad], &ct, &mut pt).unwrap();
+        assert_eq!(&pt[..n], b"hello");
+    }
+}
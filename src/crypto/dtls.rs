@@ -6,8 +6,9 @@ use std::time::Instant;
 
 use crate::net::DatagramSend;
 
-use super::CryptoProvider;
-use super::{CryptoError, Fingerprint, KeyingMaterial, SrtpProfile};
+use super::{CryptoBackend, CryptoProvider, DtlsBackend, DtlsCertBackend};
+use super::{CryptoError, Fingerprint, FingerprintHash, KeyingMaterial, SrtpProfile};
+use std::sync::Arc;
 
 // libWebRTC says "WebRTC" here when doing OpenSSL, for BoringSSL they seem
 // to generate a random 8 characters.
@@ -17,7 +18,7 @@ use super::{CryptoError, Fingerprint, KeyingMaterial, SrtpProfile};
 // https://github.com/pion/webrtc/blob/eed2bb2d3b9f204f9de1cd7e1046ca5d652778d2/constants.go#L31
 const DTLS_CERT_IDENTITY: &str = "WebRTC";
 
-/// Events arising from a [`Dtls`] instance.
+/// Events arising from a DTLS instance.
 pub enum DtlsEvent {
     /// When the DTLS has finished handshaking.
     Connected,
@@ -25,13 +26,78 @@ pub enum DtlsEvent {
     /// Keying material for SRTP encryption master key and the selected SRTP profile.
     SrtpKeyingMaterial(KeyingMaterial, SrtpProfile),
 
-    /// The fingerprint of the remote peer.
+    /// The DER encoded certificate of the remote peer.
+    ///
+    /// Optional. Without it, only `sha-256` fingerprints in the SDP can be verified.
+    RemoteCertificate(Vec<u8>),
+
+    /// The `sha-256` fingerprint of the remote peer.
     ///
     /// This should be checked against the fingerprint communicated in the SDP.
     RemoteFingerprint(Fingerprint),
 
     /// Decrypted data from incoming DTLS traffic.
     Data(Vec<u8>),
+
+    /// A line in the NSS key log format (`SSLKEYLOGFILE`), if enabled with
+    /// [`DtlsBackend::set_key_log()`].
+    ///
+    /// Emitted after [`DtlsEvent::SrtpKeyingMaterial`].
+    KeyLog(String),
+
+    /// The remote peer ended the connection with `close_notify` or a fatal alert.
+    Alert(DtlsAlert),
+}
+
+/// A DTLS alert from the remote peer, as in RFC 5246 7.2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DtlsAlert(pub u8);
+
+impl DtlsAlert {
+    /// The remote closed the connection normally.
+    pub const CLOSE_NOTIFY: DtlsAlert = DtlsAlert(0);
+
+    /// Whether this is a normal close rather than an error.
+    pub fn is_close_notify(&self) -> bool {
+        *self == Self::CLOSE_NOTIFY
+    }
+
+    /// The name of the alert, such as `handshake_failure`.
+    pub fn name(&self) -> Option<&'static str> {
+        Some(match self.0 {
+            0 => "close_notify",
+            10 => "unexpected_message",
+            20 => "bad_record_mac",
+            22 => "record_overflow",
+            40 => "handshake_failure",
+            42 => "bad_certificate",
+            43 => "unsupported_certificate",
+            44 => "certificate_revoked",
+            45 => "certificate_expired",
+            46 => "certificate_unknown",
+            47 => "illegal_parameter",
+            48 => "unknown_ca",
+            49 => "access_denied",
+            50 => "decode_error",
+            51 => "decrypt_error",
+            70 => "protocol_version",
+            71 => "insufficient_security",
+            80 => "internal_error",
+            90 => "user_canceled",
+            109 => "missing_extension",
+            110 => "unsupported_extension",
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for DtlsAlert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(v) => write!(f, "{}", v),
+            None => write!(f, "alert({})", self.0),
+        }
+    }
 }
 
 /// Defines the type of key pair to generate for the DTLS certificate.
@@ -44,6 +110,21 @@ pub enum DtlsPKeyType {
     EcDsaP256,
 }
 
+/// Whether DTLS 1.3 (RFC 9147) is negotiated.
+///
+/// DTLS 1.3 is currently only implemented by [`CryptoProvider::RustCrypto`]. The
+/// other providers always use DTLS 1.2 and fail to start with [`Dtls13Mode::Require`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dtls13Mode {
+    /// Only use DTLS 1.2.
+    Disabled,
+    /// Use DTLS 1.3 when the remote peer supports it, otherwise fall back to DTLS 1.2.
+    #[default]
+    Prefer,
+    /// Only use DTLS 1.3. The handshake fails with peers only supporting DTLS 1.2.
+    Require,
+}
+
 /// Controls certificate generation options.
 #[derive(Clone, Debug)]
 pub struct DtlsCertOptions {
@@ -76,6 +157,11 @@ enum DtlsCertInner {
     WinCrypto(super::wincrypto::WinCryptoDtlsCert),
     #[cfg(not(all(feature = "wincrypto", target_os = "windows")))]
     WinCrypto(DummyCert),
+    #[cfg(feature = "rust-crypto")]
+    RustCrypto(super::rust_crypto::RustCryptoDtlsCert),
+    #[cfg(not(feature = "rust-crypto"))]
+    RustCrypto(DummyCert),
+    Custom(Arc<dyn CryptoBackend>, Arc<dyn DtlsCertBackend>),
 }
 
 impl DtlsCert {
@@ -87,6 +173,7 @@ impl DtlsCert {
     ///
     /// * **openssl** (defaults to on) for crypto backed by OpenSSL.
     /// * **wincrypto** for crypto backed by windows crypto.
+    /// * **rust-crypto** for pure rust crypto.
     pub fn new(p: CryptoProvider, opts: DtlsCertOptions) -> Self {
         let inner = match p {
             CryptoProvider::OpenSsl => {
@@ -111,15 +198,32 @@ impl DtlsCert {
                     DtlsCertInner::WinCrypto(DummyCert(p))
                 }
             }
+            CryptoProvider::RustCrypto => {
+                #[cfg(feature = "rust-crypto")]
+                {
+                    let cert = super::rust_crypto::RustCryptoDtlsCert::new(opts);
+                    DtlsCertInner::RustCrypto(cert)
+                }
+                #[cfg(not(feature = "rust-crypto"))]
+                {
+                    DtlsCertInner::RustCrypto(DummyCert(p))
+                }
+            }
+            CryptoProvider::Custom(backend) => {
+                let cert = backend.create_dtls_cert(opts);
+                DtlsCertInner::Custom(backend, cert.into())
+            }
         };
 
         DtlsCert(inner)
     }
 
     pub(crate) fn crypto_provider(&self) -> CryptoProvider {
-        match self.0 {
+        match &self.0 {
             DtlsCertInner::OpenSsl(_) => CryptoProvider::OpenSsl,
             DtlsCertInner::WinCrypto(_) => CryptoProvider::WinCrypto,
+            DtlsCertInner::RustCrypto(_) => CryptoProvider::RustCrypto,
+            DtlsCertInner::Custom(b, _) => CryptoProvider::Custom(b.clone()),
         }
     }
 
@@ -130,14 +234,37 @@ impl DtlsCert {
         match &self.0 {
             DtlsCertInner::OpenSsl(v) => v.fingerprint(),
             DtlsCertInner::WinCrypto(v) => v.fingerprint(),
+            DtlsCertInner::RustCrypto(v) => v.fingerprint(),
+            DtlsCertInner::Custom(_, v) => v.fingerprint(),
             _ => unreachable!(),
         }
     }
 
+    /// Creates a fingerprint for this certificate using the given hash function.
+    ///
+    /// Returns `None` for other hash functions than `sha-256` if the crypto provider
+    /// can't give us the certificate itself.
+    pub fn fingerprint_with(&self, hash: FingerprintHash) -> Option<Fingerprint> {
+        if hash == FingerprintHash::Sha256 {
+            return Some(self.fingerprint());
+        }
+
+        let der = match &self.0 {
+            DtlsCertInner::OpenSsl(v) => v.certificate_der(),
+            DtlsCertInner::RustCrypto(v) => v.certificate_der(),
+            DtlsCertInner::Custom(_, v) => v.certificate_der(),
+            _ => None,
+        }?;
+
+        Some(Fingerprint::from_certificate(hash, &der))
+    }
+
     pub(crate) fn create_dtls_impl(&self) -> Result<DtlsImpl, CryptoError> {
         let imp = match &self.0 {
             DtlsCertInner::OpenSsl(v) => DtlsImpl::OpenSsl(v.new_dtls_impl()?),
             DtlsCertInner::WinCrypto(v) => DtlsImpl::WinCrypto(v.new_dtls_impl()?),
+            DtlsCertInner::RustCrypto(v) => DtlsImpl::RustCrypto(v.new_dtls_impl()?),
+            DtlsCertInner::Custom(_, v) => DtlsImpl::Custom(v.create_dtls()?),
         };
 
         Ok(imp)
@@ -149,6 +276,8 @@ impl fmt::Debug for DtlsCert {
         match &self.0 {
             DtlsCertInner::OpenSsl(c) => c.fmt(f),
             DtlsCertInner::WinCrypto(c) => c.fmt(f),
+            DtlsCertInner::RustCrypto(c) => c.fmt(f),
+            DtlsCertInner::Custom(_, c) => c.fmt(f),
             _ => unreachable!(),
         }
     }
@@ -161,6 +290,24 @@ pub trait DtlsInner: Sized {
     /// exactly once before starting to handshake (I/O).
     fn set_active(&mut self, active: bool);
 
+    /// Set which DTLS versions to negotiate. Called before `set_active`.
+    ///
+    /// Implementations without DTLS 1.3 support only accept DTLS 1.2.
+    fn set_dtls13(&mut self, mode: Dtls13Mode) -> Result<(), CryptoError> {
+        dtls13_unsupported(mode)
+    }
+
+    /// Set the SRTP profiles to offer in `use_srtp`, most preferred first. Called before `set_active`.
+    ///
+    /// Implementations that can't configure the profiles offer their own default.
+    fn set_srtp_profiles(&mut self, profiles: &[SrtpProfile]) -> Result<(), CryptoError> {
+        let _ = profiles;
+        Ok(())
+    }
+
+    /// Enable emitting [`DtlsEvent::KeyLog`] for the handshake secrets. Called before `set_active`.
+    fn set_key_log(&mut self, enabled: bool) {}
+
     /// Handle the handshake. Once this succeeds, it becomes a no-op.
     fn handle_handshake(&mut self, o: &mut VecDeque<DtlsEvent>) -> Result<bool, CryptoError>;
 
@@ -176,11 +323,19 @@ pub trait DtlsInner: Sized {
     /// Poll for next timeout. This is only used during DTLS handshake.
     fn poll_timeout(&mut self, now: Instant) -> Option<Instant>;
 
+    /// Handle a timeout, such as retransmitting a handshake flight.
+    ///
+    /// Implementations that handle retransmits internally can ignore this.
+    fn handle_timeout(&mut self, now: Instant) {}
+
     /// Handling incoming data to be sent as DTLS datagrams.
     fn handle_input(&mut self, data: &[u8]) -> Result<(), CryptoError>;
 
     /// Whether the DTLS connection is established.
     fn is_connected(&self) -> bool;
+
+    /// Send a `close_notify` alert, if connected.
+    fn close(&mut self) {}
 }
 
 pub(crate) enum DtlsImpl {
@@ -192,6 +347,11 @@ pub(crate) enum DtlsImpl {
     WinCrypto(super::wincrypto::WinCryptoDtls),
     #[cfg(not(all(feature = "wincrypto", target_os = "windows")))]
     WinCrypto(DummyDtlsImpl),
+    #[cfg(feature = "rust-crypto")]
+    RustCrypto(Box<super::rust_crypto::RustCryptoDtlsImpl>),
+    #[cfg(not(feature = "rust-crypto"))]
+    RustCrypto(DummyDtlsImpl),
+    Custom(Box<dyn DtlsBackend>),
 }
 
 impl DtlsImpl {
@@ -199,6 +359,35 @@ impl DtlsImpl {
         match self {
             DtlsImpl::OpenSsl(v) => v.set_active(active),
             DtlsImpl::WinCrypto(v) => v.set_active(active),
+            DtlsImpl::RustCrypto(v) => v.set_active(active),
+            DtlsImpl::Custom(v) => v.set_active(active),
+        }
+    }
+
+    pub fn set_dtls13(&mut self, mode: Dtls13Mode) -> Result<(), CryptoError> {
+        match self {
+            DtlsImpl::OpenSsl(v) => v.set_dtls13(mode),
+            DtlsImpl::WinCrypto(v) => v.set_dtls13(mode),
+            DtlsImpl::RustCrypto(v) => v.set_dtls13(mode),
+            DtlsImpl::Custom(v) => v.set_dtls13(mode),
+        }
+    }
+
+    pub fn set_srtp_profiles(&mut self, profiles: &[SrtpProfile]) -> Result<(), CryptoError> {
+        match self {
+            DtlsImpl::OpenSsl(v) => v.set_srtp_profiles(profiles),
+            DtlsImpl::WinCrypto(v) => v.set_srtp_profiles(profiles),
+            DtlsImpl::RustCrypto(v) => v.set_srtp_profiles(profiles),
+            DtlsImpl::Custom(v) => v.set_srtp_profiles(profiles),
+        }
+    }
+
+    pub fn set_key_log(&mut self, enabled: bool) {
+        match self {
+            DtlsImpl::OpenSsl(v) => v.set_key_log(enabled),
+            DtlsImpl::WinCrypto(v) => v.set_key_log(enabled),
+            DtlsImpl::RustCrypto(v) => v.set_key_log(enabled),
+            DtlsImpl::Custom(v) => v.set_key_log(enabled),
         }
     }
 
@@ -206,6 +395,8 @@ impl DtlsImpl {
         match self {
             DtlsImpl::OpenSsl(i) => i.handle_handshake(o),
             DtlsImpl::WinCrypto(i) => i.handle_handshake(o),
+            DtlsImpl::RustCrypto(i) => i.handle_handshake(o),
+            DtlsImpl::Custom(i) => i.handle_handshake(o),
         }
     }
 
@@ -213,6 +404,8 @@ impl DtlsImpl {
         match self {
             DtlsImpl::OpenSsl(i) => i.is_active(),
             DtlsImpl::WinCrypto(i) => i.is_active(),
+            DtlsImpl::RustCrypto(i) => i.is_active(),
+            DtlsImpl::Custom(i) => i.is_active(),
         }
     }
 
@@ -224,6 +417,8 @@ impl DtlsImpl {
         match self {
             DtlsImpl::OpenSsl(i) => i.handle_receive(m, o),
             DtlsImpl::WinCrypto(i) => i.handle_receive(m, o),
+            DtlsImpl::RustCrypto(i) => i.handle_receive(m, o),
+            DtlsImpl::Custom(i) => i.handle_receive(m, o),
         }
     }
 
@@ -231,6 +426,8 @@ impl DtlsImpl {
         match self {
             DtlsImpl::OpenSsl(i) => i.poll_datagram(),
             DtlsImpl::WinCrypto(i) => i.poll_datagram(),
+            DtlsImpl::RustCrypto(i) => i.poll_datagram(),
+            DtlsImpl::Custom(i) => i.poll_datagram(),
         }
     }
 
@@ -238,6 +435,17 @@ impl DtlsImpl {
         match self {
             DtlsImpl::OpenSsl(i) => i.poll_timeout(now),
             DtlsImpl::WinCrypto(i) => i.poll_timeout(now),
+            DtlsImpl::RustCrypto(i) => i.poll_timeout(now),
+            DtlsImpl::Custom(i) => i.poll_timeout(now),
+        }
+    }
+
+    pub fn handle_timeout(&mut self, now: Instant) {
+        match self {
+            DtlsImpl::OpenSsl(i) => i.handle_timeout(now),
+            DtlsImpl::WinCrypto(i) => i.handle_timeout(now),
+            DtlsImpl::RustCrypto(i) => i.handle_timeout(now),
+            DtlsImpl::Custom(i) => i.handle_timeout(now),
         }
     }
 
@@ -245,6 +453,8 @@ impl DtlsImpl {
         match self {
             DtlsImpl::OpenSsl(i) => i.handle_input(data),
             DtlsImpl::WinCrypto(i) => i.handle_input(data),
+            DtlsImpl::RustCrypto(i) => i.handle_input(data),
+            DtlsImpl::Custom(i) => i.handle_input(data),
         }
     }
 
@@ -252,6 +462,17 @@ impl DtlsImpl {
         match self {
             DtlsImpl::OpenSsl(i) => i.is_connected(),
             DtlsImpl::WinCrypto(i) => i.is_connected(),
+            DtlsImpl::RustCrypto(i) => i.is_connected(),
+            DtlsImpl::Custom(i) => i.is_connected(),
+        }
+    }
+
+    pub fn close(&mut self) {
+        match self {
+            DtlsImpl::OpenSsl(i) => i.close(),
+            DtlsImpl::WinCrypto(i) => i.close(),
+            DtlsImpl::RustCrypto(i) => i.close(),
+            DtlsImpl::Custom(i) => i.close(),
         }
     }
 }
@@ -267,6 +488,10 @@ impl DummyCert {
     fn new_dtls_impl(&self) -> Result<DummyDtlsImpl, CryptoError> {
         panic!("Must enable feature: {}", self.0)
     }
+
+    fn certificate_der(&self) -> Option<Vec<u8>> {
+        panic!("Must enable feature: {}", self.0)
+    }
 }
 
 pub struct DummyDtlsImpl(CryptoProvider);
@@ -276,6 +501,18 @@ impl DummyDtlsImpl {
         panic!("Must enable feature: {}", self.0)
     }
 
+    fn set_dtls13(&self, mode: Dtls13Mode) -> Result<(), CryptoError> {
+        panic!("Must enable feature: {}", self.0)
+    }
+
+    fn set_srtp_profiles(&self, profiles: &[SrtpProfile]) -> Result<(), CryptoError> {
+        panic!("Must enable feature: {}", self.0)
+    }
+
+    fn set_key_log(&self, enabled: bool) {
+        panic!("Must enable feature: {}", self.0)
+    }
+
     fn handle_handshake(&self, o: &mut VecDeque<DtlsEvent>) -> Result<bool, CryptoError> {
         panic!("Must enable feature: {}", self.0)
     }
@@ -296,6 +533,10 @@ impl DummyDtlsImpl {
         panic!("Must enable feature: {}", self.0)
     }
 
+    fn handle_timeout(&self, now: Instant) {
+        panic!("Must enable feature: {}", self.0)
+    }
+
     fn handle_input(&self, data: &[u8]) -> Result<(), CryptoError> {
         panic!("Must enable feature: {}", self.0)
     }
@@ -303,4 +544,20 @@ impl DummyDtlsImpl {
     fn is_connected(&self) -> bool {
         panic!("Must enable feature: {}", self.0)
     }
+
+    fn close(&self) {
+        panic!("Must enable feature: {}", self.0)
+    }
+}
+
+/// The [`Dtls13Mode`] accepted by implementations that only do DTLS 1.2.
+pub(crate) fn dtls13_unsupported(mode: Dtls13Mode) -> Result<(), CryptoError> {
+    if mode == Dtls13Mode::Require {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "DTLS 1.3 is not supported by this crypto provider",
+        )
+        .into());
+    }
+    Ok(())
 }
@@ -1,5 +1,7 @@
 use core::fmt;
 
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
 /// Certificate fingerprint.
 ///
 /// DTLS uses self signed certificates, and the fingerprint is communicated via
@@ -15,6 +17,79 @@ pub struct Fingerprint {
     pub bytes: Vec<u8>,
 }
 
+/// Hash function for certificate fingerprints (RFC 8122).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FingerprintHash {
+    /// `sha-256`, which is what all WebRTC implementations use.
+    Sha256,
+    /// `sha-384`
+    Sha384,
+    /// `sha-512`
+    Sha512,
+}
+
+impl FingerprintHash {
+    /// The name used in the `a=fingerprint` line.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FingerprintHash::Sha256 => "sha-256",
+            FingerprintHash::Sha384 => "sha-384",
+            FingerprintHash::Sha512 => "sha-512",
+        }
+    }
+
+    /// Parse the name used in the `a=fingerprint` line. Hash names are case-insensitive.
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Self::Sha256, Self::Sha384, Self::Sha512]
+            .into_iter()
+            .find(|h| h.as_str().eq_ignore_ascii_case(name))
+    }
+}
+
+impl fmt::Display for FingerprintHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl Fingerprint {
+    /// Fingerprint of a DER encoded certificate.
+    pub fn from_certificate(hash: FingerprintHash, der: &[u8]) -> Fingerprint {
+        let bytes = match hash {
+            FingerprintHash::Sha256 => Sha256::digest(der).to_vec(),
+            FingerprintHash::Sha384 => Sha384::digest(der).to_vec(),
+            FingerprintHash::Sha512 => Sha512::digest(der).to_vec(),
+        };
+
+        Fingerprint {
+            hash_func: hash.as_str().into(),
+            bytes,
+        }
+    }
+
+    /// The hash function, if it is one we support.
+    pub fn hash(&self) -> Option<FingerprintHash> {
+        FingerprintHash::from_name(&self.hash_func)
+    }
+
+    /// Whether this expected fingerprint (from SDP) matches the remote certificate.
+    ///
+    /// The `sha256` fingerprint is always available from the DTLS backend, other hash
+    /// functions can only be checked when the backend provides the certificate itself.
+    pub(crate) fn matches(&self, sha256: &Fingerprint, der: Option<&[u8]>) -> bool {
+        let Some(hash) = self.hash() else {
+            return false;
+        };
+
+        if hash == FingerprintHash::Sha256 && sha256.hash() == Some(hash) {
+            return self.bytes == sha256.bytes;
+        }
+
+        der.map(|d| Fingerprint::from_certificate(hash, d).bytes == self.bytes)
+            .unwrap_or(false)
+    }
+}
+
 // DO NOT CHANGE!
 // This format is exactly what's needed in n SDP.
 impl fmt::Display for Fingerprint {
@@ -68,4 +143,31 @@ mod test {
             "foo 00:01:02:03:04:05:06:07:08:09:0A:0B:0C:0D:0E:0F:10:11"
         );
     }
+
+    #[test]
+    fn fingerprint_matches() {
+        let der = b"not really a certificate";
+        let sha256 = Fingerprint::from_certificate(FingerprintHash::Sha256, der);
+        let sha384 = Fingerprint::from_certificate(FingerprintHash::Sha384, der);
+        let sha512 = Fingerprint::from_certificate(FingerprintHash::Sha512, der);
+
+        assert_eq!(sha384.bytes.len(), 48);
+        assert_eq!(sha512.bytes.len(), 64);
+
+        let mut upper = sha256.clone();
+        upper.hash_func = "SHA-256".into();
+        assert!(upper.matches(&sha256, None));
+
+        // Other hash functions need the certificate.
+        assert!(!sha384.matches(&sha256, None));
+        assert!(sha384.matches(&sha256, Some(der)));
+        assert!(sha512.matches(&sha256, Some(der)));
+        assert!(!sha512.matches(&sha256, Some(b"other")));
+
+        let unknown = Fingerprint {
+            hash_func: "md5".into(),
+            bytes: vec![1, 2, 3],
+        };
+        assert!(!unknown.matches(&sha256, Some(der)));
+    }
 }
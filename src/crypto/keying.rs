@@ -4,6 +4,7 @@ use std::ops::Deref;
 pub struct KeyingMaterial(Vec<u8>);
 
 impl KeyingMaterial {
+    /// Wrap keying material exported from the DTLS session.
     pub fn new(m: Vec<u8>) -> Self {
         KeyingMaterial(m)
     }
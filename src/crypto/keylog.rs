@@ -0,0 +1,150 @@
+use std::fmt;
+
+use crate::util::base64;
+
+use super::SrtpProfile;
+
+/// Secrets for decrypting captured traffic offline, i.e. with Wireshark.
+///
+/// Enable using [`RtcConfig::enable_key_log()`][crate::RtcConfig::enable_key_log].
+/// Anyone holding these can decrypt the session. Should not be enabled outside of
+/// tests and troubleshooting.
+#[derive(Clone, PartialEq, Eq)]
+pub enum KeyLog {
+    /// A line in the NSS key log format, which is what `SSLKEYLOGFILE` contains.
+    ///
+    /// For DTLS 1.2 this is `CLIENT_RANDOM <client random> <master secret>`, for DTLS 1.3
+    /// there is one line per traffic secret. Append the lines to a file and point
+    /// Wireshark's "(Pre)-Master-Secret log filename" setting to it.
+    Dtls(String),
+
+    /// SRTP master keys and salts for both directions.
+    Srtp(SrtpKeyLog),
+}
+
+/// SRTP master keys and salts, as derived from DTLS or exchanged with SDES.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SrtpKeyLog {
+    /// The SRTP profile in use.
+    pub profile: SrtpProfile,
+    /// Master key for the SRTP we send.
+    pub local_master_key: Vec<u8>,
+    /// Master salt for the SRTP we send.
+    pub local_master_salt: Vec<u8>,
+    /// Master key for the SRTP we receive.
+    pub remote_master_key: Vec<u8>,
+    /// Master salt for the SRTP we receive.
+    pub remote_master_salt: Vec<u8>,
+}
+
+impl SrtpKeyLog {
+    /// Split keying material exported with `EXTRACTOR-dtls_srtp`.
+    ///
+    /// The active (client) side uses the left hand keys to send.
+    pub(crate) fn new(profile: SrtpProfile, mat: &[u8], active: bool) -> Self {
+        let (key_len, salt_len) = profile.master_key_salt_len();
+
+        let (keys, salts) = mat.split_at(key_len * 2);
+        let (client_key, server_key) = keys.split_at(key_len);
+        let (client_salt, server_salt) = salts[..salt_len * 2].split_at(salt_len);
+
+        let (local, remote) = if active {
+            ((client_key, client_salt), (server_key, server_salt))
+        } else {
+            ((server_key, server_salt), (client_key, client_salt))
+        };
+
+        SrtpKeyLog {
+            profile,
+            local_master_key: local.0.to_vec(),
+            local_master_salt: local.1.to_vec(),
+            remote_master_key: remote.0.to_vec(),
+            remote_master_salt: remote.1.to_vec(),
+        }
+    }
+
+    /// Key and salt for the SRTP we send, base64 encoded like an SDES `inline:` key.
+    ///
+    /// This is the format taken by tools such as libsrtp's `rtp_decoder`.
+    pub fn local_inline_key(&self) -> String {
+        inline_key(&self.local_master_key, &self.local_master_salt)
+    }
+
+    /// Key and salt for the SRTP we receive, base64 encoded like an SDES `inline:` key.
+    pub fn remote_inline_key(&self) -> String {
+        inline_key(&self.remote_master_key, &self.remote_master_salt)
+    }
+}
+
+fn inline_key(key: &[u8], salt: &[u8]) -> String {
+    let mut v = key.to_vec();
+    v.extend_from_slice(salt);
+    base64::encode(&v)
+}
+
+/// One line in the NSS key log format.
+pub(crate) fn key_log_line(label: &str, client_random: &[u8], secret: &[u8]) -> String {
+    let mut s = String::with_capacity(label.len() + 2 + (client_random.len() + secret.len()) * 2);
+    s.push_str(label);
+    s.push(' ');
+    push_hex(&mut s, client_random);
+    s.push(' ');
+    push_hex(&mut s, secret);
+    s
+}
+
+fn push_hex(s: &mut String, bytes: &[u8]) {
+    use std::fmt::Write;
+    for b in bytes {
+        let _ = write!(s, "{:02x}", b);
+    }
+}
+
+impl fmt::Debug for KeyLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Don't log the secrets.
+        match self {
+            Self::Dtls(line) => {
+                let label = line.split(' ').next().unwrap_or_default();
+                f.debug_tuple("Dtls").field(&label).finish()
+            }
+            Self::Srtp(v) => f.debug_tuple("Srtp").field(v).finish(),
+        }
+    }
+}
+
+impl fmt::Debug for SrtpKeyLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SrtpKeyLog")
+            .field("profile", &self.profile)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn nss_line() {
+        let line = key_log_line("CLIENT_RANDOM", &[0x01, 0xab], &[0xff, 0x00, 0x10]);
+        assert_eq!(line, "CLIENT_RANDOM 01ab ff0010");
+    }
+
+    #[test]
+    fn srtp_split() {
+        let profile = SrtpProfile::Aes128CmSha1_80;
+        let mat: Vec<u8> = (0..60).collect();
+
+        let client = SrtpKeyLog::new(profile, &mat, true);
+        assert_eq!(client.local_master_key, (0..16).collect::<Vec<_>>());
+        assert_eq!(client.remote_master_key, (16..32).collect::<Vec<_>>());
+        assert_eq!(client.local_master_salt, (32..46).collect::<Vec<_>>());
+        assert_eq!(client.remote_master_salt, (46..60).collect::<Vec<_>>());
+
+        let server = SrtpKeyLog::new(profile, &mat, false);
+        assert_eq!(server.local_master_key, client.remote_master_key);
+        assert_eq!(server.local_master_salt, client.remote_master_salt);
+        assert_eq!(server.remote_inline_key(), client.local_inline_key());
+    }
+}
@@ -3,6 +3,7 @@
 use once_cell::sync::OnceCell;
 use std::fmt;
 use std::io;
+use std::sync::Arc;
 use thiserror::Error;
 
 /// Crypto provider setting.
@@ -11,8 +12,10 @@ use thiserror::Error;
 ///
 /// * **openssl** (defaults to on) for crypto backed by OpenSSL.
 /// * **wincrypto** for crypto backed by windows crypto.
+/// * **rust-crypto** for pure rust crypto (no C dependencies).
 ///
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// A user supplied implementation can be used via [`CryptoProvider::Custom`].
+#[derive(Debug, Clone)]
 pub enum CryptoProvider {
     /// OpenSSL (the default)
     ///
@@ -22,6 +25,12 @@ pub enum CryptoProvider {
     ///
     /// Requires feature flag **wincrypto**.
     WinCrypto,
+    /// Pure rust implementation using the RustCrypto crates.
+    ///
+    /// Requires feature flag **rust-crypto**.
+    RustCrypto,
+    /// User supplied implementation of the [`CryptoBackend`] traits.
+    Custom(Arc<dyn CryptoBackend>),
 }
 
 static PROCESS_DEFAULT: OnceCell<CryptoProvider> = OnceCell::new();
@@ -31,9 +40,16 @@ impl CryptoProvider {
         match self {
             CryptoProvider::OpenSsl => SrtpCrypto::new_openssl(),
             CryptoProvider::WinCrypto => SrtpCrypto::new_wincrypto(),
+            CryptoProvider::RustCrypto => SrtpCrypto::new_rust_crypto(),
+            CryptoProvider::Custom(v) => SrtpCrypto::Custom(v.srtp_crypto()),
         }
     }
 
+    /// Create a provider from a user supplied crypto implementation.
+    pub fn custom(backend: impl CryptoBackend + 'static) -> Self {
+        CryptoProvider::Custom(Arc::new(backend))
+    }
+
     /// Install the selected crypto provider as default for the process.
     ///
     /// This makes any new instance of [`Rtc`][crate::Rtc] pick up this default automatically.
@@ -42,24 +58,27 @@ impl CryptoProvider {
     /// should never install a process default.
     pub fn install_process_default(&self) {
         PROCESS_DEFAULT
-            .set(*self)
+            .set(self.clone())
             .expect("CryptoProvider::install_process_default() called once");
     }
 
     /// Can be repeated in the same process.
     #[doc(hidden)]
     pub fn __test_install_process_default(&self) {
-        let _ = PROCESS_DEFAULT.set(*self);
+        let _ = PROCESS_DEFAULT.set(self.clone());
     }
 
     /// Get a possible crypto backend using feature flags.
     ///
-    /// Favors **openssl** if enabled. Panics if no crypto backend is available.
+    /// Favors **openssl** if enabled, then **wincrypto** and last **rust-crypto**.
+    /// Panics if no crypto backend is available.
     pub fn from_feature_flags() -> CryptoProvider {
         if cfg!(feature = "openssl") {
             return CryptoProvider::OpenSsl;
         } else if cfg!(all(feature = "wincrypto", target_os = "windows")) {
             return CryptoProvider::WinCrypto;
+        } else if cfg!(feature = "rust-crypto") {
+            return CryptoProvider::RustCrypto;
         }
         panic!("No crypto backend enabled");
     }
@@ -75,18 +94,29 @@ mod ossl;
 #[cfg(all(feature = "wincrypto", target_os = "windows"))]
 mod wincrypto;
 
+#[cfg(feature = "rust-crypto")]
+mod rust_crypto;
+
+mod backend;
+pub use backend::{CryptoBackend, DtlsBackend, DtlsCertBackend, SrtpCryptoBackend};
+
 mod dtls;
 pub(crate) use dtls::DtlsImpl;
-pub use dtls::{DtlsCert, DtlsCertOptions, DtlsEvent, DtlsPKeyType};
+pub use dtls::{Dtls13Mode, DtlsAlert, DtlsCert, DtlsCertOptions, DtlsEvent, DtlsPKeyType};
 
 mod finger;
-pub use finger::Fingerprint;
+pub use finger::{Fingerprint, FingerprintHash};
 
 mod keying;
 pub use keying::KeyingMaterial;
 
+mod keylog;
+pub(crate) use keylog::key_log_line;
+pub use keylog::{KeyLog, SrtpKeyLog};
+
 mod srtp;
 pub use srtp::{aead_aes_128_gcm, aes_128_cm_sha1_80, SrtpCrypto, SrtpProfile};
+pub use srtp::{aead_aes_256_gcm, aes_256_cm_sha1_80};
 
 /// SHA1 HMAC as used for STUN and older SRTP.
 /// If sha1 feature is enabled, it uses `rust-crypto` crate.
@@ -130,6 +160,17 @@ pub fn sha1_hmac(key: &[u8], payloads: &[&[u8]]) -> [u8; 20] {
     wincrypto::sha1_hmac(key, payloads)
 }
 
+/// If rust-crypto is the only provider and sha1 is not enabled, it uses `sha1_smol`.
+#[cfg(all(
+    feature = "rust-crypto",
+    not(feature = "sha1"),
+    not(feature = "openssl"),
+    not(feature = "wincrypto")
+))]
+pub fn sha1_hmac(key: &[u8], payloads: &[&[u8]]) -> [u8; 20] {
+    rust_crypto::sha1_hmac(key, payloads)
+}
+
 /// Errors that can arise in DTLS.
 #[derive(Debug, Error)]
 pub enum CryptoError {
@@ -143,9 +184,18 @@ pub enum CryptoError {
     #[cfg(all(feature = "wincrypto", target_os = "windows"))]
     WinCrypto(#[from] wincrypto::WinCryptoError),
 
+    /// Some error from the pure rust DTLS implementation.
+    #[error("{0}")]
+    #[cfg(feature = "rust-crypto")]
+    RustCrypto(#[from] rust_crypto::RustCryptoError),
+
     /// Other IO errors.
     #[error("{0}")]
     Io(#[from] io::Error),
+
+    /// Error from a user supplied [`CryptoBackend`].
+    #[error("{0}")]
+    Other(Box<dyn std::error::Error + Send + Sync>),
 }
 
 impl fmt::Display for CryptoProvider {
@@ -153,6 +203,22 @@ impl fmt::Display for CryptoProvider {
         match self {
             CryptoProvider::OpenSsl => write!(f, "openssl"),
             CryptoProvider::WinCrypto => write!(f, "wincrypto"),
+            CryptoProvider::RustCrypto => write!(f, "rust-crypto"),
+            CryptoProvider::Custom(_) => write!(f, "custom"),
         }
     }
 }
+
+impl PartialEq for CryptoProvider {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            // Same instance of the user supplied backend.
+            (CryptoProvider::Custom(a), CryptoProvider::Custom(b)) => {
+                Arc::as_ptr(a) as *const () == Arc::as_ptr(b) as *const ()
+            }
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+
+impl Eq for CryptoProvider {}
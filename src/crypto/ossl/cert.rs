@@ -106,6 +106,11 @@ impl OsslDtlsCert {
         }
     }
 
+    /// The DER encoded certificate.
+    pub fn certificate_der(&self) -> Option<Vec<u8>> {
+        self.x509.to_der().ok()
+    }
+
     pub(crate) fn new_dtls_impl(&self) -> Result<OsslDtlsImpl, CryptoError> {
         OsslDtlsImpl::new(self.clone())
     }
@@ -5,7 +5,7 @@ use std::time::{Duration, Instant};
 use openssl::ssl::{Ssl, SslContext, SslContextBuilder, SslMethod, SslOptions, SslVerifyMode};
 
 use crate::crypto::dtls::DtlsInner;
-use crate::crypto::{DtlsEvent, SrtpProfile};
+use crate::crypto::{DtlsAlert, DtlsEvent, SrtpProfile};
 use crate::io::{DATAGRAM_MTU, DATAGRAM_MTU_WARN};
 
 use super::cert::OsslDtlsCert;
@@ -52,6 +52,15 @@ impl DtlsInner for OsslDtlsImpl {
         self.tls.is_active()
     }
 
+    fn set_srtp_profiles(&mut self, profiles: &[SrtpProfile]) -> Result<(), CryptoError> {
+        self.tls
+            .set_srtp_profiles(&SrtpProfile::openssl_list(profiles))
+    }
+
+    fn set_key_log(&mut self, enabled: bool) {
+        self.tls.set_key_log(enabled);
+    }
+
     fn handle_receive(&mut self, m: &[u8], o: &mut VecDeque<DtlsEvent>) -> Result<(), CryptoError> {
         self.tls.inner_mut().set_incoming(m);
 
@@ -62,11 +71,21 @@ impl DtlsInner for OsslDtlsImpl {
 
         let mut buf = vec![0; 2000];
         let n = match self.tls.read(&mut buf) {
+            Ok(0) if self.tls.is_remote_closed() => {
+                o.push_back(DtlsEvent::Alert(DtlsAlert::CLOSE_NOTIFY));
+                return Ok(());
+            }
             Ok(v) => v,
             Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
                 return Ok(());
             }
-            Err(e) => return Err(e.into()),
+            Err(e) => {
+                if let Some(alert) = remote_alert(&e) {
+                    o.push_back(DtlsEvent::Alert(alert));
+                    return Ok(());
+                }
+                return Err(e.into());
+            }
         };
         buf.truncate(n);
 
@@ -106,6 +125,10 @@ impl DtlsInner for OsslDtlsImpl {
         self.tls.is_connected()
     }
 
+    fn close(&mut self) {
+        self.tls.shutdown();
+    }
+
     fn handle_handshake(&mut self, output: &mut VecDeque<DtlsEvent>) -> Result<bool, CryptoError> {
         if self.tls.is_connected() {
             // Nice. Nothing to do.
@@ -113,14 +136,19 @@ impl DtlsInner for OsslDtlsImpl {
         } else if self.tls.complete_handshake_until_block()? {
             output.push_back(DtlsEvent::Connected);
 
-            let (keying_material, srtp_profile, fingerprint) = self
+            let (keying_material, srtp_profile, fingerprint, der) = self
                 .tls
                 .take_srtp_keying_material()
                 .expect("Exported keying material");
 
+            output.push_back(DtlsEvent::RemoteCertificate(der));
             output.push_back(DtlsEvent::RemoteFingerprint(fingerprint));
 
             output.push_back(DtlsEvent::SrtpKeyingMaterial(keying_material, srtp_profile));
+
+            for line in self.tls.take_key_log() {
+                output.push_back(DtlsEvent::KeyLog(line));
+            }
             Ok(false)
         } else {
             Ok(true)
@@ -128,6 +156,20 @@ impl DtlsInner for OsslDtlsImpl {
     }
 }
 
+/// The alert from the remote peer, if that is what failed the read.
+fn remote_alert(e: &io::Error) -> Option<DtlsAlert> {
+    // OpenSSL reports received alerts as reason SSL_AD_REASON_OFFSET + description.
+    const SSL_AD_REASON_OFFSET: i32 = 1000;
+
+    let e = e.get_ref()?.downcast_ref::<openssl::ssl::Error>()?;
+    e.ssl_error()?.errors().iter().find_map(|e| {
+        let description = e.reason_code() - SSL_AD_REASON_OFFSET;
+        (0..=255)
+            .contains(&description)
+            .then_some(DtlsAlert(description as u8))
+    })
+}
+
 pub fn dtls_create_ctx(cert: &OsslDtlsCert) -> Result<SslContext, CryptoError> {
     // TODO: Technically we want to disallow DTLS < 1.2, but that requires
     // us to use this commented out unsafe. We depend on browsers disallowing
@@ -136,17 +178,7 @@ pub fn dtls_create_ctx(cert: &OsslDtlsCert) -> Result<SslContext, CryptoError> {
     let mut ctx = SslContextBuilder::new(SslMethod::dtls())?;
 
     ctx.set_cipher_list(DTLS_CIPHERS)?;
-    let srtp_profiles = {
-        // Rust can't join directly to a string, need to allocate a vec first :(
-        // This happens very rarely so the extra allocations don't matter
-        let all: Vec<_> = SrtpProfile::ALL
-            .iter()
-            .map(SrtpProfile::openssl_name)
-            .collect();
-
-        all.join(":")
-    };
-    ctx.set_tlsext_use_srtp(&srtp_profiles)?;
+    ctx.set_tlsext_use_srtp(&SrtpProfile::openssl_list(SrtpProfile::DEFAULT))?;
 
     let mut mode = SslVerifyMode::empty();
     mode.insert(SslVerifyMode::PEER);
@@ -16,12 +16,28 @@ pub use srtp::OsslSrtpCryptoImpl;
 
 impl SrtpProfile {
     /// What this profile is called in OpenSSL parlance.
-    pub(crate) fn openssl_name(&self) -> &'static str {
+    ///
+    /// `None` for profiles that OpenSSL can't negotiate via DTLS.
+    pub(crate) fn openssl_name(&self) -> Option<&'static str> {
         match self {
             #[cfg(feature = "_internal_test_exports")]
-            SrtpProfile::PassThrough => "NULL",
-            SrtpProfile::Aes128CmSha1_80 => "SRTP_AES128_CM_SHA1_80",
-            SrtpProfile::AeadAes128Gcm => "SRTP_AEAD_AES_128_GCM",
+            SrtpProfile::PassThrough => Some("NULL"),
+            SrtpProfile::Aes128CmSha1_80 => Some("SRTP_AES128_CM_SHA1_80"),
+            SrtpProfile::AeadAes128Gcm => Some("SRTP_AEAD_AES_128_GCM"),
+            SrtpProfile::Aes256CmSha1_80 => None,
+            SrtpProfile::AeadAes256Gcm => Some("SRTP_AEAD_AES_256_GCM"),
         }
     }
+
+    /// The profiles joined as a `use_srtp` list for OpenSSL.
+    pub(crate) fn openssl_list(profiles: &[SrtpProfile]) -> String {
+        // Rust can't join directly to a string, need to allocate a vec first :(
+        // This happens very rarely so the extra allocations don't matter
+        let all: Vec<_> = profiles
+            .iter()
+            .filter_map(SrtpProfile::openssl_name)
+            .collect();
+
+        all.join(":")
+    }
 }
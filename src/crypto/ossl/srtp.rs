@@ -4,50 +4,57 @@ use openssl::symm::{Cipher, Crypter, Mode};
 
 use crate::crypto::srtp::SrtpCryptoImpl;
 use crate::crypto::srtp::{aead_aes_128_gcm, aes_128_cm_sha1_80};
+use crate::crypto::srtp::{aead_aes_256_gcm, aes_256_cm_sha1_80};
 use crate::crypto::CryptoError;
 
 pub struct OsslSrtpCryptoImpl;
 
 impl SrtpCryptoImpl for OsslSrtpCryptoImpl {
-    type Aes128CmSha1_80 = OsslAes128CmSha1_80;
-    type AeadAes128Gcm = OsslAeadAes128Gcm;
+    type Aes128CmSha1_80 = OsslAesCmSha1_80;
+    type AeadAes128Gcm = OsslAeadAesGcm;
+    type Aes256CmSha1_80 = OsslAesCmSha1_80;
+    type AeadAes256Gcm = OsslAeadAesGcm;
 
     fn srtp_aes_128_ecb_round(&self, key: &[u8], input: &[u8], output: &mut [u8]) {
-        let mut aes =
-            Crypter::new(Cipher::aes_128_ecb(), Mode::Encrypt, key, None).expect("AES deriver");
-
-        // Run AES
-        let count = aes.update(input, output).expect("AES update");
-        let rest = aes.finalize(&mut output[count..]).expect("AES finalize");
+        aes_ecb_round(Cipher::aes_128_ecb(), key, input, output)
+    }
 
-        assert_eq!(count + rest, 16 + 16); // input len + block size
+    fn srtp_aes_256_ecb_round(&self, key: &[u8], input: &[u8], output: &mut [u8]) {
+        aes_ecb_round(Cipher::aes_256_ecb(), key, input, output)
     }
 }
 
-pub struct OsslAes128CmSha1_80(CipherCtx);
+fn aes_ecb_round(t: Cipher, key: &[u8], input: &[u8], output: &mut [u8]) {
+    let mut aes = Crypter::new(t, Mode::Encrypt, key, None).expect("AES deriver");
 
-impl aes_128_cm_sha1_80::CipherCtx for OsslAes128CmSha1_80 {
-    fn new(key: aes_128_cm_sha1_80::AesKey, encrypt: bool) -> Self
-    where
-        Self: Sized,
-    {
-        let t = cipher::Cipher::aes_128_ctr();
+    // Run AES
+    let count = aes.update(input, output).expect("AES update");
+    let rest = aes.finalize(&mut output[count..]).expect("AES finalize");
+
+    assert_eq!(count + rest, 16 + 16); // input len + block size
+}
+
+/// AES counter mode for both the 128 and 256 bit key profiles.
+pub struct OsslAesCmSha1_80(CipherCtx);
+
+impl OsslAesCmSha1_80 {
+    fn new_with(t: &cipher::CipherRef, key: &[u8], encrypt: bool) -> Self {
         let mut ctx = CipherCtx::new().expect("a reusable cipher context");
 
         if encrypt {
-            ctx.encrypt_init(Some(t), Some(&key[..]), None)
+            ctx.encrypt_init(Some(t), Some(key), None)
                 .expect("enc init");
         } else {
-            ctx.decrypt_init(Some(t), Some(&key[..]), None)
+            ctx.decrypt_init(Some(t), Some(key), None)
                 .expect("enc init");
         }
 
-        OsslAes128CmSha1_80(ctx)
+        OsslAesCmSha1_80(ctx)
     }
 
-    fn encrypt(
+    fn encrypt_inner(
         &mut self,
-        iv: &aes_128_cm_sha1_80::RtpIv,
+        iv: &[u8],
         input: &[u8],
         output: &mut [u8],
     ) -> Result<(), CryptoError> {
@@ -57,9 +64,9 @@ impl aes_128_cm_sha1_80::CipherCtx for OsslAes128CmSha1_80 {
         Ok(())
     }
 
-    fn decrypt(
+    fn decrypt_inner(
         &mut self,
-        iv: &aes_128_cm_sha1_80::RtpIv,
+        iv: &[u8],
         input: &[u8],
         output: &mut [u8],
     ) -> Result<(), CryptoError> {
@@ -70,42 +77,88 @@ impl aes_128_cm_sha1_80::CipherCtx for OsslAes128CmSha1_80 {
     }
 }
 
-pub struct OsslAeadAes128Gcm(CipherCtx);
+impl aes_128_cm_sha1_80::CipherCtx for OsslAesCmSha1_80 {
+    fn new(key: aes_128_cm_sha1_80::AesKey, encrypt: bool) -> Self
+    where
+        Self: Sized,
+    {
+        Self::new_with(cipher::Cipher::aes_128_ctr(), &key, encrypt)
+    }
 
-impl aead_aes_128_gcm::CipherCtx for OsslAeadAes128Gcm {
-    fn new(key: aead_aes_128_gcm::AeadKey, encrypt: bool) -> Self
+    fn encrypt(
+        &mut self,
+        iv: &aes_128_cm_sha1_80::RtpIv,
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<(), CryptoError> {
+        self.encrypt_inner(iv, input, output)
+    }
+
+    fn decrypt(
+        &mut self,
+        iv: &aes_128_cm_sha1_80::RtpIv,
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<(), CryptoError> {
+        self.decrypt_inner(iv, input, output)
+    }
+}
+
+impl aes_256_cm_sha1_80::CipherCtx for OsslAesCmSha1_80 {
+    fn new(key: aes_256_cm_sha1_80::AesKey, encrypt: bool) -> Self
     where
         Self: Sized,
     {
-        let t = cipher::Cipher::aes_128_gcm();
+        Self::new_with(cipher::Cipher::aes_256_ctr(), &key, encrypt)
+    }
+
+    fn encrypt(
+        &mut self,
+        iv: &aes_256_cm_sha1_80::RtpIv,
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<(), CryptoError> {
+        self.encrypt_inner(iv, input, output)
+    }
+
+    fn decrypt(
+        &mut self,
+        iv: &aes_256_cm_sha1_80::RtpIv,
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<(), CryptoError> {
+        self.decrypt_inner(iv, input, output)
+    }
+}
+
+/// AES-GCM for both the 128 and 256 bit key profiles.
+pub struct OsslAeadAesGcm(CipherCtx);
+
+impl OsslAeadAesGcm {
+    fn new_with(t: &cipher::CipherRef, key: &[u8], encrypt: bool) -> Self {
         let mut ctx = CipherCtx::new().expect("a reusable cipher context");
 
         if encrypt {
-            ctx.encrypt_init(Some(t), Some(&key), None)
+            ctx.encrypt_init(Some(t), Some(key), None)
                 .expect("enc init");
             ctx.set_iv_length(aead_aes_128_gcm::IV_LEN)
                 .expect("IV length");
             ctx.set_padding(false);
         } else {
-            ctx.decrypt_init(Some(t), Some(&key), None)
+            ctx.decrypt_init(Some(t), Some(key), None)
                 .expect("dec init");
         }
 
-        OsslAeadAes128Gcm(ctx)
+        OsslAeadAesGcm(ctx)
     }
 
-    fn encrypt(
+    fn encrypt_inner(
         &mut self,
         iv: &[u8; aead_aes_128_gcm::IV_LEN],
         aad: &[u8],
         input: &[u8],
         output: &mut [u8],
     ) -> Result<(), CryptoError> {
-        assert!(
-            aad.len() >= 12,
-            "Associated data length MUST be at least 12 octets"
-        );
-
         // Set the IV
         self.0.encrypt_init(None, None, Some(iv))?;
 
@@ -126,7 +179,7 @@ impl aead_aes_128_gcm::CipherCtx for OsslAeadAes128Gcm {
         Ok(())
     }
 
-    fn decrypt(
+    fn decrypt_inner(
         &mut self,
         iv: &[u8; aead_aes_128_gcm::IV_LEN],
         aads: &[&[u8]],
@@ -156,3 +209,61 @@ impl aead_aes_128_gcm::CipherCtx for OsslAeadAes128Gcm {
         Ok(count + final_count)
     }
 }
+
+impl aead_aes_128_gcm::CipherCtx for OsslAeadAesGcm {
+    fn new(key: aead_aes_128_gcm::AeadKey, encrypt: bool) -> Self
+    where
+        Self: Sized,
+    {
+        Self::new_with(cipher::Cipher::aes_128_gcm(), &key, encrypt)
+    }
+
+    fn encrypt(
+        &mut self,
+        iv: &[u8; aead_aes_128_gcm::IV_LEN],
+        aad: &[u8],
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<(), CryptoError> {
+        self.encrypt_inner(iv, aad, input, output)
+    }
+
+    fn decrypt(
+        &mut self,
+        iv: &[u8; aead_aes_128_gcm::IV_LEN],
+        aads: &[&[u8]],
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<usize, CryptoError> {
+        self.decrypt_inner(iv, aads, input, output)
+    }
+}
+
+impl aead_aes_256_gcm::CipherCtx for OsslAeadAesGcm {
+    fn new(key: aead_aes_256_gcm::AeadKey, encrypt: bool) -> Self
+    where
+        Self: Sized,
+    {
+        Self::new_with(cipher::Cipher::aes_256_gcm(), &key, encrypt)
+    }
+
+    fn encrypt(
+        &mut self,
+        iv: &[u8; aead_aes_256_gcm::IV_LEN],
+        aad: &[u8],
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<(), CryptoError> {
+        self.encrypt_inner(iv, aad, input, output)
+    }
+
+    fn decrypt(
+        &mut self,
+        iv: &[u8; aead_aes_256_gcm::IV_LEN],
+        aads: &[&[u8]],
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<usize, CryptoError> {
+        self.decrypt_inner(iv, aads, input, output)
+    }
+}
@@ -3,9 +3,9 @@ use std::{io, mem};
 
 use openssl::hash::MessageDigest;
 use openssl::srtp::SrtpProfileId;
-use openssl::ssl::{HandshakeError, MidHandshakeSslStream, Ssl, SslStream};
+use openssl::ssl::{HandshakeError, MidHandshakeSslStream, ShutdownState, Ssl, SslStream};
 
-use crate::crypto::{Fingerprint, KeyingMaterial, SrtpProfile};
+use crate::crypto::{key_log_line, Fingerprint, KeyingMaterial, SrtpProfile};
 
 use super::CryptoError;
 
@@ -14,8 +14,10 @@ const DTLS_KEY_LABEL: &str = "EXTRACTOR-dtls_srtp";
 pub struct TlsStream<S> {
     active: Option<bool>,
     state: State<S>,
-    keying_mat: Option<(KeyingMaterial, SrtpProfile, Fingerprint)>,
+    keying_mat: Option<(KeyingMaterial, SrtpProfile, Fingerprint, Vec<u8>)>,
     exported: bool,
+    key_log: bool,
+    key_log_lines: Vec<String>,
 }
 
 pub enum State<S> {
@@ -39,6 +41,8 @@ where
             state: State::Init(ssl, stream),
             keying_mat: None,
             exported: false,
+            key_log: false,
+            key_log_lines: vec![],
         }
     }
 
@@ -54,6 +58,18 @@ where
         self.active = Some(active);
     }
 
+    pub fn set_srtp_profiles(&mut self, profiles: &str) -> Result<(), CryptoError> {
+        let State::Init(ssl, _) = &mut self.state else {
+            panic!("set_srtp_profiles before handshake");
+        };
+        ssl.set_tlsext_use_srtp(profiles)?;
+        Ok(())
+    }
+
+    pub fn set_key_log(&mut self, enabled: bool) {
+        self.key_log = enabled;
+    }
+
     pub fn complete_handshake_until_block(&mut self) -> Result<bool, CryptoError> {
         if let Err(e) = self.handshaken() {
             if e.kind() == io::ErrorKind::WouldBlock {
@@ -81,6 +97,9 @@ where
         // first time we complete the handshake, we extract the keying material for SRTP.
         if !self.exported {
             let keying_mat = export_srtp_keying_material(v)?;
+            if self.key_log {
+                self.key_log_lines.push(client_random_line(v));
+            }
             self.exported = true;
             self.keying_mat = Some(keying_mat);
         }
@@ -90,10 +109,31 @@ where
 
     pub fn take_srtp_keying_material(
         &mut self,
-    ) -> Option<(KeyingMaterial, SrtpProfile, Fingerprint)> {
+    ) -> Option<(KeyingMaterial, SrtpProfile, Fingerprint, Vec<u8>)> {
         self.keying_mat.take()
     }
 
+    pub fn take_key_log(&mut self) -> Vec<String> {
+        mem::take(&mut self.key_log_lines)
+    }
+
+    /// Whether the remote sent a `close_notify`.
+    pub fn is_remote_closed(&mut self) -> bool {
+        let State::Established(v) = &mut self.state else {
+            return false;
+        };
+        v.get_shutdown().contains(ShutdownState::RECEIVED)
+    }
+
+    /// Send a `close_notify`, if the handshake is complete.
+    pub fn shutdown(&mut self) {
+        if let State::Established(v) = &mut self.state {
+            if let Err(e) = v.shutdown() {
+                debug!("DTLS shutdown failed: {:?}", e);
+            }
+        }
+    }
+
     pub fn inner_mut(&mut self) -> &mut S {
         match &mut self.state {
             State::Init(_, s) => s,
@@ -159,7 +199,7 @@ where
 
 fn export_srtp_keying_material<S>(
     stream: &mut SslStream<S>,
-) -> Result<(KeyingMaterial, SrtpProfile, Fingerprint), io::Error> {
+) -> Result<(KeyingMaterial, SrtpProfile, Fingerprint, Vec<u8>), io::Error> {
     let ssl = stream.ssl();
 
     // remote peer certificate fingerprint
@@ -172,6 +212,7 @@ fn export_srtp_keying_material<S>(
         hash_func: "sha-256".into(),
         bytes: digest.to_vec(),
     };
+    let der = x509.to_der()?;
 
     let srtp_profile_id = ssl
         .selected_srtp_profile()
@@ -185,7 +226,23 @@ fn export_srtp_keying_material<S>(
 
     let mat = KeyingMaterial::new(buf);
 
-    Ok((mat, srtp_profile, fp))
+    Ok((mat, srtp_profile, fp, der))
+}
+
+/// The DTLS 1.2 master secret as a `CLIENT_RANDOM` key log line.
+fn client_random_line<S>(stream: &mut SslStream<S>) -> String {
+    let ssl = stream.ssl();
+
+    let mut client_random = [0; 32];
+    let n = ssl.client_random(&mut client_random);
+
+    let mut master_key = [0; 48];
+    let m = ssl
+        .session()
+        .map(|s| s.master_key(&mut master_key))
+        .unwrap_or(0);
+
+    key_log_line("CLIENT_RANDOM", &client_random[..n], &master_key[..m])
 }
 
 impl<S> io::Read for TlsStream<S>
@@ -217,6 +274,7 @@ impl TryFrom<SrtpProfileId> for SrtpProfile {
         match value {
             SrtpProfileId::SRTP_AES128_CM_SHA1_80 => Ok(SrtpProfile::Aes128CmSha1_80),
             SrtpProfileId::SRTP_AEAD_AES_128_GCM => Ok(SrtpProfile::AeadAes128Gcm),
+            SrtpProfileId::SRTP_AEAD_AES_256_GCM => Ok(SrtpProfile::AeadAes256Gcm),
             x => Err(io::Error::new(
                 io::ErrorKind::Other,
                 format!("Unsupported SRTP profile {:x}", x.as_raw()),
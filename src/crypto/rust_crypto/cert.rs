@@ -0,0 +1,297 @@
+use std::fmt;
+use std::time::SystemTime;
+
+use p256::ecdsa::signature::Signer;
+use p256::ecdsa::{DerSignature, SigningKey, VerifyingKey};
+use rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+
+use crate::crypto::dtls::{DtlsCertOptions, DtlsPKeyType};
+use crate::crypto::Fingerprint;
+
+use super::{CryptoError, RustCryptoDtlsImpl, RustCryptoError};
+
+const OID_ECDSA_WITH_SHA256: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02];
+const OID_EC_PUBLIC_KEY: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+const OID_PRIME256V1: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+const OID_COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+
+const TAG_INTEGER: u8 = 0x02;
+const TAG_BIT_STRING: u8 = 0x03;
+const TAG_OID: u8 = 0x06;
+const TAG_UTF8_STRING: u8 = 0x0c;
+const TAG_UTC_TIME: u8 = 0x17;
+const TAG_GENERALIZED_TIME: u8 = 0x18;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_SET: u8 = 0x31;
+const TAG_EXPLICIT_0: u8 = 0xa0;
+
+/// Certificate used for DTLS.
+#[derive(Clone)]
+pub struct RustCryptoDtlsCert {
+    pub(crate) key: SigningKey,
+    pub(crate) der: Vec<u8>,
+}
+
+impl RustCryptoDtlsCert {
+    /// Creates a new (self signed) DTLS certificate.
+    ///
+    /// [`DtlsPKeyType::Rsa2048`] is not supported by this provider and falls
+    /// back to an EcDsaP256 certificate.
+    pub fn new(options: DtlsCertOptions) -> Self {
+        if let DtlsPKeyType::Rsa2048 = options.pkey_type {
+            warn!("rust-crypto does not support Rsa2048, using EcDsaP256");
+        }
+
+        let key = SigningKey::random(&mut OsRng);
+        let der = self_signed(&key, &options.common_name);
+
+        RustCryptoDtlsCert { key, der }
+    }
+
+    /// Produce a (public) fingerprint of the cert.
+    ///
+    /// This is sent via SDP to the other peer to lock down the DTLS
+    /// to this specific certificate.
+    pub fn fingerprint(&self) -> Fingerprint {
+        fingerprint(&self.der)
+    }
+
+    pub fn certificate_der(&self) -> Option<Vec<u8>> {
+        Some(self.der.clone())
+    }
+
+    pub(crate) fn new_dtls_impl(&self) -> Result<Box<RustCryptoDtlsImpl>, CryptoError> {
+        Ok(Box::new(RustCryptoDtlsImpl::new(self.clone())))
+    }
+}
+
+impl fmt::Debug for RustCryptoDtlsCert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RustCryptoDtlsCert")
+            .field("fingerprint", &self.fingerprint())
+            .finish()
+    }
+}
+
+pub(crate) fn fingerprint(der: &[u8]) -> Fingerprint {
+    Fingerprint {
+        hash_func: "sha-256".into(),
+        bytes: Sha256::digest(der).to_vec(),
+    }
+}
+
+// The libWebRTC code we try to match is at:
+// https://webrtc.googlesource.com/src/+/1568f1b1330f94494197696fe235094e6293b258/rtc_base/openssl_certificate.cc#58
+fn self_signed(key: &SigningKey, common_name: &str) -> Vec<u8> {
+    let sig_alg = der(TAG_SEQUENCE, &der(TAG_OID, OID_ECDSA_WITH_SHA256));
+
+    // For Firefox, the serial number must be unique across all certificates, including those of other
+    // processes/machines! See https://github.com/versatica/mediasoup/issues/127#issuecomment-474460153
+    // and https://github.com/algesten/str0m/issues/517
+    let mut serial = [0_u8; 16];
+    OsRng.fill_bytes(&mut serial);
+    // Positive and no leading zero byte.
+    serial[0] = (serial[0] & 0x7f) | 0x40;
+
+    let name = der(
+        TAG_SEQUENCE,
+        &der(
+            TAG_SET,
+            &der(
+                TAG_SEQUENCE,
+                &[
+                    der(TAG_OID, OID_COMMON_NAME),
+                    der(TAG_UTF8_STRING, common_name.as_bytes()),
+                ]
+                .concat(),
+            ),
+        ),
+    );
+
+    let now = unix_time();
+    let validity = der(
+        TAG_SEQUENCE,
+        &[time(now - 3600), time(now + 7 * 24 * 3600)].concat(),
+    );
+
+    let point = VerifyingKey::from(key).to_encoded_point(false);
+    let spki = der(
+        TAG_SEQUENCE,
+        &[
+            der(
+                TAG_SEQUENCE,
+                &[
+                    der(TAG_OID, OID_EC_PUBLIC_KEY),
+                    der(TAG_OID, OID_PRIME256V1),
+                ]
+                .concat(),
+            ),
+            bit_string(point.as_bytes()),
+        ]
+        .concat(),
+    );
+
+    let tbs = der(
+        TAG_SEQUENCE,
+        &[
+            // X509.V3 (zero indexed)
+            der(TAG_EXPLICIT_0, &der(TAG_INTEGER, &[2])),
+            der(TAG_INTEGER, &serial),
+            sig_alg.clone(),
+            name.clone(),
+            validity,
+            name,
+            spki,
+        ]
+        .concat(),
+    );
+
+    let signature: DerSignature = key.sign(&tbs);
+
+    der(
+        TAG_SEQUENCE,
+        &[tbs, sig_alg, bit_string(signature.as_bytes())].concat(),
+    )
+}
+
+/// Extract the public key of a P-256 ECDSA certificate.
+pub(crate) fn public_key(cert: &[u8]) -> Result<VerifyingKey, RustCryptoError> {
+    let err = RustCryptoError::Certificate;
+
+    let (_, cert, _) = read_tlv(cert, TAG_SEQUENCE).ok_or(err("not a certificate"))?;
+    let (_, mut tbs, _) = read_tlv(cert, TAG_SEQUENCE).ok_or(err("no tbsCertificate"))?;
+
+    // Optional version
+    if tbs.first() == Some(&TAG_EXPLICIT_0) {
+        tbs = skip_tlv(tbs).ok_or(err("bad version"))?;
+    }
+
+    // serialNumber, signature, issuer, validity, subject
+    for _ in 0..5 {
+        tbs = skip_tlv(tbs).ok_or(err("truncated tbsCertificate"))?;
+    }
+
+    let (_, spki, _) = read_tlv(tbs, TAG_SEQUENCE).ok_or(err("no subjectPublicKeyInfo"))?;
+    let (_, alg, rest) = read_tlv(spki, TAG_SEQUENCE).ok_or(err("no algorithm"))?;
+    let (_, alg_oid, params) = read_tlv(alg, TAG_OID).ok_or(err("no algorithm oid"))?;
+    let (_, curve_oid, _) = read_tlv(params, TAG_OID).ok_or(err("no curve oid"))?;
+
+    if alg_oid != OID_EC_PUBLIC_KEY || curve_oid != OID_PRIME256V1 {
+        return Err(err("only ECDSA P-256 is supported"));
+    }
+
+    let (_, bits, _) = read_tlv(rest, TAG_BIT_STRING).ok_or(err("no public key"))?;
+    if bits.first() != Some(&0) {
+        return Err(err("bad public key bit string"));
+    }
+
+    VerifyingKey::from_sec1_bytes(&bits[1..]).map_err(|_| err("bad public key"))
+}
+
+fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+    let len = content.len();
+    let mut out = Vec::with_capacity(len + 4);
+    out.push(tag);
+    if len < 0x80 {
+        out.push(len as u8);
+    } else if len < 0x100 {
+        out.extend_from_slice(&[0x81, len as u8]);
+    } else {
+        out.extend_from_slice(&[0x82, (len >> 8) as u8, len as u8]);
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+fn bit_string(bytes: &[u8]) -> Vec<u8> {
+    der(TAG_BIT_STRING, &[&[0][..], bytes].concat())
+}
+
+/// Read a TLV, returning (tag, content, rest). `None` if the tag does not match.
+fn read_tlv(buf: &[u8], expect: u8) -> Option<(u8, &[u8], &[u8])> {
+    let tag = *buf.first()?;
+    if tag != expect {
+        return None;
+    }
+    let (len, header) = match *buf.get(1)? {
+        n if n < 0x80 => (n as usize, 2),
+        0x81 => (*buf.get(2)? as usize, 3),
+        0x82 => (((*buf.get(2)? as usize) << 8) | *buf.get(3)? as usize, 4),
+        _ => return None,
+    };
+    let content = buf.get(header..header + len)?;
+    Some((tag, content, &buf[header + len..]))
+}
+
+fn skip_tlv(buf: &[u8]) -> Option<&[u8]> {
+    let tag = *buf.first()?;
+    read_tlv(buf, tag).map(|(_, _, rest)| rest)
+}
+
+fn time(unix: i64) -> Vec<u8> {
+    let days = unix.div_euclid(86_400);
+    let secs = unix.rem_euclid(86_400);
+    let (y, m, d) = civil_from_days(days);
+    let (hh, mm, ss) = (secs / 3600, (secs / 60) % 60, secs % 60);
+
+    // RFC 5280 4.1.2.5: UTCTime up to 2049, GeneralizedTime after.
+    if y < 2050 {
+        let s = format!("{:02}{m:02}{d:02}{hh:02}{mm:02}{ss:02}Z", y % 100);
+        der(TAG_UTC_TIME, s.as_bytes())
+    } else {
+        let s = format!("{y:04}{m:02}{d:02}{hh:02}{mm:02}{ss:02}Z");
+        der(TAG_GENERALIZED_TIME, s.as_bytes())
+    }
+}
+
+// Howard Hinnant's days to civil date algorithm.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    (y, m, d)
+}
+
+// See the same TODO for the OpenSSL certificate.
+fn unix_time() -> i64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn self_signed_has_public_key() {
+        let cert = RustCryptoDtlsCert::new(DtlsCertOptions::default());
+        let key = public_key(&cert.der).unwrap();
+        assert_eq!(key, VerifyingKey::from(&cert.key));
+    }
+
+    #[test]
+    fn rsa_falls_back_to_p256() {
+        let options = DtlsCertOptions {
+            pkey_type: DtlsPKeyType::Rsa2048,
+            ..Default::default()
+        };
+        let cert = RustCryptoDtlsCert::new(options);
+        assert!(public_key(&cert.der).is_ok());
+    }
+
+    #[test]
+    fn civil_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(19_723), (2024, 1, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+    }
+}
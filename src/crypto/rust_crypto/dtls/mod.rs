@@ -0,0 +1,2010 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::io;
+use std::time::{Duration, Instant};
+
+use aes_gcm::aead::AeadInPlace;
+use aes_gcm::{Aes128Gcm, KeyInit, Nonce, Tag};
+use p256::ecdh::EphemeralSecret;
+use p256::ecdsa::signature::{Signer, Verifier};
+use p256::ecdsa::{DerSignature, Signature, VerifyingKey};
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use p256::PublicKey;
+use rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+use crate::crypto::dtls::DtlsInner;
+use crate::crypto::{
+    key_log_line, CryptoError, Dtls13Mode, DtlsAlert, DtlsEvent, KeyingMaterial, SrtpProfile,
+};
+use crate::io::{DATAGRAM_MTU, DATAGRAM_MTU_WARN};
+use crate::net::DatagramSend;
+
+use super::cert::{fingerprint, public_key, RustCryptoDtlsCert};
+use super::hkdf::HASH_LEN;
+use super::message::*;
+use super::prf::prf_sha256;
+use super::RustCryptoError;
+
+mod v13;
+use v13::{EpochKeys, DOWNGRADE_SENTINEL, HELLO_RETRY_REQUEST_RANDOM};
+use v13::{UNIFIED_HEADER_BITS, UNIFIED_HEADER_MASK};
+
+const KEY_LEN: usize = 16;
+const FIXED_IV_LEN: usize = 4;
+const EXPLICIT_NONCE_LEN: usize = 8;
+const TAG_LEN: usize = 16;
+const MASTER_SECRET_LEN: usize = 48;
+const VERIFY_DATA_LEN: usize = 12;
+
+/// Max size of plaintext in a record.
+const MAX_RECORD_PLAINTEXT: usize = 16_384;
+
+/// How far ahead of the next expected handshake message we buffer.
+const MAX_HANDSHAKE_AHEAD: u16 = 8;
+
+/// Largest handshake message we reassemble. A WebRTC handshake has nothing
+/// bigger than a certificate of a few KiB.
+const MAX_HANDSHAKE_MESSAGE: usize = 16_384;
+
+const INITIAL_RTO: Duration = Duration::from_secs(1);
+const MAX_RTO: Duration = Duration::from_secs(60);
+
+const ALERT_LEVEL_WARNING: u8 = 1;
+const ALERT_LEVEL_FATAL: u8 = 2;
+const ALERT_CLOSE_NOTIFY: u8 = 0;
+const ALERT_HANDSHAKE_FAILURE: u8 = 40;
+const ALERT_BAD_CERTIFICATE: u8 = 42;
+const ALERT_DECODE_ERROR: u8 = 50;
+
+/// DTLS 1.2 and 1.3 implementation using the RustCrypto crates.
+pub struct RustCryptoDtlsImpl {
+    cert: RustCryptoDtlsCert,
+
+    /// Set once by `set_active`.
+    active: Option<bool>,
+
+    /// Whether to offer/accept DTLS 1.3.
+    dtls13: Dtls13Mode,
+
+    /// SRTP profiles to offer/accept, most preferred first.
+    srtp_profiles: Vec<SrtpProfile>,
+
+    /// Whether to emit the handshake secrets as `DtlsEvent::KeyLog`.
+    key_log: bool,
+
+    /// Negotiated version. DTLS 1.2 until a DTLS 1.3 ServerHello.
+    version: Version,
+
+    state: State,
+
+    /// Handshake parameters accumulated during the handshake.
+    hs: Box<Handshake>,
+
+    /// Keys once derived from the master secret.
+    keys: Option<Box<Keys>>,
+
+    /// DTLS 1.3 keys per epoch, 2 for handshake and 3 for application data.
+    read13: [Option<Box<EpochKeys>>; 4],
+    write13: [Option<Box<EpochKeys>>; 4],
+
+    /// Epoch of incoming records we currently accept encrypted.
+    read_epoch: u16,
+
+    /// Next record sequence number per write epoch.
+    write_seq: [u64; 4],
+
+    /// Replay protection per epoch.
+    replay: [ReplayWindow; 4],
+
+    /// The last flight sent. Kept for retransmission.
+    flight: Vec<FlightItem>,
+
+    /// Record numbers of each transmission of the last flight. Used to
+    /// match DTLS 1.3 ACKs.
+    flight_records: Vec<Vec<(u64, u64)>>,
+
+    /// Whether the last flight is retransmitted on timer.
+    ///
+    /// The final flight of the handshake is only retransmitted when the
+    /// remote peer retransmits its last flight. The exception is the DTLS 1.3
+    /// client, which retransmits its final flight until ACKed.
+    flight_timer: bool,
+
+    /// When the flight is to be retransmitted.
+    flight_timeout: Option<Instant>,
+
+    /// Current retransmission timeout.
+    rto: Duration,
+
+    /// Datagrams ready to be sent.
+    outgoing: VecDeque<DatagramSend>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Version {
+    Dtls12,
+    Dtls13,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Before set_active.
+    Init,
+    /// Client that has yet to send the ClientHello.
+    ClientStart,
+    /// Client waiting for ServerHello through ServerHelloDone, or
+    /// ServerHello through Finished for DTLS 1.3.
+    ClientWaitServerFlight,
+    /// Client waiting for the server ChangeCipherSpec and Finished.
+    ClientWaitFinished,
+    /// DTLS 1.3 client waiting for the server to ACK its Finished.
+    ClientWaitAck,
+    /// Server waiting for the ClientHello.
+    ServerWaitClientHello,
+    /// Server waiting for Certificate through Finished from client.
+    ServerWaitClientFlight,
+    /// Handshake is complete.
+    Connected,
+}
+
+#[derive(Default)]
+struct Handshake {
+    /// All handshake messages, as input to Finished, CertificateVerify
+    /// and the extended master secret.
+    transcript: Vec<u8>,
+
+    /// The same messages in the DTLS 1.3 transcript form. Both are kept
+    /// since the version isn't known until ServerHello.
+    transcript13: Vec<u8>,
+
+    client_random: [u8; 32],
+    server_random: [u8; 32],
+
+    /// Cookie from a HelloVerifyRequest.
+    cookie: Vec<u8>,
+
+    srtp_profile: Option<SrtpProfile>,
+    extended_master_secret: bool,
+    renegotiation_info: bool,
+
+    ecdh: Option<EphemeralSecret>,
+    remote_ecdh_public: Option<PublicKey>,
+
+    /// Our DTLS 1.3 key_share, kept for a ClientHello resent with a cookie.
+    key_share: Vec<u8>,
+
+    /// A HelloRetryRequest was sent or received (DTLS 1.3).
+    hello_retry: bool,
+
+    /// Cookie from a HelloRetryRequest.
+    retry_cookie: Vec<u8>,
+
+    remote_cert: Option<Vec<u8>>,
+    remote_key: Option<VerifyingKey>,
+
+    /// Remote CertificateVerify checked out.
+    remote_verified: bool,
+
+    /// Last handshake message handled, to enforce the message order.
+    last_msg: Option<u8>,
+
+    /// Server sent CertificateRequest.
+    cert_requested: bool,
+
+    /// The certificate_request_context to echo (DTLS 1.3).
+    cert_request_context: Vec<u8>,
+
+    master_secret: Option<[u8; MASTER_SECRET_LEN]>,
+
+    /// DTLS 1.3 key schedule.
+    handshake_secret: [u8; HASH_LEN],
+    client_hs_secret: [u8; HASH_LEN],
+    server_hs_secret: [u8; HASH_LEN],
+    exporter_secret: Option<[u8; HASH_LEN]>,
+
+    /// Key log lines, if enabled, emitted once connected.
+    key_log: Vec<String>,
+
+    /// Next message_seq to send.
+    send_seq: u16,
+
+    /// Next message_seq expected.
+    recv_seq: u16,
+
+    /// Epoch of our outgoing handshake messages (DTLS 1.3).
+    write_epoch: u16,
+
+    /// Lowest epoch accepted for new handshake messages.
+    recv_epoch: u16,
+
+    /// Record numbers of received handshake records to ACK (DTLS 1.3).
+    acks: Vec<(u64, u64)>,
+
+    /// Incoming handshake messages being reassembled.
+    incoming: BTreeMap<u16, Reassembly>,
+
+    /// Remote sent ChangeCipherSpec.
+    remote_ccs: bool,
+
+    /// Events are yet to be emitted for the completed handshake.
+    emit_connected: bool,
+}
+
+impl Handshake {
+    fn add_transcript(&mut self, msg_type: u8, message_seq: u16, body: &[u8]) {
+        let message = handshake_message(msg_type, message_seq, body);
+        self.transcript.extend_from_slice(&message);
+        self.transcript13
+            .extend_from_slice(&tls13_message(msg_type, body));
+    }
+}
+
+struct Reassembly {
+    msg_type: u8,
+    body: Vec<u8>,
+    received: Vec<bool>,
+}
+
+impl Reassembly {
+    fn is_complete(&self) -> bool {
+        self.received.iter().all(|r| *r)
+    }
+}
+
+struct Keys {
+    client_write: Aes128Gcm,
+    server_write: Aes128Gcm,
+    client_iv: [u8; FIXED_IV_LEN],
+    server_iv: [u8; FIXED_IV_LEN],
+}
+
+enum FlightItem {
+    Handshake {
+        epoch: u16,
+        message: Vec<u8>,
+    },
+    ChangeCipherSpec,
+    /// DTLS 1.3 ACK of the records in the remote's final flight.
+    Ack(Vec<(u64, u64)>),
+}
+
+/// A decrypted record.
+struct Record {
+    content_type: u8,
+    epoch: u16,
+    seq: u64,
+    payload: Vec<u8>,
+}
+
+impl RustCryptoDtlsImpl {
+    pub fn new(cert: RustCryptoDtlsCert) -> Self {
+        RustCryptoDtlsImpl {
+            cert,
+            active: None,
+            dtls13: Dtls13Mode::default(),
+            srtp_profiles: SrtpProfile::DEFAULT.to_vec(),
+            key_log: false,
+            version: Version::Dtls12,
+            state: State::Init,
+            hs: Box::default(),
+            keys: None,
+            read13: Default::default(),
+            write13: Default::default(),
+            read_epoch: 0,
+            write_seq: [0; 4],
+            replay: Default::default(),
+            flight: vec![],
+            flight_records: vec![],
+            flight_timer: false,
+            flight_timeout: None,
+            rto: INITIAL_RTO,
+            outgoing: VecDeque::new(),
+        }
+    }
+
+    fn is_client(&self) -> bool {
+        self.active == Some(true)
+    }
+
+    /// The epoch of application data records.
+    fn app_epoch(&self) -> u16 {
+        match self.version {
+            Version::Dtls12 => 1,
+            Version::Dtls13 => 3,
+        }
+    }
+
+    fn handle_datagram(
+        &mut self,
+        mut buf: &[u8],
+        o: &mut VecDeque<DtlsEvent>,
+    ) -> Result<(), RustCryptoError> {
+        let mut retransmit = false;
+
+        while !buf.is_empty() {
+            // DTLS 1.3 encrypted records use the unified header (RFC 9147 4).
+            let read = if buf[0] & UNIFIED_HEADER_MASK == UNIFIED_HEADER_BITS {
+                self.read_record13(buf)
+            } else {
+                self.read_record(buf)
+            };
+            // Invalid records are silently discarded (RFC 6347 4.1.2.7, RFC 9147 4.5.2).
+            // Without a valid header we can't tell where the next record starts.
+            let (record, rest) = match read {
+                Ok(v) => v,
+                Err(e) => {
+                    trace!("Drop rest of DTLS datagram: {}", e);
+                    break;
+                }
+            };
+            buf = rest;
+
+            let Some(Record {
+                content_type,
+                epoch,
+                seq,
+                payload,
+            }) = record
+            else {
+                continue;
+            };
+            let payload = &payload[..];
+
+            if epoch == 3 && self.state == State::ClientWaitAck {
+                // Only a server that got our Finished sends in epoch 3.
+                self.set_connected();
+            }
+
+            match content_type {
+                CONTENT_HANDSHAKE => {
+                    retransmit |= self.handle_handshake_record(epoch, seq, payload)?;
+                }
+                CONTENT_CHANGE_CIPHER_SPEC => {
+                    // Only accepted once we have the keys to decrypt the following records.
+                    if epoch == 0 && self.keys.is_some() && !self.hs.remote_ccs {
+                        self.hs.remote_ccs = true;
+                        self.read_epoch = 1;
+                        self.hs.recv_epoch = 1;
+                    }
+                }
+                CONTENT_ACK => {
+                    if self.version == Version::Dtls13 && epoch >= 2 {
+                        self.handle_ack(payload)?;
+                    }
+                }
+                CONTENT_ALERT => {
+                    let &[level, description] = payload else {
+                        trace!("Drop DTLS alert of length: {}", payload.len());
+                        continue;
+                    };
+                    let ends = description == ALERT_CLOSE_NOTIFY || level == ALERT_LEVEL_FATAL;
+                    if ends && self.is_connected() && epoch == self.app_epoch() {
+                        debug!("DTLS alert from remote ends connection: {}", description);
+                        o.push_back(DtlsEvent::Alert(DtlsAlert(description)));
+                    } else if description == ALERT_CLOSE_NOTIFY {
+                        debug!("DTLS close_notify from remote");
+                    } else if level == ALERT_LEVEL_FATAL && (epoch > 0 || !self.is_connected()) {
+                        return Err(RustCryptoError::Alert(description));
+                    } else {
+                        debug!("DTLS alert from remote: {} {}", level, description);
+                    }
+                }
+                CONTENT_APPLICATION_DATA => {
+                    if epoch == self.app_epoch() && self.is_connected() {
+                        o.push_back(DtlsEvent::Data(payload.to_vec()));
+                    }
+                }
+                _ => trace!("Drop DTLS record of unknown type: {}", content_type),
+            }
+
+            self.process_handshake(o)?;
+        }
+
+        if retransmit {
+            debug!("Retransmit DTLS flight on remote retransmission");
+            self.send_flight();
+        }
+
+        Ok(())
+    }
+
+    /// Read a record with the DTLS 1.2 header, which is also used for
+    /// plaintext records in DTLS 1.3.
+    fn read_record<'a>(
+        &mut self,
+        buf: &'a [u8],
+    ) -> Result<(Option<Record>, &'a [u8]), RustCryptoError> {
+        let mut r = Reader::new(buf);
+        let content_type = r.u8()?;
+        let _version = r.bytes(2)?;
+        let epoch = r.u16()?;
+        let seq = r.u48()?;
+        let fragment = r.vec16()?;
+        let rest = r.rest();
+
+        let payload = match epoch {
+            0 => fragment.to_vec(),
+            1 if self.read_epoch == 1 => {
+                if !self.replay[1].check(seq) {
+                    trace!("Drop replayed DTLS record: {}", seq);
+                    return Ok((None, rest));
+                }
+                let plain = match self.decrypt_record(content_type, epoch, seq, fragment) {
+                    Ok(v) => v,
+                    Err(e) => {
+                        debug!("Drop DTLS record: {}", e);
+                        return Ok((None, rest));
+                    }
+                };
+                self.replay[1].update(seq);
+                plain
+            }
+            _ => {
+                trace!("Drop DTLS record with epoch: {}", epoch);
+                return Ok((None, rest));
+            }
+        };
+
+        let record = Record {
+            content_type,
+            epoch,
+            seq,
+            payload,
+        };
+        Ok((Some(record), rest))
+    }
+
+    /// Buffer handshake fragments. Returns true if the remote retransmitted
+    /// an earlier flight.
+    fn handle_handshake_record(
+        &mut self,
+        epoch: u16,
+        seq: u64,
+        mut payload: &[u8],
+    ) -> Result<bool, RustCryptoError> {
+        let mut retransmit = false;
+
+        while !payload.is_empty() {
+            let mut r = Reader::new(payload);
+            let fragment =
+                HandshakeHeader::parse(&mut r).and_then(|h| Ok((h, r.bytes(h.fragment_length)?)));
+            let (h, data) = match fragment {
+                Ok(v) => v,
+                // Plaintext records are not authenticated, anyone could have sent them.
+                Err(e) if epoch == 0 => {
+                    trace!("Drop DTLS handshake record: {}", e);
+                    break;
+                }
+                Err(e) => return Err(e),
+            };
+            payload = r.rest();
+
+            if h.message_seq < self.hs.recv_seq {
+                // The remote didn't get our last flight.
+                retransmit = !self.flight.is_empty();
+                continue;
+            }
+
+            if h.message_seq >= self.hs.recv_seq + MAX_HANDSHAKE_AHEAD {
+                continue;
+            }
+
+            if h.length > MAX_HANDSHAKE_MESSAGE {
+                debug!("Drop DTLS handshake message of length: {}", h.length);
+                continue;
+            }
+
+            // Once the remote has switched epoch, new handshake
+            // messages must be encrypted.
+            if epoch < self.hs.recv_epoch {
+                continue;
+            }
+
+            // The server ACKs the client's final flight.
+            let record = (epoch as u64, seq);
+            if epoch >= 2 && !self.is_client() && !self.hs.acks.contains(&record) {
+                self.hs.acks.push(record);
+            }
+
+            let entry = self
+                .hs
+                .incoming
+                .entry(h.message_seq)
+                .or_insert_with(|| Reassembly {
+                    msg_type: h.msg_type,
+                    body: vec![0; h.length],
+                    received: vec![false; h.length],
+                });
+
+            if entry.msg_type != h.msg_type || entry.body.len() != h.length {
+                if epoch == 0 {
+                    trace!("Drop inconsistent DTLS handshake fragment");
+                    break;
+                }
+                return Err(RustCryptoError::Decode("inconsistent handshake fragment"));
+            }
+
+            let range = h.fragment_offset..h.fragment_offset + h.fragment_length;
+            entry.body[range.clone()].copy_from_slice(data);
+            entry.received[range].iter_mut().for_each(|r| *r = true);
+        }
+
+        Ok(retransmit)
+    }
+
+    /// Process complete handshake messages in order.
+    fn process_handshake(&mut self, o: &mut VecDeque<DtlsEvent>) -> Result<(), RustCryptoError> {
+        loop {
+            let seq = self.hs.recv_seq;
+            let Some(entry) = self.hs.incoming.get(&seq) else {
+                break;
+            };
+            if !entry.is_complete() {
+                break;
+            }
+
+            // Finished can only be processed after ChangeCipherSpec.
+            if entry.msg_type == FINISHED && self.version == Version::Dtls12 && !self.hs.remote_ccs
+            {
+                break;
+            }
+
+            let entry = self.hs.incoming.remove(&seq).unwrap();
+            self.hs.recv_seq += 1;
+
+            self.handle_message(seq, entry.msg_type, entry.body)?;
+        }
+
+        if self.hs.emit_connected {
+            self.hs.emit_connected = false;
+            self.emit_connected(o);
+        }
+
+        Ok(())
+    }
+
+    fn handle_message(
+        &mut self,
+        seq: u16,
+        msg_type: u8,
+        body: Vec<u8>,
+    ) -> Result<(), RustCryptoError> {
+        use State::*;
+
+        trace!(
+            "Handle DTLS handshake message: {} {:?}",
+            msg_type,
+            self.state
+        );
+
+        if self.version == Version::Dtls13 {
+            return self.handle_message13(msg_type, seq, body);
+        }
+
+        if !self.is_expected12(msg_type) {
+            return Err(RustCryptoError::Handshake("unexpected handshake message"));
+        }
+        self.hs.last_msg = Some(msg_type);
+
+        match (self.state, msg_type) {
+            (ClientWaitServerFlight, HELLO_VERIFY_REQUEST) => {
+                let hvr = HelloVerifyRequest::parse(&body)?;
+                // RFC 6347 4.2.1: The initial ClientHello and HelloVerifyRequest
+                // are not part of the handshake transcript.
+                self.hs.transcript.clear();
+                self.hs.transcript13.clear();
+                self.hs.cookie = hvr.cookie;
+                self.send_client_hello();
+                return Ok(());
+            }
+            (ClientWaitServerFlight, SERVER_HELLO)
+                if body.get(2..34) == Some(&HELLO_RETRY_REQUEST_RANDOM[..]) =>
+            {
+                return self.handle_hello_retry_request(seq, &body);
+            }
+            (ClientWaitServerFlight, _) => {
+                self.hs.add_transcript(msg_type, seq, &body);
+                self.handle_server_flight(msg_type, &body)?;
+            }
+            (ClientWaitFinished, FINISHED) => {
+                self.verify_finished(b"server finished", &body)?;
+                self.hs.add_transcript(msg_type, seq, &body);
+                self.set_connected();
+            }
+            (ServerWaitClientHello, CLIENT_HELLO) => {
+                self.hs.add_transcript(msg_type, seq, &body);
+                self.handle_client_hello(&body)?;
+            }
+            (ServerWaitClientFlight, CERTIFICATE_VERIFY) => {
+                // Signature is over the transcript prior to CertificateVerify.
+                let (sig_alg, signature) = parse_digitally_signed(&body)?;
+                self.verify_signature(sig_alg, &signature, &[&self.hs.transcript])?;
+                self.hs.remote_verified = true;
+                self.hs.add_transcript(msg_type, seq, &body);
+            }
+            (ServerWaitClientFlight, FINISHED) => {
+                if !self.hs.remote_verified {
+                    return Err(RustCryptoError::Handshake("client sent no certificate"));
+                }
+                self.verify_finished(b"client finished", &body)?;
+                self.hs.add_transcript(msg_type, seq, &body);
+                self.send_server_finished();
+                self.set_connected();
+            }
+            (ServerWaitClientFlight, _) => {
+                self.hs.add_transcript(msg_type, seq, &body);
+                self.handle_client_flight(msg_type, &body)?;
+            }
+            _ => {
+                return Err(RustCryptoError::Handshake("unexpected handshake message"));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether a DTLS 1.2 message may follow the previous one.
+    ///
+    /// Client: ServerHello, Certificate, ServerKeyExchange, CertificateRequest?,
+    /// ServerHelloDone, Finished. Server: ClientHello, Certificate,
+    /// ClientKeyExchange, CertificateVerify, Finished.
+    fn is_expected12(&self, msg_type: u8) -> bool {
+        use State::*;
+
+        let last = self.hs.last_msg;
+
+        match self.state {
+            ClientWaitServerFlight => matches!(
+                (last, msg_type),
+                (
+                    None | Some(HELLO_VERIFY_REQUEST),
+                    HELLO_VERIFY_REQUEST | SERVER_HELLO
+                ) | (Some(SERVER_HELLO), CERTIFICATE)
+                    | (Some(CERTIFICATE), SERVER_KEY_EXCHANGE)
+                    | (
+                        Some(SERVER_KEY_EXCHANGE),
+                        CERTIFICATE_REQUEST | SERVER_HELLO_DONE
+                    )
+                    | (Some(CERTIFICATE_REQUEST), SERVER_HELLO_DONE)
+            ),
+            ClientWaitFinished => matches!((last, msg_type), (Some(SERVER_HELLO_DONE), FINISHED)),
+            ServerWaitClientHello => msg_type == CLIENT_HELLO,
+            ServerWaitClientFlight => matches!(
+                (last, msg_type),
+                (Some(CLIENT_HELLO), CERTIFICATE)
+                    | (Some(CERTIFICATE), CLIENT_KEY_EXCHANGE)
+                    | (Some(CLIENT_KEY_EXCHANGE), CERTIFICATE_VERIFY)
+                    | (Some(CERTIFICATE_VERIFY), FINISHED)
+            ),
+            _ => false,
+        }
+    }
+
+    fn handle_server_flight(&mut self, msg_type: u8, body: &[u8]) -> Result<(), RustCryptoError> {
+        match msg_type {
+            SERVER_HELLO => {
+                let sh = ServerHello::parse(body)?;
+                if sh.extensions.supported_versions == [DTLS_1_3_VERSION] {
+                    if self.dtls13 == Dtls13Mode::Disabled {
+                        return Err(RustCryptoError::Handshake("DTLS 1.3 not offered"));
+                    }
+                    return self.handle_server_hello13(sh);
+                }
+                if self.hs.hello_retry {
+                    return Err(RustCryptoError::Handshake(
+                        "DTLS 1.2 after HelloRetryRequest",
+                    ));
+                }
+                match self.dtls13 {
+                    Dtls13Mode::Require => {
+                        return Err(RustCryptoError::Handshake("DTLS 1.3 required"));
+                    }
+                    // RFC 8446 4.1.3
+                    Dtls13Mode::Prefer if sh.random[24..] == DOWNGRADE_SENTINEL => {
+                        return Err(RustCryptoError::Handshake("DTLS 1.3 downgrade detected"));
+                    }
+                    _ => {}
+                }
+                if sh.cipher_suite != TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256 {
+                    return Err(RustCryptoError::Handshake("unexpected cipher suite"));
+                }
+                let profile = self.offered_srtp_profile(&sh.extensions);
+                if profile.is_none() {
+                    return Err(RustCryptoError::Handshake("no SRTP profile selected"));
+                }
+                self.hs.server_random = sh.random;
+                self.hs.srtp_profile = profile;
+                self.hs.extended_master_secret = sh.extensions.extended_master_secret;
+            }
+            CERTIFICATE => self.handle_certificate(body)?,
+            SERVER_KEY_EXCHANGE => {
+                let ske = ServerKeyExchange::parse(body)?;
+                let signed = [
+                    &self.hs.client_random[..],
+                    &self.hs.server_random[..],
+                    &ske.params[..],
+                ];
+                self.verify_signature(ske.sig_alg, &ske.signature, &signed)?;
+                self.hs.remote_ecdh_public = Some(
+                    PublicKey::from_sec1_bytes(&ske.public)
+                        .map_err(|_| RustCryptoError::Handshake("bad ECDH public key"))?,
+                );
+            }
+            CERTIFICATE_REQUEST => {
+                let cr = CertificateRequest::parse(body)?;
+                if !cr.cert_types.contains(&CERT_TYPE_ECDSA_SIGN)
+                    || !cr.signature_algorithms.contains(&ECDSA_SECP256R1_SHA256)
+                {
+                    return Err(RustCryptoError::Handshake(
+                        "unsupported certificate request",
+                    ));
+                }
+                self.hs.cert_requested = true;
+            }
+            SERVER_HELLO_DONE => self.send_client_flight()?,
+            _ => return Err(RustCryptoError::Handshake("unexpected message from server")),
+        }
+        Ok(())
+    }
+
+    fn handle_client_flight(&mut self, msg_type: u8, body: &[u8]) -> Result<(), RustCryptoError> {
+        match msg_type {
+            CERTIFICATE => self.handle_certificate(body)?,
+            CLIENT_KEY_EXCHANGE => {
+                let public = parse_client_key_exchange(body)?;
+                self.hs.remote_ecdh_public = Some(
+                    PublicKey::from_sec1_bytes(&public)
+                        .map_err(|_| RustCryptoError::Handshake("bad ECDH public key"))?,
+                );
+                // The session hash for the extended master secret includes ClientKeyExchange.
+                self.derive_keys()?;
+            }
+            _ => return Err(RustCryptoError::Handshake("unexpected message from client")),
+        }
+        Ok(())
+    }
+
+    fn handle_certificate(&mut self, body: &[u8]) -> Result<(), RustCryptoError> {
+        if self.hs.remote_key.is_some() {
+            return Err(RustCryptoError::Handshake("duplicate certificate"));
+        }
+        let certificate = Certificate::parse(body)?;
+        let Some(cert) = certificate.certs.into_iter().next() else {
+            return Err(RustCryptoError::Handshake("empty certificate"));
+        };
+        // The certificate is self signed and verified against the SDP fingerprint
+        // by the caller, so all we need is the public key.
+        self.hs.remote_key = Some(public_key(&cert)?);
+        self.hs.remote_cert = Some(cert);
+        Ok(())
+    }
+
+    fn handle_client_hello(&mut self, body: &[u8]) -> Result<(), RustCryptoError> {
+        let ch = ClientHello::parse(body)?;
+        let ext = &ch.extensions;
+
+        let offers13 = ext.supported_versions.contains(&DTLS_1_3_VERSION)
+            && ch.cipher_suites.contains(&TLS_AES_128_GCM_SHA256);
+        let key_share = ext.key_shares.iter().find(|(g, _)| *g == SECP256R1);
+
+        if self.dtls13 != Dtls13Mode::Disabled && offers13 {
+            if let Some((_, public)) = key_share {
+                let public = public.clone();
+                return self.handle_client_hello13(ch, &public);
+            }
+            // Browsers often only send an X25519 key share up front.
+            if ext.supported_groups.contains(&SECP256R1) && !self.hs.hello_retry {
+                self.send_hello_retry_request(&ch);
+                return Ok(());
+            }
+        }
+        if self.hs.hello_retry {
+            return Err(RustCryptoError::Handshake(
+                "no key share after HelloRetryRequest",
+            ));
+        }
+        if self.dtls13 == Dtls13Mode::Require {
+            return Err(RustCryptoError::Handshake("DTLS 1.3 required"));
+        }
+        if !ext.supported_versions.is_empty() && !ext.supported_versions.contains(&DTLS_1_2_VERSION)
+        {
+            return Err(RustCryptoError::Handshake("no common DTLS version"));
+        }
+
+        if !ch
+            .cipher_suites
+            .contains(&TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256)
+        {
+            return Err(RustCryptoError::Handshake("no common cipher suite"));
+        }
+        if !ext.supported_groups.is_empty() && !ext.supported_groups.contains(&SECP256R1) {
+            return Err(RustCryptoError::Handshake("no common curve"));
+        }
+
+        let profile = self.select_srtp_profile(ext)?;
+
+        self.hs.client_random = ch.random;
+        OsRng.fill_bytes(&mut self.hs.server_random);
+        if self.dtls13 != Dtls13Mode::Disabled {
+            // Tell a DTLS 1.3 capable client that we downgraded.
+            self.hs.server_random[24..].copy_from_slice(&DOWNGRADE_SENTINEL);
+        }
+        self.hs.srtp_profile = Some(profile);
+        self.hs.extended_master_secret = ext.extended_master_secret;
+        // TLS_EMPTY_RENEGOTIATION_INFO_SCSV
+        self.hs.renegotiation_info = ext.renegotiation_info || ch.cipher_suites.contains(&0x00ff);
+
+        let server_hello = ServerHello {
+            random: self.hs.server_random,
+            session_id: vec![],
+            cipher_suite: TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256,
+            extensions: Extensions {
+                srtp_profiles: vec![profile.dtls_id().unwrap()],
+                point_formats: vec![POINT_FORMAT_UNCOMPRESSED],
+                extended_master_secret: self.hs.extended_master_secret,
+                renegotiation_info: self.hs.renegotiation_info,
+                ..Default::default()
+            },
+        };
+
+        let certificate = Certificate {
+            certs: vec![self.cert.der.clone()],
+        };
+
+        let ecdh = EphemeralSecret::random(&mut OsRng);
+        let public = ecdh.public_key().to_encoded_point(false);
+        self.hs.ecdh = Some(ecdh);
+
+        let params = ServerKeyExchange::params(public.as_bytes());
+        let signed = [
+            &self.hs.client_random[..],
+            &self.hs.server_random[..],
+            &params[..],
+        ]
+        .concat();
+        let signature: DerSignature = self.cert.key.sign(&signed);
+        let ske = ServerKeyExchange {
+            params,
+            public: public.as_bytes().to_vec(),
+            sig_alg: ECDSA_SECP256R1_SHA256,
+            signature: signature.as_bytes().to_vec(),
+        };
+
+        let cr = CertificateRequest {
+            cert_types: vec![CERT_TYPE_ECDSA_SIGN],
+            signature_algorithms: vec![ECDSA_SECP256R1_SHA256],
+        };
+
+        self.start_flight(true);
+        self.push_handshake(SERVER_HELLO, &server_hello.encode());
+        self.push_handshake(CERTIFICATE, &certificate.encode());
+        self.push_handshake(SERVER_KEY_EXCHANGE, &ske.encode());
+        self.push_handshake(CERTIFICATE_REQUEST, &cr.encode());
+        self.push_handshake(SERVER_HELLO_DONE, &[]);
+        self.send_flight();
+
+        self.state = State::ServerWaitClientFlight;
+
+        Ok(())
+    }
+
+    fn send_client_hello(&mut self) {
+        let offer12 = self.dtls13 != Dtls13Mode::Require;
+        let offer13 = self.dtls13 != Dtls13Mode::Disabled;
+
+        // A ClientHello resent for a cookie or a HelloRetryRequest keeps the random.
+        if self.hs.cookie.is_empty() && !self.hs.hello_retry {
+            OsRng.fill_bytes(&mut self.hs.client_random);
+        }
+        if offer13 && self.hs.key_share.is_empty() {
+            let ecdh = EphemeralSecret::random(&mut OsRng);
+            let public = ecdh.public_key().to_encoded_point(false);
+            self.hs.key_share = public.as_bytes().to_vec();
+            self.hs.ecdh = Some(ecdh);
+        }
+
+        let mut cipher_suites = vec![];
+        let mut supported_versions = vec![];
+        let mut key_shares = vec![];
+        if offer13 {
+            cipher_suites.push(TLS_AES_128_GCM_SHA256);
+            supported_versions.push(DTLS_1_3_VERSION);
+            key_shares.push((SECP256R1, self.hs.key_share.clone()));
+        }
+        if offer12 {
+            cipher_suites.push(TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256);
+            if offer13 {
+                supported_versions.push(DTLS_1_2_VERSION);
+            }
+        }
+
+        let ch = ClientHello {
+            random: self.hs.client_random,
+            session_id: vec![],
+            cookie: self.hs.cookie.clone(),
+            cipher_suites,
+            extensions: Extensions {
+                srtp_profiles: self
+                    .srtp_profiles
+                    .iter()
+                    .filter_map(SrtpProfile::dtls_id)
+                    .collect(),
+                supported_groups: vec![SECP256R1],
+                point_formats: vec![POINT_FORMAT_UNCOMPRESSED],
+                signature_algorithms: vec![ECDSA_SECP256R1_SHA256],
+                extended_master_secret: offer12,
+                renegotiation_info: offer12,
+                supported_versions,
+                key_shares,
+                cookie: self.hs.retry_cookie.clone(),
+                ..Default::default()
+            },
+        };
+
+        self.start_flight(true);
+        self.push_handshake(CLIENT_HELLO, &ch.encode());
+        self.send_flight();
+
+        self.state = State::ClientWaitServerFlight;
+    }
+
+    fn send_client_flight(&mut self) -> Result<(), RustCryptoError> {
+        if self.hs.remote_key.is_none() || self.hs.remote_ecdh_public.is_none() {
+            return Err(RustCryptoError::Handshake("incomplete server flight"));
+        }
+        if !self.hs.cert_requested {
+            return Err(RustCryptoError::Handshake(
+                "server did not request certificate",
+            ));
+        }
+
+        let ecdh = EphemeralSecret::random(&mut OsRng);
+        let public = ecdh.public_key().to_encoded_point(false);
+        self.hs.ecdh = Some(ecdh);
+
+        let certificate = Certificate {
+            certs: vec![self.cert.der.clone()],
+        };
+
+        self.start_flight(true);
+        self.push_handshake(CERTIFICATE, &certificate.encode());
+        self.push_handshake(
+            CLIENT_KEY_EXCHANGE,
+            &encode_client_key_exchange(public.as_bytes()),
+        );
+
+        self.derive_keys()?;
+
+        let signature: DerSignature = self.cert.key.sign(&self.hs.transcript);
+        self.push_handshake(
+            CERTIFICATE_VERIFY,
+            &encode_digitally_signed(ECDSA_SECP256R1_SHA256, signature.as_bytes()),
+        );
+
+        self.flight.push(FlightItem::ChangeCipherSpec);
+        let verify_data = self.verify_data(b"client finished");
+        self.push_handshake(FINISHED, &verify_data);
+        self.send_flight();
+
+        self.state = State::ClientWaitFinished;
+
+        Ok(())
+    }
+
+    fn send_server_finished(&mut self) {
+        self.start_flight(false);
+        self.flight.push(FlightItem::ChangeCipherSpec);
+        let verify_data = self.verify_data(b"server finished");
+        self.push_handshake(FINISHED, &verify_data);
+        self.send_flight();
+    }
+
+    fn derive_keys(&mut self) -> Result<(), RustCryptoError> {
+        let (Some(ecdh), Some(remote)) = (self.hs.ecdh.take(), &self.hs.remote_ecdh_public) else {
+            return Err(RustCryptoError::Handshake("missing ECDH parameters"));
+        };
+
+        let shared = ecdh.diffie_hellman(remote);
+        let pre_master = shared.raw_secret_bytes().as_slice();
+
+        let mut master = [0; MASTER_SECRET_LEN];
+        if self.hs.extended_master_secret {
+            // RFC 7627
+            let session_hash = Sha256::digest(&self.hs.transcript);
+            prf_sha256(
+                pre_master,
+                b"extended master secret",
+                &[&session_hash],
+                &mut master,
+            );
+        } else {
+            prf_sha256(
+                pre_master,
+                b"master secret",
+                &[&self.hs.client_random, &self.hs.server_random],
+                &mut master,
+            );
+        }
+
+        let mut key_block = [0; 2 * (KEY_LEN + FIXED_IV_LEN)];
+        prf_sha256(
+            &master,
+            b"key expansion",
+            &[&self.hs.server_random, &self.hs.client_random],
+            &mut key_block,
+        );
+
+        let (client_key, rest) = key_block.split_at(KEY_LEN);
+        let (server_key, rest) = rest.split_at(KEY_LEN);
+        let (client_iv, server_iv) = rest.split_at(FIXED_IV_LEN);
+
+        self.keys = Some(Box::new(Keys {
+            client_write: Aes128Gcm::new_from_slice(client_key).expect("16 byte key"),
+            server_write: Aes128Gcm::new_from_slice(server_key).expect("16 byte key"),
+            client_iv: client_iv.try_into().unwrap(),
+            server_iv: server_iv.try_into().unwrap(),
+        }));
+        self.hs.master_secret = Some(master);
+
+        Ok(())
+    }
+
+    fn verify_data(&self, label: &[u8]) -> [u8; VERIFY_DATA_LEN] {
+        let master = self.hs.master_secret.as_ref().expect("master secret");
+        let hash = Sha256::digest(&self.hs.transcript);
+        let mut out = [0; VERIFY_DATA_LEN];
+        prf_sha256(master, label, &[&hash], &mut out);
+        out
+    }
+
+    fn verify_finished(&self, label: &[u8], body: &[u8]) -> Result<(), RustCryptoError> {
+        if self.hs.master_secret.is_none() || !bool::from(body.ct_eq(&self.verify_data(label))) {
+            return Err(RustCryptoError::Handshake("Finished verification failed"));
+        }
+        Ok(())
+    }
+
+    fn verify_signature(
+        &self,
+        sig_alg: u16,
+        signature: &[u8],
+        signed: &[&[u8]],
+    ) -> Result<(), RustCryptoError> {
+        if sig_alg != ECDSA_SECP256R1_SHA256 {
+            return Err(RustCryptoError::Handshake(
+                "unsupported signature algorithm",
+            ));
+        }
+        let Some(key) = &self.hs.remote_key else {
+            return Err(RustCryptoError::Handshake("no remote certificate"));
+        };
+        let signature = Signature::from_der(signature)
+            .map_err(|_| RustCryptoError::Handshake("bad signature encoding"))?;
+        key.verify(&signed.concat(), &signature)
+            .map_err(|_| RustCryptoError::Handshake("signature verification failed"))
+    }
+
+    fn set_connected(&mut self) {
+        debug!("DTLS handshake complete");
+        self.state = State::Connected;
+        self.flight_timer = false;
+        self.flight_timeout = None;
+        self.hs.incoming.clear();
+        self.hs.emit_connected = true;
+    }
+
+    /// As server, we pick the first profile in our order of preference.
+    fn select_srtp_profile(&self, ext: &Extensions) -> Result<SrtpProfile, RustCryptoError> {
+        self.srtp_profiles
+            .iter()
+            .copied()
+            .find(|p| {
+                p.dtls_id()
+                    .is_some_and(|id| ext.srtp_profiles.contains(&id))
+            })
+            .ok_or(RustCryptoError::Handshake("no common SRTP profile"))
+    }
+
+    /// As client, the server must select exactly one of the profiles we offered.
+    fn offered_srtp_profile(&self, ext: &Extensions) -> Option<SrtpProfile> {
+        match ext.srtp_profiles.as_slice() {
+            [id] => SrtpProfile::from_dtls_id(*id).filter(|p| self.srtp_profiles.contains(p)),
+            _ => None,
+        }
+    }
+
+    fn emit_connected(&mut self, o: &mut VecDeque<DtlsEvent>) {
+        let profile = self.hs.srtp_profile.expect("SRTP profile");
+
+        let mut mat = vec![0; profile.keying_material_len()];
+        match self.version {
+            Version::Dtls12 => {
+                let master = self.hs.master_secret.expect("master secret");
+                prf_sha256(
+                    &master,
+                    b"EXTRACTOR-dtls_srtp",
+                    &[&self.hs.client_random, &self.hs.server_random],
+                    &mut mat,
+                );
+                self.log_secret("CLIENT_RANDOM", &master);
+            }
+            Version::Dtls13 => self.export13(b"EXTRACTOR-dtls_srtp", &mut mat),
+        }
+
+        let remote_cert = self.hs.remote_cert.as_ref().expect("remote certificate");
+
+        o.push_back(DtlsEvent::Connected);
+        o.push_back(DtlsEvent::RemoteCertificate(remote_cert.clone()));
+        o.push_back(DtlsEvent::RemoteFingerprint(fingerprint(remote_cert)));
+        o.push_back(DtlsEvent::SrtpKeyingMaterial(
+            KeyingMaterial::new(mat),
+            profile,
+        ));
+
+        for line in self.hs.key_log.drain(..) {
+            o.push_back(DtlsEvent::KeyLog(line));
+        }
+    }
+
+    /// Keep a secret for the key log, keyed by the client random.
+    fn log_secret(&mut self, label: &str, secret: &[u8]) {
+        if self.key_log {
+            let line = key_log_line(label, &self.hs.client_random, secret);
+            self.hs.key_log.push(line);
+        }
+    }
+
+    fn start_flight(&mut self, timer: bool) {
+        self.flight.clear();
+        self.flight_records.clear();
+        self.flight_timer = timer;
+        self.flight_timeout = None;
+        self.rto = INITIAL_RTO;
+    }
+
+    fn push_handshake(&mut self, msg_type: u8, body: &[u8]) {
+        let message = handshake_message(msg_type, self.hs.send_seq, body);
+        self.hs.add_transcript(msg_type, self.hs.send_seq, body);
+        self.hs.send_seq += 1;
+
+        let epoch = match self.version {
+            // Finished is the only message sent after ChangeCipherSpec.
+            Version::Dtls12 if msg_type == FINISHED => 1,
+            Version::Dtls12 => 0,
+            Version::Dtls13 => self.hs.write_epoch,
+        };
+
+        self.flight.push(FlightItem::Handshake { epoch, message });
+    }
+
+    /// Send (or resend) the current flight.
+    fn send_flight(&mut self) {
+        let mut records = vec![];
+
+        for item in &self.flight {
+            match item {
+                FlightItem::ChangeCipherSpec => {
+                    records.push((CONTENT_CHANGE_CIPHER_SPEC, 0, vec![1]));
+                }
+                FlightItem::Ack(acks) => {
+                    records.push((CONTENT_ACK, 3, encode_ack(acks)));
+                }
+                FlightItem::Handshake { epoch, message } => {
+                    // This also covers the smaller DTLS 1.3 overhead.
+                    let overhead = RECORD_HEADER_LEN
+                        + HANDSHAKE_HEADER_LEN
+                        + if *epoch > 0 {
+                            EXPLICIT_NONCE_LEN + TAG_LEN
+                        } else {
+                            0
+                        };
+                    let max_fragment = DATAGRAM_MTU - overhead;
+
+                    let mut r = Reader::new(message);
+                    let h = HandshakeHeader::parse(&mut r).expect("own handshake message");
+                    let body = r.rest();
+
+                    let mut offset = 0;
+                    loop {
+                        let len = (body.len() - offset).min(max_fragment);
+                        let mut fragment = Vec::with_capacity(HANDSHAKE_HEADER_LEN + len);
+                        HandshakeHeader {
+                            fragment_offset: offset,
+                            fragment_length: len,
+                            ..h
+                        }
+                        .write(&mut fragment);
+                        fragment.extend_from_slice(&body[offset..offset + len]);
+                        records.push((CONTENT_HANDSHAKE, *epoch, fragment));
+
+                        offset += len;
+                        if offset >= body.len() {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut sent = vec![];
+        let mut datagram: Vec<u8> = vec![];
+        for (content_type, epoch, payload) in records {
+            if content_type == CONTENT_HANDSHAKE {
+                sent.push((epoch as u64, self.write_seq[epoch as usize]));
+            }
+            let record = self.encode_record(content_type, epoch, &payload);
+            if !datagram.is_empty() && datagram.len() + record.len() > DATAGRAM_MTU {
+                self.outgoing
+                    .push_back(std::mem::take(&mut datagram).into());
+            }
+            datagram.extend_from_slice(&record);
+        }
+        if !datagram.is_empty() {
+            self.outgoing.push_back(datagram.into());
+        }
+
+        if self.version == Version::Dtls13 {
+            self.flight_records.push(sent);
+        }
+    }
+
+    fn encode_record(&mut self, content_type: u8, epoch: u16, payload: &[u8]) -> Vec<u8> {
+        if epoch >= 2 {
+            return self.encode_record13(content_type, epoch, payload);
+        }
+
+        let seq = self.write_seq[epoch as usize];
+        self.write_seq[epoch as usize] += 1;
+
+        let mut out = Vec::with_capacity(RECORD_HEADER_LEN + payload.len() + 24);
+        out.push(content_type);
+        out.extend_from_slice(&DTLS_1_2);
+        put_u16(&mut out, epoch);
+        put_u48(&mut out, seq);
+
+        if epoch == 0 {
+            put_u16(&mut out, payload.len() as u16);
+            out.extend_from_slice(payload);
+            return out;
+        }
+
+        let keys = self.keys.as_ref().expect("keys for epoch 1");
+        let (cipher, iv) = if self.is_client() {
+            (&keys.client_write, &keys.client_iv)
+        } else {
+            (&keys.server_write, &keys.server_iv)
+        };
+
+        let explicit = explicit_nonce(epoch, seq);
+        let nonce = [&iv[..], &explicit[..]].concat();
+        let aad = additional_data(content_type, epoch, seq, payload.len());
+
+        let mut buf = payload.to_vec();
+        let tag = cipher
+            .encrypt_in_place_detached(Nonce::from_slice(&nonce), &aad, &mut buf)
+            .expect("AES-GCM encrypt");
+
+        put_u16(&mut out, (EXPLICIT_NONCE_LEN + buf.len() + TAG_LEN) as u16);
+        out.extend_from_slice(&explicit);
+        out.extend_from_slice(&buf);
+        out.extend_from_slice(&tag);
+        out
+    }
+
+    fn decrypt_record(
+        &self,
+        content_type: u8,
+        epoch: u16,
+        seq: u64,
+        fragment: &[u8],
+    ) -> Result<Vec<u8>, RustCryptoError> {
+        if fragment.len() < EXPLICIT_NONCE_LEN + TAG_LEN {
+            return Err(RustCryptoError::Decode("record too short"));
+        }
+        let keys = self.keys.as_ref().expect("keys for epoch 1");
+        let (cipher, iv) = if self.is_client() {
+            (&keys.server_write, &keys.server_iv)
+        } else {
+            (&keys.client_write, &keys.client_iv)
+        };
+
+        let (explicit, rest) = fragment.split_at(EXPLICIT_NONCE_LEN);
+        let (ciphertext, tag) = rest.split_at(rest.len() - TAG_LEN);
+
+        let nonce = [&iv[..], explicit].concat();
+        let aad = additional_data(content_type, epoch, seq, ciphertext.len());
+
+        let mut buf = ciphertext.to_vec();
+        cipher
+            .decrypt_in_place_detached(
+                Nonce::from_slice(&nonce),
+                &aad,
+                &mut buf,
+                Tag::from_slice(tag),
+            )
+            .map_err(|_| RustCryptoError::Decode("record authentication failed"))?;
+
+        Ok(buf)
+    }
+
+    /// Tell the remote why we give up on the connection.
+    fn send_fatal_alert(&mut self, error: &RustCryptoError) {
+        let description = match error {
+            RustCryptoError::Decode(_) => ALERT_DECODE_ERROR,
+            RustCryptoError::Handshake(_) => ALERT_HANDSHAKE_FAILURE,
+            RustCryptoError::Certificate(_) => ALERT_BAD_CERTIFICATE,
+            // The remote already ended it.
+            RustCryptoError::Alert(_) => return,
+        };
+        let epoch = if self.is_connected() {
+            self.app_epoch()
+        } else {
+            match self.version {
+                Version::Dtls12 => 0,
+                Version::Dtls13 => self.hs.write_epoch,
+            }
+        };
+        let alert = [ALERT_LEVEL_FATAL, description];
+        let record = self.encode_record(CONTENT_ALERT, epoch, &alert);
+        self.outgoing.push_back(record.into());
+    }
+}
+
+impl DtlsInner for RustCryptoDtlsImpl {
+    fn set_dtls13(&mut self, mode: Dtls13Mode) -> Result<(), CryptoError> {
+        self.dtls13 = mode;
+        Ok(())
+    }
+
+    fn set_srtp_profiles(&mut self, profiles: &[SrtpProfile]) -> Result<(), CryptoError> {
+        self.srtp_profiles = profiles.to_vec();
+        Ok(())
+    }
+
+    fn set_key_log(&mut self, enabled: bool) {
+        self.key_log = enabled;
+    }
+
+    fn set_active(&mut self, active: bool) {
+        assert!(self.active.is_none(), "set_active called once");
+        self.active = Some(active);
+        self.state = if active {
+            State::ClientStart
+        } else {
+            State::ServerWaitClientHello
+        };
+    }
+
+    fn handle_handshake(&mut self, o: &mut VecDeque<DtlsEvent>) -> Result<bool, CryptoError> {
+        if self.state == State::ClientStart {
+            self.send_client_hello();
+        }
+        if self.hs.emit_connected {
+            self.hs.emit_connected = false;
+            self.emit_connected(o);
+        }
+        Ok(!self.is_connected())
+    }
+
+    fn is_active(&self) -> Option<bool> {
+        self.active
+    }
+
+    fn handle_receive(&mut self, m: &[u8], o: &mut VecDeque<DtlsEvent>) -> Result<(), CryptoError> {
+        if self.state == State::ClientStart {
+            self.send_client_hello();
+        }
+        let result = self.handle_datagram(m, o);
+        if let Err(e) = &result {
+            self.send_fatal_alert(e);
+        }
+        Ok(result?)
+    }
+
+    fn poll_datagram(&mut self) -> Option<DatagramSend> {
+        let x = self.outgoing.pop_front();
+        if let Some(x) = &x {
+            if x.len() > DATAGRAM_MTU_WARN {
+                warn!("DTLS above MTU {}: {}", DATAGRAM_MTU_WARN, x.len());
+            }
+            trace!("Poll datagram: {}", x.len());
+        }
+        x
+    }
+
+    fn poll_timeout(&mut self, now: Instant) -> Option<Instant> {
+        if !self.flight_timer {
+            return None;
+        }
+        // The timer starts when the flight is first polled.
+        Some(*self.flight_timeout.get_or_insert(now + self.rto))
+    }
+
+    fn handle_timeout(&mut self, now: Instant) {
+        let Some(timeout) = self.flight_timeout else {
+            return;
+        };
+        if !self.flight_timer || now < timeout {
+            return;
+        }
+
+        debug!("Retransmit DTLS flight after {:?}", self.rto);
+        self.rto = (self.rto * 2).min(MAX_RTO);
+        self.flight_timeout = Some(now + self.rto);
+
+        self.send_flight();
+    }
+
+    fn handle_input(&mut self, data: &[u8]) -> Result<(), CryptoError> {
+        if !self.is_connected() {
+            return Err(io::Error::new(io::ErrorKind::WouldBlock, "DTLS handshaking").into());
+        }
+
+        for chunk in data.chunks(MAX_RECORD_PLAINTEXT) {
+            let record = self.encode_record(CONTENT_APPLICATION_DATA, self.app_epoch(), chunk);
+            self.outgoing.push_back(record.into());
+        }
+
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+        self.state == State::Connected
+    }
+
+    fn close(&mut self) {
+        if !self.is_connected() {
+            return;
+        }
+        let alert = [ALERT_LEVEL_WARNING, ALERT_CLOSE_NOTIFY];
+        let record = self.encode_record(CONTENT_ALERT, self.app_epoch(), &alert);
+        self.outgoing.push_back(record.into());
+    }
+}
+
+fn explicit_nonce(epoch: u16, seq: u64) -> [u8; EXPLICIT_NONCE_LEN] {
+    ((epoch as u64) << 48 | seq).to_be_bytes()
+}
+
+fn additional_data(content_type: u8, epoch: u16, seq: u64, len: usize) -> Vec<u8> {
+    let mut aad = explicit_nonce(epoch, seq).to_vec();
+    aad.push(content_type);
+    aad.extend_from_slice(&DTLS_1_2);
+    put_u16(&mut aad, len as u16);
+    aad
+}
+
+/// Sliding window replay protection (RFC 6347 4.1.2.6).
+#[derive(Default)]
+struct ReplayWindow {
+    max: Option<u64>,
+    bits: u64,
+}
+
+impl ReplayWindow {
+    fn check(&self, seq: u64) -> bool {
+        let Some(max) = self.max else {
+            return true;
+        };
+        if seq > max {
+            return true;
+        }
+        let diff = max - seq;
+        diff < 64 && self.bits & (1 << diff) == 0
+    }
+
+    fn update(&mut self, seq: u64) {
+        let Some(max) = self.max else {
+            self.max = Some(seq);
+            self.bits = 1;
+            return;
+        };
+        if seq > max {
+            let shift = seq - max;
+            self.bits = if shift >= 64 { 0 } else { self.bits << shift };
+            self.bits |= 1;
+            self.max = Some(seq);
+        } else {
+            self.bits |= 1 << (max - seq);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crypto::DtlsCertOptions;
+
+    fn new_pair(
+        client_mode: Dtls13Mode,
+        server_mode: Dtls13Mode,
+    ) -> (RustCryptoDtlsImpl, RustCryptoDtlsImpl) {
+        let c1 = RustCryptoDtlsCert::new(DtlsCertOptions::default());
+        let c2 = RustCryptoDtlsCert::new(DtlsCertOptions::default());
+        let mut client = RustCryptoDtlsImpl::new(c1);
+        let mut server = RustCryptoDtlsImpl::new(c2);
+        client.set_dtls13(client_mode).unwrap();
+        server.set_dtls13(server_mode).unwrap();
+        client.set_active(true);
+        server.set_active(false);
+        (client, server)
+    }
+
+    fn exchange(
+        from: &mut RustCryptoDtlsImpl,
+        to: &mut RustCryptoDtlsImpl,
+        o: &mut VecDeque<DtlsEvent>,
+    ) {
+        while let Some(d) = from.poll_datagram() {
+            to.handle_receive(&d, o).unwrap();
+        }
+    }
+
+    fn keying_material(o: &VecDeque<DtlsEvent>) -> Vec<u8> {
+        o.iter()
+            .find_map(|e| match e {
+                DtlsEvent::SrtpKeyingMaterial(m, p) => {
+                    assert_eq!(*p, SrtpProfile::AeadAes128Gcm);
+                    Some(m.to_vec())
+                }
+                _ => None,
+            })
+            .expect("keying material")
+    }
+
+    #[test]
+    fn handshake_and_data() {
+        let (mut client, mut server) = new_pair(Dtls13Mode::Disabled, Dtls13Mode::Disabled);
+        let mut oc = VecDeque::new();
+        let mut os = VecDeque::new();
+
+        client.handle_handshake(&mut oc).unwrap();
+
+        for _ in 0..4 {
+            exchange(&mut client, &mut server, &mut os);
+            exchange(&mut server, &mut client, &mut oc);
+        }
+
+        assert!(client.is_connected());
+        assert!(server.is_connected());
+        assert_eq!(keying_material(&oc), keying_material(&os));
+
+        client.handle_input(b"hello").unwrap();
+        exchange(&mut client, &mut server, &mut os);
+        let data = os.iter().find_map(|e| match e {
+            DtlsEvent::Data(d) => Some(d.clone()),
+            _ => None,
+        });
+        assert_eq!(data.as_deref(), Some(&b"hello"[..]));
+    }
+
+    #[test]
+    fn retransmit_lost_flight() {
+        let (mut client, mut server) = new_pair(Dtls13Mode::Disabled, Dtls13Mode::Disabled);
+        let mut oc = VecDeque::new();
+        let mut os = VecDeque::new();
+
+        let now = Instant::now();
+        client.handle_handshake(&mut oc).unwrap();
+
+        // Drop the ClientHello.
+        while client.poll_datagram().is_some() {}
+
+        let timeout = client.poll_timeout(now).unwrap();
+        client.handle_timeout(timeout);
+
+        for _ in 0..4 {
+            exchange(&mut client, &mut server, &mut os);
+            exchange(&mut server, &mut client, &mut oc);
+        }
+
+        assert!(client.is_connected());
+        assert!(server.is_connected());
+    }
+
+    fn handshake(
+        client: &mut RustCryptoDtlsImpl,
+        server: &mut RustCryptoDtlsImpl,
+    ) -> (VecDeque<DtlsEvent>, VecDeque<DtlsEvent>) {
+        let mut oc = VecDeque::new();
+        let mut os = VecDeque::new();
+        client.handle_handshake(&mut oc).unwrap();
+        for _ in 0..4 {
+            exchange(client, server, &mut os);
+            exchange(server, client, &mut oc);
+        }
+        (oc, os)
+    }
+
+    #[test]
+    fn dtls13_handshake_and_data() {
+        let (mut client, mut server) = new_pair(Dtls13Mode::Require, Dtls13Mode::Require);
+        let (mut oc, mut os) = handshake(&mut client, &mut server);
+
+        assert!(client.is_connected());
+        assert!(server.is_connected());
+        assert_eq!(client.version, Version::Dtls13);
+        assert_eq!(server.version, Version::Dtls13);
+        assert_eq!(keying_material(&oc), keying_material(&os));
+
+        // The server ACKed the client's final flight.
+        assert!(client.poll_timeout(Instant::now()).is_none());
+
+        client.handle_input(b"hello").unwrap();
+        exchange(&mut client, &mut server, &mut os);
+        assert!(os
+            .iter()
+            .any(|e| matches!(e, DtlsEvent::Data(d) if d == b"hello")));
+
+        server.handle_input(b"world").unwrap();
+        exchange(&mut server, &mut client, &mut oc);
+        assert!(oc
+            .iter()
+            .any(|e| matches!(e, DtlsEvent::Data(d) if d == b"world")));
+    }
+
+    #[test]
+    fn close_notify() {
+        for mode in [Dtls13Mode::Disabled, Dtls13Mode::Require] {
+            let (mut client, mut server) = new_pair(mode, mode);
+            let (_, _) = handshake(&mut client, &mut server);
+
+            let mut os = VecDeque::new();
+            client.close();
+            exchange(&mut client, &mut server, &mut os);
+
+            assert!(
+                matches!(os.back(), Some(DtlsEvent::Alert(a)) if a.is_close_notify()),
+                "{:?}",
+                mode
+            );
+        }
+    }
+
+    fn plaintext_record(content_type: u8, seq: u64, payload: &[u8]) -> Vec<u8> {
+        let mut out = vec![content_type];
+        out.extend_from_slice(&DTLS_1_2);
+        put_u16(&mut out, 0);
+        put_u48(&mut out, seq);
+        put_u16(&mut out, payload.len() as u16);
+        out.extend_from_slice(payload);
+        out
+    }
+
+    #[test]
+    fn out_of_order_message_rejected() {
+        let (mut client, mut server) = new_pair(Dtls13Mode::Disabled, Dtls13Mode::Disabled);
+        let mut oc = VecDeque::new();
+        let mut os = VecDeque::new();
+
+        client.handle_handshake(&mut oc).unwrap();
+        exchange(&mut client, &mut server, &mut os);
+        while server.poll_datagram().is_some() {}
+
+        // ClientKeyExchange without the Certificate before it.
+        let msg = handshake_message(CLIENT_KEY_EXCHANGE, 1, &[0; 66]);
+        let record = plaintext_record(CONTENT_HANDSHAKE, 10, &msg);
+        assert!(server.handle_receive(&record, &mut os).is_err());
+
+        let alert = server.poll_datagram().expect("fatal alert");
+        assert_eq!(alert[0], CONTENT_ALERT);
+        assert_eq!(
+            alert[RECORD_HEADER_LEN..],
+            [ALERT_LEVEL_FATAL, ALERT_HANDSHAKE_FAILURE]
+        );
+    }
+
+    #[test]
+    fn dtls13_out_of_order_message_rejected() {
+        let (mut client, mut server) = new_pair(Dtls13Mode::Require, Dtls13Mode::Require);
+        let mut oc = VecDeque::new();
+        let mut os = VecDeque::new();
+
+        client.handle_handshake(&mut oc).unwrap();
+        exchange(&mut client, &mut server, &mut os);
+        assert_eq!(server.version, Version::Dtls13);
+
+        // Finished without Certificate and CertificateVerify.
+        assert!(server.handle_message(1, FINISHED, vec![0; 32]).is_err());
+        assert!(!server.is_connected());
+    }
+
+    #[test]
+    fn oversized_message_dropped() {
+        let (mut client, mut server) = new_pair(Dtls13Mode::Disabled, Dtls13Mode::Disabled);
+        let mut oc = VecDeque::new();
+        let mut os = VecDeque::new();
+
+        client.handle_handshake(&mut oc).unwrap();
+        exchange(&mut client, &mut server, &mut os);
+
+        let mut msg = vec![];
+        HandshakeHeader {
+            msg_type: CERTIFICATE,
+            length: 1 << 23,
+            message_seq: 1,
+            fragment_offset: 0,
+            fragment_length: 4,
+        }
+        .write(&mut msg);
+        msg.extend_from_slice(&[0; 4]);
+        let record = plaintext_record(CONTENT_HANDSHAKE, 10, &msg);
+        server.handle_receive(&record, &mut os).unwrap();
+
+        assert!(server.hs.incoming.is_empty());
+    }
+
+    #[test]
+    fn garbage_after_handshake_dropped() {
+        for mode in [Dtls13Mode::Disabled, Dtls13Mode::Require] {
+            let (mut client, mut server) = new_pair(mode, mode);
+            let (_, mut os) = handshake(&mut client, &mut server);
+            while server.poll_datagram().is_some() {}
+
+            let mut finished = vec![];
+            HandshakeHeader {
+                msg_type: FINISHED,
+                length: 12,
+                message_seq: 9,
+                fragment_offset: 0,
+                fragment_length: 12,
+            }
+            .write(&mut finished);
+
+            let mut encrypted = vec![CONTENT_APPLICATION_DATA, 0xfe, 0xfd, 0, 1];
+            put_u48(&mut encrypted, 9);
+            put_u16(&mut encrypted, 24);
+            encrypted.extend_from_slice(&[0; 24]);
+
+            let mut unified = vec![0x2f, 0, 9, 0, 20];
+            unified.extend_from_slice(&[0; 20]);
+
+            let garbage: &[&[u8]] = &[
+                // Truncated record header.
+                &[CONTENT_HANDSHAKE, 0xfe],
+                // Record length beyond the datagram.
+                &encrypted[..20],
+                // Truncated handshake header.
+                &plaintext_record(CONTENT_HANDSHAKE, 20, &finished[..5]),
+                // Handshake fragment beyond the record.
+                &plaintext_record(CONTENT_HANDSHAKE, 21, &finished),
+                &plaintext_record(CONTENT_ALERT, 22, &[]),
+                &encrypted,
+                // Unified header without sequence number.
+                &unified[..1],
+                &unified[..4],
+                &unified,
+            ];
+
+            for g in garbage {
+                server.handle_receive(g, &mut os).unwrap();
+                assert!(server.poll_datagram().is_none(), "{:?} {:?}", mode, g);
+            }
+            assert!(server.is_connected());
+
+            client.handle_input(b"hello").unwrap();
+            exchange(&mut client, &mut server, &mut os);
+            assert!(os
+                .iter()
+                .any(|e| matches!(e, DtlsEvent::Data(d) if d == b"hello")));
+        }
+    }
+
+    #[test]
+    fn dtls13_version_negotiation() {
+        use Dtls13Mode::*;
+
+        let cases = [
+            (Prefer, Prefer, Some(Version::Dtls13)),
+            (Prefer, Disabled, Some(Version::Dtls12)),
+            (Disabled, Prefer, Some(Version::Dtls12)),
+            (Require, Prefer, Some(Version::Dtls13)),
+            (Prefer, Require, Some(Version::Dtls13)),
+            (Require, Disabled, None),
+            (Disabled, Require, None),
+        ];
+
+        for (client_mode, server_mode, expected) in cases {
+            let (mut client, mut server) = new_pair(client_mode, server_mode);
+            let mut oc = VecDeque::new();
+            let mut os = VecDeque::new();
+            client.handle_handshake(&mut oc).unwrap();
+
+            let mut failed = false;
+            for _ in 0..4 {
+                while let Some(d) = client.poll_datagram() {
+                    failed |= server.handle_receive(&d, &mut os).is_err();
+                }
+                while let Some(d) = server.poll_datagram() {
+                    failed |= client.handle_receive(&d, &mut oc).is_err();
+                }
+            }
+
+            let msg = format!("{:?} {:?}", client_mode, server_mode);
+            match expected {
+                Some(version) => {
+                    assert!(client.is_connected() && server.is_connected(), "{}", msg);
+                    assert_eq!(client.version, version, "{}", msg);
+                    assert_eq!(keying_material(&oc), keying_material(&os), "{}", msg);
+                }
+                None => {
+                    assert!(failed, "{}", msg);
+                    assert!(!client.is_connected() && !server.is_connected(), "{}", msg);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn dtls13_downgrade_detected() {
+        // A DTLS 1.3 capable server that (maliciously) only offers DTLS 1.2.
+        let (mut client, mut server) = new_pair(Dtls13Mode::Prefer, Dtls13Mode::Prefer);
+        let mut oc = VecDeque::new();
+        client.handle_handshake(&mut oc).unwrap();
+
+        // Strip the DTLS 1.3 offer from the ClientHello as seen by the server.
+        let ch = client.poll_datagram().unwrap();
+        let mut r = Reader::new(&ch);
+        r.bytes(RECORD_HEADER_LEN + HANDSHAKE_HEADER_LEN).unwrap();
+        let mut hello = ClientHello::parse(r.rest()).unwrap();
+        hello.extensions.supported_versions.clear();
+        hello.extensions.key_shares.clear();
+        server.handle_client_hello(&hello.encode()).unwrap();
+
+        let mut err = None;
+        while let Some(d) = server.poll_datagram() {
+            if let Err(e) = client.handle_receive(&d, &mut oc) {
+                err = Some(e);
+            }
+        }
+        assert!(err.unwrap().to_string().contains("downgrade"));
+    }
+
+    #[test]
+    fn dtls13_hello_retry_request() {
+        const X25519: u16 = 29;
+
+        let (mut client, mut server) = new_pair(Dtls13Mode::Prefer, Dtls13Mode::Prefer);
+        let mut oc = VecDeque::new();
+        let mut os = VecDeque::new();
+        client.handle_handshake(&mut oc).unwrap();
+
+        // Like a browser, the first ClientHello only has an X25519 key share.
+        let ch = client.poll_datagram().unwrap();
+        let mut r = Reader::new(&ch);
+        r.bytes(RECORD_HEADER_LEN + HANDSHAKE_HEADER_LEN).unwrap();
+        let mut hello = ClientHello::parse(r.rest()).unwrap();
+        hello.extensions.supported_groups = vec![X25519, SECP256R1];
+        hello.extensions.key_shares = vec![(X25519, vec![9; 32])];
+        let body = hello.encode();
+        client.hs.transcript13 = tls13_message(CLIENT_HELLO, &body);
+        client.hs.key_share.clear();
+        client.hs.ecdh = None;
+
+        let msg = handshake_message(CLIENT_HELLO, 0, &body);
+        server
+            .handle_receive(&plaintext_record(CONTENT_HANDSHAKE, 0, &msg), &mut os)
+            .unwrap();
+
+        let hrr = server.poll_datagram().unwrap();
+        let mut r = Reader::new(&hrr);
+        r.bytes(RECORD_HEADER_LEN + HANDSHAKE_HEADER_LEN).unwrap();
+        let sh = ServerHello::parse(r.rest()).unwrap();
+        assert_eq!(sh.random, HELLO_RETRY_REQUEST_RANDOM);
+        assert_eq!(sh.extensions.selected_group, Some(SECP256R1));
+        assert_eq!(server.version, Version::Dtls12);
+        client.handle_receive(&hrr, &mut oc).unwrap();
+
+        for _ in 0..4 {
+            exchange(&mut client, &mut server, &mut os);
+            exchange(&mut server, &mut client, &mut oc);
+        }
+
+        assert!(client.is_connected());
+        assert!(server.is_connected());
+        assert_eq!(client.version, Version::Dtls13);
+        assert_eq!(server.version, Version::Dtls13);
+        assert_eq!(keying_material(&oc), keying_material(&os));
+    }
+
+    #[test]
+    fn dtls13_retransmit_lost_ack() {
+        let (mut client, mut server) = new_pair(Dtls13Mode::Require, Dtls13Mode::Require);
+        let mut oc = VecDeque::new();
+        let mut os = VecDeque::new();
+
+        let now = Instant::now();
+        client.handle_handshake(&mut oc).unwrap();
+        exchange(&mut client, &mut server, &mut os);
+        exchange(&mut server, &mut client, &mut oc);
+        exchange(&mut client, &mut server, &mut os);
+        assert!(server.is_connected());
+
+        // Drop the ACK, the client retransmits its final flight on timer.
+        while server.poll_datagram().is_some() {}
+        assert!(!client.is_connected());
+        let timeout = client.poll_timeout(now).unwrap();
+        client.handle_timeout(timeout);
+
+        // The server answers the retransmission with the ACK again.
+        exchange(&mut client, &mut server, &mut os);
+        exchange(&mut server, &mut client, &mut oc);
+        assert!(client.is_connected());
+        assert!(client.poll_timeout(timeout).is_none());
+    }
+
+    #[test]
+    fn replay_window() {
+        let mut w = ReplayWindow::default();
+        assert!(w.check(5));
+        w.update(5);
+        assert!(!w.check(5));
+        assert!(w.check(4));
+        w.update(100);
+        assert!(!w.check(5));
+        assert!(w.check(99));
+        w.update(99);
+        assert!(!w.check(99));
+    }
+
+    #[cfg(feature = "openssl")]
+    fn openssl_interop(rust_active: bool) {
+        use crate::crypto::ossl::{OsslDtlsCert, OsslDtlsImpl};
+
+        let cert = RustCryptoDtlsCert::new(DtlsCertOptions::default());
+        let mut rust = RustCryptoDtlsImpl::new(cert);
+        let cert = OsslDtlsCert::new(DtlsCertOptions::default());
+        let mut ossl = OsslDtlsImpl::new(cert).unwrap();
+
+        rust.set_active(rust_active);
+        ossl.set_active(!rust_active);
+
+        let mut or = VecDeque::new();
+        let mut oo = VecDeque::new();
+
+        rust.handle_handshake(&mut or).unwrap();
+        ossl.handle_handshake(&mut oo).unwrap();
+
+        for _ in 0..6 {
+            while let Some(d) = rust.poll_datagram() {
+                ossl.handle_receive(&d, &mut oo).unwrap();
+            }
+            while let Some(d) = ossl.poll_datagram() {
+                rust.handle_receive(&d, &mut or).unwrap();
+            }
+        }
+
+        assert!(rust.is_connected());
+        assert!(ossl.is_connected());
+        assert_eq!(keying_material(&or), keying_material(&oo));
+
+        ossl.handle_input(b"from openssl").unwrap();
+        while let Some(d) = ossl.poll_datagram() {
+            rust.handle_receive(&d, &mut or).unwrap();
+        }
+        assert!(or
+            .iter()
+            .any(|e| matches!(e, DtlsEvent::Data(d) if d == b"from openssl")));
+
+        rust.handle_input(b"from rust").unwrap();
+        while let Some(d) = rust.poll_datagram() {
+            ossl.handle_receive(&d, &mut oo).unwrap();
+        }
+        assert!(oo
+            .iter()
+            .any(|e| matches!(e, DtlsEvent::Data(d) if d == b"from rust")));
+    }
+
+    #[test]
+    #[cfg(feature = "openssl")]
+    fn openssl_server() {
+        openssl_interop(true);
+    }
+
+    #[test]
+    #[cfg(feature = "openssl")]
+    fn openssl_client() {
+        openssl_interop(false);
+    }
+}
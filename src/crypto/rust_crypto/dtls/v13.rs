@@ -0,0 +1,624 @@
+//! DTLS 1.3 (RFC 9147) handshake, key schedule and record layer.
+//!
+//! The DTLS 1.2 state machine in the parent module negotiates the version. Once
+//! a DTLS 1.3 ServerHello is sent or received, handshake messages are handled here.
+
+use aes::cipher::BlockEncrypt;
+use aes::Aes128;
+
+use super::super::hkdf::{derive_secret, expand_label, extract, hmac_sha256, HASH_LEN};
+use super::*;
+
+/// Last 8 bytes of the ServerHello random of a DTLS 1.3 capable server
+/// negotiating DTLS 1.2 (RFC 8446 4.1.3).
+pub(super) const DOWNGRADE_SENTINEL: [u8; 8] = *b"DOWNGRD\x01";
+
+/// ServerHello random of a HelloRetryRequest, SHA-256 of "HelloRetryRequest"
+/// (RFC 8446 4.1.3).
+pub(super) const HELLO_RETRY_REQUEST_RANDOM: [u8; 32] = [
+    0xcf, 0x21, 0xad, 0x74, 0xe5, 0x9a, 0x61, 0x11, 0xbe, 0x1d, 0x8c, 0x02, 0x1e, 0x65, 0xb8, 0x91,
+    0xc2, 0xa2, 0x11, 0x16, 0x7a, 0xbb, 0x8c, 0x5e, 0x07, 0x9e, 0x09, 0xe2, 0xc8, 0xa8, 0x33, 0x9c,
+];
+
+/// The fixed bits 001 of the unified header.
+pub(super) const UNIFIED_HEADER_MASK: u8 = 0b1110_0000;
+pub(super) const UNIFIED_HEADER_BITS: u8 = 0b0010_0000;
+
+const HEADER_CID: u8 = 0b0001_0000;
+const HEADER_SEQ_16: u8 = 0b0000_1000;
+const HEADER_LENGTH: u8 = 0b0000_0100;
+
+const IV_LEN: usize = 12;
+
+/// Traffic keys of one epoch.
+pub(super) struct EpochKeys {
+    aead: Aes128Gcm,
+    iv: [u8; IV_LEN],
+    /// Record sequence number encryption (RFC 9147 4.2.3).
+    sn: Aes128,
+}
+
+impl EpochKeys {
+    fn new(secret: &[u8]) -> Box<Self> {
+        let mut key = [0; KEY_LEN];
+        let mut iv = [0; IV_LEN];
+        let mut sn = [0; KEY_LEN];
+        expand_label(secret, b"key", &[], &mut key);
+        expand_label(secret, b"iv", &[], &mut iv);
+        expand_label(secret, b"sn", &[], &mut sn);
+
+        Box::new(EpochKeys {
+            aead: Aes128Gcm::new_from_slice(&key).expect("16 byte key"),
+            iv,
+            sn: Aes128::new_from_slice(&sn).expect("16 byte key"),
+        })
+    }
+
+    fn nonce(&self, seq: u64) -> [u8; IV_LEN] {
+        let mut nonce = self.iv;
+        for (n, s) in nonce[IV_LEN - 8..].iter_mut().zip(seq.to_be_bytes()) {
+            *n ^= s;
+        }
+        nonce
+    }
+
+    /// The mask for the sequence number, from the first 16 bytes of ciphertext.
+    fn mask(&self, ciphertext: &[u8]) -> [u8; 16] {
+        let mut block = aes::Block::clone_from_slice(&ciphertext[..16]);
+        self.sn.encrypt_block(&mut block);
+        block.into()
+    }
+}
+
+impl RustCryptoDtlsImpl {
+    pub(super) fn handle_message13(
+        &mut self,
+        msg_type: u8,
+        seq: u16,
+        body: Vec<u8>,
+    ) -> Result<(), RustCryptoError> {
+        use State::*;
+
+        if !self.is_expected13(msg_type) {
+            return Err(RustCryptoError::Handshake("unexpected handshake message"));
+        }
+        self.hs.last_msg = Some(msg_type);
+
+        match (self.state, msg_type) {
+            (ClientWaitServerFlight, ENCRYPTED_EXTENSIONS) => {
+                let ext = parse_encrypted_extensions(&body)?;
+                let profile = self.offered_srtp_profile(&ext);
+                if profile.is_none() {
+                    return Err(RustCryptoError::Handshake("no SRTP profile selected"));
+                }
+                self.hs.srtp_profile = profile;
+            }
+            (ClientWaitServerFlight, CERTIFICATE_REQUEST) => {
+                let cr = CertificateRequest13::parse(&body)?;
+                if !cr.signature_algorithms.contains(&ECDSA_SECP256R1_SHA256) {
+                    return Err(RustCryptoError::Handshake(
+                        "unsupported certificate request",
+                    ));
+                }
+                self.hs.cert_requested = true;
+                self.hs.cert_request_context = cr.context;
+            }
+            (ClientWaitServerFlight | ServerWaitClientFlight, CERTIFICATE) => {
+                if self.hs.remote_key.is_some() || self.hs.remote_verified {
+                    return Err(RustCryptoError::Handshake("duplicate certificate"));
+                }
+                let certificate = Certificate13::parse(&body)?;
+                let Some(cert) = certificate.certs.into_iter().next() else {
+                    return Err(RustCryptoError::Handshake("empty certificate"));
+                };
+                self.hs.remote_key = Some(public_key(&cert)?);
+                self.hs.remote_cert = Some(cert);
+            }
+            (ClientWaitServerFlight | ServerWaitClientFlight, CERTIFICATE_VERIFY) => {
+                // Signature is over the transcript prior to CertificateVerify.
+                let (sig_alg, signature) = parse_digitally_signed(&body)?;
+                let content = self.certificate_verify_content(!self.is_client());
+                self.verify_signature(sig_alg, &signature, &[&content])?;
+                self.hs.remote_verified = true;
+            }
+            (ClientWaitServerFlight, FINISHED) => {
+                if !self.hs.remote_verified || self.hs.srtp_profile.is_none() {
+                    return Err(RustCryptoError::Handshake("incomplete server flight"));
+                }
+                self.verify_finished13(&self.hs.server_hs_secret, &body)?;
+                self.hs.add_transcript(msg_type, seq, &body);
+                self.derive_application_secrets();
+                return self.send_client_flight13();
+            }
+            (ServerWaitClientFlight, FINISHED) => {
+                if !self.hs.remote_verified {
+                    return Err(RustCryptoError::Handshake("client sent no certificate"));
+                }
+                self.verify_finished13(&self.hs.client_hs_secret, &body)?;
+                self.hs.add_transcript(msg_type, seq, &body);
+                self.set_connected();
+
+                // The ACK replaces the server Finished flight of DTLS 1.2 (RFC 9147 7.1).
+                self.start_flight(false);
+                self.flight.push(FlightItem::Ack(self.hs.acks.clone()));
+                self.send_flight();
+                return Ok(());
+            }
+            _ => {
+                return Err(RustCryptoError::Handshake("unexpected handshake message"));
+            }
+        }
+
+        self.hs.add_transcript(msg_type, seq, &body);
+
+        Ok(())
+    }
+
+    /// Whether a DTLS 1.3 message may follow the previous one.
+    ///
+    /// Client: ServerHello, EncryptedExtensions, CertificateRequest?, Certificate,
+    /// CertificateVerify, Finished. Server: ClientHello, Certificate,
+    /// CertificateVerify, Finished.
+    fn is_expected13(&self, msg_type: u8) -> bool {
+        use State::*;
+
+        let last = self.hs.last_msg;
+
+        match self.state {
+            ClientWaitServerFlight => matches!(
+                (last, msg_type),
+                (Some(SERVER_HELLO), ENCRYPTED_EXTENSIONS)
+                    | (
+                        Some(ENCRYPTED_EXTENSIONS),
+                        CERTIFICATE_REQUEST | CERTIFICATE
+                    )
+                    | (Some(CERTIFICATE_REQUEST), CERTIFICATE)
+                    | (Some(CERTIFICATE), CERTIFICATE_VERIFY)
+                    | (Some(CERTIFICATE_VERIFY), FINISHED)
+            ),
+            ServerWaitClientFlight => matches!(
+                (last, msg_type),
+                (Some(CLIENT_HELLO), CERTIFICATE)
+                    | (Some(CERTIFICATE), CERTIFICATE_VERIFY)
+                    | (Some(CERTIFICATE_VERIFY), FINISHED)
+            ),
+            _ => false,
+        }
+    }
+
+    pub(super) fn handle_server_hello13(&mut self, sh: ServerHello) -> Result<(), RustCryptoError> {
+        if sh.cipher_suite != TLS_AES_128_GCM_SHA256 {
+            return Err(RustCryptoError::Handshake("unexpected cipher suite"));
+        }
+        let Some((SECP256R1, public)) = sh.extensions.key_shares.first() else {
+            return Err(RustCryptoError::Handshake("unsupported key share"));
+        };
+        let remote = PublicKey::from_sec1_bytes(public)
+            .map_err(|_| RustCryptoError::Handshake("bad ECDH public key"))?;
+        let Some(ecdh) = self.hs.ecdh.take() else {
+            return Err(RustCryptoError::Handshake("missing ECDH parameters"));
+        };
+        let shared = ecdh.diffie_hellman(&remote);
+
+        self.version = Version::Dtls13;
+        self.hs.server_random = sh.random;
+        self.derive_handshake_secrets(shared.raw_secret_bytes());
+
+        // The rest of the server flight is encrypted.
+        self.hs.recv_epoch = 2;
+
+        Ok(())
+    }
+
+    /// Ask a client that offered DTLS 1.3 without a secp256r1 key share for one
+    /// (RFC 8446 4.1.4, RFC 9147 5.1).
+    pub(super) fn send_hello_retry_request(&mut self, ch: &ClientHello) {
+        let hrr = ServerHello {
+            random: HELLO_RETRY_REQUEST_RANDOM,
+            session_id: ch.session_id.clone(),
+            cipher_suite: TLS_AES_128_GCM_SHA256,
+            extensions: Extensions {
+                supported_versions: vec![DTLS_1_3_VERSION],
+                selected_group: Some(SECP256R1),
+                ..Default::default()
+            },
+        };
+
+        self.hs.hello_retry = true;
+        self.replace_client_hello_hash();
+
+        // No timer, it is resent if the first ClientHello is.
+        self.start_flight(false);
+        self.push_handshake(SERVER_HELLO, &hrr.encode());
+        self.send_flight();
+    }
+
+    pub(super) fn handle_hello_retry_request(
+        &mut self,
+        seq: u16,
+        body: &[u8],
+    ) -> Result<(), RustCryptoError> {
+        let hrr = ServerHello::parse(body)?;
+        if self.hs.hello_retry
+            || self.dtls13 == Dtls13Mode::Disabled
+            || hrr.extensions.supported_versions != [DTLS_1_3_VERSION]
+            || hrr.cipher_suite != TLS_AES_128_GCM_SHA256
+        {
+            return Err(RustCryptoError::Handshake("unexpected HelloRetryRequest"));
+        }
+        // A retry for the key share we already sent is not allowed either.
+        if hrr.extensions.selected_group != Some(SECP256R1) || !self.hs.key_share.is_empty() {
+            return Err(RustCryptoError::Handshake(
+                "unsupported HelloRetryRequest group",
+            ));
+        }
+
+        self.hs.hello_retry = true;
+        self.hs.retry_cookie = hrr.extensions.cookie;
+        // The next ServerHello is expected again.
+        self.hs.last_msg = None;
+
+        self.replace_client_hello_hash();
+        self.hs.add_transcript(SERVER_HELLO, seq, body);
+        self.send_client_hello();
+
+        Ok(())
+    }
+
+    /// After a HelloRetryRequest, the first ClientHello is replaced by its hash in
+    /// the transcript (RFC 8446 4.4.1).
+    fn replace_client_hello_hash(&mut self) {
+        let hash = Sha256::digest(&self.hs.transcript13);
+        self.hs.transcript13 = tls13_message(MESSAGE_HASH, &hash);
+    }
+
+    pub(super) fn handle_client_hello13(
+        &mut self,
+        ch: ClientHello,
+        public: &[u8],
+    ) -> Result<(), RustCryptoError> {
+        let remote = PublicKey::from_sec1_bytes(public)
+            .map_err(|_| RustCryptoError::Handshake("bad ECDH public key"))?;
+        let profile = self.select_srtp_profile(&ch.extensions)?;
+
+        self.version = Version::Dtls13;
+        self.hs.client_random = ch.random;
+        OsRng.fill_bytes(&mut self.hs.server_random);
+        self.hs.srtp_profile = Some(profile);
+
+        let ecdh = EphemeralSecret::random(&mut OsRng);
+        let public = ecdh.public_key().to_encoded_point(false);
+        let shared = ecdh.diffie_hellman(&remote);
+
+        let server_hello = ServerHello {
+            random: self.hs.server_random,
+            session_id: ch.session_id,
+            cipher_suite: TLS_AES_128_GCM_SHA256,
+            extensions: Extensions {
+                supported_versions: vec![DTLS_1_3_VERSION],
+                key_shares: vec![(SECP256R1, public.as_bytes().to_vec())],
+                ..Default::default()
+            },
+        };
+
+        let encrypted_extensions = Extensions {
+            srtp_profiles: vec![profile.dtls_id().unwrap()],
+            ..Default::default()
+        };
+
+        let cr = CertificateRequest13 {
+            context: vec![],
+            signature_algorithms: vec![ECDSA_SECP256R1_SHA256],
+        };
+
+        let certificate = Certificate13 {
+            context: vec![],
+            certs: vec![self.cert.der.clone()],
+        };
+
+        self.start_flight(true);
+        self.push_handshake(SERVER_HELLO, &server_hello.encode());
+
+        self.derive_handshake_secrets(shared.raw_secret_bytes());
+        self.hs.write_epoch = 2;
+
+        self.push_handshake(
+            ENCRYPTED_EXTENSIONS,
+            &encode_encrypted_extensions(&encrypted_extensions),
+        );
+        self.push_handshake(CERTIFICATE_REQUEST, &cr.encode());
+        self.push_handshake(CERTIFICATE, &certificate.encode());
+        self.push_certificate_verify();
+        let verify_data = self.finished13(&self.hs.server_hs_secret);
+        self.push_handshake(FINISHED, &verify_data);
+        self.send_flight();
+
+        self.derive_application_secrets();
+
+        self.hs.recv_epoch = 2;
+        self.state = State::ServerWaitClientFlight;
+
+        Ok(())
+    }
+
+    fn send_client_flight13(&mut self) -> Result<(), RustCryptoError> {
+        if !self.hs.cert_requested {
+            return Err(RustCryptoError::Handshake(
+                "server did not request certificate",
+            ));
+        }
+
+        let certificate = Certificate13 {
+            context: self.hs.cert_request_context.clone(),
+            certs: vec![self.cert.der.clone()],
+        };
+
+        self.start_flight(true);
+        self.hs.write_epoch = 2;
+        self.push_handshake(CERTIFICATE, &certificate.encode());
+        self.push_certificate_verify();
+        let verify_data = self.finished13(&self.hs.client_hs_secret);
+        self.push_handshake(FINISHED, &verify_data);
+        self.send_flight();
+
+        // We could send application data now, but wait for the server to have
+        // our Finished, or it would drop SRTP arriving ahead of it.
+        self.state = State::ClientWaitAck;
+
+        Ok(())
+    }
+
+    fn push_certificate_verify(&mut self) {
+        let content = self.certificate_verify_content(self.is_client());
+        let signature: DerSignature = self.cert.key.sign(&content);
+        self.push_handshake(
+            CERTIFICATE_VERIFY,
+            &encode_digitally_signed(ECDSA_SECP256R1_SHA256, signature.as_bytes()),
+        );
+    }
+
+    /// What is signed in CertificateVerify (RFC 8446 4.4.3).
+    fn certificate_verify_content(&self, client: bool) -> Vec<u8> {
+        let context: &[u8] = if client {
+            b"TLS 1.3, client CertificateVerify"
+        } else {
+            b"TLS 1.3, server CertificateVerify"
+        };
+        let mut content = vec![0x20; 64];
+        content.extend_from_slice(context);
+        content.push(0);
+        content.extend_from_slice(&Sha256::digest(&self.hs.transcript13));
+        content
+    }
+
+    fn finished13(&self, traffic_secret: &[u8]) -> [u8; HASH_LEN] {
+        let mut key = [0; HASH_LEN];
+        expand_label(traffic_secret, b"finished", &[], &mut key);
+        hmac_sha256(&key, &[&Sha256::digest(&self.hs.transcript13)])
+    }
+
+    fn verify_finished13(&self, traffic_secret: &[u8], body: &[u8]) -> Result<(), RustCryptoError> {
+        if !bool::from(body.ct_eq(&self.finished13(traffic_secret))) {
+            return Err(RustCryptoError::Handshake("Finished verification failed"));
+        }
+        Ok(())
+    }
+
+    /// Handshake traffic secrets from the transcript through ServerHello.
+    fn derive_handshake_secrets(&mut self, shared: &[u8]) {
+        let zeros = [0; HASH_LEN];
+        let early = extract(&zeros, &zeros);
+        let derived = derive_secret(&early, b"derived", &[]);
+        let handshake = extract(&derived, shared);
+
+        let client = derive_secret(&handshake, b"c hs traffic", &self.hs.transcript13);
+        let server = derive_secret(&handshake, b"s hs traffic", &self.hs.transcript13);
+        self.install_keys(2, &client, &server);
+        self.log_secret("CLIENT_HANDSHAKE_TRAFFIC_SECRET", &client);
+        self.log_secret("SERVER_HANDSHAKE_TRAFFIC_SECRET", &server);
+
+        self.hs.handshake_secret = handshake;
+        self.hs.client_hs_secret = client;
+        self.hs.server_hs_secret = server;
+    }
+
+    /// Application traffic secrets from the transcript through server Finished.
+    fn derive_application_secrets(&mut self) {
+        let derived = derive_secret(&self.hs.handshake_secret, b"derived", &[]);
+        let master = extract(&derived, &[0; HASH_LEN]);
+
+        let client = derive_secret(&master, b"c ap traffic", &self.hs.transcript13);
+        let server = derive_secret(&master, b"s ap traffic", &self.hs.transcript13);
+        self.install_keys(3, &client, &server);
+        self.log_secret("CLIENT_TRAFFIC_SECRET_0", &client);
+        self.log_secret("SERVER_TRAFFIC_SECRET_0", &server);
+
+        let exporter = derive_secret(&master, b"exp master", &self.hs.transcript13);
+        self.log_secret("EXPORTER_SECRET", &exporter);
+        self.hs.exporter_secret = Some(exporter);
+    }
+
+    fn install_keys(&mut self, epoch: usize, client: &[u8], server: &[u8]) {
+        let (write, read) = if self.is_client() {
+            (client, server)
+        } else {
+            (server, client)
+        };
+        self.write13[epoch] = Some(EpochKeys::new(write));
+        self.read13[epoch] = Some(EpochKeys::new(read));
+    }
+
+    /// TLS-Exporter with an empty context (RFC 8446 7.5).
+    pub(super) fn export13(&self, label: &[u8], out: &mut [u8]) {
+        let exporter = self.hs.exporter_secret.as_ref().expect("exporter secret");
+        let secret = derive_secret(exporter, label, &[]);
+        expand_label(&secret, b"exporter", &Sha256::digest([]), out);
+    }
+
+    pub(super) fn handle_ack(&mut self, payload: &[u8]) -> Result<(), RustCryptoError> {
+        let acks = parse_ack(payload)?;
+
+        // We don't do partial retransmissions, the flight is done once any
+        // transmission of it is fully acknowledged.
+        let done = self
+            .flight_records
+            .iter()
+            .any(|t| !t.is_empty() && t.iter().all(|r| acks.contains(r)));
+
+        if done && self.state == State::ClientWaitAck {
+            debug!("DTLS Finished acknowledged");
+            self.set_connected();
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn encode_record13(
+        &mut self,
+        content_type: u8,
+        epoch: u16,
+        payload: &[u8],
+    ) -> Vec<u8> {
+        let seq = self.write_seq[epoch as usize];
+        self.write_seq[epoch as usize] += 1;
+
+        let keys = self.write13[epoch as usize]
+            .as_ref()
+            .expect("keys for epoch");
+
+        // DTLSInnerPlaintext without padding.
+        let mut buf = Vec::with_capacity(payload.len() + 1 + TAG_LEN);
+        buf.extend_from_slice(payload);
+        buf.push(content_type);
+
+        let mut header = [0; UNIFIED_HEADER_LEN];
+        header[0] = UNIFIED_HEADER_BITS | HEADER_SEQ_16 | HEADER_LENGTH | (epoch & 3) as u8;
+        header[1..3].copy_from_slice(&(seq as u16).to_be_bytes());
+        header[3..5].copy_from_slice(&((buf.len() + TAG_LEN) as u16).to_be_bytes());
+
+        let tag = keys
+            .aead
+            .encrypt_in_place_detached(Nonce::from_slice(&keys.nonce(seq)), &header, &mut buf)
+            .expect("AES-GCM encrypt");
+        buf.extend_from_slice(&tag);
+
+        let mask = keys.mask(&buf);
+        header[1] ^= mask[0];
+        header[2] ^= mask[1];
+
+        let mut out = header.to_vec();
+        out.extend_from_slice(&buf);
+        out
+    }
+
+    /// Read a record with the unified header.
+    pub(super) fn read_record13<'a>(
+        &mut self,
+        buf: &'a [u8],
+    ) -> Result<(Option<Record>, &'a [u8]), RustCryptoError> {
+        let flags = buf[0];
+        if flags & HEADER_CID > 0 {
+            // We never negotiate connection ids and can't tell where the record ends.
+            trace!("Drop DTLS record with connection id");
+            return Ok((None, &[]));
+        }
+
+        let mut r = Reader::new(&buf[1..]);
+        let seq_len = if flags & HEADER_SEQ_16 > 0 { 2 } else { 1 };
+        r.bytes(seq_len)?;
+        let (header_len, ciphertext, rest) = if flags & HEADER_LENGTH > 0 {
+            let ciphertext = r.vec16()?;
+            (1 + seq_len + 2, ciphertext, r.rest())
+        } else {
+            (1 + seq_len, r.rest(), &[][..])
+        };
+
+        // Only epochs 2 and 3 are used, so the low bits are the epoch.
+        let epoch = (flags & 3) as u16;
+        let Some(keys) = self.read13[epoch as usize].as_ref() else {
+            trace!("Drop DTLS record with epoch: {}", epoch);
+            return Ok((None, rest));
+        };
+        if ciphertext.len() < TAG_LEN + 1 {
+            return Ok((None, rest));
+        }
+
+        let mut header = buf[..header_len].to_vec();
+        let mask = keys.mask(ciphertext);
+        let mut low = 0;
+        for i in 0..seq_len {
+            header[1 + i] ^= mask[i];
+            low = (low << 8) | header[1 + i] as u64;
+        }
+
+        let replay = &self.replay[epoch as usize];
+        let seq = reconstruct_seq(replay.max, low, seq_len as u32 * 8);
+        if !replay.check(seq) {
+            trace!("Drop replayed DTLS record: {}", seq);
+            return Ok((None, rest));
+        }
+
+        let (ciphertext, tag) = ciphertext.split_at(ciphertext.len() - TAG_LEN);
+        let mut plain = ciphertext.to_vec();
+        if keys
+            .aead
+            .decrypt_in_place_detached(
+                Nonce::from_slice(&keys.nonce(seq)),
+                &header,
+                &mut plain,
+                Tag::from_slice(tag),
+            )
+            .is_err()
+        {
+            debug!("Drop DTLS record: record authentication failed");
+            return Ok((None, rest));
+        }
+        self.replay[epoch as usize].update(seq);
+
+        // Strip the padding, the last non-zero byte is the content type.
+        let Some(pos) = plain.iter().rposition(|b| *b != 0) else {
+            return Err(RustCryptoError::Decode("record without content type"));
+        };
+        let content_type = plain[pos];
+        plain.truncate(pos);
+
+        let record = Record {
+            content_type,
+            epoch,
+            seq,
+            payload: plain,
+        };
+        Ok((Some(record), rest))
+    }
+}
+
+/// The full sequence number closest to the next expected given its low bits (RFC 9147 4.2.2).
+fn reconstruct_seq(max: Option<u64>, low: u64, bits: u32) -> u64 {
+    let expected = max.map(|m| m + 1).unwrap_or(0);
+    let window = 1 << bits;
+    let candidate = (expected & !(window - 1)) | low;
+
+    [
+        candidate.checked_sub(window),
+        Some(candidate),
+        candidate.checked_add(window),
+    ]
+    .into_iter()
+    .flatten()
+    .min_by_key(|c| c.abs_diff(expected))
+    .unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reconstruct_sequence_number() {
+        assert_eq!(reconstruct_seq(None, 0, 16), 0);
+        assert_eq!(reconstruct_seq(Some(5), 7, 16), 7);
+        assert_eq!(reconstruct_seq(Some(0xfffe), 0x0001, 16), 0x1_0001);
+        assert_eq!(reconstruct_seq(Some(0x1_0002), 0xffff, 16), 0xffff);
+        assert_eq!(reconstruct_seq(Some(300), 0x2d, 8), 301);
+    }
+}
@@ -0,0 +1,98 @@
+//! HKDF (RFC 5869) and the HKDF-Expand-Label of TLS 1.3 (RFC 8446 section 7.1).
+
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub const HASH_LEN: usize = 32;
+
+/// DTLS 1.3 uses its own label prefix (RFC 9147 section 5.9).
+const LABEL_PREFIX: &[u8] = b"dtls13";
+
+pub fn hmac_sha256(key: &[u8], data: &[&[u8]]) -> [u8; HASH_LEN] {
+    let mut h = HmacSha256::new_from_slice(key).expect("HMAC can take key of any size");
+    data.iter().for_each(|d| h.update(d));
+    h.finalize().into_bytes().into()
+}
+
+pub fn extract(salt: &[u8], ikm: &[u8]) -> [u8; HASH_LEN] {
+    hmac_sha256(salt, &[ikm])
+}
+
+pub fn expand(prk: &[u8], info: &[u8], out: &mut [u8]) {
+    let mut t: Vec<u8> = vec![];
+    for (i, chunk) in out.chunks_mut(HASH_LEN).enumerate() {
+        t = hmac_sha256(prk, &[&t, info, &[i as u8 + 1]]).to_vec();
+        chunk.copy_from_slice(&t[..chunk.len()]);
+    }
+}
+
+/// HKDF-Expand-Label with the DTLS 1.3 label prefix.
+pub fn expand_label(secret: &[u8], label: &[u8], context: &[u8], out: &mut [u8]) {
+    expand_label_prefixed(LABEL_PREFIX, secret, label, context, out)
+}
+
+fn expand_label_prefixed(
+    prefix: &[u8],
+    secret: &[u8],
+    label: &[u8],
+    context: &[u8],
+    out: &mut [u8],
+) {
+    let mut info = Vec::with_capacity(4 + prefix.len() + label.len() + context.len());
+    info.extend_from_slice(&(out.len() as u16).to_be_bytes());
+    info.push((prefix.len() + label.len()) as u8);
+    info.extend_from_slice(prefix);
+    info.extend_from_slice(label);
+    info.push(context.len() as u8);
+    info.extend_from_slice(context);
+    expand(secret, &info, out);
+}
+
+/// Derive-Secret(secret, label, messages), where `transcript` is the concatenated messages.
+pub fn derive_secret(secret: &[u8], label: &[u8], transcript: &[u8]) -> [u8; HASH_LEN] {
+    let mut out = [0; HASH_LEN];
+    expand_label(secret, label, &Sha256::digest(transcript), &mut out);
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn hkdf_rfc5869_case_1() {
+        let ikm = [0x0b; 22];
+        let salt: Vec<u8> = (0..=0x0c).collect();
+        let info: Vec<u8> = (0xf0..=0xf9).collect();
+
+        let prk = extract(&salt, &ikm);
+        assert_eq!(prk[..8], [0x07, 0x77, 0x09, 0x36, 0x2c, 0x2e, 0x32, 0xdf]);
+
+        let mut okm = [0; 42];
+        expand(&prk, &info, &mut okm);
+        assert_eq!(okm[..8], [0x3c, 0xb2, 0x5f, 0x25, 0xfa, 0xac, 0xd5, 0x7a]);
+        assert_eq!(okm[34..], [0x72, 0x08, 0xd5, 0xb8, 0x87, 0x18, 0x58, 0x65]);
+    }
+
+    #[test]
+    fn expand_label_rfc8448() {
+        // RFC 8448 section 3, the "derived" secret from the early secret.
+        let early = extract(&[0; HASH_LEN], &[0; HASH_LEN]);
+        assert_eq!(early[..8], [0x33, 0xad, 0x0a, 0x1c, 0x60, 0x7e, 0xc0, 0x3b]);
+
+        let mut derived = [0; HASH_LEN];
+        expand_label_prefixed(
+            b"tls13 ",
+            &early,
+            b"derived",
+            &Sha256::digest([]),
+            &mut derived,
+        );
+        assert_eq!(
+            derived[..8],
+            [0x6f, 0x26, 0x15, 0xa1, 0x08, 0xc7, 0x02, 0xc5]
+        );
+    }
+}
@@ -0,0 +1,773 @@
+//! DTLS 1.2 and 1.3 handshake messages (RFC 5246, RFC 6347, RFC 8446, RFC 9147).
+
+use super::RustCryptoError;
+
+pub const CONTENT_CHANGE_CIPHER_SPEC: u8 = 20;
+pub const CONTENT_ALERT: u8 = 21;
+pub const CONTENT_HANDSHAKE: u8 = 22;
+pub const CONTENT_APPLICATION_DATA: u8 = 23;
+pub const CONTENT_ACK: u8 = 26;
+
+pub const CLIENT_HELLO: u8 = 1;
+pub const SERVER_HELLO: u8 = 2;
+pub const HELLO_VERIFY_REQUEST: u8 = 3;
+pub const ENCRYPTED_EXTENSIONS: u8 = 8;
+pub const CERTIFICATE: u8 = 11;
+pub const SERVER_KEY_EXCHANGE: u8 = 12;
+pub const CERTIFICATE_REQUEST: u8 = 13;
+pub const SERVER_HELLO_DONE: u8 = 14;
+pub const CERTIFICATE_VERIFY: u8 = 15;
+pub const CLIENT_KEY_EXCHANGE: u8 = 16;
+pub const FINISHED: u8 = 20;
+pub const MESSAGE_HASH: u8 = 254;
+
+pub const DTLS_1_2: [u8; 2] = [254, 253];
+
+/// Versions in the supported_versions extension.
+pub const DTLS_1_2_VERSION: u16 = 0xfefd;
+pub const DTLS_1_3_VERSION: u16 = 0xfefc;
+
+pub const TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256: u16 = 0xc02b;
+pub const TLS_AES_128_GCM_SHA256: u16 = 0x1301;
+pub const SECP256R1: u16 = 23;
+pub const ECDSA_SECP256R1_SHA256: u16 = 0x0403;
+pub const CERT_TYPE_ECDSA_SIGN: u8 = 64;
+pub const CURVE_TYPE_NAMED: u8 = 3;
+pub const POINT_FORMAT_UNCOMPRESSED: u8 = 0;
+
+const EXT_SUPPORTED_GROUPS: u16 = 10;
+const EXT_EC_POINT_FORMATS: u16 = 11;
+const EXT_SIGNATURE_ALGORITHMS: u16 = 13;
+const EXT_USE_SRTP: u16 = 14;
+const EXT_EXTENDED_MASTER_SECRET: u16 = 23;
+const EXT_SUPPORTED_VERSIONS: u16 = 43;
+const EXT_COOKIE: u16 = 44;
+const EXT_KEY_SHARE: u16 = 51;
+const EXT_RENEGOTIATION_INFO: u16 = 0xff01;
+
+/// Length of the DTLS handshake message header.
+pub const HANDSHAKE_HEADER_LEN: usize = 12;
+
+/// Length of the DTLS record header.
+pub const RECORD_HEADER_LEN: usize = 13;
+
+/// Length of the DTLS 1.3 unified header we send, with 16 bit sequence number and length.
+pub const UNIFIED_HEADER_LEN: usize = 5;
+
+pub struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Reader(buf)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn rest(&self) -> &'a [u8] {
+        self.0
+    }
+
+    pub fn bytes(&mut self, n: usize) -> Result<&'a [u8], RustCryptoError> {
+        if self.0.len() < n {
+            return Err(RustCryptoError::Decode("truncated"));
+        }
+        let (a, b) = self.0.split_at(n);
+        self.0 = b;
+        Ok(a)
+    }
+
+    pub fn u8(&mut self) -> Result<u8, RustCryptoError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn u16(&mut self) -> Result<u16, RustCryptoError> {
+        let b = self.bytes(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    pub fn u24(&mut self) -> Result<usize, RustCryptoError> {
+        let b = self.bytes(3)?;
+        Ok(((b[0] as usize) << 16) | ((b[1] as usize) << 8) | b[2] as usize)
+    }
+
+    pub fn u48(&mut self) -> Result<u64, RustCryptoError> {
+        let b = self.bytes(6)?;
+        Ok(b.iter().fold(0, |acc, v| (acc << 8) | *v as u64))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, RustCryptoError> {
+        let b = self.bytes(8)?;
+        Ok(u64::from_be_bytes(b.try_into().unwrap()))
+    }
+
+    pub fn vec8(&mut self) -> Result<&'a [u8], RustCryptoError> {
+        let n = self.u8()? as usize;
+        self.bytes(n)
+    }
+
+    pub fn vec16(&mut self) -> Result<&'a [u8], RustCryptoError> {
+        let n = self.u16()? as usize;
+        self.bytes(n)
+    }
+
+    pub fn vec24(&mut self) -> Result<&'a [u8], RustCryptoError> {
+        let n = self.u24()?;
+        self.bytes(n)
+    }
+
+    fn u16_list(&mut self) -> Result<Vec<u16>, RustCryptoError> {
+        let mut r = Reader(self.vec16()?);
+        let mut v = vec![];
+        while !r.is_empty() {
+            v.push(r.u16()?);
+        }
+        Ok(v)
+    }
+}
+
+pub fn put_u16(out: &mut Vec<u8>, v: u16) {
+    out.extend_from_slice(&v.to_be_bytes());
+}
+
+pub fn put_u24(out: &mut Vec<u8>, v: usize) {
+    out.extend_from_slice(&(v as u32).to_be_bytes()[1..]);
+}
+
+pub fn put_u48(out: &mut Vec<u8>, v: u64) {
+    out.extend_from_slice(&v.to_be_bytes()[2..]);
+}
+
+fn put_vec8(out: &mut Vec<u8>, v: &[u8]) {
+    out.push(v.len() as u8);
+    out.extend_from_slice(v);
+}
+
+fn put_vec16(out: &mut Vec<u8>, v: &[u8]) {
+    put_u16(out, v.len() as u16);
+    out.extend_from_slice(v);
+}
+
+fn put_vec24(out: &mut Vec<u8>, v: &[u8]) {
+    put_u24(out, v.len());
+    out.extend_from_slice(v);
+}
+
+fn u16_list(v: &[u16]) -> Vec<u8> {
+    v.iter().flat_map(|x| x.to_be_bytes()).collect()
+}
+
+/// Header of a (possibly fragmented) handshake message.
+#[derive(Debug, Clone, Copy)]
+pub struct HandshakeHeader {
+    pub msg_type: u8,
+    pub length: usize,
+    pub message_seq: u16,
+    pub fragment_offset: usize,
+    pub fragment_length: usize,
+}
+
+impl HandshakeHeader {
+    pub fn parse(r: &mut Reader) -> Result<Self, RustCryptoError> {
+        let h = HandshakeHeader {
+            msg_type: r.u8()?,
+            length: r.u24()?,
+            message_seq: r.u16()?,
+            fragment_offset: r.u24()?,
+            fragment_length: r.u24()?,
+        };
+        if h.fragment_offset + h.fragment_length > h.length {
+            return Err(RustCryptoError::Decode("fragment beyond message length"));
+        }
+        Ok(h)
+    }
+
+    pub fn write(&self, out: &mut Vec<u8>) {
+        out.push(self.msg_type);
+        put_u24(out, self.length);
+        put_u16(out, self.message_seq);
+        put_u24(out, self.fragment_offset);
+        put_u24(out, self.fragment_length);
+    }
+}
+
+/// Serialize a whole (unfragmented) handshake message including the header.
+///
+/// This is also the form used for the handshake transcript.
+pub fn handshake_message(msg_type: u8, message_seq: u16, body: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(HANDSHAKE_HEADER_LEN + body.len());
+    HandshakeHeader {
+        msg_type,
+        length: body.len(),
+        message_seq,
+        fragment_offset: 0,
+        fragment_length: body.len(),
+    }
+    .write(&mut out);
+    out.extend_from_slice(body);
+    out
+}
+
+/// Serialize a handshake message as it goes into the DTLS 1.3 transcript.
+///
+/// DTLS 1.3 hashes the messages as in TLS 1.3, without message_seq and fragment
+/// fields (RFC 9147 section 5.2).
+pub fn tls13_message(msg_type: u8, body: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(4 + body.len());
+    out.push(msg_type);
+    put_u24(&mut out, body.len());
+    out.extend_from_slice(body);
+    out
+}
+
+/// The hello extensions we care about.
+#[derive(Debug, Default, Clone)]
+pub struct Extensions {
+    pub srtp_profiles: Vec<u16>,
+    pub supported_groups: Vec<u16>,
+    pub point_formats: Vec<u8>,
+    pub signature_algorithms: Vec<u16>,
+    pub extended_master_secret: bool,
+    pub renegotiation_info: bool,
+    /// DTLS 1.3 supported_versions. A single entry outside the ClientHello.
+    pub supported_versions: Vec<u16>,
+    /// DTLS 1.3 key_share entries. A single entry outside the ClientHello.
+    pub key_shares: Vec<(u16, Vec<u8>)>,
+    /// DTLS 1.3 key_share of a HelloRetryRequest, the group to send a key share for.
+    pub selected_group: Option<u16>,
+    /// DTLS 1.3 cookie, sent in a HelloRetryRequest and echoed in the ClientHello.
+    pub cookie: Vec<u8>,
+}
+
+impl Extensions {
+    /// Parse extensions. Some extensions are encoded differently in the ClientHello.
+    fn parse(r: &mut Reader, client_hello: bool) -> Result<Self, RustCryptoError> {
+        let mut ext = Extensions::default();
+        if r.is_empty() {
+            return Ok(ext);
+        }
+
+        let mut r = Reader(r.vec16()?);
+        while !r.is_empty() {
+            let typ = r.u16()?;
+            let mut data = Reader(r.vec16()?);
+            match typ {
+                EXT_USE_SRTP => {
+                    ext.srtp_profiles = data.u16_list()?;
+                    // MKI is ignored
+                }
+                EXT_SUPPORTED_GROUPS => ext.supported_groups = data.u16_list()?,
+                EXT_EC_POINT_FORMATS => ext.point_formats = data.vec8()?.to_vec(),
+                EXT_SIGNATURE_ALGORITHMS => ext.signature_algorithms = data.u16_list()?,
+                EXT_EXTENDED_MASTER_SECRET => ext.extended_master_secret = true,
+                EXT_SUPPORTED_VERSIONS if client_hello => {
+                    let mut list = Reader(data.vec8()?);
+                    while !list.is_empty() {
+                        ext.supported_versions.push(list.u16()?);
+                    }
+                }
+                EXT_SUPPORTED_VERSIONS => ext.supported_versions = vec![data.u16()?],
+                EXT_KEY_SHARE if client_hello => {
+                    let mut list = Reader(data.vec16()?);
+                    while !list.is_empty() {
+                        let group = list.u16()?;
+                        ext.key_shares.push((group, list.vec16()?.to_vec()));
+                    }
+                }
+                EXT_KEY_SHARE if data.rest().len() == 2 => {
+                    ext.selected_group = Some(data.u16()?);
+                }
+                EXT_KEY_SHARE => {
+                    let group = data.u16()?;
+                    ext.key_shares = vec![(group, data.vec16()?.to_vec())];
+                }
+                EXT_COOKIE => ext.cookie = data.vec16()?.to_vec(),
+                EXT_RENEGOTIATION_INFO => {
+                    // Only initial handshakes, so the renegotiated_connection must be empty.
+                    if !data.vec8()?.is_empty() {
+                        return Err(RustCryptoError::Handshake("renegotiation not supported"));
+                    }
+                    ext.renegotiation_info = true;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(ext)
+    }
+
+    fn write(&self, out: &mut Vec<u8>, client_hello: bool) {
+        let mut ext = vec![];
+        let mut add = |typ: u16, data: &[u8]| {
+            put_u16(&mut ext, typ);
+            put_vec16(&mut ext, data);
+        };
+
+        if self.renegotiation_info {
+            add(EXT_RENEGOTIATION_INFO, &[0]);
+        }
+        if self.extended_master_secret {
+            add(EXT_EXTENDED_MASTER_SECRET, &[]);
+        }
+        if !self.supported_groups.is_empty() {
+            let mut d = vec![];
+            put_vec16(&mut d, &u16_list(&self.supported_groups));
+            add(EXT_SUPPORTED_GROUPS, &d);
+        }
+        if !self.point_formats.is_empty() {
+            let mut d = vec![];
+            put_vec8(&mut d, &self.point_formats);
+            add(EXT_EC_POINT_FORMATS, &d);
+        }
+        if !self.signature_algorithms.is_empty() {
+            let mut d = vec![];
+            put_vec16(&mut d, &u16_list(&self.signature_algorithms));
+            add(EXT_SIGNATURE_ALGORITHMS, &d);
+        }
+        if !self.srtp_profiles.is_empty() {
+            let mut d = vec![];
+            put_vec16(&mut d, &u16_list(&self.srtp_profiles));
+            put_vec8(&mut d, &[]); // no MKI
+            add(EXT_USE_SRTP, &d);
+        }
+        if !self.supported_versions.is_empty() {
+            let versions = u16_list(&self.supported_versions);
+            if client_hello {
+                let mut d = vec![];
+                put_vec8(&mut d, &versions);
+                add(EXT_SUPPORTED_VERSIONS, &d);
+            } else {
+                add(EXT_SUPPORTED_VERSIONS, &versions);
+            }
+        }
+        if !self.key_shares.is_empty() {
+            let mut entries = vec![];
+            for (group, key) in &self.key_shares {
+                put_u16(&mut entries, *group);
+                put_vec16(&mut entries, key);
+            }
+            if client_hello {
+                let mut d = vec![];
+                put_vec16(&mut d, &entries);
+                add(EXT_KEY_SHARE, &d);
+            } else {
+                add(EXT_KEY_SHARE, &entries);
+            }
+        }
+        if let Some(group) = self.selected_group {
+            add(EXT_KEY_SHARE, &group.to_be_bytes());
+        }
+        if !self.cookie.is_empty() {
+            let mut d = vec![];
+            put_vec16(&mut d, &self.cookie);
+            add(EXT_COOKIE, &d);
+        }
+
+        put_vec16(out, &ext);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ClientHello {
+    pub random: [u8; 32],
+    pub session_id: Vec<u8>,
+    pub cookie: Vec<u8>,
+    pub cipher_suites: Vec<u16>,
+    pub extensions: Extensions,
+}
+
+impl ClientHello {
+    pub fn parse(body: &[u8]) -> Result<Self, RustCryptoError> {
+        let mut r = Reader(body);
+        let _version = r.bytes(2)?;
+        let random = r.bytes(32)?.try_into().unwrap();
+        let session_id = r.vec8()?.to_vec();
+        let cookie = r.vec8()?.to_vec();
+        let cipher_suites = r.u16_list()?;
+        let _compression = r.vec8()?;
+        let extensions = Extensions::parse(&mut r, true)?;
+        Ok(ClientHello {
+            random,
+            session_id,
+            cookie,
+            cipher_suites,
+            extensions,
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = vec![];
+        out.extend_from_slice(&DTLS_1_2);
+        out.extend_from_slice(&self.random);
+        put_vec8(&mut out, &self.session_id);
+        put_vec8(&mut out, &self.cookie);
+        put_vec16(&mut out, &u16_list(&self.cipher_suites));
+        put_vec8(&mut out, &[0]); // null compression
+        self.extensions.write(&mut out, true);
+        out
+    }
+}
+
+pub struct HelloVerifyRequest {
+    pub cookie: Vec<u8>,
+}
+
+impl HelloVerifyRequest {
+    pub fn parse(body: &[u8]) -> Result<Self, RustCryptoError> {
+        let mut r = Reader(body);
+        let _version = r.bytes(2)?;
+        let cookie = r.vec8()?.to_vec();
+        Ok(HelloVerifyRequest { cookie })
+    }
+}
+
+pub struct ServerHello {
+    pub random: [u8; 32],
+    /// Echo of the ClientHello session_id in DTLS 1.3, empty in DTLS 1.2.
+    pub session_id: Vec<u8>,
+    pub cipher_suite: u16,
+    pub extensions: Extensions,
+}
+
+impl ServerHello {
+    pub fn parse(body: &[u8]) -> Result<Self, RustCryptoError> {
+        let mut r = Reader(body);
+        let version = r.bytes(2)?;
+        if version != DTLS_1_2 {
+            return Err(RustCryptoError::Handshake("only DTLS 1.2 is supported"));
+        }
+        let random = r.bytes(32)?.try_into().unwrap();
+        let session_id = r.vec8()?.to_vec();
+        let cipher_suite = r.u16()?;
+        let compression = r.u8()?;
+        if compression != 0 {
+            return Err(RustCryptoError::Handshake("compression not supported"));
+        }
+        let extensions = Extensions::parse(&mut r, false)?;
+        Ok(ServerHello {
+            random,
+            session_id,
+            cipher_suite,
+            extensions,
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = vec![];
+        out.extend_from_slice(&DTLS_1_2);
+        out.extend_from_slice(&self.random);
+        put_vec8(&mut out, &self.session_id);
+        put_u16(&mut out, self.cipher_suite);
+        out.push(0); // null compression
+        self.extensions.write(&mut out, false);
+        out
+    }
+}
+
+pub struct Certificate {
+    pub certs: Vec<Vec<u8>>,
+}
+
+impl Certificate {
+    pub fn parse(body: &[u8]) -> Result<Self, RustCryptoError> {
+        let mut list = Reader(Reader(body).vec24()?);
+        let mut certs = vec![];
+        while !list.is_empty() {
+            certs.push(list.vec24()?.to_vec());
+        }
+        Ok(Certificate { certs })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut list = vec![];
+        for c in &self.certs {
+            put_vec24(&mut list, c);
+        }
+        let mut out = vec![];
+        put_vec24(&mut out, &list);
+        out
+    }
+}
+
+pub struct ServerKeyExchange {
+    /// ServerECDHParams, which is also part of what is signed.
+    pub params: Vec<u8>,
+    pub public: Vec<u8>,
+    pub sig_alg: u16,
+    pub signature: Vec<u8>,
+}
+
+impl ServerKeyExchange {
+    pub fn params(public: &[u8]) -> Vec<u8> {
+        let mut params = vec![CURVE_TYPE_NAMED];
+        put_u16(&mut params, SECP256R1);
+        put_vec8(&mut params, public);
+        params
+    }
+
+    pub fn parse(body: &[u8]) -> Result<Self, RustCryptoError> {
+        let mut r = Reader(body);
+        let curve_type = r.u8()?;
+        let curve = r.u16()?;
+        if curve_type != CURVE_TYPE_NAMED || curve != SECP256R1 {
+            return Err(RustCryptoError::Handshake("unsupported curve"));
+        }
+        let public = r.vec8()?.to_vec();
+        let params = body[..body.len() - r.rest().len()].to_vec();
+        let sig_alg = r.u16()?;
+        let signature = r.vec16()?.to_vec();
+        Ok(ServerKeyExchange {
+            params,
+            public,
+            sig_alg,
+            signature,
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = self.params.clone();
+        put_u16(&mut out, self.sig_alg);
+        put_vec16(&mut out, &self.signature);
+        out
+    }
+}
+
+pub struct CertificateRequest {
+    pub cert_types: Vec<u8>,
+    pub signature_algorithms: Vec<u16>,
+}
+
+impl CertificateRequest {
+    pub fn parse(body: &[u8]) -> Result<Self, RustCryptoError> {
+        let mut r = Reader(body);
+        let cert_types = r.vec8()?.to_vec();
+        let signature_algorithms = r.u16_list()?;
+        let _authorities = r.vec16()?;
+        Ok(CertificateRequest {
+            cert_types,
+            signature_algorithms,
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = vec![];
+        put_vec8(&mut out, &self.cert_types);
+        put_vec16(&mut out, &u16_list(&self.signature_algorithms));
+        put_vec16(&mut out, &[]); // no certificate authorities
+        out
+    }
+}
+
+/// DTLS 1.3 EncryptedExtensions.
+pub fn parse_encrypted_extensions(body: &[u8]) -> Result<Extensions, RustCryptoError> {
+    Extensions::parse(&mut Reader(body), false)
+}
+
+pub fn encode_encrypted_extensions(extensions: &Extensions) -> Vec<u8> {
+    let mut out = vec![];
+    extensions.write(&mut out, false);
+    out
+}
+
+/// DTLS 1.3 CertificateRequest.
+pub struct CertificateRequest13 {
+    pub context: Vec<u8>,
+    pub signature_algorithms: Vec<u16>,
+}
+
+impl CertificateRequest13 {
+    pub fn parse(body: &[u8]) -> Result<Self, RustCryptoError> {
+        let mut r = Reader(body);
+        let context = r.vec8()?.to_vec();
+        let extensions = Extensions::parse(&mut r, false)?;
+        Ok(CertificateRequest13 {
+            context,
+            signature_algorithms: extensions.signature_algorithms,
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = vec![];
+        put_vec8(&mut out, &self.context);
+        Extensions {
+            signature_algorithms: self.signature_algorithms.clone(),
+            ..Default::default()
+        }
+        .write(&mut out, false);
+        out
+    }
+}
+
+/// DTLS 1.3 Certificate, which has a context and per certificate extensions.
+pub struct Certificate13 {
+    pub context: Vec<u8>,
+    pub certs: Vec<Vec<u8>>,
+}
+
+impl Certificate13 {
+    pub fn parse(body: &[u8]) -> Result<Self, RustCryptoError> {
+        let mut r = Reader(body);
+        let context = r.vec8()?.to_vec();
+        let mut list = Reader(r.vec24()?);
+        let mut certs = vec![];
+        while !list.is_empty() {
+            certs.push(list.vec24()?.to_vec());
+            let _extensions = list.vec16()?;
+        }
+        Ok(Certificate13 { context, certs })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut list = vec![];
+        for c in &self.certs {
+            put_vec24(&mut list, c);
+            put_vec16(&mut list, &[]);
+        }
+        let mut out = vec![];
+        put_vec8(&mut out, &self.context);
+        put_vec24(&mut out, &list);
+        out
+    }
+}
+
+/// DTLS 1.3 ACK, a list of record numbers (epoch, sequence number).
+pub fn parse_ack(body: &[u8]) -> Result<Vec<(u64, u64)>, RustCryptoError> {
+    let mut list = Reader(Reader(body).vec16()?);
+    let mut records = vec![];
+    while !list.is_empty() {
+        records.push((list.u64()?, list.u64()?));
+    }
+    Ok(records)
+}
+
+pub fn encode_ack(records: &[(u64, u64)]) -> Vec<u8> {
+    let mut list = vec![];
+    for (epoch, seq) in records {
+        list.extend_from_slice(&epoch.to_be_bytes());
+        list.extend_from_slice(&seq.to_be_bytes());
+    }
+    let mut out = vec![];
+    put_vec16(&mut out, &list);
+    out
+}
+
+/// ClientKeyExchange for ECDHE, the client public point.
+pub fn parse_client_key_exchange(body: &[u8]) -> Result<Vec<u8>, RustCryptoError> {
+    Ok(Reader(body).vec8()?.to_vec())
+}
+
+pub fn encode_client_key_exchange(public: &[u8]) -> Vec<u8> {
+    let mut out = vec![];
+    put_vec8(&mut out, public);
+    out
+}
+
+/// DigitallySigned, as in CertificateVerify.
+pub fn parse_digitally_signed(body: &[u8]) -> Result<(u16, Vec<u8>), RustCryptoError> {
+    let mut r = Reader(body);
+    let sig_alg = r.u16()?;
+    let signature = r.vec16()?.to_vec();
+    Ok((sig_alg, signature))
+}
+
+pub fn encode_digitally_signed(sig_alg: u16, signature: &[u8]) -> Vec<u8> {
+    let mut out = vec![];
+    put_u16(&mut out, sig_alg);
+    put_vec16(&mut out, signature);
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn client_hello_round_trip() {
+        let ch = ClientHello {
+            random: [7; 32],
+            session_id: vec![],
+            cookie: vec![1, 2, 3],
+            cipher_suites: vec![TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256, 0xc02f],
+            extensions: Extensions {
+                srtp_profiles: vec![7, 1],
+                supported_groups: vec![SECP256R1],
+                point_formats: vec![POINT_FORMAT_UNCOMPRESSED],
+                signature_algorithms: vec![ECDSA_SECP256R1_SHA256],
+                extended_master_secret: true,
+                renegotiation_info: true,
+                supported_versions: vec![DTLS_1_3_VERSION, DTLS_1_2_VERSION],
+                key_shares: vec![(SECP256R1, vec![4; 65])],
+                selected_group: None,
+                cookie: vec![6; 20],
+            },
+        };
+
+        let parsed = ClientHello::parse(&ch.encode()).unwrap();
+        assert_eq!(parsed.random, ch.random);
+        assert_eq!(parsed.cookie, ch.cookie);
+        assert_eq!(parsed.cipher_suites, ch.cipher_suites);
+        assert_eq!(parsed.extensions.srtp_profiles, vec![7, 1]);
+        assert_eq!(parsed.extensions.supported_groups, vec![SECP256R1]);
+        assert!(parsed.extensions.extended_master_secret);
+        assert!(parsed.extensions.renegotiation_info);
+        assert_eq!(
+            parsed.extensions.supported_versions,
+            vec![DTLS_1_3_VERSION, DTLS_1_2_VERSION]
+        );
+        assert_eq!(parsed.extensions.key_shares, vec![(SECP256R1, vec![4; 65])]);
+        assert_eq!(parsed.extensions.cookie, vec![6; 20]);
+    }
+
+    #[test]
+    fn server_hello_13_round_trip() {
+        let sh = ServerHello {
+            random: [3; 32],
+            session_id: vec![5; 32],
+            cipher_suite: TLS_AES_128_GCM_SHA256,
+            extensions: Extensions {
+                supported_versions: vec![DTLS_1_3_VERSION],
+                key_shares: vec![(SECP256R1, vec![4; 65])],
+                ..Default::default()
+            },
+        };
+
+        let parsed = ServerHello::parse(&sh.encode()).unwrap();
+        assert_eq!(parsed.session_id, sh.session_id);
+        assert_eq!(parsed.extensions.supported_versions, vec![DTLS_1_3_VERSION]);
+        assert_eq!(parsed.extensions.key_shares, vec![(SECP256R1, vec![4; 65])]);
+
+        let hrr = ServerHello {
+            random: [3; 32],
+            session_id: vec![],
+            cipher_suite: TLS_AES_128_GCM_SHA256,
+            extensions: Extensions {
+                supported_versions: vec![DTLS_1_3_VERSION],
+                selected_group: Some(SECP256R1),
+                cookie: vec![6; 20],
+                ..Default::default()
+            },
+        };
+
+        let parsed = ServerHello::parse(&hrr.encode()).unwrap();
+        assert_eq!(parsed.extensions.selected_group, Some(SECP256R1));
+        assert!(parsed.extensions.key_shares.is_empty());
+        assert_eq!(parsed.extensions.cookie, vec![6; 20]);
+
+        let acks = [(2, 0), (2, 1), (3, 7)];
+        assert_eq!(parse_ack(&encode_ack(&acks)).unwrap(), acks);
+    }
+
+    #[test]
+    fn handshake_header() {
+        let msg = handshake_message(FINISHED, 5, &[9; 12]);
+        let mut r = Reader::new(&msg);
+        let h = HandshakeHeader::parse(&mut r).unwrap();
+        assert_eq!(h.msg_type, FINISHED);
+        assert_eq!(h.length, 12);
+        assert_eq!(h.message_seq, 5);
+        assert_eq!(h.fragment_offset, 0);
+        assert_eq!(h.fragment_length, 12);
+        assert_eq!(r.rest(), &[9; 12]);
+    }
+}
@@ -0,0 +1,83 @@
+//! Pure rust implementation of cryptographic functions using the RustCrypto crates.
+//!
+//! This includes a DTLS 1.2 and 1.3 implementation limited to what WebRTC needs: ECDHE
+//! with ECDSA P-256 certificates, AES-128-GCM and the `use_srtp` extension.
+
+use thiserror::Error;
+
+use super::CryptoError;
+
+mod cert;
+pub use cert::RustCryptoDtlsCert;
+
+mod hkdf;
+mod message;
+mod prf;
+
+mod dtls;
+pub use dtls::RustCryptoDtlsImpl;
+
+mod srtp;
+pub use srtp::RustCryptoSrtpCryptoImpl;
+
+/// Errors from the pure rust crypto provider.
+#[derive(Debug, Error)]
+pub enum RustCryptoError {
+    /// A DTLS record or handshake message could not be parsed.
+    #[error("DTLS decode: {0}")]
+    Decode(&'static str),
+
+    /// The DTLS handshake failed.
+    #[error("DTLS handshake: {0}")]
+    Handshake(&'static str),
+
+    /// The remote peer sent a fatal alert.
+    #[error("DTLS alert from remote: {0}")]
+    Alert(u8),
+
+    /// The remote certificate is not usable.
+    #[error("DTLS certificate: {0}")]
+    Certificate(&'static str),
+}
+
+/// SHA1 HMAC when neither the sha1 nor openssl features are enabled.
+#[cfg(not(feature = "sha1"))]
+pub fn sha1_hmac(key: &[u8], payloads: &[&[u8]]) -> [u8; 20] {
+    use sha1_smol::Sha1;
+
+    const BLOCK_LEN: usize = 64;
+
+    let mut k = [0_u8; BLOCK_LEN];
+    if key.len() > BLOCK_LEN {
+        k[..20].copy_from_slice(&Sha1::from(key).digest().bytes());
+    } else {
+        k[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = Sha1::new();
+    inner.update(&k.map(|b| b ^ 0x36));
+    for payload in payloads {
+        inner.update(payload);
+    }
+
+    let mut outer = Sha1::new();
+    outer.update(&k.map(|b| b ^ 0x5c));
+    outer.update(&inner.digest().bytes());
+    outer.digest().bytes()
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    #[cfg(not(feature = "sha1"))]
+    fn sha1_hmac_rfc2202() {
+        let tag = super::sha1_hmac(b"Jefe", &[b"what do ya want ", b"for nothing?"]);
+        assert_eq!(
+            tag,
+            [
+                0xef, 0xfc, 0xdf, 0x6a, 0xe5, 0xeb, 0x2f, 0xa2, 0xd2, 0x74, 0x16, 0xd5, 0xf1, 0x84,
+                0xdf, 0x9c, 0x25, 0x9a, 0x7c, 0x79
+            ]
+        );
+    }
+}
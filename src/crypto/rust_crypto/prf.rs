@@ -0,0 +1,67 @@
+//! TLS 1.2 pseudo random function (RFC 5246 section 5).
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// PRF(secret, label, seed) with SHA-256, filling `out`.
+pub fn prf_sha256(secret: &[u8], label: &[u8], seeds: &[&[u8]], out: &mut [u8]) {
+    let hmac = || HmacSha256::new_from_slice(secret).expect("HMAC can take key of any size");
+
+    // A(1) = HMAC(secret, label + seed)
+    let mut a = {
+        let mut h = hmac();
+        h.update(label);
+        seeds.iter().for_each(|s| h.update(s));
+        h.finalize().into_bytes()
+    };
+
+    let mut pos = 0;
+    while pos < out.len() {
+        // P_hash = HMAC(secret, A(i) + label + seed)
+        let mut h = hmac();
+        h.update(&a);
+        h.update(label);
+        seeds.iter().for_each(|s| h.update(s));
+        let chunk = h.finalize().into_bytes();
+
+        let n = chunk.len().min(out.len() - pos);
+        out[pos..pos + n].copy_from_slice(&chunk[..n]);
+        pos += n;
+
+        // A(i+1) = HMAC(secret, A(i))
+        let mut h = hmac();
+        h.update(&a);
+        a = h.finalize().into_bytes();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn prf_sha256_test_vector() {
+        // Well known test vector for the TLS 1.2 PRF with SHA-256.
+        let secret = [
+            0x9b, 0xbe, 0x43, 0x6b, 0xa9, 0x40, 0xf0, 0x17, 0xb1, 0x76, 0x52, 0x84, 0x9a, 0x71,
+            0xdb, 0x35,
+        ];
+        let seed = [
+            0xa0, 0xba, 0x9f, 0x93, 0x6c, 0xda, 0x31, 0x18, 0x27, 0xa6, 0xf7, 0x96, 0xff, 0xd5,
+            0x19, 0x8c,
+        ];
+        let mut out = [0; 100];
+        prf_sha256(&secret, b"test label", &[&seed], &mut out);
+
+        assert_eq!(
+            out[..16],
+            [
+                0xe3, 0xf2, 0x29, 0xba, 0x72, 0x7b, 0xe1, 0x7b, 0x8d, 0x12, 0x26, 0x20, 0x55, 0x7c,
+                0xd4, 0x53
+            ]
+        );
+        assert_eq!(out[96..], [0x87, 0x34, 0x7b, 0x66]);
+    }
+}
@@ -0,0 +1,276 @@
+use aes::cipher::{BlockEncrypt, KeyIvInit, StreamCipher};
+use aes::{Aes128, Aes256};
+use aes_gcm::aead::AeadInPlace;
+use aes_gcm::{Aes128Gcm, Aes256Gcm, KeyInit, Nonce, Tag};
+
+use crate::crypto::srtp::SrtpCryptoImpl;
+use crate::crypto::srtp::{aead_aes_128_gcm, aes_128_cm_sha1_80};
+use crate::crypto::srtp::{aead_aes_256_gcm, aes_256_cm_sha1_80};
+use crate::crypto::CryptoError;
+
+use super::RustCryptoError;
+
+type Aes128Ctr = ctr::Ctr128BE<Aes128>;
+type Aes256Ctr = ctr::Ctr128BE<Aes256>;
+
+pub struct RustCryptoSrtpCryptoImpl;
+
+impl SrtpCryptoImpl for RustCryptoSrtpCryptoImpl {
+    type Aes128CmSha1_80 = RustCryptoAes128CmSha1_80;
+    type AeadAes128Gcm = RustCryptoAeadAes128Gcm;
+    type Aes256CmSha1_80 = RustCryptoAes256CmSha1_80;
+    type AeadAes256Gcm = RustCryptoAeadAes256Gcm;
+
+    fn srtp_aes_128_ecb_round(&self, key: &[u8], input: &[u8], output: &mut [u8]) {
+        let aes = Aes128::new_from_slice(key).expect("AES key");
+        let block = aes::Block::from_mut_slice(&mut output[..16]);
+        block.copy_from_slice(&input[..16]);
+        aes.encrypt_block(block);
+    }
+
+    fn srtp_aes_256_ecb_round(&self, key: &[u8], input: &[u8], output: &mut [u8]) {
+        let aes = Aes256::new_from_slice(key).expect("AES key");
+        let block = aes::Block::from_mut_slice(&mut output[..16]);
+        block.copy_from_slice(&input[..16]);
+        aes.encrypt_block(block);
+    }
+}
+
+pub struct RustCryptoAes128CmSha1_80 {
+    key: aes_128_cm_sha1_80::AesKey,
+}
+
+impl aes_128_cm_sha1_80::CipherCtx for RustCryptoAes128CmSha1_80 {
+    /// Create a new context for AES-128-CM-SHA1-80 encryption/decryption.
+    ///
+    /// The encrypt flag is ignored, since the same operation is used for both encryption and
+    /// decryption.
+    fn new(key: aes_128_cm_sha1_80::AesKey, _encrypt: bool) -> Self
+    where
+        Self: Sized,
+    {
+        RustCryptoAes128CmSha1_80 { key }
+    }
+
+    fn encrypt(
+        &mut self,
+        iv: &aes_128_cm_sha1_80::RtpIv,
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<(), CryptoError> {
+        let mut cipher = Aes128Ctr::new(&self.key.into(), iv.into());
+        cipher
+            .apply_keystream_b2b(input, &mut output[..input.len()])
+            .expect("output as long as input");
+        Ok(())
+    }
+
+    fn decrypt(
+        &mut self,
+        iv: &aes_128_cm_sha1_80::RtpIv,
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<(), CryptoError> {
+        self.encrypt(iv, input, output)
+    }
+}
+
+pub struct RustCryptoAes256CmSha1_80 {
+    key: aes_256_cm_sha1_80::AesKey,
+}
+
+impl aes_256_cm_sha1_80::CipherCtx for RustCryptoAes256CmSha1_80 {
+    /// Create a new context for AES-256-CM-SHA1-80 encryption/decryption.
+    ///
+    /// The encrypt flag is ignored, since the same operation is used for both encryption and
+    /// decryption.
+    fn new(key: aes_256_cm_sha1_80::AesKey, _encrypt: bool) -> Self
+    where
+        Self: Sized,
+    {
+        RustCryptoAes256CmSha1_80 { key }
+    }
+
+    fn encrypt(
+        &mut self,
+        iv: &aes_256_cm_sha1_80::RtpIv,
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<(), CryptoError> {
+        let mut cipher = Aes256Ctr::new(&self.key.into(), iv.into());
+        cipher
+            .apply_keystream_b2b(input, &mut output[..input.len()])
+            .expect("output as long as input");
+        Ok(())
+    }
+
+    fn decrypt(
+        &mut self,
+        iv: &aes_256_cm_sha1_80::RtpIv,
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<(), CryptoError> {
+        self.encrypt(iv, input, output)
+    }
+}
+
+pub struct RustCryptoAeadAes128Gcm(Aes128Gcm);
+
+impl aead_aes_128_gcm::CipherCtx for RustCryptoAeadAes128Gcm {
+    /// Create a new context for AES-128-GCM encryption/decryption.
+    ///
+    /// The encrypt flag is ignored, since the same key schedule is used
+    /// for both encryption and decryption.
+    fn new(key: aead_aes_128_gcm::AeadKey, _encrypt: bool) -> Self
+    where
+        Self: Sized,
+    {
+        RustCryptoAeadAes128Gcm(Aes128Gcm::new(&key.into()))
+    }
+
+    fn encrypt(
+        &mut self,
+        iv: &[u8; aead_aes_128_gcm::IV_LEN],
+        aad: &[u8],
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<(), CryptoError> {
+        gcm_encrypt(&self.0, iv, aad, input, output)
+    }
+
+    fn decrypt(
+        &mut self,
+        iv: &[u8; aead_aes_128_gcm::IV_LEN],
+        aads: &[&[u8]],
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<usize, CryptoError> {
+        gcm_decrypt(&self.0, iv, aads, input, output)
+    }
+}
+
+pub struct RustCryptoAeadAes256Gcm(Aes256Gcm);
+
+impl aead_aes_256_gcm::CipherCtx for RustCryptoAeadAes256Gcm {
+    /// Create a new context for AES-256-GCM encryption/decryption.
+    ///
+    /// The encrypt flag is ignored, since the same key schedule is used
+    /// for both encryption and decryption.
+    fn new(key: aead_aes_256_gcm::AeadKey, _encrypt: bool) -> Self
+    where
+        Self: Sized,
+    {
+        RustCryptoAeadAes256Gcm(Aes256Gcm::new(&key.into()))
+    }
+
+    fn encrypt(
+        &mut self,
+        iv: &[u8; aead_aes_256_gcm::IV_LEN],
+        aad: &[u8],
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<(), CryptoError> {
+        gcm_encrypt(&self.0, iv, aad, input, output)
+    }
+
+    fn decrypt(
+        &mut self,
+        iv: &[u8; aead_aes_256_gcm::IV_LEN],
+        aads: &[&[u8]],
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<usize, CryptoError> {
+        gcm_decrypt(&self.0, iv, aads, input, output)
+    }
+}
+
+fn gcm_encrypt(
+    aead: &impl AeadInPlace,
+    iv: &[u8; aead_aes_128_gcm::IV_LEN],
+    aad: &[u8],
+    input: &[u8],
+    output: &mut [u8],
+) -> Result<(), CryptoError> {
+    let (buf, rest) = output.split_at_mut(input.len());
+    buf.copy_from_slice(input);
+
+    let tag = aead
+        .encrypt_in_place_detached(Nonce::from_slice(iv), aad, buf)
+        .map_err(|_| RustCryptoError::Decode("AES-GCM encrypt"))?;
+
+    // Append the authentication tag to the output
+    rest[..aead_aes_128_gcm::TAG_LEN].copy_from_slice(&tag);
+
+    Ok(())
+}
+
+fn gcm_decrypt(
+    aead: &impl AeadInPlace,
+    iv: &[u8; aead_aes_128_gcm::IV_LEN],
+    aads: &[&[u8]],
+    input: &[u8],
+    output: &mut [u8],
+) -> Result<usize, CryptoError> {
+    if input.len() < aead_aes_128_gcm::TAG_LEN {
+        return Err(RustCryptoError::Decode("SRTP AES-GCM input too short").into());
+    }
+
+    let (cipher_text, tag) = input.split_at(input.len() - aead_aes_128_gcm::TAG_LEN);
+
+    let aad = aads.concat();
+    let buf = &mut output[..cipher_text.len()];
+    buf.copy_from_slice(cipher_text);
+
+    aead.decrypt_in_place_detached(Nonce::from_slice(iv), &aad, buf, Tag::from_slice(tag))
+        .map_err(|_| RustCryptoError::Decode("SRTP AES-GCM authentication failed"))?;
+
+    Ok(cipher_text.len())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crypto::srtp::aes_128_cm_sha1_80::CipherCtx;
+
+    #[test]
+    fn aes_128_cm_rfc3711() {
+        // RFC 3711 B.2 AES-CM Test Vectors
+        let key = [
+            0x2B, 0x7E, 0x15, 0x16, 0x28, 0xAE, 0xD2, 0xA6, 0xAB, 0xF7, 0x15, 0x88, 0x09, 0xCF,
+            0x4F, 0x3C,
+        ];
+        let iv = [
+            0xF0, 0xF1, 0xF2, 0xF3, 0xF4, 0xF5, 0xF6, 0xF7, 0xF8, 0xF9, 0xFA, 0xFB, 0xFC, 0xFD,
+            0x00, 0x00,
+        ];
+        let mut ctx = RustCryptoAes128CmSha1_80::new(key, true);
+        let mut out = [0; 16];
+        ctx.encrypt(&iv, &[0; 16], &mut out).unwrap();
+        assert_eq!(
+            out,
+            [
+                0xE0, 0x3E, 0xAD, 0x09, 0x35, 0xC9, 0x5E, 0x80, 0xE1, 0x66, 0xB1, 0x6D, 0xD9, 0x2B,
+                0x4E, 0xB4
+            ]
+        );
+    }
+
+    #[test]
+    fn aes_256_ecb_fips197() {
+        // FIPS-197 C.3 AES-256 example vector
+        let key: Vec<u8> = (0..32).collect();
+        let input = [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd,
+            0xee, 0xff,
+        ];
+        let mut out = [0; 32];
+        RustCryptoSrtpCryptoImpl.srtp_aes_256_ecb_round(&key, &input, &mut out);
+        assert_eq!(
+            out[..16],
+            [
+                0x8e, 0xa2, 0xb7, 0xca, 0x51, 0x67, 0x45, 0xbf, 0xea, 0xfc, 0x49, 0x90, 0x4b, 0x49,
+                0x60, 0x89
+            ]
+        );
+    }
+}
@@ -1,35 +1,103 @@
 use std::fmt;
 
 use self::aead_aes_128_gcm::AeadKey;
+use self::aead_aes_256_gcm::AeadKey as AeadKey256;
 use self::aes_128_cm_sha1_80::AesKey;
+use self::aes_256_cm_sha1_80::AesKey as AesKey256;
 
 use super::CryptoProvider;
 
+/// SRTP protection profile negotiated via DTLS (RFC 5764).
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SrtpProfile {
+    /// No encryption, only for tests.
     #[cfg(feature = "_internal_test_exports")]
     PassThrough,
+    /// `SRTP_AES128_CM_HMAC_SHA1_80`
     Aes128CmSha1_80,
+    /// `SRTP_AEAD_AES_128_GCM`
     AeadAes128Gcm,
+    /// `SRTP_AES256_CM_HMAC_SHA1_80` (RFC 6188)
+    ///
+    /// This profile has no DTLS-SRTP id and can't be negotiated via DTLS.
+    Aes256CmSha1_80,
+    /// `SRTP_AEAD_AES_256_GCM`
+    AeadAes256Gcm,
 }
 
 #[allow(dead_code)]
 impl SrtpProfile {
-    // All the profiles we support, ordered from most preferred to least.
-    pub(crate) const ALL: &'static [SrtpProfile] =
+    // All the profiles we support.
+    pub(crate) const ALL: &'static [SrtpProfile] = &[
+        SrtpProfile::AeadAes256Gcm,
+        SrtpProfile::AeadAes128Gcm,
+        SrtpProfile::Aes256CmSha1_80,
+        SrtpProfile::Aes128CmSha1_80,
+    ];
+
+    // The profiles offered via DTLS unless configured, ordered from most preferred to least.
+    pub(crate) const DEFAULT: &'static [SrtpProfile] =
         &[SrtpProfile::AeadAes128Gcm, SrtpProfile::Aes128CmSha1_80];
 
+    /// The id of the profile in the DTLS `use_srtp` extension (RFC 5764).
+    ///
+    /// `None` for profiles that can't be negotiated via DTLS.
+    pub(crate) fn dtls_id(&self) -> Option<u16> {
+        match self {
+            #[cfg(feature = "_internal_test_exports")]
+            SrtpProfile::PassThrough => None,
+            SrtpProfile::Aes128CmSha1_80 => Some(0x0001),
+            SrtpProfile::AeadAes128Gcm => Some(0x0007),
+            SrtpProfile::Aes256CmSha1_80 => None,
+            SrtpProfile::AeadAes256Gcm => Some(0x0008),
+        }
+    }
+
+    pub(crate) fn from_dtls_id(id: u16) -> Option<SrtpProfile> {
+        SrtpProfile::ALL
+            .iter()
+            .copied()
+            .find(|p| p.dtls_id() == Some(id))
+    }
+
+    /// The crypto-suite name in an SDES `a=crypto` line (RFC 4568, RFC 6188, RFC 7714).
+    pub(crate) fn sdes_suite(&self) -> Option<&'static str> {
+        match self {
+            #[cfg(feature = "_internal_test_exports")]
+            SrtpProfile::PassThrough => None,
+            SrtpProfile::Aes128CmSha1_80 => Some("AES_CM_128_HMAC_SHA1_80"),
+            SrtpProfile::AeadAes128Gcm => Some("AEAD_AES_128_GCM"),
+            SrtpProfile::Aes256CmSha1_80 => Some("AES_256_CM_HMAC_SHA1_80"),
+            SrtpProfile::AeadAes256Gcm => Some("AEAD_AES_256_GCM"),
+        }
+    }
+
+    pub(crate) fn from_sdes_suite(suite: &str) -> Option<SrtpProfile> {
+        SrtpProfile::ALL
+            .iter()
+            .copied()
+            .find(|p| p.sdes_suite() == Some(suite))
+    }
+
     /// The length of keying material to extract from the DTLS session in bytes.
+    pub fn keying_material_len(&self) -> usize {
+        let (key, salt) = self.master_key_salt_len();
+        key * 2 + salt * 2
+    }
+
+    /// The length of the master key and the master salt in bytes.
     #[rustfmt::skip]
-    pub(crate) fn keying_material_len(&self) -> usize {
+    pub(crate) fn master_key_salt_len(&self) -> (usize, usize) {
         match self {
             #[cfg(feature = "_internal_test_exports")]
-            SrtpProfile::PassThrough => 0,
-             // MASTER_KEY_LEN * 2 + MASTER_SALT * 2
+            SrtpProfile::PassThrough => (0, 0),
+             // (MASTER_KEY_LEN, MASTER_SALT)
              // TODO: This is a duplication of info that is held in srtp.rs, because we
              // don't want a dependency in that direction.
-            SrtpProfile::Aes128CmSha1_80 => 16 * 2 + 14 * 2,
-            SrtpProfile::AeadAes128Gcm   => 16 * 2 + 12 * 2,
+            SrtpProfile::Aes128CmSha1_80 => (16, 14),
+            SrtpProfile::AeadAes128Gcm   => (16, 12),
+            SrtpProfile::Aes256CmSha1_80 => (32, 14),
+            SrtpProfile::AeadAes256Gcm   => (32, 12),
         }
     }
 }
@@ -43,6 +111,11 @@ pub enum SrtpCrypto {
     WinCrypto(super::wincrypto::WinCryptoSrtpCryptoImpl),
     #[cfg(not(all(feature = "wincrypto", target_os = "windows")))]
     WinCrypto(DummySrtpCryptoImpl),
+    #[cfg(feature = "rust-crypto")]
+    RustCrypto(super::rust_crypto::RustCryptoSrtpCryptoImpl),
+    #[cfg(not(feature = "rust-crypto"))]
+    RustCrypto(DummySrtpCryptoImpl),
+    Custom(Box<dyn super::SrtpCryptoBackend>),
 }
 
 #[allow(clippy::unit_arg)]
@@ -67,6 +140,16 @@ impl SrtpCrypto {
         Self::WinCrypto(DummySrtpCryptoImpl(CryptoProvider::WinCrypto))
     }
 
+    #[cfg(feature = "rust-crypto")]
+    pub fn new_rust_crypto() -> SrtpCrypto {
+        Self::RustCrypto(super::rust_crypto::RustCryptoSrtpCryptoImpl)
+    }
+
+    #[cfg(not(feature = "rust-crypto"))]
+    pub fn new_rust_crypto() -> SrtpCrypto {
+        Self::RustCrypto(DummySrtpCryptoImpl(CryptoProvider::RustCrypto))
+    }
+
     // TODO: Can we avoice dynamic dispatch in this signature? The parameters are:
     //       1. As few "touch points" beteen rtp/srtp.rs and here as possible.
     //       2. Clear contract towards the actual impl.
@@ -79,6 +162,8 @@ impl SrtpCrypto {
         match self {
             SrtpCrypto::OpenSsl(v) => Box::new(v.new_aes_128_cm_sha1_80(key, encrypt)),
             SrtpCrypto::WinCrypto(v) => Box::new(v.new_aes_128_cm_sha1_80(key, encrypt)),
+            SrtpCrypto::RustCrypto(v) => Box::new(v.new_aes_128_cm_sha1_80(key, encrypt)),
+            SrtpCrypto::Custom(v) => v.new_aes_128_cm_sha1_80(key, encrypt),
         }
     }
 
@@ -90,6 +175,34 @@ impl SrtpCrypto {
         match self {
             SrtpCrypto::OpenSsl(v) => Box::new(v.new_aead_aes_128_gcm(key, encrypt)),
             SrtpCrypto::WinCrypto(v) => Box::new(v.new_aead_aes_128_gcm(key, encrypt)),
+            SrtpCrypto::RustCrypto(v) => Box::new(v.new_aead_aes_128_gcm(key, encrypt)),
+            SrtpCrypto::Custom(v) => v.new_aead_aes_128_gcm(key, encrypt),
+        }
+    }
+
+    pub fn new_aes_256_cm_sha1_80(
+        &self,
+        key: AesKey256,
+        encrypt: bool,
+    ) -> Box<dyn aes_256_cm_sha1_80::CipherCtx> {
+        match self {
+            SrtpCrypto::OpenSsl(v) => Box::new(v.new_aes_256_cm_sha1_80(key, encrypt)),
+            SrtpCrypto::WinCrypto(v) => Box::new(v.new_aes_256_cm_sha1_80(key, encrypt)),
+            SrtpCrypto::RustCrypto(v) => Box::new(v.new_aes_256_cm_sha1_80(key, encrypt)),
+            SrtpCrypto::Custom(v) => v.new_aes_256_cm_sha1_80(key, encrypt),
+        }
+    }
+
+    pub fn new_aead_aes_256_gcm(
+        &self,
+        key: AeadKey256,
+        encrypt: bool,
+    ) -> Box<dyn aead_aes_256_gcm::CipherCtx> {
+        match self {
+            SrtpCrypto::OpenSsl(v) => Box::new(v.new_aead_aes_256_gcm(key, encrypt)),
+            SrtpCrypto::WinCrypto(v) => Box::new(v.new_aead_aes_256_gcm(key, encrypt)),
+            SrtpCrypto::RustCrypto(v) => Box::new(v.new_aead_aes_256_gcm(key, encrypt)),
+            SrtpCrypto::Custom(v) => v.new_aead_aes_256_gcm(key, encrypt),
         }
     }
 
@@ -97,6 +210,17 @@ impl SrtpCrypto {
         match self {
             SrtpCrypto::OpenSsl(v) => v.srtp_aes_128_ecb_round(key, input, output),
             SrtpCrypto::WinCrypto(v) => v.srtp_aes_128_ecb_round(key, input, output),
+            SrtpCrypto::RustCrypto(v) => v.srtp_aes_128_ecb_round(key, input, output),
+            SrtpCrypto::Custom(v) => v.srtp_aes_128_ecb_round(key, input, output),
+        }
+    }
+
+    pub fn srtp_aes_256_ecb_round(&self, key: &[u8], input: &[u8], output: &mut [u8]) {
+        match self {
+            SrtpCrypto::OpenSsl(v) => v.srtp_aes_256_ecb_round(key, input, output),
+            SrtpCrypto::WinCrypto(v) => v.srtp_aes_256_ecb_round(key, input, output),
+            SrtpCrypto::RustCrypto(v) => v.srtp_aes_256_ecb_round(key, input, output),
+            SrtpCrypto::Custom(v) => v.srtp_aes_256_ecb_round(key, input, output),
         }
     }
 }
@@ -104,6 +228,8 @@ impl SrtpCrypto {
 pub trait SrtpCryptoImpl {
     type Aes128CmSha1_80: aes_128_cm_sha1_80::CipherCtx;
     type AeadAes128Gcm: aead_aes_128_gcm::CipherCtx;
+    type Aes256CmSha1_80: aes_256_cm_sha1_80::CipherCtx;
+    type AeadAes256Gcm: aead_aes_256_gcm::CipherCtx;
 
     fn new_aes_128_cm_sha1_80(&self, key: AesKey, encrypt: bool) -> Self::Aes128CmSha1_80 {
         <Self::Aes128CmSha1_80 as aes_128_cm_sha1_80::CipherCtx>::new(key, encrypt)
@@ -113,27 +239,48 @@ pub trait SrtpCryptoImpl {
         <Self::AeadAes128Gcm as aead_aes_128_gcm::CipherCtx>::new(key, encrypt)
     }
 
+    fn new_aes_256_cm_sha1_80(&self, key: AesKey256, encrypt: bool) -> Self::Aes256CmSha1_80 {
+        <Self::Aes256CmSha1_80 as aes_256_cm_sha1_80::CipherCtx>::new(key, encrypt)
+    }
+
+    fn new_aead_aes_256_gcm(&self, key: AeadKey256, encrypt: bool) -> Self::AeadAes256Gcm {
+        <Self::AeadAes256Gcm as aead_aes_256_gcm::CipherCtx>::new(key, encrypt)
+    }
+
     fn srtp_aes_128_ecb_round(&self, key: &[u8], input: &[u8], output: &mut [u8]);
+
+    fn srtp_aes_256_ecb_round(&self, key: &[u8], input: &[u8], output: &mut [u8]);
 }
 
+/// The `SRTP_AES128_CM_HMAC_SHA1_80` profile.
 pub mod aes_128_cm_sha1_80 {
     use std::panic::UnwindSafe;
 
     use crate::crypto::CryptoError;
 
+    /// Length of the AES key.
     pub const KEY_LEN: usize = 16;
+    /// Length of the salt.
     pub const SALT_LEN: usize = 14;
+    /// Length of the HMAC key.
     pub const HMAC_KEY_LEN: usize = 20;
+    /// Length of the (truncated) HMAC tag.
     pub const HMAC_TAG_LEN: usize = 10;
+    /// AES key.
     pub type AesKey = [u8; 16];
+    /// Salt.
     pub type RtpSalt = [u8; 14];
+    /// Initialization vector for counter mode.
     pub type RtpIv = [u8; 16];
 
+    /// AES-128 counter mode cipher context.
     pub trait CipherCtx: UnwindSafe + Send + Sync {
+        /// Create a new context for either encrypting or decrypting.
         fn new(key: AesKey, encrypt: bool) -> Self
         where
             Self: Sized;
 
+        /// Encrypt `input` into `output`, which is of equal length.
         fn encrypt(
             &mut self,
             iv: &RtpIv,
@@ -141,6 +288,7 @@ pub mod aes_128_cm_sha1_80 {
             output: &mut [u8],
         ) -> Result<(), CryptoError>;
 
+        /// Decrypt `input` into `output`, which is of equal length.
         fn decrypt(
             &mut self,
             iv: &RtpIv,
@@ -149,19 +297,19 @@ pub mod aes_128_cm_sha1_80 {
         ) -> Result<(), CryptoError>;
     }
 
-    pub fn rtp_hmac(key: &[u8], buf: &mut [u8], srtp_index: u64, hmac_start: usize) {
+    pub(crate) fn rtp_hmac(key: &[u8], buf: &mut [u8], srtp_index: u64, hmac_start: usize) {
         let roc = (srtp_index >> 16) as u32;
         let tag = crate::crypto::sha1_hmac(key, &[&buf[..hmac_start], &roc.to_be_bytes()]);
         buf[hmac_start..(hmac_start + HMAC_TAG_LEN)].copy_from_slice(&tag[0..HMAC_TAG_LEN]);
     }
 
-    pub fn rtp_verify(key: &[u8], buf: &[u8], srtp_index: u64, cmp: &[u8]) -> bool {
+    pub(crate) fn rtp_verify(key: &[u8], buf: &[u8], srtp_index: u64, cmp: &[u8]) -> bool {
         let roc = (srtp_index >> 16) as u32;
         let tag = crate::crypto::sha1_hmac(key, &[buf, &roc.to_be_bytes()]);
         &tag[0..HMAC_TAG_LEN] == cmp
     }
 
-    pub fn rtp_iv(salt: RtpSalt, ssrc: u32, srtp_index: u64) -> RtpIv {
+    pub(crate) fn rtp_iv(salt: RtpSalt, ssrc: u32, srtp_index: u64) -> RtpIv {
         let mut iv = [0; 16];
         let ssrc_be = ssrc.to_be_bytes();
         let srtp_be = srtp_index.to_be_bytes();
@@ -175,38 +323,50 @@ pub mod aes_128_cm_sha1_80 {
         iv
     }
 
-    pub fn rtcp_hmac(key: &[u8], buf: &mut [u8], hmac_index: usize) {
+    pub(crate) fn rtcp_hmac(key: &[u8], buf: &mut [u8], hmac_index: usize) {
         let tag = crate::crypto::sha1_hmac(key, &[&buf[0..hmac_index]]);
 
         buf[hmac_index..(hmac_index + HMAC_TAG_LEN)].copy_from_slice(&tag[0..HMAC_TAG_LEN]);
     }
 
-    pub fn rtcp_verify(key: &[u8], buf: &[u8], cmp: &[u8]) -> bool {
+    pub(crate) fn rtcp_verify(key: &[u8], buf: &[u8], cmp: &[u8]) -> bool {
         let tag = crate::crypto::sha1_hmac(key, &[buf]);
 
         &tag[0..HMAC_TAG_LEN] == cmp
     }
 }
 
+/// The `SRTP_AEAD_AES_128_GCM` profile.
 pub mod aead_aes_128_gcm {
     use std::panic::UnwindSafe;
 
     use crate::crypto::CryptoError;
 
+    /// Length of the AES key.
     pub const KEY_LEN: usize = 16;
+    /// Length of the salt.
     pub const SALT_LEN: usize = 12;
+    /// Length of the additional authenticated data for SRTCP.
     pub const RTCP_AAD_LEN: usize = 12;
+    /// Length of the authentication tag.
     pub const TAG_LEN: usize = 16;
+    /// Length of the initialization vector.
     pub const IV_LEN: usize = 12;
+    /// AES key.
     pub type AeadKey = [u8; KEY_LEN];
+    /// Salt.
     pub type RtpSalt = [u8; SALT_LEN];
+    /// Initialization vector.
     pub type RtpIv = [u8; SALT_LEN];
 
+    /// AES-128-GCM cipher context.
     pub trait CipherCtx: UnwindSafe + Send + Sync {
+        /// Create a new context for either encrypting or decrypting.
         fn new(key: AeadKey, encrypt: bool) -> Self
         where
             Self: Sized;
 
+        /// Encrypt `input` into `output` and append the authentication tag.
         fn encrypt(
             &mut self,
             iv: &[u8; IV_LEN],
@@ -215,6 +375,9 @@ pub mod aead_aes_128_gcm {
             output: &mut [u8],
         ) -> Result<(), CryptoError>;
 
+        /// Decrypt `input`, which ends with the authentication tag, into `output`.
+        ///
+        /// Returns the length of the plain text.
         fn decrypt(
             &mut self,
             iv: &[u8; IV_LEN],
@@ -224,7 +387,7 @@ pub mod aead_aes_128_gcm {
         ) -> Result<usize, CryptoError>;
     }
 
-    pub fn rtp_iv(salt: RtpSalt, ssrc: u32, roc: u32, seq: u16) -> RtpIv {
+    pub(crate) fn rtp_iv(salt: RtpSalt, ssrc: u32, roc: u32, seq: u16) -> RtpIv {
         // See: https://www.rfc-editor.org/rfc/rfc7714#section-8.1
 
         // TODO: See if this is faster if rewritten for u128
@@ -246,7 +409,7 @@ pub mod aead_aes_128_gcm {
         iv
     }
 
-    pub fn rtcp_iv(salt: RtpSalt, ssrc: u32, srtp_index: u32) -> RtpIv {
+    pub(crate) fn rtcp_iv(salt: RtpSalt, ssrc: u32, srtp_index: u32) -> RtpIv {
         // See: https://www.rfc-editor.org/rfc/rfc7714#section-9.1
         // TODO: See if this is faster if rewritten for u128
         let mut iv = [0; SALT_LEN];
@@ -266,6 +429,92 @@ pub mod aead_aes_128_gcm {
     }
 }
 
+/// The `SRTP_AES256_CM_HMAC_SHA1_80` profile (RFC 6188).
+///
+/// Identical to [`aes_128_cm_sha1_80`] apart from the key length.
+pub mod aes_256_cm_sha1_80 {
+    use std::panic::UnwindSafe;
+
+    use crate::crypto::CryptoError;
+
+    pub use super::aes_128_cm_sha1_80::{RtpIv, RtpSalt};
+    pub use super::aes_128_cm_sha1_80::{HMAC_KEY_LEN, HMAC_TAG_LEN, SALT_LEN};
+
+    /// Length of the AES key.
+    pub const KEY_LEN: usize = 32;
+    /// AES key.
+    pub type AesKey = [u8; KEY_LEN];
+
+    /// AES-256 counter mode cipher context.
+    pub trait CipherCtx: UnwindSafe + Send + Sync {
+        /// Create a new context for either encrypting or decrypting.
+        fn new(key: AesKey, encrypt: bool) -> Self
+        where
+            Self: Sized;
+
+        /// Encrypt `input` into `output`, which is of equal length.
+        fn encrypt(
+            &mut self,
+            iv: &RtpIv,
+            input: &[u8],
+            output: &mut [u8],
+        ) -> Result<(), CryptoError>;
+
+        /// Decrypt `input` into `output`, which is of equal length.
+        fn decrypt(
+            &mut self,
+            iv: &RtpIv,
+            input: &[u8],
+            output: &mut [u8],
+        ) -> Result<(), CryptoError>;
+    }
+}
+
+/// The `SRTP_AEAD_AES_256_GCM` profile (RFC 7714).
+///
+/// Identical to [`aead_aes_128_gcm`] apart from the key length.
+pub mod aead_aes_256_gcm {
+    use std::panic::UnwindSafe;
+
+    use crate::crypto::CryptoError;
+
+    pub use super::aead_aes_128_gcm::{RtpIv, RtpSalt};
+    pub use super::aead_aes_128_gcm::{IV_LEN, RTCP_AAD_LEN, SALT_LEN, TAG_LEN};
+
+    /// Length of the AES key.
+    pub const KEY_LEN: usize = 32;
+    /// AES key.
+    pub type AeadKey = [u8; KEY_LEN];
+
+    /// AES-256-GCM cipher context.
+    pub trait CipherCtx: UnwindSafe + Send + Sync {
+        /// Create a new context for either encrypting or decrypting.
+        fn new(key: AeadKey, encrypt: bool) -> Self
+        where
+            Self: Sized;
+
+        /// Encrypt `input` into `output` and append the authentication tag.
+        fn encrypt(
+            &mut self,
+            iv: &[u8; IV_LEN],
+            aad: &[u8],
+            input: &[u8],
+            output: &mut [u8],
+        ) -> Result<(), CryptoError>;
+
+        /// Decrypt `input`, which ends with the authentication tag, into `output`.
+        ///
+        /// Returns the length of the plain text.
+        fn decrypt(
+            &mut self,
+            iv: &[u8; IV_LEN],
+            aads: &[&[u8]],
+            input: &[u8],
+            output: &mut [u8],
+        ) -> Result<usize, CryptoError>;
+    }
+}
+
 impl fmt::Display for SrtpProfile {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -273,6 +522,8 @@ impl fmt::Display for SrtpProfile {
             SrtpProfile::PassThrough => write!(f, "PassThrough"),
             SrtpProfile::Aes128CmSha1_80 => write!(f, "SRTP_AES128_CM_SHA1_80"),
             SrtpProfile::AeadAes128Gcm => write!(f, "SRTP_AEAD_AES_128_GCM"),
+            SrtpProfile::Aes256CmSha1_80 => write!(f, "SRTP_AES256_CM_SHA1_80"),
+            SrtpProfile::AeadAes256Gcm => write!(f, "SRTP_AEAD_AES_256_GCM"),
         }
     }
 }
@@ -282,6 +533,8 @@ pub struct DummySrtpCryptoImpl(CryptoProvider);
 impl SrtpCryptoImpl for DummySrtpCryptoImpl {
     type Aes128CmSha1_80 = ();
     type AeadAes128Gcm = ();
+    type Aes256CmSha1_80 = ();
+    type AeadAes256Gcm = ();
 
     fn new_aes_128_cm_sha1_80(&self, _: AesKey, _: bool) -> Self::Aes128CmSha1_80 {
         panic!("Must enable feature: {}", self.0)
@@ -291,9 +544,21 @@ impl SrtpCryptoImpl for DummySrtpCryptoImpl {
         panic!("Must enable feature: {}", self.0)
     }
 
+    fn new_aes_256_cm_sha1_80(&self, _: AesKey256, _: bool) -> Self::Aes256CmSha1_80 {
+        panic!("Must enable feature: {}", self.0)
+    }
+
+    fn new_aead_aes_256_gcm(&self, _: AeadKey256, _: bool) -> Self::AeadAes256Gcm {
+        panic!("Must enable feature: {}", self.0)
+    }
+
     fn srtp_aes_128_ecb_round(&self, _: &[u8], _: &[u8], _: &mut [u8]) {
         panic!("Must enable feature: {}", self.0)
     }
+
+    fn srtp_aes_256_ecb_round(&self, _: &[u8], _: &[u8], _: &mut [u8]) {
+        panic!("Must enable feature: {}", self.0)
+    }
 }
 
 impl aes_128_cm_sha1_80::CipherCtx for () {
@@ -351,3 +616,59 @@ impl aead_aes_128_gcm::CipherCtx for () {
         unreachable!()
     }
 }
+
+impl aes_256_cm_sha1_80::CipherCtx for () {
+    fn new(_: AesKey256, _: bool) -> Self
+    where
+        Self: Sized,
+    {
+        unreachable!()
+    }
+
+    fn encrypt(
+        &mut self,
+        _: &aes_256_cm_sha1_80::RtpIv,
+        _: &[u8],
+        _: &mut [u8],
+    ) -> Result<(), super::CryptoError> {
+        unreachable!()
+    }
+
+    fn decrypt(
+        &mut self,
+        _: &aes_256_cm_sha1_80::RtpIv,
+        _: &[u8],
+        _: &mut [u8],
+    ) -> Result<(), super::CryptoError> {
+        unreachable!()
+    }
+}
+
+impl aead_aes_256_gcm::CipherCtx for () {
+    fn new(_: AeadKey256, _: bool) -> Self
+    where
+        Self: Sized,
+    {
+        unreachable!()
+    }
+
+    fn encrypt(
+        &mut self,
+        _: &[u8; aead_aes_256_gcm::IV_LEN],
+        _: &[u8],
+        _: &[u8],
+        _: &mut [u8],
+    ) -> Result<(), super::CryptoError> {
+        unreachable!()
+    }
+
+    fn decrypt(
+        &mut self,
+        _: &[u8; aead_aes_256_gcm::IV_LEN],
+        _: &[&[u8]],
+        _: &[u8],
+        _: &mut [u8],
+    ) -> Result<usize, super::CryptoError> {
+        unreachable!()
+    }
+}
@@ -82,11 +82,8 @@ impl DtlsInner for WinCryptoDtls {
 }
 
 fn srtp_profile_from_network_endian_id(srtp_profile_id: u16) -> SrtpProfile {
-    match srtp_profile_id {
-        0x0001 => SrtpProfile::Aes128CmSha1_80,
-        0x0007 => SrtpProfile::AeadAes128Gcm,
-        _ => panic!("Unknown SRTP profile ID: {:04x}", srtp_profile_id),
-    }
+    SrtpProfile::from_dtls_id(srtp_profile_id)
+        .unwrap_or_else(|| panic!("Unknown SRTP profile ID: {:04x}", srtp_profile_id))
 }
 
 fn transform_dtls_event(
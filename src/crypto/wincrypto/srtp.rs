@@ -1,5 +1,6 @@
 use crate::crypto::srtp::SrtpCryptoImpl;
 use crate::crypto::srtp::{aead_aes_128_gcm, aes_128_cm_sha1_80};
+use crate::crypto::srtp::{aead_aes_256_gcm, aes_256_cm_sha1_80};
 use crate::crypto::CryptoError;
 use str0m_wincrypto::{
     srtp_aead_aes_128_gcm_decrypt, srtp_aead_aes_128_gcm_encrypt, srtp_aes_128_cm,
@@ -11,12 +12,19 @@ pub struct WinCryptoSrtpCryptoImpl;
 impl SrtpCryptoImpl for WinCryptoSrtpCryptoImpl {
     type Aes128CmSha1_80 = WinCryptoAes128CmSha1_80;
     type AeadAes128Gcm = WinCryptoAeadAes128Gcm;
+    type Aes256CmSha1_80 = WinCryptoAes128CmSha1_80;
+    type AeadAes256Gcm = WinCryptoAeadAes128Gcm;
 
     fn srtp_aes_128_ecb_round(&self, key: &[u8], input: &[u8], output: &mut [u8]) {
         let key = SrtpKey::create_aes_ecb_key(key).expect("AES key");
         let count = srtp_aes_128_ecb_round(&key, input, output).expect("AES encrypt");
         assert_eq!(count, 16 + 16); // block size
     }
+
+    fn srtp_aes_256_ecb_round(&self, key: &[u8], input: &[u8], output: &mut [u8]) {
+        // CNG picks AES-128 or AES-256 from the key length.
+        self.srtp_aes_128_ecb_round(key, input, output)
+    }
 }
 
 pub struct WinCryptoAes128CmSha1_80 {
@@ -109,3 +117,81 @@ impl aead_aes_128_gcm::CipherCtx for WinCryptoAeadAes128Gcm {
         )?)
     }
 }
+
+// CNG picks the AES variant from the key length, so the 256 bit profiles reuse
+// the same contexts.
+
+impl aes_256_cm_sha1_80::CipherCtx for WinCryptoAes128CmSha1_80 {
+    fn new(key: aes_256_cm_sha1_80::AesKey, _encrypt: bool) -> Self
+    where
+        Self: Sized,
+    {
+        Self {
+            key: SrtpKey::create_aes_ctr_key(&key).expect("generate sym key"),
+        }
+    }
+
+    fn encrypt(
+        &mut self,
+        iv: &aes_256_cm_sha1_80::RtpIv,
+        plain_text: &[u8],
+        cipher_text: &mut [u8],
+    ) -> Result<(), CryptoError> {
+        srtp_aes_128_cm(&self.key, iv, plain_text, cipher_text)?;
+        Ok(())
+    }
+
+    fn decrypt(
+        &mut self,
+        iv: &aes_256_cm_sha1_80::RtpIv,
+        cipher_text: &[u8],
+        plain_text: &mut [u8],
+    ) -> Result<(), CryptoError> {
+        srtp_aes_128_cm(&self.key, iv, cipher_text, plain_text)?;
+        Ok(())
+    }
+}
+
+impl aead_aes_256_gcm::CipherCtx for WinCryptoAeadAes128Gcm {
+    fn new(key: aead_aes_256_gcm::AeadKey, _encrypt: bool) -> Self
+    where
+        Self: Sized,
+    {
+        Self {
+            key: SrtpKey::create_aes_gcm_key(&key).expect("generate sym key"),
+        }
+    }
+
+    fn encrypt(
+        &mut self,
+        iv: &[u8; aead_aes_256_gcm::IV_LEN],
+        additional_auth_data: &[u8],
+        plain_text: &[u8],
+        cipher_text: &mut [u8],
+    ) -> Result<(), CryptoError> {
+        srtp_aead_aes_128_gcm_encrypt(
+            &self.key,
+            iv,
+            additional_auth_data,
+            plain_text,
+            cipher_text,
+        )?;
+        Ok(())
+    }
+
+    fn decrypt(
+        &mut self,
+        iv: &[u8; aead_aes_256_gcm::IV_LEN],
+        additional_auth_data: &[&[u8]],
+        cipher_text: &[u8],
+        plain_text: &mut [u8],
+    ) -> Result<usize, CryptoError> {
+        Ok(srtp_aead_aes_128_gcm_decrypt(
+            &self.key,
+            iv,
+            additional_auth_data,
+            cipher_text,
+            plain_text,
+        )?)
+    }
+}
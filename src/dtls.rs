@@ -3,7 +3,8 @@ use std::time::Instant;
 use std::{fmt, io};
 use thiserror::Error;
 
-use crate::crypto::{CryptoError, DtlsImpl, Fingerprint};
+use crate::crypto::{CryptoError, CryptoProvider, Dtls13Mode, DtlsImpl};
+use crate::crypto::{Fingerprint, FingerprintHash, SrtpProfile};
 
 pub use crate::crypto::{DtlsCert, DtlsCertOptions, DtlsEvent};
 use crate::net::DatagramSend;
@@ -35,9 +36,26 @@ impl DtlsError {
 pub struct Dtls {
     dtls_impl: DtlsImpl,
 
+    /// The certificate in use.
+    cert: DtlsCert,
+
     /// The fingerprint of the certificate.
     fingerprint: Fingerprint,
 
+    /// Certificate to use on the next restart, and whether it has been sent in SDP.
+    pending_cert: Option<(DtlsCert, bool)>,
+
+    /// Hash functions for the fingerprints in SDP.
+    fingerprint_hashes: Vec<FingerprintHash>,
+
+    /// Settings kept to apply again on restart.
+    dtls13: Dtls13Mode,
+    srtp_profiles: Vec<SrtpProfile>,
+    key_log: bool,
+
+    /// Whether this is a restarted DTLS association.
+    restarted: bool,
+
     /// Remote fingerprint.
     remote_fingerprint: Option<Fingerprint>,
 
@@ -56,12 +74,97 @@ impl Dtls {
 
         Ok(Self {
             dtls_impl,
+            cert,
             fingerprint,
+            pending_cert: None,
+            fingerprint_hashes: vec![FingerprintHash::Sha256],
+            dtls13: Dtls13Mode::default(),
+            srtp_profiles: SrtpProfile::DEFAULT.to_vec(),
+            key_log: false,
+            restarted: false,
             remote_fingerprint: None,
             events: VecDeque::new(),
         })
     }
 
+    /// Replace the certificate.
+    ///
+    /// Before [`Dtls::set_active()`] this takes effect straight away, after that it is
+    /// kept for [`Dtls::restart()`].
+    pub fn set_cert(&mut self, cert: DtlsCert) -> Result<(), DtlsError> {
+        if self.is_inited() {
+            self.pending_cert = Some((cert, false));
+            return Ok(());
+        }
+
+        self.use_cert(cert)
+    }
+
+    /// Mark a pending certificate as sent in SDP. Returns true if there is one.
+    pub fn advertise_pending_cert(&mut self) -> bool {
+        if let Some((_, advertised)) = &mut self.pending_cert {
+            *advertised = true;
+        }
+        self.has_advertised_cert()
+    }
+
+    /// Whether there is a pending certificate that has been sent in SDP.
+    pub fn has_advertised_cert(&self) -> bool {
+        matches!(self.pending_cert, Some((_, true)))
+    }
+
+    /// Start a new DTLS association with the same role as the current one.
+    ///
+    /// Uses the pending certificate, if it has been sent in SDP.
+    pub fn restart(&mut self) -> Result<(), DtlsError> {
+        let active = self.is_active().expect("DTLS restart after set_active");
+
+        let cert = match self.pending_cert.take() {
+            Some((cert, true)) => cert,
+            pending => {
+                self.pending_cert = pending;
+                self.cert.clone()
+            }
+        };
+        self.use_cert(cert)?;
+
+        self.remote_fingerprint = None;
+        self.events.clear();
+        self.restarted = true;
+
+        self.set_active(active);
+        if active {
+            self.handle_handshake()?;
+        }
+
+        Ok(())
+    }
+
+    fn use_cert(&mut self, cert: DtlsCert) -> Result<(), DtlsError> {
+        let mut dtls_impl = cert.create_dtls_impl()?;
+        dtls_impl.set_dtls13(self.dtls13)?;
+        dtls_impl.set_srtp_profiles(&self.srtp_profiles)?;
+        if self.key_log {
+            dtls_impl.set_key_log(true);
+        }
+
+        self.dtls_impl = dtls_impl;
+        self.fingerprint = cert.fingerprint();
+        self.cert = cert;
+
+        Ok(())
+    }
+
+    /// Whether this is a restarted DTLS association.
+    pub fn is_restarted(&self) -> bool {
+        self.restarted
+    }
+
+    /// The crypto provider of the certificate in use.
+    pub fn crypto_provider(&self) -> CryptoProvider {
+        self.cert.crypto_provider()
+    }
+
     /// Tells if this instance has been inited.
     ///
     /// Once true, we cannot do `set_active` anymore.
@@ -77,6 +180,29 @@ impl Dtls {
         self.dtls_impl.set_active(active)
     }
 
+    /// Set whether to negotiate DTLS 1.3. Must be called before [`Dtls::set_active()`].
+    pub fn set_dtls13(&mut self, mode: Dtls13Mode) -> Result<(), DtlsError> {
+        self.dtls13 = mode;
+        self.dtls_impl
+            .set_dtls13(mode)
+            .map_err(DtlsError::CryptoError)
+    }
+
+    /// Set the SRTP profiles to offer, most preferred first. Must be called before
+    /// [`Dtls::set_active()`].
+    pub fn set_srtp_profiles(&mut self, profiles: &[SrtpProfile]) -> Result<(), DtlsError> {
+        self.srtp_profiles = profiles.to_vec();
+        self.dtls_impl
+            .set_srtp_profiles(profiles)
+            .map_err(DtlsError::CryptoError)
+    }
+
+    /// Enable key log lines for the handshake. Must be called before [`Dtls::set_active()`].
+    pub fn set_key_log(&mut self, enabled: bool) {
+        self.key_log = enabled;
+        self.dtls_impl.set_key_log(enabled)
+    }
+
     /// If set_active, returns what was set.
     pub fn is_active(&self) -> Option<bool> {
         self.dtls_impl.is_active()
@@ -89,6 +215,34 @@ impl Dtls {
         &self.fingerprint
     }
 
+    /// Set the hash functions used for [`Dtls::sdp_fingerprints()`].
+    pub fn set_fingerprint_hashes(&mut self, hashes: &[FingerprintHash]) {
+        self.fingerprint_hashes = hashes.to_vec();
+    }
+
+    /// The fingerprints to communicate in SDP, one per hash function.
+    ///
+    /// This is the pending certificate if there is one, since it is used
+    /// once the SDP negotiation completes.
+    pub fn sdp_fingerprints(&self) -> Vec<Fingerprint> {
+        let cert = match &self.pending_cert {
+            Some((cert, _)) => cert,
+            None => &self.cert,
+        };
+
+        let v: Vec<_> = self
+            .fingerprint_hashes
+            .iter()
+            .filter_map(|h| cert.fingerprint_with(*h))
+            .collect();
+
+        if v.is_empty() {
+            vec![cert.fingerprint()]
+        } else {
+            v
+        }
+    }
+
     /// Remote fingerprint.
     pub fn remote_fingerprint(&self) -> &Option<Fingerprint> {
         &self.remote_fingerprint
@@ -104,11 +258,19 @@ impl Dtls {
         self.dtls_impl.poll_timeout(now)
     }
 
+    /// Handle a timeout, used to retransmit handshake flights.
+    pub fn handle_timeout(&mut self, now: Instant) {
+        self.dtls_impl.handle_timeout(now)
+    }
+
     /// Poll for an event.
     pub fn poll_event(&mut self) -> Option<DtlsEvent> {
         let x = self.events.pop_front();
-        if x.is_some() {
-            trace!("Poll event: {:?}", x);
+        if let Some(ev) = &x {
+            trace!("Poll event: {:?}", ev);
+            if let DtlsEvent::RemoteFingerprint(fingerprint) = ev {
+                self.remote_fingerprint = Some(fingerprint.clone());
+            }
         }
         x
     }
@@ -132,23 +294,20 @@ impl Dtls {
     ///
     /// Once handshaken, this becomes a noop.
     pub fn handle_handshake(&mut self) -> Result<bool, DtlsError> {
-        let len_before = self.events.len();
-        let result = self.dtls_impl.handle_handshake(&mut self.events)?;
-
-        if self.remote_fingerprint.is_none() && self.events.len() > len_before {
-            for ev in &self.events {
-                if let DtlsEvent::RemoteFingerprint(fingerprint) = ev {
-                    self.remote_fingerprint = Some(fingerprint.clone());
-                }
-            }
-        }
-
-        Ok(result)
+        Ok(self.dtls_impl.handle_handshake(&mut self.events)?)
     }
 
     pub(crate) fn is_connected(&self) -> bool {
         self.dtls_impl.is_connected()
     }
+
+    /// Send a `close_notify` to the remote peer.
+    pub fn close(&mut self) {
+        if self.dtls_impl.is_connected() {
+            debug!("DTLS close_notify");
+            self.dtls_impl.close();
+        }
+    }
 }
 
 impl fmt::Debug for DtlsEvent {
@@ -160,10 +319,16 @@ impl fmt::Debug for DtlsEvent {
                 .field(keying_mat)
                 .field(srtp_profile)
                 .finish(),
+            Self::RemoteCertificate(arg0) => f
+                .debug_tuple("RemoteCertificate")
+                .field(&arg0.len())
+                .finish(),
             Self::RemoteFingerprint(arg0) => {
                 f.debug_tuple("RemoteFingerprint").field(arg0).finish()
             }
             Self::Data(arg0) => f.debug_tuple("Data").field(&arg0.len()).finish(),
+            Self::KeyLog(_) => write!(f, "KeyLog"),
+            Self::Alert(arg0) => f.debug_tuple("Alert").field(arg0).finish(),
         }
     }
 }
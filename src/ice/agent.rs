@@ -1,5 +1,7 @@
-use std::collections::{HashSet, VecDeque};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::net::SocketAddr;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use serde::{Deserialize, Serialize};
@@ -8,10 +10,12 @@ use crate::io::{Id, StunClass, StunMethod, StunTiming, DATAGRAM_MTU_WARN};
 use crate::io::{Protocol, StunPacket};
 use crate::io::{StunMessage, TransId};
 use crate::io::{Transmit, DATAGRAM_MTU};
+use crate::stats::{CandidatePairState, CandidatePairStats};
 use crate::util::NonCryptographicRng;
 
 use super::candidate::{Candidate, CandidateKind};
 use super::pair::{CandidatePair, CheckState, PairId};
+use super::selection::{IcePairInfo, IcePairSelection, PrioPairSelection};
 
 /// Handles the ICE protocol for a given peer.
 ///
@@ -73,6 +77,11 @@ pub struct IceAgent {
     /// The candidate pairs.
     candidate_pairs: Vec<CandidatePair>,
 
+    /// Index into `candidate_pairs` by local base and remote address.
+    ///
+    /// Rebuilt by `reindex_pairs()` whenever `candidate_pairs` changes.
+    pair_by_addrs: HashMap<(SocketAddr, SocketAddr), usize>,
+
     /// Transmit packet ready to be polled by poll_transmit.
     transmit: VecDeque<Transmit>,
 
@@ -96,6 +105,29 @@ pub struct IceAgent {
 
     /// The timing configuration for STUN bindings.
     timing_config: StunTiming,
+
+    /// Whether the local side has signalled that no more local candidates
+    /// will be added (until the next ICE restart).
+    local_end_of_candidates: bool,
+
+    /// Whether the remote side has signalled that no more remote candidates
+    /// will be added (until the next ICE restart).
+    remote_end_of_candidates: bool,
+
+    /// Whether we support renomination (draft-thatcher-ice-renomination).
+    renomination: bool,
+
+    /// Whether the remote side supports renomination.
+    remote_renomination: bool,
+
+    /// Last NOMINATION value sent when controlling and using renomination.
+    nomination_counter: u32,
+
+    /// Policy for selecting the pair to use when controlling.
+    pair_selection: Arc<dyn IcePairSelection>,
+
+    /// Pair forced by the user, overriding the `pair_selection`.
+    forced_pair: Option<PairId>,
 }
 
 #[derive(Debug)]
@@ -107,6 +139,8 @@ struct StunRequest {
     trans_id: TransId,
     prio: u32,
     use_candidate: bool,
+    nomination: Option<u32>,
+    network_cost: Option<u16>,
     remote_ufrag: String,
 }
 
@@ -143,14 +177,18 @@ pub enum IceConnectionState {
     /// or during temporary disconnections. When the problem resolves, the connection
     /// may return to the connected state.
     Disconnected,
+
+    /// The ICE agent has checked all candidate pairs against one another and has
+    /// failed to find compatible matches.
+    ///
+    /// This state is only reachable once both sides have signalled end-of-candidates
+    /// (see [`IceAgent::end_of_local_candidates`] and [`IceAgent::end_of_remote_candidates`]).
+    /// Without that, trickle ice never ends and it's always possible to "come back" if
+    /// more candidates are added. The only way out of this state is an ICE restart.
+    Failed,
     //
-    // NB: The failed and closed state doesn't really have a mapping in this implementation.
-    //     We never end trickle ice and it's always possible to "come back" if more remote
-    //     candidates are added.
+    // NB: The closed state doesn't really have a mapping in this implementation.
     //
-    // The ICE candidate has checked all candidates pairs against one another and has
-    // failed to find compatible matches.
-    // Failed,
     // The ICE agent has shut down and is no longer handling requests.
     // Closed,
 }
@@ -166,6 +204,11 @@ impl IceConnectionState {
     pub fn is_disconnected(&self) -> bool {
         *self == IceConnectionState::Disconnected
     }
+
+    /// Tells if this state is the (terminal) failed state.
+    pub fn is_failed(&self) -> bool {
+        *self == IceConnectionState::Failed
+    }
 }
 
 /// Credentials for STUN packages.
@@ -269,6 +312,7 @@ impl IceAgent {
             local_candidates: vec![],
             remote_candidates: vec![],
             candidate_pairs: vec![],
+            pair_by_addrs: HashMap::new(),
             transmit: VecDeque::new(),
             events: VecDeque::new(),
             stun_server_queue: VecDeque::new(),
@@ -277,6 +321,13 @@ impl IceAgent {
             stats: IceAgentStats::default(),
             timing_advance: Duration::from_millis(50),
             timing_config: StunTiming::default(),
+            local_end_of_candidates: false,
+            remote_end_of_candidates: false,
+            renomination: false,
+            remote_renomination: false,
+            nomination_counter: 0,
+            pair_selection: Arc::new(PrioPairSelection),
+            forced_pair: None,
         }
     }
 
@@ -302,6 +353,177 @@ impl IceAgent {
         self.ice_lite = enabled;
     }
 
+    /// Whether renomination is enabled.
+    ///
+    /// Default is disabled.
+    pub fn renomination(&self) -> bool {
+        self.renomination
+    }
+
+    /// Enable or disable renomination (`a=ice-options:renomination`).
+    ///
+    /// With renomination, the controlling agent can switch the selected candidate pair
+    /// at any time, and the controlled agent follows. It is only used if the remote
+    /// peer also supports it (see [`IceAgent::set_remote_renomination`]).
+    ///
+    /// Default is disabled.
+    pub fn set_renomination(&mut self, enabled: bool) {
+        self.renomination = enabled;
+    }
+
+    /// Set whether the remote peer supports renomination.
+    ///
+    /// This is typically learned from the remote `a=ice-options`.
+    pub fn set_remote_renomination(&mut self, enabled: bool) {
+        self.remote_renomination = enabled;
+    }
+
+    /// Whether renomination is used, i.e. supported by both sides.
+    pub fn uses_renomination(&self) -> bool {
+        self.renomination && self.remote_renomination
+    }
+
+    /// Set the policy for selecting which candidate pair to use when controlling.
+    ///
+    /// Defaults to [`PrioPairSelection`].
+    pub fn set_pair_selection(&mut self, pair_selection: Arc<dyn IcePairSelection>) {
+        self.pair_selection = pair_selection;
+    }
+
+    /// Force the use of a specific candidate pair, or `None` to go back to the pair
+    /// selection policy.
+    ///
+    /// The pair is identified by the local socket address datagrams are sent from and
+    /// the remote address they are sent to. The pair is used as soon as it has
+    /// succeeded a connectivity check, and for as long as it is working.
+    ///
+    /// Only possible when controlling. Returns `false` if we are not controlling or
+    /// there is no such candidate pair.
+    pub fn set_forced_pair(&mut self, pair: Option<(SocketAddr, SocketAddr)>) -> bool {
+        let Some((source, destination)) = pair else {
+            self.forced_pair = None;
+            self.evaluate_nomination();
+            return true;
+        };
+
+        if !self.controlling {
+            debug!("Can't force candidate pair when controlled");
+            return false;
+        }
+
+        let found = self.candidate_pairs.iter().find(|p| {
+            p.local_candidate(&self.local_candidates).base() == source
+                && p.remote_candidate(&self.remote_candidates).addr() == destination
+        });
+
+        let Some(found) = found else {
+            debug!("No candidate pair to force: {} -> {}", source, destination);
+            return false;
+        };
+
+        info!("Force candidate pair: {} -> {}", source, destination);
+        self.forced_pair = Some(found.id());
+        self.evaluate_nomination();
+
+        true
+    }
+
+    /// Record a (non-STUN) datagram sent from `source` to `destination`.
+    ///
+    /// This is used for the per candidate pair statistics.
+    pub fn record_transmit(
+        &mut self,
+        now: Instant,
+        source: SocketAddr,
+        destination: SocketAddr,
+        len: usize,
+    ) {
+        if let Some(idx) = self.pair_idx_by_addrs(source, destination) {
+            self.candidate_pairs[idx].record_packet_sent(now, len);
+        }
+    }
+
+    /// Record a (non-STUN) datagram received from `source` on `destination`.
+    ///
+    /// This is used for the per candidate pair statistics.
+    pub fn record_receive(
+        &mut self,
+        now: Instant,
+        source: SocketAddr,
+        destination: SocketAddr,
+        len: usize,
+    ) {
+        if let Some(idx) = self.pair_idx_by_addrs(destination, source) {
+            self.candidate_pairs[idx].record_packet_received(now, len);
+        }
+    }
+
+    fn pair_idx_by_addrs(&self, local: SocketAddr, remote: SocketAddr) -> Option<usize> {
+        self.pair_by_addrs.get(&(local, remote)).copied()
+    }
+
+    fn reindex_pairs(&mut self) {
+        // There can only be one candidate pair per local base / remote addr.
+        self.pair_by_addrs.clear();
+        for (idx, p) in self.candidate_pairs.iter().enumerate() {
+            let local = p.local_candidate(&self.local_candidates).base();
+            let remote = p.remote_candidate(&self.remote_candidates).addr();
+            self.pair_by_addrs.entry((local, remote)).or_insert(idx);
+        }
+    }
+
+    /// Statistics for all current candidate pairs.
+    pub fn candidate_pair_stats(&self, now: Instant) -> Vec<CandidatePairStats> {
+        self.candidate_pairs
+            .iter()
+            .map(|p| {
+                let local = p.local_candidate(&self.local_candidates);
+                let remote = p.remote_candidate(&self.remote_candidates);
+                let c = p.counters();
+
+                CandidatePairStats {
+                    proto: local.proto(),
+                    local_addr: local.base(),
+                    local_kind: local.kind(),
+                    remote_addr: remote.addr(),
+                    remote_kind: remote.kind(),
+                    state: match p.state() {
+                        CheckState::Waiting => CandidatePairState::Waiting,
+                        CheckState::InProgress => CandidatePairState::InProgress,
+                        CheckState::Succeeded => CandidatePairState::Succeeded,
+                    },
+                    nominated: p.is_nominated(),
+                    selected: self.nominated_send == Some(p.id()),
+                    bytes_sent: c.bytes_sent,
+                    bytes_received: c.bytes_received,
+                    packets_sent: c.packets_sent,
+                    packets_received: c.packets_received,
+                    requests_sent: c.requests_sent,
+                    requests_received: c.requests_received,
+                    responses_sent: c.responses_sent,
+                    responses_received: c.responses_received,
+                    current_rtt: p.rtt(),
+                    total_rtt: c.total_rtt,
+                    last_packet_sent: c.last_packet_sent,
+                    last_packet_received: c.last_packet_received,
+                    last_request_received: c.last_request_received,
+                    last_response_received: c.last_response_received,
+                    timestamp: now,
+                }
+            })
+            .collect()
+    }
+
+    /// Round trip time of the currently selected candidate pair, from the last
+    /// answered STUN binding request.
+    pub fn selected_pair_rtt(&self) -> Option<Duration> {
+        let id = self.nominated_send?;
+        self.candidate_pairs
+            .iter()
+            .find(|p| p.id() == id)
+            .and_then(|p| p.rtt())
+    }
+
     /// Set a new timing advance (Ta) value.
     ///
     /// Ta specifies the minimum increment of time that has to pass between calls to
@@ -370,6 +592,43 @@ impl IceAgent {
         &self.remote_candidates
     }
 
+    /// Signal that no more local candidates will be added.
+    ///
+    /// This corresponds to sending `a=end-of-candidates` to the remote peer. Together
+    /// with [`IceAgent::end_of_remote_candidates`], it allows the agent to move to
+    /// [`IceConnectionState::Failed`] as soon as all candidate pairs have failed.
+    ///
+    /// Cleared on ICE restart.
+    pub fn end_of_local_candidates(&mut self) {
+        if !self.local_end_of_candidates {
+            info!("End of local candidates");
+            self.local_end_of_candidates = true;
+        }
+    }
+
+    /// Signal that the remote peer will not send more candidates.
+    ///
+    /// This corresponds to receiving `a=end-of-candidates` from the remote peer. Any
+    /// remote candidate added after this is ignored.
+    ///
+    /// Cleared on ICE restart.
+    pub fn end_of_remote_candidates(&mut self) {
+        if !self.remote_end_of_candidates {
+            info!("End of remote candidates");
+            self.remote_end_of_candidates = true;
+        }
+    }
+
+    /// Whether [`IceAgent::end_of_local_candidates`] has been signalled.
+    pub fn is_end_of_local_candidates(&self) -> bool {
+        self.local_end_of_candidates
+    }
+
+    /// Whether [`IceAgent::end_of_remote_candidates`] has been signalled.
+    pub fn is_end_of_remote_candidates(&self) -> bool {
+        self.remote_end_of_candidates
+    }
+
     /// Determines whether any remote candidates match the specified address and
     /// have been verified with a STUN request/response.
     pub fn has_viable_remote_candidate(&self, addr: SocketAddr) -> bool {
@@ -474,6 +733,11 @@ impl IceAgent {
     pub fn add_local_candidate(&mut self, mut c: Candidate) -> bool {
         let ip = c.addr().ip();
 
+        if self.local_end_of_candidates {
+            debug!("Reject local candidate after end-of-candidates: {:?}", c);
+            return false;
+        }
+
         if self.ice_lite {
             // Reject all non-host candidates.
             if c.kind() != CandidateKind::Host {
@@ -627,6 +891,11 @@ impl IceAgent {
             return;
         }
 
+        if self.remote_end_of_candidates {
+            debug!("Reject remote candidate after end-of-candidates: {:?}", c);
+            return;
+        }
+
         if let Some(creds) = &self.remote_credentials {
             if let Some(ufrag) = c.ufrag() {
                 if ufrag != creds.ufrag {
@@ -805,6 +1074,8 @@ impl IceAgent {
             let pair = self.candidate_pairs.pop();
             debug!("Remove overflow pair {:?}", pair);
         }
+
+        self.reindex_pairs();
     }
 
     /// Invalidate a candidate and remove it from the connection.
@@ -873,10 +1144,15 @@ impl IceAgent {
         self.remote_credentials = None;
         self.remote_candidates.clear();
         self.candidate_pairs.clear();
+        self.pair_by_addrs.clear();
         self.has_exceeded_max_candidate_pairs = false;
         self.transmit.clear();
         self.events.clear();
         self.discovered_recv.clear();
+        self.remote_end_of_candidates = false;
+        self.remote_renomination = false;
+        self.nomination_counter = 0;
+        self.forced_pair = None;
 
         if keep_local_candidates {
             // If we're keeping the candidates, we must update the ufrag to the new credentials.
@@ -884,8 +1160,10 @@ impl IceAgent {
             for c in &mut self.local_candidates {
                 c.set_ufrag(&local_credentials.ufrag)
             }
+            // The same candidates are kept, so a complete set stays complete.
         } else {
             self.local_candidates.clear();
+            self.local_end_of_candidates = false;
         }
 
         self.local_credentials = local_credentials;
@@ -898,12 +1176,14 @@ impl IceAgent {
     fn discard_candidate_pairs_by_local(&mut self, local_idx: usize) {
         trace!("Discard pairs for local candidate index: {:?}", local_idx);
         self.candidate_pairs.retain(|c| c.local_idx() != local_idx);
+        self.reindex_pairs();
     }
 
     /// Discard candidate pairs that contain the candidate identified by a remote index.
     fn discard_candidate_pairs_by_remote(&mut self, remote: usize) {
         trace!("Discard pairs for remote candidate index: {:?}", remote);
         self.candidate_pairs.retain(|c| c.remote_idx() != remote);
+        self.reindex_pairs();
     }
 
     /// Tells whether the message is for this agent instance.
@@ -979,9 +1259,9 @@ impl IceAgent {
                 trace!("Message rejected, unknown STUN class");
                 false
             }
-            (StunMethod::Unknown, _) => {
-                // Without a known method, it's impossible to know how to validate the message
-                trace!("Message rejected, unknown STUN method");
+            (_, _) => {
+                // ICE only uses Binding, other methods can't be validated.
+                trace!("Message rejected, unexpected STUN method: {:?}", method);
                 false
             }
         }
@@ -1069,6 +1349,7 @@ impl IceAgent {
             keep
         });
         if any_pruned {
+            self.reindex_pairs();
             self.evaluate_nomination();
             self.evaluate_state(now);
         }
@@ -1219,6 +1500,8 @@ impl IceAgent {
             trans_id,
             prio,
             use_candidate,
+            nomination: message.nomination(),
+            network_cost: message.network_cost(),
             remote_ufrag: remote_ufrag.into(),
         };
 
@@ -1333,6 +1616,11 @@ impl IceAgent {
             self.remote_candidates.len() - 1
         };
 
+        if let Some(cost) = req.network_cost {
+            // The remote peer tells us about the cost of its network.
+            self.remote_candidates[remote_idx].set_network_cost(cost);
+        }
+
         let local_idx = match self.local_candidates.iter().enumerate().find(|(_, v)| {
             // The local candidate will be
             // either a host candidate (for cases where the request was not received
@@ -1392,6 +1680,7 @@ impl IceAgent {
 
             self.candidate_pairs.push(pair);
             self.candidate_pairs.sort();
+            self.reindex_pairs();
         }
 
         let pair = self
@@ -1408,6 +1697,7 @@ impl IceAgent {
         let remote_addr = remote.addr();
 
         pair.increase_remote_binding_requests(req.now);
+        pair.record_remote_binding_request(req.now);
 
         if !self.controlling && !pair.is_nominated() && req.use_candidate {
             // We need to answer a nomination request with a binding request
@@ -1417,7 +1707,16 @@ impl IceAgent {
             pair.nominate(self.ice_lite);
         }
 
-        if self.controlling && pair.state() == CheckState::Succeeded {
+        // With renomination, the controlling agent tells us which pair to use by
+        // sending a higher NOMINATION value than before.
+        let renominated = match req.nomination {
+            Some(v) if !self.controlling && req.use_candidate && self.renomination => {
+                pair.record_nomination_value(v)
+            }
+            _ => false,
+        };
+
+        if (self.controlling && pair.state() == CheckState::Succeeded) || renominated {
             // See if we can nominate something now.
             self.evaluate_nomination();
         }
@@ -1448,10 +1747,19 @@ impl IceAgent {
         };
 
         self.transmit.push_back(trans);
+
+        if let Some(pair) = self
+            .candidate_pairs
+            .iter_mut()
+            .find(|p| p.local_idx() == local_idx && p.remote_idx() == remote_idx)
+        {
+            pair.record_response_sent();
+        }
     }
 
     fn stun_client_binding_request(&mut self, now: Instant, pair_idx: usize) {
         let (username, password) = self.stun_credentials(false);
+        let uses_renomination = self.uses_renomination();
 
         let pair = &mut self.candidate_pairs[pair_idx];
         let local = pair.local_candidate(&self.local_candidates);
@@ -1459,6 +1767,12 @@ impl IceAgent {
         let prio = local.prio_prflx();
         // Only the controlling side sends USE-CANDIDATE.
         let use_candidate = self.controlling && pair.is_nominated();
+        let nomination = if use_candidate && uses_renomination {
+            pair.nomination_value()
+        } else {
+            None
+        };
+        let network_cost = local.signalled_network_cost();
 
         let trans_id = pair.new_attempt(now, &self.timing_config);
 
@@ -1471,6 +1785,8 @@ impl IceAgent {
             self.control_tie_breaker,
             prio,
             use_candidate,
+            nomination,
+            network_cost,
         );
 
         trace!(
@@ -1563,7 +1879,7 @@ impl IceAgent {
             let base = local_sent_from.base();
 
             // o  The type is peer reflexive.
-            let candidate = Candidate::peer_reflexive(
+            let mut candidate = Candidate::peer_reflexive(
                 local_sent_from.proto(),
                 mapped_address,
                 base,
@@ -1572,6 +1888,9 @@ impl IceAgent {
                 self.local_credentials.ufrag.clone(),
             );
 
+            // Same network interface as the candidate we sent from.
+            candidate.copy_network_info(local_sent_from);
+
             debug!(
                 "Created local peer reflexive candidate for mapped address: {}",
                 mapped_address
@@ -1605,58 +1924,115 @@ impl IceAgent {
     }
 
     fn evaluate_nomination(&mut self) {
-        let nominated_pair_priority = self.nominated_pair_priority();
-
-        let best_prio = if self.controlling {
+        let best_idx = if self.controlling {
             // For controlling agents, we pick the best candidate pair using
-            // this strategy.
-            self.candidate_pairs
-                .iter_mut()
-                .filter(|p| p.state() == CheckState::Succeeded)
-                .max_by_key(|p| p.prio())
+            // the pair selection policy (unless the user forced a pair).
+            self.best_controlling_pair()
         } else {
             // For controlled agents, we pick the best pair from what the controlling
-            // agent has indicated with USE-CANDIDATE stun attribute.
+            // agent has indicated with USE-CANDIDATE stun attribute. With renomination
+            // the highest NOMINATION value wins.
             self.candidate_pairs
-                .iter_mut()
-                .filter(|p| p.is_nominated())
-                .max_by_key(|p| p.prio())
+                .iter()
+                .enumerate()
+                .filter(|(_, p)| p.is_nominated())
+                .max_by_key(|(_, p)| (p.nomination_value(), p.prio()))
+                .map(|(i, _)| i)
         };
 
-        if let Some(best_prio) = best_prio {
-            if let Some(nominated) = nominated_pair_priority {
-                if nominated == best_prio.prio() {
-                    // The best prio is also the current nominated prio. Make
-                    // no changes since there can be multiple pairs having the
-                    // same best_prio.
-                    return;
-                }
-            }
-            trace!("Nominating best candidate");
+        let Some(best_idx) = best_idx else {
+            return;
+        };
+
+        let current = self
+            .nominated_send
+            .and_then(|id| self.candidate_pairs.iter().find(|p| p.id() == id));
 
-            if !best_prio.is_nominated() && (self.controlling || self.ice_lite) {
-                // ice lite progresses pair to success straight away.
-                best_prio.nominate(self.ice_lite);
+        if let Some(current) = current {
+            let best = &self.candidate_pairs[best_idx];
+
+            let keep_current = if current.id() == best.id() {
+                true
+            } else if self.controlling {
+                // Only switch when the new pair is strictly better, since there can be
+                // multiple pairs that are equally good.
+                self.forced_pair != Some(best.id())
+                    && self.compare_pairs(best, current) != Ordering::Greater
+            } else {
+                (best.nomination_value(), best.prio())
+                    == (current.nomination_value(), current.prio())
+            };
+
+            if keep_current {
+                return;
             }
+        }
 
-            let local = best_prio.local_candidate(&self.local_candidates);
-            let remote = best_prio.remote_candidate(&self.remote_candidates);
+        trace!("Nominating best candidate");
 
-            self.nominated_send = Some(best_prio.id());
-            self.emit_event(IceAgentEvent::NominatedSend {
-                proto: local.proto(),
-                source: local.base(),
-                destination: remote.addr(),
-            })
+        let renominate = self.controlling && self.uses_renomination();
+        if renominate {
+            self.nomination_counter += 1;
         }
+
+        let best = &mut self.candidate_pairs[best_idx];
+
+        if renominate {
+            best.renominate(self.nomination_counter);
+        } else if !best.is_nominated() && (self.controlling || self.ice_lite) {
+            // ice lite progresses pair to success straight away.
+            best.nominate(self.ice_lite);
+        }
+
+        let local = best.local_candidate(&self.local_candidates);
+        let remote = best.remote_candidate(&self.remote_candidates);
+
+        self.nominated_send = Some(best.id());
+        self.emit_event(IceAgentEvent::NominatedSend {
+            proto: local.proto(),
+            source: local.base(),
+            destination: remote.addr(),
+        })
     }
 
-    fn nominated_pair_priority(&self) -> Option<u64> {
-        let id = self.nominated_send?;
+    fn best_controlling_pair(&self) -> Option<usize> {
+        if let Some(id) = self.forced_pair {
+            let forced = self
+                .candidate_pairs
+                .iter()
+                .position(|p| p.id() == id && p.state() == CheckState::Succeeded);
+
+            if forced.is_some() {
+                return forced;
+            }
+        }
 
         self.candidate_pairs
             .iter()
-            .find_map(|p| (p.id() == id).then_some(p.prio()))
+            .enumerate()
+            .filter(|(_, p)| p.state() == CheckState::Succeeded)
+            .max_by(|(_, a), (_, b)| self.compare_pairs(a, b))
+            .map(|(i, _)| i)
+    }
+
+    fn compare_pairs(&self, a: &CandidatePair, b: &CandidatePair) -> Ordering {
+        let a = self.pair_info(a);
+        let b = self.pair_info(b);
+        self.pair_selection.compare(&a, &b)
+    }
+
+    fn pair_info<'a>(&'a self, pair: &CandidatePair) -> IcePairInfo<'a> {
+        let local = pair.local_candidate(&self.local_candidates);
+        let remote = pair.remote_candidate(&self.remote_candidates);
+
+        IcePairInfo {
+            local,
+            remote,
+            source: local.base(),
+            destination: remote.addr(),
+            prio: pair.prio(),
+            rtt: pair.rtt(),
+        }
     }
 
     fn set_connection_state(&mut self, state: IceConnectionState, reason: &'static str) {
@@ -1684,7 +2060,11 @@ impl IceAgent {
         // As a special case, before the ice agent has received any add_remote_candidate() or
         // discovered a peer reflexive via a STUN message, the agent is still viable. This is
         // also the case for ice_restart.
-        if self.remote_candidates.is_empty() {
+        //
+        // Once both sides have signalled end-of-candidates, no more candidates are coming
+        // and an empty set of remote candidates is a failure.
+        let end_of_candidates = self.local_end_of_candidates && self.remote_end_of_candidates;
+        if self.remote_candidates.is_empty() && !end_of_candidates {
             any_still_possible = true;
         }
 
@@ -1704,7 +2084,14 @@ impl IceAgent {
                         self.set_connection_state(Completed, "got nomination, no others to try");
                     }
                 } else if !any_still_possible {
-                    self.set_connection_state(Disconnected, "no possible pairs");
+                    if end_of_candidates {
+                        self.set_connection_state(
+                            Failed,
+                            "no possible pairs after end-of-candidates",
+                        );
+                    } else {
+                        self.set_connection_state(Disconnected, "no possible pairs");
+                    }
                 }
             }
             Connected => {
@@ -1729,6 +2116,9 @@ impl IceAgent {
                     self.set_connection_state(Disconnected, "none nominated");
                 }
             }
+            Failed => {
+                // Terminal until the next ICE restart.
+            }
         }
     }
 
@@ -2143,8 +2533,16 @@ mod test {
         prio: u32,
     ) -> Vec<u8> {
         let username = format!("{}:{}", local_creds.ufrag, remote_creds.ufrag);
-        let binding_req =
-            StunMessage::binding_request(&username, TransId::new(), controlling, 0, prio, false);
+        let binding_req = StunMessage::binding_request(
+            &username,
+            TransId::new(),
+            controlling,
+            0,
+            prio,
+            false,
+            None,
+            None,
+        );
         serialize_stun_msg(binding_req, &local_creds.pass)
     }
 
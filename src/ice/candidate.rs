@@ -79,6 +79,13 @@ pub struct Candidate {
     /// If we discarded this candidate (for example due to being redundant
     /// against another candidate).
     discarded: bool,
+
+    /// User supplied metadata about the network interface of a local candidate.
+    network_type: NetworkType,
+
+    /// Explicit network cost. For local candidates this is set by the user, for
+    /// remote candidates it is learned from the GOOG-NETWORK-INFO STUN attribute.
+    network_cost: Option<u16>,
 }
 
 impl fmt::Debug for Candidate {
@@ -125,6 +132,8 @@ impl Candidate {
             ufrag,
             local_preference: None,
             discarded: false,
+            network_type: NetworkType::Unknown,
+            network_cost: None,
         }
     }
 
@@ -409,6 +418,49 @@ impl Candidate {
         self.discarded
     }
 
+    /// Set the type of network this (local) candidate is on.
+    ///
+    /// This is used by the [`IcePairSelection`][crate::ice::IcePairSelection] to
+    /// prefer some networks over others. Defaults to [`NetworkType::Unknown`].
+    pub fn set_network_type(&mut self, network_type: NetworkType) {
+        self.network_type = network_type;
+    }
+
+    /// The type of network this candidate is on.
+    pub fn network_type(&self) -> NetworkType {
+        self.network_type
+    }
+
+    /// Set an explicit network cost for this (local) candidate.
+    ///
+    /// Overrides the default cost derived from [`Candidate::network_type()`]. The cost
+    /// is also signalled to the remote peer in STUN binding requests.
+    pub fn set_network_cost(&mut self, cost: u16) {
+        self.network_cost = Some(cost);
+    }
+
+    /// The network cost of this candidate. Lower is better.
+    ///
+    /// For remote candidates, this is the cost the remote peer signalled, if any.
+    pub fn network_cost(&self) -> u16 {
+        self.network_cost
+            .unwrap_or_else(|| self.network_type.default_cost())
+    }
+
+    /// The network cost to signal in STUN, if the user provided any network metadata.
+    pub(crate) fn signalled_network_cost(&self) -> Option<u16> {
+        if self.network_cost.is_some() || self.network_type != NetworkType::Unknown {
+            Some(self.network_cost())
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn copy_network_info(&mut self, other: &Candidate) {
+        self.network_type = other.network_type;
+        self.network_cost = other.network_cost;
+    }
+
     pub(crate) fn set_ufrag(&mut self, ufrag: &str) {
         self.ufrag = Some(ufrag.into());
     }
@@ -480,6 +532,41 @@ impl fmt::Display for CandidateKind {
     }
 }
 
+/// Type of network a candidate is on.
+///
+/// This is user supplied metadata for local candidates, used when selecting
+/// which candidate pair to use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum NetworkType {
+    /// The network type is not known.
+    #[default]
+    Unknown,
+    /// Wired ethernet.
+    Ethernet,
+    /// Wifi.
+    Wifi,
+    /// Mobile data, such as 4G/5G.
+    Cellular,
+    /// A VPN interface.
+    Vpn,
+    /// Loopback interface.
+    Loopback,
+}
+
+impl NetworkType {
+    /// The default network cost for this type of network.
+    ///
+    /// The values follow libwebrtc where cellular is heavily penalized.
+    pub fn default_cost(&self) -> u16 {
+        match self {
+            NetworkType::Ethernet | NetworkType::Loopback => 0,
+            NetworkType::Wifi | NetworkType::Vpn => 10,
+            NetworkType::Unknown => 50,
+            NetworkType::Cellular => 900,
+        }
+    }
+}
+
 fn is_valid_ip(ip: IpAddr) -> bool {
     match ip {
         IpAddr::V4(v) => {
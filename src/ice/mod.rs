@@ -7,10 +7,13 @@ mod agent;
 pub use agent::{IceAgent, IceAgentEvent, IceConnectionState, IceCreds};
 
 mod candidate;
-pub use candidate::{Candidate, CandidateKind};
+pub use candidate::{Candidate, CandidateKind, NetworkType};
 
 mod pair;
 
+mod selection;
+pub use selection::{IcePairInfo, IcePairSelection, NetworkCostPairSelection, PrioPairSelection};
+
 /// Errors from the ICE agent.
 #[allow(missing_docs)]
 #[derive(Debug, Error)]
@@ -68,6 +71,175 @@ mod test {
         );
     }
 
+    #[test]
+    pub fn drop_host_end_of_candidates() {
+        let mut a1 = TestAgent::new(info_span!("L"));
+        let mut a2 = TestAgent::new(info_span!("R"));
+
+        let c1 = host("1.1.1.1:9999", "udp"); // 9999 is just dropped by propagate
+        a1.add_local_candidate(c1.clone());
+        a2.add_remote_candidate(c1);
+        let c2 = host("2.2.2.2:1000", "udp");
+        a2.add_local_candidate(c2.clone());
+        a1.add_remote_candidate(c2);
+        a1.set_controlling(true);
+        a2.set_controlling(false);
+
+        for a in [&mut a1, &mut a2] {
+            a.end_of_local_candidates();
+            a.end_of_remote_candidates();
+        }
+
+        loop {
+            if a1.state().is_failed() && a2.state().is_failed() {
+                break;
+            }
+            assert!(!a1.state().is_disconnected());
+            assert!(!a2.state().is_disconnected());
+            progress(&mut a1, &mut a2);
+        }
+
+        // Candidates after end-of-candidates are ignored.
+        a1.add_remote_candidate(host("2.2.2.2:1001", "udp"));
+        assert!(!a1.add_local_candidate(host("1.1.1.1:1001", "udp")));
+        assert_eq!(a1.remote_candidates().len(), 1);
+        assert_eq!(a1.local_candidates().len(), 1);
+    }
+
+    #[test]
+    pub fn no_remote_candidates_end_of_candidates() {
+        let mut a1 = TestAgent::new(info_span!("L"));
+        let mut a2 = TestAgent::new(info_span!("R"));
+
+        a1.add_local_candidate(host("1.1.1.1:1000", "udp"));
+        a1.set_controlling(true);
+
+        // Without end-of-candidates, an agent without remote candidates is still viable.
+        for _ in 0..4 {
+            progress(&mut a1, &mut a2);
+        }
+        assert_eq!(a1.state(), IceConnectionState::Checking);
+
+        a1.end_of_local_candidates();
+        a1.end_of_remote_candidates();
+
+        for _ in 0..4 {
+            progress(&mut a1, &mut a2);
+        }
+        assert_eq!(a1.state(), IceConnectionState::Failed);
+
+        // ICE restart gets us out of the failed state. The kept local candidates
+        // are still complete.
+        a1.ice_restart(IceCreds::new(), true);
+        assert_eq!(a1.state(), IceConnectionState::Checking);
+        assert!(a1.is_end_of_local_candidates());
+        assert!(!a1.is_end_of_remote_candidates());
+
+        // New local candidates start a new round of gathering.
+        a1.ice_restart(IceCreds::new(), false);
+        assert!(!a1.is_end_of_local_candidates());
+    }
+
+    #[test]
+    pub fn renomination_follows_forced_pair() {
+        let mut a1 = TestAgent::new(info_span!("L"));
+        let mut a2 = TestAgent::new(info_span!("R"));
+
+        let c1 = host("1.1.1.1:1000", "udp");
+        a1.add_local_candidate(c1.clone());
+        a2.add_remote_candidate(c1);
+        let c3 = host("5.5.5.5:1000", "udp");
+        a1.add_local_candidate(c3.clone());
+        a2.add_remote_candidate(c3);
+        let c2 = host("2.2.2.2:1000", "udp");
+        a2.add_local_candidate(c2.clone());
+        a1.add_remote_candidate(c2);
+        a1.set_controlling(true);
+        a2.set_controlling(false);
+
+        for a in [&mut a1, &mut a2] {
+            a.set_renomination(true);
+            a.set_remote_renomination(true);
+        }
+
+        loop {
+            if a1.state().is_connected() && a2.state().is_connected() {
+                break;
+            }
+            progress(&mut a1, &mut a2);
+        }
+
+        let (source, destination) = last_nominated(&a1).unwrap();
+        assert_eq!(destination, sock("2.2.2.2:1000"));
+
+        // Force the other pair.
+        let other = if source == sock("1.1.1.1:1000") {
+            sock("5.5.5.5:1000")
+        } else {
+            sock("1.1.1.1:1000")
+        };
+        assert!(a1.set_forced_pair(Some((other, destination))));
+        assert!(!a2.set_forced_pair(Some((destination, other))));
+
+        loop {
+            let a1_switched = last_nominated(&a1) == Some((other, destination));
+            let a2_switched = last_nominated(&a2) == Some((destination, other));
+            if a1_switched && a2_switched {
+                break;
+            }
+            progress(&mut a1, &mut a2);
+        }
+    }
+
+    #[test]
+    pub fn pair_selection_network_cost() {
+        let mut a1 = TestAgent::new(info_span!("L"));
+        let mut a2 = TestAgent::new(info_span!("R"));
+
+        a1.set_pair_selection(std::sync::Arc::new(NetworkCostPairSelection::default()));
+
+        let mut c1 = host("1.1.1.1:1000", "udp");
+        c1.set_network_type(NetworkType::Cellular);
+        a1.add_local_candidate(c1.clone());
+        a2.add_remote_candidate(c1);
+        let mut c3 = host("5.5.5.5:1000", "udp");
+        c3.set_network_type(NetworkType::Ethernet);
+        a1.add_local_candidate(c3.clone());
+        a2.add_remote_candidate(c3);
+        let c2 = host("2.2.2.2:1000", "udp");
+        a2.add_local_candidate(c2.clone());
+        a1.add_remote_candidate(c2);
+        a1.set_controlling(true);
+        a2.set_controlling(false);
+
+        loop {
+            let selected = last_nominated(&a1).map(|(s, _)| s);
+            if a1.state().is_connected() && selected == Some(sock("5.5.5.5:1000")) {
+                break;
+            }
+            progress(&mut a1, &mut a2);
+        }
+
+        // The remote learns the network cost from the STUN binding requests.
+        let remote = a2
+            .remote_candidates()
+            .iter()
+            .find(|c| c.addr() == sock("1.1.1.1:1000"))
+            .unwrap();
+        assert_eq!(remote.network_cost(), 900);
+    }
+
+    fn last_nominated(a: &TestAgent) -> Option<(SocketAddr, SocketAddr)> {
+        a.events.iter().rev().find_map(|(_, e)| match e {
+            IceAgentEvent::NominatedSend {
+                source,
+                destination,
+                ..
+            } => Some((*source, *destination)),
+            _ => None,
+        })
+    }
+
     use std::net::IpAddr;
     use std::net::{Ipv4Addr, SocketAddr};
     use std::ops::{Deref, DerefMut};
@@ -47,6 +47,34 @@ pub struct CandidatePair {
 
     /// State of nomination for this candidate pair.
     nomination_state: NominationState,
+
+    /// Renomination value. For the controlling agent, this is the value sent
+    /// in the NOMINATION attribute. For the controlled, the highest value received.
+    nomination_value: Option<u32>,
+
+    /// Round trip time of the last answered binding request.
+    rtt: Option<Duration>,
+
+    /// Counters for the candidate pair stats.
+    counters: PairCounters,
+}
+
+/// Traffic counters for a candidate pair.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PairCounters {
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub packets_sent: u64,
+    pub packets_received: u64,
+    pub requests_sent: u64,
+    pub requests_received: u64,
+    pub responses_sent: u64,
+    pub responses_received: u64,
+    pub total_rtt: Duration,
+    pub last_packet_sent: Option<Instant>,
+    pub last_packet_received: Option<Instant>,
+    pub last_request_received: Option<Instant>,
+    pub last_response_received: Option<Instant>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -126,6 +154,9 @@ impl CandidatePair {
             remote_binding_requests: Default::default(),
             remote_binding_request_time: Default::default(),
             nomination_state: Default::default(),
+            nomination_value: Default::default(),
+            rtt: Default::default(),
+            counters: Default::default(),
         }
     }
 
@@ -206,10 +237,75 @@ impl CandidatePair {
         }
     }
 
+    /// Nominate the pair again, regardless of previous nomination state.
+    ///
+    /// Used for renomination where the controlling agent can switch to a pair
+    /// that was previously nominated.
+    pub fn renominate(&mut self, nomination_value: u32) {
+        self.nomination_value = Some(nomination_value);
+        self.nomination_state = NominationState::Nominated;
+        debug!("Renominated pair: {:?}", self);
+    }
+
+    pub fn nomination_value(&self) -> Option<u32> {
+        self.nomination_value
+    }
+
+    /// Record a NOMINATION value received from the controlling agent.
+    ///
+    /// Returns `true` if the value is higher than what we had before.
+    pub fn record_nomination_value(&mut self, nomination_value: u32) -> bool {
+        if self
+            .nomination_value
+            .map(|v| nomination_value > v)
+            .unwrap_or(true)
+        {
+            self.nomination_value = Some(nomination_value);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Round trip time of the last answered binding request.
+    pub fn rtt(&self) -> Option<Duration> {
+        self.rtt
+    }
+
+    pub fn counters(&self) -> &PairCounters {
+        &self.counters
+    }
+
+    /// Records a STUN binding request from the remote.
+    pub fn record_remote_binding_request(&mut self, now: Instant) {
+        self.counters.requests_received += 1;
+        self.counters.last_request_received = Some(now);
+    }
+
+    /// Records a STUN binding response queued to the remote.
+    pub fn record_response_sent(&mut self) {
+        self.counters.responses_sent += 1;
+    }
+
+    /// Records a (non-STUN) datagram sent using this pair.
+    pub fn record_packet_sent(&mut self, now: Instant, len: usize) {
+        self.counters.bytes_sent += len as u64;
+        self.counters.packets_sent += 1;
+        self.counters.last_packet_sent = Some(now);
+    }
+
+    /// Records a (non-STUN) datagram received on this pair.
+    pub fn record_packet_received(&mut self, now: Instant, len: usize) {
+        self.counters.bytes_received += len as u64;
+        self.counters.packets_received += 1;
+        self.counters.last_packet_received = Some(now);
+    }
+
     pub fn copy_nominated_and_success_state(&mut self, other: &CandidatePair) {
         match other.nomination_state {
             NominationState::Nominated | NominationState::Success => {
                 self.nomination_state = other.nomination_state;
+                self.nomination_value = other.nomination_value;
             }
             NominationState::None => {} // None is the default, no need to copy
             NominationState::Attempt => {} // Attempt can't be copied because we don't have sent binding requests in the new pair.
@@ -236,6 +332,7 @@ impl CandidatePair {
         };
 
         self.binding_attempts.push_back(attempt);
+        self.counters.requests_sent += 1;
 
         // Never keep more than the maximum allowed retransmits.
         while self.binding_attempts.len() > timing_config.max_retransmits() {
@@ -279,6 +376,12 @@ impl CandidatePair {
 
         attempt.respone_recv = Some(now);
 
+        let rtt = now - attempt.request_sent;
+        self.rtt = Some(rtt);
+        self.counters.total_rtt += rtt;
+        self.counters.responses_received += 1;
+        self.counters.last_response_received = Some(now);
+
         if attempt.nominated && self.nomination_state == NominationState::Attempt {
             self.nomination_state = NominationState::Success;
             debug!("Nomination success: {:?}", self);
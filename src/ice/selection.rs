@@ -0,0 +1,107 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::net::SocketAddr;
+use std::panic::RefUnwindSafe;
+use std::time::Duration;
+
+use crate::Candidate;
+
+/// Information about a candidate pair given to an [`IcePairSelection`].
+#[derive(Debug)]
+pub struct IcePairInfo<'a> {
+    /// The local candidate of the pair.
+    pub local: &'a Candidate,
+    /// The remote candidate of the pair.
+    pub remote: &'a Candidate,
+    /// The local socket address datagrams are sent from.
+    pub source: SocketAddr,
+    /// The remote socket address datagrams are sent to.
+    pub destination: SocketAddr,
+    /// The candidate pair priority as calculated by RFC 8445.
+    pub prio: u64,
+    /// Round trip time from the last answered STUN binding request.
+    pub rtt: Option<Duration>,
+}
+
+/// Policy for which candidate pair the controlling ICE agent uses.
+///
+/// The policy is only asked about candidate pairs that have succeeded a connectivity
+/// check. To make the remote peer also switch to the selected pair, renomination must
+/// be negotiated (see [`RtcConfig::set_ice_renomination()`][crate::RtcConfig::set_ice_renomination]).
+/// Without renomination the controlled side picks the highest priority nominated pair.
+///
+/// ```
+/// # use std::cmp::Ordering;
+/// # use str0m::ice::NetworkType;
+/// # use str0m::ice::{IcePairInfo, IcePairSelection};
+/// // Never use cellular if there's any other choice.
+/// #[derive(Debug)]
+/// struct AvoidCellular;
+///
+/// impl IcePairSelection for AvoidCellular {
+///     fn compare(&self, a: &IcePairInfo, b: &IcePairInfo) -> Ordering {
+///         let cell = |p: &IcePairInfo| p.local.network_type() == NetworkType::Cellular;
+///         cell(b).cmp(&cell(a)).then(a.prio.cmp(&b.prio))
+///     }
+/// }
+/// ```
+pub trait IcePairSelection: fmt::Debug + Send + Sync + RefUnwindSafe + 'static {
+    /// Compare two candidate pairs. [`Ordering::Greater`] means `a` is better than `b`.
+    ///
+    /// The agent only switches away from the currently selected pair when another pair
+    /// compares strictly greater.
+    fn compare(&self, a: &IcePairInfo, b: &IcePairInfo) -> Ordering;
+}
+
+/// Select candidate pairs by priority (RFC 8445). This is the default.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PrioPairSelection;
+
+impl IcePairSelection for PrioPairSelection {
+    fn compare(&self, a: &IcePairInfo, b: &IcePairInfo) -> Ordering {
+        a.prio.cmp(&b.prio)
+    }
+}
+
+/// Select candidate pairs by network cost, then by round trip time.
+///
+/// The network cost of a pair is the highest of the local and remote candidate cost
+/// (see [`Candidate::network_cost()`]). Round trip times that differ less than
+/// `rtt_threshold` are considered equal, in which case priority decides.
+#[derive(Debug, Clone, Copy)]
+pub struct NetworkCostPairSelection {
+    /// Smallest difference in RTT considered significant.
+    pub rtt_threshold: Duration,
+}
+
+impl Default for NetworkCostPairSelection {
+    fn default() -> Self {
+        NetworkCostPairSelection {
+            rtt_threshold: Duration::from_millis(10),
+        }
+    }
+}
+
+impl IcePairSelection for NetworkCostPairSelection {
+    fn compare(&self, a: &IcePairInfo, b: &IcePairInfo) -> Ordering {
+        let cost = |p: &IcePairInfo| p.local.network_cost().max(p.remote.network_cost());
+
+        // Lower cost is better.
+        let by_cost = cost(b).cmp(&cost(a));
+
+        let by_rtt = match (a.rtt, b.rtt) {
+            (Some(ra), Some(rb)) => {
+                let diff = if ra > rb { ra - rb } else { rb - ra };
+                if diff < self.rtt_threshold {
+                    Ordering::Equal
+                } else {
+                    // Lower rtt is better.
+                    rb.cmp(&ra)
+                }
+            }
+            _ => Ordering::Equal,
+        };
+
+        by_cost.then(by_rtt).then(a.prio.cmp(&b.prio))
+    }
+}
@@ -10,7 +10,7 @@ use std::ops::Deref;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-mod stun;
+pub(crate) mod stun;
 pub use stun::StunMessage;
 pub(crate) use stun::{
     Class as StunClass, Method as StunMethod, StunError, StunTiming, TransId,
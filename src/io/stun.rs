@@ -1,13 +1,20 @@
 use std::fmt;
-use std::io::Write;
+use std::io;
 use std::net::IpAddr;
 use std::net::SocketAddr;
+use std::str;
 use std::time::Duration;
 
 use crc::{Crc, CRC_32_ISO_HDLC};
+use hmac::{Hmac, Mac};
+use md5::Md5;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
 use thiserror::Error;
 
+use crate::util::NonCryptographicRng;
+
 pub(crate) const DEFAULT_MAX_RETRANSMITS: usize = 9;
 
 #[derive(Debug)] // Purposely not `Clone` / `Copy` to ensure we always use the latest one everywhere.
@@ -79,10 +86,12 @@ pub enum StunError {
     Io(#[from] io::Error),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// STUN transaction id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct TransId([u8; 12]);
 
 impl TransId {
+    /// Creates a new random transaction id.
     pub fn new() -> Self {
         let mut t = [0_u8; 12];
         for v in &mut t {
@@ -91,7 +100,17 @@ impl TransId {
         TransId(t)
     }
 
-    fn from_slice(s: &[u8]) -> Self {
+    /// Creates a transaction id from bytes.
+    pub fn from_bytes(bytes: [u8; 12]) -> Self {
+        TransId(bytes)
+    }
+
+    /// The bytes of this transaction id.
+    pub fn as_bytes(&self) -> &[u8; 12] {
+        &self.0
+    }
+
+    pub(crate) fn from_slice(s: &[u8]) -> Self {
         let mut t = [0_u8; 12];
         t[..].copy_from_slice(s);
         TransId(t)
@@ -101,60 +120,51 @@ impl TransId {
 /// Represents a STUN message as fit for our purposes.
 ///
 /// STUN is a very flexible protocol.
-/// This implementations only provides what we need for our ICE implementation.
+/// This is the view of a [`Message`] our ICE implementation needs.
 #[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct StunMessage<'a> {
     method: Method,
     class: Class,
     trans_id: TransId,
     attrs: Attributes<'a>,
+    // The message up until, but not including, the MESSAGE-INTEGRITY attribute.
     integrity: &'a [u8],
-    integrity_len: u16,
 }
 
 impl<'a> StunMessage<'a> {
     /// Parse a STUN message from a slice of bytes.
     pub fn parse(buf: &[u8]) -> Result<StunMessage, StunError> {
-        if buf.len() < 4 {
-            return Err(StunError::Parse("Buffer too short".into()));
-        }
-
-        let typ = (buf[0] as u16 & 0b0011_1111) << 8 | buf[1] as u16;
-        let len = (buf[2] as u16) << 8 | buf[3] as u16;
-        if len & 0b0000_0011 > 0 {
-            return Err(StunError::Parse("len is not a multiple of 4".into()));
-        }
-        if len as usize != buf.len() - 20 {
-            return Err(StunError::Parse(
-                "STUN length vs UDP packet mismatch".into(),
-            ));
-        }
-        if &buf[4..8] != MAGIC {
-            return Err(StunError::Parse("magic cookie mismatch".into()));
-        }
-        // typ is method and class
-        // |M11|M10|M9|M8|M7|C1|M6|M5|M4|C0|M3|M2|M1|M0|
-        // |11 |10 |9 |8 |7 |1 |6 |5 |4 |0 |3 |2 |1 |0 |
-        let class = Class::from_typ(typ);
-        let method = Method::from_typ(typ);
-        let trans_id = TransId::from_slice(&buf[8..20]);
+        let message = Message::parse(buf)?;
 
-        let mut message_integrity_offset = 0;
-
-        let attrs = Attributes::parse(&buf[20..], trans_id, &mut message_integrity_offset)?;
-
-        // message-integrity only includes the length up until and including
-        // the message-integrity attribute.
-        if message_integrity_offset == 0 {
+        let Some(integrity_off) = message.integrity else {
             return Err(StunError::Parse("No message integrity in incoming".into()));
-        }
+        };
 
-        // length including message integrity attribute
-        let integrity_len = (message_integrity_offset + 4 + 20) as u16;
+        let mut attrs = Attributes::default();
+        for attr in message.attributes() {
+            match *attr {
+                Attribute::Username(v) => attrs.username = Some(v),
+                Attribute::MessageIntegrity(v) => attrs.message_integrity = Some(v),
+                Attribute::ErrorCode(code, reason) => attrs.error_code = Some((code, reason)),
+                Attribute::Realm(v) => attrs.realm = Some(v),
+                Attribute::Nonce(v) => attrs.nonce = Some(v),
+                Attribute::XorMappedAddress(v) => attrs.xor_mapped_address = Some(v),
+                Attribute::Software(v) => attrs.software = Some(v),
+                Attribute::Fingerprint(v) => attrs.fingerprint = Some(v),
+                Attribute::Priority(v) => attrs.priority = Some(v),
+                Attribute::UseCandidate => attrs.use_candidate = true,
+                Attribute::IceControlled(v) => attrs.ice_controlled = Some(v),
+                Attribute::IceControlling(v) => attrs.ice_controlling = Some(v),
+                Attribute::NetworkCost { network_id, cost } => {
+                    attrs.network_cost = Some((network_id, cost))
+                }
+                Attribute::Nomination(v) => attrs.nomination = Some(v),
+                _ => {}
+            }
+        }
 
-        // password as key is called "short-term credentials"
-        // buffer from beginning including header (+20) to where message-integrity starts.
-        let integrity = &buf[0..(message_integrity_offset + 20)];
+        let method = message.method();
+        let class = message.class();
 
         if method == Method::Binding && class == Class::Success {
             if attrs.xor_mapped_address.is_none() {
@@ -172,10 +182,9 @@ impl<'a> StunMessage<'a> {
         Ok(StunMessage {
             class,
             method,
-            trans_id,
+            trans_id: message.transaction_id(),
             attrs,
-            integrity,
-            integrity_len,
+            integrity: &buf[..integrity_off],
         })
     }
 
@@ -206,6 +215,7 @@ impl<'a> StunMessage<'a> {
     }
 
     /// Constructs a new BINDING request from the provided data.
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn binding_request(
         username: &'a str,
         trans_id: TransId,
@@ -213,6 +223,8 @@ impl<'a> StunMessage<'a> {
         control_tie_breaker: u64,
         prio: u32,
         use_candidate: bool,
+        nomination: Option<u32>,
+        network_cost: Option<u16>,
     ) -> Self {
         StunMessage {
             class: Class::Request,
@@ -224,10 +236,11 @@ impl<'a> StunMessage<'a> {
                 ice_controlled: (!controlling).then_some(control_tie_breaker),
                 priority: Some(prio),
                 use_candidate,
+                nomination,
+                network_cost: network_cost.map(|c| (0, c)),
                 ..Default::default()
             },
             integrity: &[],
-            integrity_len: 0,
         }
     }
 
@@ -242,7 +255,6 @@ impl<'a> StunMessage<'a> {
                 ..Default::default()
             },
             integrity: &[],
-            integrity_len: 0,
         }
     }
 
@@ -266,20 +278,22 @@ impl<'a> StunMessage<'a> {
         self.attrs.use_candidate
     }
 
+    /// If present, returns the value of the NOMINATION attribute (used for renomination).
+    pub(crate) fn nomination(&self) -> Option<u32> {
+        self.attrs.nomination
+    }
+
+    /// If present, returns the network cost from the GOOG-NETWORK-INFO attribute.
+    pub(crate) fn network_cost(&self) -> Option<u16> {
+        self.attrs.network_cost.map(|(_, cost)| cost)
+    }
+
     /// Verify the integrity of this message against the provided password.
     #[must_use]
     pub(crate) fn check_integrity(&self, password: &str) -> bool {
         if let Some(integ) = self.attrs.message_integrity {
-            let comp = crate::crypto::sha1_hmac(
-                password.as_bytes(),
-                &[
-                    &self.integrity[..2],
-                    &[(self.integrity_len >> 8) as u8, self.integrity_len as u8],
-                    &self.integrity[4..],
-                ],
-            );
-
-            comp == integ
+            let comp = sha1_integrity(password.as_bytes(), self.integrity);
+            comp.ct_eq(integ).into()
         } else {
             false
         }
@@ -289,82 +303,59 @@ impl<'a> StunMessage<'a> {
     ///
     /// The provided password is used to authenticate the message.
     pub(crate) fn to_bytes(self, password: &str, buf: &mut [u8]) -> Result<usize, StunError> {
-        const MSG_HEADER_LEN: usize = 20;
-        const MSG_INTEGRITY_LEN: usize = 20;
-        const FPRINT_LEN: usize = 4;
-        const ATTR_TLV_LENGTH: usize = 4;
-
-        let attr_len = self.attrs.padded_len()
-            + MSG_INTEGRITY_LEN
-            + ATTR_TLV_LENGTH
-            + FPRINT_LEN
-            + ATTR_TLV_LENGTH;
-
-        let mut buf = io::Cursor::new(buf);
-
-        // Message header
-        {
-            let typ = self.class.to_u16() | self.method.to_u16();
-            buf.write_all(&typ.to_be_bytes())?;
-
-            // -8 for fingerprint
-            buf.write_all(&((attr_len - 8) as u16).to_be_bytes())?;
-            buf.write_all(MAGIC)?;
-            buf.write_all(&self.trans_id.0)?;
-        }
-
-        // Custom attributes
-        self.attrs.to_bytes(&mut buf, &self.trans_id.0)?;
-
-        // Message integrity
-        buf.write_all(&Attributes::MESSAGE_INTEGRITY.to_be_bytes())?;
-        buf.write_all(&(MSG_INTEGRITY_LEN as u16).to_be_bytes())?;
-        buf.write_all(&[0; MSG_INTEGRITY_LEN])?; // placeholder
-        let integrity_value_offset = MSG_HEADER_LEN + self.attrs.padded_len() + ATTR_TLV_LENGTH;
-
-        // Fingerprint
-        buf.write_all(&Attributes::FINGERPRINT.to_be_bytes())?;
-        buf.write_all(&(FPRINT_LEN as u16).to_be_bytes())?;
-        buf.write_all(&[0; FPRINT_LEN])?; // placeholder
-        let fingerprint_value_offest = integrity_value_offset + MSG_INTEGRITY_LEN + ATTR_TLV_LENGTH;
-
-        let buf = buf.into_inner();
-
-        // Compute and fill in message integrity
-        let hmac = crate::crypto::sha1_hmac(
-            password.as_bytes(),
-            &[&buf[0..(integrity_value_offset - ATTR_TLV_LENGTH)]],
-        );
-        buf[integrity_value_offset..(integrity_value_offset + MSG_INTEGRITY_LEN)]
-            .copy_from_slice(&hmac);
-
-        // Fill in total message length
-        buf[2..4].copy_from_slice(&(attr_len as u16).to_be_bytes());
+        let a = self.attrs;
+
+        let attrs = [
+            a.username.map(Attribute::Username),
+            a.ice_controlled.map(Attribute::IceControlled),
+            a.ice_controlling.map(Attribute::IceControlling),
+            a.priority.map(Attribute::Priority),
+            a.xor_mapped_address.map(Attribute::XorMappedAddress),
+            a.use_candidate.then_some(Attribute::UseCandidate),
+            a.network_cost
+                .map(|(network_id, cost)| Attribute::NetworkCost { network_id, cost }),
+            a.nomination.map(Attribute::Nomination),
+        ];
 
-        // Compute and fill in fingerprint
-        let crc = Crc::<u32>::new(&CRC_32_ISO_HDLC)
-            .checksum(&buf[0..(fingerprint_value_offest - ATTR_TLV_LENGTH)])
-            ^ 0x5354_554e;
-        buf[fingerprint_value_offest..(fingerprint_value_offest + FPRINT_LEN)]
-            .copy_from_slice(&crc.to_be_bytes());
+        let bytes = attrs
+            .into_iter()
+            .flatten()
+            .fold(
+                MessageBuilder::new(self.class, self.method, self.trans_id),
+                MessageBuilder::attribute,
+            )
+            .message_integrity(Credentials::ShortTerm { password })
+            .fingerprint()
+            .build()?;
+
+        let Some(out) = buf.get_mut(..bytes.len()) else {
+            return Err(io::Error::from(io::ErrorKind::WriteZero).into());
+        };
+        out.copy_from_slice(&bytes);
 
-        Ok(MSG_HEADER_LEN + attr_len)
+        Ok(bytes.len())
     }
 }
 
-const MAGIC: &[u8] = &[0x21, 0x12, 0xA4, 0x42];
+pub(crate) const MAGIC: &[u8] = &[0x21, 0x12, 0xA4, 0x42];
 
-#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
-pub(crate) enum Class {
+/// Class of a STUN message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Class {
+    /// A request expecting a success or failure response.
     Request,
+    /// An indication, which gets no response.
     Indication,
+    /// Success response.
     Success,
+    /// Error response.
     Failure,
+    /// Never produced by parsing. Cannot be serialized.
     Unknown,
 }
 
 impl Class {
-    fn from_typ(typ: u16) -> Self {
+    pub(crate) fn from_typ(typ: u16) -> Self {
         use Class::*;
         match typ & 0b0000_0001_0001_0000 {
             0b0000_0000_0000_0000 => Request,
@@ -375,7 +366,7 @@ impl Class {
         }
     }
 
-    fn to_u16(self) -> u16 {
+    pub(crate) fn to_u16(self) -> u16 {
         use Class::*;
         match self {
             Request => 0b0000_0000_0000_0000,
@@ -387,28 +378,71 @@ impl Class {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
-pub(crate) enum Method {
+/// Method of a STUN message.
+///
+/// Binding is defined in RFC 8489, the others are TURN methods from RFC 8656.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Method {
+    /// Binding (0x001).
     Binding,
-    Unknown,
+    /// TURN Allocate (0x003).
+    Allocate,
+    /// TURN Refresh (0x004).
+    Refresh,
+    /// TURN Send (0x006).
+    Send,
+    /// TURN Data (0x007).
+    Data,
+    /// TURN CreatePermission (0x008).
+    CreatePermission,
+    /// TURN ChannelBind (0x009).
+    ChannelBind,
+    /// Any other 12 bit method number.
+    Unknown(u16),
 }
 
 impl Method {
-    fn from_typ(typ: u16) -> Self {
+    /// Method from the 12 bit method number.
+    pub fn from_u16(v: u16) -> Self {
         use Method::*;
-        match typ & 0b0011_1110_1110_1111 {
-            0b0000_0000_0000_0001 => Binding,
-            _ => Unknown,
+        match v & 0x0fff {
+            0x001 => Binding,
+            0x003 => Allocate,
+            0x004 => Refresh,
+            0x006 => Send,
+            0x007 => Data,
+            0x008 => CreatePermission,
+            0x009 => ChannelBind,
+            v => Unknown(v),
         }
     }
 
-    fn to_u16(self) -> u16 {
+    /// The 12 bit method number.
+    pub fn to_u16(self) -> u16 {
         use Method::*;
         match self {
-            Binding => 0b0000_0000_0000_0001,
-            _ => panic!("Unknown method"),
+            Binding => 0x001,
+            Allocate => 0x003,
+            Refresh => 0x004,
+            Send => 0x006,
+            Data => 0x007,
+            CreatePermission => 0x008,
+            ChannelBind => 0x009,
+            Unknown(v) => v & 0x0fff,
         }
     }
+
+    // typ is method and class
+    // |M11|M10|M9|M8|M7|C1|M6|M5|M4|C0|M3|M2|M1|M0|
+    pub(crate) fn from_typ(typ: u16) -> Self {
+        let v = (typ & 0x000f) | ((typ & 0x00e0) >> 1) | ((typ & 0x3e00) >> 2);
+        Self::from_u16(v)
+    }
+
+    pub(crate) fn to_typ(self) -> u16 {
+        let v = self.to_u16();
+        (v & 0x000f) | ((v & 0x0070) << 1) | ((v & 0x0f80) << 2)
+    }
 }
 
 #[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
@@ -427,6 +461,7 @@ pub struct Attributes<'a> {
     ice_controlled: Option<u64>,            // 0x8029
     ice_controlling: Option<u64>,           // 0x802a
     network_cost: Option<(u16, u16)>,       // 0xc057 https://tools.ietf.org/html/draft-thatcher-ice-network-cost-00
+    nomination: Option<u32>,                // 0xc001 https://tools.ietf.org/html/draft-thatcher-ice-renomination-01
 }
 
 impl<'a> fmt::Debug for Attributes<'a> {
@@ -472,6 +507,9 @@ impl<'a> fmt::Debug for Attributes<'a> {
         if let Some(value) = self.network_cost {
             debug_struct.field("network_cost", &value);
         }
+        if let Some(value) = self.nomination {
+            debug_struct.field("nomination", &value);
+        }
 
         debug_struct.finish()
     }
@@ -497,259 +535,7 @@ impl<'a> Attributes<'a> {
     }
 }
 
-use std::{io, str};
-
-use crate::util::NonCryptographicRng;
-
-impl<'a> Attributes<'a> {
-    const ALTERNATE_SERVER: u16 = 0x8023;
-    const ERROR_CODE: u16 = 0x0009;
-    const FINGERPRINT: u16 = 0x8028;
-    const ICE_CONTROLLED: u16 = 0x8029;
-    const ICE_CONTROLLING: u16 = 0x802a;
-    const MAPPED_ADDRESS: u16 = 0x0001;
-    const MESSAGE_INTEGRITY: u16 = 0x0008;
-    const NETWORK_COST: u16 = 0xc057;
-    const NONCE: u16 = 0x0015;
-    const PRIORITY: u16 = 0x0024;
-    const REALM: u16 = 0x0014;
-    const SOFTWARE: u16 = 0x0022;
-    const UNKNOWN_ATTRIBUTES: u16 = 0x000a;
-    const USE_CANDIDATE: u16 = 0x0025;
-    const USERNAME: u16 = 0x0006;
-    const XOR_MAPPED_ADDRESS: u16 = 0x0020;
-
-    fn padded_len(&self) -> usize {
-        const ATTR_TLV_LENGTH: usize = 4;
-
-        let username = self
-            .username
-            .map(|v| {
-                let pad = 4 - (v.len() % 4) % 4;
-                ATTR_TLV_LENGTH + v.len() + pad
-            })
-            .unwrap_or_default();
-        let ice_controlled = self
-            .ice_controlled
-            .map(|_| ATTR_TLV_LENGTH + 8)
-            .unwrap_or_default();
-        let ice_controlling = self
-            .ice_controlling
-            .map(|_| ATTR_TLV_LENGTH + 8)
-            .unwrap_or_default();
-        let priority = self
-            .priority
-            .map(|p| ATTR_TLV_LENGTH + p.to_le_bytes().len())
-            .unwrap_or_default();
-        let address = self
-            .xor_mapped_address
-            .map(|a| ATTR_TLV_LENGTH + if a.is_ipv4() { 8 } else { 20 })
-            .unwrap_or_default();
-        let use_candidate = if self.use_candidate {
-            ATTR_TLV_LENGTH
-        } else {
-            0
-        };
-
-        username + ice_controlled + ice_controlling + priority + address + use_candidate
-    }
-
-    fn to_bytes(self, vec: &mut dyn Write, trans_id: &[u8]) -> io::Result<()> {
-        if let Some(v) = self.username {
-            vec.write_all(&Self::USERNAME.to_be_bytes())?;
-            vec.write_all(&(v.len() as u16).to_be_bytes())?;
-            vec.write_all(v.as_bytes())?;
-            let pad = 4 - (v.len() % 4) % 4;
-            for _ in 0..pad {
-                vec.write_all(&[0])?;
-            }
-        }
-        if let Some(v) = self.ice_controlled {
-            vec.write_all(&Self::ICE_CONTROLLED.to_be_bytes())?;
-            vec.write_all(&8_u16.to_be_bytes())?;
-            vec.write_all(&v.to_be_bytes())?;
-        }
-        if let Some(v) = self.ice_controlling {
-            vec.write_all(&Self::ICE_CONTROLLING.to_be_bytes())?;
-            vec.write_all(&8_u16.to_be_bytes())?;
-            vec.write_all(&v.to_be_bytes())?;
-        }
-        if let Some(v) = self.priority {
-            vec.write_all(&Self::PRIORITY.to_be_bytes())?;
-            vec.write_all(&4_u16.to_be_bytes())?;
-            vec.write_all(&v.to_be_bytes())?;
-        }
-        if let Some(v) = self.xor_mapped_address {
-            let mut buf = [0_u8; 20];
-            let len = encode_xor(v, &mut buf, trans_id);
-            vec.write_all(&Self::XOR_MAPPED_ADDRESS.to_be_bytes())?;
-            vec.write_all(&((len as u16).to_be_bytes()))?;
-            vec.write_all(&buf[0..len])?;
-        }
-        if self.use_candidate {
-            vec.write_all(&Self::USE_CANDIDATE.to_be_bytes())?;
-            vec.write_all(&0_u16.to_be_bytes())?;
-        }
-
-        Ok(())
-    }
-
-    fn parse(
-        mut buf: &'a [u8],
-        trans_id: TransId,
-        msg_integrity_off: &mut usize,
-    ) -> Result<Attributes<'a>, StunError> {
-        let mut attributes = Attributes::default();
-
-        let mut off = 0;
-        // With the exception of the FINGERPRINT
-        //    attribute, which appears after MESSAGE-INTEGRITY, agents MUST ignore
-        //    all other attributes that follow MESSAGE-INTEGRITY
-        let mut ignore_rest = false;
-        loop {
-            if buf.is_empty() {
-                break;
-            }
-            let typ = u16::from_le_bytes([buf[1], buf[0]]);
-            let len = u16::from_le_bytes([buf[3], buf[2]]) as usize;
-            // trace!(
-            //     "STUN attribute typ 0x{:04x?} len {}: {:02x?}",
-            //     typ,
-            //     len,
-            //     buf
-            // );
-            if len > buf.len() - 4 {
-                return Err(StunError::Parse(format!(
-                    "Bad STUN attribute length: {} > {}",
-                    len,
-                    buf.len() - 4,
-                )));
-            }
-            if !ignore_rest || typ == Self::FINGERPRINT {
-                match typ {
-                    Self::MAPPED_ADDRESS => {
-                        warn!("STUN got MappedAddress");
-                    }
-                    Self::USERNAME => {
-                        attributes.username = Some(decode_str(typ, &buf[4..], len)?);
-                    }
-                    Self::MESSAGE_INTEGRITY => {
-                        if len != 20 {
-                            return Err(StunError::Parse(
-                                "Expected message integrity to have length 20".into(),
-                            ));
-                        }
-                        // message integrity is up until, but not including the message
-                        // integrity attribute.
-                        *msg_integrity_off = off;
-                        ignore_rest = true;
-                        attributes.message_integrity = Some(&buf[4..24]);
-                    }
-                    Self::ERROR_CODE => {
-                        if buf[4] != 0 || buf[5] != 0 || buf[6] & 0b1111_1000 != 0 {
-                            return Err(StunError::Parse("Expected 0 at top of error code".into()));
-                        }
-                        let class = buf[6] as u16 * 100;
-                        if class < 300 || class > 699 {
-                            return Err(StunError::Parse(format!(
-                                "Error class is not in range: {class}"
-                            )));
-                        }
-                        let code = class + (buf[7] % 100) as u16;
-                        attributes.error_code = Some((code, decode_str(typ, &buf[8..], len - 4)?));
-                    }
-                    Self::UNKNOWN_ATTRIBUTES => {
-                        warn!("STUN got UnknownAttributes");
-                    }
-                    Self::REALM => {
-                        attributes.realm = Some(decode_str(typ, &buf[4..], len)?);
-                    }
-                    Self::NONCE => {
-                        attributes.nonce = Some(decode_str(typ, &buf[4..], len)?);
-                    }
-                    Self::XOR_MAPPED_ADDRESS => {
-                        attributes.xor_mapped_address = Some(decode_xor(&buf[4..], trans_id)?);
-                    }
-                    Self::SOFTWARE => {
-                        attributes.software = Some(decode_str(typ, &buf[4..], len)?);
-                    }
-                    Self::PRIORITY => {
-                        if len != 4 {
-                            return Err(StunError::Parse("Priority that isnt 4 in length".into()));
-                        }
-                        let bytes = [buf[4], buf[5], buf[6], buf[7]];
-                        attributes.priority = Some(u32::from_be_bytes(bytes));
-                    }
-                    Self::USE_CANDIDATE => {
-                        if len != 0 {
-                            return Err(StunError::Parse(
-                                "UseCandidate that isnt 0 in length".into(),
-                            ));
-                        }
-                        attributes.use_candidate = true;
-                    }
-                    Self::ALTERNATE_SERVER => {
-                        warn!("STUN got AlternateServer");
-                    }
-                    Self::FINGERPRINT => {
-                        let bytes = [buf[4], buf[5], buf[6], buf[7]];
-                        attributes.fingerprint = Some(u32::from_be_bytes(bytes));
-                    }
-                    Self::ICE_CONTROLLED => {
-                        if len != 8 {
-                            return Err(StunError::Parse(
-                                "IceControlled that isnt 8 in length".into(),
-                            ));
-                        }
-                        let mut bytes = [0_u8; 8];
-                        bytes.copy_from_slice(&buf[4..(4 + 8)]);
-                        attributes.ice_controlled = Some(u64::from_be_bytes(bytes));
-                    }
-                    Self::ICE_CONTROLLING => {
-                        if len != 8 {
-                            return Err(StunError::Parse(
-                                "IceControlling that isnt 8 in length".into(),
-                            ));
-                        }
-                        let mut bytes = [0_u8; 8];
-                        bytes.copy_from_slice(&buf[4..(4 + 8)]);
-                        attributes.ice_controlling = Some(u64::from_be_bytes(bytes));
-                    }
-                    Self::NETWORK_COST => {
-                        if len != 4 {
-                            warn!("NetworkCost that isnt 4 in length");
-                        } else {
-                            let net_id = (buf[4] as u16) << 8 | buf[5] as u16;
-                            let cost = (buf[6] as u16) << 8 | buf[7] as u16;
-                            attributes.network_cost = Some((net_id, cost));
-                        }
-                    }
-                    _ => {}
-                }
-            }
-            // attributes are on even 32 bit boundaries
-            let pad = (4 - (len % 4)) % 4;
-            let pad_len = len + pad;
-            buf = &buf[(4 + pad_len)..];
-            off += 4 + pad_len;
-        }
-        Ok(attributes)
-    }
-}
-
-fn decode_str(typ: u16, buf: &[u8], len: usize) -> Result<&str, StunError> {
-    if len > 128 {
-        return Err(StunError::Parse(format!(
-            "0x{typ:04x?} too long str len: {len}"
-        )));
-    }
-    match str::from_utf8(&buf[0..len]).ok() {
-        Some(v) => Ok(v),
-        None => Err(StunError::Parse(format!("0x{typ:04x?} malformed utf-8"))),
-    }
-}
-
-fn encode_xor(addr: SocketAddr, buf: &mut [u8; 20], trans_id: &[u8]) -> usize {
+pub(crate) fn encode_xor(addr: SocketAddr, buf: &mut [u8; 20], trans_id: &[u8]) -> usize {
     let port = addr.port() ^ 0x2112;
     buf[2..4].copy_from_slice(&port.to_be_bytes());
     buf[1] = if addr.is_ipv4() { 1 } else { 2 };
@@ -775,7 +561,7 @@ fn encode_xor(addr: SocketAddr, buf: &mut [u8; 20], trans_id: &[u8]) -> usize {
     }
 }
 
-fn decode_xor(buf: &[u8], trans_id: TransId) -> Result<SocketAddr, StunError> {
+pub(crate) fn decode_xor(buf: &[u8], trans_id: TransId) -> Result<SocketAddr, StunError> {
     let port = (((buf[2] as u16) << 8) | (buf[3] as u16)) ^ 0x2112;
     let ip_buf = &buf[4..];
     let ip = match buf[1] {
@@ -804,6 +590,854 @@ fn decode_xor(buf: &[u8], trans_id: TransId) -> Result<SocketAddr, StunError> {
     Ok(SocketAddr::new(ip, port))
 }
 
+pub(crate) const HEADER_LEN: usize = 20;
+const FINGERPRINT_XOR: u32 = 0x5354_554e;
+
+const MAPPED_ADDRESS: u16 = 0x0001;
+const USERNAME: u16 = 0x0006;
+const MESSAGE_INTEGRITY: u16 = 0x0008;
+const ERROR_CODE: u16 = 0x0009;
+const UNKNOWN_ATTRIBUTES: u16 = 0x000a;
+const REALM: u16 = 0x0014;
+const NONCE: u16 = 0x0015;
+const MESSAGE_INTEGRITY_SHA256: u16 = 0x001c;
+const PASSWORD_ALGORITHM: u16 = 0x001d;
+const USERHASH: u16 = 0x001e;
+const XOR_MAPPED_ADDRESS: u16 = 0x0020;
+const PRIORITY: u16 = 0x0024;
+const USE_CANDIDATE: u16 = 0x0025;
+const PASSWORD_ALGORITHMS: u16 = 0x8002;
+const ALTERNATE_DOMAIN: u16 = 0x8003;
+const SOFTWARE: u16 = 0x8022;
+const ALTERNATE_SERVER: u16 = 0x8023;
+const FINGERPRINT: u16 = 0x8028;
+const ICE_CONTROLLED: u16 = 0x8029;
+const ICE_CONTROLLING: u16 = 0x802a;
+const NOMINATION: u16 = 0xc001;
+const NETWORK_COST: u16 = 0xc057;
+
+/// A STUN attribute.
+///
+/// Attributes not known to this module are kept as [`Attribute::Other`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Attribute<'a> {
+    /// MAPPED-ADDRESS (0x0001).
+    MappedAddress(SocketAddr),
+    /// USERNAME (0x0006).
+    Username(&'a str),
+    /// MESSAGE-INTEGRITY (0x0008). Use [`MessageBuilder::message_integrity()`] to add it.
+    MessageIntegrity(&'a [u8]),
+    /// ERROR-CODE (0x0009). Code is 300-699.
+    ErrorCode(u16, &'a str),
+    /// UNKNOWN-ATTRIBUTES (0x000A).
+    UnknownAttributes(Vec<u16>),
+    /// REALM (0x0014).
+    Realm(&'a str),
+    /// NONCE (0x0015).
+    Nonce(&'a str),
+    /// MESSAGE-INTEGRITY-SHA256 (0x001C). Use [`MessageBuilder::message_integrity_sha256()`]
+    /// to add it.
+    MessageIntegritySha256(&'a [u8]),
+    /// PASSWORD-ALGORITHM (0x001D).
+    PasswordAlgorithm(PasswordAlgorithm),
+    /// USERHASH (0x001E).
+    Userhash(&'a [u8]),
+    /// XOR-MAPPED-ADDRESS (0x0020).
+    XorMappedAddress(SocketAddr),
+    /// PRIORITY (0x0024), RFC 8445.
+    Priority(u32),
+    /// USE-CANDIDATE (0x0025), RFC 8445.
+    UseCandidate,
+    /// PASSWORD-ALGORITHMS (0x8002).
+    PasswordAlgorithms(Vec<PasswordAlgorithm>),
+    /// ALTERNATE-DOMAIN (0x8003).
+    AlternateDomain(&'a str),
+    /// SOFTWARE (0x8022).
+    Software(&'a str),
+    /// ALTERNATE-SERVER (0x8023).
+    AlternateServer(SocketAddr),
+    /// FINGERPRINT (0x8028). Use [`MessageBuilder::fingerprint()`] to add it.
+    Fingerprint(u32),
+    /// ICE-CONTROLLED (0x8029), RFC 8445.
+    IceControlled(u64),
+    /// ICE-CONTROLLING (0x802A), RFC 8445.
+    IceControlling(u64),
+    /// NOMINATION (0xC001), draft-thatcher-ice-renomination.
+    Nomination(u32),
+    /// GOOG-NETWORK-INFO (0xC057), draft-thatcher-ice-network-cost.
+    NetworkCost {
+        /// Network id.
+        network_id: u16,
+        /// Network cost.
+        cost: u16,
+    },
+    /// Any other attribute as a raw TLV, without padding.
+    Other {
+        /// Attribute type.
+        typ: u16,
+        /// Attribute value.
+        value: &'a [u8],
+    },
+}
+
+/// Password algorithm used to derive long-term credential keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PasswordAlgorithm {
+    /// MD5 (0x0001). Used when no other algorithm is agreed.
+    Md5,
+    /// SHA-256 (0x0002).
+    Sha256,
+    /// Any other algorithm number.
+    Unknown(u16),
+}
+
+impl PasswordAlgorithm {
+    /// Pick the algorithm to use from the PASSWORD-ALGORITHMS offered by a server.
+    ///
+    /// Prefers SHA-256 over MD5. Returns `None` if neither is offered.
+    pub fn negotiate(offered: &[PasswordAlgorithm]) -> Option<PasswordAlgorithm> {
+        [PasswordAlgorithm::Sha256, PasswordAlgorithm::Md5]
+            .into_iter()
+            .find(|a| offered.contains(a))
+    }
+
+    fn from_u16(v: u16) -> Self {
+        match v {
+            0x0001 => PasswordAlgorithm::Md5,
+            0x0002 => PasswordAlgorithm::Sha256,
+            v => PasswordAlgorithm::Unknown(v),
+        }
+    }
+
+    fn to_u16(self) -> u16 {
+        match self {
+            PasswordAlgorithm::Md5 => 0x0001,
+            PasswordAlgorithm::Sha256 => 0x0002,
+            PasswordAlgorithm::Unknown(v) => v,
+        }
+    }
+}
+
+/// Credentials for MESSAGE-INTEGRITY and MESSAGE-INTEGRITY-SHA256.
+///
+/// Passwords are used as given, no OpaqueString (RFC 8265) processing is done.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Credentials<'a> {
+    /// Short-term credentials, as used by ICE. The key is the password.
+    ShortTerm {
+        /// The password.
+        password: &'a str,
+    },
+    /// Long-term credentials, as used by TURN.
+    ///
+    /// The key is a hash of `username:realm:password` using `algorithm`. This is MD5,
+    /// unless another algorithm was agreed using PASSWORD-ALGORITHMS (RFC 8489 section
+    /// 9.2.4), see [`PasswordAlgorithm::negotiate()`].
+    LongTerm {
+        /// The username.
+        username: &'a str,
+        /// The realm.
+        realm: &'a str,
+        /// The password.
+        password: &'a str,
+        /// The agreed password algorithm.
+        algorithm: PasswordAlgorithm,
+    },
+}
+
+impl<'a> Credentials<'a> {
+    fn key(&self) -> Option<Vec<u8>> {
+        match self {
+            Credentials::ShortTerm { password } => Some(password.as_bytes().to_vec()),
+            Credentials::LongTerm {
+                username,
+                realm,
+                password,
+                algorithm,
+            } => {
+                let parts = [
+                    username.as_bytes(),
+                    b":",
+                    realm.as_bytes(),
+                    b":",
+                    password.as_bytes(),
+                ];
+                match algorithm {
+                    PasswordAlgorithm::Md5 => {
+                        let mut h = Md5::new();
+                        parts.iter().for_each(|p| h.update(p));
+                        Some(h.finalize().to_vec())
+                    }
+                    PasswordAlgorithm::Sha256 => {
+                        let mut h = Sha256::new();
+                        parts.iter().for_each(|p| h.update(p));
+                        Some(h.finalize().to_vec())
+                    }
+                    PasswordAlgorithm::Unknown(_) => None,
+                }
+            }
+        }
+    }
+}
+
+/// A parsed STUN message.
+///
+/// Parsing only checks the message is well formed. Integrity and fingerprint must be
+/// checked using [`Message::verify_integrity()`] and [`Message::verify_fingerprint()`].
+pub struct Message<'a> {
+    buf: &'a [u8],
+    class: Class,
+    method: Method,
+    trans_id: TransId,
+    attrs: Vec<Attribute<'a>>,
+    // Offsets to the start of the respective attribute (the TLV header).
+    integrity: Option<usize>,
+    integrity_sha256: Option<usize>,
+    fingerprint: Option<usize>,
+}
+
+impl<'a> Message<'a> {
+    /// Parse a STUN message.
+    ///
+    /// Attributes following MESSAGE-INTEGRITY are ignored, except for
+    /// MESSAGE-INTEGRITY-SHA256 and FINGERPRINT (RFC 8489 section 14.5).
+    pub fn parse(buf: &'a [u8]) -> Result<Message<'a>, StunError> {
+        if buf.len() < HEADER_LEN {
+            return Err(StunError::Parse("Buffer too short".into()));
+        }
+        if buf[0] & 0b1100_0000 != 0 {
+            return Err(StunError::Parse("First two bits are not zero".into()));
+        }
+        let len = u16::from_be_bytes([buf[2], buf[3]]) as usize;
+        if len & 0b11 != 0 {
+            return Err(StunError::Parse("len is not a multiple of 4".into()));
+        }
+        if len != buf.len() - HEADER_LEN {
+            return Err(StunError::Parse("STUN length vs buffer mismatch".into()));
+        }
+        if &buf[4..8] != MAGIC {
+            return Err(StunError::Parse("magic cookie mismatch".into()));
+        }
+
+        let typ = u16::from_be_bytes([buf[0], buf[1]]);
+        let class = Class::from_typ(typ);
+        let method = Method::from_typ(typ);
+        let trans_id = TransId::from_slice(&buf[8..20]);
+
+        let mut message = Message {
+            buf,
+            class,
+            method,
+            trans_id,
+            attrs: vec![],
+            integrity: None,
+            integrity_sha256: None,
+            fingerprint: None,
+        };
+
+        let mut off = HEADER_LEN;
+        while off < buf.len() {
+            if buf.len() - off < 4 {
+                return Err(StunError::Parse("Truncated attribute header".into()));
+            }
+            let typ = u16::from_be_bytes([buf[off], buf[off + 1]]);
+            let len = u16::from_be_bytes([buf[off + 2], buf[off + 3]]) as usize;
+            let padded = (len + 3) & !3;
+            if buf.len() - off - 4 < padded {
+                return Err(StunError::Parse(format!("0x{typ:04x} attribute too long")));
+            }
+            let value = &buf[off + 4..off + 4 + len];
+
+            if message.fingerprint.is_some() {
+                return Err(StunError::Parse("Attribute after FINGERPRINT".into()));
+            }
+
+            let ignored = match typ {
+                FINGERPRINT => false,
+                MESSAGE_INTEGRITY_SHA256 => message.integrity_sha256.is_some(),
+                _ => message.integrity.is_some() || message.integrity_sha256.is_some(),
+            };
+
+            if !ignored {
+                match typ {
+                    MESSAGE_INTEGRITY => message.integrity = Some(off),
+                    MESSAGE_INTEGRITY_SHA256 => message.integrity_sha256 = Some(off),
+                    FINGERPRINT => message.fingerprint = Some(off),
+                    _ => {}
+                }
+                let attr = parse_attribute(typ, value, trans_id)?;
+                message.attrs.push(attr);
+            }
+
+            off += 4 + padded;
+        }
+
+        Ok(message)
+    }
+
+    /// Class of the message.
+    pub fn class(&self) -> Class {
+        self.class
+    }
+
+    /// Method of the message.
+    pub fn method(&self) -> Method {
+        self.method
+    }
+
+    /// Transaction id of the message.
+    pub fn transaction_id(&self) -> TransId {
+        self.trans_id
+    }
+
+    /// All attributes in the order they appear in the message.
+    pub fn attributes(&self) -> &[Attribute<'a>] {
+        &self.attrs
+    }
+
+    /// USERNAME, if present.
+    pub fn username(&self) -> Option<&'a str> {
+        self.attrs.iter().find_map(|a| match a {
+            Attribute::Username(v) => Some(*v),
+            _ => None,
+        })
+    }
+
+    /// REALM, if present.
+    pub fn realm(&self) -> Option<&'a str> {
+        self.attrs.iter().find_map(|a| match a {
+            Attribute::Realm(v) => Some(*v),
+            _ => None,
+        })
+    }
+
+    /// NONCE, if present.
+    pub fn nonce(&self) -> Option<&'a str> {
+        self.attrs.iter().find_map(|a| match a {
+            Attribute::Nonce(v) => Some(*v),
+            _ => None,
+        })
+    }
+
+    /// ERROR-CODE, if present.
+    pub fn error_code(&self) -> Option<(u16, &'a str)> {
+        self.attrs.iter().find_map(|a| match a {
+            Attribute::ErrorCode(code, reason) => Some((*code, *reason)),
+            _ => None,
+        })
+    }
+
+    /// XOR-MAPPED-ADDRESS, if present.
+    pub fn xor_mapped_address(&self) -> Option<SocketAddr> {
+        self.attrs.iter().find_map(|a| match a {
+            Attribute::XorMappedAddress(v) => Some(*v),
+            _ => None,
+        })
+    }
+
+    /// Whether the message has a MESSAGE-INTEGRITY or MESSAGE-INTEGRITY-SHA256 attribute.
+    pub fn has_integrity(&self) -> bool {
+        self.integrity.is_some() || self.integrity_sha256.is_some()
+    }
+
+    /// PASSWORD-ALGORITHM, if present.
+    pub fn password_algorithm(&self) -> Option<PasswordAlgorithm> {
+        password_algorithm(&self.attrs)
+    }
+
+    /// PASSWORD-ALGORITHMS, if present.
+    pub fn password_algorithms(&self) -> Option<&[PasswordAlgorithm]> {
+        self.attrs.iter().find_map(|a| match a {
+            Attribute::PasswordAlgorithms(v) => Some(&v[..]),
+            _ => None,
+        })
+    }
+
+    /// Verify MESSAGE-INTEGRITY-SHA256, or MESSAGE-INTEGRITY if there is no SHA256 variant.
+    ///
+    /// For long-term credentials, the message must use the algorithm of the credentials.
+    /// A request or indication without PASSWORD-ALGORITHM uses MD5, while responses use
+    /// the algorithm of the request.
+    ///
+    /// Returns `false` if the message has neither, or uses another password algorithm.
+    #[must_use]
+    pub fn verify_integrity(&self, credentials: &Credentials) -> bool {
+        // The algorithm is not taken from the message, since that would let anyone
+        // rewriting PASSWORD-ALGORITHM pick the key.
+        if !algorithm_matches(self.class, &self.attrs, credentials) {
+            return false;
+        }
+
+        let Some(key) = credentials.key() else {
+            return false;
+        };
+
+        if let Some(off) = self.integrity_sha256 {
+            let len = u16::from_be_bytes([self.buf[off + 2], self.buf[off + 3]]) as usize;
+            // RFC 8489 allows truncation down to 16 bytes.
+            if !(16..=32).contains(&len) || len % 4 != 0 {
+                return false;
+            }
+            let expected = &self.buf[off + 4..off + 4 + len];
+            let comp = sha256_integrity(&key, &self.buf[..off], len);
+            comp[..len].ct_eq(expected).into()
+        } else if let Some(off) = self.integrity {
+            let expected = &self.buf[off + 4..off + 4 + 20];
+            let comp = sha1_integrity(&key, &self.buf[..off]);
+            comp.ct_eq(expected).into()
+        } else {
+            false
+        }
+    }
+
+    /// Verify the FINGERPRINT attribute.
+    ///
+    /// Returns `false` if the message has none.
+    #[must_use]
+    pub fn verify_fingerprint(&self) -> bool {
+        let Some(off) = self.fingerprint else {
+            return false;
+        };
+        let Some(Attribute::Fingerprint(expected)) = self.attrs.last() else {
+            return false;
+        };
+        let crc = Crc::<u32>::new(&CRC_32_ISO_HDLC).checksum(&self.buf[..off]) ^ FINGERPRINT_XOR;
+        crc.ct_eq(expected).into()
+    }
+}
+
+impl<'a> fmt::Debug for Message<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Message")
+            .field("class", &self.class)
+            .field("method", &self.method)
+            .field("trans_id", &self.trans_id)
+            .field("attrs", &self.attrs)
+            .finish()
+    }
+}
+
+/// Builder of STUN messages.
+///
+/// Attributes are written in the order they are added. MESSAGE-INTEGRITY,
+/// MESSAGE-INTEGRITY-SHA256 and FINGERPRINT are always placed last, in that order.
+#[derive(Debug)]
+pub struct MessageBuilder<'a> {
+    class: Class,
+    method: Method,
+    trans_id: TransId,
+    attrs: Vec<Attribute<'a>>,
+    integrity: Option<Credentials<'a>>,
+    integrity_sha256: Option<Credentials<'a>>,
+    fingerprint: bool,
+}
+
+impl<'a> MessageBuilder<'a> {
+    /// Start building a new message.
+    pub fn new(class: Class, method: Method, trans_id: TransId) -> Self {
+        MessageBuilder {
+            class,
+            method,
+            trans_id,
+            attrs: vec![],
+            integrity: None,
+            integrity_sha256: None,
+            fingerprint: false,
+        }
+    }
+
+    /// Add an attribute.
+    pub fn attribute(mut self, attr: Attribute<'a>) -> Self {
+        self.attrs.push(attr);
+        self
+    }
+
+    /// Add MESSAGE-INTEGRITY (HMAC-SHA1) using the credentials.
+    pub fn message_integrity(mut self, credentials: Credentials<'a>) -> Self {
+        self.integrity = Some(credentials);
+        self
+    }
+
+    /// Add MESSAGE-INTEGRITY-SHA256 (HMAC-SHA256) using the credentials.
+    pub fn message_integrity_sha256(mut self, credentials: Credentials<'a>) -> Self {
+        self.integrity_sha256 = Some(credentials);
+        self
+    }
+
+    /// Add FINGERPRINT.
+    pub fn fingerprint(mut self) -> Self {
+        self.fingerprint = true;
+        self
+    }
+
+    /// Serialize the message.
+    pub fn build(self) -> Result<Vec<u8>, StunError> {
+        if self.class == Class::Unknown {
+            return Err(StunError::Parse("Unknown class can't be serialized".into()));
+        }
+
+        let mut buf = Vec::with_capacity(256);
+        let typ = self.class.to_u16() | self.method.to_typ();
+        buf.extend_from_slice(&typ.to_be_bytes());
+        buf.extend_from_slice(&[0, 0]); // length placeholder
+        buf.extend_from_slice(MAGIC);
+        buf.extend_from_slice(self.trans_id.as_bytes());
+
+        let mut value = Vec::new();
+        for attr in &self.attrs {
+            value.clear();
+            let typ = encode_attribute(attr, self.trans_id, &mut value)?;
+            write_tlv(&mut buf, typ, &value)?;
+        }
+
+        let key = |c: &Credentials| {
+            if !algorithm_matches(self.class, &self.attrs, c) {
+                return Err(StunError::Parse(
+                    "PASSWORD-ALGORITHM does not match the credentials".into(),
+                ));
+            }
+            c.key()
+                .ok_or_else(|| StunError::Parse("Unsupported password algorithm".into()))
+        };
+
+        if let Some(creds) = &self.integrity {
+            let key = key(creds)?;
+            let hmac = sha1_integrity(&key, &buf);
+            write_tlv(&mut buf, MESSAGE_INTEGRITY, &hmac)?;
+        }
+
+        if let Some(creds) = &self.integrity_sha256 {
+            let key = key(creds)?;
+            let hmac = sha256_integrity(&key, &buf, 32);
+            write_tlv(&mut buf, MESSAGE_INTEGRITY_SHA256, &hmac)?;
+        }
+
+        if self.fingerprint {
+            set_len(&mut buf, 4 + 4);
+            let crc = Crc::<u32>::new(&CRC_32_ISO_HDLC).checksum(&buf) ^ FINGERPRINT_XOR;
+            write_tlv(&mut buf, FINGERPRINT, &crc.to_be_bytes())?;
+        }
+
+        set_len(&mut buf, 0);
+
+        Ok(buf)
+    }
+}
+
+/// Set the header length to the current attributes plus `extra`.
+fn set_len(buf: &mut [u8], extra: usize) {
+    let len = (buf.len() - HEADER_LEN + extra) as u16;
+    buf[2..4].copy_from_slice(&len.to_be_bytes());
+}
+
+fn write_tlv(buf: &mut Vec<u8>, typ: u16, value: &[u8]) -> Result<(), StunError> {
+    let Ok(len) = u16::try_from(value.len()) else {
+        return Err(StunError::Parse(format!("0x{typ:04x} attribute too long")));
+    };
+    if buf.len() - HEADER_LEN + 4 + value.len() > u16::MAX as usize {
+        return Err(StunError::Parse("STUN message too long".into()));
+    }
+    buf.extend_from_slice(&typ.to_be_bytes());
+    buf.extend_from_slice(&len.to_be_bytes());
+    buf.extend_from_slice(value);
+    let pad = (4 - value.len() % 4) % 4;
+    buf.extend_from_slice(&[0; 3][..pad]);
+    Ok(())
+}
+
+fn encode_attribute(
+    attr: &Attribute,
+    trans_id: TransId,
+    out: &mut Vec<u8>,
+) -> Result<u16, StunError> {
+    use Attribute::*;
+    let typ = match attr {
+        MappedAddress(v) => {
+            encode_addr(*v, out);
+            MAPPED_ADDRESS
+        }
+        Username(v) => {
+            out.extend_from_slice(v.as_bytes());
+            USERNAME
+        }
+        ErrorCode(code, reason) => {
+            if !(300..700).contains(code) {
+                return Err(StunError::Parse(format!("Invalid error code: {code}")));
+            }
+            out.extend_from_slice(&[0, 0, (code / 100) as u8, (code % 100) as u8]);
+            out.extend_from_slice(reason.as_bytes());
+            ERROR_CODE
+        }
+        UnknownAttributes(v) => {
+            v.iter()
+                .for_each(|t| out.extend_from_slice(&t.to_be_bytes()));
+            UNKNOWN_ATTRIBUTES
+        }
+        Realm(v) => {
+            out.extend_from_slice(v.as_bytes());
+            REALM
+        }
+        Nonce(v) => {
+            out.extend_from_slice(v.as_bytes());
+            NONCE
+        }
+        PasswordAlgorithm(v) => {
+            // Neither MD5 nor SHA-256 have parameters.
+            out.extend_from_slice(&v.to_u16().to_be_bytes());
+            out.extend_from_slice(&[0, 0]);
+            PASSWORD_ALGORITHM
+        }
+        Userhash(v) => {
+            out.extend_from_slice(v);
+            USERHASH
+        }
+        XorMappedAddress(v) => {
+            let mut b = [0; 20];
+            let n = encode_xor(*v, &mut b, trans_id.as_bytes());
+            out.extend_from_slice(&b[..n]);
+            XOR_MAPPED_ADDRESS
+        }
+        Priority(v) => {
+            out.extend_from_slice(&v.to_be_bytes());
+            PRIORITY
+        }
+        UseCandidate => USE_CANDIDATE,
+        PasswordAlgorithms(v) => {
+            for a in v {
+                out.extend_from_slice(&a.to_u16().to_be_bytes());
+                out.extend_from_slice(&[0, 0]);
+            }
+            PASSWORD_ALGORITHMS
+        }
+        AlternateDomain(v) => {
+            out.extend_from_slice(v.as_bytes());
+            ALTERNATE_DOMAIN
+        }
+        Software(v) => {
+            out.extend_from_slice(v.as_bytes());
+            SOFTWARE
+        }
+        AlternateServer(v) => {
+            encode_addr(*v, out);
+            ALTERNATE_SERVER
+        }
+        IceControlled(v) => {
+            out.extend_from_slice(&v.to_be_bytes());
+            ICE_CONTROLLED
+        }
+        IceControlling(v) => {
+            out.extend_from_slice(&v.to_be_bytes());
+            ICE_CONTROLLING
+        }
+        Nomination(v) => {
+            out.extend_from_slice(&v.to_be_bytes());
+            NOMINATION
+        }
+        NetworkCost { network_id, cost } => {
+            out.extend_from_slice(&network_id.to_be_bytes());
+            out.extend_from_slice(&cost.to_be_bytes());
+            NETWORK_COST
+        }
+        Other { typ, value } => {
+            if matches!(
+                *typ,
+                MESSAGE_INTEGRITY | MESSAGE_INTEGRITY_SHA256 | FINGERPRINT
+            ) {
+                return Err(StunError::Parse(format!(
+                    "0x{typ:04x} is added by MessageBuilder"
+                )));
+            }
+            out.extend_from_slice(value);
+            *typ
+        }
+        MessageIntegrity(_) | MessageIntegritySha256(_) | Fingerprint(_) => {
+            return Err(StunError::Parse(
+                "Integrity and fingerprint are added by MessageBuilder".into(),
+            ));
+        }
+    };
+    Ok(typ)
+}
+
+fn parse_attribute(typ: u16, v: &[u8], trans_id: TransId) -> Result<Attribute<'_>, StunError> {
+    let check_len = |ok: bool| {
+        if ok {
+            Ok(())
+        } else {
+            Err(StunError::Parse(format!(
+                "0x{typ:04x} invalid len: {}",
+                v.len()
+            )))
+        }
+    };
+    let string =
+        || str::from_utf8(v).map_err(|_| StunError::Parse(format!("0x{typ:04x} malformed utf-8")));
+
+    use Attribute::*;
+    let attr = match typ {
+        MAPPED_ADDRESS => MappedAddress(decode_addr(typ, v)?),
+        USERNAME => Username(string()?),
+        MESSAGE_INTEGRITY => {
+            check_len(v.len() == 20)?;
+            MessageIntegrity(v)
+        }
+        ERROR_CODE => {
+            check_len(v.len() >= 4)?;
+            let class = (v[2] & 0b111) as u16;
+            if !(3..=6).contains(&class) || v[3] > 99 {
+                return Err(StunError::Parse(format!(
+                    "Error code is not in range: {class}{:02}",
+                    v[3]
+                )));
+            }
+            let code = class * 100 + v[3] as u16;
+            let reason = str::from_utf8(&v[4..])
+                .map_err(|_| StunError::Parse(format!("0x{typ:04x} malformed utf-8")))?;
+            ErrorCode(code, reason)
+        }
+        UNKNOWN_ATTRIBUTES => {
+            check_len(v.len() % 2 == 0)?;
+            UnknownAttributes(
+                v.chunks(2)
+                    .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                    .collect(),
+            )
+        }
+        REALM => Realm(string()?),
+        NONCE => Nonce(string()?),
+        MESSAGE_INTEGRITY_SHA256 => {
+            check_len(v.len() >= 16 && v.len() <= 32)?;
+            MessageIntegritySha256(v)
+        }
+        PASSWORD_ALGORITHM => {
+            check_len(v.len() >= 4)?;
+            PasswordAlgorithm(self::PasswordAlgorithm::from_u16(u16::from_be_bytes([
+                v[0], v[1],
+            ])))
+        }
+        USERHASH => {
+            check_len(v.len() == 32)?;
+            Userhash(v)
+        }
+        XOR_MAPPED_ADDRESS => {
+            check_len(addr_len_ok(v))?;
+            XorMappedAddress(decode_xor(v, trans_id)?)
+        }
+        PRIORITY => {
+            check_len(v.len() == 4)?;
+            Priority(u32::from_be_bytes([v[0], v[1], v[2], v[3]]))
+        }
+        USE_CANDIDATE => UseCandidate,
+        PASSWORD_ALGORITHMS => {
+            let mut algos = vec![];
+            let mut rest = v;
+            while !rest.is_empty() {
+                check_len(rest.len() >= 4)?;
+                let algo = u16::from_be_bytes([rest[0], rest[1]]);
+                let params = u16::from_be_bytes([rest[2], rest[3]]) as usize;
+                let next = 4 + ((params + 3) & !3);
+                check_len(rest.len() >= next)?;
+                algos.push(self::PasswordAlgorithm::from_u16(algo));
+                rest = &rest[next..];
+            }
+            PasswordAlgorithms(algos)
+        }
+        ALTERNATE_DOMAIN => AlternateDomain(string()?),
+        SOFTWARE => Software(string()?),
+        ALTERNATE_SERVER => AlternateServer(decode_addr(typ, v)?),
+        FINGERPRINT => {
+            check_len(v.len() == 4)?;
+            Fingerprint(u32::from_be_bytes([v[0], v[1], v[2], v[3]]))
+        }
+        ICE_CONTROLLED => {
+            check_len(v.len() == 8)?;
+            IceControlled(u64::from_be_bytes(v.try_into().unwrap()))
+        }
+        ICE_CONTROLLING => {
+            check_len(v.len() == 8)?;
+            IceControlling(u64::from_be_bytes(v.try_into().unwrap()))
+        }
+        NOMINATION if v.len() == 4 => Nomination(u32::from_be_bytes([v[0], v[1], v[2], v[3]])),
+        NETWORK_COST if v.len() == 4 => NetworkCost {
+            network_id: u16::from_be_bytes([v[0], v[1]]),
+            cost: u16::from_be_bytes([v[2], v[3]]),
+        },
+        NOMINATION | NETWORK_COST => {
+            warn!("0x{typ:04x} invalid len: {}", v.len());
+            Other { typ, value: v }
+        }
+        _ => Other { typ, value: v },
+    };
+    Ok(attr)
+}
+
+fn addr_len_ok(v: &[u8]) -> bool {
+    (v.len() == 8 && v[1] == 1) || (v.len() == 20 && v[1] == 2)
+}
+
+fn decode_addr(typ: u16, v: &[u8]) -> Result<SocketAddr, StunError> {
+    if v.len() < 2 || !addr_len_ok(v) {
+        return Err(StunError::Parse(format!("0x{typ:04x} invalid address")));
+    }
+    let port = u16::from_be_bytes([v[2], v[3]]);
+    let ip = if v[1] == 1 {
+        let b: [u8; 4] = v[4..8].try_into().unwrap();
+        IpAddr::from(b)
+    } else {
+        let b: [u8; 16] = v[4..20].try_into().unwrap();
+        IpAddr::from(b)
+    };
+    Ok(SocketAddr::new(ip, port))
+}
+
+fn encode_addr(addr: SocketAddr, out: &mut Vec<u8>) {
+    out.push(0);
+    out.push(if addr.is_ipv4() { 1 } else { 2 });
+    out.extend_from_slice(&addr.port().to_be_bytes());
+    match addr.ip() {
+        IpAddr::V4(ip) => out.extend_from_slice(&ip.octets()),
+        IpAddr::V6(ip) => out.extend_from_slice(&ip.octets()),
+    }
+}
+
+fn password_algorithm(attrs: &[Attribute]) -> Option<PasswordAlgorithm> {
+    attrs.iter().find_map(|a| match a {
+        Attribute::PasswordAlgorithm(v) => Some(*v),
+        _ => None,
+    })
+}
+
+/// Whether a message with `attrs` is keyed with the password algorithm of `credentials`.
+fn algorithm_matches(class: Class, attrs: &[Attribute], credentials: &Credentials) -> bool {
+    let Credentials::LongTerm { algorithm, .. } = credentials else {
+        return true;
+    };
+
+    match password_algorithm(attrs) {
+        Some(v) => v == *algorithm,
+        // Responses don't repeat the algorithm of the request.
+        None if matches!(class, Class::Success | Class::Failure) => true,
+        None => *algorithm == PasswordAlgorithm::Md5,
+    }
+}
+
+/// HMAC-SHA1 for a MESSAGE-INTEGRITY following `before`, the message up until the
+/// attribute. The header length is adjusted to end with the attribute.
+fn sha1_integrity(key: &[u8], before: &[u8]) -> [u8; 20] {
+    let len = (before.len() - HEADER_LEN + 4 + 20) as u16;
+    crate::crypto::sha1_hmac(key, &[&before[..2], &len.to_be_bytes(), &before[4..]])
+}
+
+/// HMAC-SHA256 for a MESSAGE-INTEGRITY-SHA256 of `value_len` following `before`.
+fn sha256_integrity(key: &[u8], before: &[u8], value_len: usize) -> [u8; 32] {
+    let len = (before.len() - HEADER_LEN + 4 + value_len) as u16;
+    let mut hmac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC can take key of any size");
+    for payload in [&before[..2], &len.to_be_bytes(), &before[4..]] {
+        hmac.update(payload);
+    }
+    hmac.finalize().into_bytes().into()
+}
+
 impl<'a> fmt::Debug for StunMessage<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("StunMessage")
@@ -868,13 +1502,14 @@ mod test {
             ice_controlled: Some(10),
             ice_controlling: Some(100),
             network_cost: Some((10, 10)),
+            nomination: Some(3),
         };
 
         let dbg_print = format!("{attrs:?}");
 
         assert_eq!(
             dbg_print,
-            r#"Attributes { username: "foo", message_integrity: [48, 48, 48, 48], error_code: (401, "Unauthorized"), realm: "baz", nonce: "abcd", xor_mapped_address: 127.0.0.1:0, software: "str0m", fingerprint: 9999, priority: 1, use_candidate: true, ice_controlled: 10, ice_controlling: 100, network_cost: (10, 10) }"#
+            r#"Attributes { username: "foo", message_integrity: [48, 48, 48, 48], error_code: (401, "Unauthorized"), realm: "baz", nonce: "abcd", xor_mapped_address: 127.0.0.1:0, software: "str0m", fingerprint: 9999, priority: 1, use_candidate: true, ice_controlled: 10, ice_controlling: 100, network_cost: (10, 10), nomination: 3 }"#
         );
     }
 
@@ -884,4 +1519,281 @@ mod test {
 
         assert!(result.is_err());
     }
+
+    // RFC 5769 2.1 Sample Request
+    const REQUEST: &[u8] = &[
+        0x00, 0x01, 0x00, 0x58, 0x21, 0x12, 0xa4, 0x42, 0xb7, 0xe7, 0xa7, 0x01, 0xbc, 0x34, 0xd6,
+        0x86, 0xfa, 0x87, 0xdf, 0xae, 0x80, 0x22, 0x00, 0x10, 0x53, 0x54, 0x55, 0x4e, 0x20, 0x74,
+        0x65, 0x73, 0x74, 0x20, 0x63, 0x6c, 0x69, 0x65, 0x6e, 0x74, 0x00, 0x24, 0x00, 0x04, 0x6e,
+        0x00, 0x01, 0xff, 0x80, 0x29, 0x00, 0x08, 0x93, 0x2f, 0xf9, 0xb1, 0x51, 0x26, 0x3b, 0x36,
+        0x00, 0x06, 0x00, 0x09, 0x65, 0x76, 0x74, 0x6a, 0x3a, 0x68, 0x36, 0x76, 0x59, 0x20, 0x20,
+        0x20, 0x00, 0x08, 0x00, 0x14, 0x9a, 0xea, 0xa7, 0x0c, 0xbf, 0xd8, 0xcb, 0x56, 0x78, 0x1e,
+        0xf2, 0xb5, 0xb2, 0xd3, 0xf2, 0x49, 0xc1, 0xb5, 0x71, 0xa2, 0x80, 0x28, 0x00, 0x04, 0xe5,
+        0x7a, 0x3b, 0xcf,
+    ];
+
+    // RFC 5769 2.2 Sample IPv4 Response
+    const RESPONSE: &[u8] = &[
+        0x01, 0x01, 0x00, 0x3c, 0x21, 0x12, 0xa4, 0x42, 0xb7, 0xe7, 0xa7, 0x01, 0xbc, 0x34, 0xd6,
+        0x86, 0xfa, 0x87, 0xdf, 0xae, 0x80, 0x22, 0x00, 0x0b, 0x74, 0x65, 0x73, 0x74, 0x20, 0x76,
+        0x65, 0x63, 0x74, 0x6f, 0x72, 0x20, 0x00, 0x20, 0x00, 0x08, 0x00, 0x01, 0xa1, 0x47, 0xe1,
+        0x12, 0xa6, 0x43, 0x00, 0x08, 0x00, 0x14, 0x2b, 0x91, 0xf5, 0x99, 0xfd, 0x9e, 0x90, 0xc3,
+        0x8c, 0x74, 0x89, 0xf9, 0x2a, 0xf9, 0xba, 0x53, 0xf0, 0x6b, 0xe7, 0xd7, 0x80, 0x28, 0x00,
+        0x04, 0xc0, 0x7d, 0x4c, 0x96,
+    ];
+
+    const PASS: &str = "VOkJxbRl1RmTxUk/WvJxBt";
+
+    #[test]
+    fn rfc5769_request() {
+        let msg = Message::parse(REQUEST).unwrap();
+        assert_eq!(msg.class(), Class::Request);
+        assert_eq!(msg.method(), Method::Binding);
+        assert_eq!(msg.username(), Some("evtj:h6vY"));
+        assert_eq!(msg.attributes()[0], Attribute::Software("STUN test client"));
+        assert_eq!(msg.attributes()[1], Attribute::Priority(0x6e0001ff));
+        assert_eq!(
+            msg.attributes()[2],
+            Attribute::IceControlled(0x932ff9b151263b36)
+        );
+        assert!(msg.verify_fingerprint());
+        assert!(msg.verify_integrity(&Credentials::ShortTerm { password: PASS }));
+        assert!(!msg.verify_integrity(&Credentials::ShortTerm { password: "nope" }));
+    }
+
+    #[test]
+    fn rfc5769_response() {
+        let msg = Message::parse(RESPONSE).unwrap();
+        assert_eq!(msg.class(), Class::Success);
+        assert_eq!(
+            msg.xor_mapped_address(),
+            Some("192.0.2.1:32853".parse().unwrap())
+        );
+        assert!(msg.verify_fingerprint());
+        assert!(msg.verify_integrity(&Credentials::ShortTerm { password: PASS }));
+    }
+
+    #[test]
+    fn rebuild_rfc5769_response() {
+        let msg = Message::parse(RESPONSE).unwrap();
+        let buf = MessageBuilder::new(msg.class(), msg.method(), msg.transaction_id())
+            .attribute(Attribute::Software("test vector"))
+            .attribute(Attribute::XorMappedAddress(
+                msg.xor_mapped_address().unwrap(),
+            ))
+            .message_integrity(Credentials::ShortTerm { password: PASS })
+            .fingerprint()
+            .build()
+            .unwrap();
+
+        // Only difference is the padding after SOFTWARE, which is 0x20 in the RFC.
+        let mut expected = RESPONSE.to_vec();
+        expected[35] = 0;
+        let rebuilt = Message::parse(&buf).unwrap();
+        assert_eq!(buf[..36], expected[..36]);
+        assert!(rebuilt.verify_integrity(&Credentials::ShortTerm { password: PASS }));
+        assert!(rebuilt.verify_fingerprint());
+    }
+
+    #[test]
+    fn long_term_round_trip() {
+        let creds = Credentials::LongTerm {
+            username: "user",
+            realm: "example.org",
+            password: "pass",
+            algorithm: PasswordAlgorithm::Sha256,
+        };
+        let trans_id = TransId::new();
+        let addr: SocketAddr = "[2001:db8::1]:3478".parse().unwrap();
+
+        let buf = MessageBuilder::new(Class::Request, Method::Allocate, trans_id)
+            .attribute(Attribute::Username("user"))
+            .attribute(Attribute::Realm("example.org"))
+            .attribute(Attribute::Nonce("abc"))
+            .attribute(Attribute::PasswordAlgorithm(PasswordAlgorithm::Sha256))
+            .attribute(Attribute::XorMappedAddress(addr))
+            .attribute(Attribute::Other {
+                typ: 0x0019,
+                value: &[17, 0, 0, 0],
+            })
+            .message_integrity(creds)
+            .message_integrity_sha256(creds)
+            .fingerprint()
+            .build()
+            .unwrap();
+
+        let msg = Message::parse(&buf).unwrap();
+        assert_eq!(msg.method(), Method::Allocate);
+        assert_eq!(msg.transaction_id(), trans_id);
+        assert_eq!(msg.xor_mapped_address(), Some(addr));
+        assert!(msg.attributes().contains(&Attribute::Other {
+            typ: 0x0019,
+            value: &[17, 0, 0, 0]
+        }));
+        assert!(msg.verify_integrity(&creds));
+        assert!(msg.verify_fingerprint());
+
+        let wrong = Credentials::LongTerm {
+            username: "user",
+            realm: "example.org",
+            password: "other",
+            algorithm: PasswordAlgorithm::Sha256,
+        };
+        assert!(!msg.verify_integrity(&wrong));
+    }
+
+    #[test]
+    fn long_term_algorithm_must_match() {
+        let md5 = Credentials::LongTerm {
+            username: "user",
+            realm: "example.org",
+            password: "pass",
+            algorithm: PasswordAlgorithm::Md5,
+        };
+        let sha256 = Credentials::LongTerm {
+            username: "user",
+            realm: "example.org",
+            password: "pass",
+            algorithm: PasswordAlgorithm::Sha256,
+        };
+
+        // A request without PASSWORD-ALGORITHM is keyed with MD5, which must not be
+        // accepted when SHA-256 was agreed.
+        let buf = MessageBuilder::new(Class::Request, Method::Allocate, TransId::new())
+            .attribute(Attribute::Username("user"))
+            .message_integrity(md5)
+            .build()
+            .unwrap();
+        let msg = Message::parse(&buf).unwrap();
+        assert!(msg.verify_integrity(&md5));
+        assert!(!msg.verify_integrity(&sha256));
+
+        let buf = MessageBuilder::new(Class::Request, Method::Allocate, TransId::new())
+            .attribute(Attribute::PasswordAlgorithm(PasswordAlgorithm::Md5))
+            .message_integrity(md5)
+            .build()
+            .unwrap();
+        let msg = Message::parse(&buf).unwrap();
+        assert_eq!(msg.password_algorithm(), Some(PasswordAlgorithm::Md5));
+        assert!(!msg.verify_integrity(&sha256));
+
+        // Responses don't carry the algorithm.
+        let buf = MessageBuilder::new(Class::Success, Method::Allocate, TransId::new())
+            .message_integrity(sha256)
+            .build()
+            .unwrap();
+        let msg = Message::parse(&buf).unwrap();
+        assert!(msg.verify_integrity(&sha256));
+        assert!(!msg.verify_integrity(&md5));
+
+        let mismatch = MessageBuilder::new(Class::Request, Method::Allocate, TransId::new())
+            .message_integrity(sha256)
+            .build();
+        assert!(mismatch.is_err());
+    }
+
+    #[test]
+    fn negotiate_password_algorithm() {
+        use PasswordAlgorithm::*;
+        assert_eq!(PasswordAlgorithm::negotiate(&[Md5, Sha256]), Some(Sha256));
+        assert_eq!(PasswordAlgorithm::negotiate(&[Md5]), Some(Md5));
+        assert_eq!(PasswordAlgorithm::negotiate(&[Unknown(7)]), None);
+    }
+
+    #[test]
+    fn ice_message_round_trip() {
+        let trans_id = TransId::new();
+        // A username of a multiple of 4 needs no padding.
+        let msg = StunMessage::binding_request(
+            "abcd:efgh",
+            trans_id,
+            true,
+            7,
+            1234,
+            true,
+            Some(3),
+            Some(10),
+        );
+        let mut buf = [0; 200];
+        let n = msg.to_bytes("pass", &mut buf).unwrap();
+
+        let generic = Message::parse(&buf[..n]).unwrap();
+        assert!(generic.verify_integrity(&Credentials::ShortTerm { password: "pass" }));
+        assert!(generic.verify_fingerprint());
+        assert!(generic.attributes().contains(&Attribute::NetworkCost {
+            network_id: 0,
+            cost: 10
+        }));
+
+        let parsed = StunMessage::parse(&buf[..n]).unwrap();
+        assert_eq!(parsed.trans_id(), trans_id);
+        assert_eq!(parsed.split_username(), Some(("abcd", "efgh")));
+        assert_eq!(parsed.prio(), Some(1234));
+        assert!(parsed.use_candidate());
+        assert_eq!(parsed.nomination(), Some(3));
+        assert_eq!(parsed.network_cost(), Some(10));
+        assert!(parsed.check_integrity("pass"));
+        assert!(!parsed.check_integrity("nope"));
+
+        assert!(msg.to_bytes("pass", &mut buf[..n - 1]).is_err());
+    }
+
+    #[test]
+    fn error_response() {
+        let buf = MessageBuilder::new(Class::Failure, Method::Binding, TransId::new())
+            .attribute(Attribute::ErrorCode(401, "Unauthorized"))
+            .attribute(Attribute::UnknownAttributes(vec![0x0019, 0x8000]))
+            .attribute(Attribute::PasswordAlgorithms(vec![
+                PasswordAlgorithm::Sha256,
+                PasswordAlgorithm::Md5,
+            ]))
+            .build()
+            .unwrap();
+
+        let msg = Message::parse(&buf).unwrap();
+        assert_eq!(msg.class(), Class::Failure);
+        assert_eq!(msg.error_code(), Some((401, "Unauthorized")));
+        assert_eq!(
+            msg.attributes()[1],
+            Attribute::UnknownAttributes(vec![0x0019, 0x8000])
+        );
+        assert!(!msg.has_integrity());
+        assert!(!msg.verify_fingerprint());
+    }
+
+    #[test]
+    fn method_numbers() {
+        for m in [
+            Method::Binding,
+            Method::Allocate,
+            Method::ChannelBind,
+            Method::Unknown(0xabc),
+        ] {
+            assert_eq!(Method::from_typ(m.to_typ()), m);
+        }
+        assert_eq!(Method::Allocate.to_typ() | Class::Success.to_u16(), 0x0103);
+    }
+
+    #[test]
+    fn attributes_after_integrity_are_ignored() {
+        let mut buf = MessageBuilder::new(Class::Indication, Method::Send, TransId::new())
+            .message_integrity(Credentials::ShortTerm { password: "x" })
+            .build()
+            .unwrap();
+        buf.extend_from_slice(&[0x00, 0x06, 0x00, 0x04, b'a', b'b', b'c', b'd']);
+        buf[3] += 8;
+
+        let msg = Message::parse(&buf).unwrap();
+        assert_eq!(msg.username(), None);
+        assert!(msg.verify_integrity(&Credentials::ShortTerm { password: "x" }));
+    }
+
+    #[test]
+    fn reject_malformed() {
+        assert!(Message::parse(&[]).is_err());
+        assert!(Message::parse(&RESPONSE[..RESPONSE.len() - 4]).is_err());
+        let mut buf = RESPONSE.to_vec();
+        buf[4] = 0;
+        assert!(Message::parse(&buf).is_err());
+    }
 }
@@ -299,11 +299,11 @@
 //!
 //! # Crypto backends
 //!
-//! str0m has two crypto backends, `openssl` and `wincrypto`. The default is
-//! `openssl` which works on all platforms (also Windows). Ideally we want a
-//! pure rust version of the crypto code, but WebRTC currently requires
-//! DTLS 1.2 (not the latest version 1.3), and that leaves us only with a
-//! few possible options.
+//! str0m has three crypto backends, `openssl`, `wincrypto` and `rust-crypto`.
+//! The default is `openssl` which works on all platforms (also Windows).
+//! `rust-crypto` is a pure rust backend without any C dependencies. It
+//! contains its own DTLS 1.2 implementation limited to what WebRTC needs
+//! (ECDHE with ECDSA P-256 certificates and AES-128-GCM).
 //!
 //! When compiling for Windows, the `openssl` feature can be removed and
 //! only rely on `wincrypto`. However notice that `str0m` never picks up a
@@ -613,8 +613,10 @@ extern crate tracing;
 use bwe::{Bwe, BweKind};
 use change::{DirectApi, SdpApi};
 use rtp::RawPacket;
+use std::collections::VecDeque;
 use std::fmt;
 use std::net::SocketAddr;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use streams::RtpPacket;
 use streams::StreamPaused;
@@ -623,20 +625,39 @@ use util::InstantExt;
 
 mod crypto;
 use crypto::CryptoProvider;
-use crypto::Fingerprint;
+use crypto::SrtpProfile;
+use crypto::{Dtls13Mode, DtlsAlert};
+use crypto::{Fingerprint, FingerprintHash};
+use crypto::{KeyLog, SrtpKeyLog};
 
 mod dtls;
 use dtls::{Dtls, DtlsCert, DtlsCertOptions, DtlsEvent};
 
+mod sdes;
+use sdes::{Sdes, SdesKeys};
+
 #[path = "ice/mod.rs"]
 mod ice_;
 use ice_::IceAgent;
 use ice_::IceAgentEvent;
 pub use ice_::{Candidate, CandidateKind, IceConnectionState, IceCreds};
+use ice_::{IcePairSelection, PrioPairSelection};
 
 /// Additional configuration.
 pub mod config {
     pub use super::crypto::{CryptoProvider, DtlsCert, DtlsCertOptions, DtlsPKeyType, Fingerprint};
+    pub use super::crypto::{Dtls13Mode, DtlsAlert, FingerprintHash, SrtpProfile};
+    pub use super::crypto::{KeyLog, SrtpKeyLog};
+
+    /// Traits and types for supplying a custom crypto implementation.
+    ///
+    /// See [`CryptoProvider::Custom`].
+    pub mod crypto {
+        pub use crate::crypto::{aead_aes_128_gcm, aes_128_cm_sha1_80};
+        pub use crate::crypto::{aead_aes_256_gcm, aes_256_cm_sha1_80};
+        pub use crate::crypto::{CryptoBackend, DtlsBackend, DtlsCertBackend, SrtpCryptoBackend};
+        pub use crate::crypto::{CryptoError, DtlsEvent, KeyingMaterial, SrtpProfile};
+    }
 }
 
 /// Low level ICE access.
@@ -648,6 +669,8 @@ pub mod config {
 pub mod ice {
     pub use crate::ice_::IceCreds;
     pub use crate::ice_::{IceAgent, IceAgentEvent};
+    pub use crate::ice_::{IcePairInfo, IcePairSelection};
+    pub use crate::ice_::{NetworkCostPairSelection, NetworkType, PrioPairSelection};
     pub use crate::io::{StunMessage, StunPacket};
 }
 
@@ -701,7 +724,7 @@ pub mod rtp {
 pub mod bwe;
 
 mod sctp;
-use sctp::{RtcSctp, SctpEvent};
+use sctp::{RtcSctp, SctpClose, SctpEvent};
 
 mod sdp;
 
@@ -709,12 +732,13 @@ pub mod format;
 use format::CodecConfig;
 
 pub mod channel;
-use channel::{Channel, ChannelData, ChannelHandler, ChannelId};
+use channel::{Channel, ChannelChunk, ChannelData, ChannelHandler, ChannelId};
 
 pub mod media;
 use media::{Direction, Media, Mid, Pt, Rid, Writer};
 use media::{KeyframeRequest, KeyframeRequestKind};
-use media::{MediaAdded, MediaChanged, MediaData};
+use media::{MediaAdded, MediaChanged, MediaData, PayloadChanged};
+use media::{SFrame, SFrameConfig};
 
 pub mod change;
 
@@ -725,7 +749,11 @@ mod session;
 use session::Session;
 
 pub mod stats;
-use stats::{MediaEgressStats, MediaIngressStats, PeerStats, Stats, StatsEvent, StatsSnapshot};
+use stats::SctpStats;
+use stats::{CandidatePairStats, ChannelStats, MediaEgressStats, MediaIngressStats, PeerStats};
+use stats::{Stats, StatsEvent, StatsSnapshot};
+
+pub mod stun;
 
 mod streams;
 
@@ -736,9 +764,12 @@ pub mod net {
 
 /// Various error types.
 pub mod error {
+    pub use crate::change::DataTokenError;
+    pub use crate::crypto::CryptoError;
     pub use crate::dtls::DtlsError;
     pub use crate::ice_::IceError;
     pub use crate::io::NetError;
+    pub use crate::media::SFrameError;
     pub use crate::packet::PacketError;
     pub use crate::rtp_::RtpError;
     pub use crate::sctp::{ProtoError, SctpError};
@@ -747,6 +778,12 @@ pub mod error {
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// How long [`Rtc::close()`] waits for the SCTP shutdown to complete.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// SCTP error cause "User-Initiated Abort" (RFC 9260 3.3.10.12).
+const SCTP_USER_INITIATED_ABORT: u16 = 12;
+
 /// Errors for the whole Rtc engine.
 #[derive(Debug, Error)]
 #[non_exhaustive]
@@ -821,6 +858,14 @@ pub enum RtcError {
     #[error("{0}")]
     Sctp(#[from] error::SctpError),
 
+    /// SFrame (end-to-end encryption) errors.
+    #[error("{0}")]
+    SFrame(#[from] error::SFrameError),
+
+    /// The SDES-SRTP master key and salt has the wrong length for the profile.
+    #[error("SDES key for {0} must be {1} bytes")]
+    SdesKeyLength(SrtpProfile, usize),
+
     /// [`SdpApi`] was not done in a correct order.
     ///
     /// For [`SdpApi`]:
@@ -871,11 +916,12 @@ pub struct Rtc {
     alive: bool,
     ice: IceAgent,
     dtls: Dtls,
+    sdes: Sdes,
     sctp: RtcSctp,
     chan: ChannelHandler,
     stats: Option<Stats>,
     session: Session,
-    remote_fingerprint: Option<Fingerprint>,
+    remote_fingerprints: Vec<Fingerprint>,
     remote_addrs: Vec<SocketAddr>,
     send_addr: Option<SendAddr>,
     need_init_time: bool,
@@ -885,6 +931,20 @@ pub struct Rtc {
     change_counter: usize,
     last_timeout_reason: Reason,
     crypto_provider: CryptoProvider,
+    key_log: Option<VecDeque<KeyLog>>,
+    closing: Option<Closing>,
+    close_reason: Option<CloseReason>,
+    close_on_ice_failure: bool,
+}
+
+/// A graceful close started by [`Rtc::close()`].
+struct Closing {
+    /// Stop waiting for the SCTP shutdown at this time.
+    deadline: Instant,
+    /// Whether an SCTP shutdown was started.
+    sctp: bool,
+    /// Whether the DTLS close_notify is sent.
+    dtls_closed: bool,
 }
 
 struct SendAddr {
@@ -907,6 +967,11 @@ pub enum Event {
     /// connected to the peer or not.
     IceConnectionStateChange(IceConnectionState),
 
+    /// The session has ended, either by [`Rtc::close()`] or the remote peer.
+    ///
+    /// This is the last event. After it, [`Rtc::is_alive()`] is `false`.
+    Closed(CloseReason),
+
     // =================== Media related events ==================
 
     /// Upon adding new media to the session. The lines are emitted.
@@ -922,6 +987,11 @@ pub enum Event {
     ///. Currently only covers a change of direction.
     MediaChanged(MediaChanged),
 
+    /// The remote peer started sending a different payload type on a media.
+    ///
+    /// Emitted before the [`Event::MediaData`] with the new payload type.
+    PayloadChanged(PayloadChanged),
+
     // =================== Data channel related events ===================
 
     /// A data channel has opened.
@@ -941,9 +1011,31 @@ pub enum Event {
     /// Incoming data channel data from the remote peer.
     ChannelData(ChannelData),
 
+    /// A piece of incoming data channel data from the remote peer.
+    ///
+    /// See [`Channel::set_chunked_receive()`].
+    ChannelChunk(ChannelChunk),
+
+    /// The remote peer started closing a data channel.
+    ///
+    /// The channel is in [`ChannelState::Closing`][channel::ChannelState::Closing] and
+    /// [`Event::ChannelClose`] follows. A channel closed locally goes to closing without this event.
+    ChannelClosing(ChannelId),
+
     /// A data channel has been closed.
     ChannelClose(ChannelId),
 
+    /// The buffered amount of a data channel fell to, or below, its low threshold.
+    ///
+    /// See [`Channel::set_buffered_amount_low_threshold()`].
+    ChannelBufferedAmountLow(ChannelId),
+
+    /// A message accepted by a data channel write was lost before it was sent.
+    ///
+    /// Writes are checked up front, but messages wait in a queue for their turn, and the
+    /// channel or the association can close in the meantime. One event per lost message.
+    ChannelWriteFailed(ChannelId),
+
     // =================== Statistics and BWE related events ===================
 
     /// Statistics event for the Rtc instance
@@ -957,6 +1049,15 @@ pub enum Event {
     /// Aggregated statistics for each media (mid, rid) in the egress direction
     MediaEgressStats(MediaEgressStats),
 
+    /// Statistics for each ICE candidate pair.
+    CandidatePairStats(Box<CandidatePairStats>),
+
+    /// Statistics for each data channel.
+    ChannelStats(Box<ChannelStats>),
+
+    /// Statistics for the SCTP association carrying the data channels.
+    SctpStats(SctpStats),
+
     /// A new estimate from the bandwidth estimation subsystem.
     EgressBitrateEstimate(BweKind),
 
@@ -981,6 +1082,13 @@ pub enum Event {
     /// This clones data, and is therefore expensive.
     /// Should not be enabled outside of tests and troubleshooting.
     RawPacket(Box<RawPacket>),
+
+    /// Secrets for decrypting captured DTLS and SRTP traffic.
+    ///
+    /// Enable using [`RtcConfig::enable_key_log()`].
+    /// Anyone holding these can decrypt the session.
+    /// Should not be enabled outside of tests and troubleshooting.
+    KeyLog(KeyLog),
 }
 
 impl Event {
@@ -994,6 +1102,52 @@ impl Event {
     }
 }
 
+/// Why the session ended, as reported by [`Event::Closed`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum CloseReason {
+    /// We closed using [`Rtc::close()`] and everything has been sent.
+    Local,
+
+    /// The remote peer sent a DTLS `close_notify`, i.e. hung up.
+    DtlsCloseNotify,
+
+    /// The remote peer sent a fatal DTLS alert.
+    DtlsAlert(DtlsAlert),
+
+    /// The remote peer did a graceful SCTP shutdown of the data channels.
+    SctpShutdown,
+
+    /// The remote peer aborted the SCTP association, with the first error cause
+    /// code (RFC 9260 3.3.10), if any.
+    ///
+    /// Browsers closing the peer connection abort with cause 12, "User-Initiated Abort".
+    SctpAbort(Option<u16>),
+
+    /// ICE has [failed][IceConnectionState::Failed], i.e. the remote peer is unreachable.
+    ///
+    /// Only used with [`RtcConfig::set_close_on_ice_failure()`].
+    IceFailed,
+}
+
+impl CloseReason {
+    /// Whether the session ended because of an error rather than either side hanging up.
+    ///
+    /// ```
+    /// # use str0m::CloseReason;
+    /// assert!(!CloseReason::DtlsCloseNotify.is_failure());
+    /// assert!(!CloseReason::SctpAbort(Some(12)).is_failure());
+    /// assert!(CloseReason::IceFailed.is_failure());
+    /// ```
+    pub fn is_failure(&self) -> bool {
+        match self {
+            CloseReason::Local | CloseReason::DtlsCloseNotify | CloseReason::SctpShutdown => false,
+            CloseReason::SctpAbort(cause) => *cause != Some(SCTP_USER_INITIATED_ABORT),
+            CloseReason::DtlsAlert(_) | CloseReason::IceFailed => true,
+        }
+    }
+}
+
 /// Input as expected by [`Rtc::handle_input()`]. Either network data or a timeout.
 #[derive(Debug)]
 #[allow(clippy::large_enum_variant)] // We purposely don't want to allocate.
@@ -1132,13 +1286,15 @@ impl Rtc {
     }
 
     pub(crate) fn new_from_config(config: RtcConfig) -> Self {
-        let session = Session::new(&config);
+        let mut session = Session::new(&config);
 
         let local_creds = config.local_ice_credentials.unwrap_or_else(IceCreds::new);
         let mut ice = IceAgent::with_local_credentials(local_creds);
         if config.ice_lite {
             ice.set_ice_lite(config.ice_lite);
         }
+        ice.set_renomination(config.ice_renomination);
+        ice.set_pair_selection(config.ice_pair_selection);
 
         let dtls_cert = match config.dtls_cert_config {
             DtlsCertConfig::Options(options) => DtlsCert::new(config.crypto_provider, options),
@@ -1147,15 +1303,32 @@ impl Rtc {
 
         let crypto_provider = dtls_cert.crypto_provider();
 
+        session.sframe = config
+            .sframe
+            .map(|c| SFrame::new(c, crypto_provider.clone()));
+
+        let mut dtls = Dtls::new(dtls_cert).expect("DTLS to init without problem");
+        dtls.set_dtls13(config.dtls13)
+            .expect("DTLS 1.3 mode supported by crypto provider");
+        dtls.set_srtp_profiles(&config.srtp_profiles)
+            .expect("SRTP profiles to be set before handshake");
+        dtls.set_fingerprint_hashes(&config.fingerprint_hashes);
+        if config.enable_key_log {
+            dtls.set_key_log(true);
+        }
+
+        let sdes = Sdes::new(config.sdes_srtp, &config.srtp_profiles);
+
         Rtc {
             alive: true,
             ice,
-            dtls: Dtls::new(dtls_cert).expect("DTLS to init without problem"),
+            dtls,
+            sdes,
             session,
-            sctp: RtcSctp::new(),
+            sctp: RtcSctp::new(config.sctp_max_message_size),
             chan: ChannelHandler::default(),
             stats: config.stats_interval.map(Stats::new),
-            remote_fingerprint: None,
+            remote_fingerprints: vec![],
             remote_addrs: vec![],
             send_addr: None,
             need_init_time: true,
@@ -1165,6 +1338,10 @@ impl Rtc {
             change_counter: 0,
             last_timeout_reason: Reason::NotHappening,
             crypto_provider,
+            key_log: config.enable_key_log.then(VecDeque::new),
+            closing: None,
+            close_reason: None,
+            close_on_ice_failure: config.close_on_ice_failure,
         }
     }
 
@@ -1174,7 +1351,8 @@ impl Rtc {
     /// the DTLS fingerprint from the setup not matching that of the TLS negotiation
     /// (since that would potentially indicate a MITM attack!).
     ///
-    /// The instance can be manually disconnected using [`Rtc::disconnect()`].
+    /// The instance can be manually disconnected using [`Rtc::disconnect()`], or
+    /// gracefully using [`Rtc::close()`].
     ///
     /// ```
     /// # #[cfg(feature = "openssl")] {
@@ -1194,7 +1372,8 @@ impl Rtc {
     /// Force disconnects the instance making [`Rtc::is_alive()`] return `false`.
     ///
     /// This makes [`Rtc::poll_output`] and [`Rtc::handle_input`] go inert and not
-    /// produce anymore network output or events.
+    /// produce anymore network output or events. The remote peer is not told, see
+    /// [`Rtc::close()`] for that.
     ///
     /// ```
     /// # #[cfg(feature = "openssl")] {
@@ -1212,6 +1391,53 @@ impl Rtc {
         }
     }
 
+    /// Gracefully close the session, telling the remote peer.
+    ///
+    /// This sends RTCP BYE for the streams we send, does an SCTP shutdown of the data
+    /// channels and ends with a DTLS `close_notify`. Keep driving the instance as usual,
+    /// [`Rtc::poll_output()`] produces the transmits until everything is flushed, and
+    /// then [`Event::Closed`] with [`CloseReason::Local`]. After that, the instance is
+    /// disconnected as with [`Rtc::disconnect()`].
+    ///
+    /// The SCTP shutdown is given up after 3 seconds if the remote peer doesn't answer.
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl")] {
+    /// # use str0m::{Rtc, Output, Event, CloseReason};
+    /// let mut rtc = Rtc::new();
+    ///
+    /// rtc.close();
+    ///
+    /// // Nothing to flush, since we never connected.
+    /// let output = rtc.poll_output().unwrap();
+    /// assert!(matches!(output, Output::Event(Event::Closed(CloseReason::Local))));
+    /// assert!(!rtc.is_alive());
+    /// # }
+    /// ```
+    pub fn close(&mut self) {
+        if !self.alive || self.closing.is_some() || self.close_reason.is_some() {
+            return;
+        }
+        info!("Close");
+
+        self.session.send_bye(self.last_now);
+        let sctp = self.sctp.shutdown();
+
+        self.closing = Some(Closing {
+            deadline: self.last_now + CLOSE_TIMEOUT,
+            sctp,
+            dtls_closed: false,
+        });
+    }
+
+    /// Emit the pending [`Event::Closed`], which disconnects the instance.
+    fn poll_closed(&mut self) -> Option<Output> {
+        let reason = self.close_reason.take()?;
+        info!("Closed: {:?}", reason);
+        self.disconnect();
+        Some(Output::Event(Event::Closed(reason)))
+    }
+
     /// Add a local ICE candidate. Local candidates are socket addresses the `Rtc` instance
     /// use for communicating with the peer.
     ///
@@ -1264,12 +1490,128 @@ impl Rtc {
         self.ice.add_remote_candidate(c);
     }
 
+    /// Signal that there will be no more local ICE candidates.
+    ///
+    /// This is the local side of [Trickle Ice][1] end-of-candidates. Once called, further
+    /// calls to [`Rtc::add_local_candidate()`] are ignored until the next ICE restart.
+    ///
+    /// For [`SdpApi`]: Subsequent offers and answers include `a=end-of-candidates`. If the
+    /// candidates are trickled, the application is responsible for signalling end-of-candidates
+    /// to the remote peer.
+    ///
+    /// Once both local and remote end-of-candidates have been signalled, the ICE state
+    /// moves to [`IceConnectionState::Failed`] as soon as all candidate pairs have failed,
+    /// instead of waiting around for more candidates.
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl")] {
+    /// # use str0m::{Rtc, Candidate};
+    /// let mut rtc = Rtc::new();
+    ///
+    /// let a = "127.0.0.1:5000".parse().unwrap();
+    /// let c = Candidate::host(a, "udp").unwrap();
+    ///
+    /// rtc.add_local_candidate(c);
+    /// rtc.end_of_local_candidates();
+    /// # }
+    /// ```
+    ///
+    /// [1]: https://www.rfc-editor.org/rfc/rfc8838.txt
+    pub fn end_of_local_candidates(&mut self) {
+        self.ice.end_of_local_candidates();
+    }
+
+    /// Signal that the remote peer will not send any more ICE candidates.
+    ///
+    /// This is the remote side of [Trickle Ice][1] end-of-candidates, i.e. what the remote
+    /// peer signals with `a=end-of-candidates`. Once called, further calls to
+    /// [`Rtc::add_remote_candidate()`] are ignored until the next ICE restart.
+    ///
+    /// For [`SdpApi`]: This happens automatically if the remote [`SdpOffer`][change::SdpOffer]
+    /// or [`SdpAnswer`][change::SdpAnswer] contains `a=end-of-candidates`, or if the remote
+    /// doesn't support trickle ice (no `a=ice-options:trickle`).
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl")] {
+    /// # use str0m::{Rtc, Candidate};
+    /// let mut rtc = Rtc::new();
+    ///
+    /// let a = "1.2.3.4:5000".parse().unwrap();
+    /// let c = Candidate::host(a, "udp").unwrap();
+    ///
+    /// rtc.add_remote_candidate(c);
+    /// rtc.end_of_remote_candidates();
+    /// # }
+    /// ```
+    ///
+    /// [1]: https://www.rfc-editor.org/rfc/rfc8838.txt
+    pub fn end_of_remote_candidates(&mut self) {
+        self.ice.end_of_remote_candidates();
+    }
+
+    /// Statistics for all current ICE candidate pairs.
+    ///
+    /// The same stats are emitted as [`Event::CandidatePairStats`] when
+    /// [`RtcConfig::set_stats_interval()`] is enabled.
+    pub fn candidate_pair_stats(&self) -> Vec<CandidatePairStats> {
+        self.ice.candidate_pair_stats(self.last_now)
+    }
+
+    /// Force the use of a specific ICE candidate pair.
+    ///
+    /// The pair is identified by the local socket address to send from and the
+    /// remote address to send to. `None` goes back to using the pair selection
+    /// policy (see [`RtcConfig::set_ice_pair_selection()`]).
+    ///
+    /// This is only possible when we are the ICE controlling side. For the remote
+    /// peer to also switch to the pair, renomination must be negotiated
+    /// (see [`RtcConfig::set_ice_renomination()`]).
+    ///
+    /// Returns `false` if we are not controlling, or there is no such candidate pair.
+    pub fn set_forced_ice_pair(&mut self, pair: Option<(SocketAddr, SocketAddr)>) -> bool {
+        self.ice.set_forced_pair(pair)
+    }
+
     /// Checks if we are connected.
     ///
-    /// This tests both if we have ICE connection and DTLS is ready.
+    /// This tests both if we have ICE connection and DTLS is ready. With SDES-SRTP,
+    /// there is no DTLS and it's enough that the SRTP keys are set.
     ///
     pub fn is_connected(&self) -> bool {
-        self.ice.state().is_connected() && self.dtls.is_connected()
+        self.ice.state().is_connected() && (self.dtls.is_connected() || self.sdes.is_keyed())
+    }
+
+    /// Replace the DTLS certificate, i.e. to rotate keys in a long running session.
+    ///
+    /// Before DTLS has started, this takes effect straight away. After that, the new
+    /// fingerprint is sent in the next SDP offer or answer, and once that negotiation
+    /// completes, a new DTLS handshake using the certificate starts. An ICE restart is
+    /// a good occasion, but any renegotiation will do. Media continues using the old
+    /// SRTP keys until the new handshake is done.
+    ///
+    /// With [`Rtc::direct_api()`], exchange the fingerprints and use
+    /// [`DirectApi::restart_dtls()`].
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl")] {
+    /// # use str0m::Rtc;
+    /// # use str0m::config::{CryptoProvider, DtlsCert, DtlsCertOptions};
+    /// let mut rtc = Rtc::new();
+    ///
+    /// let cert = DtlsCert::new(CryptoProvider::OpenSsl, DtlsCertOptions::default());
+    /// rtc.set_dtls_cert(cert).unwrap();
+    ///
+    /// // Next offer or answer has the new fingerprint.
+    /// let mut changes = rtc.sdp_api();
+    /// changes.ice_restart(true);
+    /// # }
+    /// ```
+    pub fn set_dtls_cert(&mut self, cert: DtlsCert) -> Result<(), RtcError> {
+        self.dtls.set_cert(cert)?;
+        if !self.dtls.is_inited() {
+            self.crypto_provider = self.dtls.crypto_provider();
+        }
+        Ok(())
     }
 
     /// Make changes to the Rtc session via SDP.
@@ -1364,6 +1706,52 @@ impl Rtc {
         Ok(())
     }
 
+    /// Start a new DTLS association, i.e. to use a new certificate.
+    fn restart_dtls(&mut self) -> Result<(), RtcError> {
+        if !self.dtls.is_inited() {
+            return Ok(());
+        }
+
+        info!("DTLS restart");
+        self.dtls.restart()?;
+        self.crypto_provider = self.dtls.crypto_provider();
+
+        Ok(())
+    }
+
+    fn init_sdes(&mut self, keys: SdesKeys) -> Result<(), RtcError> {
+        // Packets the remote already sent with its key count toward the key lifetime.
+        let used = match self.sdes.keys() {
+            Some(k) if k.remote == keys.remote => self.session.srtp_rx_packets(),
+            _ => 0,
+        };
+
+        if keys.remote_lifetime.is_some_and(|l| used >= l) {
+            return Err(RtcError::RemoteSdp(
+                "a=crypto key lifetime exhausted".into(),
+            ));
+        }
+
+        if !self.sdes.set_keys(keys.clone()) {
+            return Ok(());
+        }
+
+        info!("SDES set SRTP keys and profile: {}", keys.profile);
+        let mat = keys.keying_material();
+        if let Some(key_log) = &mut self.key_log {
+            let v = SrtpKeyLog::new(keys.profile, &mat, true);
+            key_log.push_back(KeyLog::Srtp(v));
+        }
+        let srtp_crypto = self.crypto_provider.srtp_crypto();
+        self.session
+            .set_keying_material(mat, &srtp_crypto, keys.profile, true);
+        self.session.set_srtp_rx_mki(keys.remote_mki);
+        self.session
+            .set_srtp_rx_lifetime(keys.remote_lifetime, used);
+
+        Ok(())
+    }
+
     fn init_sctp(&mut self, client: bool) {
         // If we got an m=application line, ensure we have negotiated the
         // SCTP association with the other side.
@@ -1404,7 +1792,10 @@ impl Rtc {
 
         match &o {
             Output::Event(e) => match e {
-                Event::ChannelData(_) | Event::MediaData(_) | Event::RtpPacket(_) => {
+                Event::ChannelData(_)
+                | Event::ChannelChunk(_)
+                | Event::MediaData(_)
+                | Event::RtpPacket(_) => {
                     trace!("{:?}", e)
                 }
                 _ => debug!("{:?}", e),
@@ -1431,7 +1822,11 @@ impl Rtc {
                     //
                 }
                 IceAgentEvent::IceConnectionStateChange(v) => {
-                    return Ok(Output::Event(Event::IceConnectionStateChange(v)))
+                    if v.is_failed() && self.close_on_ice_failure && self.closing.is_none() {
+                        // Closed follows on the next poll.
+                        self.close_reason.get_or_insert(CloseReason::IceFailed);
+                    }
+                    return Ok(Output::Event(Event::IceConnectionStateChange(v)));
                 }
                 IceAgentEvent::DiscoveredRecv { proto, source } => {
                     info!("ICE remote address: {:?}/{:?}", source, proto);
@@ -1459,12 +1854,17 @@ impl Rtc {
         }
 
         let mut dtls_connected = false;
+        let mut remote_cert = None;
 
         while let Some(e) = self.dtls.poll_event() {
             match e {
                 DtlsEvent::Connected => {
-                    debug!("DTLS connected");
-                    dtls_connected = true;
+                    if self.dtls.is_restarted() {
+                        debug!("DTLS reconnected after restart");
+                    } else {
+                        debug!("DTLS connected");
+                        dtls_connected = true;
+                    }
                 }
                 DtlsEvent::SrtpKeyingMaterial(mat, srtp_profile) => {
                     info!(
@@ -1472,25 +1872,55 @@ impl Rtc {
                         srtp_profile
                     );
                     let active = self.dtls.is_active().expect("DTLS must be inited by now");
+                    if let Some(key_log) = &mut self.key_log {
+                        let v = SrtpKeyLog::new(srtp_profile, &mat, active);
+                        key_log.push_back(KeyLog::Srtp(v));
+                    }
                     let srtp_crypto = self.crypto_provider.srtp_crypto();
                     self.session
                         .set_keying_material(mat, &srtp_crypto, srtp_profile, active);
                 }
+                DtlsEvent::RemoteCertificate(v) => {
+                    remote_cert = Some(v);
+                }
                 DtlsEvent::RemoteFingerprint(v1) => {
                     debug!("DTLS verify remote fingerprint");
-                    if let Some(v2) = &self.remote_fingerprint {
-                        if v1 != *v2 {
-                            self.disconnect();
-                            return Err(RtcError::RemoteSdp("remote fingerprint no match".into()));
-                        }
-                    } else {
+                    if self.remote_fingerprints.is_empty() {
                         self.disconnect();
                         return Err(RtcError::RemoteSdp("no a=fingerprint before dtls".into()));
                     }
+                    // Any of the a=fingerprint lines will do (RFC 8122).
+                    let der = remote_cert.as_deref();
+                    if !self
+                        .remote_fingerprints
+                        .iter()
+                        .any(|v2| v2.matches(&v1, der))
+                    {
+                        self.disconnect();
+                        return Err(RtcError::RemoteSdp("remote fingerprint no match".into()));
+                    }
                 }
                 DtlsEvent::Data(v) => {
                     self.sctp.handle_input(self.last_now, &v);
                 }
+                DtlsEvent::KeyLog(line) => {
+                    if let Some(key_log) = &mut self.key_log {
+                        key_log.push_back(KeyLog::Dtls(line));
+                    }
+                }
+                DtlsEvent::Alert(alert) => {
+                    info!("DTLS alert from remote: {}", alert);
+                    // During our own close, this is the remote answering. A remote
+                    // SCTP shutdown that came first is reported by the SCTP instead.
+                    if self.closing.is_none() && !self.sctp.is_remote_closing() {
+                        let reason = if alert.is_close_notify() {
+                            CloseReason::DtlsCloseNotify
+                        } else {
+                            CloseReason::DtlsAlert(alert)
+                        };
+                        self.close_reason.get_or_insert(reason);
+                    }
+                }
             }
         }
 
@@ -1498,7 +1928,22 @@ impl Rtc {
             return Ok(Output::Event(Event::Connected));
         }
 
-        while let Some(e) = self.sctp.poll() {
+        if self.sdes.poll_connected(self.ice.state().is_connected()) {
+            debug!("SDES-SRTP connected");
+            return Ok(Output::Event(Event::Connected));
+        }
+
+        if let Some(v) = self.key_log.as_mut().and_then(|k| k.pop_front()) {
+            return Ok(Output::Event(Event::KeyLog(v)));
+        }
+
+        // The remote closed, don't send anything more over DTLS.
+        while let Some(e) = self
+            .close_reason
+            .is_none()
+            .then(|| self.sctp.poll())
+            .flatten()
+        {
             match e {
                 SctpEvent::Transmit { mut packets } => {
                     if let Some(v) = packets.front() {
@@ -1524,6 +1969,13 @@ impl Rtc {
                     let id = self.chan.channel_id_by_stream_id(id).unwrap();
                     return Ok(Output::Event(Event::ChannelOpen(id, label)));
                 }
+                SctpEvent::Closing { id } => {
+                    let Some(id) = self.chan.channel_id_by_stream_id(id) else {
+                        warn!("Drop ChannelClosing event for id: {:?}", id);
+                        continue;
+                    };
+                    return Ok(Output::Event(Event::ChannelClosing(id)));
+                }
                 SctpEvent::Close { id } => {
                     let Some(id) = self.chan.channel_id_by_stream_id(id) else {
                         warn!("Drop ChannelClose event for id: {:?}", id);
@@ -1532,6 +1984,18 @@ impl Rtc {
                     self.chan.remove_channel(id);
                     return Ok(Output::Event(Event::ChannelClose(id)));
                 }
+                SctpEvent::BufferedAmountLow { id } => {
+                    let Some(id) = self.chan.channel_id_by_stream_id(id) else {
+                        continue;
+                    };
+                    return Ok(Output::Event(Event::ChannelBufferedAmountLow(id)));
+                }
+                SctpEvent::WriteFailed { id } => {
+                    let Some(id) = self.chan.channel_id_by_stream_id(id) else {
+                        continue;
+                    };
+                    return Ok(Output::Event(Event::ChannelWriteFailed(id)));
+                }
                 SctpEvent::Data { id, binary, data } => {
                     let Some(id) = self.chan.channel_id_by_stream_id(id) else {
                         warn!("Drop ChannelData event for id: {:?}", id);
@@ -1540,6 +2004,40 @@ impl Rtc {
                     let cd = ChannelData { id, binary, data };
                     return Ok(Output::Event(Event::ChannelData(cd)));
                 }
+                SctpEvent::Chunk {
+                    id,
+                    binary,
+                    start,
+                    end,
+                    abandoned,
+                    data,
+                } => {
+                    let Some(id) = self.chan.channel_id_by_stream_id(id) else {
+                        warn!("Drop ChannelChunk event for id: {:?}", id);
+                        continue;
+                    };
+                    let cc = ChannelChunk {
+                        id,
+                        binary,
+                        start,
+                        end,
+                        abandoned,
+                        data,
+                    };
+                    return Ok(Output::Event(Event::ChannelChunk(cc)));
+                }
+                SctpEvent::Closed { reason } => {
+                    if self.closing.is_some() {
+                        // Our own shutdown, the close continues with DTLS.
+                        continue;
+                    }
+                    let reason = match reason {
+                        SctpClose::Local => continue,
+                        SctpClose::Shutdown => CloseReason::SctpShutdown,
+                        SctpClose::Abort(cause) => CloseReason::SctpAbort(cause),
+                    };
+                    self.close_reason.get_or_insert(reason);
+                }
             }
         }
 
@@ -1557,9 +2055,17 @@ impl Rtc {
                 StatsEvent::Peer(s) => Output::Event(Event::PeerStats(s)),
                 StatsEvent::MediaIngress(s) => Output::Event(Event::MediaIngressStats(s)),
                 StatsEvent::MediaEgress(s) => Output::Event(Event::MediaEgressStats(s)),
+                StatsEvent::CandidatePair(s) => Output::Event(Event::CandidatePairStats(s)),
+                StatsEvent::Channel(s) => Output::Event(Event::ChannelStats(s)),
+                StatsEvent::Sctp(s) => Output::Event(Event::SctpStats(s)),
             });
         }
 
+        // After the remaining events, nothing more is sent.
+        if let Some(o) = self.poll_closed() {
+            return Ok(o);
+        }
+
         if let Some(v) = self.ice.poll_transmit() {
             return Ok(Output::Transmit(v));
         }
@@ -1571,6 +2077,13 @@ impl Rtc {
                 .or_else(|| self.session.poll_datagram(self.last_now));
 
             if let Some(contents) = datagram {
+                self.ice.record_transmit(
+                    self.last_now,
+                    send.source,
+                    send.destination,
+                    contents.len(),
+                );
+
                 let t = net::Transmit {
                     proto: send.proto,
                     source: send.source,
@@ -1581,6 +2094,25 @@ impl Rtc {
             }
         }
 
+        if let Some(closing) = &mut self.closing {
+            // Everything is sent at this point. The DTLS close_notify goes last,
+            // since the SCTP shutdown is sent over DTLS.
+            let sctp_done =
+                !closing.sctp || self.sctp.is_closed() || self.last_now >= closing.deadline;
+
+            if sctp_done {
+                if !closing.dtls_closed {
+                    closing.dtls_closed = true;
+                    self.dtls.close();
+                    return self.do_poll_output();
+                }
+
+                self.closing = None;
+                self.close_reason = Some(CloseReason::Local);
+                return self.do_poll_output();
+            }
+        }
+
         let stats = self.stats.as_mut();
 
         let time_and_reason = (None, Reason::NotHappening)
@@ -1588,6 +2120,7 @@ impl Rtc {
             .soonest((self.ice.poll_timeout(), Reason::Ice))
             .soonest(self.session.poll_timeout())
             .soonest((self.sctp.poll_timeout(), Reason::Sctp))
+            .soonest((self.closing.as_ref().map(|c| c.deadline), Reason::Sctp))
             .soonest((self.chan.poll_timeout(&self.sctp), Reason::Channel))
             .soonest((stats.and_then(|s| s.poll_timeout()), Reason::Stats));
 
@@ -1749,6 +2282,7 @@ impl Rtc {
 
         self.last_now = now;
         self.ice.handle_timeout(now);
+        self.dtls.handle_timeout(now);
         self.sctp.handle_timeout(now);
         self.chan.handle_timeout(now, &mut self.sctp);
         self.session.handle_timeout(now)?;
@@ -1758,6 +2292,11 @@ impl Rtc {
                 let mut snapshot = StatsSnapshot::new(now);
                 snapshot.peer_rx = self.peer_bytes_rx;
                 snapshot.peer_tx = self.peer_bytes_tx;
+                snapshot.selected_pair_rtt = self.ice.selected_pair_rtt();
+                snapshot.srtp_profile = self.session.srtp_profile();
+                snapshot.candidate_pairs = self.ice.candidate_pair_stats(now);
+                snapshot.sctp = self.sctp.stats(now);
+                self.chan.visit_stats(&mut self.sctp, &mut snapshot);
                 self.session.visit_stats(now, &mut snapshot);
                 stats.do_handle_timeout(&mut snapshot);
             }
@@ -1781,6 +2320,11 @@ impl Rtc {
 
         self.peer_bytes_rx += bytes_rx as u64;
 
+        if bytes_rx > 0 {
+            self.ice
+                .record_receive(now, r.source, r.destination, bytes_rx);
+        }
+
         match r.contents.inner {
             Stun(stun) => {
                 let packet = io::StunPacket {
@@ -1802,7 +2346,8 @@ impl Rtc {
     /// Obtain handle for writing to a data channel.
     ///
     /// This is first available when a [`ChannelId`] is advertised via [`Event::ChannelOpen`].
-    /// The function returns `None` also for IDs from [`SdpApi::add_channel()`].
+    /// The function returns `None` also for IDs from [`SdpApi::add_channel()`]. The handle
+    /// remains available while the channel is closing, and `None` once it is closed.
     ///
     /// Incoming channel data is via the [`Event::ChannelData`] event.
     ///
@@ -1821,7 +2366,7 @@ impl Rtc {
 
         let sctp_stream_id = self.chan.stream_id_by_channel_id(id)?;
 
-        if !self.sctp.is_open(sctp_stream_id) {
+        if !self.sctp.is_open_or_closing(sctp_stream_id) {
             return None;
         }
 
@@ -1835,6 +2380,13 @@ impl Rtc {
         Bwe(self)
     }
 
+    /// Manage the keys for SFrame end-to-end encryption.
+    ///
+    /// Only available if SFrame was enabled in [`RtcConfig::set_sframe()`].
+    pub fn sframe(&mut self) -> Option<&mut SFrame> {
+        self.session.sframe.as_mut()
+    }
+
     fn is_correct_change_id(&self, change_id: usize) -> bool {
         self.change_counter == change_id + 1
     }
@@ -1891,8 +2443,17 @@ pub struct RtcConfig {
     local_ice_credentials: Option<IceCreds>,
     crypto_provider: CryptoProvider,
     dtls_cert_config: DtlsCertConfig,
+    dtls13: Dtls13Mode,
+    srtp_profiles: Vec<SrtpProfile>,
+    fingerprint_hashes: Vec<FingerprintHash>,
+    cryptex: bool,
+    sframe: Option<SFrameConfig>,
+    sdes_srtp: bool,
     fingerprint_verification: bool,
     ice_lite: bool,
+    ice_renomination: bool,
+    close_on_ice_failure: bool,
+    ice_pair_selection: Arc<dyn IcePairSelection>,
     codec_config: CodecConfig,
     exts: ExtensionMap,
     stats_interval: Option<Duration>,
@@ -1903,7 +2464,9 @@ pub struct RtcConfig {
     send_buffer_audio: usize,
     send_buffer_video: usize,
     rtp_mode: bool,
+    sctp_max_message_size: usize,
     enable_raw_packets: bool,
+    enable_key_log: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -1955,7 +2518,7 @@ impl RtcConfig {
     /// Defaults to what's set in [`CryptoProvider::install_process_default()`] followed
     /// by a fallback to [`CryptoProvider::OpenSsl`].
     pub fn crypto_provider(&self) -> CryptoProvider {
-        self.crypto_provider
+        self.crypto_provider.clone()
     }
 
     /// Returns the configured DTLS certificate configuration.
@@ -2003,6 +2566,179 @@ impl RtcConfig {
         self
     }
 
+    /// Whether DTLS 1.3 is negotiated.
+    ///
+    /// Defaults to [`Dtls13Mode::Prefer`], which uses DTLS 1.3 when both the crypto provider
+    /// and the remote peer support it and otherwise falls back to DTLS 1.2.
+    pub fn dtls13(&self) -> Dtls13Mode {
+        self.dtls13
+    }
+
+    /// Require, prefer or disable DTLS 1.3 (RFC 9147).
+    ///
+    /// DTLS 1.3 is only implemented by [`CryptoProvider::RustCrypto`]. Building an [`Rtc`]
+    /// with [`Dtls13Mode::Require`] for any other provider panics.
+    ///
+    /// ```
+    /// # use str0m::RtcConfig;
+    /// # use str0m::config::Dtls13Mode;
+    /// let rtc_config = RtcConfig::default()
+    ///     .set_dtls13(Dtls13Mode::Disabled);
+    /// ```
+    pub fn set_dtls13(mut self, mode: Dtls13Mode) -> Self {
+        self.dtls13 = mode;
+        self
+    }
+
+    /// The SRTP protection profiles offered via DTLS, most preferred first.
+    ///
+    /// Defaults to [`SrtpProfile::AeadAes128Gcm`] and [`SrtpProfile::Aes128CmSha1_80`].
+    pub fn srtp_profiles(&self) -> &[SrtpProfile] {
+        &self.srtp_profiles
+    }
+
+    /// Set the SRTP protection profiles to offer in the DTLS `use_srtp` extension
+    /// (RFC 5764), most preferred first.
+    ///
+    /// The DTLS server picks the profile, so the order only decides when we are the
+    /// server. The negotiated profile is reported in [`PeerStats::srtp_profile`].
+    ///
+    /// [`SrtpProfile::Aes256CmSha1_80`] has no DTLS-SRTP id and is skipped. The WinCrypto
+    /// provider ignores this setting and always offers its built in list.
+    ///
+    /// Panics if none of the profiles can be negotiated via DTLS.
+    ///
+    /// ```
+    /// # use str0m::RtcConfig;
+    /// # use str0m::config::SrtpProfile;
+    /// let rtc_config = RtcConfig::default()
+    ///     .set_srtp_profiles(&[SrtpProfile::AeadAes256Gcm, SrtpProfile::AeadAes128Gcm]);
+    /// ```
+    pub fn set_srtp_profiles(mut self, profiles: &[SrtpProfile]) -> Self {
+        assert!(
+            profiles.iter().any(|p| p.dtls_id().is_some()),
+            "At least one SRTP profile must be negotiable via DTLS"
+        );
+        self.srtp_profiles = profiles.to_vec();
+        self
+    }
+
+    /// The hash functions for our `a=fingerprint` lines in SDP.
+    ///
+    /// Defaults to [`FingerprintHash::Sha256`].
+    ///
+    /// ```
+    /// # use str0m::Rtc;
+    /// # use str0m::config::FingerprintHash;
+    /// let config = Rtc::builder();
+    ///
+    /// assert_eq!(config.fingerprint_hashes(), &[FingerprintHash::Sha256]);
+    /// ```
+    pub fn fingerprint_hashes(&self) -> &[FingerprintHash] {
+        &self.fingerprint_hashes
+    }
+
+    /// Set the hash functions for our `a=fingerprint` lines in SDP, one line per hash function.
+    ///
+    /// The remote certificate is accepted if it matches any of the remote's `a=fingerprint`
+    /// lines, regardless of this setting. Hash functions other than `sha-256` need a crypto
+    /// provider that exposes the certificate, which WinCrypto does not.
+    ///
+    /// Panics if `hashes` is empty.
+    ///
+    /// ```
+    /// # use str0m::RtcConfig;
+    /// # use str0m::config::FingerprintHash;
+    /// let rtc_config = RtcConfig::default()
+    ///     .set_fingerprint_hashes(&[FingerprintHash::Sha384, FingerprintHash::Sha256]);
+    /// ```
+    pub fn set_fingerprint_hashes(mut self, hashes: &[FingerprintHash]) -> Self {
+        assert!(!hashes.is_empty(), "At least one fingerprint hash function");
+        self.fingerprint_hashes = hashes.to_vec();
+        self
+    }
+
+    /// Toggle Cryptex (RFC 9335), which encrypts RTP header extensions and CSRCs.
+    ///
+    /// Without Cryptex, header extensions such as audio level, mid and rid are sent in the
+    /// clear. When enabled, `a=cryptex` is added to offers, and to answers where the remote
+    /// offered it. Cryptex is only used when both sides signal it.
+    ///
+    /// Defaults to false.
+    pub fn set_cryptex(mut self, enabled: bool) -> Self {
+        self.cryptex = enabled;
+        self
+    }
+
+    /// Tells whether Cryptex is enabled.
+    ///
+    /// ```
+    /// # use str0m::Rtc;
+    /// let config = Rtc::builder();
+    ///
+    /// // Defaults to false.
+    /// assert_eq!(config.cryptex(), false);
+    /// ```
+    pub fn cryptex(&self) -> bool {
+        self.cryptex
+    }
+
+    /// Enable SFrame (RFC 9605) end-to-end encryption of media frames.
+    ///
+    /// Frames written with [`Writer::write()`] are encrypted before packetization and
+    /// incoming [`MediaData`] is decrypted. The keys are managed using [`Rtc::sframe()`].
+    /// SFrame is not signaled in the SDP; all parties must agree on it out of band.
+    ///
+    /// Defaults to None.
+    pub fn set_sframe(mut self, config: Option<SFrameConfig>) -> Self {
+        self.sframe = config;
+        self
+    }
+
+    /// The SFrame configuration, if enabled.
+    ///
+    /// ```
+    /// # use str0m::Rtc;
+    /// let config = Rtc::builder();
+    ///
+    /// // Defaults to None.
+    /// assert_eq!(config.sframe(), None);
+    /// ```
+    pub fn sframe(&self) -> Option<SFrameConfig> {
+        self.sframe
+    }
+
+    /// Use SDES-SRTP (RFC 4568) keying instead of DTLS when negotiating via SDP.
+    ///
+    /// This is for interop with peers such as SIP gateways that don't do DTLS.
+    /// Offers carry `a=crypto` lines with our keys for each of the
+    /// [`RtcConfig::srtp_profiles()`] instead of a DTLS fingerprint. Remote offers
+    /// without a fingerprint are answered using the first supported `a=crypto` line.
+    ///
+    /// The SRTP keys are sent in the clear in the SDP, so the signaling must be secure.
+    /// Data channels need DTLS and are not available. Incoming packets are
+    /// dropped once the remote key lifetime is reached, and a new offer or answer
+    /// changing the remote key or SSRCs gets a fresh local key.
+    ///
+    /// Defaults to false.
+    pub fn set_sdes_srtp(mut self, enabled: bool) -> Self {
+        self.sdes_srtp = enabled;
+        self
+    }
+
+    /// Tells whether SDES-SRTP keying is enabled.
+    ///
+    /// ```
+    /// # use str0m::Rtc;
+    /// let config = Rtc::builder();
+    ///
+    /// // Defaults to false.
+    /// assert_eq!(config.sdes_srtp(), false);
+    /// ```
+    pub fn sdes_srtp(&self) -> bool {
+        self.sdes_srtp
+    }
+
     /// Toggle ice lite. Ice lite is a mode for WebRTC servers with public IP address.
     /// An [`Rtc`] instance in ice lite mode will not make STUN binding requests, but only
     /// answer to requests from the remote peer.
@@ -2051,6 +2787,79 @@ impl RtcConfig {
         self.ice_lite
     }
 
+    /// Toggle ICE renomination (`a=ice-options:renomination`).
+    ///
+    /// With renomination, the ICE controlling side can switch candidate pair at any
+    /// time and the controlled side follows. This is used when the remote peer also
+    /// signals support for it.
+    ///
+    /// Defaults to false.
+    pub fn set_ice_renomination(mut self, enabled: bool) -> Self {
+        self.ice_renomination = enabled;
+        self
+    }
+
+    /// Tells whether ICE renomination is enabled.
+    ///
+    /// ```
+    /// # use str0m::Rtc;
+    /// let config = Rtc::builder();
+    ///
+    /// // Defaults to false.
+    /// assert_eq!(config.ice_renomination(), false);
+    /// ```
+    pub fn ice_renomination(&self) -> bool {
+        self.ice_renomination
+    }
+
+    /// Toggle closing the [`Rtc`] when ICE fails.
+    ///
+    /// When enabled, [`IceConnectionState::Failed`] is followed by
+    /// [`Event::Closed`] with [`CloseReason::IceFailed`]. When disabled, the instance
+    /// stays alive and can recover, for instance with an ICE restart.
+    ///
+    /// Defaults to false.
+    pub fn set_close_on_ice_failure(mut self, enabled: bool) -> Self {
+        self.close_on_ice_failure = enabled;
+        self
+    }
+
+    /// Tells whether the [`Rtc`] closes when ICE fails.
+    ///
+    /// ```
+    /// # use str0m::Rtc;
+    /// let config = Rtc::builder();
+    ///
+    /// // Defaults to false.
+    /// assert_eq!(config.close_on_ice_failure(), false);
+    /// ```
+    pub fn close_on_ice_failure(&self) -> bool {
+        self.close_on_ice_failure
+    }
+
+    /// Set the policy for selecting which ICE candidate pair to use.
+    ///
+    /// The policy is used when we are the ICE controlling side.
+    ///
+    /// ```
+    /// # use std::sync::Arc;
+    /// # use str0m::RtcConfig;
+    /// # use str0m::ice::NetworkCostPairSelection;
+    /// let config = RtcConfig::new()
+    ///     .set_ice_pair_selection(Arc::new(NetworkCostPairSelection::default()));
+    /// ```
+    ///
+    /// Defaults to [`PrioPairSelection`].
+    pub fn set_ice_pair_selection(mut self, pair_selection: Arc<dyn IcePairSelection>) -> Self {
+        self.ice_pair_selection = pair_selection;
+        self
+    }
+
+    /// The policy for selecting which ICE candidate pair to use.
+    pub fn ice_pair_selection(&self) -> &Arc<dyn IcePairSelection> {
+        &self.ice_pair_selection
+    }
+
     /// Lower level access to precise configuration of codecs (payload types).
     pub fn codec_config(&mut self) -> &mut CodecConfig {
         &mut self.codec_config
@@ -2163,7 +2972,8 @@ impl RtcConfig {
     ///
     /// None turns off the stats events.
     ///
-    /// This includes [`MediaEgressStats`], [`MediaIngressStats`], [`MediaEgressStats`]
+    /// This includes [`MediaEgressStats`], [`MediaIngressStats`], [`MediaEgressStats`],
+    /// [`CandidatePairStats`], [`ChannelStats`] and [`SctpStats`]
     pub fn set_stats_interval(mut self, interval: Option<Duration>) -> Self {
         self.stats_interval = interval;
         self
@@ -2380,6 +3190,34 @@ impl RtcConfig {
         self.rtp_mode
     }
 
+    /// Set the largest data channel message we accept from the remote peer.
+    ///
+    /// This is advertised as `a=max-message-size` in the SDP. Incoming messages
+    /// over the limit are dropped.
+    ///
+    /// Large messages hold up the messages of other channels written after them, unless
+    /// the remote peer supports SCTP message interleaving (I-DATA, RFC 8260).
+    ///
+    /// panics if set to 0.
+    pub fn set_sctp_max_message_size(mut self, size: usize) -> Self {
+        assert!(size > 0);
+        self.sctp_max_message_size = size;
+        self
+    }
+
+    /// Returns the largest data channel message we accept from the remote peer.
+    ///
+    /// ```
+    /// # use str0m::Rtc;
+    /// let config = Rtc::builder();
+    ///
+    /// // Defaults to 256kB.
+    /// assert_eq!(config.sctp_max_message_size(), 262_144);
+    /// ```
+    pub fn sctp_max_message_size(&self) -> usize {
+        self.sctp_max_message_size
+    }
+
     /// Enable the [`Event::RawPacket`] event.
     ///
     /// This clones data, and is therefore expensive.
@@ -2389,6 +3227,21 @@ impl RtcConfig {
         self
     }
 
+    /// Enable the [`Event::KeyLog`] event.
+    ///
+    /// Exposes the DTLS secrets in `SSLKEYLOGFILE` format and the SRTP master keys
+    /// and salts, which makes it possible to decrypt captured traffic. Not all crypto
+    /// providers support the DTLS part.
+    ///
+    /// This defeats the encryption of the session.
+    /// Should not be enabled outside of tests and troubleshooting.
+    ///
+    /// Defaults to false.
+    pub fn enable_key_log(mut self, enabled: bool) -> Self {
+        self.enable_key_log = enabled;
+        self
+    }
+
     /// Create a [`Rtc`] from the configuration.
     pub fn build(self) -> Rtc {
         Rtc::new_from_config(self)
@@ -2410,8 +3263,17 @@ impl Default for RtcConfig {
             local_ice_credentials: None,
             crypto_provider: CryptoProvider::process_default().unwrap_or(CryptoProvider::OpenSsl),
             dtls_cert_config: Default::default(),
+            dtls13: Dtls13Mode::default(),
+            srtp_profiles: SrtpProfile::DEFAULT.to_vec(),
+            fingerprint_hashes: vec![FingerprintHash::Sha256],
+            cryptex: false,
+            sframe: None,
+            sdes_srtp: false,
             fingerprint_verification: true,
             ice_lite: false,
+            ice_renomination: false,
+            close_on_ice_failure: false,
+            ice_pair_selection: Arc::new(PrioPairSelection),
             codec_config: CodecConfig::new_with_defaults(),
             exts: ExtensionMap::standard(),
             stats_interval: None,
@@ -2421,7 +3283,9 @@ impl Default for RtcConfig {
             send_buffer_audio: 50,
             send_buffer_video: 1000,
             rtp_mode: false,
+            sctp_max_message_size: sctp::DEFAULT_LOCAL_MAX_MESSAGE_SIZE,
             enable_raw_packets: false,
+            enable_key_log: false,
         }
     }
 }
@@ -2432,9 +3296,14 @@ impl PartialEq for Event {
             (Self::IceConnectionStateChange(l0), Self::IceConnectionStateChange(r0)) => l0 == r0,
             (Self::MediaAdded(m0), Self::MediaAdded(m1)) => m0 == m1,
             (Self::MediaData(m1), Self::MediaData(m2)) => m1 == m2,
+            (Self::PayloadChanged(p1), Self::PayloadChanged(p2)) => p1 == p2,
             (Self::ChannelOpen(l0, l1), Self::ChannelOpen(r0, r1)) => l0 == r0 && l1 == r1,
             (Self::ChannelData(l0), Self::ChannelData(r0)) => l0 == r0,
+            (Self::ChannelChunk(l0), Self::ChannelChunk(r0)) => l0 == r0,
+            (Self::ChannelClosing(l0), Self::ChannelClosing(r0)) => l0 == r0,
             (Self::ChannelClose(l0), Self::ChannelClose(r0)) => l0 == r0,
+            (Self::ChannelBufferedAmountLow(l0), Self::ChannelBufferedAmountLow(r0)) => l0 == r0,
+            (Self::ChannelWriteFailed(l0), Self::ChannelWriteFailed(r0)) => l0 == r0,
             _ => false,
         }
     }
@@ -54,6 +54,26 @@ pub struct MediaChanged {
     pub direction: Direction,
 }
 
+/// The remote peer started sending a different payload type (PT).
+///
+/// This event fires for the first incoming PT of a media (and rid), and every time
+/// the remote switches to another PT. It is emitted right before the first [`MediaData`]
+/// with the new PT, which means decoders can be swapped in time.
+#[derive(Debug, PartialEq, Eq)]
+pub struct PayloadChanged {
+    /// Identifier of the media.
+    pub mid: Mid,
+
+    /// Rid, if the media uses simulcast.
+    pub rid: Option<Rid>,
+
+    /// The new payload type.
+    pub pt: Pt,
+
+    /// Codec specification of the new payload type.
+    pub params: PayloadParams,
+}
+
 /// Simplified information about the simulcast config from SDP.
 ///
 /// The [full spec][1] covers many cases that are not used by simple simulcast.
@@ -6,6 +6,7 @@ use std::time::Instant;
 use crate::change::AddMedia;
 use crate::format::CodecConfig;
 use crate::io::{Id, DATAGRAM_MTU};
+use crate::packet::{CodecDepacketizer, GenericDepacketizer};
 use crate::packet::{DepacketizingBuffer, Payloader, RtpMeta};
 use crate::rtp_::ExtensionMap;
 use crate::rtp_::MidRid;
@@ -25,6 +26,9 @@ pub use event::*;
 mod writer;
 pub use writer::Writer;
 
+mod sframe;
+pub use sframe::{SFrame, SFrameCipherSuite, SFrameConfig, SFrameError};
+
 pub use crate::packet::MediaKind;
 pub use crate::rtp_::{Direction, ExtensionValues, Frequency, MediaTime, Mid, Pt, Rid};
 
@@ -88,6 +92,16 @@ pub struct Media {
     /// SDP property.
     remote_pts: Vec<Pt>,
 
+    /// Local codec preferences for this media, set via [`SdpApi::set_codec_preferences()`].
+    ///
+    /// When not empty, this narrows and orders the negotiated PTs. Otherwise the
+    /// order of Session::codec_config is used.
+    ///
+    /// SDP property.
+    ///
+    /// [`SdpApi::set_codec_preferences()`]: crate::change::SdpApi::set_codec_preferences
+    codec_prefs: Vec<Pt>,
+
     /// Remote extmaps negotiated for this media.
     ///
     /// The corresponding entries must exist in Session::codec_config.
@@ -115,6 +129,12 @@ pub struct Media {
     /// Samples to payload. Should typically only be 0 or 1.
     to_payload: VecDeque<ToPayload>,
 
+    /// The PT of the last sample handed out per RID.
+    rx_pts: HashMap<Option<Rid>, Pt>,
+
+    /// A sample held back while the [`PayloadChanged`] before it is emitted.
+    pub(crate) pending_sample: Option<MediaData>,
+
     pub(crate) need_open_event: bool,
     pub(crate) need_changed_event: bool,
 
@@ -307,6 +327,7 @@ impl Media {
         reordering_size_audio: usize,
         reordering_size_video: usize,
         params: &[PayloadParams],
+        sframe: bool,
     ) {
         if !self.dir.is_receiving() {
             return;
@@ -332,7 +353,14 @@ impl Media {
                 reordering_size_video
             };
 
-            let buffer = DepacketizingBuffer::new(codec.into(), hold_back);
+            // SFrame encrypted video is opaque and uses the generic packetization.
+            let depack = if sframe && self.kind.is_video() {
+                CodecDepacketizer::Generic(GenericDepacketizer)
+            } else {
+                codec.into()
+            };
+
+            let buffer = DepacketizingBuffer::new(depack, hold_back);
 
             self.depayloaders.insert((pt, rid), buffer);
         }
@@ -351,6 +379,23 @@ impl Media {
         buffer.push(meta, packet.payload);
     }
 
+    /// Tells whether the sample about to be handed out has another PT than the previous
+    /// one for the same RID.
+    pub(crate) fn detect_payload_change(&mut self, data: &MediaData) -> Option<PayloadChanged> {
+        let last = self.rx_pts.insert(data.rid, data.pt);
+
+        if last == Some(data.pt) {
+            return None;
+        }
+
+        Some(PayloadChanged {
+            mid: data.mid,
+            rid: data.rid,
+            pt: data.pt,
+            params: data.params,
+        })
+    }
+
     pub(crate) fn set_cname(&mut self, cname: String) {
         self.cname = cname;
     }
@@ -374,11 +419,13 @@ impl Media {
         pt: Pt,
         rid: Option<Rid>,
         params: &[PayloadParams],
+        sframe: bool,
     ) -> &mut Payloader {
+        let generic = sframe && self.kind.is_video();
         self.payloaders.entry((pt, rid)).or_insert_with(|| {
             // Unwrap is OK, the pt should be checked already when calling this function.
             let params = params.iter().find(|p| p.pt == pt).unwrap();
-            Payloader::new(params.spec)
+            Payloader::new(params.spec, generic)
         })
     }
 
@@ -404,6 +451,7 @@ impl Media {
         &mut self,
         streams: &mut Streams,
         params: &[PayloadParams],
+        sframe: bool,
     ) -> Result<(), RtcError> {
         let Some(to_payload) = self.to_payload.pop_front() else {
             return Ok(());
@@ -423,7 +471,7 @@ impl Media {
 
         let pt = *pt;
 
-        let payloader = self.payloader_for(pt, *rid, params);
+        let payloader = self.payloader_for(pt, *rid, params, sframe);
 
         const RTP_SIZE: usize = DATAGRAM_MTU - SRTP_OVERHEAD;
         // align to SRTP block size to minimize padding needs
@@ -439,15 +487,57 @@ impl Media {
     pub(crate) fn set_remote_pts(&mut self, pts: Vec<Pt>) {
         // Have we already set PTs?
         if !self.remote_pts.is_empty() {
+            // A re-negotiation can narrow and reorder the PTs, like when codec preferences
+            // are set, but not add PTs that weren't there from the start.
+            let pts: Vec<Pt> = pts
+                .into_iter()
+                .filter(|pt| self.remote_pts.contains(pt))
+                .collect();
+
+            if !pts.is_empty() && pts != self.remote_pts {
+                info!("Mid ({}) remote PT order changed to: {:?}", self.mid, pts);
+                self.remote_pts = pts;
+            }
             return;
         }
 
-        // TODO: We should verify the remote peer doesn't suddenly change the PT
-        // order or removes/adds PTs that weren't there from the start.
         info!("Mid ({}) remote PT order is: {:?}", self.mid, pts);
         self.remote_pts = pts;
     }
 
+    pub(crate) fn set_codec_preferences(&mut self, pts: Vec<Pt>) {
+        self.codec_prefs = pts;
+    }
+
+    /// The local codec preferences for this Media, if any.
+    ///
+    /// These are set via [`SdpApi::set_codec_preferences()`]. An empty slice means
+    /// the order of the `CodecConfig` for the `Rtc` instance is used.
+    ///
+    /// [`SdpApi::set_codec_preferences()`]: crate::change::SdpApi::set_codec_preferences
+    pub fn codec_preferences(&self) -> &[Pt] {
+        &self.codec_prefs
+    }
+
+    /// The params to use for this media, narrowed to the remote PTs and in the order of
+    /// the codec preferences, or the order of `params` if there are none.
+    pub(crate) fn effective_params<'a>(
+        &self,
+        params: &'a [PayloadParams],
+    ) -> Vec<&'a PayloadParams> {
+        let negotiated = |p: &&PayloadParams| self.remote_pts.contains(&p.pt);
+
+        if self.codec_prefs.is_empty() {
+            params.iter().filter(negotiated).collect()
+        } else {
+            self.codec_prefs
+                .iter()
+                .filter_map(|pt| params.iter().find(|p| p.pt == *pt))
+                .filter(negotiated)
+                .collect()
+        }
+    }
+
     pub(crate) fn set_remote_extmap(&mut self, exts: ExtensionMap) {
         self.remote_exts = exts;
     }
@@ -477,10 +567,9 @@ impl Media {
     }
 
     pub(crate) fn first_pt_with_rtx(&self, config: &CodecConfig) -> Option<Pt> {
-        config
-            .all_for_kind(self.kind)
-            // Only consider negotiated PTs
-            .filter(|p| self.remote_pts.contains(&p.pt))
+        // Only consider negotiated PTs
+        self.effective_params(config)
+            .into_iter()
             // Map to the first PT found in payload params with RTX
             .find_map(|p| p.resend().map(|_| p.pt))
     }
@@ -513,6 +602,7 @@ impl Default for Media {
             msid: Msid::random(),
             kind: MediaKind::Video,
             remote_pts: vec![],
+            codec_prefs: vec![],
             remote_exts: ExtensionMap::empty(),
             remote_created: false,
             dir: Direction::SendRecv,
@@ -522,6 +612,8 @@ impl Default for Media {
             payloaders: HashMap::new(),
             depayloaders: HashMap::new(),
             to_payload: VecDeque::default(),
+            rx_pts: HashMap::new(),
+            pending_sample: None,
             need_open_event: true,
             need_changed_event: false,
         }
@@ -561,6 +653,7 @@ impl Media {
             kind: a.kind,
             dir: a.dir,
             remote_pts: a.pts,
+            codec_prefs: a.prefs,
             remote_exts: a.exts,
             remote_created: false,
             simulcast: a.simulcast.map(|s| s.into_sdp()),
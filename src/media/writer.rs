@@ -36,15 +36,15 @@ impl<'a> Writer<'a> {
 
     /// Get the configured payload parameters for the `mid` this writer is for.
     ///
+    /// These are in the order of the codec preferences of the media, if set.
+    ///
     /// For the [`Writer::write()`] call, the `pt` must be set correctly.
     pub fn payload_params(&self) -> impl Iterator<Item = &PayloadParams> {
         // This unwrap is OK due to the invariant of self.mid being resolvable
         let media = self.session.media_by_mid(self.mid).unwrap();
-        self.session
-            .codec_config
-            .params()
-            .iter()
-            .filter(|p| media.remote_pts().contains(&p.pt))
+        media
+            .effective_params(self.session.codec_config.params())
+            .into_iter()
     }
 
     /// Match the given parameters to the configured parameters for this [`Media`].
@@ -104,6 +104,9 @@ impl<'a> Writer<'a> {
     /// This operation fails if the PT doesn't match a negotiated codec, or the RID (`None` or a value)
     /// does not match anything negotiated.
     ///
+    /// With [SFrame][crate::media::SFrame] enabled, the data is encrypted using the sender key
+    /// before packetization. This fails if no sender key is set.
+    ///
     /// Regarding `wallclock` and `rtp_time`, the wallclock is the real world time that corresponds to
     /// the `MediaTime`. For an SFU, this can be hard to know, since RTP packets typically only
     /// contain the media time (RTP time). In the simplest SFU setup, the wallclock could simply
@@ -134,7 +137,11 @@ impl<'a> Writer<'a> {
             }
         }
 
-        let data: Vec<u8> = data.into();
+        let mut data: Vec<u8> = data.into();
+
+        if let Some(sframe) = &mut self.session.sframe {
+            data = sframe.encrypt(&data)?;
+        }
 
         trace!(
             "write {:?} {:?} {:?} time: {:?} len: {}",
@@ -116,7 +116,8 @@ impl DepacketizingBuffer {
             | CodecDepacketizer::H265(_)
             | CodecDepacketizer::Boxed(_)
             | CodecDepacketizer::Opus(_)
-            | CodecDepacketizer::Null(_) => Contiguity::None,
+            | CodecDepacketizer::Null(_)
+            | CodecDepacketizer::Generic(_) => Contiguity::None,
         };
 
         DepacketizingBuffer {
@@ -635,6 +636,7 @@ mod test {
                                 ..Default::default()
                             },
                             header_len: 28,
+                            cryptex: false,
                         },
                         last_sender_info: None,
                     },
@@ -0,0 +1,120 @@
+use super::{CodecExtra, Depacketizer, PacketError, Packetizer};
+
+/// Bit set in the generic payload header for the first packet of a frame.
+const FIRST_PACKET_BIT: u8 = 0x02;
+
+/// Length of the generic payload header.
+const HEADER_LEN: usize = 1;
+
+/// Packetizes frames without knowledge of the codec.
+///
+/// This is the generic packetization used by libWebRTC, and is needed when the
+/// payload is opaque, such as when the frames are end-to-end encrypted using SFrame.
+/// Each packet starts with a one byte header where the first packet of the frame
+/// has the "first packet" bit set. The RTP marker bit is set on the last packet.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct GenericPacketizer;
+
+impl Packetizer for GenericPacketizer {
+    fn packetize(&mut self, mtu: usize, payload: &[u8]) -> Result<Vec<Vec<u8>>, PacketError> {
+        if payload.is_empty() || mtu <= HEADER_LEN {
+            return Ok(vec![]);
+        }
+
+        let max_payload = mtu - HEADER_LEN;
+
+        let out = payload
+            .chunks(max_payload)
+            .enumerate()
+            .map(|(idx, chunk)| {
+                let mut p = Vec::with_capacity(HEADER_LEN + chunk.len());
+                p.push(if idx == 0 { FIRST_PACKET_BIT } else { 0 });
+                p.extend_from_slice(chunk);
+                p
+            })
+            .collect();
+
+        Ok(out)
+    }
+
+    fn is_marker(&mut self, _data: &[u8], _previous: Option<&[u8]>, last: bool) -> bool {
+        last
+    }
+}
+
+/// Depacketizes frames packetized by [`GenericPacketizer`].
+#[derive(Default, Debug, Clone, Copy)]
+pub struct GenericDepacketizer;
+
+impl Depacketizer for GenericDepacketizer {
+    fn depacketize(
+        &mut self,
+        packet: &[u8],
+        out: &mut Vec<u8>,
+        _: &mut CodecExtra,
+    ) -> Result<(), PacketError> {
+        if packet.len() < HEADER_LEN {
+            return Err(PacketError::ErrShortPacket);
+        }
+
+        out.extend_from_slice(&packet[HEADER_LEN..]);
+
+        Ok(())
+    }
+
+    fn is_partition_head(&self, packet: &[u8]) -> bool {
+        packet
+            .first()
+            .map(|h| h & FIRST_PACKET_BIT > 0)
+            .unwrap_or(false)
+    }
+
+    fn is_partition_tail(&self, marker: bool, _packet: &[u8]) -> bool {
+        marker
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn packetize_depacketize() {
+        let mut pack = GenericPacketizer;
+        let mut depack = GenericDepacketizer;
+
+        let data: Vec<u8> = (0..=255).collect();
+        let packets = pack.packetize(101, &data).unwrap();
+        assert_eq!(packets.len(), 3);
+        assert_eq!(packets[0].len(), 101);
+        assert_eq!(packets[2].len(), 57);
+
+        assert!(depack.is_partition_head(&packets[0]));
+        assert!(!depack.is_partition_head(&packets[1]));
+        assert!(!depack.is_partition_head(&packets[2]));
+
+        let len = packets.len();
+        let markers: Vec<_> = packets
+            .iter()
+            .enumerate()
+            .map(|(i, p)| pack.is_marker(p, None, i == len - 1))
+            .collect();
+        assert_eq!(markers, [false, false, true]);
+
+        let mut out = vec![];
+        let mut extra = CodecExtra::None;
+        for p in &packets {
+            depack.depacketize(p, &mut out, &mut extra).unwrap();
+        }
+        assert_eq!(out, data);
+    }
+
+    #[test]
+    fn depacketize_empty() {
+        let mut depack = GenericDepacketizer;
+        let mut out = vec![];
+        let mut extra = CodecExtra::None;
+        assert!(depack.depacketize(&[], &mut out, &mut extra).is_err());
+        assert!(!depack.is_partition_head(&[]));
+    }
+}
@@ -34,6 +34,10 @@ use vp9::{Vp9Depacketizer, Vp9Packetizer};
 mod null;
 use null::{NullDepacketizer, NullPacketizer};
 
+mod generic;
+pub(crate) use generic::GenericDepacketizer;
+use generic::GenericPacketizer;
+
 mod buffer_rx;
 pub(crate) use buffer_rx::{DepacketizingBuffer, RtpMeta};
 mod contiguity;
@@ -193,6 +197,7 @@ pub(crate) enum CodecPacketizer {
     Vp8(Vp8Packetizer),
     Vp9(Vp9Packetizer),
     Null(NullPacketizer),
+    Generic(GenericPacketizer),
     #[allow(unused)]
     Boxed(Box<dyn Packetizer + Send + Sync + UnwindSafe>),
 }
@@ -205,6 +210,7 @@ pub(crate) enum CodecDepacketizer {
     Vp8(Vp8Depacketizer),
     Vp9(Vp9Depacketizer),
     Null(NullDepacketizer),
+    Generic(GenericDepacketizer),
     #[allow(unused)]
     Boxed(Box<dyn Depacketizer + Send + Sync + UnwindSafe>),
 }
@@ -252,6 +258,7 @@ impl Packetizer for CodecPacketizer {
             Vp8(v) => v.packetize(mtu, b),
             Vp9(v) => v.packetize(mtu, b),
             Null(v) => v.packetize(mtu, b),
+            Generic(v) => v.packetize(mtu, b),
             Boxed(v) => v.packetize(mtu, b),
         }
     }
@@ -265,6 +272,7 @@ impl Packetizer for CodecPacketizer {
             CodecPacketizer::Vp8(v) => v.is_marker(data, previous, last),
             CodecPacketizer::Vp9(v) => v.is_marker(data, previous, last),
             CodecPacketizer::Null(v) => v.is_marker(data, previous, last),
+            CodecPacketizer::Generic(v) => v.is_marker(data, previous, last),
             CodecPacketizer::Boxed(v) => v.is_marker(data, previous, last),
         }
     }
@@ -285,6 +293,7 @@ impl Depacketizer for CodecDepacketizer {
             Vp8(v) => v.depacketize(packet, out, extra),
             Vp9(v) => v.depacketize(packet, out, extra),
             Null(v) => v.depacketize(packet, out, extra),
+            Generic(v) => v.depacketize(packet, out, extra),
             Boxed(v) => v.depacketize(packet, out, extra),
         }
     }
@@ -298,6 +307,7 @@ impl Depacketizer for CodecDepacketizer {
             Vp8(v) => v.is_partition_head(packet),
             Vp9(v) => v.is_partition_head(packet),
             Null(v) => v.is_partition_head(packet),
+            Generic(v) => v.is_partition_head(packet),
             Boxed(v) => v.is_partition_head(packet),
         }
     }
@@ -311,6 +321,7 @@ impl Depacketizer for CodecDepacketizer {
             Vp8(v) => v.is_partition_tail(marker, packet),
             Vp9(v) => v.is_partition_tail(marker, packet),
             Null(v) => v.is_partition_tail(marker, packet),
+            Generic(v) => v.is_partition_tail(marker, packet),
             Boxed(v) => v.is_partition_tail(marker, packet),
         }
     }
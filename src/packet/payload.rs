@@ -4,7 +4,7 @@ use crate::rtp_::Frequency;
 use crate::streams::StreamTx;
 
 use super::PacketError;
-use super::{CodecPacketizer, Packetizer};
+use super::{CodecPacketizer, GenericPacketizer, Packetizer};
 
 #[derive(Debug)]
 pub struct Payloader {
@@ -13,9 +13,15 @@ pub struct Payloader {
 }
 
 impl Payloader {
-    pub(crate) fn new(spec: CodecSpec) -> Self {
+    pub(crate) fn new(spec: CodecSpec, generic: bool) -> Self {
+        let pack = if generic {
+            CodecPacketizer::Generic(GenericPacketizer)
+        } else {
+            spec.codec.into()
+        };
+
         Payloader {
-            pack: spec.codec.into(),
+            pack,
             clock_rate: spec.clock_rate,
         }
     }
@@ -83,6 +83,10 @@ pub(crate) enum ExtensionsForm {
     TwoByte = 0x1000,
 }
 
+// See RFC 9335 Section 5.1
+const CRYPTEX_ONE_BYTE: u16 = 0xC0DE;
+const CRYPTEX_TWO_BYTE: u16 = 0xC2DE;
+
 pub const MAX_ID_ONE_BYTE_FORM: u8 = 14;
 // With the two byte form, it could be 255, but supporting larger values makes the ExtensionMap larger.
 // So we support only up to this ID for now.
@@ -109,6 +113,23 @@ impl ExtensionsForm {
             None
         }
     }
+
+    /// The "defined by profile" value when the extensions are encrypted with Cryptex (RFC 9335).
+    pub(crate) fn serialize_cryptex(self) -> [u8; 2] {
+        match self {
+            ExtensionsForm::OneByte => CRYPTEX_ONE_BYTE,
+            ExtensionsForm::TwoByte => CRYPTEX_TWO_BYTE,
+        }
+        .to_be_bytes()
+    }
+
+    pub(crate) fn parse_cryptex(bytes: [u8; 2]) -> Option<Self> {
+        match u16::from_be_bytes(bytes) {
+            CRYPTEX_ONE_BYTE => Some(ExtensionsForm::OneByte),
+            CRYPTEX_TWO_BYTE => Some(ExtensionsForm::TwoByte),
+            _ => None,
+        }
+    }
 }
 
 // TODO: think this through. Is it unwind safe?
@@ -32,6 +32,10 @@ pub struct RtpHeader {
     pub ext_vals: ExtensionValues,
     /// Length of header.
     pub header_len: usize,
+    /// Header extensions and CSRCs are encrypted using Cryptex (RFC 9335).
+    ///
+    /// This is written as the `0xC0DE`/`0xC2DE` extension profile.
+    pub cryptex: bool,
 }
 
 impl RtpHeader {
@@ -48,7 +52,12 @@ impl RtpHeader {
         buf[8..12].copy_from_slice(&self.ssrc.to_be_bytes());
 
         let exts_form = exts.form(&self.ext_vals);
-        buf[12..14].copy_from_slice(&exts_form.serialize());
+        let profile = if self.cryptex {
+            exts_form.serialize_cryptex()
+        } else {
+            exts_form.serialize()
+        };
+        buf[12..14].copy_from_slice(&profile);
 
         let ext_buf = &mut buf[16..];
         let mut ext_len = exts.write_to(ext_buf, &self.ext_vals, exts_form);
@@ -174,6 +183,7 @@ impl RtpHeader {
         let mut ext = ExtensionValues {
             ..Default::default()
         };
+        let mut cryptex = false;
 
         let rest = if !has_extension {
             buf
@@ -183,13 +193,19 @@ impl RtpHeader {
                 return None;
             }
 
-            let Some(exts_form) = ExtensionsForm::parse([buf[0], buf[1]]) else {
+            let profile = [buf[0], buf[1]];
+            let (exts_form, is_cryptex) = if let Some(f) = ExtensionsForm::parse(profile) {
+                (f, false)
+            } else if let Some(f) = ExtensionsForm::parse_cryptex(profile) {
+                (f, true)
+            } else {
                 trace!(
                     "Ignoring unknown RTP header extensions form: {:?}",
-                    u16::from_be_bytes([buf[0], buf[1]])
+                    u16::from_be_bytes(profile)
                 );
                 return None;
             };
+            cryptex = is_cryptex;
             let ext_words = u16::from_be_bytes([buf[2], buf[3]]);
             let ext_len = ext_words as usize * 4;
 
@@ -200,7 +216,10 @@ impl RtpHeader {
                 return None;
             }
 
-            exts.parse(&buf[..ext_len], exts_form, &mut ext);
+            // Encrypted extension values are parsed after SRTP decryption.
+            if !cryptex {
+                exts.parse(&buf[..ext_len], exts_form, &mut ext);
+            }
             &buf[ext_len..]
         };
 
@@ -219,6 +238,7 @@ impl RtpHeader {
             // csrc,
             ext_vals: ext,
             header_len,
+            cryptex,
         };
 
         Some(ret)
@@ -324,6 +344,7 @@ impl Default for RtpHeader {
             ssrc: 0.into(),
             ext_vals: ExtensionValues::default(),
             header_len: 16,
+            cryptex: false,
         }
     }
 }
@@ -574,7 +595,8 @@ mod test {
                     transport_cc: Some(0),
                     ..Default::default()
                 },
-                header_len: 32
+                header_len: 32,
+                cryptex: false
             }
         );
 
@@ -600,7 +622,8 @@ mod test {
                     transport_cc: Some(2),
                     ..Default::default()
                 },
-                header_len: 32
+                header_len: 32,
+                cryptex: false
             }
         );
 
@@ -626,7 +649,8 @@ mod test {
                     transport_cc: Some(1),
                     ..Default::default()
                 },
-                header_len: 32
+                header_len: 32,
+                cryptex: false
             }
         );
     }
@@ -674,7 +698,8 @@ mod test {
                     transport_cc: Some(0),
                     ..Default::default()
                 },
-                header_len: 36
+                header_len: 36,
+                cryptex: false
             }
         );
 
@@ -700,7 +725,8 @@ mod test {
                     transport_cc: Some(2),
                     ..Default::default()
                 },
-                header_len: 36
+                header_len: 36,
+                cryptex: false
             }
         );
 
@@ -726,7 +752,8 @@ mod test {
                     transport_cc: Some(1),
                     ..Default::default()
                 },
-                header_len: 36
+                header_len: 36,
+                cryptex: false
             }
         );
     }
@@ -753,4 +780,45 @@ mod test {
         assert_eq!(Ok(vec![]), truncate(vec![1]));
         assert_eq!(Ok(vec![]), truncate(vec![]));
     }
+
+    #[test]
+    fn write_and_parse_cryptex_header() {
+        let mut exts = ExtensionMap::empty();
+        exts.set(3, Extension::AudioLevel);
+
+        let header = RtpHeader {
+            payload_type: 33.into(),
+            sequence_number: 47_000,
+            timestamp: 10_000,
+            ssrc: 44.into(),
+            ext_vals: ExtensionValues {
+                audio_level: Some(-42),
+                voice_activity: Some(false),
+                ..Default::default()
+            },
+            cryptex: true,
+            ..Default::default()
+        };
+
+        let mut buf = vec![0; DATAGRAM_MAX_PACKET_SIZE];
+        let n = header.write_to(&mut buf[..], &exts);
+        buf.truncate(n);
+
+        assert_eq!(
+            &buf,
+            &[144, 33, 183, 152, 0, 0, 39, 16, 0, 0, 0, 44, 0xC0, 0xDE, 0, 1, 48, 170, 0, 0]
+        );
+
+        // The values are encrypted on the wire, and not parsed until SRTP decryption.
+        let parsed = RtpHeader::parse(&buf, &exts).unwrap();
+        assert!(parsed.cryptex);
+        assert_eq!(parsed.header_len, 20);
+        assert_eq!(parsed.ext_vals.audio_level, None);
+
+        // Two byte form.
+        buf[12..14].copy_from_slice(&[0xC2, 0xDE]);
+        let parsed = RtpHeader::parse(&buf, &exts).unwrap();
+        assert!(parsed.cryptex);
+        assert_eq!(parsed.header_len, 20);
+    }
 }
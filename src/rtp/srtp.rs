@@ -1,8 +1,11 @@
+use std::borrow::Cow;
 use std::fmt;
 
 use crate::crypto::{aead_aes_128_gcm, aes_128_cm_sha1_80, SrtpProfile};
-use crate::crypto::{KeyingMaterial, SrtpCrypto};
+use crate::crypto::{aead_aes_256_gcm, aes_256_cm_sha1_80};
+use crate::crypto::{CryptoError, KeyingMaterial, SrtpCrypto};
 
+use super::ext::{ExtensionMap, ExtensionsForm};
 use super::header::RtpHeader;
 
 // Common among various profiles(defined in RFC3711 Section 4.3)
@@ -42,36 +45,132 @@ impl SrtpContext {
                 rtp: Derived::PassThrough,
                 rtcp: Derived::PassThrough,
                 srtcp_index: 0,
+                mki: None,
+                lifetime: None,
+                packets: 0,
             },
             SrtpProfile::Aes128CmSha1_80 => {
                 use aes_128_cm_sha1_80::{KEY_LEN, SALT_LEN};
 
                 let key = SrtpKey::<KEY_LEN, SALT_LEN>::new(mat, left);
 
-                let (rtp, rtcp) = Derived::aes_128_cm_sha1_80(crypto, &key);
+                let (rtp, rtcp) = Derived::aes_cm_sha1_80(crypto, &key, profile, |k, e| {
+                    AesCm::Aes128(crypto.new_aes_128_cm_sha1_80(k, e))
+                });
 
-                SrtpContext {
-                    rtp,
-                    rtcp,
-                    srtcp_index: 0,
-                }
+                SrtpContext::with_derived(rtp, rtcp)
+            }
+            SrtpProfile::Aes256CmSha1_80 => {
+                use aes_256_cm_sha1_80::{KEY_LEN, SALT_LEN};
+
+                let key = SrtpKey::<KEY_LEN, SALT_LEN>::new(mat, left);
+
+                let (rtp, rtcp) = Derived::aes_cm_sha1_80(crypto, &key, profile, |k, e| {
+                    AesCm::Aes256(crypto.new_aes_256_cm_sha1_80(k, e))
+                });
+
+                SrtpContext::with_derived(rtp, rtcp)
             }
             SrtpProfile::AeadAes128Gcm => {
                 use aead_aes_128_gcm::{KEY_LEN, SALT_LEN};
 
                 let key = SrtpKey::<KEY_LEN, SALT_LEN>::new(mat, left);
 
-                let (rtp, rtcp) = Derived::aead_aes_128_gcm(crypto, &key);
+                let (rtp, rtcp) = Derived::aead_aes_gcm(crypto, &key, profile, |k, e| {
+                    AesGcm::Aes128(crypto.new_aead_aes_128_gcm(k, e))
+                });
 
-                SrtpContext {
-                    rtp,
-                    rtcp,
-                    srtcp_index: 0,
-                }
+                SrtpContext::with_derived(rtp, rtcp)
+            }
+            SrtpProfile::AeadAes256Gcm => {
+                use aead_aes_256_gcm::{KEY_LEN, SALT_LEN};
+
+                let key = SrtpKey::<KEY_LEN, SALT_LEN>::new(mat, left);
+
+                let (rtp, rtcp) = Derived::aead_aes_gcm(crypto, &key, profile, |k, e| {
+                    AesGcm::Aes256(crypto.new_aead_aes_256_gcm(k, e))
+                });
+
+                SrtpContext::with_derived(rtp, rtcp)
             }
         }
     }
 
+    fn with_derived(rtp: Derived, rtcp: Derived) -> Self {
+        SrtpContext {
+            rtp,
+            rtcp,
+            srtcp_index: 0,
+            mki: None,
+            lifetime: None,
+            packets: 0,
+        }
+    }
+
+    /// The profile of this context.
+    pub fn profile(&self) -> SrtpProfile {
+        self.rtp.profile()
+    }
+
+    /// Set the Master Key Identifier (RFC 3711) expected in incoming packets.
+    ///
+    /// Only the unprotect direction uses the MKI. Packets not carrying it are discarded.
+    pub fn set_mki(&mut self, mki: Option<Vec<u8>>) {
+        self.mki = mki.filter(|m| !m.is_empty());
+    }
+
+    /// Set the max number of incoming SRTP and SRTCP packets for the master key.
+    ///
+    /// `used` is the number of packets already received with the same key. Once the
+    /// lifetime is reached, incoming packets are discarded.
+    pub fn set_lifetime(&mut self, lifetime: Option<u64>, used: u64) {
+        self.lifetime = lifetime;
+        self.packets = used;
+    }
+
+    /// Number of incoming packets unprotected with this context.
+    pub fn packets(&self) -> u64 {
+        self.packets
+    }
+
+    /// None if the master key can't unprotect more packets.
+    fn check_lifetime(&self) -> Option<()> {
+        if self.lifetime.is_some_and(|l| self.packets >= l) {
+            trace!("SRTP master key lifetime exhausted");
+            return None;
+        }
+        Some(())
+    }
+
+    /// Remove the MKI from an incoming packet, checking it is the one we expect.
+    ///
+    /// The MKI goes before the authentication tag for AES-CM, and at the
+    /// end of the packet for AEAD where the tag is part of the cipher text.
+    fn strip_mki<'a>(&self, derived: &Derived, buf: &'a [u8]) -> Option<Cow<'a, [u8]>> {
+        let Some(mki) = &self.mki else {
+            return Some(Cow::Borrowed(buf));
+        };
+
+        let tag_len = derived.mki_tag_len();
+        if buf.len() < mki.len() + tag_len {
+            return None;
+        }
+
+        let start = buf.len() - tag_len - mki.len();
+        let end = start + mki.len();
+
+        if buf[start..end] != mki[..] {
+            trace!("Unexpected SRTP MKI");
+            return None;
+        }
+
+        let mut v = Vec::with_capacity(buf.len() - mki.len());
+        v.extend_from_slice(&buf[..start]);
+        v.extend_from_slice(&buf[end..]);
+
+        Some(Cow::Owned(v))
+    }
+
     #[cfg(test)]
     fn new_aead_aes_128_gcm(
         rtp_key: [u8; aead_aes_128_gcm::KEY_LEN],
@@ -83,17 +182,22 @@ impl SrtpContext {
         let crypto = crate::CryptoProvider::from_feature_flags().srtp_crypto();
 
         Self {
-            rtp: Derived::AeadAes128Gcm {
+            rtp: Derived::AeadAesGcm {
+                profile: SrtpProfile::AeadAes128Gcm,
                 salt: rtp_salt,
-                enc: crypto.new_aead_aes_128_gcm(rtp_key, true),
-                dec: crypto.new_aead_aes_128_gcm(rtp_key, false),
+                enc: AesGcm::Aes128(crypto.new_aead_aes_128_gcm(rtp_key, true)),
+                dec: AesGcm::Aes128(crypto.new_aead_aes_128_gcm(rtp_key, false)),
             },
-            rtcp: Derived::AeadAes128Gcm {
+            rtcp: Derived::AeadAesGcm {
+                profile: SrtpProfile::AeadAes128Gcm,
                 salt: rtcp_salt,
-                enc: crypto.new_aead_aes_128_gcm(rtcp_key, true),
-                dec: crypto.new_aead_aes_128_gcm(rtcp_key, false),
+                enc: AesGcm::Aes128(crypto.new_aead_aes_128_gcm(rtcp_key, true)),
+                dec: AesGcm::Aes128(crypto.new_aead_aes_128_gcm(rtcp_key, false)),
             },
             srtcp_index,
+            mki: None,
+            lifetime: None,
+            packets: 0,
         }
     }
 }
@@ -106,6 +210,12 @@ pub struct SrtpContext {
     rtcp: Derived,
     /// Counter for outgoing SRTCP packets.
     srtcp_index: u32,
+    /// MKI expected in incoming packets, only used with SDES keying.
+    mki: Option<Vec<u8>>,
+    /// Max number of incoming packets for the master key, only used with SDES keying.
+    lifetime: Option<u64>,
+    /// Incoming packets unprotected so far.
+    packets: u64,
 }
 
 impl SrtpContext {
@@ -143,63 +253,117 @@ impl SrtpContext {
         //  |                                                                   |
         //  +- Encrypted Portion*                      Authenticated Portion ---+
         let hlen = header.header_len;
-        let input = &buf[hlen..];
+
+        // With Cryptex, the encrypted portion starts after the extension header.
+        let cryptex = header.cryptex.then(|| csrc_len(buf));
+        let mut moved: Vec<u8>;
+        let (plain, clear_len) = match cryptex {
+            Some(csrc_len) => {
+                moved = buf.to_vec();
+                cryptex_move(&mut moved[..hlen], csrc_len, true);
+                (&moved[..], CRYPTEX_CLEAR_LEN)
+            }
+            None => (buf, hlen),
+        };
+        let input = &plain[clear_len..];
 
         match &mut self.rtp {
             #[cfg(feature = "_internal_test_exports")]
-            Derived::PassThrough => input.to_vec(),
-            Derived::Aes128CmSha1_80 { key, salt, enc, .. } => {
+            Derived::PassThrough => buf[hlen..].to_vec(),
+            Derived::AesCmSha1_80 { key, salt, enc, .. } => {
                 assert!(
-                    input.len() % SRTP_BLOCK_SIZE == 0,
-                    "RTP body should be padded to 16 byte block size, {header:?} with body length {} was not", input.len()
+                    (buf.len() - hlen) % SRTP_BLOCK_SIZE == 0,
+                    "RTP body should be padded to 16 byte block size, {header:?} with body length {} was not", buf.len() - hlen
                 );
                 use aes_128_cm_sha1_80::HMAC_TAG_LEN;
 
                 let iv = aes_128_cm_sha1_80::rtp_iv(*salt, *header.ssrc, srtp_index);
 
                 let mut output = vec![0_u8; buf.len() + HMAC_TAG_LEN];
-                enc.encrypt(&iv, input, &mut output[hlen..])
+                enc.encrypt(&iv, input, &mut output[clear_len..])
                     .expect("rtp encrypt");
 
-                output[..hlen].copy_from_slice(&buf[..hlen]);
+                output[..clear_len].copy_from_slice(&plain[..clear_len]);
+
+                if let Some(csrc_len) = cryptex {
+                    cryptex_move(&mut output[..hlen], csrc_len, false);
+                }
 
                 let hmac_start = buf.len();
                 aes_128_cm_sha1_80::rtp_hmac(key, &mut output, srtp_index, hmac_start);
 
                 output
             }
-            Derived::AeadAes128Gcm { salt, enc, .. } => {
+            Derived::AeadAesGcm { salt, enc, .. } => {
                 use aead_aes_128_gcm::TAG_LEN;
                 let roc = (srtp_index >> 16) as u32;
 
                 let iv = aead_aes_128_gcm::rtp_iv(*salt, *header.ssrc, roc, header.sequence_number);
-                let aad = &buf[..hlen];
+                let aad = &plain[..clear_len];
 
                 // Input and output lengths for encryption: https://www.rfc-editor.org/rfc/rfc7714#section-5.2.1
                 let mut output = vec![0_u8; buf.len() + TAG_LEN];
-                enc.encrypt(&iv, aad, input, &mut output[hlen..])
+                enc.encrypt(&iv, aad, input, &mut output[clear_len..])
                     .expect("rtp encrypt");
 
-                output[..hlen].copy_from_slice(aad);
+                output[..clear_len].copy_from_slice(aad);
+
+                if let Some(csrc_len) = cryptex {
+                    cryptex_move(&mut output[..hlen], csrc_len, false);
+                }
 
                 output
             }
         }
     }
 
+    /// Decrypt an SRTP packet and return the payload.
+    ///
+    /// If the header was protected with Cryptex, the decrypted extension values are
+    /// parsed into `header` using `exts`.
     pub fn unprotect_rtp(
         &mut self,
         buf: &[u8],
-        header: &RtpHeader,
+        header: &mut RtpHeader,
         srtp_index: u64, // same as ext_seq
+        exts: &ExtensionMap,
     ) -> Option<Vec<u8>> {
-        match &mut self.rtp {
+        self.check_lifetime()?;
+        let output = self.decrypt_rtp(buf, header, srtp_index, exts)?;
+        self.packets += 1;
+        Some(output)
+    }
+
+    fn decrypt_rtp(
+        &mut self,
+        buf: &[u8],
+        header: &mut RtpHeader,
+        srtp_index: u64,
+        exts: &ExtensionMap,
+    ) -> Option<Vec<u8>> {
+        let stripped = self.strip_mki(&self.rtp, buf)?;
+        let buf = &stripped[..];
+
+        let hlen = header.header_len;
+
+        let cryptex = header.cryptex.then(|| csrc_len(buf));
+        let mut moved: Vec<u8>;
+        let (plain, clear_len) = match cryptex {
+            Some(csrc_len) => {
+                moved = buf.to_vec();
+                cryptex_move(&mut moved[..hlen], csrc_len, true);
+                (&moved[..], CRYPTEX_CLEAR_LEN)
+            }
+            None => (buf, hlen),
+        };
+
+        let mut output = match &mut self.rtp {
             #[cfg(feature = "_internal_test_exports")]
-            Derived::PassThrough => Some(buf.to_vec()),
-            Derived::Aes128CmSha1_80 { key, salt, dec, .. } => {
+            Derived::PassThrough => return Some(buf.to_vec()),
+            Derived::AesCmSha1_80 { key, salt, dec, .. } => {
                 use aes_128_cm_sha1_80::HMAC_TAG_LEN;
 
-                if buf.len() < HMAC_TAG_LEN {
+                if buf.len() < hlen + HMAC_TAG_LEN {
                     return None;
                 }
 
@@ -217,7 +381,7 @@ impl SrtpContext {
 
                 let iv = aes_128_cm_sha1_80::rtp_iv(*salt, *header.ssrc, srtp_index);
 
-                let input = &buf[header.header_len..hmac_start];
+                let input = &plain[clear_len..hmac_start];
                 let mut output = vec![0; input.len()];
 
                 if let Err(e) = dec.decrypt(&iv, input, &mut output) {
@@ -230,12 +394,12 @@ impl SrtpContext {
                     return None;
                 };
 
-                Some(output)
+                output
             }
-            Derived::AeadAes128Gcm { salt, dec, .. } => {
+            Derived::AeadAesGcm { salt, dec, .. } => {
                 use aead_aes_128_gcm::TAG_LEN;
 
-                if buf.len() < TAG_LEN {
+                if buf.len() < hlen + TAG_LEN {
                     return None;
                 }
 
@@ -244,7 +408,7 @@ impl SrtpContext {
 
                 let iv = aead_aes_128_gcm::rtp_iv(*salt, *header.ssrc, roc, seq);
 
-                let (aad, input) = buf.split_at(header.header_len);
+                let (aad, input) = plain.split_at(clear_len);
                 // Input and output lengths for decryption: https://www.rfc-editor.org/rfc/rfc7714#section-5.2.2
                 let mut output = vec![0; input.len() - TAG_LEN];
 
@@ -261,9 +425,30 @@ impl SrtpContext {
                     }
                 };
 
-                Some(output)
+                output
             }
-        }
+        };
+
+        let Some(csrc_len) = cryptex else {
+            return Some(output);
+        };
+
+        // The decrypted output starts with the CSRCs and extension values.
+        let encrypted_len = hlen - CRYPTEX_CLEAR_LEN;
+        let mut decrypted = plain[..CRYPTEX_CLEAR_LEN].to_vec();
+        decrypted.extend_from_slice(&output[..encrypted_len]);
+        cryptex_move(&mut decrypted, csrc_len, false);
+
+        let at = 12 + csrc_len;
+        let form = ExtensionsForm::parse_cryptex([decrypted[at], decrypted[at + 1]])?;
+        decrypted[at..at + 2].copy_from_slice(&form.serialize());
+
+        let mut parsed = RtpHeader::parse(&decrypted, exts)?;
+        parsed.cryptex = true;
+        *header = parsed;
+
+        output.drain(..encrypted_len);
+        Some(output)
     }
 
     pub fn protect_rtcp(&mut self, buf: &[u8]) -> Vec<u8> {
@@ -286,7 +471,7 @@ impl SrtpContext {
         match &mut self.rtcp {
             #[cfg(feature = "_internal_test_exports")]
             Derived::PassThrough => buf.to_vec(),
-            Derived::Aes128CmSha1_80 { key, salt, enc, .. } => {
+            Derived::AesCmSha1_80 { key, salt, enc, .. } => {
                 use aes_128_cm_sha1_80::HMAC_TAG_LEN;
 
                 let iv = aes_128_cm_sha1_80::rtp_iv(*salt, ssrc, srtcp_index as u64);
@@ -306,7 +491,7 @@ impl SrtpContext {
 
                 output
             }
-            Derived::AeadAes128Gcm { salt, enc, .. } => {
+            Derived::AeadAesGcm { salt, enc, .. } => {
                 use aead_aes_128_gcm::{RTCP_AAD_LEN, TAG_LEN};
                 let iv = aead_aes_128_gcm::rtcp_iv(*salt, ssrc, srtcp_index);
 
@@ -341,10 +526,20 @@ impl SrtpContext {
     //                  |--------------------------------------|
     //                              encrypted (aes)
     pub fn unprotect_rtcp(&mut self, buf: &[u8]) -> Option<Vec<u8>> {
+        self.check_lifetime()?;
+        let output = self.decrypt_rtcp(buf)?;
+        self.packets += 1;
+        Some(output)
+    }
+
+    fn decrypt_rtcp(&mut self, buf: &[u8]) -> Option<Vec<u8>> {
+        let stripped = self.strip_mki(&self.rtcp, buf)?;
+        let buf = &stripped[..];
+
         match &mut self.rtcp {
             #[cfg(feature = "_internal_test_exports")]
             Derived::PassThrough => Some(buf.to_vec()),
-            Derived::Aes128CmSha1_80 { key, salt, dec, .. } => {
+            Derived::AesCmSha1_80 { key, salt, dec, .. } => {
                 use aes_128_cm_sha1_80::HMAC_TAG_LEN;
 
                 if buf.len() < HMAC_TAG_LEN + SRTCP_INDEX_LEN {
@@ -398,7 +593,7 @@ impl SrtpContext {
 
                 Some(output)
             }
-            Derived::AeadAes128Gcm { salt, dec, .. } => {
+            Derived::AeadAesGcm { salt, dec, .. } => {
                 use aead_aes_128_gcm::{RTCP_AAD_LEN, TAG_LEN};
 
                 if buf.len() < SRTCP_INDEX_LEN + TAG_LEN {
@@ -504,13 +699,16 @@ impl<const ML: usize, const SL: usize> SrtpKey<ML, SL> {
     }
 
     fn derive(&self, crypto: &SrtpCrypto, label: u8, out: &mut [u8]) {
-        // AEC-CM (128 bits) defined in RFC3711
-        assert!(ML == 16, "Only valid for 128 bit master keys");
-        assert!(SL <= 14, "Only valid for 128 bit master keys");
+        // AES-CM PRF defined in RFC3711, with AES-256 for 256 bit master keys (RFC6188).
+        assert!(
+            ML == 16 || ML == 32,
+            "Only valid for 128 or 256 bit master keys"
+        );
+        assert!(SL <= 14, "Only valid for master salts up to 112 bits");
         let mut i = 0; // index in out
 
         // input layout: [salt[SL] || label, round[2]] (|| is xor 7th byte)
-        let mut input = [0; ML];
+        let mut input = [0; SRTP_BLOCK_SIZE];
 
         input[0..SL].copy_from_slice(&self.salt[..]);
         input[7] ^= label;
@@ -527,10 +725,14 @@ impl<const ML: usize, const SL: usize> SrtpKey<ML, SL> {
             // splice in round at bottom of input
             input[14..].copy_from_slice(&round.to_be_bytes()[..]);
 
-            // default key derivation function, which uses AES-128 in Counter Mode
-            crypto.srtp_aes_128_ecb_round(&self.master, &input[..], &mut buf[..]);
+            // default key derivation function, which uses AES in Counter Mode
+            if ML == 32 {
+                crypto.srtp_aes_256_ecb_round(&self.master, &input[..], &mut buf[..]);
+            } else {
+                crypto.srtp_aes_128_ecb_round(&self.master, &input[..], &mut buf[..]);
+            }
 
-            // Copy to output. Even if we get 32 bytes of output with AES 128 ECB, we
+            // Copy to output. Even if we get 32 bytes of output with AES ECB, we
             // only use the first 16. That matches the tests in the RFC.
             for j in buf.iter().take(16) {
                 if i == out.len() {
@@ -549,28 +751,44 @@ impl<const ML: usize, const SL: usize> SrtpKey<ML, SL> {
 enum Derived {
     #[cfg(feature = "_internal_test_exports")]
     PassThrough,
-    Aes128CmSha1_80 {
+    AesCmSha1_80 {
+        profile: SrtpProfile,
         key: [u8; 20],
         salt: aes_128_cm_sha1_80::RtpSalt,
-        enc: Box<dyn aes_128_cm_sha1_80::CipherCtx>,
-        dec: Box<dyn aes_128_cm_sha1_80::CipherCtx>,
+        enc: AesCm,
+        dec: AesCm,
     },
-    AeadAes128Gcm {
+    AeadAesGcm {
+        profile: SrtpProfile,
         salt: aead_aes_128_gcm::RtpSalt,
-        enc: Box<dyn aead_aes_128_gcm::CipherCtx>,
-        dec: Box<dyn aead_aes_128_gcm::CipherCtx>,
+        enc: AesGcm,
+        dec: AesGcm,
     },
 }
 
+/// AES counter mode context of either key length.
+enum AesCm {
+    Aes128(Box<dyn aes_128_cm_sha1_80::CipherCtx>),
+    Aes256(Box<dyn aes_256_cm_sha1_80::CipherCtx>),
+}
+
+/// AES-GCM context of either key length.
+enum AesGcm {
+    Aes128(Box<dyn aead_aes_128_gcm::CipherCtx>),
+    Aes256(Box<dyn aead_aes_256_gcm::CipherCtx>),
+}
+
 impl Derived {
-    fn aes_128_cm_sha1_80(
+    fn aes_cm_sha1_80<const ML: usize>(
         crypto: &SrtpCrypto,
-        srtp_key: &SrtpKey<{ aes_128_cm_sha1_80::KEY_LEN }, { aes_128_cm_sha1_80::SALT_LEN }>,
+        srtp_key: &SrtpKey<ML, { aes_128_cm_sha1_80::SALT_LEN }>,
+        profile: SrtpProfile,
+        new_ctx: impl Fn([u8; ML], bool) -> AesCm,
     ) -> (Self, Self) {
         use aes_128_cm_sha1_80::*;
 
         // RTP AES Counter
-        let mut rtp_aes = [0; KEY_LEN];
+        let mut rtp_aes = [0; ML];
         srtp_key.derive(crypto, LABEL_RTP_AES, &mut rtp_aes[..]);
 
         // RTP SHA1 HMAC
@@ -585,7 +803,7 @@ impl Derived {
         srtp_key.derive(crypto, LABEL_RTP_SALT, &mut rtp_salt[..]);
 
         // RTCP AES Counter
-        let mut rtcp_aes = [0; KEY_LEN];
+        let mut rtcp_aes = [0; ML];
         srtp_key.derive(crypto, LABEL_RTCP_AES, &mut rtcp_aes[..]);
 
         // RTCP SHA1 HMAC
@@ -599,31 +817,35 @@ impl Derived {
         let mut rtcp_salt = [0; SALT_LEN];
         srtp_key.derive(crypto, LABEL_RTCP_SALT, &mut rtcp_salt[..]);
 
-        let rtp = Derived::Aes128CmSha1_80 {
+        let rtp = Derived::AesCmSha1_80 {
+            profile,
             key: rtp_hmac,
             salt: rtp_salt,
-            enc: crypto.new_aes_128_cm_sha1_80(rtp_aes, true),
-            dec: crypto.new_aes_128_cm_sha1_80(rtp_aes, false),
+            enc: new_ctx(rtp_aes, true),
+            dec: new_ctx(rtp_aes, false),
         };
 
-        let rtcp = Derived::Aes128CmSha1_80 {
+        let rtcp = Derived::AesCmSha1_80 {
+            profile,
             key: rtcp_hmac,
             salt: rtcp_salt,
-            enc: crypto.new_aes_128_cm_sha1_80(rtcp_aes, true),
-            dec: crypto.new_aes_128_cm_sha1_80(rtcp_aes, false),
+            enc: new_ctx(rtcp_aes, true),
+            dec: new_ctx(rtcp_aes, false),
         };
 
         (rtp, rtcp)
     }
 
-    fn aead_aes_128_gcm(
+    fn aead_aes_gcm<const ML: usize>(
         crypto: &SrtpCrypto,
-        srtp_key: &SrtpKey<{ aead_aes_128_gcm::KEY_LEN }, { aead_aes_128_gcm::SALT_LEN }>,
+        srtp_key: &SrtpKey<ML, { aead_aes_128_gcm::SALT_LEN }>,
+        profile: SrtpProfile,
+        new_ctx: impl Fn([u8; ML], bool) -> AesGcm,
     ) -> (Derived, Derived) {
         use aead_aes_128_gcm::*;
 
         // RTP session key
-        let mut rtp_aes = [0; KEY_LEN];
+        let mut rtp_aes = [0; ML];
         srtp_key.derive(crypto, LABEL_RTP_AES, &mut rtp_aes[..]);
 
         // RTP session salt
@@ -631,23 +853,25 @@ impl Derived {
         srtp_key.derive(crypto, LABEL_RTP_SALT, &mut rtp_salt[..]);
 
         // RTCP session key
-        let mut rtcp_aes = [0; KEY_LEN];
+        let mut rtcp_aes = [0; ML];
         srtp_key.derive(crypto, LABEL_RTCP_AES, &mut rtcp_aes[..]);
 
         // RTCP session salt
         let mut rtcp_salt = [0; SALT_LEN];
         srtp_key.derive(crypto, LABEL_RTCP_SALT, &mut rtcp_salt[..]);
 
-        let rtp = Derived::AeadAes128Gcm {
+        let rtp = Derived::AeadAesGcm {
+            profile,
             salt: rtp_salt,
-            enc: crypto.new_aead_aes_128_gcm(rtp_aes, true),
-            dec: crypto.new_aead_aes_128_gcm(rtp_aes, false),
+            enc: new_ctx(rtp_aes, true),
+            dec: new_ctx(rtp_aes, false),
         };
 
-        let rtcp = Derived::AeadAes128Gcm {
+        let rtcp = Derived::AeadAesGcm {
+            profile,
             salt: rtcp_salt,
-            enc: crypto.new_aead_aes_128_gcm(rtcp_aes, true),
-            dec: crypto.new_aead_aes_128_gcm(rtcp_aes, false),
+            enc: new_ctx(rtcp_aes, true),
+            dec: new_ctx(rtcp_aes, false),
         };
 
         (rtp, rtcp)
@@ -657,8 +881,72 @@ impl Derived {
         match self {
             #[cfg(feature = "_internal_test_exports")]
             Derived::PassThrough => SrtpProfile::PassThrough,
-            Derived::Aes128CmSha1_80 { .. } => SrtpProfile::Aes128CmSha1_80,
-            Derived::AeadAes128Gcm { .. } => SrtpProfile::AeadAes128Gcm,
+            Derived::AesCmSha1_80 { profile, .. } => *profile,
+            Derived::AeadAesGcm { profile, .. } => *profile,
+        }
+    }
+
+    /// Length of the authentication tag following the MKI.
+    fn mki_tag_len(&self) -> usize {
+        match self {
+            #[cfg(feature = "_internal_test_exports")]
+            Derived::PassThrough => 0,
+            Derived::AesCmSha1_80 { .. } => aes_128_cm_sha1_80::HMAC_TAG_LEN,
+            Derived::AeadAesGcm { .. } => 0,
+        }
+    }
+}
+
+impl AesCm {
+    fn encrypt(
+        &mut self,
+        iv: &aes_128_cm_sha1_80::RtpIv,
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<(), CryptoError> {
+        match self {
+            AesCm::Aes128(v) => v.encrypt(iv, input, output),
+            AesCm::Aes256(v) => v.encrypt(iv, input, output),
+        }
+    }
+
+    fn decrypt(
+        &mut self,
+        iv: &aes_128_cm_sha1_80::RtpIv,
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<(), CryptoError> {
+        match self {
+            AesCm::Aes128(v) => v.decrypt(iv, input, output),
+            AesCm::Aes256(v) => v.decrypt(iv, input, output),
+        }
+    }
+}
+
+impl AesGcm {
+    fn encrypt(
+        &mut self,
+        iv: &[u8; aead_aes_128_gcm::IV_LEN],
+        aad: &[u8],
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<(), CryptoError> {
+        match self {
+            AesGcm::Aes128(v) => v.encrypt(iv, aad, input, output),
+            AesGcm::Aes256(v) => v.encrypt(iv, aad, input, output),
+        }
+    }
+
+    fn decrypt(
+        &mut self,
+        iv: &[u8; aead_aes_128_gcm::IV_LEN],
+        aads: &[&[u8]],
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<usize, CryptoError> {
+        match self {
+            AesGcm::Aes128(v) => v.decrypt(iv, aads, input, output),
+            AesGcm::Aes256(v) => v.decrypt(iv, aads, input, output),
         }
     }
 }
@@ -669,6 +957,25 @@ impl fmt::Debug for Derived {
     }
 }
 
+// Fixed header plus the extension header, which is not encrypted by Cryptex.
+const CRYPTEX_CLEAR_LEN: usize = 16;
+
+fn csrc_len(buf: &[u8]) -> usize {
+    4 * (buf[0] & 0b0000_1111) as usize
+}
+
+/// Cryptex (RFC 9335) encrypts the CSRCs and extension values, but not the 4 byte
+/// extension header between them. Moving that header in front of the CSRCs makes
+/// the encrypted portion contiguous with the payload.
+fn cryptex_move(header: &mut [u8], csrc_len: usize, to_front: bool) {
+    let region = &mut header[12..CRYPTEX_CLEAR_LEN + csrc_len];
+    if to_front {
+        region.rotate_right(4);
+    } else {
+        region.rotate_left(4);
+    }
+}
+
 fn error_details(header: &RtpHeader, srtp_index: u64) -> String {
     format!(
         "SSRC: {} seq_no: {} pt: {} mid: {:?} rid: {:?} rid_repair: {:?} srtp_index: {}",
@@ -745,6 +1052,97 @@ mod test {
         );
     }
 
+    #[test]
+    fn derive_key_aes_256() {
+        crate::init_crypto_default();
+        let crypto = crate::CryptoProvider::from_feature_flags().srtp_crypto();
+
+        // https://www.rfc-editor.org/rfc/rfc6188#section-7.2
+        //
+        // AES_256_CM_PRF Test Vectors.
+
+        let master = [
+            0xf0, 0xf0, 0x49, 0x14, 0xb5, 0x13, 0xf2, 0x76, 0x3a, 0x1b, 0x1f, 0xa1, 0x30, 0xf1,
+            0x0e, 0x29, 0x98, 0xf6, 0xf6, 0xe4, 0x3e, 0x43, 0x09, 0xd1, 0xe6, 0x22, 0xa0, 0xe3,
+            0x32, 0xb9, 0xf1, 0xb6,
+        ];
+
+        let salt = [
+            0x3b, 0x04, 0x80, 0x3d, 0xe5, 0x1e, 0xe7, 0xc9, 0x64, 0x23, 0xab, 0x5b, 0x78, 0xd2,
+        ];
+
+        let sk = SrtpKey { master, salt };
+
+        // aes crypto key
+        let mut out = [0_u8; 32];
+        sk.derive(&crypto, 0, &mut out[..]);
+
+        assert_eq!(
+            out,
+            [
+                0x5b, 0xa1, 0x06, 0x4e, 0x30, 0xec, 0x51, 0x61, 0x3c, 0xad, 0x92, 0x6c, 0x5a, 0x28,
+                0xef, 0x73, 0x1e, 0xc7, 0xfb, 0x39, 0x7f, 0x70, 0xa9, 0x60, 0x65, 0x3c, 0xaf, 0x06,
+                0x55, 0x4c, 0xd8, 0xc4,
+            ]
+        );
+
+        // hmac
+        let mut out = [0_u8; 20];
+        sk.derive(&crypto, 1, &mut out[..]);
+
+        assert_eq!(
+            out,
+            [
+                0xfd, 0x9c, 0x32, 0xd3, 0x9e, 0xd5, 0xfb, 0xb5, 0xa9, 0xdc, 0x96, 0xb3, 0x08, 0x18,
+                0x45, 0x4d, 0x13, 0x13, 0xdc, 0x05,
+            ]
+        );
+
+        // salt
+        let mut out = [0_u8; 14];
+        sk.derive(&crypto, 2, &mut out[..]);
+
+        assert_eq!(
+            out,
+            [0xfa, 0x31, 0x79, 0x16, 0x85, 0xca, 0x44, 0x4a, 0x9e, 0x07, 0xc6, 0xc6, 0x4e, 0x93,]
+        );
+    }
+
+    #[test]
+    fn aes_256_cm_keystream() {
+        crate::init_crypto_default();
+        let crypto = crate::CryptoProvider::from_feature_flags().srtp_crypto();
+
+        // https://www.rfc-editor.org/rfc/rfc6188#section-7.1
+        //
+        // AES-256-CM Test Vectors. Encrypting zeros gives the keystream.
+
+        let key = [
+            0x57, 0xf8, 0x2f, 0xe3, 0x61, 0x3f, 0xd1, 0x70, 0xa8, 0x5e, 0xc9, 0x3c, 0x40, 0xb1,
+            0xf0, 0x92, 0x2e, 0xc4, 0xcb, 0x0d, 0xc0, 0x25, 0xb5, 0x82, 0x72, 0x14, 0x7c, 0xc4,
+            0x38, 0x94, 0x4a, 0x98,
+        ];
+
+        let iv = [
+            0xf0, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8, 0xf9, 0xfa, 0xfb, 0xfc, 0xfd,
+            0x00, 0x00,
+        ];
+
+        let mut ctx = crypto.new_aes_256_cm_sha1_80(key, true);
+        let mut out = [0_u8; 48];
+        ctx.encrypt(&iv, &[0; 48], &mut out).unwrap();
+
+        assert_eq!(
+            out,
+            [
+                0x92, 0xbd, 0xd2, 0x8a, 0x93, 0xc3, 0xf5, 0x25, 0x11, 0xc6, 0x77, 0xd0, 0x8b, 0x55,
+                0x15, 0xa4, 0x9d, 0xa7, 0x1b, 0x23, 0x78, 0xa8, 0x54, 0xf6, 0x70, 0x50, 0x75, 0x6d,
+                0xed, 0x16, 0x5b, 0xac, 0x63, 0xc4, 0x86, 0x8b, 0x70, 0x96, 0xd8, 0x84, 0x21, 0xb5,
+                0x63, 0xb8, 0xc9, 0x4c, 0x9a, 0x31,
+            ]
+        );
+    }
+
     mod test_aes128_cm_sha1_80 {
         use super::aes_128_cm_sha1_80::*;
         use super::*;
@@ -810,7 +1208,7 @@ mod test {
 
         use super::aead_aes_128_gcm::*;
 
-        mod rfc7714 {
+        pub(super) mod rfc7714 {
             // Test vectors from RFC7714
 
             // Session Key (RTP and RTCP)
@@ -820,12 +1218,12 @@ mod test {
             ];
 
             // Session Salt (RTP and RTCP)
-            pub(super) const SALT: [u8; 12] = [
+            pub(crate) const SALT: [u8; 12] = [
                 0x51, 0x75, 0x69, 0x64, 0x20, 0x70, 0x72, 0x6f, 0x20, 0x71, 0x75, 0x6f,
             ];
 
             /// Full plaintext RTP packet. First 12 octets is the header
-            pub(super) const PLAINTEXT_RTP_PACKET: &[u8] = &[
+            pub(crate) const PLAINTEXT_RTP_PACKET: &[u8] = &[
                 0x80, 0x40, 0xf1, 0x7b, 0x80, 0x41, 0xf8, 0xd3, 0x55, 0x01, 0xa0, 0xb2, 0x47, 0x61,
                 0x6c, 0x6c, 0x69, 0x61, 0x20, 0x65, 0x73, 0x74, 0x20, 0x6f, 0x6d, 0x6e, 0x69, 0x73,
                 0x20, 0x64, 0x69, 0x76, 0x69, 0x73, 0x61, 0x20, 0x69, 0x6e, 0x20, 0x70, 0x61, 0x72,
@@ -842,7 +1240,7 @@ mod test {
             ];
 
             // Full plaintext RTCP packet
-            pub(super) const PLAINTEXT_RTCP_PACKET: &[u8] = &[
+            pub(crate) const PLAINTEXT_RTCP_PACKET: &[u8] = &[
                 0x81, 0xc8, 0x00, 0x0d, 0x4d, 0x61, 0x72, 0x73, 0x4e, 0x54, 0x50, 0x31, 0x4e, 0x54,
                 0x50, 0x32, 0x52, 0x54, 0x50, 0x20, 0x00, 0x00, 0x04, 0x2a, 0x00, 0x00, 0xe9, 0x30,
                 0x4c, 0x75, 0x6e, 0x61, 0xde, 0xad, 0xbe, 0xef, 0xde, 0xad, 0xbe, 0xef, 0xde, 0xad,
@@ -895,12 +1293,17 @@ mod test {
         #[test]
         fn unprotect_rtp_rfc_7714_test() {
             let mut context = make_rtp_context();
-            let header =
+            let mut header =
                 RtpHeader::parse(&rfc7714::PROTECTED_RTP_PACKET[..12], &ExtensionMap::empty())
                     .expect("header to parse");
 
             let out = context
-                .unprotect_rtp(rfc7714::PROTECTED_RTP_PACKET, &header, 0)
+                .unprotect_rtp(
+                    rfc7714::PROTECTED_RTP_PACKET,
+                    &mut header,
+                    0,
+                    &ExtensionMap::empty(),
+                )
                 .expect("decrypt rtp");
 
             assert_eq!(
@@ -923,10 +1326,10 @@ mod test {
             let encrypted = context.protect_rtp(rfc7714::PLAINTEXT_RTP_PACKET, &header, 0);
 
             // Then we decrypt the resulting cipher text
-            let header = RtpHeader::parse(&encrypted[..12], &ExtensionMap::empty())
+            let mut header = RtpHeader::parse(&encrypted[..12], &ExtensionMap::empty())
                 .expect("header to parse");
             let decrypted = context
-                .unprotect_rtp(&encrypted, &header, 0)
+                .unprotect_rtp(&encrypted, &mut header, 0, &ExtensionMap::empty())
                 .expect("rtp unprotect");
 
             // And verify we get the input back.
@@ -947,10 +1350,15 @@ mod test {
                 buf
             };
 
-            let header =
+            let mut header =
                 RtpHeader::parse(&header_buf, &ExtensionMap::empty()).expect("header to parse");
 
-            let result = context.unprotect_rtp(rfc7714::PROTECTED_RTP_PACKET, &header, 0);
+            let result = context.unprotect_rtp(
+                rfc7714::PROTECTED_RTP_PACKET,
+                &mut header,
+                0,
+                &ExtensionMap::empty(),
+            );
             assert!(result.is_none(), "Should fail to decrypt a SRTP packet that has mismatched authenicated additional data");
         }
 
@@ -966,10 +1374,10 @@ mod test {
                 input
             };
 
-            let header =
+            let mut header =
                 RtpHeader::parse(&input[..12], &ExtensionMap::empty()).expect("header to parse");
 
-            let result = context.unprotect_rtp(&input, &header, 0);
+            let result = context.unprotect_rtp(&input, &mut header, 0, &ExtensionMap::empty());
             assert!(
                 result.is_none(),
                 "Should fail to decrypt a SRTP packet with null tag"
@@ -1023,4 +1431,577 @@ mod test {
             )
         }
     }
+
+    mod test_aead_aes_256_gcm {
+        use crate::rtp_::ExtensionMap;
+
+        use super::*;
+
+        use super::test_aead_aes_128_gcm::rfc7714::{
+            PLAINTEXT_RTCP_PACKET, PLAINTEXT_RTP_PACKET, SALT,
+        };
+
+        // Test vectors from RFC7714 section 16.2, which use the same plaintext and salt
+        // as the AEAD_AES_128_GCM ones.
+
+        // Session Key (RTP and RTCP)
+        const KEY: [u8; 32] = [
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+            0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b,
+            0x1c, 0x1d, 0x1e, 0x1f,
+        ];
+
+        /// Full encrypted RTP packet. First 12 octets is the header.
+        const PROTECTED_RTP_PACKET: &[u8] = &[
+            0x80, 0x40, 0xf1, 0x7b, 0x80, 0x41, 0xf8, 0xd3, 0x55, 0x01, 0xa0, 0xb2, 0x32, 0xb1,
+            0xde, 0x78, 0xa8, 0x22, 0xfe, 0x12, 0xef, 0x9f, 0x78, 0xfa, 0x33, 0x2e, 0x33, 0xaa,
+            0xb1, 0x80, 0x12, 0x38, 0x9a, 0x58, 0xe2, 0xf3, 0xb5, 0x0b, 0x2a, 0x02, 0x76, 0xff,
+            0xae, 0x0f, 0x1b, 0xa6, 0x37, 0x99, 0xb8, 0x7b, 0x7a, 0xa3, 0xdb, 0x36, 0xdf, 0xff,
+            0xd6, 0xb0, 0xf9, 0xbb, 0x78, 0x78, 0xd7, 0xa7, 0x6c, 0x13,
+        ];
+
+        /// Full encrypted RTCP packet
+        const PROTECTED_RTCP_PACKET: &[u8] = &[
+            0x81, 0xc8, 0x00, 0x0d, 0x4d, 0x61, 0x72, 0x73, 0xd5, 0x0a, 0xe4, 0xd1, 0xf5, 0xce,
+            0x5d, 0x30, 0x4b, 0xa2, 0x97, 0xe4, 0x7d, 0x47, 0x0c, 0x28, 0x2c, 0x3e, 0xce, 0x5d,
+            0xbf, 0xfe, 0x0a, 0x50, 0xa2, 0xea, 0xa5, 0xc1, 0x11, 0x05, 0x55, 0xbe, 0x84, 0x15,
+            0xf6, 0x58, 0xc6, 0x1d, 0xe0, 0x47, 0x6f, 0x1b, 0x6f, 0xad, 0x1d, 0x1e, 0xb3, 0x0c,
+            0x44, 0x46, 0x83, 0x9f, 0x57, 0xff, 0x6f, 0x6c, 0xb2, 0x6a, 0xc3, 0xbe, 0x80, 0x00,
+            0x05, 0xd4,
+        ];
+
+        #[test]
+        fn protect_rtp_rfc_7714_test() {
+            let mut context = make_context(0);
+
+            let header = RtpHeader::parse(&PLAINTEXT_RTP_PACKET[..12], &ExtensionMap::empty())
+                .expect("header to parse");
+            let out = context.protect_rtp(PLAINTEXT_RTP_PACKET, &header, 0);
+
+            assert_eq!(out, PROTECTED_RTP_PACKET);
+        }
+
+        #[test]
+        fn unprotect_rtp_rfc_7714_test() {
+            let mut context = make_context(0);
+            let mut header = RtpHeader::parse(&PROTECTED_RTP_PACKET[..12], &ExtensionMap::empty())
+                .expect("header to parse");
+
+            let out = context
+                .unprotect_rtp(PROTECTED_RTP_PACKET, &mut header, 0, &ExtensionMap::empty())
+                .expect("decrypt rtp");
+
+            assert_eq!(out, PLAINTEXT_RTP_PACKET[12..]);
+        }
+
+        #[test]
+        fn protect_rtcp_rfc_7714_test() {
+            let mut context = make_context(0x000005d4);
+
+            let out = context.protect_rtcp(PLAINTEXT_RTCP_PACKET);
+
+            assert_eq!(out, PROTECTED_RTCP_PACKET);
+        }
+
+        #[test]
+        fn unprotect_rtcp_rfc_7714_test() {
+            let mut context = make_context(0);
+
+            let out = context
+                .unprotect_rtcp(PROTECTED_RTCP_PACKET)
+                .expect("Unprotect RTCP");
+
+            assert_eq!(out, PLAINTEXT_RTCP_PACKET);
+        }
+
+        fn make_context(srtcp_index: u32) -> SrtpContext {
+            crate::init_crypto_default();
+            let crypto = crate::CryptoProvider::from_feature_flags().srtp_crypto();
+
+            let derived = || Derived::AeadAesGcm {
+                profile: SrtpProfile::AeadAes256Gcm,
+                salt: SALT,
+                enc: AesGcm::Aes256(crypto.new_aead_aes_256_gcm(KEY, true)),
+                dec: AesGcm::Aes256(crypto.new_aead_aes_256_gcm(KEY, false)),
+            };
+
+            SrtpContext {
+                srtcp_index,
+                ..SrtpContext::with_derived(derived(), derived())
+            }
+        }
+    }
+
+    #[test]
+    fn aes_256_profiles_round_trip() {
+        crate::init_crypto_default();
+        let crypto = crate::CryptoProvider::from_feature_flags().srtp_crypto();
+
+        // 12 byte header and a payload of one AES block.
+        let rtp: Vec<u8> = [
+            0x80, 0x60, 0x12, 0x34, 0, 0, 0x30, 0x39, 0xca, 0xfe, 0xba, 0xbe,
+        ]
+        .into_iter()
+        .chain(0..16)
+        .collect();
+        let header = RtpHeader::parse(&rtp, &crate::rtp_::ExtensionMap::empty()).unwrap();
+
+        // Sender report with no report blocks.
+        let rtcp = [
+            0x80, 0xc8, 0x00, 0x06, 0xca, 0xfe, 0xba, 0xbe, 1, 2, 3, 4, 5, 6, 7, 8, 0, 0, 0, 1, 0,
+            0, 0, 2, 0, 0, 0, 3,
+        ];
+
+        for profile in [SrtpProfile::Aes256CmSha1_80, SrtpProfile::AeadAes256Gcm] {
+            let mat = KeyingMaterial::new((0..profile.keying_material_len() as u8).collect());
+            let mut tx = SrtpContext::new(&crypto, profile, &mat, true);
+            let mut rx = SrtpContext::new(&crypto, profile, &mat, true);
+            assert_eq!(tx.profile(), profile);
+
+            let protected = tx.protect_rtp(&rtp, &header, 7);
+            assert_ne!(&protected[12..28], &rtp[12..]);
+            let unprotected = rx
+                .unprotect_rtp(&protected, &mut header.clone(), 7, &ExtensionMap::empty())
+                .unwrap();
+            assert_eq!(unprotected, &rtp[12..]);
+
+            // Wrong rollover counter fails authentication.
+            assert!(rx
+                .unprotect_rtp(
+                    &protected,
+                    &mut header.clone(),
+                    7 + (1 << 16),
+                    &ExtensionMap::empty()
+                )
+                .is_none());
+
+            let protected = tx.protect_rtcp(&rtcp);
+            let unprotected = rx.unprotect_rtcp(&protected).unwrap();
+            assert_eq!(unprotected, rtcp);
+
+            // The other direction uses different keys.
+            let mut other = SrtpContext::new(&crypto, profile, &mat, false);
+            assert!(other.unprotect_rtcp(&protected).is_none());
+        }
+    }
+
+    #[test]
+    fn cryptex_round_trip() {
+        use crate::rtp_::Extension;
+
+        crate::init_crypto_default();
+        let crypto = crate::CryptoProvider::from_feature_flags().srtp_crypto();
+
+        let mut exts = ExtensionMap::empty();
+        exts.set(3, Extension::AudioLevel);
+
+        // One CSRC, a Cryptex one byte extension block with audio level and a payload
+        // of one AES block.
+        let rtp: Vec<u8> = [
+            0x91, 0x60, 0x12, 0x34, 0, 0, 0x30, 0x39, 0xca, 0xfe, 0xba, 0xbe, 1, 2, 3, 4, 0xc0,
+            0xde, 0, 1, 0x30, 0xaa, 0, 0,
+        ]
+        .into_iter()
+        .chain(0..16)
+        .collect();
+        let header = RtpHeader::parse(&rtp, &exts).unwrap();
+        assert!(header.cryptex);
+        assert_eq!(header.header_len, 24);
+
+        for profile in [SrtpProfile::Aes128CmSha1_80, SrtpProfile::AeadAes128Gcm] {
+            let mat = KeyingMaterial::new((0..profile.keying_material_len() as u8).collect());
+            let mut tx = SrtpContext::new(&crypto, profile, &mat, true);
+            let mut rx = SrtpContext::new(&crypto, profile, &mat, true);
+
+            let protected = tx.protect_rtp(&rtp, &header, 7);
+
+            // Only the fixed header and the extension header are in the clear.
+            assert_eq!(&protected[..12], &rtp[..12]);
+            assert_ne!(&protected[12..16], &rtp[12..16]);
+            assert_eq!(&protected[16..20], &rtp[16..20]);
+            assert_ne!(&protected[20..24], &rtp[20..24]);
+
+            let mut rx_header = RtpHeader::parse(&protected, &exts).unwrap();
+            assert_eq!(rx_header.ext_vals.audio_level, None);
+
+            let payload = rx
+                .unprotect_rtp(&protected, &mut rx_header, 7, &exts)
+                .unwrap();
+            assert_eq!(payload, &rtp[24..]);
+            assert!(rx_header.cryptex);
+            assert_eq!(rx_header.header_len, 24);
+            assert_eq!(rx_header.ext_vals.audio_level, Some(-42));
+            assert_eq!(rx_header.ext_vals.voice_activity, Some(true));
+        }
+    }
+
+    mod test_cryptex_rfc_9335 {
+        use crate::rtp_::Extension;
+
+        use super::*;
+
+        // Test vectors from RFC 9335 Appendix A. Each is a plain RTP packet and the same
+        // packet protected with Cryptex.
+
+        mod aes_cm {
+            pub(super) const MASTER_KEY: [u8; 16] = [
+                0xe1, 0xf9, 0x7a, 0x0d, 0x3e, 0x01, 0x8b, 0xe0, 0xd6, 0x4f, 0xa3, 0x2c, 0x06, 0xde,
+                0x41, 0x39,
+            ];
+
+            pub(super) const MASTER_SALT: [u8; 14] = [
+                0x0e, 0xc6, 0x75, 0xad, 0x49, 0x8a, 0xfe, 0xeb, 0xb6, 0x96, 0x0b, 0x3a, 0xab, 0xe6,
+            ];
+
+            // RTP packet with one-byte header extension
+            pub(super) const ONE_BYTE: (&[u8], &[u8]) = (
+                &[
+                    0x90, 0x0f, 0x12, 0x35, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0xbe,
+                    0xde, 0x00, 0x01, 0x51, 0x00, 0x02, 0x00, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab,
+                    0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab,
+                ],
+                &[
+                    0x90, 0x0f, 0x12, 0x35, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0xc0,
+                    0xde, 0x00, 0x01, 0xeb, 0x92, 0x36, 0x52, 0x51, 0xc3, 0xe0, 0x36, 0xf8, 0xde,
+                    0x27, 0xe9, 0xc2, 0x7e, 0xe3, 0xe0, 0xb4, 0x65, 0x1d, 0x9f, 0xbc, 0x42, 0x18,
+                    0xa7, 0x02, 0x44, 0x52, 0x2f, 0x34, 0xa5,
+                ],
+            );
+
+            // RTP packet with two-byte header extension
+            pub(super) const TWO_BYTE: (&[u8], &[u8]) = (
+                &[
+                    0x90, 0x0f, 0x12, 0x36, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0x10,
+                    0x00, 0x00, 0x01, 0x05, 0x02, 0x00, 0x02, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab,
+                    0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab,
+                ],
+                &[
+                    0x90, 0x0f, 0x12, 0x36, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0xc2,
+                    0xde, 0x00, 0x01, 0x4e, 0xd9, 0xcc, 0x4e, 0x6a, 0x71, 0x2b, 0x30, 0x96, 0xc5,
+                    0xca, 0x77, 0x33, 0x9d, 0x42, 0x04, 0xce, 0x0d, 0x77, 0x39, 0x6c, 0xab, 0x69,
+                    0x58, 0x5f, 0xbc, 0xe3, 0x81, 0x94, 0xa5,
+                ],
+            );
+
+            // RTP packet with one-byte header extension and CSRC fields
+            pub(super) const ONE_BYTE_CSRC: (&[u8], &[u8]) = (
+                &[
+                    0x92, 0x0f, 0x12, 0x38, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0x00,
+                    0x01, 0xe2, 0x40, 0x00, 0x00, 0xb2, 0x6e, 0xbe, 0xde, 0x00, 0x01, 0x51, 0x00,
+                    0x02, 0x00, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab,
+                    0xab, 0xab, 0xab, 0xab, 0xab,
+                ],
+                &[
+                    0x92, 0x0f, 0x12, 0x38, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0x8b,
+                    0xb6, 0xe1, 0x2b, 0x5c, 0xff, 0x16, 0xdd, 0xc0, 0xde, 0x00, 0x01, 0x92, 0x83,
+                    0x8c, 0x8c, 0x09, 0xe5, 0x83, 0x93, 0xe1, 0xde, 0x3a, 0x9a, 0x74, 0x73, 0x4d,
+                    0x67, 0x45, 0x67, 0x13, 0x38, 0xc3, 0xac, 0xf1, 0x1d, 0xa2, 0xdf, 0x84, 0x23,
+                    0xbe, 0xe0,
+                ],
+            );
+
+            // RTP packet with two-byte header extension and CSRC fields
+            pub(super) const TWO_BYTE_CSRC: (&[u8], &[u8]) = (
+                &[
+                    0x92, 0x0f, 0x12, 0x39, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0x00,
+                    0x01, 0xe2, 0x40, 0x00, 0x00, 0xb2, 0x6e, 0x10, 0x00, 0x00, 0x01, 0x05, 0x02,
+                    0x00, 0x02, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab,
+                    0xab, 0xab, 0xab, 0xab, 0xab,
+                ],
+                &[
+                    0x92, 0x0f, 0x12, 0x39, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0xf7,
+                    0x0e, 0x51, 0x3e, 0xb9, 0x0b, 0x9b, 0x25, 0xc2, 0xde, 0x00, 0x01, 0xbb, 0xed,
+                    0x48, 0x48, 0xfa, 0xa6, 0x44, 0x66, 0x5f, 0x3d, 0x7f, 0x34, 0x12, 0x59, 0x14,
+                    0xe9, 0xf4, 0xd0, 0xae, 0x92, 0x3c, 0x6f, 0x47, 0x9b, 0x95, 0xa0, 0xf7, 0xb5,
+                    0x31, 0x33,
+                ],
+            );
+
+            // RTP packet with empty two-byte header extension and CSRC fields
+            pub(super) const EMPTY_TWO_BYTE_CSRC: (&[u8], &[u8]) = (
+                &[
+                    0x92, 0x0f, 0x12, 0x3b, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0x00,
+                    0x01, 0xe2, 0x40, 0x00, 0x00, 0xb2, 0x6e, 0x10, 0x00, 0x00, 0x00, 0xab, 0xab,
+                    0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab,
+                    0xab,
+                ],
+                &[
+                    0x92, 0x0f, 0x12, 0x3b, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0xcb,
+                    0xf2, 0x4c, 0x12, 0x43, 0x30, 0xe1, 0xc8, 0xc2, 0xde, 0x00, 0x00, 0x59, 0x9d,
+                    0xd4, 0x5b, 0xc9, 0xd6, 0x87, 0xb6, 0x03, 0xe8, 0xb5, 0x9d, 0x77, 0x1f, 0xd3,
+                    0x8e, 0x88, 0xb1, 0x70, 0xe0, 0xcd, 0x31, 0xe1, 0x25, 0xea, 0xbe,
+                ],
+            );
+        }
+
+        mod aead_aes_gcm {
+            pub(super) const MASTER_KEY: [u8; 16] = [
+                0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+                0x0e, 0x0f,
+            ];
+
+            pub(super) const MASTER_SALT: [u8; 12] = [
+                0xa0, 0xa1, 0xa2, 0xa3, 0xa4, 0xa5, 0xa6, 0xa7, 0xa8, 0xa9, 0xaa, 0xab,
+            ];
+
+            // RTP packet with two-byte header extension
+            pub(super) const TWO_BYTE: (&[u8], &[u8]) = (
+                &[
+                    0x90, 0x0f, 0x12, 0x36, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0x10,
+                    0x00, 0x00, 0x01, 0x05, 0x02, 0x00, 0x02, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab,
+                    0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab,
+                ],
+                &[
+                    0x90, 0x0f, 0x12, 0x36, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0xc2,
+                    0xde, 0x00, 0x01, 0xbb, 0x75, 0xa4, 0xc5, 0x45, 0xcd, 0x1f, 0x41, 0x3b, 0xdb,
+                    0x7d, 0xaa, 0x2b, 0x1e, 0x32, 0x63, 0xde, 0x31, 0x36, 0x67, 0xc9, 0x63, 0x24,
+                    0x90, 0x81, 0xb3, 0x5a, 0x65, 0xf5, 0xcb, 0x6c, 0x88, 0xb3, 0x94, 0x23, 0x5f,
+                ],
+            );
+
+            // RTP packet with one-byte header extension and CSRC fields
+            pub(super) const ONE_BYTE_CSRC: (&[u8], &[u8]) = (
+                &[
+                    0x92, 0x0f, 0x12, 0x38, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0x00,
+                    0x01, 0xe2, 0x40, 0x00, 0x00, 0xb2, 0x6e, 0xbe, 0xde, 0x00, 0x01, 0x51, 0x00,
+                    0x02, 0x00, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab,
+                    0xab, 0xab, 0xab, 0xab, 0xab,
+                ],
+                &[
+                    0x92, 0x0f, 0x12, 0x38, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0x63,
+                    0xbb, 0xcc, 0xc4, 0xa7, 0xf6, 0x95, 0xc4, 0xc0, 0xde, 0x00, 0x01, 0x8a, 0xd7,
+                    0xc7, 0x1f, 0xac, 0x70, 0xa8, 0x0c, 0x92, 0x86, 0x6b, 0x4c, 0x6b, 0xa9, 0x85,
+                    0x46, 0xef, 0x91, 0x35, 0x86, 0xe9, 0x5f, 0xfa, 0xaf, 0xfe, 0x95, 0x68, 0x85,
+                    0xbb, 0x06, 0x47, 0xa8, 0xbc, 0x09, 0x4a, 0xc8,
+                ],
+            );
+
+            // RTP packet with two-byte header extension and CSRC fields
+            pub(super) const TWO_BYTE_CSRC: (&[u8], &[u8]) = (
+                &[
+                    0x92, 0x0f, 0x12, 0x39, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0x00,
+                    0x01, 0xe2, 0x40, 0x00, 0x00, 0xb2, 0x6e, 0x10, 0x00, 0x00, 0x01, 0x05, 0x02,
+                    0x00, 0x02, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab,
+                    0xab, 0xab, 0xab, 0xab, 0xab,
+                ],
+                &[
+                    0x92, 0x0f, 0x12, 0x39, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0x36,
+                    0x80, 0x52, 0x4f, 0x8d, 0x31, 0x2b, 0x00, 0xc2, 0xde, 0x00, 0x01, 0xc7, 0x8d,
+                    0x12, 0x00, 0x38, 0x42, 0x2b, 0xc1, 0x11, 0xa7, 0x18, 0x7a, 0x18, 0x24, 0x6f,
+                    0x98, 0x0c, 0x05, 0x9c, 0xc6, 0xbc, 0x9d, 0xf8, 0xb6, 0x26, 0x39, 0x4e, 0xca,
+                    0x34, 0x4e, 0x4b, 0x05, 0xd8, 0x0f, 0xea, 0x83,
+                ],
+            );
+
+            // RTP packet with empty one-byte header extension and CSRC fields
+            pub(super) const EMPTY_ONE_BYTE_CSRC: (&[u8], &[u8]) = (
+                &[
+                    0x92, 0x0f, 0x12, 0x3a, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0x00,
+                    0x01, 0xe2, 0x40, 0x00, 0x00, 0xb2, 0x6e, 0xbe, 0xde, 0x00, 0x00, 0xab, 0xab,
+                    0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab,
+                    0xab,
+                ],
+                &[
+                    0x92, 0x0f, 0x12, 0x3a, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0x15,
+                    0xb6, 0xbb, 0x43, 0x37, 0x90, 0x6f, 0xff, 0xc0, 0xde, 0x00, 0x00, 0xb7, 0xb9,
+                    0x64, 0x53, 0x7a, 0x2b, 0x03, 0xab, 0x7b, 0xa5, 0x38, 0x9c, 0xe9, 0x33, 0x17,
+                    0x12, 0x6b, 0x5d, 0x97, 0x4d, 0xf3, 0x0c, 0x68, 0x84, 0xdc, 0xb6, 0x51, 0xc5,
+                    0xe1, 0x20, 0xc1, 0xda,
+                ],
+            );
+
+            // RTP packet with empty two-byte header extension and CSRC fields
+            pub(super) const EMPTY_TWO_BYTE_CSRC: (&[u8], &[u8]) = (
+                &[
+                    0x92, 0x0f, 0x12, 0x3b, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0x00,
+                    0x01, 0xe2, 0x40, 0x00, 0x00, 0xb2, 0x6e, 0x10, 0x00, 0x00, 0x00, 0xab, 0xab,
+                    0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab,
+                    0xab,
+                ],
+                &[
+                    0x92, 0x0f, 0x12, 0x3b, 0xde, 0xca, 0xfb, 0xad, 0xca, 0xfe, 0xba, 0xbe, 0xdc,
+                    0xb3, 0x8c, 0x9e, 0x48, 0xbf, 0x95, 0xf4, 0xc2, 0xde, 0x00, 0x00, 0x61, 0xee,
+                    0x43, 0x2c, 0xf9, 0x20, 0x31, 0x70, 0x76, 0x61, 0x32, 0x58, 0xd3, 0xce, 0x42,
+                    0x36, 0xc0, 0x6a, 0xc4, 0x29, 0x68, 0x1a, 0xd0, 0x84, 0x13, 0x51, 0x2d, 0xc9,
+                    0x8b, 0x52, 0x07, 0xd8,
+                ],
+            );
+        }
+
+        #[test]
+        fn aes_cm_sha1_80() {
+            let mat = [&aes_cm::MASTER_KEY[..], &aes_cm::MASTER_SALT].concat();
+
+            for vector in [
+                aes_cm::ONE_BYTE,
+                aes_cm::TWO_BYTE,
+                aes_cm::ONE_BYTE_CSRC,
+                aes_cm::TWO_BYTE_CSRC,
+                aes_cm::EMPTY_TWO_BYTE_CSRC,
+            ] {
+                check(SrtpProfile::Aes128CmSha1_80, &mat, vector);
+            }
+        }
+
+        #[test]
+        fn aead_aes_128_gcm() {
+            let mat = [&aead_aes_gcm::MASTER_KEY[..], &aead_aes_gcm::MASTER_SALT].concat();
+
+            for vector in [
+                aead_aes_gcm::TWO_BYTE,
+                aead_aes_gcm::ONE_BYTE_CSRC,
+                aead_aes_gcm::TWO_BYTE_CSRC,
+                aead_aes_gcm::EMPTY_ONE_BYTE_CSRC,
+                aead_aes_gcm::EMPTY_TWO_BYTE_CSRC,
+            ] {
+                check(SrtpProfile::AeadAes128Gcm, &mat, vector);
+            }
+        }
+
+        /// `master` is the master key followed by the master salt.
+        fn check(profile: SrtpProfile, master: &[u8], (plain, protected): (&[u8], &[u8])) {
+            crate::init_crypto_default();
+            let crypto = crate::CryptoProvider::from_feature_flags().srtp_crypto();
+
+            // Both directions use the same master key.
+            let (key, salt) = master.split_at(16);
+            let mat = KeyingMaterial::new([key, key, salt, salt].concat());
+            let mut tx = SrtpContext::new(&crypto, profile, &mat, true);
+            let mut rx = SrtpContext::new(&crypto, profile, &mat, true);
+
+            // The extension value in the vectors fits the transport sequence number.
+            let mut exts = ExtensionMap::empty();
+            exts.set(5, Extension::TransportSequenceNumber);
+
+            let header = RtpHeader::parse(plain, &exts).unwrap();
+            let srtp_index = header.sequence_number as u64;
+
+            // The sender rewrites the extension profile to 0xC0DE or 0xC2DE.
+            let at = 12 + csrc_len(plain);
+            let form = ExtensionsForm::parse([plain[at], plain[at + 1]]).unwrap();
+            let mut rewritten = plain.to_vec();
+            rewritten[at..at + 2].copy_from_slice(&form.serialize_cryptex());
+            let tx_header = RtpHeader {
+                cryptex: true,
+                ..header.clone()
+            };
+
+            let out = tx.protect_rtp(&rewritten, &tx_header, srtp_index);
+            assert_eq!(out, protected);
+
+            let mut rx_header = RtpHeader::parse(protected, &exts).unwrap();
+            assert!(rx_header.cryptex);
+            assert_eq!(rx_header.ext_vals.transport_cc, None);
+
+            let payload = rx
+                .unprotect_rtp(protected, &mut rx_header, srtp_index, &exts)
+                .unwrap();
+            assert_eq!(payload, &plain[header.header_len..]);
+            assert!(rx_header.cryptex);
+            assert_eq!(rx_header.header_len, header.header_len);
+            assert_eq!(rx_header.ext_vals, header.ext_vals);
+        }
+    }
+
+    #[test]
+    fn mki_is_stripped() {
+        crate::init_crypto_default();
+        let crypto = crate::CryptoProvider::from_feature_flags().srtp_crypto();
+
+        let rtp: Vec<u8> = [
+            0x80, 0x60, 0x12, 0x34, 0, 0, 0x30, 0x39, 0xca, 0xfe, 0xba, 0xbe,
+        ]
+        .into_iter()
+        .chain(0..16)
+        .collect();
+        let header = RtpHeader::parse(&rtp, &crate::rtp_::ExtensionMap::empty()).unwrap();
+
+        let rtcp = [
+            0x80, 0xc8, 0x00, 0x06, 0xca, 0xfe, 0xba, 0xbe, 1, 2, 3, 4, 5, 6, 7, 8, 0, 0, 0, 1, 0,
+            0, 0, 2, 0, 0, 0, 3,
+        ];
+
+        let mki = [0, 0, 0, 1];
+
+        // Insert the MKI where a sender using one would put it.
+        let with_mki = |buf: Vec<u8>, tag_len: usize| {
+            let mut v = buf;
+            let at = v.len() - tag_len;
+            v.splice(at..at, mki);
+            v
+        };
+
+        for (profile, tag_len) in [
+            (
+                SrtpProfile::Aes128CmSha1_80,
+                aes_128_cm_sha1_80::HMAC_TAG_LEN,
+            ),
+            (SrtpProfile::AeadAes128Gcm, 0),
+        ] {
+            let mat = KeyingMaterial::new((0..profile.keying_material_len() as u8).collect());
+            let mut tx = SrtpContext::new(&crypto, profile, &mat, true);
+            let mut rx = SrtpContext::new(&crypto, profile, &mat, true);
+            rx.set_mki(Some(mki.to_vec()));
+
+            let protected = tx.protect_rtp(&rtp, &header, 7);
+
+            // Without the MKI the packet is dropped.
+            assert!(rx
+                .unprotect_rtp(&protected, &mut header.clone(), 7, &ExtensionMap::empty())
+                .is_none());
+
+            let protected = with_mki(protected, tag_len);
+            let unprotected = rx
+                .unprotect_rtp(&protected, &mut header.clone(), 7, &ExtensionMap::empty())
+                .unwrap();
+            assert_eq!(unprotected, &rtp[12..]);
+
+            let protected = with_mki(tx.protect_rtcp(&rtcp), tag_len);
+            let unprotected = rx.unprotect_rtcp(&protected).unwrap();
+            assert_eq!(unprotected, rtcp);
+
+            // Wrong MKI.
+            rx.set_mki(Some(vec![0, 0, 0, 2]));
+            assert!(rx.unprotect_rtcp(&protected).is_none());
+        }
+    }
+
+    #[test]
+    fn lifetime_is_enforced() {
+        crate::init_crypto_default();
+        let crypto = crate::CryptoProvider::from_feature_flags().srtp_crypto();
+
+        let rtp: Vec<u8> = [
+            0x80, 0x60, 0x12, 0x34, 0, 0, 0x30, 0x39, 0xca, 0xfe, 0xba, 0xbe,
+        ]
+        .into_iter()
+        .chain(0..16)
+        .collect();
+        let header = RtpHeader::parse(&rtp, &ExtensionMap::empty()).unwrap();
+        let rtcp = [0x80, 0xc8, 0x00, 0x01, 0xca, 0xfe, 0xba, 0xbe];
+
+        let profile = SrtpProfile::Aes128CmSha1_80;
+        let mat = KeyingMaterial::new((0..profile.keying_material_len() as u8).collect());
+        let mut tx = SrtpContext::new(&crypto, profile, &mat, true);
+        let mut rx = SrtpContext::new(&crypto, profile, &mat, true);
+        rx.set_lifetime(Some(3), 1);
+
+        let protected = tx.protect_rtp(&rtp, &header, 7);
+        let unprotect = |rx: &mut SrtpContext, buf: &[u8]| {
+            rx.unprotect_rtp(buf, &mut header.clone(), 7, &ExtensionMap::empty())
+        };
+
+        // Packets failing authentication don't use up the lifetime.
+        let mut broken = protected.clone();
+        broken[12] ^= 1;
+        assert!(unprotect(&mut rx, &broken).is_none());
+        assert_eq!(rx.packets(), 1);
+
+        assert!(unprotect(&mut rx, &protected).is_some());
+        assert!(rx.unprotect_rtcp(&tx.protect_rtcp(&rtcp)).is_some());
+        assert_eq!(rx.packets(), 3);
+
+        assert!(unprotect(&mut rx, &protected).is_none());
+        assert!(rx.unprotect_rtcp(&tx.protect_rtcp(&rtcp)).is_none());
+    }
 }
@@ -5,16 +5,21 @@ use std::fmt;
 use std::net::SocketAddr;
 use std::panic::UnwindSafe;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
-use sctp_proto::{Association, AssociationHandle, ClientConfig, DatagramEvent};
+use sctp_proto::{
+    Association, AssociationHandle, Chunks, ClientConfig, DatagramEvent, PartialRead,
+};
 use sctp_proto::{Endpoint, EndpointConfig, Stream, StreamEvent, Transmit};
-use sctp_proto::{Event, Payload, PayloadProtocolIdentifier, ServerConfig};
+use sctp_proto::{Event, Payload, PayloadProtocolIdentifier, ServerConfig, TransportConfig};
 use thiserror::Error;
 
 pub use sctp_proto::Error as ProtoError;
 use sctp_proto::ReliabilityType;
 
+use crate::channel::ChannelState;
+use crate::stats::SctpStats;
+
 mod dcep;
 use dcep::DcepOpen;
 
@@ -31,6 +36,33 @@ pub enum SctpError {
     #[error("Write on a stream before it was established")]
     WriteBeforeEstablished,
 
+    /// The write would take the buffered amount over the high threshold.
+    ///
+    /// Wait for [`Event::ChannelBufferedAmountLow`][crate::Event::ChannelBufferedAmountLow]
+    /// before writing more.
+    #[error("Write would exceed the buffered amount high threshold")]
+    WouldBlock,
+
+    /// Write on a channel that is closing.
+    #[error("Write on a stream that is closing")]
+    Closing,
+
+    /// The message is larger than the remote peer accepts (`a=max-message-size`).
+    #[error("Message of {0} bytes exceeds remote max message size of {1}")]
+    MessageTooLarge(usize, usize),
+
+    /// Appending to, or finishing, a message that was never begun.
+    #[error("No message begun on the stream")]
+    NoMessageBegun,
+
+    /// Beginning a message while another is in progress on the stream.
+    #[error("A message is already in progress on the stream")]
+    MessageInProgress,
+
+    /// Beginning a message in pieces without reliable delivery.
+    #[error("A message written in pieces must be reliable")]
+    MessageNotReliable,
+
     /// The initial DCEP is not valid.
     #[error("DCEP open message too small")]
     DcepOpenTooSmall,
@@ -52,8 +84,42 @@ pub(crate) struct RtcSctp {
     pushed_back_transmit: Option<VecDeque<Vec<u8>>>,
     last_now: Instant,
     client: bool,
+    /// We started a SHUTDOWN.
+    shutdown: bool,
+    /// How the remote ended the association, as seen in incoming chunks.
+    remote_close: Option<SctpClose>,
+    /// Largest message we accept from the remote peer.
+    local_max_message_size: usize,
+    /// Largest message the remote peer accepts from us.
+    remote_max_message_size: usize,
+    /// Virtual time of the scheduler, which is the start of the last message handed over.
+    vtime: u64,
 }
 
+/// The remote max-message-size until told otherwise (RFC 8841).
+pub(crate) const DEFAULT_MAX_MESSAGE_SIZE: usize = 65_536;
+
+/// The size we advertise in a=max-message-size, unless configured.
+pub(crate) const DEFAULT_LOCAL_MAX_MESSAGE_SIZE: usize = 262_144;
+
+/// Same as the default in sctp-proto.
+const DEFAULT_RECEIVE_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Largest chunk handed out for channels receiving in chunks.
+const RECEIVE_CHUNK_SIZE: usize = 16_384;
+
+/// Channel priority "low" of `RTCPriorityType`, which is what browsers use by default.
+const DEFAULT_PRIORITY: u16 = 256;
+
+/// Max bytes handed to sctp-proto, but not yet acknowledged, before the scheduler holds
+/// back. Without I-DATA, sctp-proto sends in the order it was written to, so this is how
+/// long a high priority message can be stuck behind others. With I-DATA, the streams are
+/// interleaved, and a stream with nothing handed over can always send.
+const SEND_WINDOW: usize = 256 * 1024;
+
+/// Scale of the virtual time of the scheduler, to not lose precision dividing by priority.
+const VTIME_SCALE: u64 = 1024;
+
 /// This is okay because there is no way for a user of Rtc to interact with the Sctp subsystem
 /// in a way that would allow them to observe a potentially broken invariant when catching a panic.
 impl UnwindSafe for RtcSctp {}
@@ -64,6 +130,7 @@ enum RtcSctpState {
     AwaitRemoteAssociation,
     AwaitAssociationEstablished,
     Established,
+    Closed,
 }
 
 impl RtcSctpState {
@@ -86,6 +153,117 @@ struct StreamEntry {
     id: u16,
     /// If we are to close this entry.
     do_close: bool,
+    /// If we have reset the outgoing stream, or the remote did.
+    reset: bool,
+    /// Writes are refused above this buffered amount.
+    buffered_amount_high: Option<usize>,
+    /// Threshold for the BufferedAmountLow event.
+    buffered_amount_low: usize,
+    /// If the buffered amount went above the low threshold since the last event.
+    above_low: bool,
+    /// Messages waiting for the scheduler, highest priority first.
+    queue: VecDeque<QueuedMessage>,
+    /// Total bytes in the queue.
+    queued: usize,
+    /// Reliability params currently set on the sctp-proto stream.
+    params: SendParams,
+    /// Virtual time when the next message of this stream would start.
+    vtime: u64,
+    /// Counters for the channel stats.
+    counters: StreamCounters,
+    /// Deliver incoming messages in chunks.
+    chunked: bool,
+    /// Incoming message being delivered in chunks.
+    reading: Option<ChunkedRead>,
+    /// sctp-proto is handing out an incoming message in pieces.
+    partial_read: bool,
+    /// Queued messages that sctp-proto refused, not yet reported.
+    failed_writes: usize,
+}
+
+/// An incoming message being handed out in chunks, as it arrives.
+#[derive(Debug)]
+struct ChunkedRead {
+    binary: bool,
+    start: bool,
+    /// The last piece read from sctp-proto, handed out up to `offset`.
+    piece: PartialRead,
+    offset: usize,
+    /// Length of the piece, without the padding of an empty message.
+    len: usize,
+    /// Bytes of the message so far.
+    received: usize,
+}
+
+/// An outgoing message written in pieces.
+#[derive(Debug, Default)]
+struct Streamed {
+    /// Some of the message is handed to sctp-proto.
+    started: bool,
+    /// No more pieces are appended.
+    finished: bool,
+    /// Bytes appended so far.
+    len: usize,
+}
+
+/// A message waiting to be handed to sctp-proto.
+#[derive(Debug)]
+struct QueuedMessage {
+    data: Vec<u8>,
+    ppi: PayloadProtocolIdentifier,
+    params: SendParams,
+    priority: u8,
+    /// Dropped if still queued at this time (MaxPacketLifetime).
+    expires: Option<Instant>,
+    /// Written in pieces, `data` is what is appended and not yet handed over.
+    streamed: Option<Streamed>,
+}
+
+impl QueuedMessage {
+    /// Written in pieces, and not finished.
+    fn is_open(&self) -> bool {
+        self.streamed.as_ref().is_some_and(|s| !s.finished)
+    }
+
+    fn is_started(&self) -> bool {
+        self.streamed.as_ref().is_some_and(|s| s.started)
+    }
+
+    /// Something to hand to sctp-proto.
+    fn is_ready(&self) -> bool {
+        match &self.streamed {
+            // The last byte is kept until finished, to end the message with.
+            Some(s) => s.finished || self.data.len() > 1,
+            None => true,
+        }
+    }
+}
+
+/// The reliability params of a sctp-proto stream.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SendParams {
+    unordered: bool,
+    typ: ReliabilityType,
+    value: u32,
+}
+
+impl Default for SendParams {
+    fn default() -> Self {
+        SendParams {
+            unordered: false,
+            typ: ReliabilityType::Reliable,
+            value: 0,
+        }
+    }
+}
+
+/// Data messages, not counting DCEP.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct StreamCounters {
+    pub messages_sent: u64,
+    pub bytes_sent: u64,
+    pub messages_received: u64,
+    pub bytes_received: u64,
 }
 
 pub(crate) enum SctpEvent {
@@ -96,6 +274,10 @@ pub(crate) enum SctpEvent {
         id: u16,
         label: String,
     },
+    /// The remote reset its outgoing stream.
+    Closing {
+        id: u16,
+    },
     Close {
         id: u16,
     },
@@ -104,6 +286,36 @@ pub(crate) enum SctpEvent {
         binary: bool,
         data: Vec<u8>,
     },
+    Chunk {
+        id: u16,
+        binary: bool,
+        start: bool,
+        end: bool,
+        abandoned: bool,
+        data: Vec<u8>,
+    },
+    BufferedAmountLow {
+        id: u16,
+    },
+    /// A queued message could not be handed to sctp-proto and is lost.
+    WriteFailed {
+        id: u16,
+    },
+    /// The association is closed, after all streams are closed.
+    Closed {
+        reason: SctpClose,
+    },
+}
+
+/// How the association ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SctpClose {
+    /// Our SHUTDOWN completed.
+    Local,
+    /// The remote did a SHUTDOWN.
+    Shutdown,
+    /// The remote sent an ABORT, with the first error cause code, if any.
+    Abort(Option<u16>),
 }
 
 /// These are the possible paths:
@@ -124,6 +336,9 @@ enum StreamEntryState {
     AwaitDcepAck,
     /// Stream is open, ready to send data.
     Open,
+    /// Stream is closing. Queued messages are sent before the outgoing stream is reset, and
+    /// the stream is closed when the remote peer has reset its outgoing stream too.
+    Closing,
     /// If some error occurs.
     Closed,
 }
@@ -144,6 +359,14 @@ pub struct ChannelConfig {
     ///
     /// Defaults to ""
     pub protocol: String,
+    /// Priority relative to other channels (RFC 8831).
+    ///
+    /// Used as weight when sharing the association with other channels. The values
+    /// of `RTCPriorityType` are 128 (very-low), 256 (low), 512 (medium) and 1024 (high).
+    /// A channel with 512 gets twice the bytes of a channel with 256.
+    ///
+    /// Defaults to 256
+    pub priority: u16,
 }
 
 impl Default for ChannelConfig {
@@ -154,10 +377,33 @@ impl Default for ChannelConfig {
             reliability: Default::default(),
             negotiated: Default::default(),
             protocol: Default::default(),
+            priority: DEFAULT_PRIORITY,
         }
     }
 }
 
+/// Options for a single message written with
+/// [`Channel::write_with()`][crate::channel::Channel::write_with()].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct WriteOptions {
+    /// Whether the data is binary or text.
+    pub binary: bool,
+    /// Override whether the message is delivered in order.
+    ///
+    /// None uses [`ChannelConfig::ordered`].
+    pub ordered: Option<bool>,
+    /// Override the reliability of the message.
+    ///
+    /// None uses [`ChannelConfig::reliability`]. A message with other settings than
+    /// the message before it waits for that message to be acknowledged, since the
+    /// settings apply to everything the SCTP stream has in flight.
+    pub reliability: Option<Reliability>,
+    /// Priority among the messages waiting to be sent on the channel. Higher goes first.
+    ///
+    /// Defaults to 0
+    pub priority: u8,
+}
+
 /// Reliability setting of a data channel.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Reliability {
@@ -177,6 +423,127 @@ pub enum Reliability {
 }
 
 impl StreamEntry {
+    /// The next chunk of an incoming message being delivered in chunks.
+    fn next_chunk(&mut self) -> Option<SctpEvent> {
+        let read = self.reading.as_mut()?;
+
+        let remaining = read.len - read.offset;
+        if remaining == 0 && !read.piece.ending {
+            // Waiting for the next piece.
+            return None;
+        }
+
+        let n = remaining.min(RECEIVE_CHUNK_SIZE);
+        let data = read.piece.bytes[read.offset..read.offset + n].to_vec();
+        read.offset += n;
+
+        let end = read.piece.ending && read.offset == read.len;
+
+        let event = SctpEvent::Chunk {
+            id: self.id,
+            binary: read.binary,
+            start: std::mem::replace(&mut read.start, false),
+            end,
+            abandoned: end && read.piece.abandoned,
+            data,
+        };
+
+        if end {
+            self.reading = None;
+        }
+
+        Some(event)
+    }
+
+    /// Take a piece of an incoming message from sctp-proto, for delivery in chunks.
+    fn receive_piece(&mut self, piece: PartialRead, max_message_size: usize) -> Option<SctpEvent> {
+        self.partial_read = !piece.ending;
+
+        if piece.beginning {
+            self.reading = Some(ChunkedRead {
+                binary: matches!(
+                    piece.ppi,
+                    PayloadProtocolIdentifier::Binary | PayloadProtocolIdentifier::BinaryEmpty
+                ),
+                start: true,
+                piece: piece.clone(),
+                offset: 0,
+                len: 0,
+                received: 0,
+            });
+        }
+
+        // Otherwise the rest of a dropped message.
+        let read = self.reading.as_mut()?;
+
+        let len = ppi_adjust_len(piece.bytes.len(), piece.ppi);
+        read.received += len;
+
+        if read.received > max_message_size {
+            warn!(
+                "Drop message of over {} bytes on stream {} over max size {}",
+                read.received, self.id, max_message_size
+            );
+
+            if read.start {
+                self.reading = None;
+                return None;
+            }
+
+            // End the chunks handed out, the rest is dropped.
+            read.piece = PartialRead {
+                bytes: Default::default(),
+                ending: true,
+                abandoned: true,
+                ..piece
+            };
+            read.len = 0;
+        } else {
+            self.counters.bytes_received += len as u64;
+            if piece.ending && !piece.abandoned {
+                self.counters.messages_received += 1;
+            }
+
+            read.piece = piece;
+            read.len = len;
+        }
+
+        read.offset = 0;
+
+        self.next_chunk()
+    }
+
+    /// End a message being delivered in chunks that will not get the rest.
+    fn abandon_read(&mut self) -> Option<SctpEvent> {
+        self.partial_read = false;
+
+        let read = self.reading.as_mut()?;
+
+        if read.start {
+            self.reading = None;
+            return None;
+        }
+
+        read.piece.bytes = Default::default();
+        read.piece.ending = true;
+        read.piece.abandoned = true;
+        read.len = 0;
+        read.offset = 0;
+
+        self.next_chunk()
+    }
+
+    /// Queued messages can be handed to sctp-proto.
+    fn can_send(&self) -> bool {
+        self.state == StreamEntryState::Open
+            || self.state == StreamEntryState::Closing && !self.reset
+    }
+
+    /// The message being written in pieces.
+    fn open_message(&mut self) -> Option<&mut QueuedMessage> {
+        self.queue.iter_mut().find(|m| m.is_open())
+    }
+
     fn set_state(&mut self, state: StreamEntryState) -> bool {
         if self.state == state {
             return false;
@@ -188,35 +555,113 @@ impl StreamEntry {
 
     #[must_use]
     fn configure_reliability(&mut self, stream: &mut Stream) -> bool {
-        let dcep: DcepOpen = self.config.as_ref().expect("config to be set").into();
+        let params = self.send_params(&WriteOptions::default());
 
-        let ret = stream.set_reliability_params(
-            dcep.unordered,
-            dcep.channel_type,
-            dcep.reliability_parameter,
-        );
+        if !self.set_params(stream, params) {
+            self.do_close = true;
+            return false;
+        }
+
+        true
+    }
+
+    fn set_params(&mut self, stream: &mut Stream, params: SendParams) -> bool {
+        let ret = stream.set_reliability_params(params.unordered, params.typ, params.value);
 
         if let Err(e) = ret {
             warn!(
                 "Failed to set reliability params on stream {}: {:?}",
                 self.id, e
             );
-            self.do_close = true;
             return false;
         }
 
+        self.params = params;
         true
     }
+
+    /// The reliability params of the channel, with overrides from the options.
+    fn send_params(&self, options: &WriteOptions) -> SendParams {
+        let config = self.config.as_ref().expect("config to be set");
+
+        let ordered = options.ordered.unwrap_or(config.ordered);
+        let reliability = options.reliability.unwrap_or(config.reliability);
+        let (typ, value) = (&reliability).into();
+
+        SendParams {
+            unordered: !ordered,
+            typ,
+            value,
+        }
+    }
+
+    /// Scheduler weight from the channel priority.
+    fn weight(&self) -> u64 {
+        let priority = self
+            .config
+            .as_ref()
+            .map(|c| c.priority)
+            .unwrap_or(DEFAULT_PRIORITY);
+
+        // Some implementations send 0.
+        if priority == 0 {
+            DEFAULT_PRIORITY as u64
+        } else {
+            priority as u64
+        }
+    }
+
+    /// Queue a message after the ones with the same or higher priority.
+    fn enqueue(&mut self, message: QueuedMessage, vtime: u64) {
+        if self.queue.is_empty() {
+            // Idle streams don't save up time to later hog the association.
+            self.vtime = self.vtime.max(vtime);
+        }
+
+        // Nothing goes ahead of a message written in pieces, since it might be partly sent.
+        let after = self
+            .queue
+            .iter()
+            .rposition(|m| m.streamed.is_some())
+            .map_or(0, |i| i + 1);
+
+        let pos = self
+            .queue
+            .iter()
+            .skip(after)
+            .position(|m| m.priority < message.priority)
+            .map_or(self.queue.len(), |i| i + after);
+
+        self.queued += message.data.len();
+        self.queue.insert(pos, message);
+    }
+
+    fn drop_expired(&mut self, now: Instant) {
+        let id = self.id;
+        let mut dropped = 0;
+
+        self.queue.retain(|m| {
+            let expired = m.expires.map(|e| now >= e).unwrap_or(false);
+            if expired {
+                debug!("Drop expired message of {} on stream {}", m.data.len(), id);
+                dropped += m.data.len();
+            }
+            !expired
+        });
+
+        self.queued -= dropped;
+    }
 }
 
 impl RtcSctp {
-    pub fn new() -> Self {
+    pub fn new(local_max_message_size: usize) -> Self {
         let mut config = EndpointConfig::default();
         // Default here is 1200, I've seen warnings that are 77 over.
         // DTLS above MTU 1200: 1277
         // Let's try 1120, see if we can avoid warnings.
         config.max_payload_size(1120);
-        let server_config = ServerConfig::default();
+        let mut server_config = ServerConfig::default();
+        server_config.transport = transport_config(local_max_message_size);
         let endpoint = Endpoint::new(Arc::new(config), Some(Arc::new(server_config)));
         let fake_addr = "1.1.1.1:5000".parse().unwrap();
 
@@ -230,6 +675,11 @@ impl RtcSctp {
             pushed_back_transmit: None,
             last_now: Instant::now(), // placeholder until init()
             client: false,
+            shutdown: false,
+            remote_close: None,
+            local_max_message_size,
+            remote_max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+            vtime: 0,
         }
     }
 
@@ -247,7 +697,12 @@ impl RtcSctp {
             info!("New local association");
             let (handle, assoc) = self
                 .endpoint
-                .connect(ClientConfig::default(), self.fake_addr)
+                .connect(
+                    ClientConfig {
+                        transport: transport_config(self.local_max_message_size),
+                    },
+                    self.fake_addr,
+                )
                 .expect("be able to create an association");
             self.handle = handle;
             self.assoc = Some(assoc);
@@ -261,6 +716,49 @@ impl RtcSctp {
         self.client
     }
 
+    pub fn local_max_message_size(&self) -> usize {
+        self.local_max_message_size
+    }
+
+    pub fn remote_max_message_size(&self) -> usize {
+        self.remote_max_message_size
+    }
+
+    pub fn set_remote_max_message_size(&mut self, size: usize) {
+        debug!("Remote max message size: {}", size);
+        self.remote_max_message_size = size;
+    }
+
+    /// Start a graceful SHUTDOWN of the association. Returns false if there is nothing to shut down.
+    pub fn shutdown(&mut self) -> bool {
+        if self.state != RtcSctpState::Established {
+            return false;
+        }
+
+        // sctp-proto sends what it has before the SHUTDOWN, but refuses new writes.
+        self.schedule(true);
+
+        let Some(assoc) = &mut self.assoc else {
+            return false;
+        };
+        if let Err(e) = assoc.shutdown() {
+            debug!("SCTP shutdown failed: {:?}", e);
+            return false;
+        }
+        info!("Shutdown association");
+        self.shutdown = true;
+        true
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.state == RtcSctpState::Closed
+    }
+
+    /// Whether the remote has sent SHUTDOWN or ABORT.
+    pub fn is_remote_closing(&self) -> bool {
+        self.state == RtcSctpState::Established && self.remote_close.is_some()
+    }
+
     /// Opens a new stream.
     pub fn open_stream(&mut self, id: u16, config: ChannelConfig) {
         // The channel might already have arrived via SCTP, and if it is negotiated out-of-band
@@ -313,13 +811,54 @@ impl RtcSctp {
         }
     }
 
-    /// Close stream.
-    pub fn close_stream(&mut self, id: u16) {
-        if let Some(entry) = self.entries.iter_mut().find(|v| v.id == id) {
-            entry.do_close = true;
+    /// Close stream. Queued messages are sent before the stream reset, unless `abort`.
+    pub fn close_stream(&mut self, id: u16, abort: bool) {
+        let Some(entry) = self.entries.iter_mut().find(|v| v.id == id) else {
+            return;
+        };
+
+        // A message not finished is never sent. Once partly sent, it can only be cut short by
+        // resetting the stream, so nothing queued after it is sent either.
+        let mut abort = abort;
+        if let Some(pos) = entry.queue.iter().position(|m| m.is_open()) {
+            if entry.queue[pos].is_started() {
+                abort = true;
+            } else if let Some(message) = entry.queue.remove(pos) {
+                entry.queued -= message.data.len();
+            }
+        }
+
+        if abort {
+            entry.queue.clear();
+            entry.queued = 0;
+        }
+
+        match entry.state {
+            StreamEntryState::Open => {
+                entry.set_state(StreamEntryState::Closing);
+            }
+            StreamEntryState::Closing | StreamEntryState::Closed => {}
+            // Not open yet, so there is nothing to flush.
+            _ => entry.do_close = true,
         }
     }
 
+    pub fn channel_state(&self, id: u16) -> Option<ChannelState> {
+        let entry = self.entries.iter().find(|e| e.id == id)?;
+
+        let state = match entry.state {
+            StreamEntryState::Closed => ChannelState::Closed,
+            StreamEntryState::Closing => ChannelState::Closing,
+            _ if entry.do_close => ChannelState::Closing,
+            StreamEntryState::AwaitOpen
+            | StreamEntryState::AwaitConfig
+            | StreamEntryState::AwaitDcepAck => ChannelState::Connecting,
+            StreamEntryState::Open => ChannelState::Open,
+        };
+
+        Some(state)
+    }
+
     pub fn is_open(&self, id: u16) -> bool {
         if self.state != RtcSctpState::Established {
             return false;
@@ -332,7 +871,170 @@ impl RtcSctp {
         rec.state == StreamEntryState::Open
     }
 
+    /// Open, or closing and flushing queued messages.
+    pub fn is_open_or_closing(&self, id: u16) -> bool {
+        self.is_open(id)
+            || self
+                .entries
+                .iter()
+                .any(|e| e.id == id && e.state == StreamEntryState::Closing)
+    }
+
     pub fn write(&mut self, id: u16, binary: bool, buf: &[u8]) -> Result<usize, SctpError> {
+        let options = WriteOptions {
+            binary,
+            ..Default::default()
+        };
+        self.write_with(id, buf, options)
+    }
+
+    pub fn write_with(
+        &mut self,
+        id: u16,
+        buf: &[u8],
+        options: WriteOptions,
+    ) -> Result<usize, SctpError> {
+        self.check_write(id, buf.len())?;
+        self.queue_message(id, buf.to_vec(), options);
+
+        Ok(buf.len())
+    }
+
+    pub fn begin_message(&mut self, id: u16, options: WriteOptions) -> Result<(), SctpError> {
+        self.check_write(id, 0)?;
+
+        let vtime = self.vtime;
+        let rec = self.entry_mut(id);
+
+        if rec.open_message().is_some() {
+            return Err(SctpError::MessageInProgress);
+        }
+
+        let params = rec.send_params(&options);
+        if params.typ != ReliabilityType::Reliable {
+            return Err(SctpError::MessageNotReliable);
+        }
+
+        let ppi = if options.binary {
+            PayloadProtocolIdentifier::Binary
+        } else {
+            PayloadProtocolIdentifier::String
+        };
+
+        let message = QueuedMessage {
+            data: Vec::new(),
+            ppi,
+            params,
+            priority: options.priority,
+            expires: None,
+            streamed: Some(Streamed::default()),
+        };
+
+        rec.enqueue(message, vtime);
+
+        Ok(())
+    }
+
+    pub fn append_message(&mut self, id: u16, buf: &[u8]) -> Result<usize, SctpError> {
+        let max = self.remote_max_message_size;
+
+        let open = self
+            .entry_mut(id)
+            .open_message()
+            .ok_or(SctpError::NoMessageBegun)?;
+        let len = open.streamed.as_ref().map(|s| s.len).unwrap_or_default() + buf.len();
+
+        if len > max {
+            return Err(SctpError::MessageTooLarge(len, max));
+        }
+
+        self.check_write(id, buf.len())?;
+
+        let buffered = self.buffered_amount(id).unwrap_or_default();
+        let rec = self.entry_mut(id);
+        let open = rec.open_message().expect("open message");
+
+        open.data.extend_from_slice(buf);
+        if let Some(s) = &mut open.streamed {
+            s.len = len;
+        }
+
+        rec.queued += buf.len();
+
+        if buffered + buf.len() > rec.buffered_amount_low {
+            rec.above_low = true;
+        }
+
+        self.schedule(false);
+
+        Ok(buf.len())
+    }
+
+    pub fn finish_message(&mut self, id: u16) -> Result<usize, SctpError> {
+        let open = self
+            .entry_mut(id)
+            .open_message()
+            .ok_or(SctpError::NoMessageBegun)?;
+
+        let streamed = open.streamed.as_mut().expect("open message is streamed");
+        streamed.finished = true;
+        let len = streamed.len;
+
+        if len == 0 {
+            open.ppi = match open.ppi {
+                PayloadProtocolIdentifier::Binary => PayloadProtocolIdentifier::BinaryEmpty,
+                _ => PayloadProtocolIdentifier::StringEmpty,
+            };
+        }
+
+        self.schedule(false);
+
+        Ok(len)
+    }
+
+    pub fn chunked_receive(&self, id: u16) -> bool {
+        self.entries
+            .iter()
+            .find(|e| e.id == id)
+            .map(|e| e.chunked)
+            .unwrap_or_default()
+    }
+
+    pub fn set_chunked_receive(&mut self, id: u16, chunked: bool) {
+        if let Some(entry) = self.entries.iter_mut().find(|e| e.id == id) {
+            entry.chunked = chunked;
+        }
+    }
+
+    pub fn abort_message(&mut self, id: u16) {
+        let Some(entry) = self.entries.iter_mut().find(|e| e.id == id) else {
+            return;
+        };
+
+        let Some(pos) = entry.queue.iter().position(|m| m.is_open()) else {
+            return;
+        };
+
+        if entry.queue[pos].is_started() {
+            // What is sent can't be taken back, the remote can only be told by a reset.
+            self.close_stream(id, true);
+            return;
+        }
+
+        if let Some(message) = entry.queue.remove(pos) {
+            entry.queued -= message.data.len();
+        }
+    }
+
+    fn entry_mut(&mut self, id: u16) -> &mut StreamEntry {
+        self.entries
+            .iter_mut()
+            .find(|e| e.id == id)
+            .expect("stream entry for write")
+    }
+
+    /// Check that a message of `len` bytes can be written to the stream.
+    fn check_write(&mut self, id: u16, len: usize) -> Result<(), SctpError> {
         if self.state != RtcSctpState::Established {
             return Err(SctpError::WriteBeforeEstablished);
         }
@@ -348,30 +1050,333 @@ impl RtcSctp {
             .find(|e| e.id == id)
             .expect("stream entry for write");
 
+        if rec.state == StreamEntryState::Closing {
+            return Err(SctpError::Closing);
+        }
+
         if rec.state != StreamEntryState::Open {
             return Err(SctpError::WriteBeforeEstablished);
         }
 
-        let mut stream = assoc.stream(id)?;
+        // What sctp-proto would refuse when the scheduler hands the message over.
+        if self.shutdown || self.remote_close.is_some() || !assoc.stream(id)?.is_writable() {
+            return Err(SctpError::Closing);
+        }
+
+        if len > self.remote_max_message_size {
+            return Err(SctpError::MessageTooLarge(
+                len,
+                self.remote_max_message_size,
+            ));
+        }
+
+        let buffered = assoc.stream(id)?.buffered_amount()? + rec.queued;
+
+        if let Some(high) = rec.buffered_amount_high {
+            // An empty buffer always takes the write, or big messages could never be sent.
+            if buffered > 0 && buffered + len > high {
+                return Err(SctpError::WouldBlock);
+            }
+        }
 
-        let ppi = if binary {
-            if buf.is_empty() {
+        Ok(())
+    }
+
+    /// Queue a checked message for the scheduler.
+    fn queue_message(&mut self, id: u16, data: Vec<u8>, options: WriteOptions) {
+        let buffered = self.buffered_amount(id).unwrap_or_default();
+
+        let ppi = if options.binary {
+            if data.is_empty() {
                 PayloadProtocolIdentifier::BinaryEmpty
             } else {
                 PayloadProtocolIdentifier::Binary
             }
-        } else if buf.is_empty() {
+        } else if data.is_empty() {
             PayloadProtocolIdentifier::StringEmpty
         } else {
             PayloadProtocolIdentifier::String
         };
 
-        Ok(stream.write_with_ppi(buf, ppi)?)
+        let now = self.last_now;
+        let vtime = self.vtime;
+        let rec = self.entry_mut(id);
+        let len = data.len();
+
+        // The lifetime of the message or, without an override, of the channel.
+        let params = rec.send_params(&options);
+        let expires = (params.typ == ReliabilityType::Timed)
+            .then(|| now + Duration::from_millis(params.value as u64));
+
+        let message = QueuedMessage {
+            data,
+            ppi,
+            params,
+            priority: options.priority,
+            expires,
+            streamed: None,
+        };
+
+        rec.enqueue(message, vtime);
+
+        if buffered + len > rec.buffered_amount_low {
+            rec.above_low = true;
+        }
+
+        self.schedule(false);
+    }
+
+    /// Hand queued messages to sctp-proto, in the order of the weighted channel priorities.
+    ///
+    /// This is start-time fair queuing, where each stream advances its virtual time by
+    /// the message size divided by the weight. With `force`, everything queued is handed
+    /// over, ignoring the window and waiting for changed reliability params.
+    fn schedule(&mut self, force: bool) {
+        if self.state != RtcSctpState::Established {
+            return;
+        }
+
+        let Some(assoc) = &mut self.assoc else {
+            return;
+        };
+
+        let now = self.last_now;
+
+        let mut in_flight = 0;
+
+        for entry in &mut self.entries {
+            entry.drop_expired(now);
+
+            if let Ok(stream) = assoc.stream(entry.id) {
+                in_flight += stream.buffered_amount().unwrap_or_default();
+            }
+        }
+
+        let interleaving = assoc.is_interleaving();
+
+        loop {
+            let window_full = !force && in_flight >= SEND_WINDOW;
+            if window_full && !interleaving {
+                break;
+            }
+
+            // Without I-DATA, the fragments of a message can't be interleaved with other
+            // messages, so a message partly handed over holds back the other streams.
+            let holding = if interleaving {
+                None
+            } else {
+                self.entries
+                    .iter()
+                    .filter(|e| e.can_send())
+                    .find(|e| e.queue.front().is_some_and(|m| m.is_started()))
+                    .map(|e| e.id)
+            };
+
+            let next = self
+                .entries
+                .iter_mut()
+                .filter(|e| e.can_send())
+                .filter(|e| holding.map_or(true, |id| id == e.id))
+                .filter(|e| {
+                    let Some(m) = e.queue.front() else {
+                        return false;
+                    };
+                    if !m.is_ready() {
+                        return false;
+                    }
+                    let idle = assoc
+                        .stream(e.id)
+                        .and_then(|s| s.buffered_amount())
+                        .map(|b| b == 0)
+                        .unwrap_or(false);
+                    // A full window only holds back the streams with something in flight,
+                    // since sctp-proto interleaves the others (we break above otherwise).
+                    if window_full && !idle {
+                        return false;
+                    }
+                    // Other params must wait for what the stream has in flight.
+                    force || m.params == e.params || idle
+                })
+                .min_by_key(|e| e.vtime);
+
+            let Some(entry) = next else {
+                break;
+            };
+
+            let mut message = entry.queue.pop_front().expect("message in queue");
+
+            let mut stream = match assoc.stream(entry.id) {
+                Ok(v) => v,
+                Err(e) => {
+                    debug!("Getting stream {} failed: {:?}", entry.id, e);
+                    entry.queued -= message.data.len();
+                    entry.do_close = true;
+                    entry.failed_writes += 1;
+                    continue;
+                }
+            };
+
+            if message.params != entry.params && !entry.set_params(&mut stream, message.params) {
+                entry.queued -= message.data.len();
+                entry.failed_writes += 1;
+                continue;
+            }
+
+            if let Some(streamed) = &mut message.streamed {
+                let padding = streamed.len == 0;
+
+                // Empty messages are padded like below. Otherwise the last byte is kept
+                // until finished, since sctp-proto can't end a message with nothing.
+                let piece = if padding {
+                    vec![0]
+                } else {
+                    let keep = usize::from(!streamed.finished);
+                    let rest = message.data.split_off(message.data.len() - keep);
+                    std::mem::replace(&mut message.data, rest)
+                };
+                let len = if padding { 0 } else { piece.len() };
+
+                entry.queued -= len;
+
+                match stream.write_fragment(&piece, message.ppi, streamed.finished) {
+                    Ok(n) => {
+                        streamed.started = true;
+                        if streamed.finished {
+                            entry.counters.messages_sent += 1;
+                        }
+                        entry.counters.bytes_sent += len as u64;
+                        in_flight += n;
+
+                        if !streamed.finished {
+                            entry.queue.push_front(message);
+                        }
+                    }
+                    Err(e) => {
+                        warn!("Failed to write to stream {}: {:?}", entry.id, e);
+                        entry.queued -= message.data.len();
+                        entry.failed_writes += 1;
+                    }
+                }
+
+                self.vtime = entry.vtime;
+                entry.vtime += len.max(1) as u64 * VTIME_SCALE / entry.weight();
+                continue;
+            }
+
+            entry.queued -= message.data.len();
+
+            // Empty messages are sent with a single byte of padding (RFC 8831 6.6), since
+            // sctp-proto would otherwise use up a sequence number without sending anything.
+            let data: &[u8] = if message.data.is_empty() {
+                &[0]
+            } else {
+                &message.data
+            };
+
+            match stream.write_with_ppi(data, message.ppi) {
+                Ok(n) => {
+                    entry.counters.messages_sent += 1;
+                    entry.counters.bytes_sent += message.data.len() as u64;
+                    in_flight += n;
+                }
+                Err(e) => {
+                    warn!("Failed to write to stream {}: {:?}", entry.id, e);
+                    entry.failed_writes += 1;
+                }
+            }
+
+            self.vtime = entry.vtime;
+            let len = message.data.len().max(1) as u64;
+            entry.vtime += len * VTIME_SCALE / entry.weight();
+        }
+    }
+
+    /// Label, protocol, state and counters of a stream.
+    pub fn stream_stats(&self, id: u16) -> Option<(&str, &str, ChannelState, StreamCounters)> {
+        let state = self.channel_state(id)?;
+        let entry = self.entries.iter().find(|e| e.id == id)?;
+
+        let (label, protocol) = entry
+            .config
+            .as_ref()
+            .map(|c| (c.label.as_str(), c.protocol.as_str()))
+            .unwrap_or_default();
+
+        Some((label, protocol, state, entry.counters))
+    }
+
+    /// Association stats, once there is an association.
+    pub fn stats(&self, now: Instant) -> Option<SctpStats> {
+        let assoc = self.assoc.as_ref()?;
+        let mut stats = assoc.stats();
+
+        Some(SctpStats {
+            rto: assoc.rto(),
+            srtt: assoc.srtt(),
+            cwnd: assoc.cwnd(),
+            rwnd: assoc.rwnd(),
+            data_chunks_received: stats.get_num_datas(),
+            sacks_received: stats.get_num_sacks(),
+            t3_timeouts: stats.get_num_t3timeouts(),
+            ack_timeouts: stats.get_num_ack_timeouts(),
+            fast_retransmits: stats.get_num_fast_retrans(),
+            timestamp: now,
+        })
+    }
+
+    pub fn buffered_amount(&mut self, id: u16) -> Result<usize, SctpError> {
+        let queued = self
+            .entries
+            .iter()
+            .find(|e| e.id == id)
+            .map(|e| e.queued)
+            .unwrap_or_default();
+
+        Ok(self.stream(id)?.buffered_amount()? + queued)
+    }
+
+    pub fn buffered_amount_low_threshold(&self, id: u16) -> usize {
+        self.entries
+            .iter()
+            .find(|e| e.id == id)
+            .map(|e| e.buffered_amount_low)
+            .unwrap_or_default()
+    }
+
+    pub fn set_buffered_amount_low_threshold(&mut self, id: u16, threshold: usize) {
+        if let Some(entry) = self.entries.iter_mut().find(|e| e.id == id) {
+            entry.buffered_amount_low = threshold;
+        }
+    }
+
+    pub fn buffered_amount_high_threshold(&self, id: u16) -> Option<usize> {
+        self.entries
+            .iter()
+            .find(|e| e.id == id)
+            .and_then(|e| e.buffered_amount_high)
+    }
+
+    pub fn set_buffered_amount_high_threshold(&mut self, id: u16, threshold: Option<usize>) {
+        if let Some(entry) = self.entries.iter_mut().find(|e| e.id == id) {
+            entry.buffered_amount_high = threshold;
+        }
+    }
+
+    fn stream(&mut self, id: u16) -> Result<Stream<'_>, SctpError> {
+        let assoc = self
+            .assoc
+            .as_mut()
+            .ok_or(SctpError::WriteBeforeEstablished)?;
+        Ok(assoc.stream(id)?)
     }
 
     pub fn handle_input(&mut self, now: Instant, data: &[u8]) {
         trace!("Handle input: {}", data.len());
 
+        if self.remote_close.is_none() {
+            self.remote_close = remote_close(data);
+        }
+
         // TODO, remove Bytes in sctp and just use &[u8].
         let data = data.to_vec().into();
         let r = self.endpoint.handle(now, self.fake_addr, None, None, data);
@@ -418,6 +1423,11 @@ impl RtcSctp {
 
         // propagate events between endpoint and association.
         while let Some(e) = assoc.poll_endpoint_event() {
+            // A SHUTDOWN drains the association straight away, which would make the
+            // endpoint drop the rest of the shutdown sequence.
+            if e.is_drained() {
+                continue;
+            }
             if let Some(ae) = self.endpoint.handle_event(self.handle, e) {
                 assoc.handle_event(ae);
             }
@@ -444,6 +1454,9 @@ impl RtcSctp {
             return Some(SctpEvent::Transmit { packets: t });
         }
 
+        // Acknowledged data might have made room for more.
+        self.schedule(false);
+
         while let Some(t) = self.poll_transmit() {
             let Some(buf) = transmit_to_vec(t) else {
                 continue;
@@ -466,7 +1479,10 @@ impl RtcSctp {
                 return self.poll();
             }
 
-            // TODO: Do we need to handle AssociationLost?
+            if let Event::AssociationLost { reason } = e {
+                info!("Association lost: {}", reason);
+                continue;
+            }
 
             if let Event::Stream(se) = e {
                 match se {
@@ -493,11 +1509,42 @@ impl RtcSctp {
             }
         }
 
+        // The association is also "closed" before it is established.
+        if self.state == RtcSctpState::Established && assoc.is_closed() {
+            // Report the streams closed before the association.
+            for entry in &mut self.entries {
+                if entry.set_state(StreamEntryState::Closed) {
+                    return Some(SctpEvent::Close { id: entry.id });
+                }
+            }
+
+            let reason = if self.shutdown {
+                SctpClose::Local
+            } else {
+                self.remote_close.unwrap_or(SctpClose::Abort(None))
+            };
+            set_state(&mut self.state, RtcSctpState::Closed);
+            return Some(SctpEvent::Closed { reason });
+        }
+
         // Must wait for association state to be established before opening streams.
         if self.state != RtcSctpState::Established {
             return None;
         }
 
+        // The buffered amount includes what is queued, so we check it ourselves
+        // rather than using the event from sctp-proto.
+        if let Some(id) = buffered_amount_low(assoc, &mut self.entries) {
+            return Some(SctpEvent::BufferedAmountLow { id });
+        }
+
+        if let Some(entry) = self.entries.iter_mut().find(|e| e.failed_writes > 0) {
+            entry.failed_writes -= 1;
+            return Some(SctpEvent::WriteFailed { id: entry.id });
+        }
+
+        let local_max_message_size = self.local_max_message_size;
+
         for entry in &mut self.entries {
             let want_open = entry.state == StreamEntryState::AwaitOpen;
 
@@ -574,33 +1621,112 @@ impl RtcSctp {
                 return Some(SctpEvent::Close { id: entry.id });
             }
 
+            // An incoming message is handed out in full before the next is read.
+            if let Some(event) = entry.next_chunk() {
+                return Some(event);
+            }
+
             let mut stream = match assoc.stream(entry.id) {
                 Ok(v) => v,
                 Err(e) => {
-                    // This is expected on browser refresh or similar abrupt shutdown.
-                    debug!("Getting stream {} failed: {:?}", entry.id, e);
-                    entry.do_close = true;
-                    continue;
+                    // The rest of a message being received will not arrive.
+                    if let Some(event) = entry.abandon_read() {
+                        return Some(event);
+                    }
+
+                    // sctp-proto removes the stream when the remote resets its outgoing stream.
+                    // The reset is answered by resetting our outgoing stream straight away,
+                    // so anything still queued can't be sent.
+                    if entry.state == StreamEntryState::Open {
+                        info!("Stream {} reset by remote", entry.id);
+                        entry.set_state(StreamEntryState::Closing);
+                        entry.reset = true;
+                        entry.failed_writes += entry.queue.len();
+                        entry.queue.clear();
+                        entry.queued = 0;
+                        return Some(SctpEvent::Closing { id: entry.id });
+                    }
+
+                    if entry.state == StreamEntryState::Closing && entry.reset {
+                        debug!("Stream {} reset completed", entry.id);
+                    } else {
+                        // This is expected on browser refresh or similar abrupt shutdown.
+                        debug!("Getting stream {} failed: {:?}", entry.id, e);
+                    }
+
+                    entry.set_state(StreamEntryState::Closed);
+                    return Some(SctpEvent::Close { id: entry.id });
                 }
             };
 
-            match stream_read_data(&mut stream) {
-                Ok(Some((buf, ppi))) => {
+            // Reset the outgoing stream once the queued messages are handed over. sctp-proto
+            // sends the reset after the data it already has.
+            if entry.state == StreamEntryState::Closing && !entry.reset && entry.queue.is_empty() {
+                debug!("Reset outgoing stream {}", entry.id);
+                entry.reset = true;
+                if let Err(e) = stream.stop() {
+                    debug!("Reset of stream {} failed: {:?}", entry.id, e);
+                    entry.set_state(StreamEntryState::Closed);
+                    return Some(SctpEvent::Close { id: entry.id });
+                }
+            }
+
+            // Channels receiving in chunks get the pieces of a message as they arrive. A
+            // message being read in pieces is finished that way, even if chunking is turned off.
+            if entry.state == StreamEntryState::Open && (entry.chunked || entry.partial_read) {
+                match stream_read_partial(&mut stream) {
+                    Ok(Some(piece)) if piece.ppi == PayloadProtocolIdentifier::Dcep => {
+                        warn!("Stream {} received DCEP when open", entry.id);
+                        entry.do_close = true;
+                        continue;
+                    }
+                    Ok(Some(piece)) => {
+                        if let Some(event) = entry.receive_piece(piece, local_max_message_size) {
+                            return Some(event);
+                        }
+                        return self.do_poll();
+                    }
+                    Ok(None) => continue,
+                    Err(_) => {
+                        entry.do_close = true;
+                        continue;
+                    }
+                }
+            }
+
+            match stream_read_chunks(&mut stream) {
+                Ok(Some(chunks)) => {
+                    let ppi = chunks.ppi;
+
                     if ppi != PayloadProtocolIdentifier::Dcep {
+                        let len = ppi_adjust_len(chunks.len(), ppi);
+
+                        if len > local_max_message_size {
+                            warn!(
+                                "Drop message of {} bytes on stream {} over max size {}",
+                                len, entry.id, local_max_message_size
+                            );
+                            return self.do_poll();
+                        }
+
                         // This is the normal path for incoming data.
-                        let buf = ppi_adjust_buf(buf, ppi);
+                        entry.counters.messages_received += 1;
+                        entry.counters.bytes_received += len as u64;
                         let binary = matches!(
                             ppi,
                             PayloadProtocolIdentifier::Binary
                                 | PayloadProtocolIdentifier::BinaryEmpty
                         );
+
                         return Some(SctpEvent::Data {
                             id: entry.id,
                             binary,
-                            data: buf,
+                            data: chunks_to_vec(&chunks, len),
                         });
                     }
 
+                    let buf = chunks_to_vec(&chunks, chunks.len());
+
                     // It's Dcep, either a DcepOpen or DcepAck.
                     match entry.state {
                         // We are in AwaitConfig state which means we are either going to get it via
@@ -686,7 +1812,14 @@ impl RtcSctp {
             return Some(t);
         }
 
-        if let Some(t) = self.assoc.as_mut()?.poll_transmit(self.last_now) {
+        let assoc = self.assoc.as_mut()?;
+        if let Some(t) = assoc.poll_transmit(self.last_now) {
+            // sctp-proto never closes the side sending the final SHUTDOWN-COMPLETE.
+            if let Payload::RawEncode(v) = &t.payload {
+                if v.iter().any(|b| is_shutdown_complete(b)) {
+                    let _ = assoc.close();
+                }
+            }
             return Some(t);
         }
 
@@ -694,6 +1827,52 @@ impl RtcSctp {
     }
 }
 
+/// Look for ABORT and SHUTDOWN chunks in an incoming packet (RFC 9260 3).
+fn remote_close(data: &[u8]) -> Option<SctpClose> {
+    const CHUNK_ABORT: u8 = 6;
+    const CHUNK_SHUTDOWN: u8 = 7;
+
+    chunks(data).find_map(|(typ, value)| match typ {
+        // The first error cause, if any.
+        CHUNK_ABORT => Some(SctpClose::Abort(
+            value.get(..2).map(|c| u16::from_be_bytes([c[0], c[1]])),
+        )),
+        CHUNK_SHUTDOWN => Some(SctpClose::Shutdown),
+        _ => None,
+    })
+}
+
+/// Whether an outgoing packet ends the shutdown sequence.
+fn is_shutdown_complete(data: &[u8]) -> bool {
+    const CHUNK_SHUTDOWN_COMPLETE: u8 = 14;
+
+    chunks(data).any(|(typ, _)| typ == CHUNK_SHUTDOWN_COMPLETE)
+}
+
+/// Iterate the (type, value) of the chunks in an SCTP packet.
+fn chunks(data: &[u8]) -> impl Iterator<Item = (u8, &[u8])> {
+    // Skip the common header.
+    let mut buf = data.get(12..).unwrap_or_default();
+
+    std::iter::from_fn(move || {
+        if buf.len() < 4 {
+            return None;
+        }
+        let typ = buf[0];
+        let len = u16::from_be_bytes([buf[2], buf[3]]) as usize;
+        if len < 4 || len > buf.len() {
+            return None;
+        }
+        let value = &buf[4..len];
+
+        // Chunks are padded to 4 bytes.
+        let padded = (len + 3) & !3;
+        buf = buf.get(padded..).unwrap_or_default();
+
+        Some((typ, value))
+    })
+}
+
 fn transmit_to_vec(t: Transmit) -> Option<VecDeque<Vec<u8>>> {
     let Payload::RawEncode(v) = t.payload else {
         return None;
@@ -715,6 +1894,9 @@ fn stream_entry<'a>(
     initial_state: StreamEntryState,
     reason: &'static str,
 ) -> &'a mut StreamEntry {
+    // A closed entry is not yet removed, but the stream id can be reused (RFC 8831 6.7).
+    entries.retain(|v| v.id != id || v.state != StreamEntryState::Closed);
+
     let idx = entries.iter().position(|v| v.id == id);
     if let Some(idx) = idx {
         entries.get_mut(idx).unwrap()
@@ -725,46 +1907,109 @@ fn stream_entry<'a>(
             state: initial_state,
             id,
             do_close: false,
+            reset: false,
+            buffered_amount_high: None,
+            buffered_amount_low: 0,
+            above_low: false,
+            queue: VecDeque::new(),
+            queued: 0,
+            params: SendParams::default(),
+            vtime: 0,
+            counters: StreamCounters::default(),
+            chunked: false,
+            reading: None,
+            partial_read: false,
+            failed_writes: 0,
         };
         entries.push(e);
         entries.last_mut().unwrap()
     }
 }
 
-fn stream_read_data(
-    stream: &mut Stream,
-) -> Result<Option<(Vec<u8>, PayloadProtocolIdentifier)>, SctpError> {
+/// The message size limit in sctp-proto is lifted, since we check the remote limit ourselves.
+///
+/// I-DATA (RFC 8260) is offered, and used if the remote peer offers it too.
+fn transport_config(local_max_message_size: usize) -> Arc<TransportConfig> {
+    // A message must fit in the receive window to be reassembled.
+    let receive_buffer = local_max_message_size
+        .max(DEFAULT_RECEIVE_BUFFER_SIZE)
+        .min(u32::MAX as usize) as u32;
+
+    let config = TransportConfig::default()
+        .with_max_message_size(u32::MAX)
+        .with_max_receive_buffer_size(receive_buffer)
+        .with_interleaving(true);
+
+    Arc::new(config)
+}
+
+/// The first stream that went from above the low threshold to at, or below it.
+fn buffered_amount_low(assoc: &mut Association, entries: &mut [StreamEntry]) -> Option<u16> {
+    for entry in entries {
+        if !entry.above_low {
+            continue;
+        }
+
+        let Ok(stream) = assoc.stream(entry.id) else {
+            continue;
+        };
+
+        let amount = stream.buffered_amount().unwrap_or_default() + entry.queued;
+
+        if amount <= entry.buffered_amount_low {
+            entry.above_low = false;
+            return Some(entry.id);
+        }
+    }
+
+    None
+}
+
+fn stream_read_chunks(stream: &mut Stream) -> Result<Option<Chunks>, SctpError> {
     let Some(chunks) = stream.read()? else {
         return Ok(None);
     };
 
-    let n = chunks.len();
-    let mut buf = vec![0; n];
+    check_ppi(chunks.ppi)?;
+
+    Ok(Some(chunks))
+}
+
+fn stream_read_partial(stream: &mut Stream) -> Result<Option<PartialRead>, SctpError> {
+    let Some(piece) = stream.read_partial()? else {
+        return Ok(None);
+    };
+
+    check_ppi(piece.ppi)?;
 
-    let l = chunks.read(&mut buf)?;
-    assert!(l == n);
+    Ok(Some(piece))
+}
 
+fn check_ppi(ppi: PayloadProtocolIdentifier) -> Result<(), SctpError> {
     use PayloadProtocolIdentifier::*;
-    match chunks.ppi {
-        Dcep | String | Binary => {} // keep as is
-        StringEmpty | BinaryEmpty => buf.clear(),
-        _ => {
-            return Err(SctpError::Proto(ProtoError::Other(
-                "Unknown PayloadProtocolIdentifier".into(),
-            )));
-        }
+    match ppi {
+        Dcep | String | Binary | StringEmpty | BinaryEmpty => Ok(()),
+        _ => Err(SctpError::Proto(ProtoError::Other(
+            "Unknown PayloadProtocolIdentifier".into(),
+        ))),
     }
+}
+
+fn chunks_to_vec(chunks: &Chunks, len: usize) -> Vec<u8> {
+    let mut buf = vec![0; chunks.len()];
 
-    Ok(Some((buf, chunks.ppi)))
+    let l = chunks.read(&mut buf).expect("buffer of chunks length");
+    assert!(l == buf.len());
+
+    buf.truncate(len);
+    buf
 }
 
-fn ppi_adjust_buf(mut buf: Vec<u8>, ppi: PayloadProtocolIdentifier) -> Vec<u8> {
+/// Empty messages are sent with a single byte of padding.
+fn ppi_adjust_len(len: usize, ppi: PayloadProtocolIdentifier) -> usize {
     match ppi {
-        PayloadProtocolIdentifier::StringEmpty | PayloadProtocolIdentifier::BinaryEmpty => {
-            buf.clear();
-            buf
-        }
-        _ => buf,
+        PayloadProtocolIdentifier::StringEmpty | PayloadProtocolIdentifier::BinaryEmpty => 0,
+        _ => len,
     }
 }
 
@@ -780,13 +2025,35 @@ impl fmt::Debug for SctpEvent {
                 .field("id", id)
                 .field("label", label)
                 .finish(),
+            Self::Closing { id } => f.debug_struct("Closing").field("id", id).finish(),
             Self::Close { id } => f.debug_struct("Close").field("id", id).finish(),
+            Self::BufferedAmountLow { id } => {
+                f.debug_struct("BufferedAmountLow").field("id", id).finish()
+            }
+            Self::WriteFailed { id } => f.debug_struct("WriteFailed").field("id", id).finish(),
             Self::Data { id, binary, data } => f
                 .debug_struct("Data")
                 .field("id", id)
                 .field("binary", binary)
                 .field("data", &data.len())
                 .finish(),
+            Self::Chunk {
+                id,
+                binary,
+                start,
+                end,
+                abandoned,
+                data,
+            } => f
+                .debug_struct("Chunk")
+                .field("id", id)
+                .field("binary", binary)
+                .field("start", start)
+                .field("end", end)
+                .field("abandoned", abandoned)
+                .field("data", &data.len())
+                .finish(),
+            Self::Closed { reason } => f.debug_struct("Closed").field("reason", reason).finish(),
         }
     }
 }
@@ -798,7 +2065,7 @@ impl From<&ChannelConfig> for DcepOpen {
             unordered: !v.ordered,
             channel_type,
             reliability_parameter,
-            priority: 0,
+            priority: v.priority,
             label: v.label.clone(),
             protocol: v.protocol.clone(),
         }
@@ -827,6 +2094,7 @@ impl From<&DcepOpen> for ChannelConfig {
             reliability: (v.channel_type, v.reliability_parameter).into(),
             negotiated: None,
             protocol: v.protocol.clone(),
+            priority: v.priority,
         }
     }
 }
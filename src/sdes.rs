@@ -0,0 +1,431 @@
+//! SDES-SRTP keying (RFC 4568), for peers that don't do DTLS.
+//!
+//! The SRTP master keys are exchanged in the clear in `a=crypto` lines of the SDP,
+//! which means the signaling channel must be secure.
+
+use std::fmt;
+
+use crate::crypto::{KeyingMaterial, SrtpProfile};
+use crate::rtp_::Ssrc;
+use crate::sdp::{Proto, SdesCrypto, SdesKeyParam};
+
+pub(crate) struct Sdes {
+    /// Whether SDP offers and answers use a=crypto instead of DTLS.
+    enabled: bool,
+    /// Our master key and salt per profile, in order of preference. These are
+    /// never used for SRTP yet, and are replaced once they are.
+    local: Vec<(SrtpProfile, Vec<u8>)>,
+    /// What we put in answers, once we accepted a remote offer.
+    answer: Option<(Proto, SdesCrypto)>,
+    /// The keys in use.
+    keys: Option<SdesKeys>,
+    /// Whether we have emitted Event::Connected.
+    connected: bool,
+}
+
+/// SRTP master keys and salts for both directions.
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) struct SdesKeys {
+    pub profile: SrtpProfile,
+    pub local: Vec<u8>,
+    pub remote: Vec<u8>,
+    /// MKI the remote puts in the packets it sends.
+    pub remote_mki: Option<Vec<u8>>,
+    /// Max number of packets the remote protects with its key.
+    pub remote_lifetime: Option<u64>,
+    /// The SSRCs in the remote SDP.
+    pub remote_ssrcs: Vec<Ssrc>,
+}
+
+impl Sdes {
+    pub fn new(enabled: bool, profiles: &[SrtpProfile]) -> Self {
+        let local = if enabled {
+            profiles
+                .iter()
+                .filter(|p| p.sdes_suite().is_some())
+                .map(|p| (*p, random_key(*p)))
+                .collect()
+        } else {
+            vec![]
+        };
+
+        Sdes {
+            enabled,
+            local,
+            answer: None,
+            keys: None,
+            connected: false,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Whether SRTP has been started using SDES.
+    pub fn is_keyed(&self) -> bool {
+        self.keys.is_some()
+    }
+
+    /// a=crypto lines for an offer, one per profile in order of preference.
+    pub fn offer_lines(&self) -> Vec<SdesCrypto> {
+        self.local
+            .iter()
+            .enumerate()
+            .map(|(i, (profile, key))| crypto_line(i as u32 + 1, *profile, key))
+            .collect()
+    }
+
+    /// The proto and a=crypto line for an answer to the last accepted offer.
+    pub fn answer_line(&self) -> Option<&(Proto, SdesCrypto)> {
+        self.answer.as_ref()
+    }
+
+    /// The keys in use, if any.
+    pub fn keys(&self) -> Option<&SdesKeys> {
+        self.keys.as_ref()
+    }
+
+    /// Pick the first offered a=crypto line we support.
+    ///
+    /// We keep our current key if the offer doesn't change the remote key or SSRCs.
+    /// Otherwise it's a new SRTP session and we answer with a fresh key (RFC 4568 §6.4).
+    pub fn accept_offer(
+        &mut self,
+        proto: Proto,
+        offered: &[&SdesCrypto],
+        ssrcs: &[Ssrc],
+    ) -> Option<SdesKeys> {
+        for c in offered {
+            let Some(profile) = SrtpProfile::from_sdes_suite(&c.suite) else {
+                debug!("Ignore unsupported a=crypto suite: {}", c.suite);
+                continue;
+            };
+
+            let Some(local) = self.local_key(profile) else {
+                debug!("Ignore a=crypto suite not configured: {}", c.suite);
+                continue;
+            };
+
+            let Some(remote) = remote_key(profile, c) else {
+                continue;
+            };
+
+            let keys = SdesKeys::new(profile, local.to_vec(), remote, ssrcs);
+
+            let keys = match &self.keys {
+                Some(current) if current.same_remote(&keys) => current.clone(),
+                _ => keys,
+            };
+
+            self.answer = Some((proto, crypto_line(c.tag, profile, &keys.local)));
+
+            return Some(keys);
+        }
+
+        None
+    }
+
+    /// Match the answer's a=crypto line to one of the lines we offered.
+    pub fn accept_answer(
+        &self,
+        answer: &[&SdesCrypto],
+        ssrcs: &[Ssrc],
+    ) -> Result<SdesKeys, String> {
+        let c = answer.first().ok_or("missing a=crypto in answer")?;
+
+        let (profile, local) = c
+            .tag
+            .checked_sub(1)
+            .and_then(|i| self.local.get(i as usize))
+            .filter(|(p, _)| p.sdes_suite() == Some(&c.suite))
+            .ok_or_else(|| format!("a=crypto tag {} {} was not offered", c.tag, c.suite))?;
+
+        let remote = remote_key(*profile, c)
+            .ok_or_else(|| format!("unusable a=crypto in answer: {}", c.suite))?;
+
+        Ok(SdesKeys::new(*profile, local.clone(), remote, ssrcs))
+    }
+
+    /// Set the keys in use. Returns false if they are unchanged.
+    pub fn set_keys(&mut self, keys: SdesKeys) -> bool {
+        if self.keys.as_ref() == Some(&keys) {
+            return false;
+        }
+
+        // Later offers and answers must not reuse a key once it's in use.
+        for (profile, local) in &mut self.local {
+            if *local == keys.local {
+                *local = random_key(*profile);
+            }
+        }
+
+        self.keys = Some(keys);
+        true
+    }
+
+    /// Returns true once, when both ICE is connected and we have keys.
+    pub fn poll_connected(&mut self, ice_connected: bool) -> bool {
+        if self.connected || !ice_connected || self.keys.is_none() {
+            return false;
+        }
+        self.connected = true;
+        true
+    }
+
+    fn local_key(&self, profile: SrtpProfile) -> Option<&[u8]> {
+        self.local
+            .iter()
+            .find(|(p, _)| *p == profile)
+            .map(|(_, k)| &k[..])
+    }
+}
+
+impl SdesKeys {
+    fn new(profile: SrtpProfile, local: Vec<u8>, remote: &SdesKeyParam, ssrcs: &[Ssrc]) -> Self {
+        SdesKeys {
+            profile,
+            local,
+            remote: remote.key_salt.clone(),
+            remote_mki: remote.mki_bytes(),
+            remote_lifetime: remote.lifetime,
+            remote_ssrcs: ssrcs.to_vec(),
+        }
+    }
+
+    /// Whether the remote side of the SRTP session is unchanged.
+    fn same_remote(&self, other: &SdesKeys) -> bool {
+        self.profile == other.profile
+            && self.remote == other.remote
+            && self.remote_mki == other.remote_mki
+            && self.remote_lifetime == other.remote_lifetime
+            && self.remote_ssrcs == other.remote_ssrcs
+    }
+
+    /// Lay out the keys the way DTLS exports them, where we are the left side.
+    pub fn keying_material(&self) -> KeyingMaterial {
+        let (key_len, _) = self.profile.master_key_salt_len();
+
+        let (local_key, local_salt) = self.local.split_at(key_len);
+        let (remote_key, remote_salt) = self.remote.split_at(key_len);
+
+        let mut mat = Vec::with_capacity(self.profile.keying_material_len());
+        mat.extend_from_slice(local_key);
+        mat.extend_from_slice(remote_key);
+        mat.extend_from_slice(local_salt);
+        mat.extend_from_slice(remote_salt);
+
+        KeyingMaterial::new(mat)
+    }
+}
+
+fn crypto_line(tag: u32, profile: SrtpProfile, key: &[u8]) -> SdesCrypto {
+    SdesCrypto {
+        tag,
+        suite: profile.sdes_suite().expect("SDES suite").into(),
+        keys: vec![SdesKeyParam {
+            key_salt: key.to_vec(),
+            lifetime: None,
+            mki: None,
+        }],
+        session_params: vec![],
+    }
+}
+
+/// The remote key, if the line is something we can use.
+fn remote_key(profile: SrtpProfile, c: &SdesCrypto) -> Option<&SdesKeyParam> {
+    // Several keys are only useful with MKI based rekeying, which we don't do.
+    let [key] = &c.keys[..] else {
+        debug!("Ignore a=crypto with {} keys", c.keys.len());
+        return None;
+    };
+
+    let (key_len, salt_len) = profile.master_key_salt_len();
+    if key.key_salt.len() != key_len + salt_len {
+        debug!("Ignore a=crypto with wrong key length: {}", c.suite);
+        return None;
+    }
+
+    // Window size hint is the only session parameter that doesn't change
+    // how SRTP works. Unknown parameters means we can't use the line.
+    if let Some(p) = c.session_params.iter().find(|p| !p.starts_with("WSH=")) {
+        debug!("Ignore a=crypto with unsupported session param: {}", p);
+        return None;
+    }
+
+    if key.lifetime == Some(0) {
+        debug!("Ignore a=crypto with zero key lifetime");
+        return None;
+    }
+
+    Some(key)
+}
+
+fn random_key(profile: SrtpProfile) -> Vec<u8> {
+    let (key_len, salt_len) = profile.master_key_salt_len();
+    let mut key = vec![0; key_len + salt_len];
+    getrandom::getrandom(&mut key).expect("random SRTP master key");
+    key
+}
+
+impl fmt::Debug for SdesKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Don't log the keys.
+        f.debug_struct("SdesKeys")
+            .field("profile", &self.profile)
+            .field("remote_mki", &self.remote_mki)
+            .field("remote_lifetime", &self.remote_lifetime)
+            .field("remote_ssrcs", &self.remote_ssrcs)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn offer_answer() {
+        let profiles = [SrtpProfile::AeadAes128Gcm, SrtpProfile::Aes128CmSha1_80];
+        let offerer = Sdes::new(true, &profiles);
+        let mut answerer = Sdes::new(true, &[SrtpProfile::Aes128CmSha1_80]);
+
+        let offer = offerer.offer_lines();
+        assert_eq!(offer.len(), 2);
+        assert_eq!(offer[0].tag, 1);
+        assert_eq!(offer[0].suite, "AEAD_AES_128_GCM");
+        assert_eq!(offer[0].keys[0].key_salt.len(), 28);
+        assert_eq!(offer[1].tag, 2);
+        assert_eq!(offer[1].suite, "AES_CM_128_HMAC_SHA1_80");
+        assert_eq!(offer[1].keys[0].key_salt.len(), 30);
+
+        let offered: Vec<_> = offer.iter().collect();
+        let a_keys = answerer
+            .accept_offer(Proto::SdesSavpf, &offered, &[])
+            .unwrap();
+        assert_eq!(a_keys.profile, SrtpProfile::Aes128CmSha1_80);
+
+        let (proto, answer) = answerer.answer_line().unwrap();
+        assert_eq!(*proto, Proto::SdesSavpf);
+        assert_eq!(answer.tag, 2);
+
+        let o_keys = offerer.accept_answer(&[answer], &[]).unwrap();
+        assert_eq!(o_keys.profile, SrtpProfile::Aes128CmSha1_80);
+        assert_eq!(o_keys.local, a_keys.remote);
+        assert_eq!(o_keys.remote, a_keys.local);
+    }
+
+    #[test]
+    fn answer_not_offered() {
+        let offerer = Sdes::new(true, &[SrtpProfile::Aes128CmSha1_80]);
+        let mut answer = offerer.offer_lines().remove(0);
+
+        answer.tag = 2;
+        assert!(offerer.accept_answer(&[&answer], &[]).is_err());
+
+        answer.tag = 1;
+        answer.suite = "AEAD_AES_128_GCM".into();
+        assert!(offerer.accept_answer(&[&answer], &[]).is_err());
+    }
+
+    #[test]
+    fn skip_unusable_lines() {
+        let mut answerer = Sdes::new(true, &[SrtpProfile::Aes128CmSha1_80]);
+
+        let line = |tag, suite: &str, key_len, params: &[&str]| SdesCrypto {
+            tag,
+            suite: suite.into(),
+            keys: vec![SdesKeyParam {
+                key_salt: vec![1; key_len],
+                lifetime: Some(1 << 31),
+                mki: Some((1, 1)),
+            }],
+            session_params: params.iter().map(|p| p.to_string()).collect(),
+        };
+
+        let offer = [
+            line(1, "F8_128_HMAC_SHA1_80", 30, &[]),
+            line(2, "AES_CM_128_HMAC_SHA1_80", 29, &[]),
+            line(3, "AES_CM_128_HMAC_SHA1_80", 30, &["UNENCRYPTED_SRTP"]),
+            line(4, "AES_CM_128_HMAC_SHA1_80", 30, &["WSH=128"]),
+        ];
+        let offered: Vec<_> = offer.iter().collect();
+
+        let keys = answerer
+            .accept_offer(Proto::SdesSavp, &offered, &[])
+            .unwrap();
+        assert_eq!(keys.remote_mki, Some(vec![1]));
+        assert_eq!(keys.remote_lifetime, Some(1 << 31));
+        assert_eq!(answerer.answer_line().unwrap().1.tag, 4);
+
+        let mut expired = line(1, "AES_CM_128_HMAC_SHA1_80", 30, &[]);
+        expired.keys[0].lifetime = Some(0);
+        assert!(answerer
+            .accept_offer(Proto::SdesSavp, &[&expired], &[])
+            .is_none());
+    }
+
+    #[test]
+    fn fresh_key_when_remote_changes() {
+        let profiles = [SrtpProfile::Aes128CmSha1_80];
+        let offerer = Sdes::new(true, &profiles);
+        let mut answerer = Sdes::new(true, &profiles);
+
+        let offer = offerer.offer_lines();
+        let ssrcs = [Ssrc::from(1)];
+        let keys = answerer
+            .accept_offer(Proto::SdesSavpf, &[&offer[0]], &ssrcs)
+            .unwrap();
+        assert!(answerer.set_keys(keys.clone()));
+
+        // The key in use is never offered again.
+        assert_ne!(answerer.offer_lines()[0].keys[0].key_salt, keys.local);
+
+        // Same offer again, nothing changes.
+        let again = answerer
+            .accept_offer(Proto::SdesSavpf, &[&offer[0]], &ssrcs)
+            .unwrap();
+        assert_eq!(again, keys);
+        assert!(!answerer.set_keys(again));
+        let answer = &answerer.answer_line().unwrap().1;
+        assert_eq!(answer.keys[0].key_salt, keys.local);
+
+        // New SSRCs with the same remote key.
+        let new_ssrc = answerer
+            .accept_offer(Proto::SdesSavpf, &[&offer[0]], &[Ssrc::from(2)])
+            .unwrap();
+        assert_eq!(new_ssrc.remote, keys.remote);
+        assert_ne!(new_ssrc.local, keys.local);
+        assert!(answerer.set_keys(new_ssrc.clone()));
+
+        // New remote key.
+        let rekeyed = Sdes::new(true, &profiles).offer_lines();
+        let new_remote = answerer
+            .accept_offer(Proto::SdesSavpf, &[&rekeyed[0]], &[Ssrc::from(2)])
+            .unwrap();
+        assert_ne!(new_remote.local, keys.local);
+        assert_ne!(new_remote.local, new_ssrc.local);
+        let answer = &answerer.answer_line().unwrap().1;
+        assert_eq!(answer.keys[0].key_salt, new_remote.local);
+    }
+
+    #[test]
+    fn keying_material_layout() {
+        let keys = SdesKeys {
+            profile: SrtpProfile::AeadAes128Gcm,
+            local: (0..28).collect(),
+            remote: (100..128).collect(),
+            remote_mki: None,
+            remote_lifetime: None,
+            remote_ssrcs: vec![],
+        };
+
+        let mat = keys.keying_material();
+        let expected: Vec<u8> = (0..16)
+            .chain(100..116)
+            .chain(16..28)
+            .chain(116..128)
+            .collect();
+        assert_eq!(&*mat, &expected[..]);
+    }
+}
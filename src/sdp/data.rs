@@ -14,6 +14,7 @@ use crate::format::FormatParams;
 use crate::format::PayloadParams;
 use crate::io::Id;
 use crate::rtp_::{Direction, Extension, Frequency, Mid, Pt, Rid, SessionId, Ssrc};
+use crate::sctp::DEFAULT_MAX_MESSAGE_SIZE;
 use crate::{Candidate, IceCreds, VERSION};
 
 use super::parser::sdp_parser;
@@ -40,10 +41,50 @@ impl Sdp {
         }
     }
 
-    pub(crate) fn fingerprint(&self) -> Option<Fingerprint> {
-        self.session
-            .fingerprint()
-            .or_else(|| self.media_lines.iter().find_map(|m| m.fingerprint()))
+    /// The a=fingerprint lines at session level, or else those of the first m-line
+    /// that has any. With BUNDLE, all m-lines share the same DTLS.
+    pub(crate) fn fingerprints(&self) -> Vec<Fingerprint> {
+        let v = self.session.fingerprints();
+        if !v.is_empty() {
+            return v;
+        }
+
+        self.media_lines
+            .iter()
+            .map(|m| m.fingerprints())
+            .find(|v| !v.is_empty())
+            .unwrap_or_default()
+    }
+
+    /// The a=crypto lines of the first m-line that has any. With BUNDLE,
+    /// all m-lines share the same SRTP keys.
+    pub(crate) fn sdes_crypto(&self) -> Vec<&SdesCrypto> {
+        self.media_lines
+            .iter()
+            .map(|m| m.sdes_crypto().collect::<Vec<_>>())
+            .find(|v| !v.is_empty())
+            .unwrap_or_default()
+    }
+
+    /// The SSRCs of all m-lines, sorted.
+    pub(crate) fn ssrcs(&self) -> Vec<Ssrc> {
+        let mut v: Vec<_> = self
+            .media_lines
+            .iter()
+            .flat_map(|m| m.ssrc_info())
+            .map(|i| i.ssrc)
+            .collect();
+        v.sort();
+        v.dedup();
+        v
+    }
+
+    /// The proto of the first RTP m-line.
+    pub(crate) fn rtp_proto(&self) -> Option<Proto> {
+        self.media_lines
+            .iter()
+            .map(|m| m.proto)
+            .find(|p| p.is_rtp())
     }
 
     pub(crate) fn ice_creds(&self) -> Option<IceCreds> {
@@ -66,6 +107,50 @@ impl Sdp {
         candidates.into_iter()
     }
 
+    /// Any end-of-candidates, either on session level or in any m-line.
+    pub(crate) fn end_of_candidates(&self) -> bool {
+        self.session.end_of_candidates() || self.media_lines.iter().any(|m| m.end_of_candidates())
+    }
+
+    /// All ice-options, both session level and in m-lines, deduplicated.
+    pub(crate) fn ice_options(&self) -> Vec<&str> {
+        let mut options: Vec<&str> = vec![];
+
+        let all = self
+            .session
+            .ice_options()
+            .chain(self.media_lines.iter().flat_map(|m| m.ice_options()));
+
+        for o in all {
+            if !options.contains(&o) {
+                options.push(o);
+            }
+        }
+
+        options
+    }
+
+    /// a=cryptex at session level or in any m-line.
+    pub(crate) fn cryptex(&self) -> bool {
+        self.session.cryptex() || self.media_lines.iter().any(|m| m.cryptex())
+    }
+
+    /// The remote limit for data channel messages (RFC 8841).
+    ///
+    /// None if there is no m=application line. The default, when the attribute is
+    /// missing, is 64kB. A value of 0 means there is no limit.
+    pub(crate) fn max_message_size(&self) -> Option<usize> {
+        let line = self.media_lines.iter().find(|m| m.typ.is_channel())?;
+
+        let size = match line.max_message_size() {
+            Some(0) => usize::MAX,
+            Some(v) => v,
+            None => DEFAULT_MAX_MESSAGE_SIZE,
+        };
+
+        Some(size)
+    }
+
     pub(crate) fn setup(&self) -> Option<Setup> {
         self.session
             .setup()
@@ -167,6 +252,16 @@ impl Session {
         None
     }
 
+    pub fn fingerprints(&self) -> Vec<Fingerprint> {
+        self.attrs
+            .iter()
+            .filter_map(|a| match a {
+                SessionAttribute::Fingerprint(v) => Some(v.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
     pub fn ice_lite(&self) -> bool {
         self.attrs
             .iter()
@@ -188,6 +283,26 @@ impl Session {
             .iter()
             .any(|a| matches!(a, SessionAttribute::EndOfCandidates))
     }
+
+    pub fn cryptex(&self) -> bool {
+        self.attrs
+            .iter()
+            .any(|a| matches!(a, SessionAttribute::Cryptex))
+    }
+
+    /// a=ice-options:trickle renomination, split on whitespace.
+    pub fn ice_options(&self) -> impl Iterator<Item = &str> {
+        self.attrs
+            .iter()
+            .filter_map(|a| {
+                if let SessionAttribute::IceOptions(v) = a {
+                    Some(v.split_whitespace())
+                } else {
+                    None
+                }
+            })
+            .flatten()
+    }
 }
 
 /// Attributes before the first m= line.
@@ -203,6 +318,7 @@ pub enum SessionAttribute {
     },
     AllowMixedExts,
     IceLite,
+    Cryptex,
     IceUfrag(String),
     IcePwd(String),
     IceOptions(String),
@@ -213,6 +329,102 @@ pub enum SessionAttribute {
     Unused(String),
 }
 
+/// Payload params from the a=rtpmap, a=fmtp and a=rtcp-fb lines of an m-line.
+pub(crate) fn rtp_params(attrs: &[MediaAttribute]) -> Vec<PayloadParams> {
+    let rtp_maps: Vec<_> = attrs
+        .iter()
+        .filter_map(|a| {
+            if let MediaAttribute::RtpMap { pt, value: c } = a {
+                Some((*pt, *c))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    let fmtps: Vec<_> = attrs
+        .iter()
+        .filter_map(|a| {
+            if let MediaAttribute::Fmtp { pt, values } = a {
+                Some((pt, values))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    let fbs: Vec<_> = attrs
+        .iter()
+        .filter_map(|a| {
+            if let MediaAttribute::RtcpFb { pt, value } = a {
+                Some((pt, value))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    let mut params: Vec<_> = rtp_maps
+        .iter()
+        .filter(|(_, c)| c.codec.is_audio() | c.codec.is_video())
+        .map(|(pt, c)| PayloadParams::new(*pt, None, (*c).into()))
+        .collect();
+
+    for p in &mut params {
+        for (pt, values) in fmtps.iter() {
+            // find matching a=fmtp line, if it exists.
+            if **pt == p.pt {
+                for param in values.iter() {
+                    p.spec.format.set_param(param);
+                }
+            }
+
+            // find resend pt, if there is one.
+            for fp in values.iter() {
+                if let FormatParam::Apt(v) = fp {
+                    if *v == p.pt {
+                        // ensure this is a rtx
+                        let is_rtx = rtp_maps
+                            .iter()
+                            .any(|(cpt, c)| cpt == *pt && c.codec == Codec::Rtx);
+                        if is_rtx {
+                            p.resend = Some(**pt);
+                        }
+                    }
+                }
+            }
+        }
+
+        // rtcp feedback mechanisms
+        for (pt, value) in fbs.iter() {
+            if **pt == p.pt {
+                match &value[..] {
+                    "goog-remb" => {
+                        p.fb_remb = true;
+                    }
+                    "transport-cc" => {
+                        p.fb_transport_cc = true;
+                    }
+                    "ccm fir" => {
+                        p.fb_fir = true;
+                    }
+                    "nack" => {
+                        p.fb_nack = true;
+                    }
+                    "nack pli" => {
+                        p.fb_pli = true;
+                    }
+                    _ => {
+                        //
+                    }
+                }
+            }
+        }
+    }
+
+    params
+}
+
 fn is_dir(a: &MediaAttribute) -> bool {
     use MediaAttribute::*;
     matches!(a, SendRecv | SendOnly | RecvOnly | Inactive)
@@ -279,101 +491,7 @@ impl MediaLine {
     }
 
     pub fn rtp_params(&self) -> Vec<PayloadParams> {
-        let rtp_maps: Vec<_> = self
-            .attrs
-            .iter()
-            .filter_map(|a| {
-                if let MediaAttribute::RtpMap { pt, value: c } = a {
-                    Some((*pt, *c))
-                } else {
-                    None
-                }
-            })
-            .collect();
-
-        let fmtps: Vec<_> = self
-            .attrs
-            .iter()
-            .filter_map(|a| {
-                if let MediaAttribute::Fmtp { pt, values } = a {
-                    Some((pt, values))
-                } else {
-                    None
-                }
-            })
-            .collect();
-
-        let fbs: Vec<_> = self
-            .attrs
-            .iter()
-            .filter_map(|a| {
-                if let MediaAttribute::RtcpFb { pt, value } = a {
-                    Some((pt, value))
-                } else {
-                    None
-                }
-            })
-            .collect();
-
-        let mut params: Vec<_> = rtp_maps
-            .iter()
-            .filter(|(_, c)| c.codec.is_audio() | c.codec.is_video())
-            .map(|(pt, c)| PayloadParams::new(*pt, None, (*c).into()))
-            .collect();
-
-        for p in &mut params {
-            for (pt, values) in fmtps.iter() {
-                // find matching a=fmtp line, if it exists.
-                if **pt == p.pt {
-                    for param in values.iter() {
-                        p.spec.format.set_param(param);
-                    }
-                }
-
-                // find resend pt, if there is one.
-                for fp in values.iter() {
-                    if let FormatParam::Apt(v) = fp {
-                        if *v == p.pt {
-                            // ensure this is a rtx
-                            let is_rtx = rtp_maps
-                                .iter()
-                                .any(|(cpt, c)| cpt == *pt && c.codec == Codec::Rtx);
-                            if is_rtx {
-                                p.resend = Some(**pt);
-                            }
-                        }
-                    }
-                }
-            }
-
-            // rtcp feedback mechanisms
-            for (pt, value) in fbs.iter() {
-                if **pt == p.pt {
-                    match &value[..] {
-                        "goog-remb" => {
-                            p.fb_remb = true;
-                        }
-                        "transport-cc" => {
-                            p.fb_transport_cc = true;
-                        }
-                        "ccm fir" => {
-                            p.fb_fir = true;
-                        }
-                        "nack" => {
-                            p.fb_nack = true;
-                        }
-                        "nack pli" => {
-                            p.fb_pli = true;
-                        }
-                        _ => {
-                            //
-                        }
-                    }
-                }
-            }
-        }
-
-        params
+        rtp_params(&self.attrs)
     }
 
     pub fn check_consistent(&self) -> Option<String> {
@@ -406,14 +524,14 @@ impl MediaLine {
 
         let dir_count = self.attrs.iter().filter(|a| is_dir(a)).count();
 
-        if self.proto == Proto::Srtp && dir_count != 1 {
+        if self.proto.is_rtp() && dir_count != 1 {
             return Some(format!(
                 "Expected exactly one of a=sendrecv, a=sendonly, a=recvonly, a=inactive for mid: {}",
                 self.mid()
             ));
         }
 
-        if self.proto == Proto::Srtp && self.pts.is_empty() {
+        if self.proto.is_rtp() && self.pts.is_empty() {
             return Some(format!("Expected at least one PT for mid: {}", self.mid()));
         }
 
@@ -486,6 +604,26 @@ impl MediaLine {
         None
     }
 
+    pub fn fingerprints(&self) -> Vec<Fingerprint> {
+        self.attrs
+            .iter()
+            .filter_map(|a| match a {
+                MediaAttribute::Fingerprint(v) => Some(v.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn sdes_crypto(&self) -> impl Iterator<Item = &SdesCrypto> {
+        self.attrs.iter().filter_map(|a| {
+            if let MediaAttribute::Crypto(v) = a {
+                Some(v)
+            } else {
+                None
+            }
+        })
+    }
+
     /// This hoovers the ice candidates from all m-lines, lots of dupes.
     /// For WebRTC we don't expect different ice states per media line.
     pub fn ice_candidates(&self) -> impl Iterator<Item = &Candidate> {
@@ -506,6 +644,37 @@ impl MediaLine {
             .any(|a| matches!(a, MediaAttribute::EndOfCandidates))
     }
 
+    pub fn cryptex(&self) -> bool {
+        self.attrs
+            .iter()
+            .any(|a| matches!(a, MediaAttribute::Cryptex))
+    }
+
+    /// a=max-message-size, where 0 means no limit.
+    pub fn max_message_size(&self) -> Option<usize> {
+        self.attrs.iter().find_map(|a| {
+            if let MediaAttribute::MaxMessageSize(v) = a {
+                Some(*v)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// a=ice-options:trickle renomination, split on whitespace.
+    pub fn ice_options(&self) -> impl Iterator<Item = &str> {
+        self.attrs
+            .iter()
+            .filter_map(|a| {
+                if let MediaAttribute::IceOptions(v) = a {
+                    Some(v.split_whitespace())
+                } else {
+                    None
+                }
+            })
+            .flatten()
+    }
+
     pub fn extmaps(&self) -> Vec<(u8, &Extension)> {
         let mut ret = vec![];
 
@@ -737,6 +906,10 @@ pub enum Proto {
     #[default]
     Srtp,
     Sctp,
+    /// SRTP keyed with SDES (a=crypto), RTP/SAVPF.
+    SdesSavpf,
+    /// SRTP keyed with SDES (a=crypto) without feedback, RTP/SAVP.
+    SdesSavp,
 }
 
 impl Proto {
@@ -744,7 +917,104 @@ impl Proto {
         match self {
             Proto::Srtp => "UDP/TLS/RTP/SAVPF",
             Proto::Sctp => "UDP/DTLS/SCTP",
+            Proto::SdesSavpf => "RTP/SAVPF",
+            Proto::SdesSavp => "RTP/SAVP",
+        }
+    }
+
+    pub fn is_rtp(&self) -> bool {
+        !matches!(self, Proto::Sctp)
+    }
+}
+
+/// a=crypto:1 AES_CM_128_HMAC_SHA1_80 inline:PS1uQCVeeCFCanVmcjkpPywjNWhcYD0mXXtxaVBR|2^20|1:4
+///
+/// SDES-SRTP keying as described in [RFC 4568][1].
+///
+/// [1]: https://www.rfc-editor.org/rfc/rfc4568
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SdesCrypto {
+    /// Identifies the line in the offer/answer.
+    pub tag: u32,
+    /// The crypto-suite, such as AES_CM_128_HMAC_SHA1_80.
+    pub suite: String,
+    /// One or more key parameters.
+    pub keys: Vec<SdesKeyParam>,
+    /// Optional session parameters, such as UNENCRYPTED_SRTCP.
+    pub session_params: Vec<String>,
+}
+
+/// inline:<key||salt base64>[|lifetime][|MKI:length]
+#[derive(Clone, PartialEq, Eq)]
+pub struct SdesKeyParam {
+    /// The SRTP master key followed by the master salt.
+    pub key_salt: Vec<u8>,
+    /// Max number of packets for this key.
+    pub lifetime: Option<u64>,
+    /// MKI value and byte length.
+    pub mki: Option<(u64, u8)>,
+}
+
+impl SdesKeyParam {
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.strip_prefix("inline:")?;
+        let mut parts = s.split('|');
+
+        let key_salt = crate::util::base64::decode(parts.next()?)?;
+
+        let mut lifetime = None;
+        let mut mki = None;
+
+        for part in parts {
+            if let Some((value, len)) = part.split_once(':') {
+                if mki.is_some() {
+                    return None;
+                }
+                let value: u64 = value.parse().ok()?;
+                let len: u8 = len.parse().ok()?;
+                if len == 0 || len > 128 || (len < 8 && value >> (len as u32 * 8) != 0) {
+                    return None;
+                }
+                mki = Some((value, len));
+            } else {
+                if lifetime.is_some() || mki.is_some() {
+                    return None;
+                }
+                let value = if let Some(exp) = part.strip_prefix("2^") {
+                    1_u64.checked_shl(exp.parse().ok()?)?
+                } else {
+                    part.parse().ok()?
+                };
+                lifetime = Some(value);
+            }
         }
+
+        Some(SdesKeyParam {
+            key_salt,
+            lifetime,
+            mki,
+        })
+    }
+
+    /// The MKI as it appears in SRTP packets.
+    pub fn mki_bytes(&self) -> Option<Vec<u8>> {
+        let (value, len) = self.mki?;
+        let len = len as usize;
+        let mut bytes = vec![0; len.saturating_sub(8)];
+        let be = value.to_be_bytes();
+        bytes.extend_from_slice(&be[8 - len.min(8)..]);
+        Some(bytes)
+    }
+}
+
+impl fmt::Debug for SdesKeyParam {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Don't log the key.
+        f.debug_struct("SdesKeyParam")
+            .field("key_salt_len", &self.key_salt.len())
+            .field("lifetime", &self.lifetime)
+            .field("mki", &self.mki)
+            .finish()
     }
 }
 
@@ -833,6 +1103,10 @@ pub enum MediaAttribute {
     RtcpMuxOnly, // only in offer, answer with a=rtcp-mux
     // reduced size rtcp. remove this if not supported.
     RtcpRsize,
+    // a=cryptex, RFC 9335
+    Cryptex,
+    // a=crypto, RFC 4568
+    Crypto(SdesCrypto),
     Candidate(Candidate),
     EndOfCandidates,
     RtpMap {
@@ -1198,6 +1472,7 @@ impl fmt::Display for SessionAttribute {
                 )?;
             }
             IceLite => write!(f, "a=ice-lite\r\n")?,
+            Cryptex => write!(f, "a=cryptex\r\n")?,
             IceUfrag(v) => write!(f, "a=ice-ufrag:{v}\r\n")?,
             IcePwd(v) => write!(f, "a=ice-pwd:{v}\r\n")?,
             IceOptions(v) => write!(f, "a=ice-options:{v}\r\n")?,
@@ -1306,6 +1581,8 @@ impl fmt::Display for MediaAttribute {
             RtcpMux => write!(f, "a=rtcp-mux\r\n")?,
             RtcpMuxOnly => write!(f, "a=rtcp-mux-only\r\n")?,
             RtcpRsize => write!(f, "a=rtcp-rsize\r\n")?,
+            Cryptex => write!(f, "a=cryptex\r\n")?,
+            Crypto(v) => write!(f, "a=crypto:{v}\r\n")?,
             Candidate(c) => write!(f, "a={}\r\n", c.to_sdp_string())?,
             EndOfCandidates => write!(f, "a=end-of-candidates\r\n")?,
             RtpMap { pt, value: c } => {
@@ -1406,6 +1683,39 @@ impl fmt::Display for MediaAttribute {
     }
 }
 
+impl fmt::Display for SdesCrypto {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} ", self.tag, self.suite)?;
+        for (idx, k) in self.keys.iter().enumerate() {
+            if idx > 0 {
+                write!(f, ";")?;
+            }
+            write!(f, "{k}")?;
+        }
+        for p in &self.session_params {
+            write!(f, " {p}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SdesKeyParam {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "inline:{}", crate::util::base64::encode(&self.key_salt))?;
+        if let Some(l) = self.lifetime {
+            if l.is_power_of_two() {
+                write!(f, "|2^{}", l.trailing_zeros())?;
+            } else {
+                write!(f, "|{l}")?;
+            }
+        }
+        if let Some((value, len)) = self.mki {
+            write!(f, "|{value}:{len}")?;
+        }
+        Ok(())
+    }
+}
+
 pub struct FingerprintFmt<'a>(pub &'a [u8]);
 
 impl<'a> std::fmt::Display for FingerprintFmt<'a> {
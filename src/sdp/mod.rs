@@ -8,7 +8,7 @@ use thiserror::Error;
 mod data;
 pub(crate) use data::{FormatParam, Sdp, Session, SessionAttribute, Setup};
 pub(crate) use data::{MediaAttribute, MediaLine, MediaType, Msid, Proto};
-pub(crate) use data::{RestrictionId, Simulcast, SimulcastGroups};
+pub(crate) use data::{RestrictionId, SdesCrypto, SdesKeyParam, Simulcast, SimulcastGroups};
 pub(crate) use parser::parse_candidate;
 
 #[cfg(test)]
@@ -16,6 +16,9 @@ pub(crate) use data::RtpMap;
 
 mod parser;
 
+mod model;
+pub use model::{MediaSection, SdpLine, SessionDescription, SessionSection};
+
 /// Errors from parsing and serializing SDP.
 #[derive(Debug, Error)]
 #[allow(missing_docs)]
@@ -0,0 +1,458 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::crypto::Fingerprint;
+use crate::format::PayloadParams;
+use crate::rtp_::{Direction, Mid, Pt};
+use crate::{Candidate, IceCreds};
+
+use super::data::{rtp_params, FormatParam, MediaAttribute};
+use super::parser::parse_media_attribute;
+use super::{SdpAnswer, SdpError, SdpOffer};
+
+/// A session description as a list of SDP lines, for inspecting and rewriting SDP.
+///
+/// Unlike [`SdpOffer`] and [`SdpAnswer`], which only keep what str0m understands, this
+/// keeps every line, in order. Serializing an unchanged description gives back the
+/// input, except that line endings are always `\r\n`.
+///
+/// ```
+/// # use str0m::change::SessionDescription;
+/// let sdp = "v=0\r\n\
+///     o=- 123 2 IN IP4 127.0.0.1\r\n\
+///     s=-\r\n\
+///     t=0 0\r\n\
+///     m=audio 9 UDP/TLS/RTP/SAVPF 111 0\r\n\
+///     c=IN IP4 0.0.0.0\r\n\
+///     a=mid:0\r\n\
+///     a=x-custom:keep me\r\n\
+///     a=rtpmap:111 opus/48000/2\r\n\
+///     a=rtpmap:0 PCMU/8000\r\n";
+///
+/// let mut desc: SessionDescription = sdp.parse().unwrap();
+/// assert_eq!(desc.to_string(), sdp);
+///
+/// let audio = &mut desc.media[0];
+/// audio.remove_codec(0.into());
+/// audio.set_bandwidth("AS", Some(64));
+///
+/// assert_eq!(audio.formats(), ["111"]);
+/// assert_eq!(audio.attribute("x-custom"), Some("keep me"));
+/// assert!(desc.to_string().contains("c=IN IP4 0.0.0.0\r\nb=AS:64\r\n"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionDescription {
+    /// The session level lines, up until the first `m=` line.
+    pub session: SessionSection,
+
+    /// One section per `m=` line.
+    pub media: Vec<MediaSection>,
+}
+
+/// The session level lines of a [`SessionDescription`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionSection {
+    /// The lines, starting with `v=0`.
+    pub lines: Vec<SdpLine>,
+}
+
+/// An m-section of a [`SessionDescription`], the `m=` line and the lines following it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MediaSection {
+    /// The lines, starting with the `m=` line.
+    pub lines: Vec<SdpLine>,
+}
+
+/// One line of SDP, like `a=mid:0`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SdpLine {
+    /// The type of line, like `v`, `m`, `b` or `a`.
+    pub typ: char,
+
+    /// Everything after the `=`.
+    pub value: String,
+}
+
+impl SdpLine {
+    /// Creates a new line.
+    pub fn new(typ: char, value: impl Into<String>) -> Self {
+        SdpLine {
+            typ,
+            value: value.into(),
+        }
+    }
+
+    /// Creates an attribute line, `a=<name>` or `a=<name>:<value>`.
+    pub fn attribute(name: &str, value: Option<&str>) -> Self {
+        match value {
+            Some(v) => SdpLine::new('a', format!("{}:{}", name, v)),
+            None => SdpLine::new('a', name),
+        }
+    }
+
+    /// Name and value of an `a=` line.
+    ///
+    /// The value is empty for attributes without one, like `a=rtcp-mux`.
+    pub fn as_attribute(&self) -> Option<(&str, &str)> {
+        if self.typ != 'a' {
+            return None;
+        }
+        Some(self.value.split_once(':').unwrap_or((&self.value, "")))
+    }
+
+    fn is_attribute(&self, name: &str) -> bool {
+        self.as_attribute().map(|(n, _)| n == name).unwrap_or(false)
+    }
+}
+
+impl SessionDescription {
+    /// Parses an SDP string.
+    ///
+    /// Lines can end with `\r\n` or `\n`. Apart from each line being `<type>=<value>`,
+    /// and the first line being `v=`, nothing is checked.
+    pub fn parse(input: &str) -> Result<Self, SdpError> {
+        let mut session = SessionSection::default();
+        let mut media: Vec<MediaSection> = vec![];
+
+        let lines = input
+            .split('\n')
+            .map(|l| l.strip_suffix('\r').unwrap_or(l))
+            .filter(|l| !l.is_empty());
+
+        for l in lines {
+            let mut chars = l.chars();
+            let (Some(typ), Some('=')) = (chars.next(), chars.next()) else {
+                return Err(SdpError::ParseError(format!("Bad SDP line: {}", l)));
+            };
+            let line = SdpLine::new(typ, chars.as_str());
+
+            if session.lines.is_empty() && typ != 'v' {
+                return Err(SdpError::ParseError("SDP must start with v=".into()));
+            }
+
+            if typ == 'm' {
+                media.push(MediaSection { lines: vec![line] });
+            } else if let Some(m) = media.last_mut() {
+                m.lines.push(line);
+            } else {
+                session.lines.push(line);
+            }
+        }
+
+        if session.lines.is_empty() {
+            return Err(SdpError::ParseError("Empty SDP".into()));
+        }
+
+        Ok(SessionDescription { session, media })
+    }
+
+    /// The m-section with the given `a=mid`.
+    pub fn media_by_mid(&mut self, mid: Mid) -> Option<&mut MediaSection> {
+        self.media.iter_mut().find(|m| m.mid() == Some(mid))
+    }
+
+    /// All lines, session level first.
+    pub fn lines(&self) -> impl Iterator<Item = &SdpLine> {
+        self.session
+            .lines
+            .iter()
+            .chain(self.media.iter().flat_map(|m| m.lines.iter()))
+    }
+}
+
+/// Methods shared by the session level and the m-sections.
+///
+/// `$before_b` are the line types that follow `b=` lines in the section, as ordered by RFC 8866.
+macro_rules! section_common {
+    ($Section:ty, $before_b:literal) => {
+        impl $Section {
+            /// The value of the first `a=<name>` line.
+            ///
+            /// The value is empty for attributes without one, like `a=rtcp-mux`.
+            pub fn attribute(&self, name: &str) -> Option<&str> {
+                self.lines
+                    .iter()
+                    .filter_map(|l| l.as_attribute())
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, v)| v)
+            }
+
+            /// The values of all `a=<name>` lines, in order.
+            pub fn attributes<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+                self.lines
+                    .iter()
+                    .filter_map(|l| l.as_attribute())
+                    .filter(move |(n, _)| *n == name)
+                    .map(|(_, v)| v)
+            }
+
+            /// Adds an `a=<name>` or `a=<name>:<value>` line last in the section.
+            pub fn add_attribute(&mut self, name: &str, value: Option<&str>) {
+                self.lines.push(SdpLine::attribute(name, value));
+            }
+
+            /// Removes all `a=<name>` lines. Returns how many were removed.
+            pub fn remove_attributes(&mut self, name: &str) -> usize {
+                let before = self.lines.len();
+                self.lines.retain(|l| !l.is_attribute(name));
+                before - self.lines.len()
+            }
+
+            /// The value of the `b=<typ>:<value>` line, like `AS` or `TIAS`.
+            pub fn bandwidth(&self, typ: &str) -> Option<u64> {
+                self.lines
+                    .iter()
+                    .filter(|l| l.typ == 'b')
+                    .filter_map(|l| l.value.split_once(':'))
+                    .find(|(t, _)| *t == typ)
+                    .and_then(|(_, v)| v.parse().ok())
+            }
+
+            /// Sets or, with `None`, removes the `b=<typ>:<value>` line.
+            ///
+            /// The unit depends on the type, `AS` is kbit/s and `TIAS` bit/s.
+            pub fn set_bandwidth(&mut self, typ: &str, value: Option<u64>) {
+                let is_typ = |l: &SdpLine| {
+                    l.typ == 'b' && l.value.split_once(':').map(|(t, _)| t) == Some(typ)
+                };
+
+                let Some(value) = value else {
+                    self.lines.retain(|l| !is_typ(l));
+                    return;
+                };
+                let line = SdpLine::new('b', format!("{}:{}", typ, value));
+
+                if let Some(l) = self.lines.iter_mut().find(|l| is_typ(l)) {
+                    *l = line;
+                    return;
+                }
+
+                let idx = self
+                    .lines
+                    .iter()
+                    .position(|l| $before_b.contains(l.typ))
+                    .unwrap_or(self.lines.len());
+                self.lines.insert(idx, line);
+            }
+
+            /// The ICE credentials from `a=ice-ufrag` and `a=ice-pwd`.
+            pub fn ice_creds(&self) -> Option<IceCreds> {
+                Some(IceCreds {
+                    ufrag: self.attribute("ice-ufrag")?.to_string(),
+                    pass: self.attribute("ice-pwd")?.to_string(),
+                })
+            }
+
+            /// The DTLS fingerprints from the `a=fingerprint` lines.
+            pub fn fingerprints(&self) -> Vec<Fingerprint> {
+                self.typed_attributes()
+                    .filter_map(|a| match a {
+                        MediaAttribute::Fingerprint(f) => Some(f),
+                        _ => None,
+                    })
+                    .collect()
+            }
+
+            fn typed_attributes(&self) -> impl Iterator<Item = MediaAttribute> + '_ {
+                self.lines
+                    .iter()
+                    .filter(|l| l.typ == 'a')
+                    .filter_map(|l| parse_media_attribute(&l.to_string()))
+            }
+        }
+    };
+}
+
+section_common!(SessionSection, "trzka");
+section_common!(MediaSection, "ka");
+
+impl MediaSection {
+    fn m_line(&self) -> Vec<&str> {
+        match self.lines.first() {
+            Some(l) if l.typ == 'm' => l.value.split(' ').collect(),
+            _ => vec![],
+        }
+    }
+
+    /// The media type of the `m=` line, like `audio`, `video` or `application`.
+    pub fn kind(&self) -> &str {
+        self.m_line().first().copied().unwrap_or("")
+    }
+
+    /// The port of the `m=` line. Port 0 means the m-section is disabled.
+    pub fn port(&self) -> Option<u16> {
+        let port = self.m_line().get(1).copied()?;
+        port.split('/').next()?.parse().ok()
+    }
+
+    /// Sets the port of the `m=` line.
+    pub fn set_port(&mut self, port: u16) {
+        let mut parts: Vec<String> = self.m_line().iter().map(|s| s.to_string()).collect();
+        if parts.len() < 2 {
+            return;
+        }
+        parts[1] = port.to_string();
+        self.lines[0].value = parts.join(" ");
+    }
+
+    /// The transport protocol of the `m=` line, like `UDP/TLS/RTP/SAVPF`.
+    pub fn proto(&self) -> &str {
+        self.m_line().get(2).copied().unwrap_or("")
+    }
+
+    /// The formats of the `m=` line, payload types for RTP.
+    pub fn formats(&self) -> Vec<&str> {
+        self.m_line().into_iter().skip(3).collect()
+    }
+
+    /// The `a=mid` value.
+    pub fn mid(&self) -> Option<Mid> {
+        self.attribute("mid").map(Mid::from)
+    }
+
+    /// The direction attribute, `a=sendrecv` and friends.
+    pub fn direction(&self) -> Option<Direction> {
+        self.lines
+            .iter()
+            .find_map(|l| as_direction(&l.value).filter(|_| l.typ == 'a'))
+    }
+
+    /// Replaces the direction attribute, or adds one if there is none.
+    pub fn set_direction(&mut self, dir: Direction) {
+        let line = SdpLine::attribute(&dir.to_string(), None);
+        let existing = self
+            .lines
+            .iter_mut()
+            .find(|l| l.typ == 'a' && as_direction(&l.value).is_some());
+
+        match existing {
+            Some(l) => *l = line,
+            None => self.lines.push(line),
+        }
+    }
+
+    /// The audio and video codecs from the `a=rtpmap`, `a=fmtp` and `a=rtcp-fb` lines.
+    ///
+    /// Only codecs str0m knows are included, use [`MediaSection::attributes()`] for the rest.
+    /// The codecs are in the order of the `a=rtpmap` lines. Resend (RTX) payload types are
+    /// in [`PayloadParams::resend()`].
+    pub fn codecs(&self) -> Vec<PayloadParams> {
+        let attrs: Vec<_> = self.typed_attributes().collect();
+        rtp_params(&attrs)
+    }
+
+    /// Removes a payload type from the `m=` line, along with its `a=rtpmap`, `a=fmtp`
+    /// and `a=rtcp-fb` lines. Any resend (RTX) payload type for it is also removed.
+    ///
+    /// Returns whether the payload type was found.
+    pub fn remove_codec(&mut self, pt: Pt) -> bool {
+        let mut remove = vec![pt];
+        for a in self.typed_attributes() {
+            if let MediaAttribute::Fmtp { pt: rtx, values } = a {
+                if values.contains(&FormatParam::Apt(pt)) {
+                    remove.push(rtx);
+                }
+            }
+        }
+
+        let formats: Vec<String> = self.formats().iter().map(|s| s.to_string()).collect();
+        let is_removed = |f: &str| f.parse::<u8>().map(|v| remove.contains(&v.into())) == Ok(true);
+
+        if !formats.iter().any(|f| is_removed(f)) {
+            return false;
+        }
+
+        let mut m_line: Vec<String> = self.m_line()[..3].iter().map(|s| s.to_string()).collect();
+        m_line.extend(formats.into_iter().filter(|f| !is_removed(f)));
+        self.lines[0].value = m_line.join(" ");
+
+        self.lines.retain(|l| match l.as_attribute() {
+            Some(("rtpmap" | "fmtp" | "rtcp-fb", v)) => {
+                !is_removed(v.split(' ').next().unwrap_or(""))
+            }
+            _ => true,
+        });
+
+        true
+    }
+
+    /// The ICE candidates from the `a=candidate` lines.
+    ///
+    /// Lines that don't parse as candidates are skipped.
+    pub fn candidates(&self) -> Vec<Candidate> {
+        self.lines
+            .iter()
+            .filter(|l| l.is_attribute("candidate"))
+            .filter_map(|l| Candidate::from_sdp_string(&l.value).ok())
+            .collect()
+    }
+
+    /// Replaces the `a=candidate` lines.
+    ///
+    /// The new lines go where the first candidate was, or else before any
+    /// `a=end-of-candidates`, or else last.
+    pub fn set_candidates(&mut self, candidates: &[Candidate]) {
+        let idx = self
+            .lines
+            .iter()
+            .position(|l| l.is_attribute("candidate") || l.is_attribute("end-of-candidates"))
+            .unwrap_or(self.lines.len());
+
+        // There are no candidates before idx, so it stays put.
+        self.lines.retain(|l| !l.is_attribute("candidate"));
+
+        let new = candidates
+            .iter()
+            .map(|c| SdpLine::new('a', c.to_sdp_string()));
+        self.lines.splice(idx..idx, new);
+    }
+}
+
+fn as_direction(v: &str) -> Option<Direction> {
+    matches!(v, "sendrecv" | "sendonly" | "recvonly" | "inactive").then(|| Direction::from(v))
+}
+
+impl FromStr for SessionDescription {
+    type Err = SdpError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SessionDescription::parse(s)
+    }
+}
+
+impl fmt::Display for SessionDescription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for l in self.lines() {
+            write!(f, "{}\r\n", l)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SdpLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.typ, self.value)
+    }
+}
+
+macro_rules! description_conv {
+    ($Struct:tt) => {
+        impl From<&$Struct> for SessionDescription {
+            fn from(v: &$Struct) -> Self {
+                // Our own serialization is always well formed.
+                SessionDescription::parse(&v.to_sdp_string()).expect("SDP to parse")
+            }
+        }
+
+        impl TryFrom<&SessionDescription> for $Struct {
+            type Error = SdpError;
+
+            fn try_from(v: &SessionDescription) -> Result<Self, Self::Error> {
+                $Struct::from_sdp_string(&v.to_string())
+            }
+        }
+    };
+}
+
+description_conv!(SdpOffer);
+description_conv!(SdpAnswer);
@@ -46,7 +46,8 @@ where
         originator_line(),           // o=- 6564425948916445306 2 IN IP4 127.0.0.1
         typed_line('s', token('-')), // s=-
         many::<Vec<_>, _, _>(ignored_session_line()),
-        optional(bandwidth_line()),                         // b=CT:1234
+        // b=CT:1234, there can be several b= lines of different types, we keep the first.
+        many::<Vec<_>, _, _>(bandwidth_line()).map(|v| v.into_iter().next()),
         typed_line('t', string("0 0")),                     // t=0 0
         many::<Vec<_>, _, _>(typed_line('r', any_value())), // r should never appear
         //
@@ -146,6 +147,9 @@ where
     // a=ice-lite
     let ice_lite = attribute_line_flag("ice-lite").map(|_| SessionAttribute::IceLite);
 
+    // a=cryptex
+    let cryptex = attribute_line_flag("cryptex").map(|_| SessionAttribute::Cryptex);
+
     // a=ice-ufrag:IdNYTNL1fjvjyEzL
     let ice_ufrag = attribute_line("ice-ufrag", any_value()).map(SessionAttribute::IceUfrag);
 
@@ -192,6 +196,7 @@ where
         attempt(group),
         attempt(msid_semantic),
         attempt(ice_lite),
+        attempt(cryptex),
         attempt(ice_ufrag),
         attempt(ice_pwd),
         attempt(ice_opt),
@@ -213,6 +218,13 @@ pub fn parse_candidate(s: &str) -> Result<Candidate, SdpError> {
         .map_err(|e| SdpError::ParseError(e.to_string()))
 }
 
+/// Parse a single `a=` line as found in an m-line, without trailing newline.
+///
+/// Attributes we don't understand become [`MediaAttribute::Unused`].
+pub(crate) fn parse_media_attribute(line: &str) -> Option<MediaAttribute> {
+    media_attribute_line().parse(line).ok().map(|(a, _)| a)
+}
+
 /// Parser for candidate, without attribute prefix (a=).
 fn candidate<Input>() -> impl Parser<Input, Output = Candidate>
 where
@@ -365,7 +377,8 @@ where
     (
         media_line(),
         optional(typed_line('c', any_value())), // c=IN IP4 0.0.0.0
-        optional(bandwidth_line()),             // b=AS:2500
+        // b=AS:2500, there can be several b= lines of different types, we keep the first.
+        many::<Vec<_>, _, _>(bandwidth_line()).map(|v| v.into_iter().next()),
         many::<Vec<_>, _, _>(media_attribute_line()),
     )
         .and_then(|((typ, port, proto, pts), _, bw, attrs)| {
@@ -413,6 +426,9 @@ where
         attempt(string("UDP/TLS/RTP/SAVPF").map(|_| Proto::Srtp)),
         attempt(string("DTLS/SCTP").map(|_| Proto::Sctp)),
         attempt(string("UDP/DTLS/SCTP").map(|_| Proto::Sctp)),
+        // SDES-SRTP, where SAVPF must be tried before SAVP.
+        attempt(string("RTP/SAVPF").map(|_| Proto::SdesSavpf)),
+        attempt(string("RTP/SAVP").map(|_| Proto::SdesSavp)),
     ));
 
     let parse_pt = not_sp().and_then(|s| {
@@ -482,6 +498,40 @@ where
     // a=setup:actpass
     let setup = attribute_line("setup", setup_val).map(MediaAttribute::Setup);
 
+    // a=crypto:1 AES_CM_128_HMAC_SHA1_80 inline:PS1uQCVeeCFCanVmcjkpPywjNWhcYD0mXXtxaVBR|2^20|1:4
+    // a=crypto:<tag> <crypto-suite> <key-params> [<session-params>]
+    let crypto = attribute_line(
+        "crypto",
+        (
+            not_sp::<Input>().and_then(|s| {
+                s.parse::<u32>()
+                    .map_err(StreamErrorFor::<Input>::message_format)
+            }),
+            token(' '),
+            not_sp(),
+            token(' '),
+            not_sp::<Input>().and_then(|s| {
+                s.split(';')
+                    .map(SdesKeyParam::parse)
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| StreamErrorFor::<Input>::message("Bad crypto key-params"))
+            }),
+            optional((token(' '), any_value())),
+        ),
+    )
+    .map(|(tag, _, suite, _, keys, params)| {
+        MediaAttribute::Crypto(SdesCrypto {
+            tag,
+            suite,
+            keys,
+            session_params: params
+                .map(|(_, p)| p.split_whitespace().map(|s| s.to_string()).collect())
+                .unwrap_or_default(),
+        })
+    });
+
+    let keying = choice((attempt(finger), attempt(setup), attempt(crypto)));
+
     // a=mid:0
     let mid = attribute_line("mid", any_value())
         .map(|m| Mid::from(m.as_str()))
@@ -544,6 +594,16 @@ where
     let rtcpmuxonly = attribute_line_flag("rtcp-mux-only").map(|_| MediaAttribute::RtcpMuxOnly);
     let rtcprsize = attribute_line_flag("rtcp-rsize").map(|_| MediaAttribute::RtcpRsize);
 
+    // a=cryptex
+    let cryptex = attribute_line_flag("cryptex").map(|_| MediaAttribute::Cryptex);
+
+    let flags = choice((
+        attempt(rtcpmux),
+        attempt(rtcpmuxonly),
+        attempt(rtcprsize),
+        attempt(cryptex),
+    ));
+
     // a=candidate
     let cand = candidate_attribute().map(MediaAttribute::Candidate);
 
@@ -758,8 +818,7 @@ where
         attempt(ice_ufrag),
         attempt(ice_pwd),
         attempt(ice_opt),
-        attempt(finger),
-        attempt(setup),
+        attempt(keying),
         attempt(mid),
         attempt(sctp_port),
         attempt(max_message_size),
@@ -767,9 +826,7 @@ where
         attempt(direction),
         attempt(msid),
         attempt(rtcp),
-        attempt(rtcpmux),
-        attempt(rtcpmuxonly),
-        attempt(rtcprsize),
+        attempt(flags),
         attempt(cand),
         attempt(endof),
         attempt(rtpmap),
@@ -910,6 +967,15 @@ mod test {
         assert_eq!(x, Ok((SessionAttribute::IceLite, "")));
     }
 
+    #[test]
+    fn cryptex_attribute_line() {
+        let x = session_attribute_line().parse("a=cryptex");
+        assert_eq!(x, Ok((SessionAttribute::Cryptex, "")));
+
+        let x = media_attribute_line().parse("a=cryptex");
+        assert_eq!(x, Ok((MediaAttribute::Cryptex, "")));
+    }
+
     #[test]
     fn session_attribute_line_finger() {
         let x = session_attribute_line().parse("a=fingerprint:sha-256 45:AD:5C:82:F8:BE");
@@ -993,6 +1059,65 @@ mod test {
         assert_eq!("a=simulcast:send 4 recv 2;3\r\n", x.0.to_string());
     }
 
+    #[test]
+    fn media_attribute_line_crypto() {
+        let line = "a=crypto:1 AES_CM_128_HMAC_SHA1_80 \
+            inline:PS1uQCVeeCFCanVmcjkpPywjNWhcYD0mXXtxaVBR|2^20|1:4 UNENCRYPTED_SRTCP";
+        let x = media_attribute_line().parse(line).unwrap();
+
+        let MediaAttribute::Crypto(c) = &x.0 else {
+            panic!("Expected a=crypto");
+        };
+        assert_eq!(c.tag, 1);
+        assert_eq!(c.suite, "AES_CM_128_HMAC_SHA1_80");
+        assert_eq!(c.keys.len(), 1);
+        assert_eq!(c.keys[0].key_salt.len(), 30);
+        assert_eq!(c.keys[0].lifetime, Some(1 << 20));
+        assert_eq!(c.keys[0].mki, Some((1, 4)));
+        assert_eq!(c.keys[0].mki_bytes(), Some(vec![0, 0, 0, 1]));
+        assert_eq!(c.session_params, ["UNENCRYPTED_SRTCP"]);
+
+        assert_eq!(format!("{line}\r\n"), x.0.to_string());
+    }
+
+    #[test]
+    fn media_attribute_line_crypto_bad_key() {
+        let x = media_attribute_line()
+            .parse("a=crypto:1 AES_CM_128_HMAC_SHA1_80 inline:PS1u*|2^20")
+            .unwrap();
+        assert!(matches!(x.0, MediaAttribute::Unused(_)));
+    }
+
+    #[test]
+    fn media_line_sdes() {
+        let m = media_line().parse("m=audio 9 RTP/SAVPF 10\r\n");
+        assert_eq!(
+            m,
+            Ok((
+                (
+                    MediaType::Audio,
+                    "9".into(),
+                    Proto::SdesSavpf,
+                    vec![10.into()],
+                ),
+                ""
+            ))
+        );
+        let m = media_line().parse("m=audio 9 RTP/SAVP 10\r\n");
+        assert_eq!(
+            m,
+            Ok((
+                (
+                    MediaType::Audio,
+                    "9".into(),
+                    Proto::SdesSavp,
+                    vec![10.into()],
+                ),
+                ""
+            ))
+        );
+    }
+
     #[test]
     fn media_line_simple() {
         let m = media_line().parse("m=audio 9 UDP/TLS/RTP/SAVPF 10\r\n");
@@ -1285,6 +1410,27 @@ mod test {
         println!("{:?}", parsed);
         parsed.expect("to parse ok");
     }
+
+    #[test]
+    fn parse_several_bandwidth_lines() {
+        let sdp = "v=0\r\n\
+        o=- 0 0 IN IP4 127.0.0.1\r\n\
+        s=-\r\n\
+        b=AS:2000\r\n\
+        b=TIAS:2000000\r\n\
+        t=0 0\r\n\
+        m=application 9 UDP/DTLS/SCTP webrtc-datachannel\r\n\
+        c=IN IP4 0.0.0.0\r\n\
+        b=AS:30\r\n\
+        b=TIAS:30000\r\n\
+        a=mid:0\r\n\
+        a=sctp-port:5000\r\n\
+        ";
+
+        let (sdp, _) = sdp_parser().parse(sdp).expect("to parse ok");
+        assert_eq!(sdp.session.bw.unwrap().val, "2000");
+        assert_eq!(sdp.media_lines[0].bw.as_ref().unwrap().typ, "AS");
+    }
 }
 
 // Safari addTransceiver('audio', {direction: 'sendonly'}))
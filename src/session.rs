@@ -9,7 +9,7 @@ use crate::format::PayloadParams;
 use crate::io::{DatagramSend, DATAGRAM_MTU, DATAGRAM_MTU_WARN};
 use crate::media::KeyframeRequestKind;
 use crate::media::Media;
-use crate::media::{MediaAdded, MediaChanged};
+use crate::media::{MediaAdded, MediaChanged, SFrame};
 use crate::packet::SendSideBandwithEstimator;
 use crate::packet::{LeakyBucketPacer, NullPacer, Pacer, PacerImpl};
 use crate::rtp::{Extension, RawPacket};
@@ -93,6 +93,15 @@ pub(crate) struct Session {
 
     pub ice_lite: bool,
 
+    /// Whether we offer and accept Cryptex (RFC 9335).
+    pub enable_cryptex: bool,
+
+    /// Whether Cryptex is in use, i.e. negotiated by both sides.
+    cryptex: bool,
+
+    /// SFrame end-to-end encryption of frames, if enabled.
+    pub sframe: Option<SFrame>,
+
     /// Whether we are running in RTP-mode.
     pub rtp_mode: bool,
 
@@ -159,6 +168,9 @@ impl Session {
             poll_packet_buf: vec![0; 2000],
             pending_packet: None,
             ice_lite: config.ice_lite,
+            enable_cryptex: config.cryptex,
+            cryptex: false,
+            sframe: None,
             rtp_mode: config.rtp_mode,
             feedback_tx: VecDeque::new(),
             feedback_rx: VecDeque::new(),
@@ -208,6 +220,30 @@ impl Session {
         self.srtp_tx = Some(SrtpContext::new(srtp_crypto, srtp_profile, &mat, left));
     }
 
+    /// Set the MKI the remote puts in SRTP packets, for SDES keying.
+    pub fn set_srtp_rx_mki(&mut self, mki: Option<Vec<u8>>) {
+        if let Some(srtp) = self.srtp_rx.as_mut() {
+            srtp.set_mki(mki);
+        }
+    }
+
+    /// Set the lifetime of the remote SDES key, counting `used` packets already received with it.
+    pub fn set_srtp_rx_lifetime(&mut self, lifetime: Option<u64>, used: u64) {
+        if let Some(srtp) = self.srtp_rx.as_mut() {
+            srtp.set_lifetime(lifetime, used);
+        }
+    }
+
+    /// Number of SRTP and SRTCP packets received with the current keys.
+    pub fn srtp_rx_packets(&self) -> u64 {
+        self.srtp_rx.as_ref().map(|s| s.packets()).unwrap_or(0)
+    }
+
+    /// The SRTP profile in use, once the keying material is set.
+    pub fn srtp_profile(&self) -> Option<SrtpProfile> {
+        self.srtp_tx.as_ref().map(|s| s.profile())
+    }
+
     pub fn handle_timeout(&mut self, now: Instant) -> Result<(), RtcError> {
         // Payload any waiting samples
         self.do_payload()?;
@@ -244,6 +280,11 @@ impl Session {
         Ok(())
     }
 
+    /// Queue RTCP BYE for the streams we send.
+    pub fn send_bye(&mut self, now: Instant) {
+        self.streams.create_bye(now, &mut self.feedback_tx);
+    }
+
     fn update_queue_state(&mut self, now: Instant) {
         let iter = self.streams.streams_tx().map(|m| m.queue_state(now));
 
@@ -354,11 +395,28 @@ impl Session {
     }
 
     pub(crate) fn handle_rtp(&mut self, now: Instant, mut header: RtpHeader, buf: &[u8]) {
-        // Rewrite absolute-send-time (if present) to be relative to now.
-        header.ext_vals.update_absolute_send_time(now);
-
         trace!("Handle RTP: {:?}", header);
 
+        // Cryptex hides the mid/rid needed to map an unknown SSRC. Decrypt to find them,
+        // assuming the stream is new enough to have a zero ROC.
+        if header.cryptex
+            && self
+                .streams
+                .mid_ssrc_rx_by_ssrc_or_rtx(now, header.ssrc)
+                .is_none()
+        {
+            if let Some(srtp) = self.srtp_rx.as_mut() {
+                let srtp_index = extend_u16(None, header.sequence_number);
+                let mut peek = header.clone();
+                if srtp
+                    .unprotect_rtp(buf, &mut peek, srtp_index, &self.exts)
+                    .is_some()
+                {
+                    header.ext_vals = peek.ext_vals;
+                }
+            }
+        }
+
         // The ssrc is the _main_ ssrc (no the rtx, that might be in the header).
         let Some((mid, ssrc)) = self.mid_and_ssrc_for_header(now, &header) else {
             debug!("No mid/SSRC for header: {:?}", header);
@@ -409,7 +467,7 @@ impl Session {
             return;
         }
 
-        let mut data = match srtp.unprotect_rtp(buf, &header, *seq_no) {
+        let mut data = match srtp.unprotect_rtp(buf, &mut header, *seq_no, &self.exts) {
             Some(v) => v,
             None => {
                 trace!(
@@ -425,6 +483,9 @@ impl Session {
             }
         };
 
+        // Rewrite absolute-send-time (if present) to be relative to now.
+        header.ext_vals.update_absolute_send_time(now);
+
         if header.has_padding && !RtpHeader::unpad_payload(&mut data) {
             // Unpadding failed. Broken data?
             trace!("unpadding of unprotected payload failed");
@@ -497,6 +558,7 @@ impl Session {
                 self.reordering_size_audio,
                 self.reordering_size_video,
                 &self.codec_config,
+                self.sframe.is_some(),
             );
         }
     }
@@ -621,7 +683,26 @@ impl Session {
         }
 
         for media in &mut self.medias {
-            if let Some(e) = media.poll_sample(&self.codec_config)? {
+            if let Some(e) = media.pending_sample.take() {
+                return Ok(Some(Event::MediaData(e)));
+            }
+
+            while let Some(mut e) = media.poll_sample(&self.codec_config)? {
+                if let Some(sframe) = &mut self.sframe {
+                    match sframe.decrypt(&e.data) {
+                        Ok(data) => e.data = data,
+                        Err(err) => {
+                            debug!("Drop MediaData ({}) failing SFrame: {}", e.mid, err);
+                            continue;
+                        }
+                    }
+                }
+
+                if let Some(changed) = media.detect_payload_change(&e) {
+                    media.pending_sample = Some(e);
+                    return Ok(Some(Event::PayloadChanged(changed)));
+                }
+
                 return Ok(Some(Event::MediaData(e)));
             }
         }
@@ -716,7 +797,7 @@ impl Session {
         let twcc_enabled = exts.id_of(Extension::TransportSequenceNumber).is_some();
         let twcc = twcc_enabled.then_some(&mut self.twcc);
 
-        let receipt = stream.poll_packet(now, exts, twcc, params, buf)?;
+        let receipt = stream.poll_packet(now, exts, twcc, params, self.cryptex, buf)?;
 
         let PacketReceipt {
             header,
@@ -803,6 +884,17 @@ impl Session {
         }
     }
 
+    pub fn cryptex(&self) -> bool {
+        self.cryptex
+    }
+
+    pub fn set_cryptex(&mut self, enabled: bool) {
+        if self.cryptex != enabled {
+            debug!("Cryptex enabled: {}", enabled);
+            self.cryptex = enabled;
+        }
+    }
+
     pub fn enable_twcc_feedback(&mut self) {
         if !self.enable_twcc_feedback {
             debug!("Enable TWCC feedback");
@@ -896,7 +988,7 @@ impl Session {
 
     fn do_payload(&mut self) -> Result<(), RtcError> {
         for m in &mut self.medias {
-            m.do_payload(&mut self.streams, &self.codec_config)?;
+            m.do_payload(&mut self.streams, &self.codec_config, self.sframe.is_some())?;
         }
 
         Ok(())
@@ -2,12 +2,16 @@
 
 use std::{
     collections::{HashMap, VecDeque},
+    net::SocketAddr,
     time::{Duration, Instant},
 };
 
+use crate::channel::{ChannelId, ChannelState};
+use crate::crypto::SrtpProfile;
+use crate::io::Protocol;
 use crate::rtp_::MidRid;
 use crate::rtp_::{Mid, Rid};
-use crate::Bitrate;
+use crate::{Bitrate, CandidateKind};
 
 pub(crate) struct Stats {
     last_now: Option<Instant>,
@@ -25,6 +29,11 @@ pub(crate) struct StatsSnapshot {
     pub ingress: HashMap<MidRid, MediaIngressStats>,
     pub egress: HashMap<MidRid, MediaEgressStats>,
     pub bwe_tx: Option<Bitrate>,
+    pub selected_pair_rtt: Option<Duration>,
+    pub srtp_profile: Option<SrtpProfile>,
+    pub candidate_pairs: Vec<CandidatePairStats>,
+    pub channels: Vec<ChannelStats>,
+    pub sctp: Option<SctpStats>,
     timestamp: Instant,
 }
 
@@ -40,9 +49,18 @@ impl StatsSnapshot {
             ingress: HashMap::new(),
             egress: HashMap::new(),
             bwe_tx: None,
+            selected_pair_rtt: None,
+            srtp_profile: None,
+            candidate_pairs: Vec::new(),
+            channels: Vec::new(),
+            sctp: None,
             timestamp,
         }
     }
+
+    pub(crate) fn timestamp(&self) -> Instant {
+        self.timestamp
+    }
 }
 
 // Output events
@@ -52,6 +70,9 @@ pub(crate) enum StatsEvent {
     Peer(PeerStats),
     MediaEgress(MediaEgressStats),
     MediaIngress(MediaIngressStats),
+    CandidatePair(Box<CandidatePairStats>),
+    Channel(Box<ChannelStats>),
+    Sctp(SctpStats),
 }
 
 /// Peer statistics in [`Event::PeerStats`][crate::Event::PeerStats].
@@ -75,6 +96,137 @@ pub struct PeerStats {
     pub egress_loss_fraction: Option<f32>,
     /// The ingress loss since the last stats event.
     pub ingress_loss_fraction: Option<f32>,
+    /// Round trip time of the selected ICE candidate pair, from the last STUN
+    /// connectivity check.
+    pub selected_pair_rtt: Option<Duration>,
+    /// The SRTP protection profile negotiated via DTLS, once connected.
+    pub srtp_profile: Option<SrtpProfile>,
+}
+
+/// ICE candidate pair statistics in [`Event::CandidatePairStats`][crate::Event::CandidatePairStats].
+///
+/// Loosely equivalent to [`RTCIceCandidatePairStats`][1]. Byte and packet counters only
+/// count non-STUN traffic.
+///
+/// [1]: https://www.w3.org/TR/webrtc-stats/#candidatepair-dict*
+#[derive(Debug, Clone)]
+pub struct CandidatePairStats {
+    /// Protocol of the candidate pair.
+    pub proto: Protocol,
+    /// Local socket address datagrams are sent from.
+    pub local_addr: SocketAddr,
+    /// Kind of the local candidate.
+    pub local_kind: CandidateKind,
+    /// Remote socket address datagrams are sent to.
+    pub remote_addr: SocketAddr,
+    /// Kind of the remote candidate.
+    pub remote_kind: CandidateKind,
+    /// Connectivity check state of the pair.
+    pub state: CandidatePairState,
+    /// Whether the pair is nominated.
+    pub nominated: bool,
+    /// Whether this is the pair currently used for sending.
+    pub selected: bool,
+    /// Total bytes sent.
+    pub bytes_sent: u64,
+    /// Total bytes received.
+    pub bytes_received: u64,
+    /// Total datagrams sent.
+    pub packets_sent: u64,
+    /// Total datagrams received.
+    pub packets_received: u64,
+    /// Total STUN binding requests sent.
+    pub requests_sent: u64,
+    /// Total STUN binding requests received.
+    pub requests_received: u64,
+    /// Total STUN binding responses sent.
+    pub responses_sent: u64,
+    /// Total STUN binding responses received.
+    pub responses_received: u64,
+    /// Round trip time of the last answered STUN binding request.
+    pub current_rtt: Option<Duration>,
+    /// Sum of all round trip times. Divide by `responses_received` for the average.
+    pub total_rtt: Duration,
+    /// Last time a datagram was sent.
+    pub last_packet_sent: Option<Instant>,
+    /// Last time a datagram was received.
+    pub last_packet_received: Option<Instant>,
+    /// Last time a STUN binding request was received.
+    pub last_request_received: Option<Instant>,
+    /// Last time a STUN binding response was received.
+    pub last_response_received: Option<Instant>,
+    /// Timestamp when these stats were generated.
+    pub timestamp: Instant,
+}
+
+/// Connectivity check state of a candidate pair.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CandidatePairState {
+    /// No check has been sent.
+    Waiting,
+    /// A check has been sent, but not answered.
+    InProgress,
+    /// A check has been answered.
+    Succeeded,
+}
+
+/// Data channel statistics in [`Event::ChannelStats`][crate::Event::ChannelStats].
+///
+/// Loosely equivalent to [`RTCDataChannelStats`][1]. Counters only include data
+/// messages, not the DCEP open/ack.
+///
+/// [1]: https://www.w3.org/TR/webrtc-stats/#dcstats-dict*
+#[derive(Debug, Clone)]
+pub struct ChannelStats {
+    /// The channel these stats are for.
+    pub id: ChannelId,
+    /// Label of the channel. Empty until the channel is configured.
+    pub label: String,
+    /// Protocol of the channel. Empty until the channel is configured.
+    pub protocol: String,
+    /// Current state of the channel.
+    pub state: ChannelState,
+    /// Total messages sent.
+    pub messages_sent: u64,
+    /// Total bytes sent.
+    pub bytes_sent: u64,
+    /// Total messages received.
+    pub messages_received: u64,
+    /// Total bytes received.
+    pub bytes_received: u64,
+    /// Bytes written, but not yet acknowledged by the remote peer.
+    pub buffered_amount: usize,
+    /// Timestamp when these stats were generated.
+    pub timestamp: Instant,
+}
+
+/// SCTP association statistics in [`Event::SctpStats`][crate::Event::SctpStats].
+///
+/// One event for the association that carries all data channels.
+#[derive(Debug, Clone)]
+pub struct SctpStats {
+    /// Current retransmission timeout (RTO).
+    ///
+    /// This is not the round trip time, see [`SctpStats::srtt`].
+    pub rto: Duration,
+    /// Smoothed round trip time (SRTT). `None` until the first RTT measurement.
+    pub srtt: Option<Duration>,
+    /// Current congestion window in bytes.
+    pub cwnd: u32,
+    /// Remote receiver window in bytes, less the bytes in flight.
+    pub rwnd: u32,
+    /// Total DATA chunks received.
+    pub data_chunks_received: u64,
+    /// Total SACK chunks received.
+    pub sacks_received: u64,
+    /// Number of retransmissions due to the T3-rtx timer expiring.
+    pub t3_timeouts: u64,
+    /// Number of times the delayed SACK timer expired.
+    pub ack_timeouts: u64,
+    /// Number of fast retransmissions.
+    pub fast_retransmits: u64,
+    /// Timestamp when these stats were generated.
+    pub timestamp: Instant,
 }
 
 /// Outgoing media statistics in [`Event::MediaEgressStats`][crate::Event::MediaEgressStats].
@@ -233,10 +385,25 @@ impl Stats {
             bwe_tx: snapshot.bwe_tx,
             egress_loss_fraction: snapshot.egress_loss_fraction,
             ingress_loss_fraction: snapshot.ingress_loss_fraction,
+            selected_pair_rtt: snapshot.selected_pair_rtt,
+            srtp_profile: snapshot.srtp_profile,
         };
 
         self.events.push_back(StatsEvent::Peer(event));
 
+        for event in snapshot.candidate_pairs.drain(..) {
+            self.events
+                .push_back(StatsEvent::CandidatePair(Box::new(event)));
+        }
+
+        if let Some(event) = snapshot.sctp.take() {
+            self.events.push_back(StatsEvent::Sctp(event));
+        }
+
+        for event in snapshot.channels.drain(..) {
+            self.events.push_back(StatsEvent::Channel(Box::new(event)));
+        }
+
         for (_, event) in snapshot.ingress.drain() {
             self.events.push_back(StatsEvent::MediaIngress(event));
         }
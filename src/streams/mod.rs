@@ -9,9 +9,9 @@ use crate::media::{KeyframeRequest, Media};
 use crate::rtp_::MidRid;
 use crate::rtp_::Ssrc;
 use crate::rtp_::{Bitrate, Pt};
+use crate::rtp_::{Goodbye, ReportList, Rtcp, RtpHeader};
 use crate::rtp_::{MediaTime, SenderInfo};
 use crate::rtp_::{Mid, Rid, SeqNo};
-use crate::rtp_::{Rtcp, RtpHeader};
 use crate::util::{already_happened, NonCryptographicRng};
 
 pub use self::receive::StreamRx;
@@ -463,6 +463,21 @@ impl Streams {
         }
     }
 
+    /// Sender reports followed by BYE for all send streams, when closing.
+    pub(crate) fn create_bye(&mut self, now: Instant, feedback: &mut VecDeque<Rtcp>) {
+        let mut ssrcs = vec![];
+
+        for stream in self.streams_tx.values_mut() {
+            stream.create_sr_and_update(now, feedback);
+            ssrcs.push(stream.ssrc());
+            ssrcs.extend(stream.rtx());
+        }
+
+        for reports in ReportList::lists_from_iter(ssrcs) {
+            feedback.push_back(Rtcp::Goodbye(Goodbye { reports }));
+        }
+    }
+
     pub(crate) fn poll_keyframe_request(&mut self) -> Option<KeyframeRequest> {
         self.streams_tx.values_mut().find_map(|s| {
             let kind = s.poll_keyframe_request()?;
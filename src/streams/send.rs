@@ -317,6 +317,7 @@ impl StreamTx {
         exts: &ExtensionMap,
         twcc: Option<&mut u64>,
         params: &[PayloadParams],
+        cryptex: bool,
         buf: &mut Vec<u8>,
     ) -> Option<PacketReceipt> {
         let mid = self.midrid.mid();
@@ -446,6 +447,8 @@ impl StreamTx {
             *twcc += 1;
         }
 
+        header.cryptex = cryptex;
+
         buf.resize(DATAGRAM_MAX_PACKET_SIZE, 0);
 
         let header_len = header.write_to(buf, exts);
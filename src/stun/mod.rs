@@ -0,0 +1,121 @@
+//! Generic STUN messages (RFC 8489).
+//!
+//! A parser and builder for implementing STUN/TURN servers, health checkers and similar
+//! outside of [`Rtc`][crate::Rtc]. This is the same codec the ICE agent uses.
+//!
+//! ```
+//! use str0m::stun::{Attribute, Class, Credentials, Message, MessageBuilder, Method, TransId};
+//!
+//! let creds = Credentials::ShortTerm { password: "secret" };
+//!
+//! let buf = MessageBuilder::new(Class::Request, Method::Binding, TransId::new())
+//!     .attribute(Attribute::Username("alice"))
+//!     .attribute(Attribute::Software("example"))
+//!     .message_integrity(creds)
+//!     .fingerprint()
+//!     .build()
+//!     .unwrap();
+//!
+//! let msg = Message::parse(&buf).unwrap();
+//! assert_eq!(msg.method(), Method::Binding);
+//! assert_eq!(msg.username(), Some("alice"));
+//! assert!(msg.verify_fingerprint());
+//! assert!(msg.verify_integrity(&creds));
+//! ```
+
+use crate::io::stun::{HEADER_LEN, MAGIC};
+
+pub use crate::io::stun::{Attribute, Credentials, Message, MessageBuilder, PasswordAlgorithm};
+pub use crate::io::stun::{Class, Method, StunError, TransId};
+
+mod responder;
+pub use responder::StunResponder;
+
+/// Destination of a datagram received on a socket shared between [`Rtc`][crate::Rtc]
+/// instances and a [`StunResponder`]. See [`demux()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Demux<'a> {
+    /// ICE connectivity check for the `Rtc` whose local ICE ufrag is `ufrag`
+    /// (see [`RtcConfig::set_local_ice_credentials()`][crate::RtcConfig::set_local_ice_credentials]).
+    Ice {
+        /// The local ufrag, i.e. the part of USERNAME before the colon.
+        ufrag: &'a str,
+    },
+    /// Unauthenticated Binding request for the [`StunResponder`].
+    Responder,
+    /// Any other STUN, such as responses to checks sent by a full ICE agent.
+    /// Route by source address.
+    OtherStun,
+    /// Not STUN, i.e. DTLS, RTP or RTCP. Route by source address.
+    Media,
+}
+
+/// Tell apart which handler a received datagram belongs to.
+///
+/// ```
+/// # use str0m::stun::{demux, Demux};
+/// # let buf = &[0x80, 0x60, 0, 1];
+/// match demux(buf) {
+///     Demux::Ice { ufrag } => { /* look up Rtc by ufrag */ }
+///     Demux::Responder => { /* StunResponder::handle() */ }
+///     Demux::OtherStun | Demux::Media => { /* look up Rtc by source address */ }
+/// }
+/// ```
+pub fn demux(buf: &[u8]) -> Demux<'_> {
+    let is_stun = buf.len() >= HEADER_LEN && buf[0] < 2 && &buf[4..8] == MAGIC;
+    if !is_stun {
+        return Demux::Media;
+    }
+
+    let Ok(message) = Message::parse(buf) else {
+        return Demux::OtherStun;
+    };
+
+    if message.method() != Method::Binding
+        || !matches!(message.class(), Class::Request | Class::Indication)
+    {
+        return Demux::OtherStun;
+    }
+
+    if let Some(username) = message.username() {
+        let ufrag = username.split(':').next().unwrap_or(username);
+        Demux::Ice { ufrag }
+    } else if message.class() == Class::Request && !message.has_integrity() {
+        Demux::Responder
+    } else {
+        Demux::OtherStun
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn demux_datagrams() {
+        let ice = MessageBuilder::new(Class::Request, Method::Binding, TransId::new())
+            .attribute(Attribute::Username("local:remote"))
+            .attribute(Attribute::Priority(1))
+            .message_integrity(Credentials::ShortTerm { password: "x" })
+            .fingerprint()
+            .build()
+            .unwrap();
+        assert_eq!(demux(&ice), Demux::Ice { ufrag: "local" });
+
+        let plain = MessageBuilder::new(Class::Request, Method::Binding, TransId::new())
+            .build()
+            .unwrap();
+        assert_eq!(demux(&plain), Demux::Responder);
+
+        let response = MessageBuilder::new(Class::Success, Method::Binding, TransId::new())
+            .attribute(Attribute::XorMappedAddress(
+                "192.0.2.1:32853".parse().unwrap(),
+            ))
+            .build()
+            .unwrap();
+        assert_eq!(demux(&response), Demux::OtherStun);
+
+        assert_eq!(demux(&[0x80, 0x60, 0x00, 0x01]), Demux::Media);
+        assert_eq!(demux(&[22, 254, 253, 0]), Demux::Media);
+    }
+}
@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+
+use crate::io::{DatagramSend, Protocol, Transmit};
+
+use super::{Attribute, Class, Message, MessageBuilder, Method};
+
+const WINDOW: Duration = Duration::from_secs(1);
+
+/// Max number of tracked source IPs. Requests from new sources are dropped when full.
+const MAX_SOURCES: usize = 10_000;
+
+/// Answers plain (unauthenticated) STUN Binding requests.
+///
+/// Used for clients doing NAT discovery against the same UDP socket that serves
+/// ICE-lite [`Rtc`][crate::Rtc] instances. Use [`demux()`][super::demux] to decide
+/// which datagrams to give to the responder.
+///
+/// Replies are rate limited per source IP. Requests over the limit are dropped. At most
+/// 10 000 source IPs are tracked at a time, requests from further sources are dropped
+/// until the tracked ones expire.
+///
+/// ```
+/// # use std::time::Instant;
+/// # use str0m::net::Protocol;
+/// # use str0m::stun::{Class, MessageBuilder, Method, StunResponder, TransId};
+/// let mut responder = StunResponder::new().set_rate_limit(5);
+///
+/// let request = MessageBuilder::new(Class::Request, Method::Binding, TransId::new())
+///     .build()
+///     .unwrap();
+///
+/// let source = "1.2.3.4:5000".parse().unwrap();
+/// let local = "10.0.0.1:3478".parse().unwrap();
+///
+/// let reply = responder.handle(Instant::now(), Protocol::Udp, source, local, &request);
+/// assert_eq!(reply.unwrap().destination, source);
+/// ```
+#[derive(Debug)]
+pub struct StunResponder {
+    rate_limit: u32,
+    software: Option<String>,
+    sources: HashMap<IpAddr, RateWindow>,
+    last_prune: Option<Instant>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct RateWindow {
+    start: Instant,
+    count: u32,
+}
+
+impl StunResponder {
+    /// Creates a new responder allowing 10 requests per second per source IP.
+    pub fn new() -> Self {
+        StunResponder {
+            rate_limit: 10,
+            software: None,
+            sources: HashMap::new(),
+            last_prune: None,
+        }
+    }
+
+    /// Max number of requests answered per second for each source IP.
+    ///
+    /// Defaults to 10.
+    pub fn set_rate_limit(mut self, per_second: u32) -> Self {
+        self.rate_limit = per_second;
+        self
+    }
+
+    /// Value of the SOFTWARE attribute in replies. Defaults to none.
+    pub fn set_software(mut self, software: Option<String>) -> Self {
+        self.software = software;
+        self
+    }
+
+    /// Handle an incoming datagram.
+    ///
+    /// Returns a reply to send for unauthenticated Binding requests. Anything else, as well
+    /// as requests over the rate limit, give `None`.
+    pub fn handle(
+        &mut self,
+        now: Instant,
+        proto: Protocol,
+        source: SocketAddr,
+        destination: SocketAddr,
+        buf: &[u8],
+    ) -> Option<Transmit> {
+        let message = Message::parse(buf).ok()?;
+
+        if message.class() != Class::Request
+            || message.method() != Method::Binding
+            || message.has_integrity()
+        {
+            return None;
+        }
+
+        if !self.allow(now, source.ip()) {
+            trace!("STUN responder rate limited: {}", source);
+            return None;
+        }
+
+        let trans_id = message.transaction_id();
+
+        // Comprehension-required attributes we don't understand (RFC 8489 section 6.3.1).
+        let unknown: Vec<u16> = message
+            .attributes()
+            .iter()
+            .filter_map(|a| match a {
+                Attribute::Other { typ, .. } if *typ < 0x8000 => Some(*typ),
+                _ => None,
+            })
+            .collect();
+
+        let mut builder = if unknown.is_empty() {
+            MessageBuilder::new(Class::Success, Method::Binding, trans_id)
+                .attribute(Attribute::XorMappedAddress(source))
+        } else {
+            MessageBuilder::new(Class::Failure, Method::Binding, trans_id)
+                .attribute(Attribute::ErrorCode(420, "Unknown Attribute"))
+                .attribute(Attribute::UnknownAttributes(unknown))
+        };
+
+        if let Some(software) = &self.software {
+            builder = builder.attribute(Attribute::Software(software));
+        }
+
+        if message.verify_fingerprint() {
+            builder = builder.fingerprint();
+        }
+
+        let contents: DatagramSend = builder.build().ok()?.into();
+
+        Some(Transmit {
+            proto,
+            source: destination,
+            destination: source,
+            contents,
+        })
+    }
+
+    fn allow(&mut self, now: Instant, ip: IpAddr) -> bool {
+        // Prune expired sources at most once per window.
+        let prune = self.last_prune.map(|t| now >= t + WINDOW).unwrap_or(true);
+        if prune {
+            self.sources.retain(|_, w| now < w.start + WINDOW);
+            self.last_prune = Some(now);
+        }
+
+        if self.sources.len() >= MAX_SOURCES && !self.sources.contains_key(&ip) {
+            return false;
+        }
+
+        let w = self.sources.entry(ip).or_insert(RateWindow {
+            start: now,
+            count: 0,
+        });
+
+        if now >= w.start + WINDOW {
+            w.start = now;
+            w.count = 0;
+        }
+
+        if w.count >= self.rate_limit {
+            return false;
+        }
+
+        w.count += 1;
+        true
+    }
+}
+
+impl Default for StunResponder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::stun::TransId;
+
+    fn request() -> Vec<u8> {
+        MessageBuilder::new(Class::Request, Method::Binding, TransId::new())
+            .fingerprint()
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn replies_with_xor_mapped_address() {
+        let mut r = StunResponder::new().set_software(Some("test".into()));
+        let source: SocketAddr = "1.2.3.4:5000".parse().unwrap();
+        let local: SocketAddr = "10.0.0.1:3478".parse().unwrap();
+        let req = request();
+
+        let t = r
+            .handle(Instant::now(), Protocol::Udp, source, local, &req)
+            .unwrap();
+        assert_eq!(t.source, local);
+        assert_eq!(t.destination, source);
+
+        let reply = Message::parse(&t.contents).unwrap();
+        assert_eq!(reply.class(), Class::Success);
+        assert_eq!(
+            reply.transaction_id(),
+            Message::parse(&req).unwrap().transaction_id()
+        );
+        assert_eq!(reply.xor_mapped_address(), Some(source));
+        assert!(reply.verify_fingerprint());
+    }
+
+    #[test]
+    fn rate_limits_per_ip() {
+        let mut r = StunResponder::new().set_rate_limit(2);
+        let now = Instant::now();
+        let a: SocketAddr = "1.2.3.4:5000".parse().unwrap();
+        let a2: SocketAddr = "1.2.3.4:5001".parse().unwrap();
+        let b: SocketAddr = "5.6.7.8:5000".parse().unwrap();
+        let local: SocketAddr = "10.0.0.1:3478".parse().unwrap();
+        let req = request();
+
+        let mut handle = |now, s| r.handle(now, Protocol::Udp, s, local, &req).is_some();
+
+        assert!(handle(now, a));
+        assert!(handle(now, a2));
+        assert!(!handle(now, a));
+        assert!(handle(now, b));
+        assert!(handle(now + WINDOW, a));
+    }
+
+    #[test]
+    fn bounded_sources() {
+        let mut r = StunResponder::default();
+        let now = Instant::now();
+        let local: SocketAddr = "10.0.0.1:3478".parse().unwrap();
+        let req = request();
+
+        let mut handle = |now, ip: u32| {
+            let s = SocketAddr::new(IpAddr::from(ip.to_be_bytes()), 5000);
+            r.handle(now, Protocol::Udp, s, local, &req).is_some()
+        };
+
+        for ip in 0..MAX_SOURCES as u32 {
+            assert!(handle(now, ip));
+        }
+
+        // Full, new sources are dropped, known ones still answered.
+        assert!(!handle(now, u32::MAX));
+        assert!(handle(now, 0));
+
+        // Once the sources expire, there is room again.
+        assert!(handle(now + WINDOW, u32::MAX));
+        assert_eq!(r.sources.len(), 1);
+    }
+
+    #[test]
+    fn ignores_authenticated_and_unknown() {
+        let mut r = StunResponder::new();
+        let source: SocketAddr = "1.2.3.4:5000".parse().unwrap();
+        let local: SocketAddr = "10.0.0.1:3478".parse().unwrap();
+        let now = Instant::now();
+
+        let authed = MessageBuilder::new(Class::Request, Method::Binding, TransId::new())
+            .message_integrity(crate::stun::Credentials::ShortTerm { password: "x" })
+            .build()
+            .unwrap();
+        assert!(r
+            .handle(now, Protocol::Udp, source, local, &authed)
+            .is_none());
+        assert!(r
+            .handle(now, Protocol::Udp, source, local, &[0x80; 40])
+            .is_none());
+
+        let unknown = MessageBuilder::new(Class::Request, Method::Binding, TransId::new())
+            .attribute(Attribute::Other {
+                typ: 0x0003,
+                value: &[0, 0, 0, 0],
+            })
+            .build()
+            .unwrap();
+        let t = r
+            .handle(now, Protocol::Udp, source, local, &unknown)
+            .unwrap();
+        let reply = Message::parse(&t.contents).unwrap();
+        assert_eq!(reply.error_code(), Some((420, "Unknown Attribute")));
+    }
+}
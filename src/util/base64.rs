@@ -0,0 +1,87 @@
+//! Standard base64 (RFC 4648) with padding.
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub(crate) fn encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity((data.len() + 2) / 3 * 4);
+
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let idx = (n >> (18 - i * 6)) & 0x3f;
+                out.push(ALPHABET[idx as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+
+    out
+}
+
+pub(crate) fn decode(s: &str) -> Option<Vec<u8>> {
+    let s = s.as_bytes();
+    if s.len() % 4 != 0 {
+        return None;
+    }
+
+    let mut out = Vec::with_capacity(s.len() / 4 * 3);
+
+    for (i, chunk) in s.chunks(4).enumerate() {
+        let last = i == s.len() / 4 - 1;
+        let pad = chunk.iter().rev().take_while(|c| **c == b'=').count();
+        if pad > 2 || (pad > 0 && !last) {
+            return None;
+        }
+
+        let mut n = 0_u32;
+        for c in &chunk[..4 - pad] {
+            let v = ALPHABET.iter().position(|a| a == c)? as u32;
+            n = n << 6 | v;
+        }
+        n <<= 6 * pad as u32;
+
+        let bytes = [(n >> 16) as u8, (n >> 8) as u8, n as u8];
+        out.extend_from_slice(&bytes[..3 - pad]);
+    }
+
+    Some(out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let cases: &[(&[u8], &str)] = &[
+            (b"", ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foob", "Zm9vYg=="),
+            (b"fooba", "Zm9vYmE="),
+            (b"foobar", "Zm9vYmFy"),
+        ];
+
+        for (data, enc) in cases {
+            assert_eq!(encode(data), *enc);
+            assert_eq!(decode(enc).as_deref(), Some(*data));
+        }
+    }
+
+    #[test]
+    fn decode_invalid() {
+        assert_eq!(decode("Zg="), None);
+        assert_eq!(decode("Z==="), None);
+        assert_eq!(decode("Zg==Zg=="), None);
+        assert_eq!(decode("Zm9*"), None);
+    }
+}
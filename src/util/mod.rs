@@ -1,5 +1,7 @@
 use std::time::{Duration, Instant};
 
+pub(crate) mod base64;
+
 mod bit_pattern;
 
 pub(crate) use bit_pattern::BitPattern;
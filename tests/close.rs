@@ -0,0 +1,220 @@
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+use str0m::media::{Direction, MediaKind, Mid};
+use str0m::rtp::rtcp::Rtcp;
+use str0m::rtp::RawPacket;
+use str0m::{Candidate, CloseReason, Event, IceConnectionState, Rtc, RtcError};
+use tracing::info_span;
+
+mod common;
+use common::{init_crypto_default, init_log, progress, progress_with_loss, TestRtc};
+
+fn connect(
+    l: &mut TestRtc,
+    r: &mut TestRtc,
+    channel: bool,
+) -> Result<(Option<Mid>, bool), RtcError> {
+    let host1 = Candidate::host((Ipv4Addr::new(1, 1, 1, 1), 1000).into(), "udp")?;
+    let host2 = Candidate::host((Ipv4Addr::new(2, 2, 2, 2), 2000).into(), "udp")?;
+    l.add_local_candidate(host1);
+    r.add_local_candidate(host2);
+
+    let mut change = l.sdp_api();
+    let mid = change.add_media(MediaKind::Audio, Direction::SendRecv, None, None, None);
+    if channel {
+        change.add_channel("chat".into());
+    }
+    let (offer, pending) = change.apply().unwrap();
+
+    let answer = r.rtc.sdp_api().accept_offer(offer)?;
+    l.rtc.sdp_api().accept_answer(pending, answer)?;
+
+    loop {
+        let open = |t: &TestRtc| {
+            t.events
+                .iter()
+                .any(|(_, e)| matches!(e, Event::ChannelOpen(_, _)))
+        };
+        if l.is_connected() && r.is_connected() && (!channel || open(l) && open(r)) {
+            break;
+        }
+        if l.duration() > Duration::from_secs(10) {
+            panic!("Failed to connect in 10 seconds");
+        }
+        progress(l, r)?;
+    }
+
+    Ok((Some(mid), channel))
+}
+
+fn send_audio(l: &mut TestRtc, r: &mut TestRtc, mid: Mid) -> Result<(), RtcError> {
+    let pt = l.params_opus().pt();
+    let end = l.duration() + Duration::from_millis(500);
+
+    while l.duration() < end {
+        let wallclock = l.start + l.duration();
+        let time = l.duration().into();
+        l.writer(mid)
+            .unwrap()
+            .write(pt, wallclock, time, vec![1_u8; 80])?;
+
+        progress(l, r)?;
+    }
+
+    Ok(())
+}
+
+fn run_until_closed(l: &mut TestRtc, r: &mut TestRtc) -> Result<Duration, RtcError> {
+    let start = l.duration();
+    while l.is_alive() || r.is_alive() {
+        if l.duration() > start + Duration::from_secs(10) {
+            panic!("Failed to close in 10 seconds");
+        }
+        progress(l, r)?;
+    }
+    Ok(l.duration() - start)
+}
+
+fn closed(t: &TestRtc) -> Vec<CloseReason> {
+    t.events
+        .iter()
+        .filter_map(|(_, e)| match e {
+            Event::Closed(v) => Some(*v),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+pub fn close_media_only() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    let rtc = || Rtc::builder().enable_raw_packets(true).build();
+    let mut l = TestRtc::new_with_rtc(info_span!("L"), rtc());
+    let mut r = TestRtc::new_with_rtc(info_span!("R"), rtc());
+
+    let (mid, _) = connect(&mut l, &mut r, false)?;
+    send_audio(&mut l, &mut r, mid.unwrap())?;
+
+    l.rtc.close();
+    let took = run_until_closed(&mut l, &mut r)?;
+    assert!(took < Duration::from_secs(1), "Close took {:?}", took);
+
+    assert_eq!(closed(&l), [CloseReason::Local]);
+    assert_eq!(closed(&r), [CloseReason::DtlsCloseNotify]);
+    assert!(!closed(&r)[0].is_failure());
+
+    // The remote got a BYE for the stream we sent.
+    let bye = r.events.iter().any(|(_, e)| {
+        matches!(
+            e.as_raw_packet(),
+            Some(RawPacket::RtcpRx(Rtcp::Goodbye(g))) if !g.reports.is_empty()
+        )
+    });
+    assert!(bye, "No RTCP BYE received");
+
+    // Closed is the last event.
+    assert!(matches!(l.events.last(), Some((_, Event::Closed(_)))));
+    assert!(matches!(r.events.last(), Some((_, Event::Closed(_)))));
+
+    Ok(())
+}
+
+#[test]
+pub fn close_with_data_channel() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    let mut l = TestRtc::new(info_span!("L"));
+    let mut r = TestRtc::new(info_span!("R"));
+
+    connect(&mut l, &mut r, true)?;
+
+    l.rtc.close();
+    let took = run_until_closed(&mut l, &mut r)?;
+    assert!(took < Duration::from_secs(1), "Close took {:?}", took);
+
+    assert_eq!(closed(&l), [CloseReason::Local]);
+    // The remote sees the SCTP shutdown before the DTLS close_notify.
+    assert_eq!(closed(&r), [CloseReason::SctpShutdown]);
+
+    for t in [&l, &r] {
+        let pos = |f: fn(&Event) -> bool| t.events.iter().position(|(_, e)| f(e));
+        let channel_close = pos(|e| matches!(e, Event::ChannelClose(_)));
+        let close = pos(|e| matches!(e, Event::Closed(_)));
+        assert!(channel_close.is_some());
+        assert!(channel_close < close);
+    }
+
+    Ok(())
+}
+
+#[test]
+pub fn close_before_connected() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    let mut l = TestRtc::new(info_span!("L"));
+    let mut r = TestRtc::new(info_span!("R"));
+
+    l.rtc.close();
+    progress(&mut l, &mut r)?;
+
+    assert!(!l.is_alive());
+    assert_eq!(closed(&l), [CloseReason::Local]);
+
+    // Closing again does nothing.
+    l.rtc.close();
+    progress(&mut l, &mut r)?;
+    assert_eq!(closed(&l), [CloseReason::Local]);
+
+    Ok(())
+}
+
+#[test]
+pub fn close_on_ice_failure() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    for enabled in [false, true] {
+        let rtc = || Rtc::builder().set_close_on_ice_failure(enabled).build();
+        let mut l = TestRtc::new_with_rtc(info_span!("L"), rtc());
+        let mut r = TestRtc::new_with_rtc(info_span!("R"), rtc());
+
+        connect(&mut l, &mut r, false)?;
+        l.rtc.end_of_local_candidates();
+        l.rtc.end_of_remote_candidates();
+
+        let failed = |t: &TestRtc| {
+            t.events.iter().any(|(_, e)| {
+                matches!(
+                    e,
+                    Event::IceConnectionStateChange(IceConnectionState::Failed)
+                )
+            })
+        };
+
+        let start = l.duration();
+        while !failed(&l) {
+            if l.duration() > start + Duration::from_secs(120) {
+                panic!("ICE did not fail in 120 seconds");
+            }
+            progress_with_loss(&mut l, &mut r, 1.0)?;
+        }
+        for _ in 0..10 {
+            progress_with_loss(&mut l, &mut r, 1.0)?;
+        }
+
+        if enabled {
+            assert_eq!(closed(&l), [CloseReason::IceFailed]);
+            assert!(!l.is_alive());
+        } else {
+            assert!(closed(&l).is_empty());
+            assert!(l.is_alive());
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,230 @@
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+use str0m::format::Codec;
+use str0m::media::{Direction, MediaKind, Pt};
+use str0m::{Candidate, Event, RtcError};
+use tracing::info_span;
+
+mod common;
+use common::{init_crypto_default, init_log, negotiate, progress, TestRtc};
+
+#[test]
+pub fn codec_preferences_per_media() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    let (mut l, mut r) = peers()?;
+
+    let h264 = pts_for(&l, Codec::H264);
+    let vp8 = l.params_vp8().pt();
+    let vp9 = l.params_vp9().pt();
+    let opus = l.params_opus().pt();
+
+    let mut change = l.sdp_api();
+    let mid_h = change.add_media(MediaKind::Video, Direction::SendRecv, None, None, None);
+    let mid_v = change.add_media(MediaKind::Video, Direction::SendRecv, None, None, None);
+    change.set_codec_preferences(mid_h, &h264);
+    // Opus is not for video, and ignored.
+    change.set_codec_preferences(mid_v, &[vp9, opus, vp8]);
+    let (offer, pending) = change.apply().unwrap();
+
+    let answer = r.rtc.sdp_api().accept_offer(offer)?;
+    l.rtc.sdp_api().accept_answer(pending, answer)?;
+
+    assert_eq!(l.media(mid_h).unwrap().codec_preferences(), h264);
+    assert_eq!(l.media(mid_v).unwrap().codec_preferences(), [vp9, vp8]);
+
+    // R gets the order of the preferences, L the order of R's answer.
+    assert_eq!(r.media(mid_h).unwrap().remote_pts(), h264);
+    assert_eq!(r.media(mid_v).unwrap().remote_pts(), [vp9, vp8]);
+    assert_eq!(l.media(mid_v).unwrap().remote_pts(), [vp8, vp9]);
+
+    let l_pts = l.media(mid_h).unwrap().remote_pts();
+    assert!(l_pts.iter().all(|pt| h264.contains(pt)));
+
+    let params: Vec<_> = l
+        .writer(mid_v)
+        .unwrap()
+        .payload_params()
+        .map(|p| p.pt())
+        .collect();
+    assert_eq!(params, [vp9, vp8]);
+
+    let writer = l.writer(mid_h).unwrap();
+    assert!(writer
+        .payload_params()
+        .all(|p| p.spec().codec == Codec::H264));
+
+    // Untouched by the preferences.
+    let mut change = l.sdp_api();
+    let mid_a = change.add_media(MediaKind::Video, Direction::SendRecv, None, None, None);
+    change.set_codec_preferences(mid_a, &[opus]);
+    let (offer, pending) = change.apply().unwrap();
+
+    let answer = r.rtc.sdp_api().accept_offer(offer)?;
+    l.rtc.sdp_api().accept_answer(pending, answer)?;
+
+    assert!(l.media(mid_a).unwrap().codec_preferences().is_empty());
+    assert!(r.media(mid_a).unwrap().remote_pts().contains(&vp8));
+    assert!(r.media(mid_a).unwrap().remote_pts().contains(&vp9));
+
+    Ok(())
+}
+
+#[test]
+pub fn codec_preferences_existing_media() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    let (mut l, mut r) = peers()?;
+
+    let mid = negotiate(&mut l, &mut r, |change| {
+        change.add_media(MediaKind::Video, Direction::SendRecv, None, None, None)
+    });
+
+    let vp8 = l.params_vp8().pt();
+    let vp9 = l.params_vp9().pt();
+    assert!(r.media(mid).unwrap().remote_pts().len() > 2);
+
+    let mut change = l.sdp_api();
+    change.set_codec_preferences(mid, &[vp9, vp8]);
+    let (_offer, pending) = change.apply().expect("preferences to need negotiation");
+
+    assert_eq!(l.media(mid).unwrap().codec_preferences(), [vp9, vp8]);
+
+    l.sdp_api().rollback(&pending)?;
+    assert!(l.media(mid).unwrap().codec_preferences().is_empty());
+
+    let mut change = l.sdp_api();
+    change.merge(pending);
+    let (offer, pending) = change.apply().unwrap();
+    assert_eq!(l.media(mid).unwrap().codec_preferences(), [vp9, vp8]);
+
+    let answer = r.rtc.sdp_api().accept_offer(offer)?;
+    l.rtc.sdp_api().accept_answer(pending, answer)?;
+
+    // The answering side narrows to what was offered.
+    assert_eq!(r.media(mid).unwrap().remote_pts(), [vp9, vp8]);
+    let params: Vec<_> = r
+        .writer(mid)
+        .unwrap()
+        .payload_params()
+        .map(|p| p.pt())
+        .collect();
+    assert_eq!(params, [vp8, vp9]);
+
+    // Reorder again.
+    let mut change = l.sdp_api();
+    change.set_codec_preferences(mid, &[vp8, vp9]);
+    let (offer, pending) = change.apply().unwrap();
+
+    let answer = r.rtc.sdp_api().accept_offer(offer)?;
+    l.rtc.sdp_api().accept_answer(pending, answer)?;
+
+    let params: Vec<_> = l
+        .writer(mid)
+        .unwrap()
+        .payload_params()
+        .map(|p| p.pt())
+        .collect();
+    assert_eq!(params, [vp8, vp9]);
+
+    // Same preferences again, nothing to negotiate.
+    let mut change = l.sdp_api();
+    change.set_codec_preferences(mid, &[vp8, vp9]);
+    assert!(change.apply().is_none());
+
+    Ok(())
+}
+
+#[test]
+pub fn payload_changed_event() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    let (mut l, mut r) = peers()?;
+
+    let mid = negotiate(&mut l, &mut r, |change| {
+        change.add_media(MediaKind::Video, Direction::SendOnly, None, None, None)
+    });
+
+    loop {
+        if l.is_connected() || r.is_connected() {
+            break;
+        }
+        progress(&mut l, &mut r)?;
+    }
+
+    let max = l.last.max(r.last);
+    l.last = max;
+    r.last = max;
+
+    let vp8 = l.params_vp8().pt();
+    let vp9 = l.params_vp9().pt();
+
+    let data = vec![1_u8; 80];
+
+    for pt in [vp8, vp9, vp8] {
+        let until = l.duration() + Duration::from_secs(1);
+
+        while l.duration() < until {
+            let wallclock = l.start + l.duration();
+            let time = l.duration().into();
+            l.writer(mid)
+                .unwrap()
+                .write(pt, wallclock, time, data.clone())?;
+
+            progress(&mut l, &mut r)?;
+        }
+    }
+
+    // Make sure the last data is through.
+    for _ in 0..20 {
+        progress(&mut l, &mut r)?;
+    }
+
+    let mut changes = vec![];
+    let mut current = None;
+
+    for (_, e) in &r.events {
+        match e {
+            Event::PayloadChanged(c) => {
+                assert_eq!(c.mid, mid);
+                assert_eq!(c.rid, None);
+                assert_eq!(c.params.pt(), c.pt);
+                current = Some(c.pt);
+                changes.push(c.pt);
+            }
+            Event::MediaData(d) => {
+                assert_eq!(Some(d.pt), current, "MediaData before PayloadChanged");
+            }
+            _ => {}
+        }
+    }
+
+    assert_eq!(changes, [vp8, vp9, vp8]);
+
+    Ok(())
+}
+
+fn pts_for(rtc: &TestRtc, codec: Codec) -> Vec<Pt> {
+    rtc.codec_config()
+        .params()
+        .iter()
+        .filter(|p| p.spec().codec == codec)
+        .map(|p| p.pt())
+        .collect()
+}
+
+fn peers() -> Result<(TestRtc, TestRtc), RtcError> {
+    let mut l = TestRtc::new(info_span!("L"));
+    let mut r = TestRtc::new(info_span!("R"));
+
+    let host1 = Candidate::host((Ipv4Addr::new(1, 1, 1, 1), 1000).into(), "udp")?;
+    let host2 = Candidate::host((Ipv4Addr::new(2, 2, 2, 2), 2000).into(), "udp")?;
+    l.add_local_candidate(host1);
+    r.add_local_candidate(host2);
+
+    Ok((l, r))
+}
@@ -0,0 +1,204 @@
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+use str0m::media::{Direction, MediaKind};
+use str0m::rtp::{ExtensionValues, RawPacket, Ssrc};
+use str0m::{Candidate, Event, RtcConfig, RtcError};
+use tracing::info_span;
+
+mod common;
+use common::{connect_l_r, init_crypto_default, init_log, progress, TestRtc};
+
+struct Outcome {
+    offer_cryptex: bool,
+    answer_cryptex: bool,
+    media_count: usize,
+    /// Headers of received RTP, as seen after SRTP decryption.
+    rx_cryptex: Vec<(bool, Option<i8>)>,
+}
+
+fn negotiate_and_send(l_cryptex: bool, r_cryptex: bool) -> Result<Outcome, RtcError> {
+    let config = |cryptex| RtcConfig::new().set_cryptex(cryptex);
+
+    let mut l = TestRtc::new_with_rtc(info_span!("L"), config(l_cryptex).build());
+    let mut r = TestRtc::new_with_rtc(
+        info_span!("R"),
+        config(r_cryptex).enable_raw_packets(true).build(),
+    );
+
+    let host1 = Candidate::host((Ipv4Addr::new(1, 1, 1, 1), 1000).into(), "udp")?;
+    let host2 = Candidate::host((Ipv4Addr::new(2, 2, 2, 2), 2000).into(), "udp")?;
+    l.add_local_candidate(host1);
+    r.add_local_candidate(host2);
+
+    let mut change = l.sdp_api();
+    let mid = change.add_media(MediaKind::Audio, Direction::SendRecv, None, None, None);
+    let (offer, pending) = change.apply().unwrap();
+    let offer_cryptex = offer.to_sdp_string().contains("a=cryptex");
+
+    let answer = r.rtc.sdp_api().accept_offer(offer)?;
+    let answer_cryptex = answer.to_sdp_string().contains("a=cryptex");
+    l.rtc.sdp_api().accept_answer(pending, answer)?;
+
+    loop {
+        if l.is_connected() || r.is_connected() {
+            break;
+        }
+        progress(&mut l, &mut r)?;
+    }
+
+    let pt = l.params_opus().pt();
+
+    while l.duration() < Duration::from_secs(3) {
+        let wallclock = l.start + l.duration();
+        let time = l.duration().into();
+        l.writer(mid)
+            .unwrap()
+            .audio_level(-42, true)
+            .write(pt, wallclock, time, vec![1_u8; 80])?;
+
+        progress(&mut l, &mut r)?;
+    }
+
+    let media_count = r
+        .events
+        .iter()
+        .filter(|(_, e)| matches!(e, Event::MediaData(_)))
+        .count();
+
+    let rx_cryptex = r
+        .events
+        .iter()
+        .filter_map(|(_, e)| match e.as_raw_packet() {
+            Some(RawPacket::RtpRx(header, _)) => {
+                Some((header.cryptex, header.ext_vals.audio_level))
+            }
+            _ => None,
+        })
+        .collect();
+
+    Ok(Outcome {
+        offer_cryptex,
+        answer_cryptex,
+        media_count,
+        rx_cryptex,
+    })
+}
+
+#[test]
+pub fn cryptex_negotiated() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    let o = negotiate_and_send(true, true)?;
+
+    assert!(o.offer_cryptex);
+    assert!(o.answer_cryptex);
+    assert!(
+        o.media_count > 50,
+        "Not enough MediaData: {}",
+        o.media_count
+    );
+
+    // Extensions are encrypted on the wire, and decrypted on receive.
+    assert!(!o.rx_cryptex.is_empty());
+    assert!(o.rx_cryptex.iter().all(|v| *v == (true, Some(-42))));
+
+    Ok(())
+}
+
+#[test]
+pub fn cryptex_not_supported_by_answerer() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    let o = negotiate_and_send(true, false)?;
+
+    assert!(o.offer_cryptex);
+    assert!(!o.answer_cryptex);
+    assert!(
+        o.media_count > 50,
+        "Not enough MediaData: {}",
+        o.media_count
+    );
+
+    assert!(!o.rx_cryptex.is_empty());
+    assert!(o.rx_cryptex.iter().all(|v| *v == (false, Some(-42))));
+
+    Ok(())
+}
+
+#[test]
+pub fn cryptex_maps_ssrc_by_encrypted_mid() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    let (mut l, mut r) = connect_l_r();
+
+    l.direct_api().set_cryptex(true);
+    r.direct_api().set_cryptex(true);
+
+    // R only knows the mid, which is encrypted on the wire.
+    let mid = "aud".into();
+    let ssrc: Ssrc = 42.into();
+    l.direct_api().declare_media(mid, MediaKind::Audio);
+    l.direct_api().declare_stream_tx(ssrc, None, mid, None);
+    r.direct_api().declare_media(mid, MediaKind::Audio);
+
+    let max = l.last.max(r.last);
+    l.last = max;
+    r.last = max;
+
+    let pt = l.params_opus().pt();
+
+    for i in 0..10_u64 {
+        let wallclock = l.start + l.duration();
+        let exts = ExtensionValues {
+            audio_level: Some(-42),
+            voice_activity: Some(false),
+            ..Default::default()
+        };
+
+        l.direct_api()
+            .stream_tx(&ssrc)
+            .unwrap()
+            .write_rtp(
+                pt,
+                (47_000 + i).into(),
+                (47_000_000 + i * 960) as u32,
+                wallclock,
+                false,
+                exts,
+                false,
+                vec![1, 2, 3, 4],
+            )
+            .expect("clean write");
+
+        let next = l.duration() + Duration::from_millis(20);
+        while l.duration() < next {
+            progress(&mut l, &mut r)?;
+        }
+    }
+
+    while l.duration() < Duration::from_secs(1) {
+        progress(&mut l, &mut r)?;
+    }
+
+    let packets: Vec<_> = r
+        .events
+        .iter()
+        .filter_map(|(_, e)| match e {
+            Event::RtpPacket(v) => Some(v),
+            _ => None,
+        })
+        .collect();
+
+    // All packets are mapped to the mid.
+    assert_eq!(packets.len(), 10);
+    for p in packets {
+        assert!(p.header.cryptex);
+        assert_eq!(p.header.ext_vals.audio_level, Some(-42));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,127 @@
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+use str0m::error::SctpError;
+use str0m::{Candidate, Event, RtcError};
+use tracing::info_span;
+
+mod common;
+use common::{init_crypto_default, init_log, progress, TestRtc};
+
+#[test]
+pub fn data_channel_buffered_amount() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    let mut l = TestRtc::new(info_span!("L"));
+    let mut r = TestRtc::new(info_span!("R"));
+
+    let host1 = Candidate::host((Ipv4Addr::new(1, 1, 1, 1), 1000).into(), "udp")?;
+    let host2 = Candidate::host((Ipv4Addr::new(2, 2, 2, 2), 2000).into(), "udp")?;
+    l.add_local_candidate(host1);
+    r.add_local_candidate(host2);
+
+    let mut change = l.sdp_api();
+    let cid = change.add_channel("file".into());
+    let (offer, pending) = change.apply().unwrap();
+
+    let answer = r.rtc.sdp_api().accept_offer(offer)?;
+    l.rtc.sdp_api().accept_answer(pending, answer)?;
+
+    loop {
+        // Wait for the DCEP open to be acknowledged too.
+        if l.channel(cid).map(|mut c| c.buffered_amount()) == Some(0) {
+            break;
+        }
+        if l.duration() > Duration::from_secs(10) {
+            panic!("Failed to open channel in 10 seconds");
+        }
+        progress(&mut l, &mut r)?;
+    }
+
+    // Only look at events after this point.
+    l.events.clear();
+
+    const CHUNK: usize = 10_000;
+    const HIGH: usize = 50_000;
+    const LOW: usize = 20_000;
+
+    {
+        let mut chan = l.channel(cid).unwrap();
+        assert_eq!(chan.buffered_amount_low_threshold(), 0);
+        assert_eq!(chan.buffered_amount_high_threshold(), None);
+
+        chan.set_buffered_amount_low_threshold(LOW);
+        chan.set_buffered_amount_high_threshold(Some(HIGH));
+        assert_eq!(chan.buffered_amount_low_threshold(), LOW);
+        assert_eq!(chan.buffered_amount_high_threshold(), Some(HIGH));
+
+        // Fill up to the high threshold.
+        let mut written = 0;
+        loop {
+            match chan.write(true, &[1; CHUNK]) {
+                Ok(n) => written += n,
+                Err(RtcError::Sctp(SctpError::WouldBlock)) => break,
+                Err(e) => return Err(e),
+            }
+        }
+
+        assert_eq!(written, HIGH);
+        assert_eq!(chan.buffered_amount(), HIGH);
+    }
+
+    let mut total = HIGH;
+    let mut low_events = 0;
+    let mut received = 0;
+
+    while received < 200_000 {
+        if l.duration() > Duration::from_secs(20) {
+            panic!("Failed to transfer in 20 seconds");
+        }
+
+        progress(&mut l, &mut r)?;
+
+        let mut is_low = false;
+        for (_, e) in l.events.drain(..) {
+            if let Event::ChannelBufferedAmountLow(id) = e {
+                assert_eq!(id, cid);
+                is_low = true;
+            }
+        }
+
+        for (_, e) in r.events.drain(..) {
+            if let Event::ChannelData(d) = e {
+                received += d.data.len();
+            }
+        }
+
+        if is_low {
+            low_events += 1;
+            let mut chan = l.channel(cid).unwrap();
+            assert!(chan.buffered_amount() <= LOW);
+
+            // Refill to the high mark.
+            while total < 200_000 {
+                match chan.write(true, &[1; CHUNK]) {
+                    Ok(n) => total += n,
+                    Err(RtcError::Sctp(SctpError::WouldBlock)) => break,
+                    Err(e) => return Err(e),
+                }
+            }
+            assert!(chan.buffered_amount() <= HIGH);
+        }
+    }
+
+    assert_eq!(received, 200_000);
+    assert!(low_events > 1, "Only {} low events", low_events);
+
+    // Drains when the last data is acknowledged.
+    while l.channel(cid).unwrap().buffered_amount() > 0 {
+        if l.duration() > Duration::from_secs(25) {
+            panic!("Buffered amount never drained");
+        }
+        progress(&mut l, &mut r)?;
+    }
+
+    Ok(())
+}
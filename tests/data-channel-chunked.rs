@@ -0,0 +1,250 @@
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+use str0m::channel::{ChannelChunk, ChannelId, Reliability, WriteOptions};
+use str0m::error::SctpError;
+use str0m::{Candidate, Event, RtcError};
+use tracing::info_span;
+
+mod common;
+use common::{init_crypto_default, init_log, progress, TestRtc};
+
+fn drain_chunks(r: &mut TestRtc, chunks: &mut Vec<ChannelChunk>) {
+    for (_, e) in r.events.drain(..) {
+        match e {
+            Event::ChannelChunk(c) => chunks.push(c),
+            Event::ChannelData(_) => panic!("ChannelData when receiving in chunks"),
+            _ => {}
+        }
+    }
+}
+
+fn connect() -> Result<(TestRtc, TestRtc, ChannelId, ChannelId), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    let mut l = TestRtc::new(info_span!("L"));
+    let mut r = TestRtc::new(info_span!("R"));
+
+    let host1 = Candidate::host((Ipv4Addr::new(1, 1, 1, 1), 1000).into(), "udp")?;
+    let host2 = Candidate::host((Ipv4Addr::new(2, 2, 2, 2), 2000).into(), "udp")?;
+    l.add_local_candidate(host1);
+    r.add_local_candidate(host2);
+
+    let mut change = l.sdp_api();
+    let cid = change.add_channel("file".into());
+    let (offer, pending) = change.apply().unwrap();
+
+    let answer = r.rtc.sdp_api().accept_offer(offer)?;
+    l.rtc.sdp_api().accept_answer(pending, answer)?;
+
+    let r_cid = loop {
+        let open = r.events.iter().find_map(|(_, e)| match e {
+            Event::ChannelOpen(id, _) => Some(*id),
+            _ => None,
+        });
+        if let (Some(id), Some(_)) = (open, l.channel(cid)) {
+            break id;
+        }
+        if l.duration() > Duration::from_secs(10) {
+            panic!("Failed to open channel in 10 seconds");
+        }
+        progress(&mut l, &mut r)?;
+    };
+
+    Ok((l, r, cid, r_cid))
+}
+
+#[test]
+pub fn data_channel_chunked() -> Result<(), RtcError> {
+    let (mut l, mut r, cid, r_cid) = connect()?;
+
+    {
+        let mut chan = r.channel(r_cid).unwrap();
+        assert!(!chan.chunked_receive());
+        chan.set_chunked_receive(true);
+    }
+
+    const SIZE: usize = 200_000;
+    let message: Vec<u8> = (0..SIZE).map(|i| i as u8).collect();
+
+    let options = WriteOptions {
+        binary: true,
+        ..Default::default()
+    };
+
+    {
+        let mut chan = l.channel(cid).unwrap();
+
+        let err = chan.append(b"nope").unwrap_err();
+        assert!(matches!(err, RtcError::Sctp(SctpError::NoMessageBegun)));
+
+        let unreliable = WriteOptions {
+            reliability: Some(Reliability::MaxRetransmits { retransmits: 0 }),
+            ..options
+        };
+        let err = chan.begin_message(unreliable).unwrap_err();
+        assert!(matches!(err, RtcError::Sctp(SctpError::MessageNotReliable)));
+
+        chan.begin_message(options)?;
+
+        let err = chan.begin_message(options).unwrap_err();
+        assert!(matches!(err, RtcError::Sctp(SctpError::MessageInProgress)));
+
+        for piece in message[..SIZE / 2].chunks(10_000) {
+            chan.append(piece)?;
+        }
+    }
+
+    r.events.clear();
+
+    let mut chunks = vec![];
+
+    // The start of the message arrives before the rest is written. The last byte
+    // appended is kept back to end the message with.
+    while chunks
+        .iter()
+        .map(|c: &ChannelChunk| c.data.len())
+        .sum::<usize>()
+        < SIZE / 2 - 1
+    {
+        progress(&mut l, &mut r)?;
+        drain_chunks(&mut r, &mut chunks);
+
+        if l.duration() > Duration::from_secs(20) {
+            panic!("Failed to receive the start in 20 seconds: {:?}", chunks);
+        }
+    }
+    assert!(chunks.iter().all(|c| !c.end));
+
+    {
+        let mut chan = l.channel(cid).unwrap();
+
+        for piece in message[SIZE / 2..].chunks(10_000) {
+            chan.append(piece)?;
+        }
+
+        // Written while the message is in progress, so it goes after.
+        chan.write(false, b"hi")?;
+
+        assert_eq!(chan.finish_message()?, SIZE);
+
+        // Going over the limit refuses the append, and keeps the message.
+        chan.begin_message(options)?;
+        chan.append(&[0; SIZE])?;
+        let err = chan.append(&[0; SIZE]).unwrap_err();
+        assert!(matches!(
+            err,
+            RtcError::Sctp(SctpError::MessageTooLarge(n, 262_144)) if n == 2 * SIZE
+        ));
+        assert_eq!(chan.finish_message()?, SIZE);
+
+        // Nothing of this one is sent, so it is discarded.
+        chan.begin_message(options)?;
+        chan.abort_message();
+        let err = chan.finish_message().unwrap_err();
+        assert!(matches!(err, RtcError::Sctp(SctpError::NoMessageBegun)));
+    }
+
+    while chunks.iter().filter(|c| c.end).count() < 3 {
+        progress(&mut l, &mut r)?;
+        drain_chunks(&mut r, &mut chunks);
+
+        if l.duration() > Duration::from_secs(30) {
+            panic!("Failed to receive chunks in 30 seconds: {:?}", chunks);
+        }
+    }
+
+    assert!(chunks.iter().all(|c| c.id == r_cid && !c.abandoned));
+    assert!(chunks.iter().all(|c| c.data.len() <= 16_384));
+
+    let messages: Vec<_> = chunks.split_inclusive(|c| c.end).collect();
+    assert_eq!(messages.len(), 3);
+
+    for m in &messages {
+        assert!(m[0].start);
+        assert!(m[1..].iter().all(|c| !c.start));
+    }
+
+    let received: Vec<u8> = messages[0].iter().flat_map(|c| c.data.clone()).collect();
+    assert!(messages[0].iter().all(|c| c.binary));
+    assert_eq!(received, message);
+
+    // The text message.
+    let text = &messages[1];
+    assert!(text.len() == 1 && !text[0].binary && text[0].data == b"hi");
+
+    let received: Vec<u8> = messages[2].iter().flat_map(|c| c.data.clone()).collect();
+    assert_eq!(received, [0; SIZE]);
+
+    // Back to whole messages.
+    r.channel(r_cid).unwrap().set_chunked_receive(false);
+    l.channel(cid).unwrap().write(false, b"whole")?;
+
+    loop {
+        progress(&mut l, &mut r)?;
+
+        let data = r.events.iter().find_map(|(_, e)| match e {
+            Event::ChannelData(d) => Some(d),
+            _ => None,
+        });
+
+        if let Some(data) = data {
+            assert_eq!(data.data, b"whole");
+            break;
+        }
+        if l.duration() > Duration::from_secs(40) {
+            panic!("Failed to receive whole message");
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+pub fn data_channel_chunked_abort() -> Result<(), RtcError> {
+    let (mut l, mut r, cid, r_cid) = connect()?;
+
+    r.channel(r_cid).unwrap().set_chunked_receive(true);
+
+    {
+        let mut chan = l.channel(cid).unwrap();
+        let options = WriteOptions {
+            binary: true,
+            ..Default::default()
+        };
+        chan.begin_message(options)?;
+        chan.append(&[1; 50_000])?;
+    }
+
+    r.events.clear();
+
+    let mut chunks = vec![];
+
+    while chunks.is_empty() {
+        progress(&mut l, &mut r)?;
+        drain_chunks(&mut r, &mut chunks);
+
+        if l.duration() > Duration::from_secs(20) {
+            panic!("Failed to receive the start in 20 seconds");
+        }
+    }
+
+    // What is sent can't be taken back, so the channel is aborted.
+    l.channel(cid).unwrap().abort_message();
+
+    while !chunks.last().map(|c| c.end).unwrap_or(false) {
+        progress(&mut l, &mut r)?;
+        drain_chunks(&mut r, &mut chunks);
+
+        if l.duration() > Duration::from_secs(30) {
+            panic!("Failed to receive the end in 30 seconds: {:?}", chunks);
+        }
+    }
+
+    let end = chunks.last().unwrap();
+    assert!(end.abandoned && end.data.is_empty());
+    assert!(chunks[..chunks.len() - 1].iter().all(|c| !c.abandoned));
+
+    Ok(())
+}
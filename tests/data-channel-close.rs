@@ -0,0 +1,156 @@
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+use str0m::channel::{ChannelId, ChannelState};
+use str0m::error::SctpError;
+use str0m::{Candidate, Event, RtcError};
+use tracing::info_span;
+
+mod common;
+use common::{init_crypto_default, init_log, progress, TestRtc};
+
+#[test]
+pub fn data_channel_close() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    let mut l = TestRtc::new(info_span!("L"));
+    let mut r = TestRtc::new(info_span!("R"));
+
+    let host1 = Candidate::host((Ipv4Addr::new(1, 1, 1, 1), 1000).into(), "udp")?;
+    let host2 = Candidate::host((Ipv4Addr::new(2, 2, 2, 2), 2000).into(), "udp")?;
+    l.add_local_candidate(host1);
+    r.add_local_candidate(host2);
+
+    let mut change = l.sdp_api();
+    let first = change.add_channel("first".into());
+    let (offer, pending) = change.apply().unwrap();
+
+    let answer = r.rtc.sdp_api().accept_offer(offer)?;
+    l.rtc.sdp_api().accept_answer(pending, answer)?;
+
+    let r_first = open(&mut l, &mut r, first, "first")?;
+
+    const COUNT: usize = 50;
+
+    {
+        let mut chan = l.channel(first).unwrap();
+        assert_eq!(chan.state(), ChannelState::Open);
+
+        for _ in 0..COUNT {
+            chan.write(true, &[1; 16_384])?;
+        }
+
+        chan.close();
+        assert_eq!(chan.state(), ChannelState::Closing);
+
+        let err = chan.write(true, &[1]).unwrap_err();
+        assert!(matches!(err, RtcError::Sctp(SctpError::Closing)));
+    }
+
+    wait_close(&mut l, &mut r, first, r_first)?;
+
+    // All messages are flushed before the close, and R is told about the closing.
+    let events: Vec<_> = r.events.iter().map(|(_, e)| e).collect();
+    let received = events
+        .iter()
+        .filter(|e| matches!(e, Event::ChannelData(d) if d.id == r_first))
+        .count();
+    assert_eq!(received, COUNT);
+
+    let closing = pos(&events, &Event::ChannelClosing(r_first));
+    let close = pos(&events, &Event::ChannelClose(r_first));
+    let last_data = events
+        .iter()
+        .rposition(|e| matches!(e, Event::ChannelData(_)))
+        .unwrap();
+    assert!(last_data < closing && closing < close);
+
+    assert!(!l
+        .events
+        .iter()
+        .any(|(_, e)| matches!(e, Event::ChannelClosing(_))));
+    assert!(l.channel(first).is_none());
+
+    // The stream id is reused for the next channel.
+    let mut change = l.sdp_api();
+    let second = change.add_channel("second".into());
+    assert!(change.apply().is_none());
+    let r_second = open(&mut l, &mut r, second, "second")?;
+
+    let stream_first = r.direct_api().sctp_stream_id_by_channel_id(r_first);
+    let stream_second = r.direct_api().sctp_stream_id_by_channel_id(r_second);
+    assert_eq!(stream_first, None);
+    assert_eq!(stream_second, Some(0));
+
+    r.events.clear();
+
+    {
+        let mut chan = l.channel(second).unwrap();
+        for _ in 0..COUNT {
+            chan.write(true, &[2; 16_384])?;
+        }
+
+        // Abort drops what is not yet handed to SCTP.
+        chan.abort();
+        assert_eq!(chan.state(), ChannelState::Closing);
+    }
+
+    wait_close(&mut l, &mut r, second, r_second)?;
+
+    let received = r
+        .events
+        .iter()
+        .filter(|(_, e)| matches!(e, Event::ChannelData(d) if d.id == r_second))
+        .count();
+    assert!(received < COUNT, "Received {} after abort", received);
+
+    Ok(())
+}
+
+fn open(
+    l: &mut TestRtc,
+    r: &mut TestRtc,
+    cid: ChannelId,
+    label: &str,
+) -> Result<ChannelId, RtcError> {
+    loop {
+        let open = r.events.iter().find_map(|(_, e)| match e {
+            Event::ChannelOpen(id, l) if l == label => Some(*id),
+            _ => None,
+        });
+        if let (Some(id), Some(_)) = (open, l.channel(cid)) {
+            return Ok(id);
+        }
+        if l.duration() > Duration::from_secs(30) {
+            panic!("Failed to open channel {}", label);
+        }
+        progress(l, r)?;
+    }
+}
+
+fn wait_close(
+    l: &mut TestRtc,
+    r: &mut TestRtc,
+    cid: ChannelId,
+    r_cid: ChannelId,
+) -> Result<(), RtcError> {
+    loop {
+        let l_closed = l.events.iter().any(|(_, e)| e == &Event::ChannelClose(cid));
+        let r_closed = r
+            .events
+            .iter()
+            .any(|(_, e)| e == &Event::ChannelClose(r_cid));
+        if l_closed && r_closed {
+            return Ok(());
+        }
+        if l.duration() > Duration::from_secs(60) {
+            panic!("Failed to close channel");
+        }
+        progress(l, r)?;
+    }
+}
+
+fn pos(events: &[&Event], event: &Event) -> usize {
+    events.iter().position(|e| *e == event).unwrap()
+}
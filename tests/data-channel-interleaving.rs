@@ -0,0 +1,77 @@
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+use str0m::{Candidate, Event, RtcError};
+use tracing::info_span;
+
+mod common;
+use common::{init_crypto_default, init_log, progress, TestRtc};
+
+#[test]
+pub fn data_channel_interleaving() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    let mut l = TestRtc::new(info_span!("L"));
+    let mut r = TestRtc::new(info_span!("R"));
+
+    let host1 = Candidate::host((Ipv4Addr::new(1, 1, 1, 1), 1000).into(), "udp")?;
+    let host2 = Candidate::host((Ipv4Addr::new(2, 2, 2, 2), 2000).into(), "udp")?;
+    l.add_local_candidate(host1);
+    r.add_local_candidate(host2);
+
+    let mut change = l.sdp_api();
+    let big = change.add_channel("big".into());
+    let small = change.add_channel("small".into());
+    let (offer, pending) = change.apply().unwrap();
+
+    let answer = r.rtc.sdp_api().accept_offer(offer)?;
+    l.rtc.sdp_api().accept_answer(pending, answer)?;
+
+    loop {
+        if l.channel(big).is_some() && l.channel(small).is_some() {
+            break;
+        }
+        if l.duration() > Duration::from_secs(10) {
+            panic!("Failed to open channels in 10 seconds");
+        }
+        progress(&mut l, &mut r)?;
+    }
+
+    // Wait for the channels to be announced at R.
+    for _ in 0..20 {
+        progress(&mut l, &mut r)?;
+    }
+    r.events.clear();
+
+    const BIG: usize = 200_000;
+
+    // Both fit in the send window, so they are handed to SCTP right away, and
+    // without I-DATA the small message would be sent after all of the big one.
+    l.channel(big).unwrap().write(true, &[1; BIG])?;
+    l.channel(small).unwrap().write(true, b"ping")?;
+
+    let mut received = vec![];
+
+    while received.len() < 2 {
+        if l.duration() > Duration::from_secs(30) {
+            panic!("Failed to receive messages in 30 seconds");
+        }
+
+        progress(&mut l, &mut r)?;
+
+        for (_, e) in r.events.drain(..) {
+            if let Event::ChannelData(d) = e {
+                received.push(d.data.len());
+            }
+        }
+    }
+
+    assert_eq!(
+        received,
+        [4, BIG],
+        "small message should not wait for the big"
+    );
+
+    Ok(())
+}
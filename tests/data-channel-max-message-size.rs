@@ -0,0 +1,93 @@
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+use str0m::error::SctpError;
+use str0m::{Candidate, Event, Rtc, RtcError};
+use tracing::info_span;
+
+mod common;
+use common::{init_crypto_default, init_log, progress, TestRtc};
+
+#[test]
+pub fn data_channel_max_message_size() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    const R_MAX: usize = 100_000;
+
+    let mut l = TestRtc::new(info_span!("L"));
+    let rtc = Rtc::builder().set_sctp_max_message_size(R_MAX).build();
+    let mut r = TestRtc::new_with_rtc(info_span!("R"), rtc);
+
+    let host1 = Candidate::host((Ipv4Addr::new(1, 1, 1, 1), 1000).into(), "udp")?;
+    let host2 = Candidate::host((Ipv4Addr::new(2, 2, 2, 2), 2000).into(), "udp")?;
+    l.add_local_candidate(host1);
+    r.add_local_candidate(host2);
+
+    let mut change = l.sdp_api();
+    let cid = change.add_channel("big".into());
+    let (offer, pending) = change.apply().unwrap();
+
+    assert!(offer.to_sdp_string().contains("a=max-message-size:262144"));
+
+    let answer = r.rtc.sdp_api().accept_offer(offer)?;
+
+    assert!(answer.to_sdp_string().contains("a=max-message-size:100000"));
+
+    l.rtc.sdp_api().accept_answer(pending, answer)?;
+
+    loop {
+        if l.channel(cid).is_some() {
+            break;
+        }
+        if l.duration() > Duration::from_secs(10) {
+            panic!("Failed to open channel in 10 seconds");
+        }
+        progress(&mut l, &mut r)?;
+    }
+
+    {
+        let mut chan = l.channel(cid).unwrap();
+        assert_eq!(chan.max_message_size(), R_MAX);
+
+        let err = chan.write(true, &[1; R_MAX + 1]).unwrap_err();
+        assert!(matches!(
+            err,
+            RtcError::Sctp(SctpError::MessageTooLarge(n, R_MAX)) if n == R_MAX + 1
+        ));
+
+        // Larger than the 64kB default of sctp-proto.
+        assert_eq!(chan.write(true, &[2; R_MAX])?, R_MAX);
+    }
+
+    loop {
+        let data = r.events.iter().find_map(|(_, e)| match e {
+            Event::ChannelData(d) => Some(d),
+            _ => None,
+        });
+
+        if let Some(data) = data {
+            assert_eq!(data.data.len(), R_MAX);
+            assert!(data.data.iter().all(|b| *b == 2));
+            break;
+        }
+
+        if l.duration() > Duration::from_secs(20) {
+            panic!("Failed to receive large message in 20 seconds");
+        }
+        progress(&mut l, &mut r)?;
+    }
+
+    // The offer from L advertised the default limit.
+    let r_cid = r
+        .events
+        .iter()
+        .find_map(|(_, e)| match e {
+            Event::ChannelOpen(id, _) => Some(*id),
+            _ => None,
+        })
+        .expect("channel open on R");
+    assert_eq!(r.channel(r_cid).unwrap().max_message_size(), 262_144);
+
+    Ok(())
+}
@@ -0,0 +1,271 @@
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+use str0m::channel::ChannelId;
+use str0m::channel::{ChannelConfig, Reliability, WriteOptions};
+use str0m::{Candidate, Event, RtcError};
+use tracing::info_span;
+
+mod common;
+use common::{init_crypto_default, init_log, progress, TestRtc};
+
+#[test]
+pub fn data_channel_priority() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    let mut l = TestRtc::new(info_span!("L"));
+    let mut r = TestRtc::new(info_span!("R"));
+
+    let host1 = Candidate::host((Ipv4Addr::new(1, 1, 1, 1), 1000).into(), "udp")?;
+    let host2 = Candidate::host((Ipv4Addr::new(2, 2, 2, 2), 2000).into(), "udp")?;
+    l.add_local_candidate(host1);
+    r.add_local_candidate(host2);
+
+    let mut change = l.sdp_api();
+    let bulk = change.add_channel_with_config(ChannelConfig {
+        label: "bulk".into(),
+        priority: 128,
+        ..Default::default()
+    });
+    let game = change.add_channel_with_config(ChannelConfig {
+        label: "game".into(),
+        priority: 1024,
+        ..Default::default()
+    });
+    let (offer, pending) = change.apply().unwrap();
+
+    let answer = r.rtc.sdp_api().accept_offer(offer)?;
+    l.rtc.sdp_api().accept_answer(pending, answer)?;
+
+    loop {
+        if l.channel(bulk).is_some() && l.channel(game).is_some() {
+            break;
+        }
+        if l.duration() > Duration::from_secs(10) {
+            panic!("Failed to open channels in 10 seconds");
+        }
+        progress(&mut l, &mut r)?;
+    }
+
+    // Wait for the channels to be announced at R.
+    for _ in 0..20 {
+        progress(&mut l, &mut r)?;
+    }
+
+    let labels: Vec<_> = r
+        .events
+        .iter()
+        .filter_map(|(_, e)| match e {
+            Event::ChannelOpen(id, label) => Some((*id, label.clone())),
+            _ => None,
+        })
+        .collect();
+    let r_game = labels.iter().find(|(_, l)| l == "game").unwrap().0;
+
+    const CHUNK: usize = 16_384;
+    const TOTAL: usize = 1024 * 1024;
+
+    // Bulk is written first, which without priorities would mean it goes first.
+    for _ in 0..TOTAL / CHUNK {
+        l.channel(bulk).unwrap().write(true, &[1; CHUNK])?;
+    }
+    for _ in 0..TOTAL / CHUNK {
+        l.channel(game).unwrap().write(true, &[2; CHUNK])?;
+    }
+
+    // Jumps ahead of the other messages queued on the bulk channel.
+    let urgent = WriteOptions {
+        binary: false,
+        ordered: Some(false),
+        reliability: Some(Reliability::MaxRetransmits { retransmits: 0 }),
+        priority: 1,
+    };
+    l.channel(bulk).unwrap().write_with(b"urgent", urgent)?;
+
+    assert!(l.channel(bulk).unwrap().buffered_amount() > TOTAL);
+
+    r.events.clear();
+
+    let mut bulk_received = 0;
+    let mut game_received = 0;
+    let mut bulk_at_game_done = None;
+    let mut urgent_at = None;
+
+    while bulk_received < TOTAL + 6 || game_received < TOTAL {
+        if l.duration() > Duration::from_secs(60) {
+            panic!("Failed to transfer in 60 seconds");
+        }
+
+        progress(&mut l, &mut r)?;
+
+        for (_, e) in r.events.drain(..) {
+            let Event::ChannelData(d) = e else {
+                continue;
+            };
+            if d.id == r_game {
+                game_received += d.data.len();
+            } else if !d.binary {
+                assert_eq!(d.data, b"urgent");
+                urgent_at = Some(bulk_received);
+                bulk_received += d.data.len();
+            } else {
+                bulk_received += d.data.len();
+            }
+        }
+
+        if game_received == TOTAL && bulk_at_game_done.is_none() {
+            bulk_at_game_done = Some(bulk_received);
+        }
+    }
+
+    // The game channel has 8 times the weight of bulk.
+    let bulk_at_game_done = bulk_at_game_done.unwrap();
+    assert!(
+        bulk_at_game_done < TOTAL / 2,
+        "Bulk got {} before game was done",
+        bulk_at_game_done
+    );
+
+    let urgent_at = urgent_at.expect("urgent message");
+    assert!(urgent_at < TOTAL / 2, "Urgent after {} of bulk", urgent_at);
+
+    // Everything drains.
+    loop {
+        if l.channel(bulk).unwrap().buffered_amount() == 0
+            && l.channel(game).unwrap().buffered_amount() == 0
+        {
+            break;
+        }
+        if l.duration() > Duration::from_secs(70) {
+            panic!("Buffered amount never drained");
+        }
+        progress(&mut l, &mut r)?;
+    }
+
+    Ok(())
+}
+
+fn open_channel(
+    config: ChannelConfig,
+) -> Result<(TestRtc, TestRtc, ChannelId, ChannelId), RtcError> {
+    let mut l = TestRtc::new(info_span!("L"));
+    let mut r = TestRtc::new(info_span!("R"));
+
+    let host1 = Candidate::host((Ipv4Addr::new(1, 1, 1, 1), 1000).into(), "udp")?;
+    let host2 = Candidate::host((Ipv4Addr::new(2, 2, 2, 2), 2000).into(), "udp")?;
+    l.add_local_candidate(host1);
+    r.add_local_candidate(host2);
+
+    let mut change = l.sdp_api();
+    let id = change.add_channel_with_config(config);
+    let (offer, pending) = change.apply().unwrap();
+
+    let answer = r.rtc.sdp_api().accept_offer(offer)?;
+    l.rtc.sdp_api().accept_answer(pending, answer)?;
+
+    loop {
+        let r_id = r.events.iter().find_map(|(_, e)| match e {
+            Event::ChannelOpen(id, _) => Some(*id),
+            _ => None,
+        });
+        if let (Some(_), Some(r_id)) = (l.channel(id), r_id) {
+            return Ok((l, r, id, r_id));
+        }
+        if l.duration() > Duration::from_secs(10) {
+            panic!("Failed to open channel in 10 seconds");
+        }
+        progress(&mut l, &mut r)?;
+    }
+}
+
+#[test]
+pub fn data_channel_lifetime_expires_queued() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    // The lifetime of the channel applies without a per message override.
+    let (mut l, mut r, id, _) = open_channel(ChannelConfig {
+        label: "lossy".into(),
+        ordered: false,
+        reliability: Reliability::MaxPacketLifetime { lifetime: 50 },
+        ..Default::default()
+    })?;
+
+    const CHUNK: usize = 16_384;
+    const TOTAL: usize = 4 * 1024 * 1024;
+
+    for _ in 0..TOTAL / CHUNK {
+        l.channel(id).unwrap().write(true, &[1; CHUNK])?;
+    }
+
+    r.events.clear();
+
+    loop {
+        if l.channel(id).unwrap().buffered_amount() == 0 {
+            break;
+        }
+        if l.duration() > Duration::from_secs(60) {
+            panic!("Buffered amount never drained");
+        }
+        progress(&mut l, &mut r)?;
+    }
+
+    let received: usize = r
+        .events
+        .iter()
+        .filter_map(|(_, e)| match e {
+            Event::ChannelData(d) => Some(d.data.len()),
+            _ => None,
+        })
+        .sum();
+
+    assert!(received > 0);
+    assert!(received < TOTAL, "Nothing expired");
+
+    Ok(())
+}
+
+#[test]
+pub fn data_channel_write_failed_on_remote_reset() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    let (mut l, mut r, id, r_id) = open_channel(ChannelConfig {
+        label: "bulk".into(),
+        ..Default::default()
+    })?;
+
+    const CHUNK: usize = 16_384;
+    const COUNT: usize = 64;
+
+    // More than is handed to SCTP at once, so the rest is queued.
+    for _ in 0..COUNT {
+        l.channel(id).unwrap().write(true, &[1; CHUNK])?;
+    }
+
+    r.channel(r_id).unwrap().close();
+
+    loop {
+        if l.events.iter().any(|(_, e)| e == &Event::ChannelClose(id)) {
+            break;
+        }
+        if l.duration() > Duration::from_secs(10) {
+            panic!("Failed to close in 10 seconds");
+        }
+        progress(&mut l, &mut r)?;
+    }
+
+    let failed = l
+        .events
+        .iter()
+        .filter(|(_, e)| e == &Event::ChannelWriteFailed(id))
+        .count();
+    assert!(failed > 0 && failed < COUNT, "Failed writes: {}", failed);
+
+    // Reported before the close.
+    let pos = |ev: &Event| l.events.iter().position(|(_, e)| e == ev);
+    assert!(pos(&Event::ChannelWriteFailed(id)) < pos(&Event::ChannelClose(id)));
+
+    Ok(())
+}
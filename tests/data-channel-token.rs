@@ -0,0 +1,95 @@
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+use str0m::change::DataToken;
+use str0m::channel::ChannelConfig;
+use str0m::{Candidate, Event, RtcError};
+use tracing::info_span;
+
+mod common;
+use common::{init_crypto_default, init_log, progress, TestRtc};
+
+#[test]
+pub fn data_channel_token() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    let mut l = TestRtc::new(info_span!("L"));
+    let mut r = TestRtc::new(info_span!("R"));
+
+    let host1 = Candidate::host((Ipv4Addr::new(1, 1, 1, 1), 1000).into(), "udp")?;
+    let host2 = Candidate::host((Ipv4Addr::new(2, 2, 2, 2), 2000).into(), "udp")?;
+    l.add_local_candidate(host1);
+    r.add_local_candidate(host2);
+
+    // Only the strings go over the signalling.
+    let token_l = l.direct_api().local_data_token(true).to_string();
+    let token_r = r.direct_api().local_data_token(false).to_string();
+
+    assert!(token_l.len() < 150, "Token too long: {}", token_l);
+
+    let remote_l: DataToken = token_r.parse().unwrap();
+    let remote_r: DataToken = token_l.parse().unwrap();
+    assert!(!remote_l.active);
+    assert_eq!(remote_l.candidates.len(), 1);
+
+    l.direct_api().start_data_session(&remote_l)?;
+    r.direct_api().start_data_session(&remote_r)?;
+
+    // One out-of-band channel created on both sides, and one in-band from L.
+    let config = ChannelConfig {
+        label: "telemetry".into(),
+        negotiated: Some(1),
+        ..Default::default()
+    };
+    let l_telemetry = l.direct_api().create_data_channel(config.clone());
+    let r_telemetry = r.direct_api().create_data_channel(config);
+
+    let l_control = l.direct_api().create_data_channel(ChannelConfig {
+        label: "control".into(),
+        ..Default::default()
+    });
+
+    loop {
+        if l.channel(l_telemetry).is_some()
+            && l.channel(l_control).is_some()
+            && r.channel(r_telemetry).is_some()
+        {
+            break;
+        }
+        if l.duration() > Duration::from_secs(10) {
+            panic!("Failed to open channels in 10 seconds");
+        }
+        progress(&mut l, &mut r)?;
+    }
+
+    let r_control = r
+        .events
+        .iter()
+        .find_map(|(_, e)| match e {
+            Event::ChannelOpen(id, label) if label == "control" => Some(*id),
+            _ => None,
+        })
+        .expect("control channel opened by L");
+
+    r.channel(r_telemetry).unwrap().write(true, &[42; 8])?;
+    l.channel(l_control).unwrap().write(false, b"reboot")?;
+
+    loop {
+        let l_got = l.events.iter().any(
+            |(_, e)| matches!(e, Event::ChannelData(d) if d.id == l_telemetry && d.data == [42; 8]),
+        );
+        let r_got = r.events.iter().any(
+            |(_, e)| matches!(e, Event::ChannelData(d) if d.id == r_control && d.data == b"reboot"),
+        );
+        if l_got && r_got {
+            break;
+        }
+        if l.duration() > Duration::from_secs(20) {
+            panic!("Failed to exchange data in 20 seconds");
+        }
+        progress(&mut l, &mut r)?;
+    }
+
+    Ok(())
+}
@@ -1,7 +1,7 @@
 use std::net::Ipv4Addr;
 use std::time::Duration;
 
-use str0m::{Candidate, RtcError};
+use str0m::{Candidate, Event, RtcError};
 use tracing::info_span;
 
 mod common;
@@ -56,3 +56,57 @@ pub fn data_channel() -> Result<(), RtcError> {
 
     Ok(())
 }
+
+#[test]
+pub fn data_channel_empty_message() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    let mut l = TestRtc::new(info_span!("L"));
+    let mut r = TestRtc::new(info_span!("R"));
+
+    let host1 = Candidate::host((Ipv4Addr::new(1, 1, 1, 1), 1000).into(), "udp")?;
+    let host2 = Candidate::host((Ipv4Addr::new(2, 2, 2, 2), 2000).into(), "udp")?;
+    l.add_local_candidate(host1);
+    r.add_local_candidate(host2);
+
+    let mut change = l.sdp_api();
+    let cid = change.add_channel("ordered".into());
+    let (offer, pending) = change.apply().unwrap();
+
+    let answer = r.rtc.sdp_api().accept_offer(offer)?;
+    l.rtc.sdp_api().accept_answer(pending, answer)?;
+
+    loop {
+        if l.channel(cid).is_some() {
+            break;
+        }
+        progress(&mut l, &mut r)?;
+    }
+
+    let mut chan = l.channel(cid).unwrap();
+    chan.write(false, b"")?;
+    chan.write(true, b"")?;
+    chan.write(false, b"after")?;
+
+    for _ in 0..50 {
+        progress(&mut l, &mut r)?;
+    }
+
+    let data: Vec<_> = r
+        .events
+        .iter()
+        .filter_map(|(_, e)| match e {
+            Event::ChannelData(d) => Some((d.binary, d.data.as_slice())),
+            _ => None,
+        })
+        .collect();
+
+    // The empty messages must not stall the message after them.
+    assert_eq!(
+        data,
+        vec![(false, &b""[..]), (true, &b""[..]), (false, &b"after"[..])]
+    );
+
+    Ok(())
+}
@@ -0,0 +1,200 @@
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+use str0m::change::SdpOffer;
+use str0m::config::{CryptoProvider, DtlsCert, DtlsCertOptions, FingerprintHash};
+use str0m::media::{Direction, MediaKind, Mid};
+use str0m::{Candidate, Event, Rtc, RtcConfig, RtcError};
+use tracing::info_span;
+
+mod common;
+use common::{init_crypto_default, init_log, progress, TestRtc};
+
+fn negotiate(
+    l: &mut TestRtc,
+    r: &mut TestRtc,
+    munge_offer: impl Fn(String) -> String,
+) -> Result<(Mid, String), RtcError> {
+    let host1 = Candidate::host((Ipv4Addr::new(1, 1, 1, 1), 1000).into(), "udp")?;
+    let host2 = Candidate::host((Ipv4Addr::new(2, 2, 2, 2), 2000).into(), "udp")?;
+    l.add_local_candidate(host1);
+    r.add_local_candidate(host2);
+
+    let mut change = l.sdp_api();
+    let mid = change.add_media(MediaKind::Audio, Direction::SendRecv, None, None, None);
+    let (offer, pending) = change.apply().unwrap();
+
+    let offer_str = munge_offer(offer.to_sdp_string());
+    let offer = SdpOffer::from_sdp_string(&offer_str)?;
+
+    let answer = r.rtc.sdp_api().accept_offer(offer)?;
+    l.rtc.sdp_api().accept_answer(pending, answer)?;
+
+    Ok((mid, offer_str))
+}
+
+fn connect(l: &mut TestRtc, r: &mut TestRtc) -> Result<(), RtcError> {
+    let start = l.duration();
+    while !(l.is_connected() && r.is_connected()) {
+        if l.duration() > start + Duration::from_secs(10) {
+            panic!("Failed to connect in 10 seconds");
+        }
+        progress(l, r)?;
+    }
+    Ok(())
+}
+
+fn send_audio(l: &mut TestRtc, r: &mut TestRtc, mid: Mid, dur: Duration) -> Result<(), RtcError> {
+    let pt = l.params_opus().pt();
+    let end = l.duration() + dur;
+
+    while l.duration() < end {
+        let wallclock = l.start + l.duration();
+        let time = l.duration().into();
+        l.writer(mid)
+            .unwrap()
+            .write(pt, wallclock, time, vec![1_u8; 80])?;
+
+        progress(l, r)?;
+    }
+
+    Ok(())
+}
+
+fn count(rtc: &TestRtc, f: impl Fn(&Event) -> bool) -> usize {
+    rtc.events.iter().filter(|(_, e)| f(e)).count()
+}
+
+fn fingerprint_lines(sdp: &str) -> Vec<&str> {
+    sdp.split("\r\n")
+        .filter(|l| l.starts_with("a=fingerprint:"))
+        .collect()
+}
+
+#[test]
+pub fn fingerprint_sha384_and_sha512() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    let config = RtcConfig::new()
+        .set_fingerprint_hashes(&[FingerprintHash::Sha384, FingerprintHash::Sha512]);
+    let mut l = TestRtc::new_with_rtc(info_span!("L"), config.build());
+    let mut r = TestRtc::new_with_rtc(info_span!("R"), Rtc::new());
+
+    let (_, offer) = negotiate(&mut l, &mut r, |s| s)?;
+
+    let lines = fingerprint_lines(&offer);
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("a=fingerprint:sha-384 "));
+    assert!(lines[1].starts_with("a=fingerprint:sha-512 "));
+
+    connect(&mut l, &mut r)
+}
+
+#[test]
+pub fn fingerprint_any_line_matches() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    let config = || {
+        RtcConfig::new().set_fingerprint_hashes(&[FingerprintHash::Sha256, FingerprintHash::Sha384])
+    };
+    let mut l = TestRtc::new_with_rtc(info_span!("L"), config().build());
+    let mut r = TestRtc::new_with_rtc(info_span!("R"), Rtc::new());
+
+    // A bad sha-256 line is fine as long as the sha-384 matches.
+    let (_, offer) = negotiate(&mut l, &mut r, |s| {
+        s.replace("a=fingerprint:sha-256 ", "a=fingerprint:sha-256 00:")
+    })?;
+    assert_eq!(fingerprint_lines(&offer).len(), 2);
+
+    connect(&mut l, &mut r)?;
+
+    // No line matching disconnects.
+    let mut l = TestRtc::new_with_rtc(info_span!("L"), config().build());
+    let mut r = TestRtc::new_with_rtc(info_span!("R"), Rtc::new());
+
+    negotiate(&mut l, &mut r, |s| {
+        s.replace("a=fingerprint:sha-256 ", "a=fingerprint:sha-256 00:")
+            .replace("a=fingerprint:sha-384 ", "a=fingerprint:sha-384 00:")
+    })?;
+
+    let res = connect(&mut l, &mut r);
+    assert!(matches!(res, Err(RtcError::RemoteSdp(_))));
+    assert!(!r.rtc.is_alive());
+
+    Ok(())
+}
+
+#[test]
+pub fn dtls_cert_rotation() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    let mut l = TestRtc::new(info_span!("L"));
+    let mut r = TestRtc::new(info_span!("R"));
+
+    let (mid, _) = negotiate(&mut l, &mut r, |s| s)?;
+    connect(&mut l, &mut r)?;
+    send_audio(&mut l, &mut r, mid, Duration::from_secs(1))?;
+
+    let old = l.direct_api().local_dtls_fingerprint();
+    let provider = CryptoProvider::from_feature_flags();
+    let cert = DtlsCert::new(provider.clone(), DtlsCertOptions::default());
+    let new = cert.fingerprint();
+    l.rtc.set_dtls_cert(cert)?;
+
+    // Not in use until renegotiated.
+    assert_eq!(l.direct_api().local_dtls_fingerprint(), old);
+
+    let mut change = l.sdp_api();
+    change.ice_restart(true);
+    let (offer, pending) = change.apply().unwrap();
+
+    let offer_str = offer.to_sdp_string();
+    assert!(offer_str.contains(&new.to_string()));
+
+    let answer = r.rtc.sdp_api().accept_offer(offer)?;
+    l.rtc.sdp_api().accept_answer(pending, answer)?;
+
+    assert_eq!(l.direct_api().local_dtls_fingerprint(), new);
+
+    connect(&mut l, &mut r)?;
+    assert_eq!(r.direct_api().remote_dtls_fingerprint(), Some(new));
+
+    let media_before = count(&r, |e| matches!(e, Event::MediaData(_)));
+    send_audio(&mut l, &mut r, mid, Duration::from_secs(2))?;
+    let media_after = count(&r, |e| matches!(e, Event::MediaData(_)));
+    assert!(
+        media_after - media_before > 50,
+        "Not enough MediaData after rotation: {}",
+        media_after - media_before
+    );
+
+    // Rotate on the answering side.
+    let cert = DtlsCert::new(provider, DtlsCertOptions::default());
+    let new = cert.fingerprint();
+    r.rtc.set_dtls_cert(cert)?;
+
+    let mut change = l.sdp_api();
+    change.ice_restart(true);
+    let (offer, pending) = change.apply().unwrap();
+    let answer = r.rtc.sdp_api().accept_offer(offer)?;
+    assert!(answer.to_sdp_string().contains(&new.to_string()));
+    l.rtc.sdp_api().accept_answer(pending, answer)?;
+
+    connect(&mut l, &mut r)?;
+
+    let media_before = count(&r, |e| matches!(e, Event::MediaData(_)));
+    send_audio(&mut l, &mut r, mid, Duration::from_secs(2))?;
+    let media_after = count(&r, |e| matches!(e, Event::MediaData(_)));
+    assert!(media_after - media_before > 50);
+
+    assert_eq!(l.direct_api().remote_dtls_fingerprint(), Some(new));
+
+    // The restart is not a new connection.
+    assert_eq!(count(&l, |e| matches!(e, Event::Connected)), 1);
+    assert_eq!(count(&r, |e| matches!(e, Event::Connected)), 1);
+
+    Ok(())
+}
@@ -0,0 +1,118 @@
+#![cfg(feature = "rust-crypto")]
+
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+use str0m::channel::ChannelId;
+use str0m::config::{CryptoProvider, Dtls13Mode};
+use str0m::{Candidate, Event, Rtc, RtcError};
+use tracing::info_span;
+
+mod common;
+use common::{init_log, progress, TestRtc};
+
+fn rtc(mode: Dtls13Mode) -> Rtc {
+    Rtc::builder()
+        .set_crypto_provider(CryptoProvider::RustCrypto)
+        .set_dtls13(mode)
+        .build()
+}
+
+/// Negotiate a data channel between two `Rtc` and run until connected, or for 5 seconds.
+fn connect(l_mode: Dtls13Mode, r_mode: Dtls13Mode) -> (TestRtc, TestRtc, ChannelId, bool) {
+    let mut l = TestRtc::new_with_rtc(info_span!("L"), rtc(l_mode));
+    let mut r = TestRtc::new_with_rtc(info_span!("R"), rtc(r_mode));
+
+    let host1 = Candidate::host((Ipv4Addr::new(1, 1, 1, 1), 1000).into(), "udp").unwrap();
+    let host2 = Candidate::host((Ipv4Addr::new(2, 2, 2, 2), 2000).into(), "udp").unwrap();
+    l.add_local_candidate(host1);
+    r.add_local_candidate(host2);
+
+    let mut change = l.sdp_api();
+    let cid = change.add_channel("dtls13".into());
+    let (offer, pending) = change.apply().unwrap();
+
+    let answer = r.rtc.sdp_api().accept_offer(offer).unwrap();
+    l.rtc.sdp_api().accept_answer(pending, answer).unwrap();
+
+    let mut ok = true;
+    while !(l.is_connected() && r.is_connected()) {
+        if progress(&mut l, &mut r).is_err() || l.duration() > Duration::from_secs(5) {
+            ok = false;
+            break;
+        }
+    }
+
+    (l, r, cid, ok)
+}
+
+fn send_data(l: &mut TestRtc, r: &mut TestRtc, cid: ChannelId) -> Result<(), RtcError> {
+    let start = l.duration();
+    let mut sent = false;
+
+    while l.duration() < start + Duration::from_secs(5) {
+        if !sent {
+            if let Some(mut chan) = l.channel(cid) {
+                chan.write(true, b"over dtls13").expect("to write");
+                sent = true;
+            }
+        }
+        progress(l, r)?;
+
+        let received = r
+            .events
+            .iter()
+            .any(|(_, e)| matches!(e, Event::ChannelData(d) if d.data == b"over dtls13"));
+        if received {
+            return Ok(());
+        }
+    }
+
+    panic!("data channel message not received");
+}
+
+#[test]
+pub fn dtls13_required_both_sides() -> Result<(), RtcError> {
+    init_log();
+
+    let (mut l, mut r, cid, ok) = connect(Dtls13Mode::Require, Dtls13Mode::Require);
+    assert!(ok, "DTLS 1.3 handshake failed");
+
+    send_data(&mut l, &mut r, cid)
+}
+
+#[test]
+pub fn dtls13_preferred_both_sides() -> Result<(), RtcError> {
+    init_log();
+
+    let (mut l, mut r, cid, ok) = connect(Dtls13Mode::Prefer, Dtls13Mode::Prefer);
+    assert!(ok, "DTLS 1.3 handshake failed");
+
+    send_data(&mut l, &mut r, cid)
+}
+
+#[test]
+pub fn dtls13_fallback_to_dtls12() -> Result<(), RtcError> {
+    init_log();
+
+    let (mut l, mut r, cid, ok) = connect(Dtls13Mode::Prefer, Dtls13Mode::Disabled);
+    assert!(ok, "DTLS 1.2 fallback failed");
+    send_data(&mut l, &mut r, cid)?;
+
+    let (mut l, mut r, cid, ok) = connect(Dtls13Mode::Disabled, Dtls13Mode::Prefer);
+    assert!(ok, "DTLS 1.2 fallback failed");
+    send_data(&mut l, &mut r, cid)
+}
+
+#[test]
+pub fn dtls13_required_against_dtls12_fails() {
+    init_log();
+
+    let (l, r, _, ok) = connect(Dtls13Mode::Require, Dtls13Mode::Disabled);
+    assert!(!ok);
+    assert!(!l.is_connected() || !r.is_connected());
+
+    let (l, r, _, ok) = connect(Dtls13Mode::Disabled, Dtls13Mode::Require);
+    assert!(!ok);
+    assert!(!l.is_connected() || !r.is_connected());
+}
@@ -0,0 +1,132 @@
+use std::time::Duration;
+
+use str0m::config::{Dtls13Mode, KeyLog, SrtpKeyLog};
+use str0m::{Event, Rtc, RtcError};
+
+mod common;
+use common::{connect_l_r_with_rtc, init_crypto_default, init_log, progress, TestRtc};
+
+fn key_logs(rtc: &TestRtc) -> (Vec<&str>, Vec<&SrtpKeyLog>) {
+    let mut dtls = vec![];
+    let mut srtp = vec![];
+    for (_, e) in &rtc.events {
+        match e {
+            Event::KeyLog(KeyLog::Dtls(v)) => dtls.push(v.as_str()),
+            Event::KeyLog(KeyLog::Srtp(v)) => srtp.push(v),
+            _ => {}
+        }
+    }
+    (dtls, srtp)
+}
+
+fn connect(l: Rtc, r: Rtc) -> Result<(TestRtc, TestRtc), RtcError> {
+    let (mut l, mut r) = connect_l_r_with_rtc(l, r);
+
+    while l.duration() < Duration::from_secs(1) {
+        progress(&mut l, &mut r)?;
+    }
+
+    Ok((l, r))
+}
+
+fn assert_srtp_mirrored(l: &TestRtc, r: &TestRtc) {
+    let (_, srtp_l) = key_logs(l);
+    let (_, srtp_r) = key_logs(r);
+    assert_eq!(srtp_l.len(), 1);
+    assert_eq!(srtp_r.len(), 1);
+
+    let (l, r) = (srtp_l[0], srtp_r[0]);
+    assert_eq!(l.profile, r.profile);
+    assert_eq!(l.local_master_key, r.remote_master_key);
+    assert_eq!(l.local_master_salt, r.remote_master_salt);
+    assert_eq!(l.remote_master_key, r.local_master_key);
+    assert_ne!(l.local_master_key, l.remote_master_key);
+    assert_eq!(l.local_inline_key(), r.remote_inline_key());
+}
+
+#[test]
+pub fn key_log_off_by_default() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    let (l, r) = connect(Rtc::new(), Rtc::new())?;
+
+    assert!(l.is_connected() && r.is_connected());
+    for rtc in [&l, &r] {
+        assert!(!rtc
+            .events
+            .iter()
+            .any(|(_, e)| matches!(e, Event::KeyLog(_))));
+    }
+
+    Ok(())
+}
+
+#[test]
+pub fn key_log_dtls12() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    let rtc = || {
+        Rtc::builder()
+            .set_dtls13(Dtls13Mode::Disabled)
+            .enable_key_log(true)
+            .build()
+    };
+    let (l, r) = connect(rtc(), rtc())?;
+
+    // Both sides log the same CLIENT_RANDOM <client random> <master secret>.
+    let (dtls_l, _) = key_logs(&l);
+    let (dtls_r, _) = key_logs(&r);
+    assert_eq!(dtls_l.len(), 1);
+    assert_eq!(dtls_l, dtls_r);
+
+    let parts: Vec<_> = dtls_l[0].split(' ').collect();
+    assert_eq!(parts[0], "CLIENT_RANDOM");
+    assert_eq!(parts[1].len(), 64);
+    assert_eq!(parts[2].len(), 96);
+
+    assert_srtp_mirrored(&l, &r);
+
+    Ok(())
+}
+
+#[cfg(feature = "rust-crypto")]
+#[test]
+pub fn key_log_dtls13() -> Result<(), RtcError> {
+    use str0m::config::CryptoProvider;
+
+    init_log();
+
+    let rtc = || {
+        Rtc::builder()
+            .set_crypto_provider(CryptoProvider::RustCrypto)
+            .set_dtls13(Dtls13Mode::Require)
+            .enable_key_log(true)
+            .build()
+    };
+    let (l, r) = connect(rtc(), rtc())?;
+
+    let (dtls_l, _) = key_logs(&l);
+    let (dtls_r, _) = key_logs(&r);
+    assert_eq!(dtls_l, dtls_r);
+
+    let labels: Vec<_> = dtls_l
+        .iter()
+        .map(|v| v.split(' ').next().unwrap())
+        .collect();
+    assert_eq!(
+        labels,
+        [
+            "CLIENT_HANDSHAKE_TRAFFIC_SECRET",
+            "SERVER_HANDSHAKE_TRAFFIC_SECRET",
+            "CLIENT_TRAFFIC_SECRET_0",
+            "SERVER_TRAFFIC_SECRET_0",
+            "EXPORTER_SECRET",
+        ]
+    );
+
+    assert_srtp_mirrored(&l, &r);
+
+    Ok(())
+}
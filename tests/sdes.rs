@@ -0,0 +1,279 @@
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+use str0m::config::SrtpProfile;
+use str0m::media::{Direction, MediaKind};
+use str0m::rtp::{ExtensionValues, Ssrc};
+use str0m::{Candidate, Event, Rtc, RtcConfig, RtcError};
+use tracing::info_span;
+
+mod common;
+use common::{init_crypto_default, init_log, progress, TestRtc};
+
+fn sdp_connect_and_send(
+    l_config: RtcConfig,
+    r_config: RtcConfig,
+    munge_offer: impl Fn(String) -> String,
+) -> Result<(String, String, TestRtc, TestRtc), RtcError> {
+    let mut l = TestRtc::new_with_rtc(info_span!("L"), l_config.build());
+    let mut r = TestRtc::new_with_rtc(info_span!("R"), r_config.build());
+
+    let host1 = Candidate::host((Ipv4Addr::new(1, 1, 1, 1), 1000).into(), "udp")?;
+    let host2 = Candidate::host((Ipv4Addr::new(2, 2, 2, 2), 2000).into(), "udp")?;
+    l.add_local_candidate(host1);
+    r.add_local_candidate(host2);
+
+    let mut change = l.sdp_api();
+    let mid = change.add_media(MediaKind::Audio, Direction::SendRecv, None, None, None);
+    let (offer, pending) = change.apply().unwrap();
+
+    let offer_str = munge_offer(offer.to_sdp_string());
+    let offer = str0m::change::SdpOffer::from_sdp_string(&offer_str)?;
+
+    let answer = r.rtc.sdp_api().accept_offer(offer)?;
+    let answer_str = answer.to_sdp_string();
+    l.rtc.sdp_api().accept_answer(pending, answer)?;
+
+    loop {
+        if l.is_connected() && r.is_connected() {
+            break;
+        }
+        progress(&mut l, &mut r)?;
+    }
+
+    let pt = l.params_opus().pt();
+
+    while l.duration() < Duration::from_secs(3) {
+        let wallclock = l.start + l.duration();
+        let time = l.duration().into();
+        l.writer(mid)
+            .unwrap()
+            .write(pt, wallclock, time, vec![1_u8; 80])?;
+
+        progress(&mut l, &mut r)?;
+    }
+
+    Ok((offer_str, answer_str, l, r))
+}
+
+fn count(rtc: &TestRtc, f: impl Fn(&Event) -> bool) -> usize {
+    rtc.events.iter().filter(|(_, e)| f(e)).count()
+}
+
+#[test]
+pub fn sdes_offer_answer() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    let config = || RtcConfig::new().set_sdes_srtp(true);
+    let (offer, answer, l, r) = sdp_connect_and_send(config(), config(), |s| s)?;
+
+    // No DTLS at all.
+    for sdp in [&offer, &answer] {
+        assert!(sdp.contains("m=audio 9 RTP/SAVPF "));
+        assert!(!sdp.contains("a=fingerprint"));
+        assert!(!sdp.contains("a=setup"));
+    }
+
+    // One line per default profile in the offer, and the one picked in the answer.
+    assert!(offer.contains("a=crypto:1 AEAD_AES_128_GCM inline:"));
+    assert!(offer.contains("a=crypto:2 AES_CM_128_HMAC_SHA1_80 inline:"));
+    assert!(answer.contains("a=crypto:1 AEAD_AES_128_GCM inline:"));
+    assert_eq!(answer.matches("a=crypto:").count(), 1);
+
+    assert_eq!(count(&l, |e| matches!(e, Event::Connected)), 1);
+    assert_eq!(count(&r, |e| matches!(e, Event::Connected)), 1);
+
+    let media_count = count(&r, |e| matches!(e, Event::MediaData(_)));
+    assert!(media_count > 50, "Not enough MediaData: {}", media_count);
+
+    Ok(())
+}
+
+#[test]
+pub fn sdes_answer_follows_offer() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    let config = || RtcConfig::new().set_sdes_srtp(true);
+
+    // Like a SIP gateway offering RTP/SAVP with only AES_CM and a key lifetime.
+    let munge = |s: String| {
+        let s = s.replace("RTP/SAVPF", "RTP/SAVP");
+        let mut lines: Vec<_> = s.split("\r\n").map(|l| l.to_string()).collect();
+        lines.retain(|l| !l.starts_with("a=crypto:1 "));
+        for l in &mut lines {
+            if l.starts_with("a=crypto:2 ") {
+                l.push_str("|2^31");
+            }
+        }
+        lines.join("\r\n")
+    };
+
+    let (_, answer, _, r) = sdp_connect_and_send(config(), config(), munge)?;
+
+    assert!(answer.contains("m=audio 9 RTP/SAVP "));
+    assert!(answer.contains("a=crypto:2 AES_CM_128_HMAC_SHA1_80 inline:"));
+    assert!(!answer.contains("|2^31"));
+
+    let media_count = count(&r, |e| matches!(e, Event::MediaData(_)));
+    assert!(media_count > 50, "Not enough MediaData: {}", media_count);
+
+    Ok(())
+}
+
+#[test]
+pub fn sdes_key_lifetime_enforced() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    let config = || RtcConfig::new().set_sdes_srtp(true);
+
+    // The offerer's key may only protect 20 packets.
+    let munge = |s: String| {
+        let lines: Vec<_> = s
+            .split("\r\n")
+            .map(|l| {
+                if l.starts_with("a=crypto:") {
+                    format!("{}|20", l)
+                } else {
+                    l.to_string()
+                }
+            })
+            .collect();
+        lines.join("\r\n")
+    };
+
+    let (_, _, _, r) = sdp_connect_and_send(config(), config(), munge)?;
+
+    let media_count = count(&r, |e| matches!(e, Event::MediaData(_)));
+    assert!(
+        media_count > 0 && media_count <= 20,
+        "Unexpected MediaData: {}",
+        media_count
+    );
+
+    Ok(())
+}
+
+#[test]
+pub fn sdes_requires_fingerprint_when_disabled() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    let mut l = Rtc::builder().set_sdes_srtp(true).build();
+    let mut r = Rtc::new();
+
+    let mut change = l.sdp_api();
+    change.add_media(MediaKind::Audio, Direction::SendRecv, None, None, None);
+    let (offer, _) = change.apply().unwrap();
+
+    let res = r.sdp_api().accept_offer(offer);
+    assert!(matches!(res, Err(RtcError::RemoteSdp(_))));
+
+    Ok(())
+}
+
+#[test]
+pub fn sdes_direct_api() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    let config = || {
+        Rtc::builder()
+            .set_rtp_mode(true)
+            .set_reordering_size_audio(0)
+            .build()
+    };
+    let mut l = TestRtc::new_with_rtc(info_span!("L"), config());
+    let mut r = TestRtc::new_with_rtc(info_span!("R"), config());
+
+    let host1 = Candidate::host((Ipv4Addr::new(1, 1, 1, 1), 1000).into(), "udp")?;
+    let host2 = Candidate::host((Ipv4Addr::new(2, 2, 2, 2), 2000).into(), "udp")?;
+    l.add_local_candidate(host1.clone());
+    l.add_remote_candidate(host2.clone());
+    r.add_local_candidate(host2);
+    r.add_remote_candidate(host1);
+
+    let creds_l = l.direct_api().local_ice_credentials();
+    let creds_r = r.direct_api().local_ice_credentials();
+    l.direct_api().set_remote_ice_credentials(creds_r);
+    r.direct_api().set_remote_ice_credentials(creds_l);
+    l.direct_api().set_ice_controlling(true);
+    r.direct_api().set_ice_controlling(false);
+
+    let profile = SrtpProfile::Aes256CmSha1_80;
+    let key_l = [1_u8; 46];
+    let key_r = [2_u8; 46];
+
+    let res = l
+        .direct_api()
+        .start_sdes_srtp(profile, &[1; 30], &key_r, None);
+    assert!(matches!(res, Err(RtcError::SdesKeyLength(_, 46))));
+
+    l.direct_api()
+        .start_sdes_srtp(profile, &key_l, &key_r, None)?;
+    r.direct_api()
+        .start_sdes_srtp(profile, &key_r, &key_l, None)?;
+
+    loop {
+        if l.is_connected() && r.is_connected() {
+            break;
+        }
+        progress(&mut l, &mut r)?;
+    }
+
+    let mid = "aud".into();
+    let ssrc: Ssrc = 42.into();
+    l.direct_api().declare_media(mid, MediaKind::Audio);
+    l.direct_api().declare_stream_tx(ssrc, None, mid, None);
+    r.direct_api().declare_media(mid, MediaKind::Audio);
+    r.direct_api().expect_stream_rx(ssrc, None, mid, None);
+
+    let max = l.last.max(r.last);
+    l.last = max;
+    r.last = max;
+
+    let pt = l.params_opus().pt();
+
+    for i in 0..10_u64 {
+        let wallclock = l.start + l.duration();
+        l.direct_api()
+            .stream_tx(&ssrc)
+            .unwrap()
+            .write_rtp(
+                pt,
+                (47_000 + i).into(),
+                (47_000_000 + i * 960) as u32,
+                wallclock,
+                false,
+                ExtensionValues::default(),
+                false,
+                vec![1, 2, 3, 4],
+            )
+            .expect("clean write");
+
+        let next = l.duration() + Duration::from_millis(20);
+        while l.duration() < next {
+            progress(&mut l, &mut r)?;
+        }
+    }
+
+    while l.duration() < Duration::from_secs(1) {
+        progress(&mut l, &mut r)?;
+    }
+
+    let payloads: Vec<_> = r
+        .events
+        .iter()
+        .filter_map(|(_, e)| match e {
+            Event::RtpPacket(v) => Some(&v.payload),
+            _ => None,
+        })
+        .collect();
+
+    assert_eq!(payloads.len(), 10);
+    assert!(payloads.iter().all(|p| **p == [1, 2, 3, 4]));
+
+    Ok(())
+}
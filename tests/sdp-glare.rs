@@ -0,0 +1,154 @@
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+use str0m::change::{PerfectNegotiation, SdpAnswer};
+use str0m::media::{Direction, MediaKind};
+use str0m::{Candidate, Event, RtcError};
+use tracing::info_span;
+
+mod common;
+use common::{init_crypto_default, init_log, negotiate, progress, TestRtc};
+
+#[test]
+pub fn sdp_rollback() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    let (mut l, mut r) = peers()?;
+
+    let mid_a = negotiate(&mut l, &mut r, |change| {
+        change.add_media(MediaKind::Audio, Direction::SendRecv, None, None, None)
+    });
+
+    let creds = l.direct_api().local_ice_credentials();
+
+    let mut change = l.sdp_api();
+    change.set_direction(mid_a, Direction::Inactive);
+    let mid_v = change.add_media(MediaKind::Video, Direction::SendOnly, None, None, None);
+    let cid = change.add_channel("chat".into());
+    let new_creds = change.ice_restart(true);
+    let (_offer, pending) = change.apply().unwrap();
+
+    assert_eq!(l.media(mid_a).unwrap().direction(), Direction::Inactive);
+
+    l.sdp_api().rollback(&pending)?;
+
+    // Back to how it was before the offer.
+    assert_eq!(l.media(mid_a).unwrap().direction(), Direction::SendRecv);
+    assert!(l.media(mid_v).is_none());
+    assert!(l.channel(cid).is_none());
+    assert_eq!(l.direct_api().local_ice_credentials(), creds);
+
+    // The pending offer can't be rolled back twice.
+    let err = l.sdp_api().rollback(&pending).unwrap_err();
+    assert!(matches!(err, RtcError::ChangesOutOfOrder));
+
+    // But it can be offered again.
+    let mut change = l.sdp_api();
+    change.merge(pending);
+    let (offer, pending) = change.apply().unwrap();
+    assert_eq!(l.media(mid_a).unwrap().direction(), Direction::Inactive);
+
+    let answer = r.rtc.sdp_api().accept_offer(offer)?;
+    l.rtc.sdp_api().accept_answer(pending, answer)?;
+
+    assert!(l.media(mid_v).is_some());
+    assert!(r.media(mid_v).is_some());
+    assert_eq!(r.media(mid_a).unwrap().direction(), Direction::Inactive);
+    assert_eq!(l.direct_api().local_ice_credentials(), new_creds);
+
+    loop {
+        if l.channel(cid).is_some() {
+            break;
+        }
+        if l.duration() > Duration::from_secs(10) {
+            panic!("Failed to open channel after rollback");
+        }
+        progress(&mut l, &mut r)?;
+    }
+
+    Ok(())
+}
+
+#[test]
+pub fn sdp_perfect_negotiation_glare() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    let (mut l, mut r) = peers()?;
+
+    let mut l_neg = PerfectNegotiation::new(true);
+    let mut r_neg = PerfectNegotiation::new(false);
+
+    // Both sides offer at the same time.
+    let mut change = l.sdp_api();
+    let mid_a = change.add_media(MediaKind::Audio, Direction::SendOnly, None, None, None);
+    let l_cid = change.add_channel("from-l".into());
+    let l_offer = l_neg.offer(change).unwrap();
+
+    let mut change = r.sdp_api();
+    let mid_v = change.add_media(MediaKind::Video, Direction::SendOnly, None, None, None);
+    let r_offer = r_neg.offer(change).unwrap();
+
+    assert!(l_neg.has_pending_offer());
+    assert!(r_neg.has_pending_offer());
+
+    // The impolite R ignores the offer from L.
+    assert!(r_neg.accept_offer(r.sdp_api(), l_offer)?.is_none());
+    assert!(r_neg.has_pending_offer());
+
+    // The polite L rolls back its own offer and answers.
+    let l_answer = l_neg.accept_offer(l.sdp_api(), r_offer)?.unwrap();
+    assert!(!l_neg.has_pending_offer());
+    assert!(l_neg.needs_offer());
+    assert!(l.media(mid_a).is_none());
+    assert!(l.channel(l_cid).is_none());
+
+    let again = SdpAnswer::from_sdp_string(&l_answer.to_sdp_string())?;
+    r_neg.accept_answer(r.sdp_api(), l_answer)?;
+    assert!(!r_neg.has_pending_offer());
+
+    // An answer without an offer.
+    let err = r_neg.accept_answer(r.sdp_api(), again).unwrap_err();
+    assert!(matches!(err, RtcError::ChangesOutOfOrder));
+
+    // L offers its changes again.
+    let l_offer = l_neg.offer(l.sdp_api()).unwrap();
+    assert!(!l_neg.needs_offer());
+
+    let r_answer = r_neg.accept_offer(r.sdp_api(), l_offer)?.unwrap();
+    l_neg.accept_answer(l.sdp_api(), r_answer)?;
+
+    for mid in [mid_a, mid_v] {
+        assert!(l.media(mid).is_some());
+        assert!(r.media(mid).is_some());
+    }
+
+    loop {
+        let r_open = r
+            .events
+            .iter()
+            .any(|(_, e)| matches!(e, Event::ChannelOpen(_, label) if label == "from-l"));
+        if l.channel(l_cid).is_some() && r_open {
+            break;
+        }
+        if l.duration() > Duration::from_secs(10) {
+            panic!("Failed to open channel after glare");
+        }
+        progress(&mut l, &mut r)?;
+    }
+
+    Ok(())
+}
+
+fn peers() -> Result<(TestRtc, TestRtc), RtcError> {
+    let mut l = TestRtc::new(info_span!("L"));
+    let mut r = TestRtc::new(info_span!("R"));
+
+    let host1 = Candidate::host((Ipv4Addr::new(1, 1, 1, 1), 1000).into(), "udp")?;
+    let host2 = Candidate::host((Ipv4Addr::new(2, 2, 2, 2), 2000).into(), "udp")?;
+    l.add_local_candidate(host1);
+    r.add_local_candidate(host2);
+
+    Ok((l, r))
+}
@@ -0,0 +1,210 @@
+use std::net::Ipv4Addr;
+
+use str0m::change::{SdpAnswer, SdpOffer, SessionDescription};
+use str0m::format::Codec;
+use str0m::media::{Direction, MediaKind, Mid};
+use str0m::{Candidate, CandidateKind, Rtc, RtcError};
+
+mod common;
+use common::{init_crypto_default, init_log};
+
+const BROWSER_OFFER: &str = "v=0\r\n\
+    o=- 6497184233416523373 2 IN IP4 127.0.0.1\r\n\
+    s=-\r\n\
+    t=0 0\r\n\
+    a=group:BUNDLE 0 1\r\n\
+    a=extmap-allow-mixed\r\n\
+    a=msid-semantic: WMS\r\n\
+    m=audio 9 UDP/TLS/RTP/SAVPF 111 0\r\n\
+    c=IN IP4 0.0.0.0\r\n\
+    a=rtcp:9 IN IP4 0.0.0.0\r\n\
+    a=candidate:1 1 udp 2130706431 10.0.0.1 5000 typ host\r\n\
+    a=ice-ufrag:Vm3S\r\n\
+    a=ice-pwd:vHTc0ppOBBJp6ULUGYTJGyKQ\r\n\
+    a=ice-options:trickle\r\n\
+    a=fingerprint:sha-256 9B:5E:2C:BF:2F:24:1F:5C:86:5C:0C:44:34:1A:5F:8A:C5:8F:3B:8D:0F:42:D8:F6:53:40:6A:D8:38:CF:2F:4C\r\n\
+    a=setup:actpass\r\n\
+    a=mid:0\r\n\
+    a=extmap:1 urn:ietf:params:rtp-hdrext:ssrc-audio-level\r\n\
+    a=sendrecv\r\n\
+    a=msid:- 4a4c1d1e-0c4e-4a7b-8e0b-2fb2a1e2b7a4\r\n\
+    a=rtcp-mux\r\n\
+    a=rtpmap:111 opus/48000/2\r\n\
+    a=rtcp-fb:111 transport-cc\r\n\
+    a=fmtp:111 minptime=10;useinbandfec=1\r\n\
+    a=rtpmap:0 PCMU/8000\r\n\
+    a=ssrc:1001 cname:x6kHbNOBMvxoHzs3\r\n\
+    a=x-google-flag:conference\r\n\
+    m=video 9 UDP/TLS/RTP/SAVPF 96 97 102 103\r\n\
+    c=IN IP4 0.0.0.0\r\n\
+    a=rtcp:9 IN IP4 0.0.0.0\r\n\
+    a=ice-ufrag:Vm3S\r\n\
+    a=ice-pwd:vHTc0ppOBBJp6ULUGYTJGyKQ\r\n\
+    a=ice-options:trickle\r\n\
+    a=fingerprint:sha-256 9B:5E:2C:BF:2F:24:1F:5C:86:5C:0C:44:34:1A:5F:8A:C5:8F:3B:8D:0F:42:D8:F6:53:40:6A:D8:38:CF:2F:4C\r\n\
+    a=setup:actpass\r\n\
+    a=mid:1\r\n\
+    a=sendrecv\r\n\
+    a=msid:- 0d4d6e8f-6a5b-4f1c-9b1a-3c8f6e2d1a0b\r\n\
+    a=rtcp-mux\r\n\
+    a=rtcp-rsize\r\n\
+    a=rtpmap:96 VP8/90000\r\n\
+    a=rtcp-fb:96 nack\r\n\
+    a=rtcp-fb:96 nack pli\r\n\
+    a=rtpmap:97 rtx/90000\r\n\
+    a=fmtp:97 apt=96\r\n\
+    a=rtpmap:102 H264/90000\r\n\
+    a=rtcp-fb:102 nack\r\n\
+    a=fmtp:102 level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42e01f\r\n\
+    a=rtpmap:103 rtx/90000\r\n\
+    a=fmtp:103 apt=102\r\n\
+    a=ssrc-group:FID 2001 2002\r\n\
+    a=ssrc:2001 cname:x6kHbNOBMvxoHzs3\r\n\
+    a=ssrc:2002 cname:x6kHbNOBMvxoHzs3\r\n";
+
+#[test]
+pub fn sdp_model_round_trip() {
+    let desc: SessionDescription = BROWSER_OFFER.parse().unwrap();
+    assert_eq!(desc.to_string(), BROWSER_OFFER);
+
+    // Line feeds only are accepted, but always written as CRLF.
+    let lf = BROWSER_OFFER.replace("\r\n", "\n");
+    let desc2: SessionDescription = lf.parse().unwrap();
+    assert_eq!(desc2, desc);
+    assert_eq!(desc2.to_string(), BROWSER_OFFER);
+
+    assert!(SessionDescription::parse("o=- 1 2 IN IP4 0.0.0.0\r\n").is_err());
+    assert!(SessionDescription::parse("v=0\r\nbroken\r\n").is_err());
+}
+
+#[test]
+pub fn sdp_model_typed() {
+    let desc: SessionDescription = BROWSER_OFFER.parse().unwrap();
+
+    assert_eq!(desc.session.attribute("group"), Some("BUNDLE 0 1"));
+    assert_eq!(desc.session.attribute("extmap-allow-mixed"), Some(""));
+    assert_eq!(desc.media.len(), 2);
+
+    let audio = &desc.media[0];
+    assert_eq!(audio.kind(), "audio");
+    assert_eq!(audio.port(), Some(9));
+    assert_eq!(audio.proto(), "UDP/TLS/RTP/SAVPF");
+    assert_eq!(audio.formats(), ["111", "0"]);
+    assert_eq!(audio.mid(), Some(Mid::from("0")));
+    assert_eq!(audio.direction(), Some(Direction::SendRecv));
+    assert_eq!(audio.ice_creds().unwrap().ufrag, "Vm3S");
+    assert_eq!(audio.fingerprints()[0].hash_func, "sha-256");
+    assert_eq!(audio.attribute("x-google-flag"), Some("conference"));
+
+    let candidates = audio.candidates();
+    assert_eq!(candidates.len(), 1);
+    assert_eq!(
+        candidates[0].addr(),
+        (Ipv4Addr::new(10, 0, 0, 1), 5000).into()
+    );
+
+    // PCMU is not a codec str0m knows.
+    let codecs = audio.codecs();
+    assert_eq!(codecs.len(), 1);
+    assert_eq!(codecs[0].spec().codec, Codec::Opus);
+    assert_eq!(codecs[0].spec().format.use_inband_fec, Some(true));
+
+    let video = &desc.media[1];
+    let codecs = video.codecs();
+    assert_eq!(codecs.len(), 2);
+    assert_eq!(codecs[0].spec().codec, Codec::Vp8);
+    assert_eq!(codecs[0].resend(), Some(97.into()));
+    assert_eq!(codecs[1].spec().codec, Codec::H264);
+}
+
+#[test]
+pub fn sdp_model_edit() {
+    let mut desc: SessionDescription = BROWSER_OFFER.parse().unwrap();
+
+    // Strip VP8, which also strips its RTX.
+    let video = desc.media_by_mid(Mid::from("1")).unwrap();
+    assert!(video.remove_codec(96.into()));
+    assert!(!video.remove_codec(96.into()));
+    assert_eq!(video.formats(), ["102", "103"]);
+    assert_eq!(video.codecs().len(), 1);
+    let rtpmaps: Vec<_> = video.attributes("rtpmap").collect();
+    assert_eq!(rtpmaps, ["102 H264/90000", "103 rtx/90000"]);
+    assert_eq!(video.attributes("rtcp-fb").count(), 1);
+
+    video.set_bandwidth("AS", Some(500));
+    video.set_bandwidth("TIAS", Some(500_000));
+    video.set_bandwidth("AS", Some(1000));
+    video.set_direction(Direction::RecvOnly);
+    assert_eq!(video.bandwidth("AS"), Some(1000));
+    assert_eq!(video.direction(), Some(Direction::RecvOnly));
+
+    // Rewrite candidates.
+    let audio = &mut desc.media[0];
+    let relay = Candidate::relayed((Ipv4Addr::new(3, 3, 3, 3), 3478).into(), "udp").unwrap();
+    audio.set_candidates(std::slice::from_ref(&relay));
+    let candidates = audio.candidates();
+    assert_eq!(candidates.len(), 1);
+    assert_eq!(candidates[0].addr(), relay.addr());
+    assert_eq!(candidates[0].kind(), CandidateKind::Relayed);
+    audio.add_attribute("end-of-candidates", None);
+
+    desc.session.set_bandwidth("AS", Some(2000));
+
+    let s = desc.to_string();
+    assert!(s.contains("t=0 0\r\na=group"));
+    assert!(s.contains("s=-\r\nb=AS:2000\r\nt=0 0\r\n"));
+    assert!(s.contains(
+        "m=video 9 UDP/TLS/RTP/SAVPF 102 103\r\n\
+        c=IN IP4 0.0.0.0\r\n\
+        b=AS:1000\r\n\
+        b=TIAS:500000\r\n\
+        a=rtcp:9"
+    ));
+    assert!(s.contains("a=rtcp:9 IN IP4 0.0.0.0\r\na=candidate:"));
+    assert!(s.contains("typ relay\r\na=ice-ufrag"));
+    assert!(s.contains("a=recvonly"));
+
+    // The edited description still works as an offer.
+    init_log();
+    init_crypto_default();
+
+    let offer = SdpOffer::try_from(&desc).unwrap();
+    let mut rtc = Rtc::new();
+    let answer = rtc.sdp_api().accept_offer(offer).unwrap();
+
+    let answer = SessionDescription::from(&answer);
+    let video = &answer.media[1];
+    assert_eq!(video.mid(), Some(Mid::from("1")));
+    assert_eq!(video.direction(), Some(Direction::SendOnly));
+    assert!(video.codecs().iter().all(|c| c.spec().codec == Codec::H264));
+
+    let _: SdpAnswer = SdpAnswer::try_from(&answer).unwrap();
+}
+
+#[test]
+pub fn sdp_model_str0m_offer() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    let mut rtc = Rtc::new();
+    let host = Candidate::host((Ipv4Addr::new(1, 1, 1, 1), 1000).into(), "udp")?;
+    rtc.add_local_candidate(host.clone());
+
+    let mut change = rtc.sdp_api();
+    let mid = change.add_media(MediaKind::Audio, Direction::SendOnly, None, None, None);
+    let (offer, _pending) = change.apply().unwrap();
+
+    let desc = SessionDescription::from(&offer);
+    assert_eq!(desc.to_string(), offer.to_sdp_string());
+
+    let audio = &desc.media[0];
+    assert_eq!(audio.mid(), Some(mid));
+    assert_eq!(audio.direction(), Some(Direction::SendOnly));
+    assert_eq!(audio.candidates()[0].addr(), host.addr());
+    assert!(!audio.codecs().is_empty());
+
+    let offer2 = SdpOffer::try_from(&desc).unwrap();
+    assert_eq!(offer2.to_sdp_string(), offer.to_sdp_string());
+
+    Ok(())
+}
@@ -0,0 +1,109 @@
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+use str0m::config::SrtpProfile;
+use str0m::media::{Direction, MediaKind};
+use str0m::{Candidate, Event, RtcConfig, RtcError};
+use tracing::info_span;
+
+mod common;
+use common::{init_crypto_default, init_log, progress, TestRtc};
+
+fn config(profiles: &[SrtpProfile]) -> RtcConfig {
+    RtcConfig::new()
+        .set_stats_interval(Some(Duration::from_secs(1)))
+        .set_srtp_profiles(profiles)
+}
+
+/// Send audio from L to R for a few seconds and return the SRTP profile reported by the stats.
+fn negotiate_and_send(
+    l_config: RtcConfig,
+    r_config: RtcConfig,
+) -> Result<(usize, Option<SrtpProfile>), RtcError> {
+    let mut l = TestRtc::new_with_rtc(info_span!("L"), l_config.build());
+    let mut r = TestRtc::new_with_rtc(info_span!("R"), r_config.build());
+
+    let host1 = Candidate::host((Ipv4Addr::new(1, 1, 1, 1), 1000).into(), "udp")?;
+    let host2 = Candidate::host((Ipv4Addr::new(2, 2, 2, 2), 2000).into(), "udp")?;
+    l.add_local_candidate(host1);
+    r.add_local_candidate(host2);
+
+    let mut change = l.sdp_api();
+    let mid = change.add_media(MediaKind::Audio, Direction::SendRecv, None, None, None);
+    let (offer, pending) = change.apply().unwrap();
+
+    let answer = r.rtc.sdp_api().accept_offer(offer)?;
+    l.rtc.sdp_api().accept_answer(pending, answer)?;
+
+    loop {
+        if l.is_connected() || r.is_connected() {
+            break;
+        }
+        progress(&mut l, &mut r)?;
+    }
+
+    let pt = l.params_opus().pt();
+
+    while l.duration() < Duration::from_secs(5) {
+        let wallclock = l.start + l.duration();
+        let time = l.duration().into();
+        l.writer(mid)
+            .unwrap()
+            .write(pt, wallclock, time, vec![1_u8; 80])?;
+
+        progress(&mut l, &mut r)?;
+    }
+
+    let media_count = r
+        .events
+        .iter()
+        .filter(|(_, e)| matches!(e, Event::MediaData(_)))
+        .count();
+
+    let profile = r
+        .events
+        .iter()
+        .rev()
+        .find_map(|(_, e)| match e {
+            Event::PeerStats(s) => Some(s.srtp_profile),
+            _ => None,
+        })
+        .flatten();
+
+    Ok((media_count, profile))
+}
+
+#[test]
+pub fn srtp_aead_aes_256_gcm() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    let profiles = [SrtpProfile::AeadAes256Gcm, SrtpProfile::AeadAes128Gcm];
+    let (media_count, profile) = negotiate_and_send(config(&profiles), config(&profiles))?;
+
+    assert!(media_count > 100, "Not enough MediaData: {}", media_count);
+    assert_eq!(profile, Some(SrtpProfile::AeadAes256Gcm));
+
+    Ok(())
+}
+
+#[test]
+pub fn srtp_profile_falls_back_to_common() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    let l_profiles = [SrtpProfile::AeadAes256Gcm, SrtpProfile::Aes128CmSha1_80];
+    let r_profiles = [SrtpProfile::Aes128CmSha1_80];
+    let (media_count, profile) = negotiate_and_send(config(&l_profiles), config(&r_profiles))?;
+
+    assert!(media_count > 100, "Not enough MediaData: {}", media_count);
+    assert_eq!(profile, Some(SrtpProfile::Aes128CmSha1_80));
+
+    Ok(())
+}
+
+#[test]
+#[should_panic(expected = "negotiable via DTLS")]
+pub fn srtp_profiles_must_be_negotiable() {
+    RtcConfig::new().set_srtp_profiles(&[SrtpProfile::Aes256CmSha1_80]);
+}
@@ -1,9 +1,10 @@
 use std::net::Ipv4Addr;
 use std::time::Duration;
 
+use str0m::channel::ChannelState;
 use str0m::format::Codec;
 use str0m::media::{Direction, MediaKind};
-use str0m::stats::MediaEgressStats;
+use str0m::stats::{CandidatePairState, MediaEgressStats};
 use str0m::{Candidate, Event, RtcConfig, RtcError};
 use tracing::info_span;
 
@@ -51,8 +52,12 @@ pub fn stats() -> Result<(), RtcError> {
     let data_a = vec![1_u8; 80];
     let data_b = vec![2_u8; 80];
 
+    // Every write from L is one RTP packet.
+    let mut sent_l = 0_u64;
+
     loop {
         {
+            sent_l += 1;
             let wallclock = l.start + l.duration();
             let time = l.duration().into();
             l.writer(mid)
@@ -135,5 +140,146 @@ pub fn stats() -> Result<(), RtcError> {
         media_count_l
     );
 
+    let pair_stats_l: Vec<_> = l
+        .events
+        .iter()
+        .filter_map(|(_, e)| match e {
+            Event::CandidatePairStats(s) => Some(s),
+            _ => None,
+        })
+        .collect();
+
+    let last = pair_stats_l.last().expect("CandidatePairStats event");
+    assert!(last.selected);
+    assert_eq!(last.state, CandidatePairState::Succeeded);
+    assert!(last.bytes_sent > 0 && last.bytes_received > 0);
+    assert!(last.responses_received > 0);
+    assert!(last.current_rtt.is_some());
+
+    let peer_rtt = l.events.iter().rev().find_map(|(_, e)| match e {
+        Event::PeerStats(s) => s.selected_pair_rtt,
+        _ => None,
+    });
+    assert!(peer_rtt.is_some());
+
+    let pairs_r = r.candidate_pair_stats();
+    assert_eq!(pairs_r.len(), 1);
+    // RTP from L, plus DTLS and RTCP, but not STUN.
+    assert!(
+        pairs_r[0].packets_received >= sent_l,
+        "Not enough packets at R: {} < {}",
+        pairs_r[0].packets_received,
+        sent_l
+    );
+    assert!(pairs_r[0].requests_received > 0);
+    assert_eq!(pairs_r[0].responses_sent, pairs_r[0].requests_received);
+
+    Ok(())
+}
+
+#[test]
+pub fn stats_data_channel() -> Result<(), RtcError> {
+    init_log();
+    init_crypto_default();
+
+    let l_config = RtcConfig::new().set_stats_interval(Some(Duration::from_secs(1)));
+    let r_config = RtcConfig::new().set_stats_interval(Some(Duration::from_secs(1)));
+
+    let mut l = TestRtc::new_with_rtc(info_span!("L"), l_config.build());
+    let mut r = TestRtc::new_with_rtc(info_span!("R"), r_config.build());
+
+    let host1 = Candidate::host((Ipv4Addr::new(1, 1, 1, 1), 1000).into(), "udp")?;
+    let host2 = Candidate::host((Ipv4Addr::new(2, 2, 2, 2), 2000).into(), "udp")?;
+    l.add_local_candidate(host1);
+    r.add_local_candidate(host2);
+
+    let mut change = l.sdp_api();
+    let cid = change.add_channel("chat".into());
+    let (offer, pending) = change.apply().unwrap();
+
+    let answer = r.rtc.sdp_api().accept_offer(offer)?;
+    l.rtc.sdp_api().accept_answer(pending, answer)?;
+
+    let mut sent = 0;
+
+    loop {
+        if sent < 10 {
+            if let Some(mut chan) = l.channel(cid) {
+                chan.write(false, b"Hello world!")?;
+                sent += 1;
+            }
+        }
+
+        progress(&mut l, &mut r)?;
+
+        if l.duration() > Duration::from_secs(5) {
+            break;
+        }
+    }
+
+    assert_eq!(sent, 10);
+
+    let chan_l = l
+        .events
+        .iter()
+        .rev()
+        .find_map(|(_, e)| match e {
+            Event::ChannelStats(s) => Some(s),
+            _ => None,
+        })
+        .expect("ChannelStats event at L");
+
+    assert_eq!(chan_l.id, cid);
+    assert_eq!(chan_l.label, "chat");
+    assert_eq!(chan_l.state, ChannelState::Open);
+    assert_eq!(chan_l.messages_sent, 10);
+    assert_eq!(chan_l.bytes_sent, 120);
+    assert_eq!(chan_l.messages_received, 0);
+    assert_eq!(chan_l.buffered_amount, 0);
+
+    let chan_r = r
+        .events
+        .iter()
+        .rev()
+        .find_map(|(_, e)| match e {
+            Event::ChannelStats(s) => Some(s),
+            _ => None,
+        })
+        .expect("ChannelStats event at R");
+
+    assert_eq!(chan_r.label, "chat");
+    assert_eq!(chan_r.messages_received, 10);
+    assert_eq!(chan_r.bytes_received, 120);
+
+    let sctp_r = r
+        .events
+        .iter()
+        .rev()
+        .find_map(|(_, e)| match e {
+            Event::SctpStats(s) => Some(s),
+            _ => None,
+        })
+        .expect("SctpStats event at R");
+
+    // DCEP open plus the messages.
+    assert!(sctp_r.data_chunks_received >= 11);
+    assert!(sctp_r.rto > Duration::ZERO);
+
+    let sctp_l = l
+        .events
+        .iter()
+        .rev()
+        .find_map(|(_, e)| match e {
+            Event::SctpStats(s) => Some(s),
+            _ => None,
+        })
+        .expect("SctpStats event at L");
+
+    // L sent the messages, so it has measured the RTT from the SACKs.
+    assert!(sctp_l.sacks_received > 0);
+    assert!(sctp_l.srtt.is_some());
+    assert!(sctp_l.cwnd > 0);
+    assert!(sctp_l.rwnd > 0);
+
     Ok(())
 }
@@ -161,11 +161,6 @@ pub fn srtp_aead_aes_128_gcm_encrypt(
         ));
     }
 
-    assert!(
-        additional_auth_data.len() >= 12,
-        "Associated data length MUST be at least 12 octets"
-    );
-
     let auth_cipher_mode_info = BCRYPT_AUTHENTICATED_CIPHER_MODE_INFO {
         pbAuthData: additional_auth_data.as_ptr() as *mut u8,
         cbAuthData: additional_auth_data.len() as u32,